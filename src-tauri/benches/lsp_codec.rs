@@ -0,0 +1,66 @@
+//! Decoding of large `textDocument/completion` responses, fed to the codec
+//! in reads of the size a pipe delivers. Run with `cargo bench --bench lsp_codec`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use tokio_util::bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+// Its unit tests are compiled in, but not run, when checking this bench in test mode
+#[path = "../src/lsp/codec.rs"]
+#[allow(dead_code)]
+mod codec;
+
+use codec::LspCodec;
+
+// Typical pipe buffer size
+const READ_SIZE: usize = 64 * 1024;
+const ROUNDS: u32 = 20;
+
+fn completion_response(items: usize) -> Vec<u8> {
+    let items: Vec<String> = (0..items)
+        .map(|i| {
+            format!(
+                r#"{{"label":"item_{i}","kind":3,"detail":"fn item_{i}(value: usize) -> Result<String, Error>","documentation":{{"kind":"markdown","value":"Documentation for `item_{i}`"}},"sortText":"{i:08}","insertText":"item_{i}($0)","insertTextFormat":2}}"#
+            )
+        })
+        .collect();
+    let body = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"result":{{"isIncomplete":false,"items":[{}]}}}}"#,
+        items.join(",")
+    );
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body).into_bytes()
+}
+
+fn bench(items: usize) {
+    let input = completion_response(items);
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut codec = LspCodec::default();
+        let mut src = BytesMut::new();
+        let mut decoded = 0;
+        for chunk in input.chunks(READ_SIZE) {
+            src.extend_from_slice(chunk);
+            while let Some(message) = codec.decode(&mut src).unwrap() {
+                decoded += black_box(message).len();
+            }
+        }
+        assert!(decoded > 0);
+    }
+    let per_round = start.elapsed() / ROUNDS;
+    let mb = input.len() as f64 / (1024.0 * 1024.0);
+    println!(
+        "completion, {:>6} items, {:>7.2} MiB: {:>10.3?} per message, {:>8.1} MiB/s",
+        items,
+        mb,
+        per_round,
+        mb / per_round.as_secs_f64()
+    );
+}
+
+fn main() {
+    for items in [1_000, 10_000, 50_000] {
+        bench(items);
+    }
+}
@@ -0,0 +1,327 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use tauri::State;
+
+use crate::encoding::{self, DecodedText};
+use crate::file_info::is_probably_binary;
+use crate::file_ops::unique_path;
+use crate::fs_guard::FsGuardState;
+
+// Entries larger than this are not opened in the editor
+const MAX_ENTRY_READ_LEN: u64 = 16 * 1024 * 1024;
+// Extraction stops beyond these, so a small archive can't fill the disk
+const MAX_EXTRACT_ENTRIES: usize = 100_000;
+const MAX_EXTRACT_LEN: u64 = 4 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveEntry {
+    /// Path inside the archive, always '/'-separated and without a trailing slash
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub is_directory: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExtractReport {
+    pub destination: String,
+    pub entries: usize,
+}
+
+fn archive_kind(path: &Path) -> Result<ArchiveKind, String> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Ok(ArchiveKind::TarGz)
+    } else if name.ends_with(".tar") {
+        Ok(ArchiveKind::Tar)
+    } else if name.ends_with(".zip") || name.ends_with(".jar") {
+        Ok(ArchiveKind::Zip)
+    } else {
+        Err(format!("Unsupported archive format: {}", path.display()))
+    }
+}
+
+/// Archive name without its archive extension(s), e.g. "src.tar.gz" -> "src"
+fn archive_stem(path: &Path) -> String {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let lower = name.to_lowercase();
+    for ext in [".tar.gz", ".tgz", ".tar", ".zip", ".jar"] {
+        if lower.ends_with(ext) {
+            return name[..name.len() - ext.len()].to_string();
+        }
+    }
+    name
+}
+
+fn normalize_entry_path(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches("./").trim_end_matches('/').to_string()
+}
+
+fn entry(path: &str, size: u64, is_directory: bool) -> ArchiveEntry {
+    let path = normalize_entry_path(path);
+    let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+    ArchiveEntry {
+        path,
+        name,
+        size,
+        is_directory,
+    }
+}
+
+fn open_zip(path: &Path) -> Result<zip::ZipArchive<BufReader<File>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    zip::ZipArchive::new(BufReader::new(file)).map_err(|e| format!("Failed to read zip archive: {}", e))
+}
+
+fn open_tar(path: &Path, kind: ArchiveKind) -> Result<tar::Archive<Box<dyn Read>>, String> {
+    let file = BufReader::new(File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?);
+    let reader: Box<dyn Read> = match kind {
+        ArchiveKind::TarGz => Box::new(GzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+pub fn list_entries(path: &Path) -> Result<Vec<ArchiveEntry>, String> {
+    let kind = archive_kind(path)?;
+    let mut entries = Vec::new();
+
+    if kind == ArchiveKind::Zip {
+        let mut archive = open_zip(path)?;
+        for i in 0..archive.len() {
+            let file = archive.by_index(i).map_err(|e| format!("Failed to read zip entry: {}", e))?;
+            entries.push(entry(file.name(), file.size(), file.is_dir()));
+        }
+    } else {
+        let mut archive = open_tar(path, kind)?;
+        for file in archive.entries().map_err(|e| format!("Failed to read tar archive: {}", e))? {
+            let file = file.map_err(|e| format!("Failed to read tar entry: {}", e))?;
+            let entry_path = file
+                .path()
+                .map_err(|e| format!("Invalid tar entry path: {}", e))?
+                .to_string_lossy()
+                .to_string();
+            entries.push(entry(&entry_path, file.size(), file.header().entry_type().is_dir()));
+        }
+    }
+
+    entries.retain(|e| !e.path.is_empty());
+    Ok(entries)
+}
+
+fn read_limited<R: Read>(reader: R, size: u64) -> Result<Vec<u8>, String> {
+    if size > MAX_ENTRY_READ_LEN {
+        return Err("Archive entry is too large to open".to_string());
+    }
+    let mut bytes = Vec::with_capacity(size as usize);
+    reader
+        .take(MAX_ENTRY_READ_LEN)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+    Ok(bytes)
+}
+
+pub fn read_entry(path: &Path, entry_path: &str) -> Result<Vec<u8>, String> {
+    let kind = archive_kind(path)?;
+    let wanted = normalize_entry_path(entry_path);
+
+    if kind == ArchiveKind::Zip {
+        let mut archive = open_zip(path)?;
+        let index = (0..archive.len())
+            .find(|&i| {
+                archive
+                    .name_for_index(i)
+                    .is_some_and(|name| normalize_entry_path(name) == wanted)
+            })
+            .ok_or_else(|| format!("No entry named {} in archive", entry_path))?;
+        let file = archive.by_index(index).map_err(|e| format!("Failed to read zip entry: {}", e))?;
+        let size = file.size();
+        return read_limited(file, size);
+    }
+
+    let mut archive = open_tar(path, kind)?;
+    for file in archive.entries().map_err(|e| format!("Failed to read tar archive: {}", e))? {
+        let file = file.map_err(|e| format!("Failed to read tar entry: {}", e))?;
+        let matches = file
+            .path()
+            .map(|p| normalize_entry_path(&p.to_string_lossy()) == wanted)
+            .unwrap_or(false);
+        if matches {
+            let size = file.size();
+            return read_limited(file, size);
+        }
+    }
+    Err(format!("No entry named {} in archive", entry_path))
+}
+
+/// Running totals of an extraction, checked against the limits
+#[derive(Default)]
+struct ExtractBudget {
+    entries: usize,
+    bytes: u64,
+}
+
+impl ExtractBudget {
+    fn add_entry(&mut self) -> Result<(), String> {
+        self.entries += 1;
+        if self.entries > MAX_EXTRACT_ENTRIES {
+            return Err(format!("Archive has more than {} entries", MAX_EXTRACT_ENTRIES));
+        }
+        Ok(())
+    }
+
+    fn add_bytes(&mut self, len: u64) -> Result<(), String> {
+        self.bytes = self.bytes.saturating_add(len);
+        if self.bytes > MAX_EXTRACT_LEN {
+            return Err(format!(
+                "Archive expands to more than {} MB",
+                MAX_EXTRACT_LEN / (1024 * 1024)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Bytes that may still be written
+    fn remaining(&self) -> u64 {
+        MAX_EXTRACT_LEN.saturating_sub(self.bytes)
+    }
+}
+
+/// Like `ZipArchive::extract`, but counts what is actually decompressed:
+/// the sizes in a zip's headers can't be trusted
+fn extract_zip(path: &Path, destination: &Path, budget: &mut ExtractBudget) -> Result<usize, String> {
+    let mut archive = open_zip(path)?;
+    let mut count = 0;
+    for i in 0..archive.len() {
+        budget.add_entry()?;
+        let mut file = archive.by_index(i).map_err(|e| format!("Failed to read zip entry: {}", e))?;
+        // enclosed_name refuses paths outside of `destination`
+        let Some(relative) = file.enclosed_name() else {
+            tracing::warn!("Skipped unsafe archive entry: {}", file.name());
+            continue;
+        };
+        let target = destination.join(relative);
+        if file.is_dir() {
+            fs::create_dir_all(&target).map_err(|e| format!("Failed to extract archive: {}", e))?;
+            count += 1;
+            continue;
+        }
+        if file.is_symlink() {
+            tracing::warn!("Skipped symlink in archive: {}", file.name());
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to extract archive: {}", e))?;
+        }
+        let mut out = File::create(&target).map_err(|e| format!("Failed to extract archive: {}", e))?;
+        // One byte over the budget is enough to know it is exceeded
+        let written = io::copy(&mut (&mut file).take(budget.remaining() + 1), &mut out)
+            .map_err(|e| format!("Failed to extract archive: {}", e))?;
+        budget.add_bytes(written)?;
+        #[cfg(unix)]
+        if let Some(mode) = file.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&target, fs::Permissions::from_mode(mode))
+                .map_err(|e| format!("Failed to extract archive: {}", e))?;
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Extracts into `destination`; entries escaping it (`../`, absolute paths)
+/// are rejected, and extraction fails beyond `MAX_EXTRACT_ENTRIES` entries
+/// or `MAX_EXTRACT_LEN` bytes
+pub fn extract(path: &Path, destination: &Path) -> Result<usize, String> {
+    let kind = archive_kind(path)?;
+    fs::create_dir_all(destination).map_err(|e| format!("Failed to create destination: {}", e))?;
+    let mut budget = ExtractBudget::default();
+
+    if kind == ArchiveKind::Zip {
+        return extract_zip(path, destination, &mut budget);
+    }
+
+    let mut archive = open_tar(path, kind)?;
+    let mut count = 0;
+    for file in archive.entries().map_err(|e| format!("Failed to read tar archive: {}", e))? {
+        let mut file = file.map_err(|e| format!("Failed to read tar entry: {}", e))?;
+        // A tar entry is exactly as long as its header says
+        budget.add_entry()?;
+        budget.add_bytes(file.size())?;
+        // unpack_in refuses paths outside of `destination`
+        match file.unpack_in(destination) {
+            Ok(true) => count += 1,
+            Ok(false) => tracing::warn!("Skipped unsafe archive entry: {:?}", file.path().ok()),
+            Err(e) => return Err(format!("Failed to extract archive: {}", e)),
+        }
+    }
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn list_archive_entries(guard: State<'_, FsGuardState>, path: String) -> Result<Vec<ArchiveEntry>, String> {
+    let path = guard.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || list_entries(&path))
+        .await
+        .map_err(|e| format!("Archive task failed: {}", e))?
+}
+
+/// Decodes a single text entry so it can be opened read-only in the editor
+#[tauri::command]
+pub async fn read_archive_file(
+    guard: State<'_, FsGuardState>,
+    archive_path: String,
+    entry_path: String,
+) -> Result<DecodedText, String> {
+    let archive_path = guard.check(&archive_path)?;
+    let bytes = tauri::async_runtime::spawn_blocking(move || read_entry(&archive_path, &entry_path))
+        .await
+        .map_err(|e| format!("Archive task failed: {}", e))??;
+    if is_probably_binary(&bytes) {
+        return Err("Archive entry is a binary file".to_string());
+    }
+    Ok(encoding::decode(&bytes, None))
+}
+
+/// Extracts the archive into `destination`, or into a new folder next to it named after the archive
+#[tauri::command]
+pub async fn extract_archive(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    destination: Option<String>,
+) -> Result<ExtractReport, String> {
+    let archive = guard.check(&path)?;
+    let destination = match destination {
+        Some(dest) => PathBuf::from(dest),
+        None => {
+            let parent = archive.parent().unwrap_or_else(|| Path::new("."));
+            unique_path(&parent.join(archive_stem(&archive)))
+        }
+    };
+    // The default folder sits next to the archive, which may itself be a
+    // single permitted file outside the workspace
+    let destination = guard.check_entry(&destination.to_string_lossy())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let entries = extract(&archive, &destination)?;
+        Ok(ExtractReport {
+            destination: destination.to_string_lossy().to_string(),
+            entries,
+        })
+    })
+    .await
+    .map_err(|e| format!("Archive task failed: {}", e))?
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::atomic_write;
+use crate::file_info::sha256_hex;
+use crate::fs_guard::FsGuardState;
+
+const ASSETS_DIR: &str = "assets";
+
+#[derive(Debug, Serialize)]
+pub struct SavedAsset {
+    /// '/'-separated path relative to the document, ready to put in `![](...)`
+    pub relative_path: String,
+    pub path: String,
+    /// True if an identical image was already in the assets folder
+    pub reused: bool,
+}
+
+/// Image extension from the file signature
+fn sniff_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else if bytes.starts_with(b"BM") {
+        Some("bmp")
+    } else if bytes.starts_with(&[0x00, 0x00, 0x01, 0x00]) {
+        Some("ico")
+    } else {
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_lowercase();
+        (head.contains("<svg")).then_some("svg")
+    }
+}
+
+/// File stem safe to use in a markdown link: no spaces or path separators
+fn sanitize_stem(name: &str) -> String {
+    let stem = Path::new(name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let cleaned: String = stem
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '-' })
+        .collect();
+    let cleaned = cleaned.trim_matches(['-', '.']).to_string();
+    if cleaned.is_empty() {
+        "image".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// An existing file in `dir` with exactly these contents
+fn find_duplicate(dir: &Path, bytes: &[u8]) -> Option<PathBuf> {
+    let hash = sha256_hex(bytes);
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.metadata().is_ok_and(|m| m.is_file() && m.len() == bytes.len() as u64))
+        .map(|entry| entry.path())
+        .find(|path| fs::read(path).is_ok_and(|existing| sha256_hex(&existing) == hash))
+}
+
+/// First of `stem.ext`, `stem-1.ext`, ... that doesn't exist in `dir`
+fn free_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let first = dir.join(format!("{}.{}", stem, ext));
+    if fs::symlink_metadata(&first).is_err() {
+        return first;
+    }
+    (1..)
+        .map(|n| dir.join(format!("{}-{}.{}", stem, n, ext)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded range always yields a free name")
+}
+
+pub fn save_image(document: &Path, bytes: &[u8], preferred_name: Option<&str>) -> Result<SavedAsset, String> {
+    let ext = sniff_extension(bytes).ok_or_else(|| "Data is not a supported image format".to_string())?;
+    let doc_dir = document
+        .parent()
+        .ok_or_else(|| "Document has no parent directory".to_string())?;
+    let assets_dir = doc_dir.join(ASSETS_DIR);
+    fs::create_dir_all(&assets_dir).map_err(|e| format!("Failed to create assets folder: {}", e))?;
+
+    let (path, reused) = match find_duplicate(&assets_dir, bytes) {
+        Some(existing) => (existing, true),
+        None => {
+            let stem = match preferred_name {
+                Some(name) if !name.trim().is_empty() => sanitize_stem(name),
+                // Pasted screenshots have no name; the hash prefix keeps them apart
+                _ => format!("image-{}", &sha256_hex(bytes)[..8]),
+            };
+            let path = free_path(&assets_dir, &stem, ext);
+            atomic_write::write_atomic(&path, bytes).map_err(|e| format!("Failed to write image: {}", e))?;
+            (path, false)
+        }
+    };
+
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    Ok(SavedAsset {
+        relative_path: format!("{}/{}", ASSETS_DIR, file_name),
+        path: path.to_string_lossy().to_string(),
+        reused,
+    })
+}
+
+/// Stores pasted or dropped image data in `assets/` next to the document,
+/// reusing an identical file if one is already there
+#[tauri::command]
+pub async fn save_pasted_image(
+    guard: State<'_, FsGuardState>,
+    document_path: String,
+    bytes: Vec<u8>,
+    preferred_name: Option<String>,
+) -> Result<SavedAsset, String> {
+    // Assets go in a folder next to the document
+    let document_path = guard.check_entry(&document_path)?;
+    tauri::async_runtime::spawn_blocking(move || save_image(&document_path, &bytes, preferred_name.as_deref()))
+    .await
+    .map_err(|e| format!("Image task failed: {}", e))?
+}
@@ -0,0 +1,69 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+/// Writes `contents` to `path` without ever leaving a half-written file behind.
+///
+/// The data goes to a temp file in the same directory (so the final rename
+/// stays on one filesystem), is fsynced, and is then renamed over the
+/// original. Permissions of an existing file are carried over, and symlinks
+/// are followed so the link itself is preserved.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let target = resolve_target(path);
+    let dir = match target.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?
+        .to_string_lossy()
+        .to_string();
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name, Uuid::new_v4().simple()));
+
+    let result = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+
+        if let Ok(metadata) = fs::metadata(&target) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+
+        fs::rename(&temp_path, &target)?;
+        sync_dir(&dir);
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Follows a symlink at `path` so the rename replaces the link target, not the link
+fn resolve_target(path: &Path) -> PathBuf {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Best-effort fsync of the directory so the rename itself is durable
+#[cfg(unix)]
+fn sync_dir(dir: &Path) {
+    if let Ok(d) = fs::File::open(dir) {
+        let _ = d.sync_all();
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) {}
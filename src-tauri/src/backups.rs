@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::atomic_write;
+use crate::file_info::{now_ms, sha256_hex, system_time_ms};
+
+// Snapshots of dirty buffers live under app_data_dir/backups, one file per document
+const BACKUP_DIR: &str = "backups";
+const MAX_BACKUP_AGE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+const MAX_BACKUPS: usize = 200;
+const PREVIEW_CHARS: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupFile {
+    path: String,
+    content: String,
+    timestamp: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoverableBuffer {
+    pub id: String,
+    pub path: String,
+    /// When the snapshot was taken (ms since epoch)
+    pub timestamp: u64,
+    /// Modification time of the file on disk, None if it no longer exists
+    pub disk_modified_ms: Option<u64>,
+    pub size: usize,
+    pub preview: String,
+}
+
+fn backup_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|d| d.join(BACKUP_DIR))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+/// Backup id for a document path; untitled buffers pass their own key as `path`
+fn backup_id(path: &str) -> String {
+    sha256_hex(path.as_bytes())[..32].to_string()
+}
+
+fn backup_file(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    // Ids come back from the frontend, so make sure they can't escape the folder
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid backup id: {}", id));
+    }
+    Ok(dir.join(format!("{}.json", id)))
+}
+
+fn read_backup(file: &Path) -> Option<BackupFile> {
+    let text = fs::read_to_string(file).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Removes snapshots that are too old, or that already match the file on
+/// disk, and then the oldest ones beyond `MAX_BACKUPS`
+fn prune(dir: &Path) -> Vec<(PathBuf, BackupFile)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let cutoff = now_ms().saturating_sub(MAX_BACKUP_AGE.as_millis() as u64);
+
+    let mut kept = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let file = entry.path();
+        if file.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let stale = match read_backup(&file) {
+            None => true,
+            Some(backup) if backup.timestamp < cutoff => true,
+            Some(backup) if fs::read_to_string(&backup.path).is_ok_and(|c| c == backup.content) => true,
+            Some(backup) => {
+                kept.push((file.clone(), backup));
+                false
+            }
+        };
+        if stale {
+            let _ = fs::remove_file(&file);
+        }
+    }
+
+    kept.sort_by_key(|(_, backup)| std::cmp::Reverse(backup.timestamp));
+    for (file, _) in kept.drain(MAX_BACKUPS.min(kept.len())..) {
+        let _ = fs::remove_file(file);
+    }
+    kept
+}
+
+/// Snapshots the contents of a dirty editor so it survives a crash. Called
+/// periodically by the frontend; each call replaces the previous snapshot.
+#[tauri::command]
+pub async fn store_unsaved_buffer(app_handle: AppHandle, path: String, content: String) -> Result<(), String> {
+    let dir = backup_dir(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+        let file = backup_file(&dir, &backup_id(&path))?;
+        let backup = BackupFile {
+            path,
+            content,
+            timestamp: now_ms(),
+        };
+        let json = serde_json::to_vec(&backup).map_err(|e| format!("Failed to serialize backup: {}", e))?;
+        atomic_write::write_atomic(&file, &json).map_err(|e| format!("Failed to write backup: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Backup task failed: {}", e))?
+}
+
+/// Drops the snapshot of a document once it has been saved or its changes discarded
+#[tauri::command]
+pub async fn discard_unsaved_buffer(app_handle: AppHandle, path: String) -> Result<(), String> {
+    let file = backup_file(&backup_dir(&app_handle)?, &backup_id(&path))?;
+    match fs::remove_file(&file) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove backup: {}", e)),
+    }
+}
+
+/// Snapshots left behind by a previous session, newest first. Prunes old
+/// and already-saved snapshots as a side effect.
+#[tauri::command]
+pub async fn list_recoverable_buffers(app_handle: AppHandle) -> Result<Vec<RecoverableBuffer>, String> {
+    let dir = backup_dir(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        prune(&dir)
+            .into_iter()
+            .map(|(file, backup)| RecoverableBuffer {
+                id: file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+                disk_modified_ms: fs::metadata(&backup.path).ok().and_then(|m| system_time_ms(m.modified())),
+                size: backup.content.len(),
+                preview: backup.content.chars().take(PREVIEW_CHARS).collect(),
+                path: backup.path,
+                timestamp: backup.timestamp,
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Backup task failed: {}", e))
+}
+
+#[tauri::command]
+pub async fn read_recoverable_buffer(app_handle: AppHandle, id: String) -> Result<String, String> {
+    let file = backup_file(&backup_dir(&app_handle)?, &id)?;
+    read_backup(&file)
+        .map(|backup| backup.content)
+        .ok_or_else(|| format!("No backup with id: {}", id))
+}
@@ -0,0 +1,251 @@
+#[cfg(target_os = "linux")]
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use tauri::{AppHandle, State};
+use url::Url;
+
+use crate::drop_import::{import_paths, ImportResult};
+use crate::file_ops::{ConflictStrategy, ProgressReporter};
+use crate::fs_guard::FsGuardState;
+
+/// Runs `command` and returns its stdout
+fn run(mut command: Command) -> Result<String, String> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} exited with {}: {}", program, output.status, stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Reads `file://` URIs (text/uri-list, GNOME's copied-files format) or
+/// plain absolute paths, one per line
+fn parse_file_list(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match Url::parse(line) {
+            Ok(url) if url.scheme() == "file" => url.to_file_path().ok(),
+            Ok(_) => None,
+            Err(_) => Some(PathBuf::from(line)).filter(|p| p.is_absolute()),
+        })
+        .filter(|path| path.exists())
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+// File managers on both X11 and Wayland read copied files as text/uri-list
+#[cfg(target_os = "linux")]
+fn write_files(paths: &[PathBuf]) -> Result<(), String> {
+    let uris: Vec<String> = paths
+        .iter()
+        .filter_map(|p| Url::from_file_path(p).ok())
+        .map(String::from)
+        .collect();
+    let list = uris.join("\r\n") + "\r\n";
+    let mut command = if wayland() {
+        let mut command = Command::new("wl-copy");
+        command.args(["--type", "text/uri-list"]);
+        command
+    } else {
+        let mut command = Command::new("xclip");
+        command.args(["-in", "-selection", "clipboard", "-target", "text/uri-list"]);
+        command
+    };
+    // The helpers fork to keep serving the selection, so their output must not
+    // be piped or waiting would last until something else is copied
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {:?}: {}", command.get_program(), e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(list.as_bytes())
+            .map_err(|e| format!("Failed to write to clipboard: {}", e))?;
+    }
+    let status = child.wait().map_err(|e| format!("Failed to write to clipboard: {}", e))?;
+    if !status.success() {
+        return Err(format!("{:?} exited with {}", command.get_program(), status));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn read_files() -> Result<Vec<PathBuf>, String> {
+    let read = |target: &str| {
+        let command = if wayland() {
+            let mut command = Command::new("wl-paste");
+            command.args(["--no-newline", "--type", target]);
+            command
+        } else {
+            let mut command = Command::new("xclip");
+            command.args(["-out", "-selection", "clipboard", "-target", target]);
+            command
+        };
+        run(command)
+    };
+    if let Ok(text) = read("text/uri-list") {
+        return Ok(parse_file_list(&text));
+    }
+    // GNOME's own format starts with a "copy" or "cut" line
+    if let Ok(text) = read("x-special/gnome-copied-files") {
+        return Ok(parse_file_list(&text));
+    }
+    // Plain text holding paths, e.g. copied from a terminal
+    Ok(read("text/plain").map(|text| parse_file_list(&text)).unwrap_or_default())
+}
+
+#[cfg(target_os = "macos")]
+const MAC_COPY_SCRIPT: &str = r#"
+ObjC.import('AppKit');
+function run(argv) {
+    const pasteboard = $.NSPasteboard.generalPasteboard;
+    pasteboard.clearContents;
+    return pasteboard.writeObjects($(argv.map(path => $.NSURL.fileURLWithPath(path))));
+}
+"#;
+
+#[cfg(target_os = "macos")]
+const MAC_PASTE_SCRIPT: &str = r#"
+ObjC.import('AppKit');
+function run() {
+    const urls = $.NSPasteboard.generalPasteboard.readObjectsForClassesOptions(
+        $([$.NSURL]),
+        $({ NSPasteboardURLReadingFileURLsOnlyKey: true })
+    );
+    const paths = [];
+    for (let i = 0; urls && !urls.isNil() && i < urls.count; i++) {
+        paths.push(urls.objectAtIndex(i).path.js);
+    }
+    return paths.join('\n');
+}
+"#;
+
+// NSPasteboard file URLs are what Finder copies and pastes
+#[cfg(target_os = "macos")]
+fn write_files(paths: &[PathBuf]) -> Result<(), String> {
+    let mut command = Command::new("osascript");
+    command.args(["-l", "JavaScript", "-e", MAC_COPY_SCRIPT]).args(paths);
+    run(command).map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+fn read_files() -> Result<Vec<PathBuf>, String> {
+    let mut command = Command::new("osascript");
+    command.args(["-l", "JavaScript", "-e", MAC_PASTE_SCRIPT]);
+    run(command).map(|text| parse_file_list(&text))
+}
+
+// Explorer uses the CF_HDROP file drop list, which PowerShell exposes directly.
+// Paths travel through an environment variable so they are never parsed as script.
+#[cfg(windows)]
+fn powershell(script: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-STA", "-Command", script])
+        .creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(windows)]
+fn write_files(paths: &[PathBuf]) -> Result<(), String> {
+    let list: Vec<String> = paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+    let mut command = powershell("Set-Clipboard -LiteralPath ($env:TMD_CLIPBOARD_PATHS -split \"`n\")");
+    command.env("TMD_CLIPBOARD_PATHS", list.join("\n"));
+    run(command).map(|_| ())
+}
+
+#[cfg(windows)]
+fn read_files() -> Result<Vec<PathBuf>, String> {
+    let command = powershell(
+        "[Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+         Get-Clipboard -Format FileDropList | ForEach-Object { $_.FullName }",
+    );
+    run(command).map(|text| parse_file_list(&text))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn write_files(_paths: &[PathBuf]) -> Result<(), String> {
+    Err("Copying files to the clipboard is not supported on this platform".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn read_files() -> Result<Vec<PathBuf>, String> {
+    Ok(Vec::new())
+}
+
+/// Puts files on the OS clipboard the way the platform's file manager does,
+/// so they can be pasted into Finder, Explorer or Nautilus
+#[tauri::command]
+pub async fn clipboard_copy_files(guard: State<'_, FsGuardState>, paths: Vec<String>) -> Result<(), String> {
+    let paths = paths
+        .iter()
+        .map(|path| guard.check(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(missing) = paths.iter().find(|p| !p.exists()) {
+        return Err(format!("Path does not exist: {}", missing.display()));
+    }
+    if paths.is_empty() {
+        return Err("No files to copy".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || write_files(&paths))
+        .await
+        .map_err(|e| format!("Clipboard task failed: {}", e))?
+}
+
+/// Files currently on the OS clipboard; empty if it holds none
+#[tauri::command]
+pub async fn clipboard_read_files() -> Result<Vec<String>, String> {
+    let paths = tauri::async_runtime::spawn_blocking(read_files)
+        .await
+        .map_err(|e| format!("Clipboard task failed: {}", e))??;
+    Ok(paths.iter().map(|p| p.to_string_lossy().to_string()).collect())
+}
+
+/// Copies the files on the OS clipboard into `target_dir`. Name clashes get a
+/// `name (1).ext` copy unless another strategy is given.
+#[tauri::command]
+pub async fn clipboard_paste_files(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    target_dir: String,
+    strategy: Option<ConflictStrategy>,
+    operation_id: Option<String>,
+) -> Result<Vec<ImportResult>, String> {
+    // The files themselves were put on the clipboard by the user, outside the app
+    let target = guard.check(&target_dir)?;
+    if !target.is_dir() {
+        return Err("Target is not a directory".to_string());
+    }
+    let strategy = strategy.unwrap_or(ConflictStrategy::Rename);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let paths = read_files()?;
+        if paths.is_empty() {
+            return Err("The clipboard holds no files".to_string());
+        }
+        let mut reporter = ProgressReporter::new(
+            operation_id.as_ref().map(|_| app_handle),
+            operation_id.unwrap_or_default(),
+        );
+        let results = import_paths(paths, &target, strategy, &mut reporter);
+        reporter.finish();
+        Ok(results)
+    })
+    .await
+    .map_err(|e| format!("Paste task failed: {}", e))?
+}
@@ -0,0 +1,442 @@
+//! Which programs `execute_command` and `spawn_command` may run. Programs on
+//! the deny list are refused, those on the allow list run, and anything else
+//! needs the user's approval through a `command-confirmation` event, as do
+//! shell lines that chain or redirect commands. Every
+//! decision goes to an audit log kept in memory and in
+//! `app_data_dir/command-audit.jsonl`.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::commands::split_command_line;
+use crate::error::AppError;
+use crate::file_info::now_ms;
+
+const POLICY_STORE: &str = "command-policy.json";
+const POLICY_KEY: &str = "policy";
+const AUDIT_FILE: &str = "command-audit.jsonl";
+// Entries kept in memory for `get_command_audit_log`
+const AUDIT_MEMORY: usize = 1000;
+// An unanswered confirmation counts as a refusal
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Program names (`git`) or full paths (`/usr/bin/git`). Allow entries
+/// match only that exact program, so `git` allows the `git` found on PATH but
+/// not `./git`; a deny entry without a path refuses the program wherever it is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandPolicy {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Ask before running programs on neither list; when false they just run
+    #[serde(default = "default_confirm")]
+    pub confirm_unlisted: bool,
+}
+
+fn default_confirm() -> bool {
+    true
+}
+
+impl Default for CommandPolicy {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            confirm_unlisted: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    /// On the allow list, or confirmation is turned off
+    Allowed,
+    /// Approved by the user
+    Confirmed,
+    /// Refused by the user, or not answered in time
+    Rejected,
+    /// On the deny list
+    Denied,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// The command that was asked for it, e.g. `execute_command`
+    pub source: String,
+    pub program: String,
+    pub args: Vec<String>,
+    pub working_dir: Option<String>,
+    pub decision: Decision,
+}
+
+/// Sent as `command-confirmation`; answer with `confirm_command`
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfirmationRequest {
+    pub request_id: String,
+    /// Programs not on the allow list as they will be run, e.g. `cargo`
+    /// or `./build.sh`
+    pub programs: Vec<String>,
+    /// The shell line chains, pipes or redirects commands, so it was asked
+    /// about even if `programs` is empty
+    pub compound: bool,
+    pub command_line: String,
+    pub working_dir: Option<String>,
+}
+
+struct Answer {
+    approved: bool,
+    remember: bool,
+}
+
+#[derive(Default)]
+pub struct CommandPolicyState {
+    pending: Mutex<HashMap<String, oneshot::Sender<Answer>>>,
+    /// Loaded from the audit file on first use
+    audit: Mutex<Option<VecDeque<AuditEntry>>>,
+}
+
+fn load_policy(app: &AppHandle) -> CommandPolicy {
+    let store = match app.store(POLICY_STORE) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Failed to open {}: {}", POLICY_STORE, e);
+            return CommandPolicy::default();
+        }
+    };
+    store
+        .get(POLICY_KEY)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn save_policy(app: &AppHandle, policy: &CommandPolicy) -> Result<(), String> {
+    let store = app
+        .store(POLICY_STORE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+    let value = serde_json::to_value(policy).map_err(|e| format!("Failed to serialize policy: {}", e))?;
+    store.set(POLICY_KEY, value);
+    store.save().map_err(|e| format!("Failed to save store: {}", e))
+}
+
+/// `git` for `/usr/bin/git` and `C:\Git\bin\git.exe`, for matching list entries
+fn program_name(program: &str) -> String {
+    let path = Path::new(program);
+    let name = if cfg!(windows) {
+        path.file_stem()
+    } else {
+        path.file_name()
+    };
+    let name = name.map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if cfg!(windows) {
+        name.to_lowercase()
+    } else {
+        name
+    }
+}
+
+fn allowed(list: &[String], program: &str) -> bool {
+    list.iter().any(|entry| entry == program)
+}
+
+fn denied(list: &[String], program: &str) -> bool {
+    let name = program_name(program);
+    list.iter()
+        .any(|entry| entry == program || (!entry.contains(['/', '\\']) && program_name(entry) == name))
+}
+
+/// Programs the request runs: for a shell line, the shell and the line's
+/// first word. What follows `;` or `|` isn't covered; see `is_compound`.
+fn programs(program: &str, args: &[String], use_shell: bool) -> Vec<String> {
+    let mut programs = vec![program.to_string()];
+    if use_shell {
+        let line = args.last().map(String::as_str).unwrap_or_default();
+        let first = split_command_line(line)
+            .ok()
+            .and_then(|words| words.into_iter().next())
+            .unwrap_or_else(|| line.to_string());
+        programs.push(first);
+    }
+    programs
+}
+
+/// Whether a shell line may run more than its first word: command
+/// separators, pipes, substitutions or redirections. Such lines are always
+/// confirmed, since the allow list only vouches for the first program.
+fn is_compound(args: &[String], use_shell: bool) -> bool {
+    let line = args.last().map(String::as_str).unwrap_or_default();
+    use_shell && (line.contains([';', '|', '&', '`', '<', '>', '\n']) || line.contains("$("))
+}
+
+fn audit_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join(AUDIT_FILE))
+}
+
+/// The newest `AUDIT_MEMORY` entries of the audit file
+fn read_audit_file(path: Option<&Path>) -> VecDeque<AuditEntry> {
+    let mut entries = VecDeque::new();
+    let Some(file) = path.and_then(|p| std::fs::File::open(p).ok()) else {
+        return entries;
+    };
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        if let Ok(entry) = serde_json::from_str(&line) {
+            entries.push_back(entry);
+            if entries.len() > AUDIT_MEMORY {
+                entries.pop_front();
+            }
+        }
+    }
+    entries
+}
+
+fn record(app: &AppHandle, state: &CommandPolicyState, entry: AuditEntry) {
+    let path = audit_path(app);
+    let line = serde_json::to_string(&entry).unwrap_or_default();
+    if let Ok(mut audit) = state.audit.lock() {
+        // Loaded before appending, so the entry isn't read back a second time
+        let audit = audit.get_or_insert_with(|| read_audit_file(path.as_deref()));
+        audit.push_back(entry);
+        while audit.len() > AUDIT_MEMORY {
+            audit.pop_front();
+        }
+    }
+    let Some(path) = path else {
+        return;
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(&path))
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = written {
+        tracing::warn!("Failed to write audit log: {}", e);
+    }
+}
+
+/// Asks the frontend and waits for `confirm_command`
+async fn confirm(app: &AppHandle, state: &CommandPolicyState, request: ConfirmationRequest) -> Answer {
+    let (tx, rx) = oneshot::channel();
+    let id = request.request_id.clone();
+    if let Ok(mut pending) = state.pending.lock() {
+        pending.insert(id.clone(), tx);
+    }
+    let _ = app.emit("command-confirmation", request);
+    let answer = tokio::time::timeout(CONFIRM_TIMEOUT, rx).await;
+    if let Ok(mut pending) = state.pending.lock() {
+        pending.remove(&id);
+    }
+    match answer {
+        Ok(Ok(answer)) => answer,
+        _ => Answer {
+            approved: false,
+            remember: false,
+        },
+    }
+}
+
+/// Checks `program` against the policy, asking the user if it is unlisted,
+/// and records the decision. Errors unless the command may run.
+pub async fn authorize(
+    app: &AppHandle,
+    state: &CommandPolicyState,
+    source: &str,
+    program: &str,
+    args: &[String],
+    working_dir: Option<&Path>,
+    use_shell: bool,
+) -> Result<(), AppError> {
+    let working_dir = working_dir.map(|dir| dir.to_string_lossy().to_string());
+    let policy = load_policy(app);
+    let programs = programs(program, args, use_shell);
+    let compound = is_compound(args, use_shell);
+
+    let decision = if let Some(program) = programs.iter().find(|p| denied(&policy.deny, p)) {
+        tracing::warn!("{} is on the deny list", program);
+        Decision::Denied
+    } else if !policy.confirm_unlisted || (!compound && programs.iter().all(|p| allowed(&policy.allow, p))) {
+        Decision::Allowed
+    } else {
+        let mut unlisted: Vec<String> = programs.into_iter().filter(|p| !allowed(&policy.allow, p)).collect();
+        unlisted.dedup();
+        let command_line = std::iter::once(program.to_string())
+            .chain(args.iter().map(|a| shell_words::quote(a).to_string()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let request = ConfirmationRequest {
+            request_id: Uuid::new_v4().to_string(),
+            programs: unlisted.clone(),
+            compound,
+            command_line,
+            working_dir: working_dir.clone(),
+        };
+        let answer = confirm(app, state, request).await;
+        if answer.approved && answer.remember {
+            // Re-read in case the policy changed while the user was deciding
+            let mut policy = load_policy(app);
+            for program in unlisted {
+                if !policy.allow.contains(&program) {
+                    policy.allow.push(program);
+                }
+            }
+            save_policy(app, &policy)?;
+        }
+        if answer.approved {
+            Decision::Confirmed
+        } else {
+            Decision::Rejected
+        }
+    };
+
+    record(
+        app,
+        state,
+        AuditEntry {
+            timestamp_ms: now_ms(),
+            source: source.to_string(),
+            program: program.to_string(),
+            args: args.to_vec(),
+            working_dir,
+            decision,
+        },
+    );
+    match decision {
+        Decision::Allowed | Decision::Confirmed => Ok(()),
+        Decision::Denied => Err(AppError::permission_denied(format!(
+            "{} is not allowed to run",
+            program_name(program)
+        ))),
+        Decision::Rejected => Err(AppError::permission_denied("Command was not approved")),
+    }
+}
+
+#[tauri::command]
+pub async fn get_command_policy(app_handle: AppHandle) -> Result<CommandPolicy, String> {
+    Ok(load_policy(&app_handle))
+}
+
+#[tauri::command]
+pub async fn set_command_policy(app_handle: AppHandle, policy: CommandPolicy) -> Result<(), String> {
+    let clean = |list: Vec<String>| -> Vec<String> {
+        list.into_iter()
+            .map(|entry| entry.trim().to_string())
+            .filter(|entry| !entry.is_empty())
+            .collect()
+    };
+    let policy = CommandPolicy {
+        allow: clean(policy.allow),
+        deny: clean(policy.deny),
+        confirm_unlisted: policy.confirm_unlisted,
+    };
+    save_policy(&app_handle, &policy)
+}
+
+/// Answers a `command-confirmation`; with `remember` its programs are
+/// added to the allow list
+#[tauri::command]
+pub async fn confirm_command(
+    state: State<'_, CommandPolicyState>,
+    request_id: String,
+    approved: bool,
+    remember: Option<bool>,
+) -> Result<(), String> {
+    let sender = state
+        .pending
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .remove(&request_id)
+        .ok_or_else(|| format!("No pending confirmation with id: {}", request_id))?;
+    let _ = sender.send(Answer {
+        approved,
+        remember: remember.unwrap_or(false),
+    });
+    Ok(())
+}
+
+/// The most recent decisions, newest last; at most `limit` (default all kept)
+#[tauri::command]
+pub async fn get_command_audit_log(
+    app_handle: AppHandle,
+    state: State<'_, CommandPolicyState>,
+    limit: Option<usize>,
+) -> Result<Vec<AuditEntry>, String> {
+    let mut audit = state.audit.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    let audit = audit.get_or_insert_with(|| read_audit_file(audit_path(&app_handle).as_deref()));
+    let skip = limit.map_or(0, |limit| audit.len().saturating_sub(limit));
+    Ok(audit.iter().skip(skip).cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn allow_entries_match_exactly() {
+        let allow = strings(&["git", "/usr/local/bin/cargo"]);
+        assert!(allowed(&allow, "git"));
+        assert!(!allowed(&allow, "./git"));
+        assert!(!allowed(&allow, "/tmp/evil/git"));
+        assert!(allowed(&allow, "/usr/local/bin/cargo"));
+        assert!(!allowed(&allow, "cargo"));
+        assert!(!allowed(&allow, "/usr/bin/cargo"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn deny_entries_without_a_path_match_anywhere() {
+        let deny = strings(&["rm", "/usr/bin/curl"]);
+        assert!(denied(&deny, "rm"));
+        assert!(denied(&deny, "/bin/rm"));
+        assert!(denied(&deny, "./rm"));
+        assert!(denied(&deny, "/usr/bin/curl"));
+        assert!(!denied(&deny, "curl"));
+        assert!(!denied(&deny, "/opt/bin/curl"));
+        assert!(!denied(&deny, "rmdir"));
+    }
+
+    #[test]
+    fn programs_include_the_first_word_of_a_shell_line() {
+        assert_eq!(programs("git", &strings(&["status"]), false), strings(&["git"]));
+        assert_eq!(
+            programs("sh", &strings(&["-c", "cargo build --release"]), true),
+            strings(&["sh", "cargo"])
+        );
+        assert_eq!(
+            programs("sh", &strings(&["-c", "'./my tool' --flag"]), true),
+            strings(&["sh", "./my tool"])
+        );
+        assert_eq!(programs("sh", &strings(&["-c", ""]), true), strings(&["sh", ""]));
+    }
+
+    #[test]
+    fn compound_shell_lines_are_detected() {
+        let shell = |line: &str| is_compound(&strings(&["-c", line]), true);
+        assert!(!shell("cargo build --release"));
+        assert!(shell("cargo build; rm -rf ~"));
+        assert!(shell("cat notes.md | grep todo"));
+        assert!(shell("make && make install"));
+        assert!(shell("echo $(whoami)"));
+        assert!(shell("echo `whoami`"));
+        assert!(shell("ls > files.txt"));
+        assert!(shell("sort < files.txt"));
+        assert!(shell("true\nrm -rf ~"));
+        // Without a shell the arguments are passed on as they are
+        assert!(!is_compound(&strings(&["a;b", "c|d"]), false));
+    }
+}
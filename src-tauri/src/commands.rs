@@ -0,0 +1,233 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::sync::oneshot;
+
+use crate::command_policy::{self, CommandPolicyState};
+use crate::fs_guard::FsGuardState;
+use crate::shell_env;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandExit {
+    pub job_id: String,
+    /// None if the process was ended by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    /// True if the job was stopped by `kill_command`
+    pub killed: bool,
+}
+
+/// Running jobs, keyed by job ID; sending on the channel kills the process
+#[derive(Default)]
+pub struct CommandState {
+    jobs: Mutex<HashMap<String, oneshot::Sender<()>>>,
+}
+
+/// Splits a command line into program and arguments, honouring single and
+/// double quotes and backslash escapes
+pub fn split_command_line(line: &str) -> Result<Vec<String>, String> {
+    let parts = shell_words::split(line).map_err(|e| format!("Invalid command line: {}", e))?;
+    if parts.is_empty() {
+        return Err("Empty command".to_string());
+    }
+    Ok(parts)
+}
+
+/// The user's shell and the flag that makes it run one command line
+fn shell() -> (String, &'static str) {
+    if cfg!(target_os = "windows") {
+        ("cmd.exe".to_string(), "/C")
+    } else {
+        let shell = std::env::var("SHELL")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "/bin/sh".to_string());
+        (shell, "-c")
+    }
+}
+
+/// Program and arguments to execute for a command request.
+///
+/// With `use_shell`, the line (plus any quoted `args`) is handed to the
+/// user's shell so pipelines, globs and variables work. Otherwise `args`, if
+/// given, are passed through untouched and `command` is the program;
+/// without them `command` is split with `split_command_line`.
+pub fn resolve_command(
+    command: &str,
+    args: Option<Vec<String>>,
+    use_shell: bool,
+) -> Result<(String, Vec<String>), String> {
+    if use_shell {
+        let mut line = command.to_string();
+        for arg in args.unwrap_or_default() {
+            line.push(' ');
+            line.push_str(&shell_words::quote(&arg));
+        }
+        if line.trim().is_empty() {
+            return Err("Empty command".to_string());
+        }
+        let (shell, flag) = shell();
+        return Ok((shell, vec![flag.to_string(), line]));
+    }
+    match args {
+        Some(_) if command.trim().is_empty() => Err("Empty command".to_string()),
+        Some(args) => Ok((command.to_string(), args)),
+        None => {
+            let mut parts = split_command_line(command)?;
+            let program = parts.remove(0);
+            Ok((program, parts))
+        }
+    }
+}
+
+/// Forwards everything read from `stream` as `{event}` until EOF
+pub(crate) async fn forward<R: AsyncRead + Unpin>(app: AppHandle, event: String, mut stream: R) {
+    let mut buffer = [0u8; 4096];
+    loop {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let _ = app.emit(&event, String::from_utf8_lossy(&buffer[..n]).to_string());
+            }
+        }
+    }
+}
+
+impl CommandState {
+    /// Removes `job_id` if it's still the job listening on `kill_rx`. Once
+    /// `kill_command` has taken our sender, the id may already belong to a
+    /// newer job.
+    fn release(&self, job_id: &str, kill_rx: &mut oneshot::Receiver<()>) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        if matches!(kill_rx.try_recv(), Err(oneshot::error::TryRecvError::Empty)) {
+            jobs.remove(job_id);
+        }
+    }
+}
+
+/// Starts `command` as job `job_id`, chosen by the caller so it can listen
+/// before any output arrives, and returns right away.
+///
+/// `command`, `args` and `use_shell` are interpreted by `resolve_command`.
+/// Output arrives on `command-stdout-{job_id}` and `command-stderr-{job_id}`,
+/// and a `CommandExit` on `command-exit-{job_id}` once the process has finished.
+/// The program must pass `command_policy` first.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn spawn_command(
+    app_handle: AppHandle,
+    state: State<'_, CommandState>,
+    policy_state: State<'_, CommandPolicyState>,
+    guard: State<'_, FsGuardState>,
+    job_id: String,
+    command: String,
+    args: Option<Vec<String>>,
+    working_dir: Option<String>,
+    use_shell: Option<bool>,
+) -> Result<String, String> {
+    // Reserve the id before the policy prompt, so a second request for it
+    // can't slip in while the first waits for the user
+    let (kill_tx, mut kill_rx) = oneshot::channel();
+    match state
+        .jobs
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .entry(job_id.clone())
+    {
+        Entry::Occupied(_) => return Err(format!("A command with id {} is already running", job_id)),
+        Entry::Vacant(slot) => {
+            slot.insert(kill_tx);
+        }
+    }
+
+    let started = async {
+        let working_dir = working_dir.map(|dir| guard.check(&dir)).transpose()?;
+        let use_shell = use_shell.unwrap_or(false);
+        let (program, args) = resolve_command(&command, args, use_shell)?;
+        command_policy::authorize(
+            &app_handle,
+            &policy_state,
+            "spawn_command",
+            &program,
+            &args,
+            working_dir.as_deref(),
+            use_shell,
+        )
+        .await?;
+
+        let mut cmd = Command::new(&program);
+        cmd.args(&args)
+            .envs(shell_env::environment())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(dir) = working_dir {
+            cmd.current_dir(dir);
+        }
+        cmd.spawn().map_err(|e| format!("Failed to start {}: {}", program, e))
+    };
+    let mut child = match started.await {
+        Ok(child) => child,
+        Err(e) => {
+            state.release(&job_id, &mut kill_rx);
+            return Err(e);
+        }
+    };
+
+    let stdout = child.stdout.take().map(|out| {
+        tauri::async_runtime::spawn(forward(app_handle.clone(), format!("command-stdout-{}", job_id), out))
+    });
+    let stderr = child.stderr.take().map(|err| {
+        tauri::async_runtime::spawn(forward(app_handle.clone(), format!("command-stderr-{}", job_id), err))
+    });
+
+    let id = job_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let (status, killed) = tokio::select! {
+            status = child.wait() => (status.ok(), false),
+            _ = &mut kill_rx => {
+                let _ = child.kill().await;
+                (child.wait().await.ok(), true)
+            }
+        };
+        // Let the readers drain so no output arrives after the exit event
+        for reader in [stdout, stderr].into_iter().flatten() {
+            let _ = reader.await;
+        }
+        app_handle.state::<CommandState>().release(&id, &mut kill_rx);
+
+        let exit = CommandExit {
+            job_id: id.clone(),
+            exit_code: status.and_then(|s| s.code()),
+            success: status.is_some_and(|s| s.success()),
+            killed,
+        };
+        let _ = app_handle.emit(&format!("command-exit-{}", id), exit);
+    });
+
+    Ok(job_id)
+}
+
+#[tauri::command]
+pub async fn kill_command(state: State<'_, CommandState>, job_id: String) -> Result<(), String> {
+    let kill = state
+        .jobs
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .remove(&job_id);
+    match kill {
+        // The job may finish between the lookup and the send; that's fine
+        Some(kill) => {
+            let _ = kill.send(());
+            Ok(())
+        }
+        None => Err(format!("No running command with id: {}", job_id)),
+    }
+}
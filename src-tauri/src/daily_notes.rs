@@ -0,0 +1,109 @@
+//! Daily notes: one markdown file per day, at a path built from the
+//! `dailyNotePath` setting and started from the `dailyNoteTemplate` template.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write as _};
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+use crate::fs_guard::FsGuardState;
+use crate::templates;
+use crate::workspace_settings;
+
+const DEFAULT_PATH: &str = "journal/%Y-%m-%d.md";
+const DEFAULT_TEMPLATE: &str = "journal";
+
+#[derive(Debug, Serialize)]
+pub struct DailyNote {
+    pub path: String,
+    /// The note's day as YYYY-MM-DD
+    pub date: String,
+    /// False if the note already existed
+    pub created: bool,
+}
+
+/// Where the note for `date` lives: `pattern` formatted with strftime
+/// specifiers, relative to `root`, with `.md` added if it has no extension
+fn note_path(root: &Path, pattern: &str, date: NaiveDate) -> Result<PathBuf, String> {
+    let mut relative = String::new();
+    write!(relative, "{}", date.format(pattern)).map_err(|_| format!("Invalid daily note path: {}", pattern))?;
+    let mut relative = PathBuf::from(relative.trim());
+    if relative.extension().is_none() {
+        relative.set_extension("md");
+    }
+    let escapes = relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if escapes || relative.file_name().is_none() {
+        return Err(format!("Daily note path must be relative to the workspace: {}", pattern));
+    }
+    Ok(root.join(relative))
+}
+
+/// Path of the daily note for `date` (today if not given, as YYYY-MM-DD),
+/// creating it from the configured template if it doesn't exist yet
+#[tauri::command]
+pub async fn open_daily_note(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    root: String,
+    date: Option<String>,
+) -> Result<DailyNote, String> {
+    // `note_path` keeps the note below the root
+    let root = guard.check(&root)?;
+    if !root.is_dir() {
+        return Err(format!("Workspace root is not a directory: {}", root.display()));
+    }
+    let date = match date {
+        Some(text) => {
+            NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").map_err(|e| format!("Invalid date {}: {}", text, e))?
+        }
+        None => Local::now().date_naive(),
+    };
+
+    let settings = workspace_settings::effective_for_root(&app_handle, &root);
+    let pattern = settings.get("dailyNotePath").and_then(Value::as_str).unwrap_or(DEFAULT_PATH);
+    let template_id = settings
+        .get("dailyNoteTemplate")
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_TEMPLATE);
+    let path = note_path(&root, pattern, date)?;
+    let note = |created| DailyNote {
+        path: path.to_string_lossy().to_string(),
+        date: date.format("%Y-%m-%d").to_string(),
+        created,
+    };
+    if path.is_file() {
+        return Ok(note(false));
+    }
+
+    // {{date}} and friends describe the note's day, not the day it was created
+    let moment = date
+        .and_time(Local::now().time())
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or_else(Local::now);
+    let content = match template_id.trim() {
+        "" => String::new(),
+        id => templates::render(&templates::template_text(&app_handle, id)?, &path, &HashMap::new(), moment),
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    // create_new so a note made in the meantime (e.g. by a sync client) is never overwritten
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return Ok(note(false)),
+        Err(e) => return Err(format!("Failed to create daily note: {}", e)),
+    };
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write daily note: {}", e))?;
+    Ok(note(true))
+}
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::handshake::server::Request;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+use crate::fs_guard::FsGuardState;
+use crate::{lsp, shell_env};
+
+// How long to wait for a TCP adapter to start listening
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+type DapReader = Box<dyn AsyncRead + Send + Unpin>;
+type DapWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// How the bridge talks to the adapter process
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DapTransport {
+    Stdio,
+    /// The adapter listens on the port substituted for `${port}` in its arguments
+    Tcp,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DapAdapterConfig {
+    pub id: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub transport: DapTransport,
+}
+
+fn adapter(id: &str, command: &str, args: &[&str], transport: DapTransport) -> DapAdapterConfig {
+    DapAdapterConfig {
+        id: id.to_string(),
+        command: command.to_string(),
+        args: args.iter().map(|s| s.to_string()).collect(),
+        transport,
+    }
+}
+
+pub fn builtin_adapters() -> Vec<DapAdapterConfig> {
+    let python = if cfg!(target_os = "windows") { "python" } else { "python3" };
+    vec![
+        adapter("codelldb", "codelldb", &["--port", "${port}"], DapTransport::Tcp),
+        adapter("delve", "dlv", &["dap", "--listen", "127.0.0.1:${port}"], DapTransport::Tcp),
+        adapter("debugpy", python, &["-m", "debugpy.adapter"], DapTransport::Stdio),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartDapResult {
+    pub session_id: String,
+    pub port: u16,
+    /// Must be sent as the `token` query parameter when connecting, like
+    /// for language servers
+    pub token: String,
+}
+
+type Tasks = Arc<StdMutex<Vec<tokio::task::JoinHandle<()>>>>;
+
+struct DapSession {
+    port: u16,
+    token: String,
+    child: Arc<Mutex<Child>>,
+    /// The acceptor and reader, plus two per connected client
+    tasks: Tasks,
+}
+
+impl Drop for DapSession {
+    fn drop(&mut self) {
+        let tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        for task in tasks.iter() {
+            task.abort();
+        }
+    }
+}
+
+fn track(tasks: &Tasks, task: tokio::task::JoinHandle<()>) {
+    let mut tasks = tasks.lock().unwrap_or_else(|e| e.into_inner());
+    tasks.retain(|t| !t.is_finished());
+    tasks.push(task);
+}
+
+async fn free_port() -> io::Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    Ok(listener.local_addr()?.port())
+}
+
+async fn connect_with_retry(port: u16) -> io::Result<TcpStream> {
+    let deadline = tokio::time::Instant::now() + TCP_CONNECT_TIMEOUT;
+    loop {
+        match TcpStream::connect(("127.0.0.1", port)).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) if tokio::time::Instant::now() >= deadline => return Err(e),
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    }
+}
+
+/// Reads one `Content-Length` framed message; `Ok(None)` on clean EOF
+async fn read_message<R: AsyncRead + Unpin>(reader: &mut BufReader<R>) -> io::Result<Option<String>> {
+    let mut content_length: Option<usize> = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some(rest) = trimmed.to_ascii_lowercase().strip_prefix("content-length:") {
+            content_length = rest.trim().parse().ok();
+        }
+    }
+
+    let mut body = vec![0u8; content_length.unwrap_or(0)];
+    reader.read_exact(&mut body).await?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+impl DapSession {
+    async fn spawn(
+        app_handle: AppHandle,
+        session_id: String,
+        config: &DapAdapterConfig,
+        cwd: Option<PathBuf>,
+    ) -> io::Result<Self> {
+        tracing::info!("Starting {} adapter", config.id);
+
+        let adapter_port = match config.transport {
+            DapTransport::Tcp => Some(free_port().await?),
+            DapTransport::Stdio => None,
+        };
+        let args: Vec<String> = config
+            .args
+            .iter()
+            .map(|a| match adapter_port {
+                Some(p) => a.replace("${port}", &p.to_string()),
+                None => a.clone(),
+            })
+            .collect();
+
+        let mut cmd = Command::new(&config.command);
+        cmd.args(&args).envs(shell_env::environment()).kill_on_drop(true).stderr(Stdio::inherit());
+        if let Some(dir) = &cwd {
+            cmd.current_dir(dir);
+        }
+        match config.transport {
+            DapTransport::Stdio => cmd.stdin(Stdio::piped()).stdout(Stdio::piped()),
+            DapTransport::Tcp => cmd.stdin(Stdio::null()).stdout(Stdio::inherit()),
+        };
+        let mut child = cmd.spawn()?;
+
+        let (reader, writer): (DapReader, DapWriter) = match adapter_port {
+            None => {
+                let stdin = child.stdin.take().ok_or_else(|| io::Error::other("No stdin"))?;
+                let stdout = child.stdout.take().ok_or_else(|| io::Error::other("No stdout"))?;
+                (Box::new(stdout), Box::new(stdin))
+            }
+            Some(port) => {
+                let stream = connect_with_retry(port).await?;
+                let (r, w) = stream.into_split();
+                (Box::new(r), Box::new(w))
+            }
+        };
+        let writer = Arc::new(Mutex::new(writer));
+        let clients: Arc<Mutex<Vec<tokio::sync::mpsc::UnboundedSender<String>>>> = Arc::new(Mutex::new(Vec::new()));
+
+        // WebSocket endpoint for the frontend
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        tracing::debug!("WebSocket server bound to port {}", port);
+
+        let token = Uuid::new_v4().simple().to_string();
+        let tasks: Tasks = Arc::default();
+
+        let clients_for_ws = clients.clone();
+        let token_for_ws = token.clone();
+        let tasks_for_ws = tasks.clone();
+        let ws_task = tokio::spawn(async move {
+            while let Ok((stream, _addr)) = listener.accept().await {
+                #[allow(clippy::result_large_err)]
+                let handshake = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response| {
+                    lsp::authorize(&token_for_ws, request, response)
+                });
+                let ws_stream = match handshake.await {
+                    Ok(s) => s,
+                    Err(e) => {
+                        tracing::warn!("WebSocket handshake failed: {}", e);
+                        continue;
+                    }
+                };
+
+                let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+                clients_for_ws.lock().await.push(tx);
+
+                let (mut sink, mut stream) = ws_stream.split();
+                let writer_for_ws = writer.clone();
+
+                // Client -> adapter
+                let inbound = tokio::spawn(async move {
+                    while let Some(Ok(msg)) = stream.next().await {
+                        if let Message::Text(text) = msg {
+                            let header = format!("Content-Length: {}\r\n\r\n", text.len());
+                            let mut w = writer_for_ws.lock().await;
+                            if w.write_all(header.as_bytes()).await.is_err()
+                                || w.write_all(text.as_bytes()).await.is_err()
+                                || w.flush().await.is_err()
+                            {
+                                tracing::warn!("Failed to write to adapter");
+                                break;
+                            }
+                        }
+                    }
+                });
+
+                // Adapter -> client
+                let outbound = tokio::spawn(async move {
+                    while let Some(msg) = rx.recv().await {
+                        if sink.send(Message::Text(msg)).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+                track(&tasks_for_ws, inbound);
+                track(&tasks_for_ws, outbound);
+            }
+        });
+
+        // Forward adapter output to every connected client
+        let clients_for_reader = clients.clone();
+        let reader_task = tokio::spawn(async move {
+            let mut reader = BufReader::new(reader);
+            loop {
+                match read_message(&mut reader).await {
+                    Ok(Some(text)) => {
+                        let mut list = clients_for_reader.lock().await;
+                        list.retain(|sender| sender.send(text.clone()).is_ok());
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::warn!("Read error: {}", e);
+                        break;
+                    }
+                }
+            }
+            tracing::info!("Adapter output closed for session {}", session_id);
+            let _ = app_handle.emit(&format!("dap-exit-{}", session_id), ());
+        });
+
+        track(&tasks, ws_task);
+        track(&tasks, reader_task);
+        Ok(Self {
+            port,
+            token,
+            child: Arc::new(Mutex::new(child)),
+            tasks,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct DapState {
+    sessions: Mutex<HashMap<String, DapSession>>,
+}
+
+#[tauri::command]
+pub async fn list_dap_adapters() -> Result<Vec<DapAdapterConfig>, String> {
+    Ok(builtin_adapters())
+}
+
+/// Spawns a debug adapter and returns the local WebSocket port bridging to it,
+/// with the token clients must present. `dap-exit-{session_id}` is emitted
+/// when the adapter goes away.
+#[tauri::command]
+pub async fn start_dap_session(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DapState>,
+    guard: tauri::State<'_, FsGuardState>,
+    adapter: String,
+    cwd: Option<String>,
+) -> Result<StartDapResult, String> {
+    let cwd = cwd.map(|dir| guard.check(&dir)).transpose()?;
+    let config = builtin_adapters()
+        .into_iter()
+        .find(|a| a.id == adapter)
+        .ok_or_else(|| format!("Unknown debug adapter: {}", adapter))?;
+
+    let session_id = Uuid::new_v4().to_string();
+    let session = DapSession::spawn(app_handle, session_id.clone(), &config, cwd)
+        .await
+        .map_err(|e| format!("Failed to start debug adapter {}: {}", config.command, e))?;
+
+    let port = session.port;
+    let token = session.token.clone();
+    state.sessions.lock().await.insert(session_id.clone(), session);
+    tracing::info!("Started session {} on port {}", session_id, port);
+
+    Ok(StartDapResult {
+        session_id,
+        port,
+        token,
+    })
+}
+
+#[tauri::command]
+pub async fn stop_dap_session(state: tauri::State<'_, DapState>, session_id: String) -> Result<(), String> {
+    let session = state
+        .sessions
+        .lock()
+        .await
+        .remove(&session_id)
+        .ok_or_else(|| format!("No debug session with id: {}", session_id))?;
+
+    let _ = session.child.lock().await.kill().await;
+    tracing::info!("Stopped session {}", session_id);
+    Ok(())
+}
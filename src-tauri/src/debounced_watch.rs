@@ -0,0 +1,86 @@
+//! Recursive file watching for the indexes that keep themselves current.
+//! Changed paths are collected until the tree has been quiet for a while
+//! and then handed over in one batch.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use notify::{RecursiveMode, Watcher};
+
+// A batch is flushed this many debounce periods after its first change even
+// if events keep coming, so a steady stream (a build writing output) still
+// gets through
+const MAX_DELAY_FACTOR: u32 = 4;
+
+/// What a watcher does with the changes it sees; runs on the watcher's thread
+pub trait WatchHandler: Send + 'static {
+    /// Whether a changed path should be collected at all
+    fn wants(&self, path: &Path) -> bool;
+
+    /// Handles a batch of changed paths, sorted; returning false stops watching
+    fn flush(&mut self, paths: Vec<PathBuf>) -> bool;
+
+    /// Runs after every wait, whether or not anything was flushed
+    fn tick(&mut self) {}
+
+    /// Runs once when watching stops
+    fn finish(&mut self) {}
+}
+
+/// Watches `root` recursively on a background thread until `stop` is set.
+/// Paths are flushed once no event arrived for `debounce`.
+pub fn watch(root: &Path, debounce: Duration, stop: Arc<AtomicBool>, mut handler: impl WatchHandler) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| format!("Failed to create watcher: {}", e))?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+    let root = root.to_path_buf();
+
+    thread::spawn(move || {
+        // Owned by the thread so watching stops when it exits
+        let _watcher = watcher;
+        let mut pending: HashSet<PathBuf> = HashSet::new();
+        let mut first_pending: Option<Instant> = None;
+        loop {
+            let quiet = match rx.recv_timeout(debounce) {
+                Ok(Ok(event)) => {
+                    pending.extend(event.paths.into_iter().filter(|p| handler.wants(p)));
+                    false
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!("Watch error for {}: {}", root.display(), e);
+                    false
+                }
+                Err(RecvTimeoutError::Timeout) => true,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            if pending.is_empty() {
+                first_pending = None;
+            } else {
+                first_pending.get_or_insert_with(Instant::now);
+            }
+
+            let overdue = first_pending.is_some_and(|t| t.elapsed() >= debounce * MAX_DELAY_FACTOR);
+            if !pending.is_empty() && (quiet || overdue) {
+                first_pending = None;
+                let mut paths: Vec<PathBuf> = pending.drain().collect();
+                paths.sort();
+                if !handler.flush(paths) {
+                    break;
+                }
+            }
+            handler.tick();
+        }
+        handler.finish();
+    });
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! `tmd://` links, e.g. `tmd://open?path=/notes/todo.md&line=42&column=3`.
+//! Links that arrive while the app runs, directly or forwarded by a second
+//! launch, are parsed and emitted as `deep-link`. The one the app was
+//! launched with arrives before the frontend listens, so it is kept for
+//! `take_pending_deep_links` instead.
+//!
+//! Any page can hand out a link, so following one grants nothing: its path
+//! only opens if it lies inside a workspace the user already opened.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::single_instance::focus_main_window;
+
+pub const SCHEME: &str = "tmd";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepLink {
+    pub url: String,
+    /// The host part, e.g. `open`
+    pub action: String,
+    pub path: Option<String>,
+    /// 1-based, as written in the link
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// Every query parameter, including the ones above
+    pub params: BTreeMap<String, String>,
+}
+
+#[derive(Default)]
+pub struct DeepLinkState {
+    pending: Mutex<Vec<DeepLink>>,
+}
+
+/// `None` for other schemes and links without an action
+pub fn parse(url: &Url) -> Option<DeepLink> {
+    if url.scheme() != SCHEME {
+        return None;
+    }
+    // `tmd://open?...` has the action as host; `tmd:open?...` as path
+    let action = url
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| url.path().trim_matches('/'))
+        .to_lowercase();
+    if action.is_empty() {
+        return None;
+    }
+    let params: BTreeMap<String, String> = url.query_pairs().into_owned().collect();
+    let number = |key: &str| params.get(key).and_then(|v| v.trim().parse().ok()).filter(|n| *n > 0);
+    Some(DeepLink {
+        url: url.to_string(),
+        action,
+        path: params.get("path").filter(|p| !p.is_empty()).cloned(),
+        line: number("line"),
+        column: number("column"),
+        params,
+    })
+}
+
+fn parse_all(urls: Vec<Url>) -> Vec<DeepLink> {
+    let mut links = Vec::new();
+    for url in urls {
+        let Some(link) = parse(&url) else {
+            tracing::warn!("Ignoring unsupported link {}", url);
+            continue;
+        };
+        tracing::info!("Received link {}", link.url);
+        links.push(link);
+    }
+    links
+}
+
+/// Registers the scheme where that happens at runtime and starts listening;
+/// called from `setup`
+pub fn init(app: &AppHandle) {
+    // Installed bundles register through their installer; this covers dev
+    // builds and AppImages
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register(SCHEME) {
+        tracing::warn!("Failed to register {}:// links: {}", SCHEME, e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for link in parse_all(event.urls()) {
+            let _ = handle.emit("deep-link", link);
+        }
+        focus_main_window(&handle);
+    });
+
+    match app.deep_link().get_current() {
+        Ok(Some(urls)) => {
+            let links = parse_all(urls);
+            if let Ok(mut pending) = app.state::<DeepLinkState>().pending.lock() {
+                pending.extend(links);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to read launch link: {}", e),
+    }
+}
+
+/// The launch link, if any and not taken yet; call once the `deep-link`
+/// listener is in place
+#[tauri::command]
+pub async fn take_pending_deep_links(state: State<'_, DeepLinkState>) -> Result<Vec<DeepLink>, String> {
+    let mut pending = state
+        .pending
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(std::mem::take(&mut *pending))
+}
@@ -0,0 +1,409 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::process::Command;
+
+use crate::fs_guard::FsGuardState;
+use crate::lsp::registry::find_executable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Linter {
+    Clippy,
+    GolangciLint,
+    Eslint,
+    Markdownlint,
+}
+
+impl Linter {
+    const ALL: [Linter; 4] = [Linter::Clippy, Linter::GolangciLint, Linter::Eslint, Linter::Markdownlint];
+
+    fn name(self) -> &'static str {
+        match self {
+            Linter::Clippy => "clippy",
+            Linter::GolangciLint => "golangci-lint",
+            Linter::Eslint => "eslint",
+            Linter::Markdownlint => "markdownlint",
+        }
+    }
+
+    fn handles(self, path: &Path) -> bool {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        match self {
+            Linter::Clippy => ext == "rs",
+            Linter::GolangciLint => ext == "go",
+            Linter::Eslint => matches!(ext.as_str(), "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "vue"),
+            Linter::Markdownlint => matches!(ext.as_str(), "md" | "markdown"),
+        }
+    }
+
+    /// Clippy and golangci-lint check a whole crate or module at once; the
+    /// others are run on the file alone
+    fn is_project_wide(self) -> bool {
+        matches!(self, Linter::Clippy | Linter::GolangciLint)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// 1-based line and column
+    pub line: usize,
+    pub column: usize,
+    pub end_line: Option<usize>,
+    pub end_column: Option<usize>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    /// Rule or lint name, e.g. `clippy::needless_return` or `MD013`
+    pub code: Option<String>,
+    pub source: Linter,
+}
+
+/// Emitted on `lint-diagnostics`; an empty list clears what `source` reported before
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiagnostics {
+    pub path: String,
+    pub source: Linter,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LinterConfig {
+    pub enabled: Vec<Linter>,
+    /// Lint files after every successful `save_file`
+    pub run_on_save: bool,
+}
+
+impl Default for LinterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Linter::ALL.to_vec(),
+            run_on_save: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DiagnosticsState {
+    config: Mutex<LinterConfig>,
+    /// Files each linter last reported problems for, so they can be cleared
+    published: Mutex<HashMap<Linter, HashSet<PathBuf>>>,
+}
+
+/// Nearest ancestor directory of `path` containing `marker`
+fn project_root(path: &Path, marker: &str) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| dir.join(marker).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Resolves a path a tool printed relative to `cwd` or one of its ancestors
+/// (cargo reports paths relative to the workspace root)
+fn resolve(cwd: &Path, reported: &str) -> PathBuf {
+    let reported = Path::new(reported);
+    if reported.is_absolute() {
+        return reported.to_path_buf();
+    }
+    cwd.ancestors()
+        .map(|dir| dir.join(reported))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| cwd.join(reported))
+}
+
+fn as_usize(value: &Value) -> Option<usize> {
+    value.as_u64().map(|n| n as usize)
+}
+
+fn parse_clippy(cwd: &Path, stdout: &str) -> HashMap<PathBuf, Vec<Diagnostic>> {
+    let mut files: HashMap<PathBuf, Vec<Diagnostic>> = HashMap::new();
+    for line in stdout.lines() {
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if value["reason"] != "compiler-message" {
+            continue;
+        }
+        let message = &value["message"];
+        let severity = match message["level"].as_str() {
+            Some("error") => DiagnosticSeverity::Error,
+            Some("warning") => DiagnosticSeverity::Warning,
+            _ => DiagnosticSeverity::Info,
+        };
+        let Some(span) = message["spans"]
+            .as_array()
+            .and_then(|spans| spans.iter().find(|s| s["is_primary"] == true))
+        else {
+            continue;
+        };
+        let Some(file) = span["file_name"].as_str() else {
+            continue;
+        };
+        files.entry(resolve(cwd, file)).or_default().push(Diagnostic {
+            line: as_usize(&span["line_start"]).unwrap_or(1),
+            column: as_usize(&span["column_start"]).unwrap_or(1),
+            end_line: as_usize(&span["line_end"]),
+            end_column: as_usize(&span["column_end"]),
+            severity,
+            message: message["message"].as_str().unwrap_or_default().to_string(),
+            code: message["code"]["code"].as_str().map(|c| c.to_string()),
+            source: Linter::Clippy,
+        });
+    }
+    files
+}
+
+fn parse_golangci(cwd: &Path, stdout: &str) -> Option<HashMap<PathBuf, Vec<Diagnostic>>> {
+    let value: Value = serde_json::from_str(stdout.lines().find(|l| l.starts_with('{'))?).ok()?;
+    let mut files: HashMap<PathBuf, Vec<Diagnostic>> = HashMap::new();
+    for issue in value["Issues"].as_array().into_iter().flatten() {
+        let pos = &issue["Pos"];
+        let Some(file) = pos["Filename"].as_str() else {
+            continue;
+        };
+        files.entry(resolve(cwd, file)).or_default().push(Diagnostic {
+            line: as_usize(&pos["Line"]).unwrap_or(1),
+            column: as_usize(&pos["Column"]).unwrap_or(1).max(1),
+            end_line: None,
+            end_column: None,
+            severity: match issue["Severity"].as_str() {
+                Some("error") => DiagnosticSeverity::Error,
+                Some("info") => DiagnosticSeverity::Info,
+                _ => DiagnosticSeverity::Warning,
+            },
+            message: issue["Text"].as_str().unwrap_or_default().to_string(),
+            code: issue["FromLinter"].as_str().map(|l| l.to_string()),
+            source: Linter::GolangciLint,
+        });
+    }
+    Some(files)
+}
+
+fn parse_eslint(stdout: &str) -> Option<HashMap<PathBuf, Vec<Diagnostic>>> {
+    let results: Vec<Value> = serde_json::from_str(stdout.trim()).ok()?;
+    Some(
+        results
+            .iter()
+            .filter_map(|result| {
+                let path = PathBuf::from(result["filePath"].as_str()?);
+                let diagnostics = result["messages"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|m| Diagnostic {
+                        line: as_usize(&m["line"]).unwrap_or(1),
+                        column: as_usize(&m["column"]).unwrap_or(1),
+                        end_line: as_usize(&m["endLine"]),
+                        end_column: as_usize(&m["endColumn"]),
+                        severity: if m["severity"] == 2 {
+                            DiagnosticSeverity::Error
+                        } else {
+                            DiagnosticSeverity::Warning
+                        },
+                        message: m["message"].as_str().unwrap_or_default().to_string(),
+                        code: m["ruleId"].as_str().map(|r| r.to_string()),
+                        source: Linter::Eslint,
+                    })
+                    .collect();
+                Some((path, diagnostics))
+            })
+            .collect(),
+    )
+}
+
+fn parse_markdownlint(path: &Path, output: &str) -> Option<HashMap<PathBuf, Vec<Diagnostic>>> {
+    let results: Vec<Value> = serde_json::from_str(output.trim()).ok()?;
+    let diagnostics = results
+        .iter()
+        .map(|r| {
+            let range = r["errorRange"].as_array();
+            let column = range.and_then(|r| r.first()).and_then(as_usize).unwrap_or(1);
+            let length = range.and_then(|r| r.get(1)).and_then(as_usize);
+            let mut message = r["ruleDescription"].as_str().unwrap_or_default().to_string();
+            if let Some(detail) = r["errorDetail"].as_str().filter(|d| !d.is_empty()) {
+                message = format!("{} [{}]", message, detail);
+            }
+            Diagnostic {
+                line: as_usize(&r["lineNumber"]).unwrap_or(1),
+                column,
+                end_line: length.map(|_| as_usize(&r["lineNumber"]).unwrap_or(1)),
+                end_column: length.map(|len| column + len),
+                severity: DiagnosticSeverity::Warning,
+                message,
+                code: r["ruleNames"].get(0).and_then(|n| n.as_str()).map(|n| n.to_string()),
+                source: Linter::Markdownlint,
+            }
+        })
+        .collect();
+    Some(HashMap::from([(path.to_path_buf(), diagnostics)]))
+}
+
+/// Runs `linter` for `path`; results are keyed by file. Linters exit non-zero
+/// when they find problems, so the exit code only matters if nothing parsed.
+async fn run_linter(linter: Linter, path: &Path) -> Result<HashMap<PathBuf, Vec<Diagnostic>>, String> {
+    let file = path.to_string_lossy().to_string();
+    let (program, args, cwd): (&str, Vec<String>, PathBuf) = match linter {
+        Linter::Clippy => {
+            let root = project_root(path, "Cargo.toml").ok_or_else(|| "No Cargo.toml found".to_string())?;
+            ("cargo", vec!["clippy".into(), "--message-format=json".into(), "--quiet".into()], root)
+        }
+        Linter::GolangciLint => {
+            let root = project_root(path, "go.mod").ok_or_else(|| "No go.mod found".to_string())?;
+            ("golangci-lint", vec!["run".into(), "--out-format=json".into(), "./...".into()], root)
+        }
+        Linter::Eslint => {
+            let root = project_root(path, "package.json")
+                .or_else(|| path.parent().map(Path::to_path_buf))
+                .unwrap_or_default();
+            ("eslint", vec!["--format".into(), "json".into(), file.clone()], root)
+        }
+        Linter::Markdownlint => {
+            let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            ("markdownlint", vec!["--json".into(), file.clone()], root)
+        }
+    };
+
+    // Like prettier, eslint is usually a project dependency rather than a global install
+    let local = cwd.join("node_modules").join(".bin").join(program);
+    let binary = find_executable(&local.to_string_lossy())
+        .or_else(|| find_executable(program))
+        .ok_or_else(|| format!("{} was not found on PATH", linter.name()))?;
+
+    let output = Command::new(binary)
+        .args(&args)
+        .current_dir(&cwd)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", linter.name(), e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let parsed = match linter {
+        Linter::Clippy => Some(parse_clippy(&cwd, &stdout)),
+        Linter::GolangciLint => parse_golangci(&cwd, &stdout),
+        Linter::Eslint => parse_eslint(&stdout),
+        // markdownlint-cli prints its JSON report on stderr
+        Linter::Markdownlint if output.status.success() => Some(HashMap::from([(path.to_path_buf(), Vec::new())])),
+        Linter::Markdownlint => parse_markdownlint(path, &stderr),
+    };
+    parsed.ok_or_else(|| {
+        format!(
+            "{} failed ({}): {}",
+            linter.name(),
+            output.status,
+            stderr.lines().next().unwrap_or("no output")
+        )
+    })
+}
+
+/// Emits the results of one run, clearing files the linter no longer reports
+fn publish(app: &AppHandle, linter: Linter, path: &Path, mut results: HashMap<PathBuf, Vec<Diagnostic>>) {
+    let state = app.state::<DiagnosticsState>();
+    let Ok(mut published) = state.published.lock() else {
+        return;
+    };
+    let previous = published.entry(linter).or_default();
+    if linter.is_project_wide() {
+        for stale in previous.iter() {
+            results.entry(stale.clone()).or_default();
+        }
+    } else {
+        results.entry(path.to_path_buf()).or_default();
+    }
+
+    for (file, diagnostics) in results {
+        if diagnostics.is_empty() {
+            previous.remove(&file);
+        } else {
+            previous.insert(file.clone());
+        }
+        let _ = app.emit(
+            "lint-diagnostics",
+            FileDiagnostics {
+                path: file.to_string_lossy().to_string(),
+                source: linter,
+                diagnostics,
+            },
+        );
+    }
+}
+
+/// Runs every enabled linter that handles `path` and publishes the results
+async fn lint(app: &AppHandle, path: &Path, only: Option<&[Linter]>) -> Vec<String> {
+    let enabled = match app.state::<DiagnosticsState>().config.lock() {
+        Ok(config) => config.enabled.clone(),
+        Err(_) => return vec!["Failed to lock state".to_string()],
+    };
+    let mut errors = Vec::new();
+    for linter in enabled {
+        if !linter.handles(path) || only.is_some_and(|only| !only.contains(&linter)) {
+            continue;
+        }
+        match run_linter(linter, path).await {
+            Ok(results) => publish(app, linter, path, results),
+            Err(e) => errors.push(e),
+        }
+    }
+    errors
+}
+
+/// Called after `save_file`; lints in the background if `run_on_save` is set
+pub fn on_save(app: &AppHandle, path: &Path) {
+    let run = app
+        .state::<DiagnosticsState>()
+        .config
+        .lock()
+        .is_ok_and(|config| config.run_on_save);
+    if !run {
+        return;
+    }
+    let app = app.clone();
+    let path = path.to_path_buf();
+    tauri::async_runtime::spawn(async move {
+        for error in lint(&app, &path, None).await {
+            tracing::warn!("{}", error);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn set_linter_config(state: State<'_, DiagnosticsState>, config: LinterConfig) -> Result<(), String> {
+    *state.config.lock().map_err(|e| format!("Failed to lock state: {}", e))? = config;
+    Ok(())
+}
+
+/// Lints `path` now with the enabled linters (or just `linters`). Results are
+/// pushed as `lint-diagnostics` events; linters that could not run are
+/// reported as errors.
+#[tauri::command]
+pub async fn run_linters(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    path: String,
+    linters: Option<Vec<Linter>>,
+) -> Result<(), String> {
+    let path = guard.check(&path)?;
+    let errors = lint(&app_handle, &path, linters.as_deref()).await;
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+use crate::atomic_write;
+use crate::file_info::sha256_hex;
+use crate::lsp::registry::find_executable;
+use crate::markdown::escape_html;
+
+const CACHE_DIR: &str = "diagrams";
+// mmdc starts a headless Chromium, which is slow the first time
+const RENDER_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagramKind {
+    Mermaid,
+    Plantuml,
+}
+
+impl DiagramKind {
+    /// The diagram kind a code fence's language names, if any
+    pub fn from_fence(lang: &str) -> Option<Self> {
+        match lang.to_ascii_lowercase().as_str() {
+            "mermaid" | "mmd" => Some(DiagramKind::Mermaid),
+            "plantuml" | "puml" | "uml" => Some(DiagramKind::Plantuml),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            DiagramKind::Mermaid => "mermaid",
+            DiagramKind::Plantuml => "plantuml",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagramTheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenderedDiagram {
+    pub svg: String,
+    /// True if the SVG came from the cache without running the renderer
+    pub cached: bool,
+}
+
+/// Errors from sources that failed to render, so an unchanged broken
+/// diagram isn't re-rendered on every preview update
+#[derive(Default)]
+pub struct DiagramState {
+    failures: Mutex<HashMap<String, String>>,
+}
+
+fn cache_key(kind: DiagramKind, theme: DiagramTheme, source: &str) -> String {
+    let theme = match theme {
+        DiagramTheme::Light => "light",
+        DiagramTheme::Dark => "dark",
+    };
+    sha256_hex(format!("{}\n{}\n{}", kind.name(), theme, source).as_bytes())
+}
+
+fn cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(CACHE_DIR))
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))
+}
+
+/// Runs `command` with `input` on stdin, killing it after `RENDER_TIMEOUT`.
+/// Returns stdout.
+fn run(mut command: Command, input: &str) -> Result<Vec<u8>, String> {
+    let program = Path::new(command.get_program())
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    // stdin, stdout and stderr are serviced on their own threads so a large
+    // diagram can't deadlock against a full pipe
+    let mut stdin = child.stdin.take();
+    let input = input.to_string();
+    let writer = std::thread::spawn(move || {
+        if let Some(stdin) = stdin.as_mut() {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+    });
+    let mut stdout = child.stdout.take();
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(stdout) = stdout.as_mut() {
+            let _ = stdout.read_to_end(&mut buf);
+        }
+        buf
+    });
+    let mut stderr = child.stderr.take();
+    let error_reader = std::thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(stderr) = stderr.as_mut() {
+            let _ = stderr.read_to_string(&mut buf);
+        }
+        buf
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() > RENDER_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{} timed out", program));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("Failed to wait for {}: {}", program, e)),
+        }
+    };
+    let _ = writer.join();
+    let output = reader.join().unwrap_or_default();
+    let stderr = error_reader.join().unwrap_or_default();
+    if !status.success() {
+        let message = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+        return Err(format!("{} failed: {}", program, message));
+    }
+    Ok(output)
+}
+
+/// mermaid-cli only reads and writes files
+fn render_mermaid(source: &str, theme: DiagramTheme) -> Result<String, String> {
+    let mmdc = find_executable("mmdc")
+        .ok_or_else(|| "Rendering mermaid diagrams needs mermaid-cli (mmdc) on PATH".to_string())?;
+    let dir = std::env::temp_dir().join(format!("tmd-mermaid-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create temporary directory: {}", e))?;
+    let input = dir.join("diagram.mmd");
+    let output = dir.join("diagram.svg");
+
+    let result = fs::write(&input, source)
+        .map_err(|e| format!("Failed to write temporary file: {}", e))
+        .and_then(|_| {
+            let mut command = Command::new(mmdc);
+            command
+                .arg("--quiet")
+                .arg("--input")
+                .arg(&input)
+                .arg("--output")
+                .arg(&output)
+                .args(["--backgroundColor", "transparent", "--theme"])
+                .arg(match theme {
+                    DiagramTheme::Light => "default",
+                    DiagramTheme::Dark => "dark",
+                });
+            run(command, "")
+        })
+        .and_then(|_| fs::read_to_string(&output).map_err(|e| format!("mmdc produced no SVG: {}", e)));
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+/// The `plantuml` launcher, or `plantuml.jar` from the app's data folder run with java
+fn plantuml_command(app: &AppHandle) -> Option<Command> {
+    if let Some(plantuml) = find_executable("plantuml") {
+        return Some(Command::new(plantuml));
+    }
+    let jar = app.path().app_data_dir().ok()?.join(CACHE_DIR).join("plantuml.jar");
+    let java = find_executable("java").filter(|_| jar.is_file())?;
+    let mut command = Command::new(java);
+    command.arg("-Djava.awt.headless=true").arg("-jar").arg(jar);
+    Some(command)
+}
+
+fn render_plantuml(app: &AppHandle, source: &str, theme: DiagramTheme) -> Result<String, String> {
+    let mut command = plantuml_command(app).ok_or_else(|| {
+        "Rendering PlantUML diagrams needs plantuml on PATH, or java and plantuml.jar in the app's diagrams folder"
+            .to_string()
+    })?;
+    command.args(["-tsvg", "-pipe", "-charset", "UTF-8"]);
+    if theme == DiagramTheme::Dark {
+        command.arg("-darkmode");
+    }
+    // Fences usually leave out the @startuml/@enduml wrapper
+    let source = if source.trim_start().starts_with("@start") {
+        source.to_string()
+    } else {
+        format!("@startuml\n{}\n@enduml\n", source.trim_end())
+    };
+    let svg = run(command, &source)?;
+    String::from_utf8(svg).map_err(|e| format!("plantuml produced invalid UTF-8: {}", e))
+}
+
+/// Renders a diagram to SVG, reusing the cached SVG for the same source and theme
+pub fn render(
+    app: &AppHandle,
+    state: &DiagramState,
+    kind: DiagramKind,
+    source: &str,
+    theme: DiagramTheme,
+) -> Result<RenderedDiagram, String> {
+    let key = cache_key(kind, theme, source);
+    let dir = cache_dir(app)?;
+    let cached = dir.join(format!("{}.svg", key));
+    if let Ok(svg) = fs::read_to_string(&cached) {
+        return Ok(RenderedDiagram { svg, cached: true });
+    }
+    if let Some(error) = state
+        .failures
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .get(&key)
+    {
+        return Err(error.clone());
+    }
+
+    let result = match kind {
+        DiagramKind::Mermaid => render_mermaid(source, theme),
+        DiagramKind::Plantuml => render_plantuml(app, source, theme),
+    };
+    let svg = match result {
+        Ok(svg) => svg,
+        Err(e) => {
+            if let Ok(mut failures) = state.failures.lock() {
+                failures.insert(key, e.clone());
+            }
+            return Err(e);
+        }
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    if let Err(e) = atomic_write::write_atomic(&cached, svg.as_bytes()) {
+        tracing::warn!("Failed to cache {}: {}", cached.display(), e);
+    }
+    Ok(RenderedDiagram { svg, cached: false })
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Replaces mermaid and PlantUML code blocks in rendered markdown with their
+/// SVG. Blocks that fail to render are kept as code with the error attached.
+pub fn render_in_html(app: &AppHandle, state: &DiagramState, html: &str, theme: DiagramTheme) -> String {
+    const OPEN: &str = "<pre><code class=\"language-";
+    const CLOSE: &str = "</code></pre>";
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(OPEN) {
+        let after = &rest[start + OPEN.len()..];
+        let Some(quote) = after.find("\">") else {
+            break;
+        };
+        let Some(end) = after.find(CLOSE) else {
+            break;
+        };
+        let block_end = start + OPEN.len() + end + CLOSE.len();
+        out.push_str(&rest[..start]);
+        let Some(kind) = DiagramKind::from_fence(&after[..quote]) else {
+            out.push_str(&rest[start..block_end]);
+            rest = &rest[block_end..];
+            continue;
+        };
+
+        let source = unescape_html(&after[quote + 2..end]);
+        match render(app, state, kind, &source, theme) {
+            Ok(diagram) => out.push_str(&format!(
+                "<div class=\"diagram diagram-{}\">{}</div>",
+                kind.name(),
+                diagram.svg.trim()
+            )),
+            Err(e) => {
+                out.push_str(&rest[start..block_end]);
+                out.push_str(&format!("<p class=\"diagram-error\">{}</p>", escape_html(&e)));
+            }
+        }
+        rest = &rest[block_end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The SVG for one mermaid or PlantUML diagram, cached by content hash
+#[tauri::command]
+pub async fn render_diagram(
+    app_handle: AppHandle,
+    kind: DiagramKind,
+    source: String,
+    theme: Option<DiagramTheme>,
+) -> Result<RenderedDiagram, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app_handle.state::<DiagramState>();
+        render(&app_handle, &state, kind, &source, theme.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("Diagram task failed: {}", e))?
+}
+
+/// Deletes every cached SVG and forgets past failures, e.g. after
+/// installing or upgrading a renderer
+#[tauri::command]
+pub async fn clear_diagram_cache(app_handle: AppHandle, state: State<'_, DiagramState>) -> Result<(), String> {
+    state
+        .failures
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .clear();
+    let dir = cache_dir(&app_handle)?;
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| format!("Failed to clear diagram cache: {}", e))?;
+    }
+    Ok(())
+}
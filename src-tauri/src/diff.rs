@@ -0,0 +1,320 @@
+use std::fs;
+
+use git2::Patch;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::encoding;
+use crate::remote::{self, RemoteState};
+
+const DEFAULT_CONTEXT_LINES: u32 = 3;
+// Word refinement compares token sequences pairwise; longer lines are
+// highlighted as a whole instead
+const MAX_WORD_DIFF_CELLS: usize = 250_000;
+
+/// One side of a comparison: buffer contents, or a file to read
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DiffInput {
+    /// Takes precedence over `path`, e.g. for unsaved buffers
+    pub content: Option<String>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+    #[default]
+    Myers,
+    Patience,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiffRequestOptions {
+    pub algorithm: DiffAlgorithm,
+    /// Unchanged lines kept around each hunk (default 3)
+    pub context_lines: Option<u32>,
+    pub ignore_whitespace: bool,
+    /// Highlight the changed words within modified lines (default true)
+    pub word_diff: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// A changed range within a line, in UTF-16 code units like JS string offsets
+#[derive(Debug, Clone, Serialize)]
+pub struct WordRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// 1-based; None for added lines
+    pub old_line: Option<u32>,
+    /// 1-based; None for removed lines
+    pub new_line: Option<u32>,
+    /// Without the line break
+    pub content: String,
+    /// For lines paired with a counterpart on the other side; empty otherwise
+    pub changes: Vec<WordRange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffResult {
+    pub hunks: Vec<DiffHunk>,
+    pub added: usize,
+    pub removed: usize,
+    pub identical: bool,
+    /// Either side contains NUL bytes; no hunks are computed
+    pub binary: bool,
+}
+
+async fn load(state: &RemoteState, input: DiffInput, side: &str) -> Result<String, String> {
+    if let Some(content) = input.content {
+        return Ok(content);
+    }
+    let path = input
+        .path
+        .ok_or_else(|| format!("No content or path given for the {} side", side))?;
+    let bytes = if remote::is_remote(&path) {
+        remote::read_file(state, &path).await?
+    } else {
+        fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?
+    };
+    Ok(encoding::decode(&bytes, None).content)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TokenClass {
+    Word,
+    Space,
+    Other,
+}
+
+/// Splits a line into words, whitespace runs and single punctuation characters
+fn tokenize(line: &str) -> Vec<&str> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            TokenClass::Word
+        } else if c.is_whitespace() {
+            TokenClass::Space
+        } else {
+            TokenClass::Other
+        }
+    };
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    for (i, c) in line.char_indices() {
+        let current = class(c);
+        if i > start && (previous != Some(current) || current == TokenClass::Other) {
+            tokens.push(&line[start..i]);
+            start = i;
+        }
+        previous = Some(current);
+    }
+    if start < line.len() {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+/// Marks which tokens of each side are not part of their longest common subsequence
+fn unmatched(old: &[&str], new: &[&str]) -> (Vec<bool>, Vec<bool>) {
+    let (n, m) = (old.len(), new.len());
+    let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[at(i, j)] = if old[i] == new[j] {
+                lengths[at(i + 1, j + 1)] + 1
+            } else {
+                lengths[at(i + 1, j)].max(lengths[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut old_changed = vec![true; n];
+    let mut new_changed = vec![true; m];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            old_changed[i] = false;
+            new_changed[j] = false;
+            i += 1;
+            j += 1;
+        } else if lengths[at(i + 1, j)] >= lengths[at(i, j + 1)] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    (old_changed, new_changed)
+}
+
+/// Merges the changed tokens into UTF-16 ranges
+fn ranges(tokens: &[&str], changed: &[bool]) -> Vec<WordRange> {
+    let mut ranges: Vec<WordRange> = Vec::new();
+    let mut offset = 0;
+    for (token, changed) in tokens.iter().zip(changed) {
+        let len = token.encode_utf16().count();
+        if *changed {
+            match ranges.last_mut() {
+                Some(last) if last.end == offset => last.end += len,
+                _ => ranges.push(WordRange {
+                    start: offset,
+                    end: offset + len,
+                }),
+            }
+        }
+        offset += len;
+    }
+    ranges
+}
+
+fn refine_pair(old: &str, new: &str) -> (Vec<WordRange>, Vec<WordRange>) {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    if old_tokens.len().saturating_mul(new_tokens.len()) > MAX_WORD_DIFF_CELLS {
+        let whole = |s: &str| {
+            let len = s.encode_utf16().count();
+            if len == 0 { Vec::new() } else { vec![WordRange { start: 0, end: len }] }
+        };
+        return (whole(old), whole(new));
+    }
+    let (old_changed, new_changed) = unmatched(&old_tokens, &new_tokens);
+    (ranges(&old_tokens, &old_changed), ranges(&new_tokens, &new_changed))
+}
+
+/// Pairs each run of removed lines with the added lines that follow it, in order
+fn refine_words(lines: &mut [DiffLine]) {
+    let mut i = 0;
+    while i < lines.len() {
+        let removed_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Removed {
+            i += 1;
+        }
+        let added_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Added {
+            i += 1;
+        }
+        let pairs = (added_start - removed_start).min(i - added_start);
+        for k in 0..pairs {
+            let (old, new) = refine_pair(&lines[removed_start + k].content, &lines[added_start + k].content);
+            lines[removed_start + k].changes = old;
+            lines[added_start + k].changes = new;
+        }
+        if i == removed_start {
+            i += 1;
+        }
+    }
+}
+
+fn compute(left: &str, right: &str, options: &DiffRequestOptions) -> Result<DiffResult, String> {
+    if left.contains('\0') || right.contains('\0') {
+        return Ok(DiffResult {
+            hunks: Vec::new(),
+            added: 0,
+            removed: 0,
+            identical: left == right,
+            binary: true,
+        });
+    }
+
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(options.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES))
+        .force_text(true)
+        .patience(options.algorithm == DiffAlgorithm::Patience)
+        .ignore_whitespace(options.ignore_whitespace);
+    let patch = Patch::from_buffers(left.as_bytes(), None, right.as_bytes(), None, Some(&mut opts))
+        .map_err(|e| format!("Failed to compute diff: {}", e.message()))?;
+
+    let mut hunks = Vec::with_capacity(patch.num_hunks());
+    let (mut added, mut removed) = (0, 0);
+    for h in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch
+            .hunk(h)
+            .map_err(|e| format!("Failed to read diff hunk: {}", e.message()))?;
+        let mut lines = Vec::with_capacity(line_count);
+        for l in 0..line_count {
+            let line = patch
+                .line_in_hunk(h, l)
+                .map_err(|e| format!("Failed to read diff line: {}", e.message()))?;
+            let kind = match line.origin() {
+                ' ' => DiffLineKind::Context,
+                '+' => DiffLineKind::Added,
+                '-' => DiffLineKind::Removed,
+                // "\ No newline at end of file" markers
+                _ => continue,
+            };
+            match kind {
+                DiffLineKind::Added => added += 1,
+                DiffLineKind::Removed => removed += 1,
+                DiffLineKind::Context => {}
+            }
+            let content = String::from_utf8_lossy(line.content());
+            let content = content.strip_suffix('\n').unwrap_or(&content);
+            let content = content.strip_suffix('\r').unwrap_or(content);
+            lines.push(DiffLine {
+                kind,
+                old_line: line.old_lineno(),
+                new_line: line.new_lineno(),
+                content: content.to_string(),
+                changes: Vec::new(),
+            });
+        }
+        if options.word_diff.unwrap_or(true) {
+            refine_words(&mut lines);
+        }
+        hunks.push(DiffHunk {
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines,
+        });
+    }
+
+    Ok(DiffResult {
+        identical: hunks.is_empty(),
+        hunks,
+        added,
+        removed,
+        binary: false,
+    })
+}
+
+/// Compares two files or buffers (local or remote) line by line, refining
+/// modified lines down to the words that changed
+#[tauri::command]
+pub async fn compute_diff(
+    remote_state: State<'_, RemoteState>,
+    left: DiffInput,
+    right: DiffInput,
+    options: Option<DiffRequestOptions>,
+) -> Result<DiffResult, String> {
+    let left = load(&remote_state, left, "left").await?;
+    let right = load(&remote_state, right, "right").await?;
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || compute(&left, &right, &options))
+        .await
+        .map_err(|e| format!("Diff task failed: {}", e))?
+}
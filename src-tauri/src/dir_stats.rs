@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ignore::WalkBuilder;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::fs_guard::FsGuardState;
+
+// Minimum interval between two progress events
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Cumulative totals, emitted on `directory-stats-{stats_id}`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DirectoryStats {
+    pub path: String,
+    pub files: u64,
+    pub directories: u64,
+    /// Apparent size of all regular files; symlinks are counted but not followed
+    pub bytes: u64,
+    pub symlinks: u64,
+    /// Entries that could not be read (permissions, races with deletion)
+    pub errors: u64,
+    pub done: bool,
+    pub cancelled: bool,
+}
+
+#[derive(Default)]
+pub struct DirStatsState {
+    jobs: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+fn walk(app: &AppHandle, event: &str, root: &Path, cancelled: &AtomicBool) -> DirectoryStats {
+    let mut stats = DirectoryStats {
+        path: root.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let mut last_emit = Instant::now();
+
+    // Everything counts here, including hidden and gitignored files
+    let walker = WalkBuilder::new(root).standard_filters(false).follow_links(false).build();
+    for entry in walker {
+        if cancelled.load(Ordering::Relaxed) {
+            stats.cancelled = true;
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => {
+                stats.errors += 1;
+                continue;
+            }
+        };
+        // The root itself isn't part of its own contents
+        if entry.depth() == 0 {
+            continue;
+        }
+        match entry.file_type() {
+            Some(t) if t.is_dir() => stats.directories += 1,
+            Some(t) if t.is_symlink() => stats.symlinks += 1,
+            Some(_) => match entry.metadata() {
+                Ok(metadata) => {
+                    stats.files += 1;
+                    stats.bytes += metadata.len();
+                }
+                Err(_) => stats.errors += 1,
+            },
+            None => stats.errors += 1,
+        }
+
+        if last_emit.elapsed() >= UPDATE_INTERVAL {
+            let _ = app.emit(event, stats.clone());
+            last_emit = Instant::now();
+        }
+    }
+
+    stats.done = true;
+    stats
+}
+
+/// Starts measuring a folder in the background. Running totals are emitted on
+/// `directory-stats-{stats_id}`; the last event has `done` set.
+#[tauri::command]
+pub async fn compute_directory_stats(
+    app_handle: AppHandle,
+    state: State<'_, DirStatsState>,
+    guard: State<'_, FsGuardState>,
+    stats_id: String,
+    path: String,
+) -> Result<(), String> {
+    let root = guard.check(&path)?;
+    if !root.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut jobs = state.jobs.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        // A new request with the same id supersedes the old one
+        if let Some(old) = jobs.insert(stats_id.clone(), cancelled.clone()) {
+            old.store(true, Ordering::Relaxed);
+        }
+    }
+
+    thread::spawn(move || {
+        let event = format!("directory-stats-{}", stats_id);
+        let stats = walk(&app_handle, &event, &root, &cancelled);
+
+        if let Ok(mut jobs) = app_handle.state::<DirStatsState>().jobs.lock() {
+            if jobs.get(&stats_id).is_some_and(|flag| Arc::ptr_eq(flag, &cancelled)) {
+                jobs.remove(&stats_id);
+            }
+        }
+        let _ = app_handle.emit(&event, stats);
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_directory_stats(state: State<'_, DirStatsState>, stats_id: String) -> Result<(), String> {
+    let mut jobs = state.jobs.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(flag) = jobs.remove(&stats_id) {
+        flag.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
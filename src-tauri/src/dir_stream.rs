@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::fs_guard::FsGuardState;
+use crate::{file_entry, ignored_names, FileEntry};
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// One page of a listing, emitted on `directory-batch-{stream_id}`
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryBatch {
+    pub path: String,
+    /// In directory order; sorting is up to the receiver
+    pub entries: Vec<FileEntry>,
+    /// Entries sent so far, including this batch
+    pub total: usize,
+    pub done: bool,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct DirStreamState {
+    streams: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+struct StreamOptions {
+    show_hidden: bool,
+    hide_ignored: bool,
+    /// Lowercased; matched against entry names
+    filter: Option<String>,
+    batch_size: usize,
+}
+
+/// Reads `dir` and emits it in batches, all but the last; returns that one
+/// and the number of entries in all of them
+fn stream(
+    app: &AppHandle,
+    event: &str,
+    dir: &Path,
+    options: &StreamOptions,
+    cancelled: &AtomicBool,
+) -> Result<(Vec<FileEntry>, usize), String> {
+    let path = dir.to_string_lossy().to_string();
+    let ignored = ignored_names(dir);
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+    let mut batch = Vec::with_capacity(options.batch_size);
+    let mut total = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err("Listing was cancelled".to_string());
+        }
+        if let Some(filter) = &options.filter {
+            if !entry.file_name().to_string_lossy().to_lowercase().contains(filter) {
+                continue;
+            }
+        }
+        let Some(entry) = file_entry(&entry, &ignored, options.show_hidden, options.hide_ignored) else {
+            continue;
+        };
+        batch.push(entry);
+        if batch.len() >= options.batch_size {
+            total += batch.len();
+            let entries = std::mem::replace(&mut batch, Vec::with_capacity(options.batch_size));
+            let _ = app.emit(
+                event,
+                DirectoryBatch {
+                    path: path.clone(),
+                    entries,
+                    total,
+                    done: false,
+                    cancelled: false,
+                    error: None,
+                },
+            );
+        }
+    }
+    total += batch.len();
+    Ok((batch, total))
+}
+
+/// Lists a folder in the background, for folders too large for
+/// `read_directory`. Entries arrive in batches of `batch_size` on
+/// `directory-batch-{stream_id}`; the last one has `done` set. `filter`
+/// keeps only names containing it, ignoring case.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn read_directory_stream(
+    app_handle: AppHandle,
+    state: State<'_, DirStreamState>,
+    guard: State<'_, FsGuardState>,
+    stream_id: String,
+    path: String,
+    show_hidden: Option<bool>,
+    hide_ignored: Option<bool>,
+    filter: Option<String>,
+    batch_size: Option<usize>,
+) -> Result<(), String> {
+    let dir = guard.check(&path)?;
+    if !dir.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let options = StreamOptions {
+        show_hidden: show_hidden.unwrap_or(true),
+        hide_ignored: hide_ignored.unwrap_or(false),
+        filter: filter.map(|f| f.trim().to_lowercase()).filter(|f| !f.is_empty()),
+        batch_size: batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1),
+    };
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut streams = state.streams.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        // A new request with the same id supersedes the old one
+        if let Some(old) = streams.insert(stream_id.clone(), cancelled.clone()) {
+            old.store(true, Ordering::Relaxed);
+        }
+    }
+
+    thread::spawn(move || {
+        let event = format!("directory-batch-{}", stream_id);
+        let result = stream(&app_handle, &event, &dir, &options, &cancelled);
+
+        let state = app_handle.state::<DirStreamState>();
+        let Ok(mut streams) = state.streams.lock() else {
+            return;
+        };
+        // A newer request with the same id owns the event now, and its
+        // listener must not see this one finish. Emitting under the lock
+        // keeps the next request from starting in between.
+        match streams.get(&stream_id) {
+            Some(flag) if !Arc::ptr_eq(flag, &cancelled) => return,
+            Some(_) => {
+                streams.remove(&stream_id);
+            }
+            None => {}
+        }
+        let batch = match result {
+            Ok((entries, total)) => DirectoryBatch {
+                path,
+                entries,
+                total,
+                done: true,
+                cancelled: false,
+                error: None,
+            },
+            Err(e) => {
+                let cancelled = cancelled.load(Ordering::Relaxed);
+                DirectoryBatch {
+                    path,
+                    entries: Vec::new(),
+                    total: 0,
+                    done: true,
+                    cancelled,
+                    error: (!cancelled).then_some(e),
+                }
+            }
+        };
+        let _ = app_handle.emit(&event, batch);
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_directory_stream(state: State<'_, DirStreamState>, stream_id: String) -> Result<(), String> {
+    let mut streams = state.streams.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(flag) = streams.remove(&stream_id) {
+        flag.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{AppHandle, DragDropEvent, Emitter, Manager, State, Window, WindowEvent};
+
+use crate::file_ops::{copy_with_strategy, measure, ConflictStrategy, ProgressReporter};
+use crate::fs_guard::{self, FsGuardState};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DroppedItem {
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    /// File size in bytes, 0 for directories
+    pub size: u64,
+}
+
+/// Payload of the `file-drop` event
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDropEvent {
+    /// "enter", "over", "drop" or "leave"
+    pub kind: &'static str,
+    pub items: Vec<DroppedItem>,
+    /// Cursor position in logical pixels, relative to the window
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub source: String,
+    pub destination: Option<String>,
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+/// Absolute, symlink-free path without the Windows verbatim prefix
+fn normalize(path: &Path) -> PathBuf {
+    let resolved = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let text = resolved.to_string_lossy();
+    match text.strip_prefix(r"\\?\") {
+        Some(stripped) if !stripped.starts_with("UNC") => PathBuf::from(stripped),
+        _ => resolved,
+    }
+}
+
+fn describe(path: &Path) -> Option<DroppedItem> {
+    let link_metadata = fs::symlink_metadata(path).ok()?;
+    let resolved = normalize(path);
+    let metadata = fs::metadata(&resolved).unwrap_or(link_metadata.clone());
+    Some(DroppedItem {
+        name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        path: resolved.to_string_lossy().to_string(),
+        is_dir: metadata.is_dir(),
+        is_symlink: link_metadata.file_type().is_symlink(),
+        size: if metadata.is_dir() { 0 } else { metadata.len() },
+    })
+}
+
+/// Forwards OS file drags on a window as `file-drop` events with resolved
+/// paths, so the frontend doesn't have to stat each one
+pub fn handle_window_event(window: &Window, event: &WindowEvent) {
+    let WindowEvent::DragDrop(drag) = event else {
+        return;
+    };
+    let scale = window.scale_factor().unwrap_or(1.0);
+    let (kind, paths, position) = match drag {
+        DragDropEvent::Enter { paths, position } => ("enter", paths.as_slice(), Some(*position)),
+        DragDropEvent::Over { position } => ("over", &[][..], Some(*position)),
+        DragDropEvent::Drop { paths, position } => ("drop", paths.as_slice(), Some(*position)),
+        DragDropEvent::Leave => ("leave", &[][..], None),
+        _ => return,
+    };
+    let position = position.map(|p| p.to_logical::<f64>(scale));
+    // Dropping is the user handing these paths to the app; folders are
+    // permitted whole but don't become workspace roots
+    if kind == "drop" {
+        for path in paths {
+            fs_guard::grant_file(window.app_handle(), path);
+        }
+    }
+    let payload = FileDropEvent {
+        kind,
+        items: paths.iter().filter_map(|p| describe(p)).collect(),
+        x: position.map(|p| p.x).unwrap_or_default(),
+        y: position.map(|p| p.y).unwrap_or_default(),
+    };
+    let _ = window.emit("file-drop", payload);
+}
+
+/// Copies each path into `target`, collecting per-item results
+pub(crate) fn import_paths(
+    paths: Vec<PathBuf>,
+    target: &Path,
+    strategy: ConflictStrategy,
+    reporter: &mut ProgressReporter,
+) -> Vec<ImportResult> {
+    for path in &paths {
+        let (files, bytes) = measure(path);
+        reporter.add_totals(files, bytes);
+    }
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let source = normalize(&path);
+            let path = path.to_string_lossy().to_string();
+            let Some(name) = source.file_name() else {
+                return ImportResult {
+                    source: path,
+                    destination: None,
+                    skipped: false,
+                    error: Some("Cannot import a filesystem root".to_string()),
+                };
+            };
+            match copy_with_strategy(&source, &target.join(name), strategy, reporter) {
+                Ok(result) => ImportResult {
+                    source: path,
+                    destination: Some(result.destination),
+                    skipped: result.skipped,
+                    error: None,
+                },
+                Err(e) => ImportResult {
+                    source: path,
+                    destination: None,
+                    skipped: false,
+                    error: Some(e),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Copies dropped files and folders into `target_dir`. Name clashes get a
+/// `name (1).ext` copy unless another strategy is given; one failure doesn't
+/// stop the rest. Progress goes to `file-operation-progress-{operation_id}`.
+#[tauri::command]
+pub async fn import_files_into_workspace(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    paths: Vec<String>,
+    target_dir: String,
+    strategy: Option<ConflictStrategy>,
+    operation_id: Option<String>,
+) -> Result<Vec<ImportResult>, String> {
+    let target = guard.check(&target_dir)?;
+    let paths = paths
+        .iter()
+        .map(|path| guard.check(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !target.is_dir() {
+        return Err("Target is not a directory".to_string());
+    }
+    let strategy = strategy.unwrap_or(ConflictStrategy::Rename);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut reporter = ProgressReporter::new(
+            operation_id.as_ref().map(|_| app_handle),
+            operation_id.unwrap_or_default(),
+        );
+        let results = import_paths(paths, &target, strategy, &mut reporter);
+        reporter.finish();
+        results
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))
+}
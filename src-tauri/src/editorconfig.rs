@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use globset::GlobBuilder;
+
+const EDITORCONFIG_FILE: &str = ".editorconfig";
+const KNOWN_PROPERTIES: &[&str] = &[
+    "indent_style",
+    "indent_size",
+    "tab_width",
+    "end_of_line",
+    "charset",
+    "trim_trailing_whitespace",
+    "insert_final_newline",
+];
+
+/// Resolved EditorConfig properties for one file; names and the values of
+/// the standard properties are lowercased
+#[derive(Debug, Default, Clone)]
+pub struct EditorConfig {
+    pub properties: HashMap<String, String>,
+}
+
+impl EditorConfig {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str).filter(|v| *v != "unset")
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn get_usize(&self, key: &str) -> Option<usize> {
+        self.get(key)?.parse().ok().filter(|n| *n > 0)
+    }
+}
+
+struct Section {
+    pattern: String,
+    properties: Vec<(String, String)>,
+}
+
+/// Parses the INI-style file; returns whether it declares `root = true`
+fn parse(text: &str) -> (bool, Vec<Section>) {
+    let mut root = false;
+    let mut sections: Vec<Section> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(pattern) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push(Section {
+                pattern: pattern.to_string(),
+                properties: Vec::new(),
+            });
+            continue;
+        }
+        let Some((key, value)) = line.split_once(['=', ':']) else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_string();
+        match sections.last_mut() {
+            Some(section) => {
+                // Values are case-insensitive except for unknown properties
+                let value = if KNOWN_PROPERTIES.contains(&key.as_str()) { value.to_lowercase() } else { value };
+                section.properties.push((key, value));
+            }
+            None if key == "root" => root = value.eq_ignore_ascii_case("true"),
+            None => {}
+        }
+    }
+    (root, sections)
+}
+
+/// Whether a section glob applies to `relative`, the file's path relative to
+/// the directory of the `.editorconfig`
+fn matches(pattern: &str, relative: &str) -> bool {
+    // Globs without a slash match the file name at any depth
+    let pattern = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{}", pattern),
+    };
+    GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher().is_match(relative))
+        .unwrap_or(false)
+}
+
+/// Collects the properties for `path` from every `.editorconfig` between its
+/// folder and the nearest one marked `root = true`; closer files win
+pub fn resolve(path: &Path) -> EditorConfig {
+    let mut files = Vec::new();
+    for dir in path.ancestors().skip(1) {
+        let Ok(text) = fs::read_to_string(dir.join(EDITORCONFIG_FILE)) else {
+            continue;
+        };
+        let (root, sections) = parse(&text);
+        files.push((dir.to_path_buf(), sections));
+        if root {
+            break;
+        }
+    }
+
+    let mut config = EditorConfig::default();
+    // Apply from the outermost file inwards so later assignments override
+    for (dir, sections) in files.iter().rev() {
+        let Ok(relative) = path.strip_prefix(dir) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        for section in sections.iter().filter(|s| matches(&s.pattern, &relative)) {
+            for (key, value) in &section.properties {
+                config.properties.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    // indent_size = tab means "use tab_width", and tab_width defaults to indent_size
+    if config.get("indent_size") == Some("tab") {
+        if let Some(width) = config.properties.get("tab_width").cloned() {
+            config.properties.insert("indent_size".to_string(), width);
+        }
+    }
+    if config.get("tab_width").is_none() {
+        if let Some(size) = config.get_usize("indent_size") {
+            config.properties.insert("tab_width".to_string(), size.to_string());
+        }
+    }
+    config
+}
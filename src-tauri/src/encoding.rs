@@ -0,0 +1,114 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde::Serialize;
+use tauri::State;
+
+use crate::fs_guard::FsGuardState;
+use crate::line_endings::{self, LineEnding};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedText {
+    pub content: String,
+    /// Canonical WHATWG name, e.g. "UTF-8", "GBK", "Shift_JIS", "windows-1252"
+    pub encoding: String,
+    pub has_bom: bool,
+    /// True if some bytes could not be decoded and were replaced with U+FFFD
+    pub had_errors: bool,
+    /// Prevailing line ending; None when the text has no line breaks
+    pub line_ending: Option<LineEnding>,
+    /// True if both LF and CRLF occur
+    pub mixed_line_endings: bool,
+}
+
+/// Looks up an encoding by any of its WHATWG labels ("utf8", "gb2312", "latin1", ...)
+pub fn encoding_for_label(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| format!("Unknown encoding: {}", label))
+}
+
+/// Guesses the encoding of `bytes`: a BOM wins, then valid UTF-8, then a statistical guess
+pub fn detect_encoding(bytes: &[u8]) -> (&'static Encoding, bool) {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return (encoding, true);
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return (UTF_8, false);
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    (detector.guess(None, true), false)
+}
+
+/// Decodes `bytes`, detecting the encoding unless one is forced
+pub fn decode(bytes: &[u8], forced: Option<&'static Encoding>) -> DecodedText {
+    let (encoding, has_bom) = match forced {
+        Some(encoding) => {
+            let has_bom = Encoding::for_bom(bytes).is_some_and(|(e, _)| e == encoding);
+            (encoding, has_bom)
+        }
+        None => detect_encoding(bytes),
+    };
+
+    let body = if has_bom {
+        let bom_len = Encoding::for_bom(bytes).map(|(_, len)| len).unwrap_or(0);
+        &bytes[bom_len..]
+    } else {
+        bytes
+    };
+    let (content, had_errors) = encoding.decode_without_bom_handling(body);
+    let line_endings = line_endings::count(&content);
+
+    DecodedText {
+        content: content.into_owned(),
+        encoding: encoding.name().to_string(),
+        has_bom,
+        had_errors,
+        line_ending: line_endings.dominant(),
+        mixed_line_endings: line_endings.is_mixed(),
+    }
+}
+
+/// Encodes `content` in `encoding`, optionally prefixed with a BOM.
+/// Fails if the text contains characters the encoding cannot represent.
+pub fn encode(content: &str, encoding: &'static Encoding, bom: bool) -> Result<Vec<u8>, String> {
+    // encoding_rs only decodes UTF-16, so encode it by hand
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let mut bytes = Vec::with_capacity(content.len() * 2 + 2);
+        if bom {
+            bytes.extend_from_slice(if encoding == UTF_16LE { &[0xFF, 0xFE] } else { &[0xFE, 0xFF] });
+        }
+        for unit in content.encode_utf16() {
+            let pair = if encoding == UTF_16LE { unit.to_le_bytes() } else { unit.to_be_bytes() };
+            bytes.extend_from_slice(&pair);
+        }
+        return Ok(bytes);
+    }
+
+    let (encoded, actual, had_errors) = encoding.encode(content);
+    if had_errors || actual != encoding {
+        return Err(format!("Content contains characters that cannot be saved as {}", encoding.name()));
+    }
+
+    let mut bytes = Vec::with_capacity(encoded.len() + 3);
+    if bom && encoding == UTF_8 {
+        bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+/// Reads a text file in any encoding. Pass `encoding` to reopen with a specific one.
+#[tauri::command]
+pub async fn read_file_with_encoding(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    encoding: Option<String>,
+) -> Result<DecodedText, String> {
+    let path = guard.check(&path)?;
+    let forced = match encoding {
+        Some(label) => Some(encoding_for_label(&label)?),
+        None => None,
+    };
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(decode(&bytes, forced))
+}
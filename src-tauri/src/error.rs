@@ -0,0 +1,138 @@
+//! Errors returned by commands. They serialize as an object tagged with a
+//! `code` (`not_found`, `permission_denied`, ...) next to a `message` that
+//! can be shown as is, plus the OS error number when there is one, e.g.
+//! `{"code": "not_found", "message": "Failed to read file: ...", "os_error": 2}`.
+
+use std::fmt;
+use std::io;
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum AppError {
+    /// A file, folder, session or server that doesn't exist
+    NotFound {
+        message: String,
+        os_error: Option<i32>,
+    },
+    PermissionDenied {
+        message: String,
+        os_error: Option<i32>,
+    },
+    AlreadyExists {
+        message: String,
+        os_error: Option<i32>,
+    },
+    /// The arguments themselves are wrong; retrying won't help
+    InvalidInput {
+        message: String,
+    },
+    /// Not available on this platform
+    Unsupported {
+        message: String,
+    },
+    /// Any other failure reported by the OS
+    Io {
+        message: String,
+        os_error: Option<i32>,
+    },
+    /// Everything without a more specific code
+    Failed {
+        message: String,
+    },
+}
+
+impl AppError {
+    /// `context: error`, coded by the error's kind
+    pub fn io(context: &str, error: io::Error) -> Self {
+        let message = format!("{}: {}", context, error);
+        let os_error = error.raw_os_error();
+        match error.kind() {
+            io::ErrorKind::NotFound => AppError::NotFound { message, os_error },
+            io::ErrorKind::PermissionDenied => AppError::PermissionDenied { message, os_error },
+            io::ErrorKind::AlreadyExists => AppError::AlreadyExists { message, os_error },
+            io::ErrorKind::InvalidInput => AppError::InvalidInput { message },
+            _ => AppError::Io { message, os_error },
+        }
+    }
+
+    /// Like `io`, for the `trash` crate's errors
+    pub fn trash(context: &str, error: trash::Error) -> Self {
+        match error {
+            trash::Error::Os { code, .. } => AppError::io(context, io::Error::from_raw_os_error(code)),
+            #[cfg(all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))]
+            trash::Error::FileSystem { source, .. } => AppError::io(context, source),
+            trash::Error::RestoreCollision { path, .. } => AppError::AlreadyExists {
+                message: format!("{}: {} already exists", context, path.display()),
+                os_error: None,
+            },
+            error => AppError::Io {
+                message: format!("{}: {}", context, error),
+                os_error: None,
+            },
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound {
+            message: message.into(),
+            os_error: None,
+        }
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        AppError::PermissionDenied {
+            message: message.into(),
+            os_error: None,
+        }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        AppError::InvalidInput {
+            message: message.into(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound { message, .. }
+            | AppError::PermissionDenied { message, .. }
+            | AppError::AlreadyExists { message, .. }
+            | AppError::InvalidInput { message }
+            | AppError::Unsupported { message }
+            | AppError::Io { message, .. }
+            | AppError::Failed { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// Helpers that still report plain strings end up as `Failed`
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Failed { message }
+    }
+}
+
+/// For callers that haven't moved off `String` errors yet
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::NotFound { message, .. }
+            | AppError::PermissionDenied { message, .. }
+            | AppError::AlreadyExists { message, .. }
+            | AppError::InvalidInput { message }
+            | AppError::Unsupported { message }
+            | AppError::Io { message, .. }
+            | AppError::Failed { message } => message,
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+use crate::atomic_write;
+use crate::diagrams::{self, DiagramState, DiagramTheme};
+use crate::fs_guard::{FsGuardState, Scope};
+use crate::lsp::registry::find_executable;
+use crate::markdown::{self, escape_html, RenderOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Html,
+    Pdf,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportTheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum PageSize {
+    #[default]
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PageSize {
+    fn css(self) -> &'static str {
+        match self {
+            PageSize::A4 => "A4",
+            PageSize::A5 => "A5",
+            PageSize::Letter => "letter",
+            PageSize::Legal => "legal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ExportOptions {
+    /// Defaults to the document path with an .html/.pdf extension
+    pub output_path: Option<String>,
+    pub theme: ExportTheme,
+    pub page_size: PageSize,
+    /// Page margin in millimetres, PDF only
+    pub margin_mm: Option<u32>,
+    /// Defaults to the document's file name
+    pub title: Option<String>,
+    /// Headless browser used for PDF output; found on PATH if not set
+    pub browser_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportProgress {
+    pub path: String,
+    pub stage: String,
+    /// 0.0 ..= 1.0
+    pub progress: f32,
+}
+
+const LIGHT_THEME: &str = r#"
+body { color: #24292f; background: #ffffff; }
+a { color: #0969da; }
+code, pre { background: #f6f8fa; }
+blockquote { color: #57606a; border-left-color: #d0d7de; }
+th, td { border-color: #d0d7de; }
+hr { border-color: #d0d7de; }
+.tok-keyword { color: #cf222e; } .tok-string { color: #0a3069; } .tok-comment { color: #6e7781; font-style: italic; }
+.tok-number, .tok-constant { color: #0550ae; } .tok-type { color: #953800; } .tok-function { color: #8250df; }
+"#;
+
+const DARK_THEME: &str = r#"
+body { color: #c9d1d9; background: #0d1117; }
+a { color: #58a6ff; }
+code, pre { background: #161b22; }
+blockquote { color: #8b949e; border-left-color: #30363d; }
+th, td { border-color: #30363d; }
+hr { border-color: #30363d; }
+.tok-keyword { color: #ff7b72; } .tok-string { color: #a5d6ff; } .tok-comment { color: #8b949e; font-style: italic; }
+.tok-number, .tok-constant { color: #79c0ff; } .tok-type { color: #ffa657; } .tok-function { color: #d2a8ff; }
+"#;
+
+const BASE_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; line-height: 1.6; max-width: 860px; margin: 0 auto; padding: 32px; }
+pre { padding: 12px 16px; overflow: auto; border-radius: 6px; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 0.9em; padding: 0.1em 0.3em; border-radius: 4px; }
+pre code { padding: 0; background: transparent; }
+blockquote { margin: 0; padding: 0 1em; border-left: 4px solid; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid; padding: 6px 12px; }
+img { max-width: 100%; }
+hr { border: 0; border-top: 1px solid; }
+.task-list-item { list-style: none; }
+.footnotes { font-size: 0.9em; }
+.diagram { margin: 1em 0; text-align: center; } .diagram svg { max-width: 100%; height: auto; }
+.diagram-error { color: #cf222e; font-size: 0.9em; }
+math[display="block"] { margin: 1em 0; overflow-x: auto; }
+@media print { body { max-width: none; padding: 0; } pre { white-space: pre-wrap; } }
+"#;
+
+pub(crate) fn emit_progress(app_handle: &AppHandle, path: &str, stage: &str, progress: f32) {
+    let _ = app_handle.emit(
+        "export-progress",
+        ExportProgress {
+            path: path.to_string(),
+            stage: stage.to_string(),
+            progress,
+        },
+    );
+}
+
+fn mime_for(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    Some(match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        _ => return None,
+    })
+}
+
+/// Local file an `<img src>` refers to, resolved against the document's folder
+fn local_image_path(src: &str, base_dir: &Path) -> Option<PathBuf> {
+    if src.contains("://") || src.starts_with("data:") || src.starts_with('#') {
+        return None;
+    }
+    let src = src.split(['?', '#']).next().unwrap_or(src);
+    let decoded = src.replace("%20", " ");
+    let path = Path::new(&decoded);
+    let full = if path.is_absolute() { path.to_path_buf() } else { base_dir.join(path) };
+    full.is_file().then_some(full)
+}
+
+/// Replaces local image references with data URIs so the HTML is self-contained
+/// Images outside `scope` are left as links
+fn inline_images(html: &str, base_dir: &Path, scope: &Scope, mut on_image: impl FnMut(usize, usize)) -> String {
+    const MARKER: &str = "<img src=\"";
+    let total = html.matches(MARKER).count();
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut done = 0;
+
+    while let Some(pos) = rest.find(MARKER) {
+        let start = pos + MARKER.len();
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find('"').unwrap_or(rest.len());
+        // The renderer escaped the attribute; undo that to get the path back
+        let src = rest[..end].replace("&amp;", "&").replace("&quot;", "\"");
+
+        let data_uri = local_image_path(&src, base_dir).and_then(|path| {
+            let path = scope.check(&path.to_string_lossy()).ok()?;
+            let mime = mime_for(&path)?;
+            let bytes = fs::read(&path).ok()?;
+            Some(format!("data:{};base64,{}", mime, general_purpose::STANDARD.encode(bytes)))
+        });
+        match data_uri {
+            Some(uri) => out.push_str(&uri),
+            None => out.push_str(&rest[..end]),
+        }
+        rest = &rest[end..];
+        done += 1;
+        on_image(done, total);
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A standalone page around `body`; `extra_style` is appended to the built-in CSS
+pub(crate) fn html_document(title: &str, body: &str, options: &ExportOptions, extra_style: &str) -> String {
+    let theme = match options.theme {
+        ExportTheme::Light => LIGHT_THEME,
+        ExportTheme::Dark => DARK_THEME,
+    };
+    let page = format!(
+        "@page {{ size: {}; margin: {}mm; }}",
+        options.page_size.css(),
+        options.margin_mm.unwrap_or(18)
+    );
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\" />\n<title>{}</title>\n<style>{}{}{}{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        BASE_STYLE,
+        theme,
+        page,
+        extra_style,
+        body
+    )
+}
+
+/// A Chromium-based browser able to `--print-to-pdf`
+fn find_browser() -> Option<PathBuf> {
+    let names = [
+        "chromium",
+        "chromium-browser",
+        "google-chrome",
+        "google-chrome-stable",
+        "microsoft-edge",
+        "msedge",
+        "chrome",
+    ];
+    if let Some(path) = names.iter().find_map(|name| find_executable(name)) {
+        return Some(path);
+    }
+
+    let known: &[&str] = if cfg!(target_os = "macos") {
+        &[
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "/Applications/Chromium.app/Contents/MacOS/Chromium",
+            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+        ]
+    } else if cfg!(target_os = "windows") {
+        &[
+            r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+            r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+            r"C:\Program Files\Microsoft\Edge\Application\msedge.exe",
+        ]
+    } else {
+        &[]
+    };
+    known.iter().map(PathBuf::from).find(|p| p.is_file())
+}
+
+fn print_to_pdf(browser: &Path, html: &str, output: &Path) -> Result<(), String> {
+    let temp = std::env::temp_dir().join(format!("tmd-export-{}.html", Uuid::new_v4()));
+    fs::write(&temp, html).map_err(|e| format!("Failed to write temporary file: {}", e))?;
+
+    let result = Command::new(browser)
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--no-pdf-header-footer")
+        .arg(format!("--print-to-pdf={}", output.display()))
+        .arg(&temp)
+        .output();
+    let _ = fs::remove_file(&temp);
+
+    let result = result.map_err(|e| format!("Failed to run {}: {}", browser.display(), e))?;
+    if !result.status.success() || !output.is_file() {
+        return Err(format!(
+            "PDF export failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Exports a markdown document to self-contained HTML (images and styles
+/// inlined) or to PDF through a headless Chromium-based browser. Progress is
+/// emitted on `export-progress`; returns the path written.
+#[tauri::command]
+pub async fn export_markdown(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    path: String,
+    format: ExportFormat,
+    options: Option<ExportOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let scope = guard.scope();
+    let source = scope.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let content = fs::read_to_string(&source).map_err(|e| format!("Failed to read file: {}", e))?;
+        let output = match &options.output_path {
+            Some(p) => PathBuf::from(p),
+            None => source.with_extension(match format {
+                ExportFormat::Html => "html",
+                ExportFormat::Pdf => "pdf",
+            }),
+        };
+        let output = scope.check_entry(&output.to_string_lossy())?;
+        let title = options.title.clone().unwrap_or_else(|| {
+            source
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+
+        emit_progress(&app_handle, &path, "rendering", 0.0);
+        // Math becomes MathML here so the exported file needs no KaTeX
+        let render_options = RenderOptions {
+            math: true,
+            ..RenderOptions::default()
+        };
+        let body = markdown::render(&content, &render_options);
+        emit_progress(&app_handle, &path, "diagrams", 0.1);
+        let diagram_theme = match options.theme {
+            ExportTheme::Light => DiagramTheme::Light,
+            ExportTheme::Dark => DiagramTheme::Dark,
+        };
+        let body = diagrams::render_in_html(&app_handle, &app_handle.state::<DiagramState>(), &body, diagram_theme);
+
+        let base_dir = source.parent().unwrap_or_else(|| Path::new("."));
+        let body = inline_images(&body, base_dir, &scope, |done, total| {
+            emit_progress(&app_handle, &path, "images", 0.2 + 0.5 * done as f32 / total as f32);
+        });
+        let html = html_document(&title, &body, &options, "");
+
+        match format {
+            ExportFormat::Html => {
+                emit_progress(&app_handle, &path, "writing", 0.8);
+                atomic_write::write_atomic(&output, html.as_bytes())
+                    .map_err(|e| format!("Failed to write file: {}", e))?;
+            }
+            ExportFormat::Pdf => {
+                let browser = match &options.browser_path {
+                    Some(p) => PathBuf::from(p),
+                    None => find_browser().ok_or_else(|| {
+                        "PDF export needs Chrome, Chromium or Edge; none was found".to_string()
+                    })?,
+                };
+                emit_progress(&app_handle, &path, "printing", 0.8);
+                print_to_pdf(&browser, &html, &output)?;
+            }
+        }
+
+        emit_progress(&app_handle, &path, "done", 1.0);
+        Ok(output.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
@@ -0,0 +1,137 @@
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use ignore::WalkBuilder;
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher, Utf32Str};
+use serde::Serialize;
+use tauri::State;
+
+use crate::fs_guard::FsGuardState;
+
+const DEFAULT_QUERY_LIMIT: usize = 50;
+
+/// Every file under a workspace root, as '/'-separated paths relative to it
+pub struct FileIndex {
+    root: PathBuf,
+    paths: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct FileIndexState {
+    index: Mutex<Option<Arc<FileIndex>>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileMatch {
+    pub path: String,
+    pub relative_path: String,
+    pub score: u32,
+    /// Char positions in `relative_path` that matched, for highlighting
+    pub indices: Vec<u32>,
+}
+
+pub fn build(root: &Path, include_hidden: bool) -> FileIndex {
+    let walker = WalkBuilder::new(root)
+        .hidden(!include_hidden)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut paths: Vec<String> = walker
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            entry
+                .path()
+                .strip_prefix(root)
+                .ok()
+                .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+    paths.sort();
+
+    FileIndex {
+        root: root.to_path_buf(),
+        paths,
+    }
+}
+
+/// fzf-style ranking: best score first, shorter paths winning ties
+pub fn query(index: &FileIndex, query: &str, limit: usize) -> Vec<FileMatch> {
+    let mut matcher = Matcher::new(Config::DEFAULT.match_paths());
+    let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+    let mut buf = Vec::new();
+
+    let mut scored: Vec<(u32, &String)> = index
+        .paths
+        .iter()
+        .filter_map(|path| {
+            pattern
+                .score(Utf32Str::new(path, &mut buf), &mut matcher)
+                .map(|score| (score, path))
+        })
+        .collect();
+
+    let key = |(score, path): &(u32, &String)| (Reverse(*score), path.len());
+    if scored.len() > limit && limit > 0 {
+        scored.select_nth_unstable_by_key(limit - 1, key);
+    }
+    scored.truncate(limit);
+    scored.sort_by_key(key);
+
+    scored
+        .into_iter()
+        .map(|(score, path)| {
+            let mut indices = Vec::new();
+            pattern.indices(Utf32Str::new(path, &mut buf), &mut matcher, &mut indices);
+            indices.sort_unstable();
+            indices.dedup();
+            FileMatch {
+                path: index.root.join(path).to_string_lossy().to_string(),
+                relative_path: path.clone(),
+                score,
+                indices,
+            }
+        })
+        .collect()
+}
+
+/// Walks `root` (respecting .gitignore and friends) and keeps the file list in
+/// memory for `query_file_index`. Returns the number of indexed files.
+#[tauri::command]
+pub async fn build_file_index(
+    state: State<'_, FileIndexState>,
+    guard: State<'_, FsGuardState>,
+    root: String,
+    include_hidden: Option<bool>,
+) -> Result<usize, String> {
+    let root = guard.check(&root)?;
+    let include_hidden = include_hidden.unwrap_or(false);
+    let index = tauri::async_runtime::spawn_blocking(move || build(&root, include_hidden))
+        .await
+        .map_err(|e| format!("Indexing task failed: {}", e))?;
+
+    let count = index.paths.len();
+    *state.index.lock().map_err(|e| format!("Failed to lock state: {}", e))? = Some(Arc::new(index));
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn query_file_index(
+    state: State<'_, FileIndexState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<FileMatch>, String> {
+    let index = state
+        .index
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .clone()
+        .ok_or_else(|| "File index has not been built".to_string())?;
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+
+    tauri::async_runtime::spawn_blocking(move || self::query(&index, &query, limit))
+        .await
+        .map_err(|e| format!("Query task failed: {}", e))
+}
@@ -0,0 +1,143 @@
+use std::fs::{self, File, Metadata};
+use std::io::Read;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::State;
+
+use crate::fs_guard::FsGuardState;
+
+// Only this many leading bytes are sniffed for NUL bytes
+const BINARY_SNIFF_LEN: usize = 8000;
+
+// Extensions that are binary regardless of their content
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tiff", "psd", "pdf", "zip", "gz", "tgz",
+    "bz2", "xz", "7z", "rar", "jar", "war", "exe", "dll", "so", "dylib", "a", "lib", "o", "obj",
+    "class", "wasm", "bin", "dat", "db", "sqlite", "mp3", "mp4", "mov", "avi", "mkv", "wav",
+    "flac", "ogg", "ttf", "otf", "woff", "woff2", "eot", "doc", "docx", "xls", "xlsx", "ppt",
+    "pptx", "odt", "epub", "iso", "dmg",
+];
+
+#[derive(Debug, Serialize)]
+pub struct FileStat {
+    pub path: String,
+    pub size: u64,
+    pub is_directory: bool,
+    pub is_file: bool,
+    pub is_symlink: bool,
+    /// Milliseconds since the Unix epoch
+    pub modified_ms: Option<u64>,
+    /// Creation (birth) time where the platform records it
+    pub created_ms: Option<u64>,
+    /// Unix inode change time (ctime); `None` on other platforms
+    pub changed_ms: Option<u64>,
+    pub readonly: bool,
+    /// Unix permission bits, e.g. 0o644
+    pub mode: Option<u32>,
+    /// `ls`-style permission string, e.g. "rw-r--r--"
+    pub permissions: Option<String>,
+    pub is_binary: bool,
+}
+
+/// True if the sniffed prefix contains NUL bytes (UTF-16 text with a BOM excepted)
+pub fn is_probably_binary(bytes: &[u8]) -> bool {
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return false;
+    }
+    bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
+
+pub fn has_binary_extension(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| BINARY_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Extension check first, then content sniffing of the first few KB
+pub fn is_binary_file(path: &Path) -> bool {
+    if has_binary_extension(path) {
+        return true;
+    }
+    let mut file = match File::open(path) {
+        Ok(f) => f,
+        Err(_) => return false,
+    };
+    let mut buf = Vec::with_capacity(BINARY_SNIFF_LEN);
+    if file.by_ref().take(BINARY_SNIFF_LEN as u64).read_to_end(&mut buf).is_err() {
+        return false;
+    }
+    is_probably_binary(&buf)
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn system_time_ms(time: std::io::Result<SystemTime>) -> Option<u64> {
+    time.ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+/// Milliseconds since the Unix epoch; 0 if the clock is set before it
+pub fn now_ms() -> u64 {
+    system_time_ms(Ok(SystemTime::now())).unwrap_or(0)
+}
+
+#[cfg(unix)]
+fn unix_details(metadata: &Metadata) -> (Option<u64>, Option<u32>, Option<String>) {
+    use std::os::unix::fs::MetadataExt;
+
+    let mode = metadata.mode() & 0o777;
+    let permissions: String = (0..9)
+        .map(|i| {
+            let bit = 1 << (8 - i);
+            if mode & bit == 0 {
+                '-'
+            } else {
+                ['r', 'w', 'x'][i % 3]
+            }
+        })
+        .collect();
+    let changed_ms = (metadata.ctime() as u64)
+        .saturating_mul(1000)
+        .saturating_add(metadata.ctime_nsec() as u64 / 1_000_000);
+    (Some(changed_ms), Some(mode), Some(permissions))
+}
+
+#[cfg(not(unix))]
+fn unix_details(_metadata: &Metadata) -> (Option<u64>, Option<u32>, Option<String>) {
+    (None, None, None)
+}
+
+pub fn stat(path: &Path) -> Result<FileStat, String> {
+    let link_metadata = fs::symlink_metadata(path).map_err(|e| format!("Failed to stat path: {}", e))?;
+    // Report the target's details for symlinks, falling back to the link for broken ones
+    let metadata = fs::metadata(path).unwrap_or_else(|_| link_metadata.clone());
+    let (changed_ms, mode, permissions) = unix_details(&metadata);
+
+    Ok(FileStat {
+        path: path.to_string_lossy().to_string(),
+        size: metadata.len(),
+        is_directory: metadata.is_dir(),
+        is_file: metadata.is_file(),
+        is_symlink: link_metadata.file_type().is_symlink(),
+        modified_ms: system_time_ms(metadata.modified()),
+        created_ms: system_time_ms(metadata.created()),
+        changed_ms,
+        readonly: metadata.permissions().readonly(),
+        mode,
+        permissions,
+        is_binary: metadata.is_file() && is_binary_file(path),
+    })
+}
+
+#[tauri::command]
+pub async fn stat_path(guard: State<'_, FsGuardState>, path: String) -> Result<FileStat, String> {
+    // Describes the entry itself, so a link is checked as a link
+    stat(&guard.check_entry(&path)?)
+}
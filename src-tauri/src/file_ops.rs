@@ -0,0 +1,547 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::fs_guard::{FsGuardState, Scope};
+
+// Minimum interval between two progress events
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What to do when the destination already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Fail with an error so the caller can ask the user
+    #[default]
+    Error,
+    /// Replace existing files (directories are merged)
+    Overwrite,
+    /// Leave the destination untouched
+    Skip,
+    /// Pick a free name such as `notes (1).md`
+    Rename,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileOperationProgress {
+    pub operation_id: String,
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub current_path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileOperationResult {
+    /// Where the item ended up (differs from the request when renamed)
+    pub destination: String,
+    pub skipped: bool,
+}
+
+/// Emits throttled progress events for one copy/move operation
+pub struct ProgressReporter {
+    app_handle: Option<AppHandle>,
+    progress: FileOperationProgress,
+    last_emit: Option<Instant>,
+}
+
+impl ProgressReporter {
+    pub fn new(app_handle: Option<AppHandle>, operation_id: String) -> Self {
+        Self {
+            app_handle,
+            progress: FileOperationProgress {
+                operation_id,
+                files_done: 0,
+                files_total: 0,
+                bytes_done: 0,
+                bytes_total: 0,
+                current_path: String::new(),
+            },
+            last_emit: None,
+        }
+    }
+
+    pub fn add_totals(&mut self, files: u64, bytes: u64) {
+        self.progress.files_total += files;
+        self.progress.bytes_total += bytes;
+    }
+
+    fn file_done(&mut self, path: &Path, bytes: u64) {
+        self.progress.files_done += 1;
+        self.progress.bytes_done += bytes;
+        self.progress.current_path = path.to_string_lossy().to_string();
+        if self.last_emit.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL) {
+            self.emit();
+        }
+    }
+
+    /// Sets the completed counts outright and reports them
+    fn settle(&mut self, files_done: u64, bytes_done: u64) {
+        self.progress.files_done = files_done;
+        self.progress.bytes_done = bytes_done;
+        self.emit();
+    }
+
+    pub fn finish(&mut self) {
+        self.emit();
+    }
+
+    fn emit(&mut self) {
+        if let Some(app_handle) = &self.app_handle {
+            let event = format!("file-operation-progress-{}", self.progress.operation_id);
+            let _ = app_handle.emit(&event, self.progress.clone());
+        }
+        self.last_emit = Some(Instant::now());
+    }
+}
+
+/// Returns `path` if it is free, otherwise the first free `name (n).ext` next to it
+pub fn unique_path(path: &Path) -> PathBuf {
+    if fs::symlink_metadata(path).is_err() {
+        return path.to_path_buf();
+    }
+
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let is_dir = path.is_dir();
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    // Directories and dotfiles keep their whole name as the stem
+    let (stem, ext) = match file_name.rfind('.') {
+        Some(idx) if idx > 0 && !is_dir => (file_name[..idx].to_string(), file_name[idx..].to_string()),
+        _ => (file_name.clone(), String::new()),
+    };
+
+    (1..)
+        .map(|n| parent.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded range always yields a free name")
+}
+
+/// Counts files and bytes below `path` so progress can be reported as a fraction
+pub fn measure(path: &Path) -> (u64, u64) {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return (0, 0),
+    };
+    if !metadata.is_dir() {
+        return (1, metadata.len());
+    }
+
+    let mut files = 0;
+    let mut bytes = 0;
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            let (f, b) = measure(&entry.path());
+            files += f;
+            bytes += b;
+        }
+    }
+    (files, bytes)
+}
+
+fn copy_recursive(
+    source: &Path,
+    destination: &Path,
+    overwrite: bool,
+    reporter: &mut ProgressReporter,
+) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(source)?;
+        if overwrite && fs::symlink_metadata(destination).is_ok() {
+            fs::remove_file(destination)?;
+        }
+        create_symlink(&target, destination, source.is_dir())?;
+        reporter.file_done(source, 0);
+    } else if metadata.is_dir() {
+        if !destination.is_dir() {
+            fs::create_dir(destination)?;
+        }
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &destination.join(entry.file_name()), overwrite, reporter)?;
+        }
+        fs::set_permissions(destination, metadata.permissions())?;
+    } else {
+        if !overwrite && destination.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Destination already exists: {}", destination.display()),
+            ));
+        }
+        let bytes = fs::copy(source, destination)?;
+        reporter.file_done(source, bytes);
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path, _is_dir: bool) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path, is_dir: bool) -> io::Result<()> {
+    if is_dir {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Resolves the final destination according to `strategy`.
+/// Returns `None` when the operation should be skipped.
+pub fn resolve_destination(
+    source: &Path,
+    destination: &Path,
+    strategy: ConflictStrategy,
+) -> Result<Option<PathBuf>, String> {
+    if fs::symlink_metadata(source).is_err() {
+        return Err("Source path does not exist".to_string());
+    }
+    if source.is_dir() && destination.starts_with(source) {
+        return Err("Cannot copy or move a directory into itself".to_string());
+    }
+    if fs::symlink_metadata(destination).is_err() {
+        return Ok(Some(destination.to_path_buf()));
+    }
+
+    match strategy {
+        ConflictStrategy::Error => Err(format!("Destination already exists: {}", destination.display())),
+        ConflictStrategy::Skip => Ok(None),
+        ConflictStrategy::Rename => Ok(Some(unique_path(destination))),
+        ConflictStrategy::Overwrite => {
+            if source.is_dir() != destination.is_dir() {
+                return Err("Cannot overwrite a file with a directory or vice versa".to_string());
+            }
+            Ok(Some(destination.to_path_buf()))
+        }
+    }
+}
+
+pub fn copy_with_strategy(
+    source: &Path,
+    destination: &Path,
+    strategy: ConflictStrategy,
+    reporter: &mut ProgressReporter,
+) -> Result<FileOperationResult, String> {
+    let destination = match resolve_destination(source, destination, strategy)? {
+        Some(d) => d,
+        None => {
+            return Ok(FileOperationResult {
+                destination: destination.to_string_lossy().to_string(),
+                skipped: true,
+            })
+        }
+    };
+
+    copy_recursive(source, &destination, strategy == ConflictStrategy::Overwrite, reporter)
+        .map_err(|e| format!("Failed to copy: {}", e))?;
+
+    Ok(FileOperationResult {
+        destination: destination.to_string_lossy().to_string(),
+        skipped: false,
+    })
+}
+
+pub fn move_with_strategy(
+    source: &Path,
+    destination: &Path,
+    strategy: ConflictStrategy,
+    reporter: &mut ProgressReporter,
+) -> Result<FileOperationResult, String> {
+    let destination = match resolve_destination(source, destination, strategy)? {
+        Some(d) => d,
+        None => {
+            return Ok(FileOperationResult {
+                destination: destination.to_string_lossy().to_string(),
+                skipped: true,
+            })
+        }
+    };
+
+    // A plain rename is instant but only works within one filesystem and
+    // can't merge into an existing directory
+    let merging = destination.is_dir();
+    if !merging {
+        match fs::rename(source, &destination) {
+            Ok(_) => {
+                let (files, bytes) = (reporter.progress.files_total, reporter.progress.bytes_total);
+                reporter.progress.files_done = files;
+                reporter.progress.bytes_done = bytes;
+                return Ok(FileOperationResult {
+                    destination: destination.to_string_lossy().to_string(),
+                    skipped: false,
+                });
+            }
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+            Err(e) => return Err(format!("Failed to move: {}", e)),
+        }
+    }
+
+    copy_recursive(source, &destination, strategy == ConflictStrategy::Overwrite, reporter)
+        .map_err(|e| format!("Failed to move: {}", e))?;
+    remove_path(source).map_err(|e| format!("Copied but failed to remove source: {}", e))?;
+
+    Ok(FileOperationResult {
+        destination: destination.to_string_lossy().to_string(),
+        skipped: false,
+    })
+}
+
+/// Copies a file or directory tree to `destination` (the full target path).
+/// Progress is emitted on `file-operation-progress-{operation_id}` when an id is given.
+#[tauri::command]
+pub async fn copy_path(
+    app_handle: AppHandle,
+    source: String,
+    destination: String,
+    strategy: Option<ConflictStrategy>,
+    operation_id: Option<String>,
+) -> Result<FileOperationResult, String> {
+    let scope = app_handle.state::<FsGuardState>().scope();
+    // The copy reads what a link points to, so the source is checked as followed
+    let source = scope.check(&source)?;
+    let destination = scope.check_entry(&destination)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut reporter = ProgressReporter::new(
+            operation_id.as_ref().map(|_| app_handle),
+            operation_id.unwrap_or_default(),
+        );
+        let (files, bytes) = measure(&source);
+        reporter.add_totals(files, bytes);
+
+        let result = copy_with_strategy(&source, &destination, strategy.unwrap_or_default(), &mut reporter);
+        reporter.finish();
+        result
+    })
+    .await
+    .map_err(|e| format!("Copy task failed: {}", e))?
+}
+
+/// Moves a file or directory tree to `destination`, falling back to
+/// copy-and-delete when the rename crosses filesystems.
+#[tauri::command]
+pub async fn move_path(
+    app_handle: AppHandle,
+    source: String,
+    destination: String,
+    strategy: Option<ConflictStrategy>,
+    operation_id: Option<String>,
+) -> Result<FileOperationResult, String> {
+    let scope = app_handle.state::<FsGuardState>().scope();
+    let source = scope.check_entry(&source)?;
+    let destination = scope.check_entry(&destination)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut reporter = ProgressReporter::new(
+            operation_id.as_ref().map(|_| app_handle),
+            operation_id.unwrap_or_default(),
+        );
+        let (files, bytes) = measure(&source);
+        reporter.add_totals(files, bytes);
+
+        let result = move_with_strategy(&source, &destination, strategy.unwrap_or_default(), &mut reporter);
+        reporter.finish();
+        result
+    })
+    .await
+    .map_err(|e| format!("Move task failed: {}", e))?
+}
+
+/// One step of `batch_fs_operation`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOperation {
+    Copy { source: String, destination: String },
+    Move { source: String, destination: String },
+    /// Moves to the trash unless `permanent` is set
+    Delete {
+        path: String,
+        #[serde(default)]
+        permanent: bool,
+    },
+    /// Renames in place; the renames of a batch are undone if any of them fails
+    Rename { source: String, destination: String },
+}
+
+impl BatchOperation {
+    fn source(&self) -> &str {
+        match self {
+            BatchOperation::Copy { source, .. }
+            | BatchOperation::Move { source, .. }
+            | BatchOperation::Rename { source, .. } => source,
+            BatchOperation::Delete { path, .. } => path,
+        }
+    }
+
+    fn destination(&self) -> Option<&str> {
+        match self {
+            BatchOperation::Copy { destination, .. }
+            | BatchOperation::Move { destination, .. }
+            | BatchOperation::Rename { destination, .. } => Some(destination),
+            BatchOperation::Delete { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchOperationResult {
+    /// Position of the operation in the request
+    pub index: usize,
+    pub success: bool,
+    pub destination: Option<String>,
+    pub skipped: bool,
+    pub error: Option<String>,
+    /// Set on renames that succeeded but were reverted because another failed
+    pub rolled_back: bool,
+}
+
+fn run_operation(
+    op: &BatchOperation,
+    scope: &Scope,
+    strategy: ConflictStrategy,
+    reporter: &mut ProgressReporter,
+) -> Result<FileOperationResult, String> {
+    let source = match op {
+        BatchOperation::Copy { source, .. } => scope.check(source)?,
+        _ => scope.check_entry(op.source())?,
+    };
+    // Empty for deletes, which have none
+    let destination = op
+        .destination()
+        .map(|destination| scope.check_entry(destination))
+        .transpose()?
+        .unwrap_or_default();
+    match op {
+        BatchOperation::Copy { .. } => copy_with_strategy(&source, &destination, strategy, reporter),
+        BatchOperation::Move { .. } => move_with_strategy(&source, &destination, strategy, reporter),
+        BatchOperation::Delete { permanent, .. } => {
+            let path = source.as_path();
+            if fs::symlink_metadata(path).is_err() {
+                return Err("Path does not exist".to_string());
+            }
+            if *permanent {
+                remove_path(path).map_err(|e| format!("Failed to delete: {}", e))?;
+            } else {
+                trash::delete(path).map_err(|e| format!("Failed to move to trash: {}", e))?;
+            }
+            Ok(FileOperationResult {
+                destination: String::new(),
+                skipped: false,
+            })
+        }
+        BatchOperation::Rename { destination: requested, .. } => {
+            let destination = match resolve_destination(&source, &destination, strategy)? {
+                Some(d) => d,
+                None => {
+                    return Ok(FileOperationResult {
+                        destination: requested.clone(),
+                        skipped: true,
+                    })
+                }
+            };
+            fs::rename(&source, &destination).map_err(|e| format!("Failed to rename: {}", e))?;
+            Ok(FileOperationResult {
+                destination: destination.to_string_lossy().to_string(),
+                skipped: false,
+            })
+        }
+    }
+}
+
+/// Runs several copy/move/delete/rename operations in order as one job.
+///
+/// Progress for the whole batch is emitted on
+/// `file-operation-progress-{operation_id}`. A failing operation doesn't stop
+/// the others; each gets its own result. If any rename fails, the renames that
+/// did succeed are reverted (best effort) so a bulk rename is all or nothing.
+#[tauri::command]
+pub async fn batch_fs_operation(
+    app_handle: AppHandle,
+    ops: Vec<BatchOperation>,
+    strategy: Option<ConflictStrategy>,
+    operation_id: Option<String>,
+) -> Result<Vec<BatchOperationResult>, String> {
+    let scope = app_handle.state::<FsGuardState>().scope();
+    tauri::async_runtime::spawn_blocking(move || {
+        let strategy = strategy.unwrap_or_default();
+        let mut reporter = ProgressReporter::new(
+            operation_id.as_ref().map(|_| app_handle),
+            operation_id.unwrap_or_default(),
+        );
+        let sizes: Vec<(u64, u64)> = ops.iter().map(|op| measure(Path::new(op.source()))).collect();
+        for (files, bytes) in &sizes {
+            reporter.add_totals(*files, *bytes);
+        }
+
+        let mut results = Vec::with_capacity(ops.len());
+        let mut renamed: Vec<(usize, PathBuf, PathBuf)> = Vec::new();
+        let mut rename_failed = false;
+        let (mut files_done, mut bytes_done) = (0, 0);
+        for (index, op) in ops.iter().enumerate() {
+            let outcome = run_operation(op, &scope, strategy, &mut reporter);
+            // Renames, deletes and skips don't report per file; count the whole item as done
+            files_done += sizes[index].0;
+            bytes_done += sizes[index].1;
+            reporter.settle(files_done, bytes_done);
+
+            results.push(match outcome {
+                Ok(result) => {
+                    if let (BatchOperation::Rename { source, .. }, false) = (op, result.skipped) {
+                        renamed.push((index, PathBuf::from(source), PathBuf::from(&result.destination)));
+                    }
+                    BatchOperationResult {
+                        index,
+                        success: true,
+                        destination: Some(result.destination).filter(|d| !d.is_empty()),
+                        skipped: result.skipped,
+                        error: None,
+                        rolled_back: false,
+                    }
+                }
+                Err(error) => {
+                    rename_failed |= matches!(op, BatchOperation::Rename { .. });
+                    BatchOperationResult {
+                        index,
+                        success: false,
+                        destination: None,
+                        skipped: false,
+                        error: Some(error),
+                        rolled_back: false,
+                    }
+                }
+            });
+        }
+
+        if rename_failed {
+            for (index, source, destination) in renamed.into_iter().rev() {
+                match fs::rename(&destination, &source) {
+                    Ok(()) => results[index].rolled_back = true,
+                    Err(e) => {
+                        results[index].error = Some(format!("Failed to undo rename: {}", e));
+                    }
+                }
+            }
+        }
+
+        reporter.finish();
+        results
+    })
+    .await
+    .map_err(|e| format!("Batch task failed: {}", e))
+}
@@ -0,0 +1,111 @@
+//! Sort orders for the file explorer, applied by `read_directory` so the
+//! frontend can render the listing as it arrives.
+
+use std::cmp::Ordering;
+
+use serde::Deserialize;
+
+use crate::FileEntry;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Name,
+    Modified,
+    Size,
+}
+
+/// Where folders go relative to files
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FolderOrder {
+    #[default]
+    First,
+    Last,
+    /// Sorted together with files
+    Mixed,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct SortOptions {
+    pub key: SortKey,
+    pub descending: bool,
+    pub folders: FolderOrder,
+    /// Compare runs of digits by value, so `file2` comes before `file10`
+    pub natural: bool,
+}
+
+/// Compares names the way people count: digit runs by numeric value,
+/// everything else case-insensitively
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_chars, mut b_chars) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let (x, y) = match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => break,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => (*x, *y),
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let take_digits = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                let mut digits = String::new();
+                while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(c);
+                }
+                digits
+            };
+            let (x, y) = (take_digits(&mut a_chars), take_digits(&mut b_chars));
+            let (x_value, y_value) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+            let ordering = x_value
+                .len()
+                .cmp(&y_value.len())
+                .then_with(|| x_value.cmp(y_value))
+                // Equal values: fewer leading zeros first
+                .then_with(|| x.len().cmp(&y.len()));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        } else {
+            let ordering = x.to_lowercase().cmp(y.to_lowercase());
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            a_chars.next();
+            b_chars.next();
+        }
+    }
+    a.cmp(b)
+}
+
+fn name_cmp(a: &str, b: &str, natural: bool) -> Ordering {
+    if natural {
+        natural_cmp(a, b)
+    } else {
+        a.to_lowercase().cmp(&b.to_lowercase())
+    }
+}
+
+/// Sorts `entries` in place; entries without a modification time sort as
+/// the oldest, and ties are broken by name
+pub fn sort_entries(entries: &mut [FileEntry], options: &SortOptions) {
+    entries.sort_by(|a, b| {
+        let folders = match options.folders {
+            FolderOrder::First => b.is_directory.cmp(&a.is_directory),
+            FolderOrder::Last => a.is_directory.cmp(&b.is_directory),
+            FolderOrder::Mixed => Ordering::Equal,
+        };
+        let ordering = match options.key {
+            SortKey::Name => Ordering::Equal,
+            SortKey::Modified => a.modified_ms.cmp(&b.modified_ms),
+            SortKey::Size => a.size.cmp(&b.size),
+        }
+        .then_with(|| name_cmp(&a.name, &b.name, options.natural));
+        folders.then(if options.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        })
+    });
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::encoding::{self, DecodedText};
+use crate::error::AppError;
+use crate::file_info::{sha256_hex, system_time_ms};
+use crate::fs_guard::FsGuardState;
+
+/// What a file looked like on disk when the editor last read or wrote it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileVersion {
+    #[serde(default)]
+    pub modified_ms: Option<u64>,
+    /// SHA-256 of the raw bytes; when present it decides conflicts on its own
+    #[serde(default)]
+    pub hash: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileWithMetadata {
+    #[serde(flatten)]
+    pub text: DecodedText,
+    pub size: u64,
+    pub version: FileVersion,
+}
+
+/// Error returned by `save_file`; `message` is always set so callers that only
+/// display errors keep working
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SaveError {
+    /// The file changed (or disappeared) on disk since `expected` was observed
+    Conflict {
+        message: String,
+        path: String,
+        current: Option<FileVersion>,
+    },
+    Failed { message: String },
+    /// Any other failure; carries the error's `code`, e.g. `permission_denied`
+    Error(AppError),
+}
+
+impl From<String> for SaveError {
+    fn from(message: String) -> Self {
+        SaveError::Failed { message }
+    }
+}
+
+impl From<AppError> for SaveError {
+    fn from(error: AppError) -> Self {
+        SaveError::Error(error)
+    }
+}
+
+pub fn version_of(path: &Path, bytes: &[u8]) -> FileVersion {
+    FileVersion {
+        modified_ms: fs::metadata(path).ok().and_then(|m| system_time_ms(m.modified())),
+        hash: Some(sha256_hex(bytes)),
+    }
+}
+
+/// Current on-disk version, or `None` if the file doesn't exist
+pub fn current_version(path: &Path) -> Result<Option<FileVersion>, String> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(version_of(path, &bytes))),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read file: {}", e)),
+    }
+}
+
+/// Fails with `SaveError::Conflict` unless the file on disk still matches `expected`
+pub fn check_unchanged(path: &Path, expected: &FileVersion) -> Result<(), SaveError> {
+    let current = current_version(path)?;
+    let unchanged = match (&current, &expected.hash, expected.modified_ms) {
+        (None, _, _) => false,
+        (Some(cur), Some(hash), _) => cur.hash.as_ref() == Some(hash),
+        (Some(cur), None, Some(mtime)) => cur.modified_ms == Some(mtime),
+        // Nothing to compare against
+        (Some(_), None, None) => true,
+    };
+    if unchanged {
+        return Ok(());
+    }
+
+    let message = match current {
+        Some(_) => format!("{} was modified on disk since it was opened", path.display()),
+        None => format!("{} was deleted on disk since it was opened", path.display()),
+    };
+    Err(SaveError::Conflict {
+        message,
+        path: path.to_string_lossy().to_string(),
+        current,
+    })
+}
+
+/// Like `read_file_with_encoding`, plus the version to pass back to `save_file`
+#[tauri::command]
+pub async fn read_file_with_metadata(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    encoding: Option<String>,
+) -> Result<FileWithMetadata, String> {
+    let path = guard.check(&path)?;
+    let forced = encoding.as_deref().map(encoding::encoding_for_label).transpose()?;
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let version = version_of(&path, &bytes);
+    Ok(FileWithMetadata {
+        text: encoding::decode(&bytes, forced),
+        size: bytes.len() as u64,
+        version,
+    })
+}
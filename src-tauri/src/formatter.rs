@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::fs_guard::FsGuardState;
+use crate::lsp::registry::find_executable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Formatter {
+    Rustfmt,
+    Gofmt,
+    Prettier,
+    Black,
+    Ruff,
+    ClangFormat,
+}
+
+impl Formatter {
+    fn binary(self) -> &'static str {
+        match self {
+            Formatter::Rustfmt => "rustfmt",
+            Formatter::Gofmt => "gofmt",
+            Formatter::Prettier => "prettier",
+            Formatter::Black => "black",
+            Formatter::Ruff => "ruff",
+            Formatter::ClangFormat => "clang-format",
+        }
+    }
+}
+
+/// Error returned by `format_document`; `message` is always set so callers
+/// that only display errors keep working
+#[derive(Debug, Serialize)]
+pub struct FormatError {
+    pub message: String,
+    pub formatter: Option<Formatter>,
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+impl From<String> for FormatError {
+    fn from(message: String) -> Self {
+        FormatError {
+            message,
+            formatter: None,
+            exit_code: None,
+            stderr: String::new(),
+        }
+    }
+}
+
+const PRETTIER_CONFIGS: &[&str] = &[
+    ".prettierrc",
+    ".prettierrc.json",
+    ".prettierrc.yaml",
+    ".prettierrc.yml",
+    ".prettierrc.js",
+    ".prettierrc.cjs",
+    ".prettierrc.mjs",
+    ".prettierrc.toml",
+    "prettier.config.js",
+    "prettier.config.cjs",
+    "prettier.config.mjs",
+];
+
+/// First ancestor directory of `path` containing any of `names`, and the match
+fn find_upwards(path: &Path, names: &[&str]) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find_map(|dir| names.iter().map(|name| dir.join(name)).find(|p| p.is_file()))
+}
+
+fn uses_prettier(path: &Path) -> bool {
+    if find_upwards(path, PRETTIER_CONFIGS).is_some() {
+        return true;
+    }
+    // A "prettier" key or dependency in package.json counts as configuration too
+    find_upwards(path, &["package.json"])
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .is_some_and(|pkg| {
+            pkg.get("prettier").is_some()
+                || ["dependencies", "devDependencies"]
+                    .iter()
+                    .any(|key| pkg.get(key).and_then(|d| d.get("prettier")).is_some())
+        })
+}
+
+fn uses_ruff(path: &Path) -> bool {
+    find_upwards(path, &["ruff.toml", ".ruff.toml"]).is_some()
+        || find_upwards(path, &["pyproject.toml"])
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .is_some_and(|text| text.contains("[tool.ruff"))
+}
+
+/// The formatter for `path`, from its extension and the project's config files
+pub fn detect(path: &Path) -> Option<Formatter> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "rs" => Some(Formatter::Rustfmt),
+        "go" => Some(Formatter::Gofmt),
+        "py" | "pyi" if uses_ruff(path) => Some(Formatter::Ruff),
+        "py" | "pyi" => Some(Formatter::Black),
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" => Some(Formatter::ClangFormat),
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "css" | "scss" | "less" | "vue" | "html" | "graphql" => {
+            Some(Formatter::Prettier)
+        }
+        // Prose and data files are only reformatted in projects that opted into prettier
+        "md" | "markdown" | "json" | "yaml" | "yml" if uses_prettier(path) => Some(Formatter::Prettier),
+        _ => None,
+    }
+}
+
+/// Rust edition from the nearest Cargo.toml; rustfmt defaults to 2015 otherwise
+fn rust_edition(path: &Path) -> Option<String> {
+    let manifest = find_upwards(path, &["Cargo.toml"])?;
+    let value: toml::Value = std::fs::read_to_string(manifest).ok()?.parse().ok()?;
+    value
+        .get("package")
+        .and_then(|p| p.get("edition"))
+        .and_then(|e| e.as_str())
+        .map(|e| e.to_string())
+}
+
+fn build_command(formatter: Formatter, path: &Path) -> Result<Command, FormatError> {
+    let not_found = || FormatError {
+        message: format!("{} was not found on PATH", formatter.binary()),
+        formatter: Some(formatter),
+        exit_code: None,
+        stderr: String::new(),
+    };
+    // Prefer the project's own prettier over a global one
+    let binary = match formatter {
+        Formatter::Prettier => path
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join("node_modules").join(".bin").join("prettier"))
+            .find_map(|p| find_executable(&p.to_string_lossy()))
+            .or_else(|| find_executable("prettier")),
+        other => find_executable(other.binary()),
+    }
+    .ok_or_else(not_found)?;
+
+    let file = path.to_string_lossy().to_string();
+    let mut cmd = Command::new(binary);
+    match formatter {
+        Formatter::Rustfmt => {
+            cmd.args(["--emit", "stdout"]);
+            if let Some(edition) = rust_edition(path) {
+                cmd.args(["--edition", &edition]);
+            }
+        }
+        Formatter::Gofmt => {}
+        Formatter::Prettier => {
+            cmd.args(["--stdin-filepath", &file]);
+        }
+        Formatter::Black => {
+            cmd.args(["--quiet", "--stdin-filename", &file, "-"]);
+        }
+        Formatter::Ruff => {
+            cmd.args(["format", "--stdin-filename", &file, "-"]);
+        }
+        Formatter::ClangFormat => {
+            cmd.arg(format!("--assume-filename={}", file));
+        }
+    }
+    // Config files (rustfmt.toml, .clang-format, ...) are found from the working directory
+    if let Some(dir) = path.parent().filter(|d| d.is_dir()) {
+        cmd.current_dir(dir);
+    }
+    Ok(cmd)
+}
+
+/// Pipes `content` through a formatter and returns the formatted text.
+///
+/// `formatter` overrides detection; otherwise it is chosen from the file
+/// extension and project config (e.g. `ruff` when pyproject.toml configures
+/// it, prettier for markdown only in projects that use prettier).
+#[tauri::command]
+pub async fn format_document(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    content: String,
+    formatter: Option<Formatter>,
+) -> Result<String, FormatError> {
+    // The formatter runs in the file's folder and picks up its config there
+    let path = guard.check(&path).map_err(String::from)?;
+    let formatter = formatter
+        .or_else(|| detect(&path))
+        .ok_or_else(|| format!("No formatter available for {}", path.display()))?;
+
+    let mut child = build_command(formatter, &path)?
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", formatter.binary(), e))?;
+
+    // Written from a separate task so a formatter that streams its output
+    // can't deadlock against a full stdin pipe
+    let mut stdin = child.stdin.take().ok_or_else(|| "Failed to open formatter stdin".to_string())?;
+    let writer = tauri::async_runtime::spawn(async move {
+        let _ = stdin.write_all(content.as_bytes()).await;
+    });
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", formatter.binary(), e))?;
+    let _ = writer.await;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let summary = stderr.lines().next().unwrap_or("").to_string();
+        return Err(FormatError {
+            message: format!("{} failed: {}", formatter.binary(), summary),
+            formatter: Some(formatter),
+            exit_code: output.status.code(),
+            stderr,
+        });
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|e| format!("{} produced invalid UTF-8: {}", formatter.binary(), e).into())
+}
@@ -0,0 +1,604 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use tauri::State;
+
+use crate::atomic_write;
+use crate::fs_guard::FsGuardState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatterFormat {
+    Yaml,
+    Toml,
+}
+
+impl FrontMatterFormat {
+    fn fence(self) -> &'static str {
+        match self {
+            FrontMatterFormat::Yaml => "---",
+            FrontMatterFormat::Toml => "+++",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FrontMatter {
+    /// None if the document has no front matter
+    pub format: Option<FrontMatterFormat>,
+    pub data: Map<String, Value>,
+    /// 0-based line where the markdown body starts
+    pub body_start_line: usize,
+}
+
+/// Front matter block of `content`: format, raw text between the fences,
+/// and the byte offset where the body starts
+pub(crate) fn split(content: &str) -> Option<(FrontMatterFormat, &str, usize)> {
+    let content_start = if content.starts_with('\u{feff}') { 3 } else { 0 };
+    let text = &content[content_start..];
+    let format = if text.starts_with("---") {
+        FrontMatterFormat::Yaml
+    } else if text.starts_with("+++") {
+        FrontMatterFormat::Toml
+    } else {
+        return None;
+    };
+    let fence = format.fence();
+
+    let first_line_end = text.find('\n')?;
+    if text[..first_line_end].trim_end() != fence {
+        return None;
+    }
+
+    let mut offset = first_line_end + 1;
+    for line in text[offset..].split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        let closes = trimmed == fence || (format == FrontMatterFormat::Yaml && trimmed == "...");
+        if closes {
+            let raw = &text[first_line_end + 1..offset];
+            return Some((format, raw, content_start + offset + line.len()));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+// ---------------------------------------------------------------------------
+// TOML
+// ---------------------------------------------------------------------------
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::Number(i.into()),
+        toml::Value::Float(f) => Number::from_f64(f).map(Value::Number).unwrap_or(Value::Null),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect()),
+    }
+}
+
+/// TOML has no null, so null values (and null array items) are dropped
+fn json_to_toml(value: Value) -> Option<toml::Value> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => toml::Value::Boolean(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None => toml::Value::Float(n.as_f64()?),
+        },
+        // Keep dates typed when they round-trip as TOML datetimes
+        Value::String(s) => match s.parse::<toml::value::Datetime>() {
+            Ok(d) => toml::Value::Datetime(d),
+            Err(_) => toml::Value::String(s),
+        },
+        Value::Array(items) => toml::Value::Array(items.into_iter().filter_map(json_to_toml).collect()),
+        Value::Object(map) => toml::Value::Table(
+            map.into_iter()
+                .filter_map(|(k, v)| json_to_toml(v).map(|v| (k, v)))
+                .collect(),
+        ),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// YAML (the subset used for front matter: block maps and sequences, flow
+// collections, quoted and block scalars)
+// ---------------------------------------------------------------------------
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_ignorable(line: &str) -> bool {
+    let t = line.trim();
+    t.is_empty() || t.starts_with('#')
+}
+
+/// Drops a trailing ` # comment` that isn't inside quotes
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '#') if prev.is_whitespace() => return text[..i].trim_end(),
+            _ => {}
+        }
+        prev = c;
+    }
+    text.trim_end()
+}
+
+/// Splits `key: rest` at the first `:` followed by a space or the end, outside quotes
+fn split_key(text: &str) -> Option<(String, &str)> {
+    let mut quote = None;
+    let bytes = text.as_bytes();
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') if i == 0 => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, ':') if i + 1 == text.len() || bytes[i + 1] == b' ' => {
+                let key = text[..i].trim();
+                let key = match parse_scalar(key) {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                return Some((key, text[i + 1..].trim()));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_double_quoted(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('0') => out.push('\0'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                if let Some(ch) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    out.push(ch);
+                }
+            }
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+fn parse_scalar(text: &str) -> Value {
+    let text = text.trim();
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        return Value::String(parse_double_quoted(&text[1..text.len() - 1]));
+    }
+    if text.len() >= 2 && text.starts_with('\'') && text.ends_with('\'') {
+        return Value::String(text[1..text.len() - 1].replace("''", "'"));
+    }
+    if text.starts_with('[') || text.starts_with('{') {
+        let chars: Vec<char> = text.chars().collect();
+        let mut i = 0;
+        if let Some(value) = parse_flow(&chars, &mut i) {
+            return value;
+        }
+    }
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    if let Ok(i) = text.parse::<i64>() {
+        return Value::Number(i.into());
+    }
+    if text.chars().any(|c| c.is_ascii_digit()) && !text.contains(|c: char| c.is_alphabetic() && c != 'e' && c != 'E') {
+        if let Some(n) = text.parse::<f64>().ok().and_then(Number::from_f64) {
+            return Value::Number(n);
+        }
+    }
+    Value::String(text.to_string())
+}
+
+/// Parses a flow collection (`[a, b]`, `{k: v}`) or a scalar inside one
+fn parse_flow(chars: &[char], i: &mut usize) -> Option<Value> {
+    let skip_ws = |i: &mut usize| {
+        while *i < chars.len() && chars[*i].is_whitespace() {
+            *i += 1;
+        }
+    };
+    skip_ws(i);
+    match chars.get(*i)? {
+        '[' => {
+            *i += 1;
+            let mut items = Vec::new();
+            loop {
+                skip_ws(i);
+                match chars.get(*i)? {
+                    ']' => {
+                        *i += 1;
+                        return Some(Value::Array(items));
+                    }
+                    ',' => *i += 1,
+                    _ => items.push(parse_flow(chars, i)?),
+                }
+            }
+        }
+        '{' => {
+            *i += 1;
+            let mut map = Map::new();
+            loop {
+                skip_ws(i);
+                match chars.get(*i)? {
+                    '}' => {
+                        *i += 1;
+                        return Some(Value::Object(map));
+                    }
+                    ',' => *i += 1,
+                    _ => {
+                        let key = match parse_flow_scalar(chars, i, true) {
+                            Value::String(s) => s,
+                            other => other.to_string(),
+                        };
+                        skip_ws(i);
+                        let value = if chars.get(*i) == Some(&':') {
+                            *i += 1;
+                            parse_flow(chars, i)?
+                        } else {
+                            Value::Null
+                        };
+                        map.insert(key, value);
+                    }
+                }
+            }
+        }
+        _ => Some(parse_flow_scalar(chars, i, false)),
+    }
+}
+
+fn parse_flow_scalar(chars: &[char], i: &mut usize, is_key: bool) -> Value {
+    let start = *i;
+    if let Some(&quote) = chars.get(*i).filter(|c| **c == '"' || **c == '\'') {
+        *i += 1;
+        while *i < chars.len() {
+            if quote == '"' && chars[*i] == '\\' {
+                *i += 2;
+                continue;
+            }
+            if chars[*i] == quote {
+                if quote == '\'' && chars.get(*i + 1) == Some(&'\'') {
+                    *i += 2;
+                    continue;
+                }
+                break;
+            }
+            *i += 1;
+        }
+        *i += 1;
+        let end = (*i).min(chars.len());
+        return parse_scalar(&chars[start..end].iter().collect::<String>());
+    }
+    while *i < chars.len() && !matches!(chars[*i], ',' | ']' | '}') && !(is_key && chars[*i] == ':') {
+        *i += 1;
+    }
+    parse_scalar(&chars[start..*i].iter().collect::<String>())
+}
+
+struct YamlParser {
+    lines: Vec<String>,
+    pos: usize,
+}
+
+impl YamlParser {
+    fn skip_ignorable(&mut self) {
+        while self.pos < self.lines.len() && is_ignorable(&self.lines[self.pos]) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek_indent(&mut self) -> Option<usize> {
+        self.skip_ignorable();
+        self.lines.get(self.pos).map(|l| indent_of(l))
+    }
+
+    fn is_sequence_item(line: &str) -> bool {
+        let t = line.trim_start();
+        t == "-" || t.starts_with("- ")
+    }
+
+    fn parse_block(&mut self, indent: usize) -> Value {
+        match self.peek_indent() {
+            Some(i) if i >= indent => {
+                if Self::is_sequence_item(&self.lines[self.pos]) {
+                    self.parse_sequence(i)
+                } else {
+                    self.parse_map(i)
+                }
+            }
+            _ => Value::Null,
+        }
+    }
+
+    fn parse_sequence(&mut self, indent: usize) -> Value {
+        let mut items = Vec::new();
+        while self.peek_indent() == Some(indent) && Self::is_sequence_item(&self.lines[self.pos]) {
+            let line = self.lines[self.pos].clone();
+            let after_dash = &line[indent + 1..];
+            let rest = after_dash.trim_start();
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.parse_block(indent + 1));
+            } else if Self::is_sequence_item(rest) || split_key(strip_comment(rest)).is_some() {
+                // Nested block inside the item: re-read the line with the dash blanked out
+                let content_indent = indent + 1 + (after_dash.len() - rest.len());
+                self.lines[self.pos] = format!("{}{}", " ".repeat(content_indent), rest);
+                items.push(self.parse_block(content_indent));
+            } else {
+                self.pos += 1;
+                items.push(self.parse_value(rest, indent));
+            }
+        }
+        Value::Array(items)
+    }
+
+    fn parse_map(&mut self, indent: usize) -> Value {
+        let mut map = Map::new();
+        while self.peek_indent() == Some(indent) && !Self::is_sequence_item(&self.lines[self.pos]) {
+            let line = self.lines[self.pos].clone();
+            let Some((key, rest)) = split_key(strip_comment(line.trim_start())) else {
+                // Not a mapping entry; skip it rather than fail the whole document
+                self.pos += 1;
+                continue;
+            };
+            self.pos += 1;
+            let value = if rest.is_empty() {
+                match self.peek_indent() {
+                    Some(i) if i > indent => self.parse_block(i),
+                    // A sequence may sit at the same indentation as its key
+                    Some(i) if i == indent && Self::is_sequence_item(&self.lines[self.pos]) => {
+                        self.parse_sequence(i)
+                    }
+                    _ => Value::Null,
+                }
+            } else {
+                self.parse_value(rest, indent)
+            };
+            map.insert(key, value);
+        }
+        Value::Object(map)
+    }
+
+    /// Inline value after `key:` or `- `, including `|` / `>` block scalars
+    fn parse_value(&mut self, rest: &str, indent: usize) -> Value {
+        let rest = strip_comment(rest);
+        let Some(style) = rest.chars().next().filter(|c| *c == '|' || *c == '>') else {
+            return parse_scalar(rest);
+        };
+        let chomp = rest[1..].chars().find(|c| *c == '-' || *c == '+');
+
+        let mut lines = Vec::new();
+        let mut block_indent = None;
+        while self.pos < self.lines.len() {
+            let line = &self.lines[self.pos];
+            if line.trim().is_empty() {
+                lines.push(String::new());
+                self.pos += 1;
+                continue;
+            }
+            let line_indent = indent_of(line);
+            let min = *block_indent.get_or_insert(line_indent);
+            if line_indent <= indent || line_indent < min {
+                break;
+            }
+            lines.push(line[min..].to_string());
+            self.pos += 1;
+        }
+
+        let trailing = lines.iter().rev().take_while(|l| l.is_empty()).count();
+        let content = &lines[..lines.len() - trailing];
+        let mut text = if style == '|' {
+            content.join("\n")
+        } else {
+            // Folded: single newlines become spaces, blank lines become newlines
+            let mut out = String::new();
+            for (i, line) in content.iter().enumerate() {
+                if line.is_empty() {
+                    out.push('\n');
+                } else {
+                    if i > 0 && !content[i - 1].is_empty() {
+                        out.push(' ');
+                    }
+                    out.push_str(line);
+                }
+            }
+            out
+        };
+        match chomp {
+            Some('-') => {}
+            Some('+') => text.push_str(&"\n".repeat(trailing + 1)),
+            _ => text.push('\n'),
+        }
+        Value::String(text)
+    }
+}
+
+pub fn parse_yaml(text: &str) -> Result<Map<String, Value>, String> {
+    let mut parser = YamlParser {
+        lines: text.lines().map(|l| l.replace('\t', "  ")).collect(),
+        pos: 0,
+    };
+    match parser.parse_block(0) {
+        Value::Object(map) => Ok(map),
+        Value::Null => Ok(Map::new()),
+        _ => Err("Front matter must be a mapping".to_string()),
+    }
+}
+
+fn yaml_needs_quotes(s: &str) -> bool {
+    s.is_empty()
+        || s != s.trim()
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.ends_with(':')
+        || s.contains(['\n', '\r', '\t', '"'])
+        || s.starts_with(['-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '%', '@', '`'])
+        || !matches!(parse_scalar(s), Value::String(_))
+}
+
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::String(s) if yaml_needs_quotes(s) => serde_json::to_string(s).unwrap_or_default(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn write_yaml(value: &Value, indent: usize, out: &mut String) {
+    let pad = " ".repeat(indent);
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                out.push_str(&format!("{}{}:", pad, yaml_scalar(&Value::String(key.clone()))));
+                write_yaml_child(value, indent, out);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                out.push_str(&format!("{}-", pad));
+                match item {
+                    Value::Object(map) if !map.is_empty() => {
+                        // First entry shares the dash line, the rest align with it
+                        let mut nested = String::new();
+                        write_yaml(item, indent + 2, &mut nested);
+                        out.push(' ');
+                        out.push_str(&nested[indent + 2..]);
+                    }
+                    _ => write_yaml_child(item, indent, out),
+                }
+            }
+        }
+        scalar => {
+            out.push_str(&pad);
+            out.push_str(&yaml_scalar(scalar));
+            out.push('\n');
+        }
+    }
+}
+
+/// Writes the value after `key:` or `-`, inline when it's a scalar or empty collection
+fn write_yaml_child(value: &Value, indent: usize, out: &mut String) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            out.push('\n');
+            write_yaml(value, indent + 2, out);
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push('\n');
+            write_yaml(value, indent + 2, out);
+        }
+        Value::Object(_) => out.push_str(" {}\n"),
+        Value::Array(_) => out.push_str(" []\n"),
+        scalar => {
+            out.push(' ');
+            out.push_str(&yaml_scalar(scalar));
+            out.push('\n');
+        }
+    }
+}
+
+pub fn to_yaml(map: &Map<String, Value>) -> String {
+    let mut out = String::new();
+    write_yaml(&Value::Object(map.clone()), 0, &mut out);
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+pub fn parse(content: &str) -> Result<FrontMatter, String> {
+    let Some((format, raw, body_start)) = split(content) else {
+        return Ok(FrontMatter {
+            format: None,
+            data: Map::new(),
+            body_start_line: 0,
+        });
+    };
+
+    let data = match format {
+        FrontMatterFormat::Yaml => parse_yaml(raw)?,
+        FrontMatterFormat::Toml => {
+            let table: toml::Table = raw.parse().map_err(|e| format!("Invalid TOML front matter: {}", e))?;
+            table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect()
+        }
+    };
+    Ok(FrontMatter {
+        format: Some(format),
+        data,
+        body_start_line: content[..body_start].matches('\n').count(),
+    })
+}
+
+/// `content` with its front matter replaced by `data`; an empty map removes it
+pub fn replace(content: &str, data: Map<String, Value>, format: Option<FrontMatterFormat>) -> Result<String, String> {
+    let existing = split(content);
+    let body = existing.map(|(_, _, start)| &content[start..]).unwrap_or(content);
+    if data.is_empty() {
+        return Ok(body.to_string());
+    }
+
+    let format = format
+        .or(existing.map(|(f, _, _)| f))
+        .unwrap_or(FrontMatterFormat::Yaml);
+    let serialized = match format {
+        FrontMatterFormat::Yaml => to_yaml(&data),
+        FrontMatterFormat::Toml => {
+            let table = match json_to_toml(Value::Object(data)) {
+                Some(toml::Value::Table(t)) => t,
+                _ => toml::Table::new(),
+            };
+            toml::to_string(&table).map_err(|e| format!("Failed to serialize TOML: {}", e))?
+        }
+    };
+
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let fence = format.fence();
+    let block = format!("{fence}\n{}{fence}\n", serialized).replace('\n', newline);
+    Ok(format!("{}{}", block, body))
+}
+
+#[tauri::command]
+pub async fn parse_front_matter(guard: State<'_, FsGuardState>, path: String) -> Result<FrontMatter, String> {
+    let path = guard.check(&path)?;
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    parse(&content)
+}
+
+/// Rewrites the document's front matter from `map`, keeping its current
+/// format unless `format` is given (YAML for documents without any)
+#[tauri::command]
+pub async fn update_front_matter(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    map: Map<String, Value>,
+    format: Option<FrontMatterFormat>,
+) -> Result<(), String> {
+    let path = guard.check(&path)?;
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let updated = replace(&content, map, format)?;
+    atomic_write::write_atomic(&path, updated.as_bytes()).map_err(|e| format!("Failed to write file: {}", e))
+}
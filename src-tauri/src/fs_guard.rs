@@ -0,0 +1,298 @@
+//! Keeps the filesystem commands inside the folders the user opened. Paths
+//! are canonicalized, so `..` and symlinks can't lead out of a workspace
+//! root, and anything outside the roots needs an explicit permit.
+//!
+//! Everything is denied until something is granted, and grants only come
+//! from native code acting on a user's choice: the pickers, paths passed on
+//! the command line or by a second launch, Open Recent menu entries and
+//! drag and drop. The webview can drop roots but never add them, and has no
+//! store access, so it can't plant entries in the recent list either.
+
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    /// Canonical workspace roots; everything below them is allowed
+    roots: Vec<PathBuf>,
+    /// Canonical files or folders allowed outside the roots
+    permitted: Vec<PathBuf>,
+}
+
+#[derive(Default)]
+pub struct FsGuardState {
+    scope: Mutex<Scope>,
+}
+
+/// Canonical form of `path`, which need not exist yet: the deepest existing
+/// ancestor is canonicalized and the missing components appended. On Windows
+/// the result keeps its ordinary form (`C:\...`, not `\\?\C:\...`), since
+/// commands hand it on to the frontend.
+fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    if !path.is_absolute() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Path must be absolute"));
+    }
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        match dunce::canonicalize(existing) {
+            Ok(canonical) => {
+                return Ok(missing.into_iter().rev().fold(canonical, |acc, name| acc.join(name)));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        match (existing.parent(), existing.components().next_back()) {
+            (Some(parent), Some(Component::Normal(name))) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            (_, Some(Component::CurDir)) => existing = existing.parent().unwrap_or(existing),
+            // `..` below a missing folder can't be resolved without guessing
+            _ => return Err(io::Error::new(io::ErrorKind::NotFound, "Path does not exist")),
+        }
+    }
+}
+
+/// Like `canonicalize`, but a symlink at `path` itself is not followed, so
+/// deleting or renaming a link acts on the link
+fn canonicalize_entry(path: &Path) -> io::Result<PathBuf> {
+    match (path.parent(), path.components().next_back()) {
+        (Some(parent), Some(Component::Normal(name))) => Ok(canonicalize(parent)?.join(name)),
+        _ => canonicalize(path),
+    }
+}
+
+impl Scope {
+    fn allows(&self, canonical: &Path) -> bool {
+        self.roots.iter().any(|root| canonical.starts_with(root))
+            || self.permitted.iter().any(|permitted| canonical.starts_with(permitted))
+    }
+
+    fn verify(&self, path: &str, canonical: io::Result<PathBuf>) -> Result<PathBuf, AppError> {
+        let canonical = canonical.map_err(|e| AppError::io(&format!("Invalid path {}", path), e))?;
+        if self.allows(&canonical) {
+            Ok(canonical)
+        } else {
+            tracing::warn!("Refused access outside the workspace: {}", canonical.display());
+            Err(AppError::permission_denied(format!(
+                "Access denied: {} is outside the open workspace",
+                path
+            )))
+        }
+    }
+
+    /// Checks a path whose contents are read or written; symlinks are
+    /// followed. Returns the canonical path.
+    pub fn check(&self, path: &str) -> Result<PathBuf, AppError> {
+        self.verify(path, canonicalize(Path::new(path)))
+    }
+
+    /// Checks a path that is created, deleted or renamed itself
+    pub fn check_entry(&self, path: &str) -> Result<PathBuf, AppError> {
+        self.verify(path, canonicalize_entry(Path::new(path)))
+    }
+}
+
+impl FsGuardState {
+    /// A copy of the current scope, for checks on another thread
+    pub fn scope(&self) -> Scope {
+        self.scope
+            .lock()
+            .map(|scope| scope.clone())
+            .unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    pub fn check(&self, path: &str) -> Result<PathBuf, AppError> {
+        self.scope().check(path)
+    }
+
+    pub fn check_entry(&self, path: &str) -> Result<PathBuf, AppError> {
+        self.scope().check_entry(path)
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Scope)) -> Result<(), String> {
+        let mut scope = self.scope.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        f(&mut scope);
+        Ok(())
+    }
+
+    fn add_root(&self, root: PathBuf) -> Result<(), String> {
+        self.update(|scope| {
+            if !scope.roots.contains(&root) {
+                scope.roots.push(root);
+            }
+        })
+    }
+
+    fn permit(&self, path: PathBuf) -> Result<(), String> {
+        self.update(|scope| {
+            if !scope.permitted.contains(&path) {
+                scope.permitted.push(path);
+            }
+        })
+    }
+}
+
+/// Registers a folder the user chose natively, e.g. in a dialog
+pub(crate) fn grant_folder(app: &AppHandle, folder: &Path) {
+    match dunce::canonicalize(folder) {
+        Ok(root) => {
+            let _ = app.state::<FsGuardState>().add_root(root);
+        }
+        Err(e) => tracing::warn!("Failed to resolve {}: {}", folder.display(), e),
+    }
+}
+
+/// Permits a file the user chose natively; it may not exist yet
+pub(crate) fn grant_file(app: &AppHandle, file: &Path) {
+    match canonicalize(file) {
+        Ok(path) => {
+            let _ = app.state::<FsGuardState>().permit(path);
+        }
+        Err(e) => tracing::warn!("Failed to resolve {}: {}", file.display(), e),
+    }
+}
+
+/// Stops allowing the folder; reopening it takes a native choice again
+#[tauri::command]
+pub async fn remove_workspace_root(state: State<'_, FsGuardState>, path: String) -> Result<(), String> {
+    let root = dunce::canonicalize(&path).unwrap_or_else(|_| PathBuf::from(&path));
+    state.update(|scope| scope.roots.retain(|r| r != &root))
+}
+
+#[tauri::command]
+pub async fn get_workspace_roots(state: State<'_, FsGuardState>) -> Result<Vec<String>, String> {
+    Ok(state
+        .scope()
+        .roots
+        .iter()
+        .map(|root| root.to_string_lossy().to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh folder with `root/inside.txt` and `outside/secret.txt`
+    struct Fixture {
+        dir: PathBuf,
+        root: PathBuf,
+        outside: PathBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = dunce::canonicalize(std::env::temp_dir())
+                .unwrap()
+                .join(format!("fs-guard-{}", uuid::Uuid::new_v4().simple()));
+            let root = dir.join("root");
+            let outside = dir.join("outside");
+            std::fs::create_dir_all(&root).unwrap();
+            std::fs::create_dir_all(&outside).unwrap();
+            std::fs::write(root.join("inside.txt"), "").unwrap();
+            std::fs::write(outside.join("secret.txt"), "").unwrap();
+            Fixture { dir, root, outside }
+        }
+
+        fn scope(&self) -> Scope {
+            Scope {
+                roots: vec![self.root.clone()],
+                permitted: Vec::new(),
+            }
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn s(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn denies_everything_by_default() {
+        let fx = Fixture::new();
+        assert!(!Scope::default().allows(&fx.root));
+        assert!(Scope::default().check(&s(&fx.root.join("inside.txt"))).is_err());
+    }
+
+    #[test]
+    fn allows_paths_below_roots() {
+        let fx = Fixture::new();
+        let scope = fx.scope();
+        assert!(scope.allows(&fx.root));
+        assert!(scope.allows(&fx.root.join("inside.txt")));
+        assert_eq!(scope.check(&s(&fx.root.join("inside.txt"))).unwrap(), fx.root.join("inside.txt"));
+        // Not created yet, e.g. a new file
+        assert!(scope.check(&s(&fx.root.join("new/file.md"))).is_ok());
+        assert!(scope.check(&s(&fx.outside.join("secret.txt"))).is_err());
+    }
+
+    #[test]
+    fn roots_match_whole_components() {
+        let fx = Fixture::new();
+        let sibling = fx.dir.join("root-other");
+        std::fs::create_dir_all(&sibling).unwrap();
+        assert!(!fx.scope().allows(&sibling));
+        assert!(fx.scope().check(&s(&sibling)).is_err());
+    }
+
+    #[test]
+    fn permits_only_the_permitted_path() {
+        let fx = Fixture::new();
+        let secret = fx.outside.join("secret.txt");
+        let scope = Scope {
+            roots: Vec::new(),
+            permitted: vec![secret.clone()],
+        };
+        assert!(scope.check(&s(&secret)).is_ok());
+        assert!(scope.check(&s(&fx.outside.join("other.txt"))).is_err());
+        assert!(scope.check(&s(&fx.outside)).is_err());
+    }
+
+    #[test]
+    fn parent_components_cannot_leave_a_root() {
+        let fx = Fixture::new();
+        let scope = fx.scope();
+        let escape = fx.root.join("..").join("outside").join("secret.txt");
+        assert!(scope.check(&s(&escape)).is_err());
+        assert!(scope.check_entry(&s(&escape)).is_err());
+        let inside = fx.root.join("sub").join("..").join("inside.txt");
+        std::fs::create_dir_all(fx.root.join("sub")).unwrap();
+        assert_eq!(scope.check(&s(&inside)).unwrap(), fx.root.join("inside.txt"));
+    }
+
+    #[test]
+    fn relative_paths_are_refused() {
+        assert!(Scope::default().check("notes/todo.md").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_escape_a_root() {
+        let fx = Fixture::new();
+        let scope = fx.scope();
+        let file_link = fx.root.join("secret-link");
+        let dir_link = fx.root.join("outside-link");
+        std::os::unix::fs::symlink(fx.outside.join("secret.txt"), &file_link).unwrap();
+        std::os::unix::fs::symlink(&fx.outside, &dir_link).unwrap();
+
+        // Reading through a link resolves it
+        assert!(scope.check(&s(&file_link)).is_err());
+        assert!(scope.check(&s(&dir_link.join("secret.txt"))).is_err());
+        assert!(scope.check(&s(&dir_link.join("new.txt"))).is_err());
+        // The link itself may still be deleted or renamed
+        assert_eq!(scope.check_entry(&s(&file_link)).unwrap(), file_link);
+        assert!(scope.check_entry(&s(&dir_link.join("secret.txt"))).is_err());
+    }
+}
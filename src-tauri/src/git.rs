@@ -0,0 +1,709 @@
+use std::path::{Path, PathBuf};
+
+use git2::build::CheckoutBuilder;
+use git2::{
+    Branch, BranchType, DiffFormat, DiffOptions, ErrorCode, ObjectType, Patch, Repository, StashFlags, Status,
+    StatusOptions,
+};
+use serde::Serialize;
+use tauri::State;
+
+use crate::fs_guard::FsGuardState;
+
+#[derive(Debug, Serialize)]
+pub struct GitFileStatus {
+    pub path: String,
+    pub relative_path: String,
+    /// Change recorded in the index: "added", "modified", "deleted", "renamed", "typechange"
+    pub index_status: Option<String>,
+    /// Change in the working tree: "untracked", "modified", "deleted", "renamed", "typechange"
+    pub worktree_status: Option<String>,
+    pub conflicted: bool,
+}
+
+/// A changed region of the buffer for the editor gutter, in 1-based line numbers
+#[derive(Debug, Serialize)]
+pub struct LineChange {
+    /// "added", "modified" or "deleted"
+    pub kind: String,
+    pub start_line: u32,
+    /// Inclusive; equal to `start_line` for deletions
+    pub end_line: u32,
+    /// For deletions, the number of lines removed below `start_line` (0 = top of file)
+    pub deleted_lines: u32,
+}
+
+fn open_repo(path: &Path) -> Result<Repository, String> {
+    Repository::discover(path).map_err(|e| format!("Failed to open git repository: {}", e.message()))
+}
+
+fn workdir(repo: &Repository) -> Result<&Path, String> {
+    repo.workdir()
+        .ok_or_else(|| "Bare repositories are not supported".to_string())
+}
+
+/// Converts an absolute or workdir-relative path into a path relative to the repository workdir
+pub fn relative_to_workdir(repo: &Repository, path: &str) -> Result<PathBuf, String> {
+    let workdir = workdir(repo)?;
+    let path = Path::new(path);
+    if path.is_relative() {
+        return Ok(path.to_path_buf());
+    }
+
+    // Compare canonical forms so symlinked roots (e.g. /tmp on macOS) still match
+    let canonical_workdir = workdir.canonicalize().unwrap_or_else(|_| workdir.to_path_buf());
+    let canonical_path = match path.canonicalize() {
+        Ok(p) => p,
+        // Deleted files can't be canonicalized, so canonicalize their parent instead
+        Err(_) => match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => parent
+                .canonicalize()
+                .map(|p| p.join(name))
+                .unwrap_or_else(|_| path.to_path_buf()),
+            _ => path.to_path_buf(),
+        },
+    };
+
+    canonical_path
+        .strip_prefix(&canonical_workdir)
+        .or_else(|_| path.strip_prefix(workdir))
+        .map(|p| p.to_path_buf())
+        .map_err(|_| format!("Path is outside the repository: {}", path.display()))
+}
+
+fn index_status(status: Status) -> Option<String> {
+    let s = if status.is_index_new() {
+        "added"
+    } else if status.is_index_modified() {
+        "modified"
+    } else if status.is_index_deleted() {
+        "deleted"
+    } else if status.is_index_renamed() {
+        "renamed"
+    } else if status.is_index_typechange() {
+        "typechange"
+    } else {
+        return None;
+    };
+    Some(s.to_string())
+}
+
+fn worktree_status(status: Status) -> Option<String> {
+    let s = if status.is_wt_new() {
+        "untracked"
+    } else if status.is_wt_modified() {
+        "modified"
+    } else if status.is_wt_deleted() {
+        "deleted"
+    } else if status.is_wt_renamed() {
+        "renamed"
+    } else if status.is_wt_typechange() {
+        "typechange"
+    } else {
+        return None;
+    };
+    Some(s.to_string())
+}
+
+#[tauri::command]
+pub async fn git_status(guard: State<'_, FsGuardState>, repo_path: String) -> Result<Vec<GitFileStatus>, String> {
+    let repo_path = guard.check(&repo_path)?;
+    let repo = open_repo(&repo_path)?;
+    let workdir = workdir(&repo)?.to_path_buf();
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true);
+
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .map_err(|e| format!("Failed to get git status: {}", e.message()))?;
+
+    let mut result = Vec::new();
+    for entry in statuses.iter() {
+        let status = entry.status();
+        if status.is_ignored() {
+            continue;
+        }
+        let relative_path = match entry.path() {
+            Some(p) => p.to_string(),
+            None => continue,
+        };
+        result.push(GitFileStatus {
+            path: workdir.join(&relative_path).to_string_lossy().to_string(),
+            relative_path,
+            index_status: index_status(status),
+            worktree_status: worktree_status(status),
+            conflicted: status.is_conflicted(),
+        });
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn git_stage(guard: State<'_, FsGuardState>, repo_path: String, paths: Vec<String>) -> Result<(), String> {
+    let repo_path = guard.check(&repo_path)?;
+    let repo = open_repo(&repo_path)?;
+    let workdir = workdir(&repo)?.to_path_buf();
+    let mut index = repo
+        .index()
+        .map_err(|e| format!("Failed to read index: {}", e.message()))?;
+
+    for path in &paths {
+        let relative = relative_to_workdir(&repo, path)?;
+        if workdir.join(&relative).exists() {
+            index
+                .add_path(&relative)
+                .map_err(|e| format!("Failed to stage {}: {}", relative.display(), e.message()))?;
+        } else {
+            // Staging a deleted file means removing it from the index
+            index
+                .remove_path(&relative)
+                .map_err(|e| format!("Failed to stage {}: {}", relative.display(), e.message()))?;
+        }
+    }
+
+    index
+        .write()
+        .map_err(|e| format!("Failed to write index: {}", e.message()))
+}
+
+#[tauri::command]
+pub async fn git_unstage(guard: State<'_, FsGuardState>, repo_path: String, paths: Vec<String>) -> Result<(), String> {
+    let repo_path = guard.check(&repo_path)?;
+    let repo = open_repo(&repo_path)?;
+    let relative: Vec<PathBuf> = paths
+        .iter()
+        .map(|p| relative_to_workdir(&repo, p))
+        .collect::<Result<_, _>>()?;
+
+    let head = repo.head();
+    match head {
+        Ok(head) => {
+            let head_commit = head
+                .peel_to_commit()
+                .map_err(|e| format!("Failed to resolve HEAD: {}", e.message()))?;
+            repo.reset_default(Some(head_commit.as_object()), relative.iter())
+                .map_err(|e| format!("Failed to unstage: {}", e.message()))
+        }
+        // No commits yet: unstaging means dropping the entries from the index
+        Err(e) if e.code() == ErrorCode::UnbornBranch => {
+            let mut index = repo
+                .index()
+                .map_err(|e| format!("Failed to read index: {}", e.message()))?;
+            for path in &relative {
+                let _ = index.remove_path(path);
+            }
+            index
+                .write()
+                .map_err(|e| format!("Failed to write index: {}", e.message()))
+        }
+        Err(e) => Err(format!("Failed to resolve HEAD: {}", e.message())),
+    }
+}
+
+#[tauri::command]
+pub async fn git_commit(guard: State<'_, FsGuardState>, repo_path: String, message: String) -> Result<String, String> {
+    let repo_path = guard.check(&repo_path)?;
+    if message.trim().is_empty() {
+        return Err("Commit message is empty".to_string());
+    }
+
+    let repo = open_repo(&repo_path)?;
+    let signature = repo
+        .signature()
+        .map_err(|e| format!("Git user.name and user.email must be configured: {}", e.message()))?;
+
+    let mut index = repo
+        .index()
+        .map_err(|e| format!("Failed to read index: {}", e.message()))?;
+    let tree_id = index
+        .write_tree()
+        .map_err(|e| format!("Failed to write tree: {}", e.message()))?;
+    let tree = repo
+        .find_tree(tree_id)
+        .map_err(|e| format!("Failed to find tree: {}", e.message()))?;
+
+    let parent = match repo.head() {
+        Ok(head) => Some(
+            head.peel_to_commit()
+                .map_err(|e| format!("Failed to resolve HEAD: {}", e.message()))?,
+        ),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(format!("Failed to resolve HEAD: {}", e.message())),
+    };
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    let oid = repo
+        .commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)
+        .map_err(|e| format!("Failed to commit: {}", e.message()))?;
+
+    Ok(oid.to_string())
+}
+
+/// Returns a unified diff for one file, either index-vs-HEAD (`staged`) or
+/// working tree-vs-index.
+#[tauri::command]
+pub async fn git_diff_file(
+    guard: State<'_, FsGuardState>,
+    repo_path: String,
+    path: String,
+    staged: Option<bool>,
+) -> Result<String, String> {
+    let repo_path = guard.check(&repo_path)?;
+    let repo = open_repo(&repo_path)?;
+    let relative = relative_to_workdir(&repo, &path)?;
+
+    let mut opts = DiffOptions::new();
+    opts.pathspec(&relative)
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .show_untracked_content(true);
+
+    let diff = if staged.unwrap_or(false) {
+        let head_tree = match repo.head() {
+            Ok(head) => Some(
+                head.peel_to_tree()
+                    .map_err(|e| format!("Failed to resolve HEAD: {}", e.message()))?,
+            ),
+            Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+            Err(e) => return Err(format!("Failed to resolve HEAD: {}", e.message())),
+        };
+        repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))
+    } else {
+        repo.diff_index_to_workdir(None, Some(&mut opts))
+    }
+    .map_err(|e| format!("Failed to compute diff: {}", e.message()))?;
+
+    let mut patch = String::new();
+    diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+        match line.origin() {
+            '+' | '-' | ' ' => patch.push(line.origin()),
+            _ => {}
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })
+    .map_err(|e| format!("Failed to format diff: {}", e.message()))?;
+
+    Ok(patch)
+}
+
+/// Returns the checked-out branch name, or the short commit id when HEAD is detached.
+#[tauri::command]
+pub async fn git_current_branch(guard: State<'_, FsGuardState>, repo_path: String) -> Result<String, String> {
+    let repo_path = guard.check(&repo_path)?;
+    let repo = open_repo(&repo_path)?;
+
+    let head = repo.head();
+    match head {
+        Ok(head) => {
+            if head.is_branch() {
+                Ok(head.shorthand().unwrap_or("HEAD").to_string())
+            } else {
+                let oid = head
+                    .target()
+                    .ok_or_else(|| "HEAD has no target".to_string())?;
+                Ok(oid.to_string()[..7].to_string())
+            }
+        }
+        // A fresh repository: HEAD points at a branch that has no commits yet
+        Err(e) if e.code() == ErrorCode::UnbornBranch => {
+            let head = repo
+                .find_reference("HEAD")
+                .map_err(|e| format!("Failed to read HEAD: {}", e.message()))?;
+            let target = head.symbolic_target().unwrap_or("HEAD");
+            Ok(target.trim_start_matches("refs/heads/").to_string())
+        }
+        Err(e) => Err(format!("Failed to resolve HEAD: {}", e.message())),
+    }
+}
+
+/// Diffs an (unsaved) buffer against the file's contents at HEAD.
+/// Files not in HEAD are reported as entirely added.
+#[tauri::command]
+pub async fn git_line_diff(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    current_content: String,
+) -> Result<Vec<LineChange>, String> {
+    let file = guard.check(&path)?;
+    let repo = open_repo(file.parent().unwrap_or(&file))?;
+    let relative = relative_to_workdir(&repo, &file.to_string_lossy())?;
+
+    let head = repo.head();
+    let head_tree = match head {
+        Ok(head) => Some(
+            head.peel_to_tree()
+                .map_err(|e| format!("Failed to resolve HEAD: {}", e.message()))?,
+        ),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(format!("Failed to resolve HEAD: {}", e.message())),
+    };
+    let blob = match head_tree.as_ref().map(|tree| tree.get_path(&relative)) {
+        Some(Ok(entry)) => Some(
+            repo.find_blob(entry.id())
+                .map_err(|e| format!("Failed to read HEAD version: {}", e.message()))?,
+        ),
+        _ => None,
+    };
+    let old_content: &[u8] = blob.as_ref().map(|b| b.content()).unwrap_or(&[]);
+
+    let mut opts = DiffOptions::new();
+    opts.context_lines(0).force_text(true);
+    let patch = Patch::from_buffers(
+        old_content,
+        Some(&relative),
+        current_content.as_bytes(),
+        Some(&relative),
+        Some(&mut opts),
+    )
+    .map_err(|e| format!("Failed to compute diff: {}", e.message()))?;
+
+    let mut changes = Vec::new();
+    for i in 0..patch.num_hunks() {
+        let (hunk, _) = patch
+            .hunk(i)
+            .map_err(|e| format!("Failed to read diff hunk: {}", e.message()))?;
+        let (start, added, removed) = (hunk.new_start(), hunk.new_lines(), hunk.old_lines());
+        let change = if added == 0 {
+            // With zero context, new_start is the line just above the removed block
+            LineChange {
+                kind: "deleted".to_string(),
+                start_line: start,
+                end_line: start,
+                deleted_lines: removed,
+            }
+        } else {
+            LineChange {
+                kind: if removed == 0 { "added" } else { "modified" }.to_string(),
+                start_line: start,
+                end_line: start + added - 1,
+                deleted_lines: 0,
+            }
+        };
+        changes.push(change);
+    }
+
+    Ok(changes)
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitBranch {
+    /// Short name, e.g. "main" or "origin/main"
+    pub name: String,
+    pub is_remote: bool,
+    pub is_current: bool,
+    /// Short name of the upstream branch, for local branches that track one
+    pub upstream: Option<String>,
+    /// Commits not yet on / only on the upstream
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    pub commit_id: Option<String>,
+    pub commit_summary: Option<String>,
+    /// Unix seconds
+    pub commit_time: Option<i64>,
+}
+
+/// Tracked files with uncommitted changes (staged or not), relative to the workdir
+fn dirty_paths(repo: &Repository) -> Result<Vec<String>, String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .map_err(|e| format!("Failed to get git status: {}", e.message()))?;
+    Ok(statuses
+        .iter()
+        .filter(|entry| entry.status() != Status::CURRENT)
+        .filter_map(|entry| entry.path().map(str::to_string))
+        .collect())
+}
+
+fn dirty_error(paths: &[String]) -> String {
+    let shown: Vec<&str> = paths.iter().take(5).map(String::as_str).collect();
+    let more = if paths.len() > shown.len() {
+        format!(" and {} more", paths.len() - shown.len())
+    } else {
+        String::new()
+    };
+    format!(
+        "Working tree has uncommitted changes ({}{}); commit or stash them first",
+        shown.join(", "),
+        more
+    )
+}
+
+fn branch_info(repo: &Repository, branch: &Branch, kind: BranchType) -> Option<GitBranch> {
+    let name = branch.name().ok().flatten()?.to_string();
+    let commit = branch.get().peel_to_commit().ok();
+    let upstream = if kind == BranchType::Local { branch.upstream().ok() } else { None };
+    let (ahead, behind) = match (&commit, upstream.as_ref().and_then(|u| u.get().target())) {
+        (Some(commit), Some(upstream)) => match repo.graph_ahead_behind(commit.id(), upstream) {
+            Ok((ahead, behind)) => (Some(ahead), Some(behind)),
+            Err(_) => (None, None),
+        },
+        _ => (None, None),
+    };
+    Some(GitBranch {
+        is_remote: kind == BranchType::Remote,
+        is_current: branch.is_head(),
+        upstream: upstream.and_then(|u| u.name().ok().flatten().map(str::to_string)),
+        ahead,
+        behind,
+        commit_id: commit.as_ref().map(|c| c.id().to_string()[..7].to_string()),
+        commit_summary: commit.as_ref().and_then(|c| c.summary().map(str::to_string)),
+        commit_time: commit.as_ref().map(|c| c.time().seconds()),
+        name,
+    })
+}
+
+/// Local branches, then remote-tracking ones unless `include_remote` is false
+#[tauri::command]
+pub async fn git_list_branches(
+    guard: State<'_, FsGuardState>,
+    repo_path: String,
+    include_remote: Option<bool>,
+) -> Result<Vec<GitBranch>, String> {
+    let repo_path = guard.check(&repo_path)?;
+    let repo = open_repo(&repo_path)?;
+    let filter = if include_remote.unwrap_or(true) { None } else { Some(BranchType::Local) };
+    let branches = repo
+        .branches(filter)
+        .map_err(|e| format!("Failed to list branches: {}", e.message()))?;
+
+    let mut result: Vec<GitBranch> = branches
+        .flatten()
+        .filter_map(|(branch, kind)| branch_info(&repo, &branch, kind))
+        // "origin/HEAD" is an alias, not a branch of its own
+        .filter(|b| !(b.is_remote && b.name.ends_with("/HEAD")))
+        .collect();
+    result.sort_by(|a, b| a.is_remote.cmp(&b.is_remote).then_with(|| a.name.cmp(&b.name)));
+    Ok(result)
+}
+
+/// Creates `name` at `start_point` (any revision, HEAD by default) and
+/// optionally switches to it
+#[tauri::command]
+pub async fn git_create_branch(
+    guard: State<'_, FsGuardState>,
+    repo_path: String,
+    name: String,
+    start_point: Option<String>,
+    checkout: Option<bool>,
+) -> Result<GitBranch, String> {
+    let repo_path = guard.check(&repo_path)?;
+    let name = name.trim().to_string();
+    if !Branch::name_is_valid(&name).unwrap_or(false) {
+        return Err(format!("Invalid branch name: {}", name));
+    }
+    let repo = open_repo(&repo_path)?;
+    let revision = start_point.unwrap_or_else(|| "HEAD".to_string());
+    let commit = repo
+        .revparse_single(&revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Failed to resolve {}: {}", revision, e.message()))?;
+    let branch = repo
+        .branch(&name, &commit, false)
+        .map_err(|e| format!("Failed to create branch: {}", e.message()))?;
+
+    if checkout.unwrap_or(false) {
+        switch_to(&repo, &name, false)?;
+    }
+    let branch = repo
+        .find_branch(&name, BranchType::Local)
+        .unwrap_or(branch);
+    branch_info(&repo, &branch, BranchType::Local).ok_or_else(|| "Failed to read new branch".to_string())
+}
+
+fn switch_to(repo: &Repository, branch: &str, allow_dirty: bool) -> Result<(), String> {
+    let dirty = dirty_paths(repo)?;
+    if !dirty.is_empty() && !allow_dirty {
+        return Err(dirty_error(&dirty));
+    }
+
+    // A remote-tracking name ("origin/feature") gets a local branch that tracks it
+    let local = match repo.find_branch(branch, BranchType::Local) {
+        Ok(local) => local,
+        Err(_) => {
+            let remote = repo
+                .find_branch(branch, BranchType::Remote)
+                .map_err(|_| format!("Branch not found: {}", branch))?;
+            let commit = remote
+                .get()
+                .peel_to_commit()
+                .map_err(|e| format!("Failed to resolve {}: {}", branch, e.message()))?;
+            let local_name = branch.split_once('/').map_or(branch, |(_, rest)| rest);
+            let mut local = repo
+                .branch(local_name, &commit, false)
+                .map_err(|e| format!("Failed to create branch {}: {}", local_name, e.message()))?;
+            local
+                .set_upstream(Some(branch))
+                .map_err(|e| format!("Failed to set upstream: {}", e.message()))?;
+            local
+        }
+    };
+    let refname = local
+        .get()
+        .name()
+        .ok_or_else(|| "Branch name is not valid UTF-8".to_string())?
+        .to_string();
+    let target = local
+        .get()
+        .peel(ObjectType::Commit)
+        .map_err(|e| format!("Failed to resolve branch: {}", e.message()))?;
+
+    // Safe mode keeps local changes and refuses to overwrite any of them
+    repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| format!("Failed to check out {}: {}", branch, e.message()))?;
+    repo.set_head(&refname)
+        .map_err(|e| format!("Failed to update HEAD: {}", e.message()))
+}
+
+/// Switches to a local branch, or creates a tracking branch for a remote one.
+/// Refuses when tracked files have uncommitted changes unless `allow_dirty`
+/// is set, in which case changes are carried over if they don't conflict.
+#[tauri::command]
+pub async fn git_checkout(
+    guard: State<'_, FsGuardState>,
+    repo_path: String,
+    branch: String,
+    allow_dirty: Option<bool>,
+) -> Result<(), String> {
+    let repo_path = guard.check(&repo_path)?;
+    let repo = open_repo(&repo_path)?;
+    switch_to(&repo, &branch, allow_dirty.unwrap_or(false))
+}
+
+/// Deletes a local branch. Unless `force` is set, only branches whose commits
+/// are all reachable from HEAD or their upstream can be deleted.
+#[tauri::command]
+pub async fn git_delete_branch(
+    guard: State<'_, FsGuardState>,
+    repo_path: String,
+    name: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let repo_path = guard.check(&repo_path)?;
+    let repo = open_repo(&repo_path)?;
+    let mut branch = repo
+        .find_branch(&name, BranchType::Local)
+        .map_err(|_| format!("Branch not found: {}", name))?;
+    if branch.is_head() {
+        return Err("Cannot delete the checked-out branch".to_string());
+    }
+
+    if !force.unwrap_or(false) {
+        let tip = branch
+            .get()
+            .target()
+            .ok_or_else(|| "Branch has no target".to_string())?;
+        let merged_into = |target: Option<git2::Oid>| {
+            target.is_some_and(|t| t == tip || repo.graph_descendant_of(t, tip).unwrap_or(false))
+        };
+        let head = repo.head().ok().and_then(|h| h.target());
+        let upstream = branch.upstream().ok().and_then(|u| u.get().target());
+        if !merged_into(head) && !merged_into(upstream) {
+            return Err(format!("Branch {} is not fully merged; delete it with force to discard its commits", name));
+        }
+    }
+
+    branch
+        .delete()
+        .map_err(|e| format!("Failed to delete branch: {}", e.message()))
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitStash {
+    /// Position in the stash list; 0 is the most recent (stash@{0})
+    pub index: usize,
+    pub id: String,
+    pub message: String,
+    /// Unix seconds
+    pub time: Option<i64>,
+}
+
+fn stash_error(action: &str, e: git2::Error) -> String {
+    match e.code() {
+        ErrorCode::NotFound => "No such stash entry".to_string(),
+        ErrorCode::Conflict | ErrorCode::MergeConflict => {
+            format!("Failed to {} stash: it conflicts with local changes", action)
+        }
+        _ => format!("Failed to {} stash: {}", action, e.message()),
+    }
+}
+
+/// Shelves the working tree and index changes. Returns the stash commit id.
+#[tauri::command]
+pub async fn git_stash_save(
+    guard: State<'_, FsGuardState>,
+    repo_path: String,
+    message: Option<String>,
+    include_untracked: Option<bool>,
+) -> Result<String, String> {
+    let repo_path = guard.check(&repo_path)?;
+    let mut repo = open_repo(&repo_path)?;
+    let signature = repo
+        .signature()
+        .map_err(|e| format!("Git user.name and user.email must be configured: {}", e.message()))?;
+
+    let mut flags = StashFlags::DEFAULT;
+    if include_untracked.unwrap_or(false) {
+        flags |= StashFlags::INCLUDE_UNTRACKED;
+    }
+    let message = message.filter(|m| !m.trim().is_empty());
+    let oid = repo
+        .stash_save(&signature, message.as_deref().unwrap_or(""), Some(flags))
+        .map_err(|e| match e.code() {
+            ErrorCode::NotFound => "No local changes to stash".to_string(),
+            _ => format!("Failed to save stash: {}", e.message()),
+        })?;
+    Ok(oid.to_string())
+}
+
+#[tauri::command]
+pub async fn git_stash_list(guard: State<'_, FsGuardState>, repo_path: String) -> Result<Vec<GitStash>, String> {
+    let repo_path = guard.check(&repo_path)?;
+    let mut repo = open_repo(&repo_path)?;
+    let mut entries = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        entries.push((index, message.to_string(), *oid));
+        true
+    })
+    .map_err(|e| format!("Failed to list stashes: {}", e.message()))?;
+
+    Ok(entries
+        .into_iter()
+        .map(|(index, message, oid)| GitStash {
+            index,
+            id: oid.to_string(),
+            message,
+            time: repo.find_commit(oid).ok().map(|c| c.time().seconds()),
+        })
+        .collect())
+}
+
+/// Re-applies a stash on top of the working tree, keeping it in the list
+#[tauri::command]
+pub async fn git_stash_apply(guard: State<'_, FsGuardState>, repo_path: String, index: usize) -> Result<(), String> {
+    let repo_path = guard.check(&repo_path)?;
+    let mut repo = open_repo(&repo_path)?;
+    repo.stash_apply(index, None).map_err(|e| stash_error("apply", e))
+}
+
+/// Applies a stash and removes it from the list if it applied cleanly
+#[tauri::command]
+pub async fn git_stash_pop(guard: State<'_, FsGuardState>, repo_path: String, index: usize) -> Result<(), String> {
+    let repo_path = guard.check(&repo_path)?;
+    let mut repo = open_repo(&repo_path)?;
+    repo.stash_pop(index, None).map_err(|e| stash_error("pop", e))
+}
+
+#[tauri::command]
+pub async fn git_stash_drop(guard: State<'_, FsGuardState>, repo_path: String, index: usize) -> Result<(), String> {
+    let repo_path = guard.check(&repo_path)?;
+    let mut repo = open_repo(&repo_path)?;
+    repo.stash_drop(index).map_err(|e| stash_error("drop", e))
+}
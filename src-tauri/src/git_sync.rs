@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use git2::build::CheckoutBuilder;
+use git2::{
+    AutotagOption, BranchType, Cred, CredentialType, ErrorCode, FetchOptions, PushOptions, RemoteCallbacks,
+    Repository,
+};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::fs_guard::FsGuardState;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+// How long a credential prompt waits for the user before the operation fails
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+// Wrong passwords make libgit2 ask again; stop after this many prompts
+const MAX_PROMPTS: usize = 3;
+
+#[derive(Debug, Deserialize)]
+pub struct GitCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Credential prompts waiting for `git_provide_credentials`
+#[derive(Default)]
+pub struct GitSyncState {
+    prompts: Mutex<HashMap<String, mpsc::Sender<Option<GitCredentials>>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CredentialRequest {
+    request_id: String,
+    operation_id: String,
+    url: String,
+    username: Option<String>,
+    /// Set when the previous answer was rejected by the server
+    retry: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncProgress {
+    operation_id: String,
+    /// "receiving", "resolving" or "pushing"
+    phase: &'static str,
+    current: usize,
+    total: usize,
+    bytes: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FetchResult {
+    pub remote: String,
+    pub received_objects: usize,
+    pub received_bytes: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PullStatus {
+    UpToDate,
+    FastForward,
+    Merged,
+    /// The merge stopped with conflicts; resolve them and commit
+    Conflicts,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PullResult {
+    pub status: PullStatus,
+    pub conflicts: Vec<String>,
+    /// The new HEAD commit, if it moved
+    pub commit: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PushResult {
+    pub remote: String,
+    pub branch: String,
+    /// Refs the remote refused, with its reason (e.g. non-fast-forward)
+    pub rejected: Vec<String>,
+}
+
+struct Reporter {
+    app: AppHandle,
+    operation_id: String,
+    last_emit: Option<Instant>,
+}
+
+impl Reporter {
+    fn report(&mut self, phase: &'static str, current: usize, total: usize, bytes: usize) {
+        let done = total > 0 && current >= total;
+        if !done && self.last_emit.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+            return;
+        }
+        self.last_emit = Some(Instant::now());
+        let _ = self.app.emit(
+            "git-sync-progress",
+            SyncProgress {
+                operation_id: self.operation_id.clone(),
+                phase,
+                current,
+                total,
+                bytes,
+            },
+        );
+    }
+}
+
+/// Asks the frontend for a username and password and blocks until it answers
+fn prompt_credentials(app: &AppHandle, operation_id: &str, url: &str, username: Option<&str>, retry: bool) -> Option<GitCredentials> {
+    let state = app.state::<GitSyncState>();
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = mpsc::channel();
+    state.prompts.lock().ok()?.insert(request_id.clone(), tx);
+
+    let _ = app.emit(
+        "git-credential-request",
+        CredentialRequest {
+            request_id: request_id.clone(),
+            operation_id: operation_id.to_string(),
+            url: url.to_string(),
+            username: username.map(str::to_string),
+            retry,
+        },
+    );
+    let answer = rx.recv_timeout(PROMPT_TIMEOUT).ok().flatten();
+    if let Ok(mut prompts) = state.prompts.lock() {
+        prompts.remove(&request_id);
+    }
+    answer
+}
+
+/// Tries the SSH agent, then git's credential helpers, then asks the user
+fn callbacks<'a>(repo: &'a Repository, reporter: &'a Mutex<Reporter>) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut tried_agent = false;
+    let mut tried_helper = false;
+    let mut prompts = 0;
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(CredentialType::SSH_KEY) && !tried_agent {
+            tried_agent = true;
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if !tried_helper {
+                tried_helper = true;
+                if let Ok(config) = repo.config() {
+                    if let Ok(cred) = Cred::credential_helper(&config, url, username) {
+                        return Ok(cred);
+                    }
+                }
+            }
+            if prompts < MAX_PROMPTS {
+                prompts += 1;
+                let (app, operation_id) = match reporter.lock() {
+                    Ok(r) => (r.app.clone(), r.operation_id.clone()),
+                    Err(_) => return Err(git2::Error::from_str("Failed to lock state")),
+                };
+                return match prompt_credentials(&app, &operation_id, url, username, prompts > 1) {
+                    Some(c) => Cred::userpass_plaintext(&c.username, &c.password),
+                    None => Err(git2::Error::from_str("Authentication cancelled")),
+                };
+            }
+        }
+        if allowed.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+        Err(git2::Error::from_str("Authentication failed"))
+    });
+    callbacks.transfer_progress(|stats| {
+        if let Ok(mut r) = reporter.lock() {
+            if stats.received_objects() < stats.total_objects() {
+                r.report("receiving", stats.received_objects(), stats.total_objects(), stats.received_bytes());
+            } else {
+                r.report("resolving", stats.indexed_deltas(), stats.total_deltas(), stats.received_bytes());
+            }
+        }
+        true
+    });
+    callbacks.push_transfer_progress(|current, total, bytes| {
+        if let Ok(mut r) = reporter.lock() {
+            r.report("pushing", current, total, bytes);
+        }
+    });
+    callbacks
+}
+
+fn new_reporter(app: &AppHandle, operation_id: Option<String>) -> Mutex<Reporter> {
+    Mutex::new(Reporter {
+        app: app.clone(),
+        operation_id: operation_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        last_emit: None,
+    })
+}
+
+fn open_repo(path: &Path) -> Result<Repository, String> {
+    Repository::discover(path).map_err(|e| format!("Failed to open git repository: {}", e.message()))
+}
+
+/// The current branch's short name and its upstream (remote, branch on the remote), if any
+fn current_branch(repo: &Repository) -> Result<(String, Option<(String, String)>), String> {
+    let head = repo.head().map_err(|e| match e.code() {
+        ErrorCode::UnbornBranch => "The current branch has no commits yet".to_string(),
+        _ => format!("Failed to resolve HEAD: {}", e.message()),
+    })?;
+    if !head.is_branch() {
+        return Err("HEAD is detached; check out a branch first".to_string());
+    }
+    let name = head
+        .shorthand()
+        .ok_or_else(|| "Branch name is not valid UTF-8".to_string())?
+        .to_string();
+    let refname = format!("refs/heads/{}", name);
+
+    let upstream = repo.branch_upstream_remote(&refname).ok().and_then(|remote| {
+        let remote = remote.as_str()?.to_string();
+        let merge = repo.branch_upstream_merge(&refname).ok()?;
+        let merge = merge.as_str()?.strip_prefix("refs/heads/")?.to_string();
+        Some((remote, merge))
+    });
+    Ok((name, upstream))
+}
+
+fn default_remote(repo: &Repository, remote: Option<String>) -> Result<String, String> {
+    if let Some(remote) = remote.filter(|r| !r.is_empty()) {
+        return Ok(remote);
+    }
+    if let Ok((_, Some((remote, _)))) = current_branch(repo) {
+        return Ok(remote);
+    }
+    let remotes = repo
+        .remotes()
+        .map_err(|e| format!("Failed to list remotes: {}", e.message()))?;
+    let names: Vec<&str> = remotes.iter().flatten().collect();
+    match names.as_slice() {
+        [] => Err("Repository has no remotes".to_string()),
+        [only] => Ok(only.to_string()),
+        _ if names.contains(&"origin") => Ok("origin".to_string()),
+        _ => Err("Several remotes are configured; choose one".to_string()),
+    }
+}
+
+fn fetch_remote(repo: &Repository, remote_name: &str, reporter: &Mutex<Reporter>) -> Result<FetchResult, String> {
+    let mut remote = repo
+        .find_remote(remote_name)
+        .map_err(|e| format!("Failed to find remote {}: {}", remote_name, e.message()))?;
+    let mut options = FetchOptions::new();
+    options
+        .remote_callbacks(callbacks(repo, reporter))
+        .download_tags(AutotagOption::Auto)
+        .prune(git2::FetchPrune::On);
+    // An empty refspec list uses the remote's configured fetch refspecs
+    remote
+        .fetch::<&str>(&[], Some(&mut options), None)
+        .map_err(|e| format!("Failed to fetch {}: {}", remote_name, e.message()))?;
+
+    let stats = remote.stats();
+    Ok(FetchResult {
+        remote: remote_name.to_string(),
+        received_objects: stats.received_objects(),
+        received_bytes: stats.received_bytes(),
+    })
+}
+
+fn pull(repo: &Repository, reporter: &Mutex<Reporter>) -> Result<PullResult, String> {
+    let (branch, upstream) = current_branch(repo)?;
+    let (remote, remote_branch) =
+        upstream.ok_or_else(|| format!("Branch {} has no upstream; push it first or set one", branch))?;
+    fetch_remote(repo, &remote, reporter)?;
+
+    let tracking = repo
+        .find_branch(&format!("{}/{}", remote, remote_branch), BranchType::Remote)
+        .map_err(|e| format!("Failed to find {}/{}: {}", remote, remote_branch, e.message()))?;
+    let incoming = repo
+        .reference_to_annotated_commit(tracking.get())
+        .map_err(|e| format!("Failed to resolve upstream: {}", e.message()))?;
+    let (analysis, _) = repo
+        .merge_analysis(&[&incoming])
+        .map_err(|e| format!("Failed to analyse merge: {}", e.message()))?;
+
+    if analysis.is_up_to_date() {
+        return Ok(PullResult {
+            status: PullStatus::UpToDate,
+            conflicts: Vec::new(),
+            commit: None,
+        });
+    }
+
+    if analysis.is_fast_forward() {
+        let target = repo
+            .find_object(incoming.id(), None)
+            .map_err(|e| format!("Failed to resolve upstream: {}", e.message()))?;
+        // Safe mode refuses to overwrite local changes to files the pull touches
+        repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))
+            .map_err(|e| format!("Failed to update working tree: {}", e.message()))?;
+        let mut head = repo
+            .find_reference(&format!("refs/heads/{}", branch))
+            .map_err(|e| format!("Failed to find branch: {}", e.message()))?;
+        head.set_target(incoming.id(), &format!("pull: fast-forward to {}/{}", remote, remote_branch))
+            .map_err(|e| format!("Failed to update branch: {}", e.message()))?;
+        return Ok(PullResult {
+            status: PullStatus::FastForward,
+            conflicts: Vec::new(),
+            commit: Some(incoming.id().to_string()),
+        });
+    }
+
+    repo.merge(&[&incoming], None, Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| format!("Failed to merge: {}", e.message()))?;
+    let mut index = repo
+        .index()
+        .map_err(|e| format!("Failed to read index: {}", e.message()))?;
+    if index.has_conflicts() {
+        let conflicts = index
+            .conflicts()
+            .map_err(|e| format!("Failed to read conflicts: {}", e.message()))?
+            .flatten()
+            .filter_map(|c| c.our.or(c.their).or(c.ancestor))
+            .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+            .collect();
+        return Ok(PullResult {
+            status: PullStatus::Conflicts,
+            conflicts,
+            commit: None,
+        });
+    }
+
+    let signature = repo
+        .signature()
+        .map_err(|e| format!("Git user.name and user.email must be configured: {}", e.message()))?;
+    let tree_id = index
+        .write_tree()
+        .map_err(|e| format!("Failed to write tree: {}", e.message()))?;
+    let tree = repo
+        .find_tree(tree_id)
+        .map_err(|e| format!("Failed to find tree: {}", e.message()))?;
+    let ours = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .map_err(|e| format!("Failed to resolve HEAD: {}", e.message()))?;
+    let theirs = repo
+        .find_commit(incoming.id())
+        .map_err(|e| format!("Failed to resolve upstream: {}", e.message()))?;
+    let message = format!("Merge branch '{}' of {} into {}", remote_branch, remote, branch);
+    let oid = repo
+        .commit(Some("HEAD"), &signature, &signature, &message, &tree, &[&ours, &theirs])
+        .map_err(|e| format!("Failed to commit merge: {}", e.message()))?;
+    let _ = repo.cleanup_state();
+
+    Ok(PullResult {
+        status: PullStatus::Merged,
+        conflicts: Vec::new(),
+        commit: Some(oid.to_string()),
+    })
+}
+
+fn push(
+    repo: &Repository,
+    remote: Option<String>,
+    set_upstream: bool,
+    force: bool,
+    reporter: &Mutex<Reporter>,
+) -> Result<PushResult, String> {
+    let (branch, upstream) = current_branch(repo)?;
+    let (remote_name, remote_branch) = match (remote.filter(|r| !r.is_empty()), upstream) {
+        (Some(remote), Some((up_remote, up_branch))) if remote == up_remote => (remote, up_branch),
+        (Some(remote), _) => (remote, branch.clone()),
+        (None, Some(upstream)) => upstream,
+        (None, None) => (default_remote(repo, None)?, branch.clone()),
+    };
+
+    let mut remote = repo
+        .find_remote(&remote_name)
+        .map_err(|e| format!("Failed to find remote {}: {}", remote_name, e.message()))?;
+    let rejected = Mutex::new(Vec::new());
+    let mut callbacks = callbacks(repo, reporter);
+    callbacks.push_update_reference(|refname, status| {
+        if let (Some(reason), Ok(mut rejected)) = (status, rejected.lock()) {
+            rejected.push(format!("{}: {}", refname, reason));
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+
+    let refspec = format!(
+        "{}refs/heads/{}:refs/heads/{}",
+        if force { "+" } else { "" },
+        branch,
+        remote_branch
+    );
+    remote
+        .push(&[refspec.as_str()], Some(&mut options))
+        .map_err(|e| format!("Failed to push to {}: {}", remote_name, e.message()))?;
+    drop(options);
+
+    let rejected = rejected.into_inner().unwrap_or_default();
+    if set_upstream && rejected.is_empty() {
+        let mut local = repo
+            .find_branch(&branch, BranchType::Local)
+            .map_err(|e| format!("Failed to find branch: {}", e.message()))?;
+        local
+            .set_upstream(Some(&format!("{}/{}", remote_name, remote_branch)))
+            .map_err(|e| format!("Failed to set upstream: {}", e.message()))?;
+    }
+    Ok(PushResult {
+        remote: remote_name,
+        branch: remote_branch,
+        rejected,
+    })
+}
+
+/// Fetches from `remote` (the current branch's upstream remote, or origin).
+/// Emits `git-sync-progress` and, when a password is needed, `git-credential-request`.
+#[tauri::command]
+pub async fn git_fetch(
+    app: AppHandle,
+    guard: State<'_, FsGuardState>,
+    repo_path: String,
+    remote: Option<String>,
+    operation_id: Option<String>,
+) -> Result<FetchResult, String> {
+    let repo_path = guard.check(&repo_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = open_repo(&repo_path)?;
+        let remote = default_remote(&repo, remote)?;
+        fetch_remote(&repo, &remote, &new_reporter(&app, operation_id))
+    })
+    .await
+    .map_err(|e| format!("Fetch task failed: {}", e))?
+}
+
+/// Fetches the current branch's upstream and fast-forwards or merges it.
+/// Conflicts are reported, leaving the repository mid-merge for the user to resolve.
+#[tauri::command]
+pub async fn git_pull(
+    app: AppHandle,
+    guard: State<'_, FsGuardState>,
+    repo_path: String,
+    operation_id: Option<String>,
+) -> Result<PullResult, String> {
+    let repo_path = guard.check(&repo_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = open_repo(&repo_path)?;
+        pull(&repo, &new_reporter(&app, operation_id))
+    })
+    .await
+    .map_err(|e| format!("Pull task failed: {}", e))?
+}
+
+/// Pushes the current branch to its upstream, or to a same-named branch on
+/// `remote`. `set_upstream` records that branch as the upstream afterwards.
+#[tauri::command]
+pub async fn git_push(
+    app: AppHandle,
+    guard: State<'_, FsGuardState>,
+    repo_path: String,
+    remote: Option<String>,
+    set_upstream: Option<bool>,
+    force: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<PushResult, String> {
+    let repo_path = guard.check(&repo_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = open_repo(&repo_path)?;
+        push(
+            &repo,
+            remote,
+            set_upstream.unwrap_or(false),
+            force.unwrap_or(false),
+            &new_reporter(&app, operation_id),
+        )
+    })
+    .await
+    .map_err(|e| format!("Push task failed: {}", e))?
+}
+
+/// Answers a `git-credential-request`; None cancels the operation
+#[tauri::command]
+pub async fn git_provide_credentials(
+    state: State<'_, GitSyncState>,
+    request_id: String,
+    credentials: Option<GitCredentials>,
+) -> Result<(), String> {
+    let sender = state
+        .prompts
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .remove(&request_id)
+        .ok_or_else(|| "Credential request is no longer pending".to_string())?;
+    let _ = sender.send(credentials);
+    Ok(())
+}
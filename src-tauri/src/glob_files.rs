@@ -0,0 +1,108 @@
+//! Resolves glob patterns against a workspace, for features like "open all
+//! *.test.ts" or export filters. Patterns are relative to the root with `/`
+//! separators; like .gitignore, one without a `/` matches at any depth and
+//! a leading `/` anchors it to the root.
+
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde::Serialize;
+use tauri::State;
+
+use crate::fs_guard::FsGuardState;
+
+// Keeps a stray `**` from sending the whole disk to the webview
+const DEFAULT_LIMIT: usize = 10_000;
+
+#[derive(Debug, Serialize)]
+pub struct GlobFile {
+    pub path: String,
+    pub relative_path: String,
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let normalized = match pattern.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if pattern.contains('/') => pattern.to_string(),
+            None => format!("**/{}", pattern),
+        };
+        let glob = GlobBuilder::new(&normalized)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| format!("Invalid patterns: {}", e))
+}
+
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(root)
+        .ok()
+        .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+}
+
+fn walk(root: &Path, include: &GlobSet, exclude: &GlobSet, include_ignored: bool, limit: usize) -> Vec<GlobFile> {
+    let prune_root = root.to_path_buf();
+    let prune = exclude.clone();
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .git_ignore(!include_ignored)
+        .git_global(!include_ignored)
+        .git_exclude(!include_ignored)
+        .ignore(!include_ignored)
+        // Excluded folders are not descended into at all
+        .filter_entry(move |entry| {
+            entry.file_name() != ".git"
+                && !(entry.file_type().is_some_and(|t| t.is_dir())
+                    && relative(&prune_root, entry.path()).is_some_and(|rel| prune.is_match(rel)))
+        })
+        .build();
+
+    let mut files: Vec<GlobFile> = walker
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            let relative_path = relative(root, entry.path())?;
+            (include.is_match(&relative_path) && !exclude.is_match(&relative_path)).then(|| GlobFile {
+                path: entry.path().to_string_lossy().to_string(),
+                relative_path,
+            })
+        })
+        .take(limit)
+        .collect();
+    files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    files
+}
+
+/// Files under `root` matching any of `patterns` and none of
+/// `exclude_patterns`, sorted by relative path. Gitignored files are skipped
+/// unless `include_ignored` is set; at most `limit` (default 10,000) files
+/// are returned.
+#[tauri::command]
+pub async fn glob_files(
+    guard: State<'_, FsGuardState>,
+    root: String,
+    patterns: Vec<String>,
+    exclude_patterns: Option<Vec<String>>,
+    include_ignored: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<GlobFile>, String> {
+    let root = guard.check(&root)?;
+    if !root.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    let include = glob_set(&patterns)?;
+    let exclude = glob_set(&exclude_patterns.unwrap_or_default())?;
+    if include.is_empty() {
+        return Ok(Vec::new());
+    }
+    let include_ignored = include_ignored.unwrap_or(false);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+    tauri::async_runtime::spawn_blocking(move || walk(&root, &include, &exclude, include_ignored, limit))
+        .await
+        .map_err(|e| format!("Glob failed: {}", e))
+}
@@ -0,0 +1,150 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::file_info::system_time_ms;
+use crate::fs_guard::FsGuardState;
+
+// A page is meant to fill a screen, not to load the file
+const MAX_PAGE_LEN: u64 = 64 * 1024;
+// Patches are for small edits; anything larger should go through save_file
+const MAX_PATCH_LEN: usize = 64 * 1024;
+const DEFAULT_BYTES_PER_ROW: usize = 16;
+
+#[derive(Debug, Serialize)]
+pub struct HexRow {
+    pub offset: u64,
+    /// Space-separated lowercase hex pairs, e.g. "48 65 6c 6c 6f"
+    pub hex: String,
+    /// Printable ASCII as-is, everything else as '.'
+    pub ascii: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HexPage {
+    /// Start of the page, rounded down to a row boundary
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+    pub rows: Vec<HexRow>,
+    pub next_offset: u64,
+    pub total_size: u64,
+    pub eof: bool,
+    /// Pass back to `write_file_hex_patch` to detect changes made meanwhile
+    pub modified_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HexPatch {
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+}
+
+fn layout(offset: u64, bytes: &[u8], bytes_per_row: usize) -> Vec<HexRow> {
+    bytes
+        .chunks(bytes_per_row)
+        .enumerate()
+        .map(|(i, row)| HexRow {
+            offset: offset + (i * bytes_per_row) as u64,
+            hex: row.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" "),
+            ascii: row
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect(),
+        })
+        .collect()
+}
+
+fn read_page(path: &Path, offset: u64, length: u64, bytes_per_row: usize) -> Result<HexPage, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let metadata = file.metadata().map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let total_size = metadata.len();
+
+    let offset = (offset.min(total_size) / bytes_per_row as u64) * bytes_per_row as u64;
+    let length = length.min(MAX_PAGE_LEN).min(total_size - offset);
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek: {}", e))?;
+    let mut bytes = Vec::with_capacity(length as usize);
+    Read::by_ref(&mut file)
+        .take(length)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let next_offset = offset + bytes.len() as u64;
+    Ok(HexPage {
+        offset,
+        rows: layout(offset, &bytes, bytes_per_row),
+        bytes,
+        next_offset,
+        total_size,
+        eof: next_offset >= total_size,
+        modified_ms: system_time_ms(metadata.modified()),
+    })
+}
+
+fn apply_patches(path: &Path, patches: &[HexPatch], expected_modified_ms: Option<u64>) -> Result<Option<u64>, String> {
+    let patched: usize = patches.iter().map(|p| p.bytes.len()).sum();
+    if patched > MAX_PATCH_LEN {
+        return Err(format!("Patch too large ({} bytes, at most {})", patched, MAX_PATCH_LEN));
+    }
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let metadata = file.metadata().map_err(|e| format!("Failed to read metadata: {}", e))?;
+    if expected_modified_ms.is_some_and(|expected| system_time_ms(metadata.modified()) != Some(expected)) {
+        return Err("File was modified on disk since it was read".to_string());
+    }
+    // Overwrite only: the file never grows or shrinks, so offsets stay valid
+    let total_size = metadata.len();
+    if let Some(p) = patches.iter().find(|p| p.offset.checked_add(p.bytes.len() as u64).is_none_or(|end| end > total_size)) {
+        return Err(format!("Patch at offset {} runs past the end of the file", p.offset));
+    }
+
+    for patch in patches {
+        file.seek(SeekFrom::Start(patch.offset))
+            .map_err(|e| format!("Failed to seek: {}", e))?;
+        file.write_all(&patch.bytes)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+    file.sync_all().map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let metadata = file.metadata().map_err(|e| format!("Failed to read metadata: {}", e))?;
+    Ok(system_time_ms(metadata.modified()))
+}
+
+/// Reads up to `length` bytes (64 KiB at most) from `offset` laid out as hex
+/// rows with an ASCII column. Continue with `next_offset` until `eof`.
+#[tauri::command]
+pub async fn read_file_hex(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    offset: u64,
+    length: u64,
+    bytes_per_row: Option<usize>,
+) -> Result<HexPage, String> {
+    let path = guard.check(&path)?;
+    let bytes_per_row = bytes_per_row.unwrap_or(DEFAULT_BYTES_PER_ROW).clamp(1, 64);
+    tauri::async_runtime::spawn_blocking(move || read_page(&path, offset, length, bytes_per_row))
+        .await
+        .map_err(|e| format!("Read task failed: {}", e))?
+}
+
+/// Overwrites bytes in place without changing the file's length. Fails if
+/// the file changed since `expected_modified_ms`; returns the new value.
+#[tauri::command]
+pub async fn write_file_hex_patch(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    patches: Vec<HexPatch>,
+    expected_modified_ms: Option<u64>,
+) -> Result<Option<u64>, String> {
+    let path = guard.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || apply_patches(&path, &patches, expected_modified_ms))
+        .await
+        .map_err(|e| format!("Write task failed: {}", e))?
+}
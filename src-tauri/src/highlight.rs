@@ -0,0 +1,396 @@
+use serde::Serialize;
+
+// Requests for more lines than this are clamped so one call stays cheap
+const MAX_HIGHLIGHT_LINES: usize = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Comment,
+    Number,
+    Constant,
+    Type,
+    Function,
+}
+
+impl TokenKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenKind::Keyword => "keyword",
+            TokenKind::String => "string",
+            TokenKind::Comment => "comment",
+            TokenKind::Number => "number",
+            TokenKind::Constant => "constant",
+            TokenKind::Type => "type",
+            TokenKind::Function => "function",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenSpan {
+    /// 0-based line number
+    pub line: usize,
+    /// 0-based start column in characters
+    pub start: usize,
+    /// 0-based end column in characters (exclusive)
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HighlightResult {
+    pub spans: Vec<TokenSpan>,
+    pub start_line: usize,
+    /// Exclusive; less than requested when the document is shorter
+    pub end_line: usize,
+    pub total_lines: usize,
+}
+
+struct StringRule {
+    open: &'static str,
+    close: &'static str,
+    multiline: bool,
+}
+
+struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    strings: &'static [StringRule],
+    keywords: &'static [&'static str],
+    constants: &'static [&'static str],
+}
+
+const C_STRINGS: &[StringRule] = &[
+    StringRule { open: "\"", close: "\"", multiline: false },
+    StringRule { open: "'", close: "'", multiline: false },
+];
+
+const RUST: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    // Lifetimes look like char literals, so only double quotes are strings
+    strings: &[StringRule { open: "\"", close: "\"", multiline: true }],
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn",
+        "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self",
+        "Self", "static", "struct", "super", "trait", "type", "unsafe", "use", "where", "while",
+    ],
+    constants: &["true", "false", "None", "Some", "Ok", "Err"],
+};
+
+const GO: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    strings: &[
+        StringRule { open: "\"", close: "\"", multiline: false },
+        StringRule { open: "'", close: "'", multiline: false },
+        StringRule { open: "`", close: "`", multiline: true },
+    ],
+    keywords: &[
+        "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough", "for", "func",
+        "go", "goto", "if", "import", "interface", "map", "package", "range", "return", "select", "struct",
+        "switch", "type", "var",
+    ],
+    constants: &["true", "false", "nil", "iota"],
+};
+
+const JAVASCRIPT: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    strings: &[
+        StringRule { open: "\"", close: "\"", multiline: false },
+        StringRule { open: "'", close: "'", multiline: false },
+        StringRule { open: "`", close: "`", multiline: true },
+    ],
+    keywords: &[
+        "abstract", "as", "async", "await", "break", "case", "catch", "class", "const", "continue", "debugger",
+        "declare", "default", "delete", "do", "else", "enum", "export", "extends", "finally", "for", "from",
+        "function", "get", "if", "implements", "import", "in", "instanceof", "interface", "keyof", "let", "new",
+        "of", "private", "protected", "public", "readonly", "return", "set", "static", "super", "switch", "this",
+        "throw", "try", "type", "typeof", "var", "void", "while", "yield",
+    ],
+    constants: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    strings: &[
+        StringRule { open: "\"\"\"", close: "\"\"\"", multiline: true },
+        StringRule { open: "'''", close: "'''", multiline: true },
+        StringRule { open: "\"", close: "\"", multiline: false },
+        StringRule { open: "'", close: "'", multiline: false },
+    ],
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else",
+        "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "match", "case",
+        "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield", "self",
+    ],
+    constants: &["True", "False", "None"],
+};
+
+const C_LIKE: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    strings: C_STRINGS,
+    keywords: &[
+        "auto", "break", "case", "catch", "class", "const", "constexpr", "continue", "default", "delete", "do",
+        "else", "enum", "explicit", "extern", "for", "friend", "goto", "if", "inline", "namespace", "new",
+        "operator", "private", "protected", "public", "register", "return", "sizeof", "static", "struct",
+        "switch", "template", "this", "throw", "try", "typedef", "typename", "union", "using", "virtual",
+        "volatile", "while", "#include", "#define", "#ifdef", "#ifndef", "#endif", "#if", "#else", "#pragma",
+    ],
+    constants: &["true", "false", "NULL", "nullptr"],
+};
+
+const JAVA: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    strings: C_STRINGS,
+    keywords: &[
+        "abstract", "assert", "break", "case", "catch", "class", "continue", "default", "do", "else", "enum",
+        "extends", "final", "finally", "for", "if", "implements", "import", "instanceof", "interface", "native",
+        "new", "package", "private", "protected", "public", "record", "return", "static", "super", "switch",
+        "synchronized", "this", "throw", "throws", "try", "var", "void", "volatile", "while",
+    ],
+    constants: &["true", "false", "null"],
+};
+
+const JSON: Syntax = Syntax {
+    line_comments: &[],
+    block_comment: None,
+    strings: &[StringRule { open: "\"", close: "\"", multiline: false }],
+    keywords: &[],
+    constants: &["true", "false", "null"],
+};
+
+const SHELL: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    strings: C_STRINGS,
+    keywords: &[
+        "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac", "in",
+        "function", "return", "local", "export", "readonly",
+    ],
+    constants: &["true", "false"],
+};
+
+fn syntax_for(language: &str) -> Option<&'static Syntax> {
+    match language {
+        "rust" => Some(&RUST),
+        "go" => Some(&GO),
+        "javascript" | "typescript" | "jsx" | "tsx" => Some(&JAVASCRIPT),
+        "python" => Some(&PYTHON),
+        "c" | "cpp" | "csharp" => Some(&C_LIKE),
+        "java" => Some(&JAVA),
+        "json" => Some(&JSON),
+        "shell" | "bash" | "sh" => Some(&SHELL),
+        _ => None,
+    }
+}
+
+pub fn is_supported(language: &str) -> bool {
+    syntax_for(language).is_some()
+}
+
+/// What a line starts inside of, carried over from the previous line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineState {
+    Normal,
+    BlockComment,
+    /// Index into `Syntax::strings`
+    String(usize),
+}
+
+fn starts_with_at(chars: &[char], at: usize, pattern: &str) -> bool {
+    pattern
+        .chars()
+        .enumerate()
+        .all(|(k, p)| chars.get(at + k) == Some(&p))
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$' || c == '#'
+}
+
+/// Position just after the closing delimiter, or None if the line ends first
+fn find_close(chars: &[char], from: usize, close: &str, escapes: bool) -> Option<usize> {
+    let mut i = from;
+    while i < chars.len() {
+        if escapes && chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if starts_with_at(chars, i, close) {
+            return Some(i + close.chars().count());
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Scans one line, pushing its spans when `spans` is given, and returns the state for the next line
+fn scan_line(
+    syntax: &Syntax,
+    line_no: usize,
+    line: &str,
+    state: LineState,
+    mut spans: Option<&mut Vec<TokenSpan>>,
+) -> LineState {
+    let chars: Vec<char> = line.chars().collect();
+    let mut push = |start: usize, end: usize, kind: TokenKind| {
+        if let Some(spans) = spans.as_deref_mut() {
+            if end > start {
+                spans.push(TokenSpan { line: line_no, start, end, kind });
+            }
+        }
+    };
+
+    let mut i = 0;
+    // Finish whatever construct the previous line left open
+    match state {
+        LineState::Normal => {}
+        LineState::BlockComment => {
+            let (_, close) = syntax.block_comment.expect("block comment state without delimiters");
+            match find_close(&chars, 0, close, false) {
+                Some(end) => {
+                    push(0, end, TokenKind::Comment);
+                    i = end;
+                }
+                None => {
+                    push(0, chars.len(), TokenKind::Comment);
+                    return state;
+                }
+            }
+        }
+        LineState::String(index) => {
+            let rule = &syntax.strings[index];
+            match find_close(&chars, 0, rule.close, true) {
+                Some(end) => {
+                    push(0, end, TokenKind::String);
+                    i = end;
+                }
+                None => {
+                    push(0, chars.len(), TokenKind::String);
+                    return state;
+                }
+            }
+        }
+    }
+
+    'scan: while i < chars.len() {
+        let c = chars[i];
+
+        if syntax.line_comments.iter().any(|p| starts_with_at(&chars, i, p)) {
+            push(i, chars.len(), TokenKind::Comment);
+            return LineState::Normal;
+        }
+
+        if let Some((open, close)) = syntax.block_comment {
+            if starts_with_at(&chars, i, open) {
+                match find_close(&chars, i + open.chars().count(), close, false) {
+                    Some(end) => {
+                        push(i, end, TokenKind::Comment);
+                        i = end;
+                        continue;
+                    }
+                    None => {
+                        push(i, chars.len(), TokenKind::Comment);
+                        return LineState::BlockComment;
+                    }
+                }
+            }
+        }
+
+        for (index, rule) in syntax.strings.iter().enumerate() {
+            if starts_with_at(&chars, i, rule.open) {
+                match find_close(&chars, i + rule.open.chars().count(), rule.close, true) {
+                    Some(end) => {
+                        push(i, end, TokenKind::String);
+                        i = end;
+                        continue 'scan;
+                    }
+                    None => {
+                        push(i, chars.len(), TokenKind::String);
+                        return if rule.multiline { LineState::String(index) } else { LineState::Normal };
+                    }
+                }
+            }
+        }
+
+        if c.is_ascii_digit() && (i == 0 || !is_ident_char(chars[i - 1])) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.' || chars[i] == '_') {
+                i += 1;
+            }
+            push(start, i, TokenKind::Number);
+            continue;
+        }
+
+        if is_ident_char(c) {
+            let start = i;
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if syntax.keywords.contains(&word.as_str()) {
+                push(start, i, TokenKind::Keyword);
+            } else if syntax.constants.contains(&word.as_str()) {
+                push(start, i, TokenKind::Constant);
+            } else if chars.get(i) == Some(&'(') {
+                push(start, i, TokenKind::Function);
+            } else if word.starts_with(|ch: char| ch.is_uppercase()) {
+                push(start, i, TokenKind::Type);
+            }
+            continue;
+        }
+
+        i += 1;
+    }
+    LineState::Normal
+}
+
+/// Token spans for lines `start_line..end_line` of `content`. Lines before
+/// the range are only scanned for open comments/strings, so highlighting the
+/// visible part of a huge file stays cheap.
+pub fn highlight(content: &str, language: &str, start_line: usize, end_line: usize) -> HighlightResult {
+    let total_lines = content.lines().count();
+    let end_line = end_line.min(total_lines).min(start_line.saturating_add(MAX_HIGHLIGHT_LINES));
+    let mut spans = Vec::new();
+
+    if let Some(syntax) = syntax_for(language) {
+        let mut state = LineState::Normal;
+        for (line_no, line) in content.lines().enumerate().take(end_line) {
+            let out = if line_no >= start_line { Some(&mut spans) } else { None };
+            state = scan_line(syntax, line_no, line, state, out);
+        }
+    }
+
+    HighlightResult {
+        spans,
+        start_line: start_line.min(end_line),
+        end_line,
+        total_lines,
+    }
+}
+
+/// Highlights a line range of a document; `language` uses the editor's file type ids
+#[tauri::command]
+pub async fn highlight_range(
+    content: String,
+    language: String,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+) -> Result<HighlightResult, String> {
+    if !is_supported(&language) {
+        return Err(format!("No highlighter for language: {}", language));
+    }
+    let start_line = start_line.unwrap_or(0);
+    let end_line = end_line.unwrap_or(usize::MAX);
+    tauri::async_runtime::spawn_blocking(move || highlight(&content, &language, start_line, end_line))
+        .await
+        .map_err(|e| format!("Highlight task failed: {}", e))
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::atomic_write;
+use crate::encoding::{self, DecodedText};
+use crate::file_info::{now_ms, sha256_hex};
+use crate::file_version::{self, FileVersion};
+use crate::fs_guard::FsGuardState;
+use crate::links::LinkIndexState;
+
+// Layout: app_data_dir/history/<file id>/{path,<timestamp>-<hash>-<size>.gz}
+const HISTORY_DIR: &str = "history";
+const PATH_FILE: &str = "path";
+const MAX_ENTRY_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const MAX_ENTRIES_PER_FILE: usize = 50;
+/// Compressed bytes kept per file before the oldest snapshots are dropped
+const MAX_BYTES_PER_FILE: u64 = 10 * 1024 * 1024;
+/// Larger saves are not snapshotted at all
+const MAX_SNAPSHOT_SIZE: usize = 5 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub path: String,
+    /// When the save happened (ms since epoch)
+    pub timestamp: u64,
+    /// Uncompressed size in bytes
+    pub size: u64,
+    /// Prefix of the SHA-256 of the saved bytes
+    pub hash: String,
+}
+
+struct StoredEntry {
+    file: PathBuf,
+    timestamp: u64,
+    hash: String,
+    size: u64,
+    stored_size: u64,
+}
+
+fn history_root(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|d| d.join(HISTORY_DIR))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+fn file_id(path: &Path) -> String {
+    sha256_hex(path.to_string_lossy().as_bytes())[..32].to_string()
+}
+
+/// Snapshots of one file, newest first
+fn entries_in(dir: &Path) -> Vec<StoredEntry> {
+    let Ok(read) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<StoredEntry> = read
+        .filter_map(|e| e.ok())
+        .filter_map(|entry| {
+            let file = entry.path();
+            let stem = file.file_name()?.to_str()?.strip_suffix(".gz")?.to_string();
+            let mut parts = stem.splitn(3, '-');
+            let timestamp = parts.next()?.parse().ok()?;
+            let hash = parts.next()?.to_string();
+            let size = parts.next()?.parse().ok()?;
+            let stored_size = entry.metadata().ok()?.len();
+            Some(StoredEntry {
+                file,
+                timestamp,
+                hash,
+                size,
+                stored_size,
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    entries
+}
+
+/// Drops snapshots past the age, count or size limits; the newest one is
+/// always kept so a file never loses its whole history
+fn prune(dir: &Path) {
+    let cutoff = now_ms().saturating_sub(MAX_ENTRY_AGE.as_millis() as u64);
+    let mut total = 0;
+    for (i, entry) in entries_in(dir).into_iter().enumerate() {
+        total += entry.stored_size;
+        if i > 0 && (i >= MAX_ENTRIES_PER_FILE || entry.timestamp < cutoff || total > MAX_BYTES_PER_FILE) {
+            let _ = fs::remove_file(&entry.file);
+        }
+    }
+}
+
+/// Appends a snapshot of `bytes` as just saved to `path`. Saving the same
+/// contents twice in a row only records it once.
+pub fn record(app: &AppHandle, path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if bytes.len() > MAX_SNAPSHOT_SIZE {
+        return Ok(());
+    }
+    let dir = history_root(app)?.join(file_id(path));
+    let hash = sha256_hex(bytes)[..16].to_string();
+    if entries_in(&dir).first().is_some_and(|latest| latest.hash == hash) {
+        return Ok(());
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create history directory: {}", e))?;
+    let path_file = dir.join(PATH_FILE);
+    if !path_file.exists() {
+        fs::write(&path_file, path.to_string_lossy().as_bytes())
+            .map_err(|e| format!("Failed to write history: {}", e))?;
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(bytes)
+        .map_err(|e| format!("Failed to compress snapshot: {}", e))?;
+    let compressed = encoder
+        .finish()
+        .map_err(|e| format!("Failed to compress snapshot: {}", e))?;
+    let file = dir.join(format!("{}-{}-{}.gz", now_ms(), hash, bytes.len()));
+    atomic_write::write_atomic(&file, &compressed).map_err(|e| format!("Failed to write history: {}", e))?;
+
+    prune(&dir);
+    Ok(())
+}
+
+/// Splits an entry id into its snapshot file and the document it belongs to
+fn resolve_entry(app: &AppHandle, id: &str) -> Result<(PathBuf, PathBuf), String> {
+    // Ids come back from the frontend, so make sure they can't escape the folder
+    let invalid = || format!("Invalid history entry id: {}", id);
+    let (file_id, stem) = id.split_once('-').ok_or_else(invalid)?;
+    if file_id.is_empty()
+        || !file_id.chars().all(|c| c.is_ascii_hexdigit())
+        || !stem.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+    {
+        return Err(invalid());
+    }
+    let dir = history_root(app)?.join(file_id);
+    let file = dir.join(format!("{}.gz", stem));
+    if !file.is_file() {
+        return Err(format!("No history entry with id: {}", id));
+    }
+    let document = fs::read_to_string(dir.join(PATH_FILE)).map_err(|e| format!("Failed to read history: {}", e))?;
+    Ok((file, PathBuf::from(document)))
+}
+
+fn read_entry(file: &Path) -> Result<Vec<u8>, String> {
+    let compressed = fs::read(file).map_err(|e| format!("Failed to read history: {}", e))?;
+    let mut bytes = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to decompress snapshot: {}", e))?;
+    Ok(bytes)
+}
+
+/// Saved versions of a file, newest first
+#[tauri::command]
+pub async fn list_file_history(app_handle: AppHandle, path: String) -> Result<Vec<HistoryEntry>, String> {
+    let file_id = file_id(Path::new(&path));
+    let dir = history_root(&app_handle)?.join(&file_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        entries_in(&dir)
+            .into_iter()
+            .map(|entry| {
+                let stem = entry.file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                HistoryEntry {
+                    id: format!("{}-{}", file_id, stem),
+                    path: path.clone(),
+                    timestamp: entry.timestamp,
+                    size: entry.size,
+                    hash: entry.hash,
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("History task failed: {}", e))
+}
+
+/// Contents of a snapshot, decoded like `read_file` does
+#[tauri::command]
+pub async fn read_file_history_entry(app_handle: AppHandle, id: String) -> Result<DecodedText, String> {
+    let (file, _) = resolve_entry(&app_handle, &id)?;
+    let bytes = read_entry(&file)?;
+    Ok(encoding::decode(&bytes, None))
+}
+
+/// Writes a snapshot back over its file; the restore itself becomes the
+/// newest history entry
+#[tauri::command]
+pub async fn restore_file_history_entry(
+    app_handle: AppHandle,
+    link_index: State<'_, LinkIndexState>,
+    guard: State<'_, FsGuardState>,
+    id: String,
+) -> Result<FileVersion, String> {
+    let (file, document) = resolve_entry(&app_handle, &id)?;
+    let document = guard.check(&document.to_string_lossy())?;
+    let bytes = read_entry(&file)?;
+    atomic_write::write_atomic(&document, &bytes).map_err(|e| format!("Failed to restore file: {}", e))?;
+    link_index.refresh(&document);
+    let _ = record(&app_handle, &document, &bytes);
+    Ok(file_version::version_of(&document, &bytes))
+}
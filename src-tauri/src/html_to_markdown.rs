@@ -0,0 +1,899 @@
+//! Converts HTML from the clipboard (browsers, Word, Google Docs) to
+//! markdown, so rich text pastes as clean markdown rather than plain text.
+
+use url::Url;
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    /// One declaration of the inline style, lowercased
+    fn style(&self, property: &str) -> Option<String> {
+        self.attr("style")?.split(';').find_map(|declaration| {
+            let (key, value) = declaration.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(property)
+                .then(|| value.trim().to_ascii_lowercase())
+        })
+    }
+}
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// Elements whose content is never part of the pasted text
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "head", "script", "style", "title", "template", "noscript", "iframe", "object", "svg", "button", "select",
+    "textarea",
+];
+
+const BLOCK_ELEMENTS: &[&str] = &[
+    "html", "body", "p", "div", "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol", "li", "blockquote", "pre", "table",
+    "thead", "tbody", "tfoot", "tr", "td", "th", "hr", "section", "article", "header", "footer", "main", "nav",
+    "aside", "figure", "figcaption", "dl", "dt", "dd", "address", "details", "summary", "fieldset", "center",
+];
+
+fn is_block(name: &str) -> bool {
+    BLOCK_ELEMENTS.contains(&name)
+}
+
+fn is_hidden(element: &Element) -> bool {
+    SKIPPED_ELEMENTS.contains(&element.name.as_str())
+        || element.style("display").as_deref() == Some("none")
+        // Word's list markers, which become real markdown list markers instead
+        || element.style("mso-list").as_deref() == Some("ignore")
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "hellip" => '…',
+        "mdash" => '—',
+        "ndash" => '–',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "bull" => '•',
+        "middot" => '·',
+        "times" => '×',
+        "divide" => '÷',
+        "plusmn" => '±',
+        "deg" => '°',
+        "sect" => '§',
+        "para" => '¶',
+        "euro" => '€',
+        "pound" => '£',
+        "yen" => '¥',
+        "cent" => '¢',
+        "shy" => '\u{ad}',
+        _ => return None,
+    })
+}
+
+pub(crate) fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 32)
+            .and_then(|end| entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Parses the start tag at the beginning of `s`; returns the element, whether
+/// it was self-closing, and how many bytes it took
+fn parse_tag(s: &str) -> (Element, bool, usize) {
+    let bytes = s.as_bytes();
+    let name_end = s[1..]
+        .find(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+        .map(|n| n + 1)
+        .unwrap_or(s.len());
+    let mut element = Element {
+        name: s[1..name_end].to_ascii_lowercase(),
+        ..Element::default()
+    };
+    let skip_spaces = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    };
+
+    let mut i = name_end;
+    loop {
+        i = skip_spaces(i);
+        match bytes.get(i) {
+            None => return (element, false, i),
+            Some(b'>') => return (element, false, i + 1),
+            Some(b'/') if bytes.get(i + 1) == Some(&b'>') => return (element, true, i + 2),
+            Some(b'/' | b'=') => {
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !matches!(bytes[i], b'=' | b'>' | b'/') {
+            i += 1;
+        }
+        let name = s[start..i].to_ascii_lowercase();
+        i = skip_spaces(i);
+        let mut value = String::new();
+        if bytes.get(i) == Some(&b'=') {
+            i = skip_spaces(i + 1);
+            match bytes.get(i) {
+                Some(&quote @ (b'"' | b'\'')) => {
+                    let end = s[i + 1..].find(quote as char).map(|e| i + 1 + e).unwrap_or(s.len());
+                    value = decode_entities(&s[i + 1..end]);
+                    i = (end + 1).min(s.len());
+                }
+                _ => {
+                    let start = i;
+                    while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                        i += 1;
+                    }
+                    value = decode_entities(&s[start..i]);
+                }
+            }
+        }
+        element.attrs.push((name, value));
+    }
+}
+
+fn pop_into_parent(stack: &mut Vec<Element>) {
+    if let Some(element) = stack.pop() {
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(Node::Element(element));
+        }
+    }
+}
+
+/// Closes the nearest open element named in `names`, unless one of
+/// `boundaries` is opened after it
+fn close_implied(stack: &mut Vec<Element>, names: &[&str], boundaries: &[&str]) {
+    for pos in (1..stack.len()).rev() {
+        let name = stack[pos].name.as_str();
+        if names.contains(&name) {
+            while stack.len() > pos {
+                pop_into_parent(stack);
+            }
+            return;
+        }
+        if boundaries.contains(&name) {
+            return;
+        }
+    }
+}
+
+/// Builds an element tree, tolerating the unclosed tags and stray end tags
+/// that clipboard HTML is full of
+fn parse(html: &str) -> Element {
+    let mut stack = vec![Element::default()];
+    let mut rest = html;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map(|end| &after[end + 3..]).unwrap_or("");
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map(|end| &rest[end + 1..]).unwrap_or("");
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').unwrap_or(after.len());
+            let name = after[..end].trim().to_ascii_lowercase();
+            if let Some(pos) = stack.iter().rposition(|e| e.name == name).filter(|&pos| pos > 0) {
+                while stack.len() > pos {
+                    pop_into_parent(&mut stack);
+                }
+            }
+            rest = after.get(end + 1..).unwrap_or("");
+            continue;
+        }
+        if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let (element, self_closing, len) = parse_tag(rest);
+            rest = &rest[len..];
+            if element.name == "script" || element.name == "style" {
+                // Raw text: skip straight to the end tag
+                let close = format!("</{}", element.name);
+                rest = match rest.to_ascii_lowercase().find(&close) {
+                    Some(pos) => rest[pos..].find('>').map(|end| &rest[pos + end + 1..]).unwrap_or(""),
+                    None => "",
+                };
+                continue;
+            }
+            match element.name.as_str() {
+                "li" => close_implied(&mut stack, &["li"], &["ul", "ol"]),
+                "dt" | "dd" => close_implied(&mut stack, &["dt", "dd"], &["dl"]),
+                "tr" => close_implied(&mut stack, &["tr"], &["table", "thead", "tbody", "tfoot"]),
+                "td" | "th" => close_implied(&mut stack, &["td", "th"], &["tr", "table"]),
+                "p" => close_implied(&mut stack, &["p"], &["div", "li", "td", "th", "blockquote", "body"]),
+                _ => {}
+            }
+            let void = self_closing || VOID_ELEMENTS.contains(&element.name.as_str());
+            stack.push(element);
+            if void {
+                pop_into_parent(&mut stack);
+            }
+            continue;
+        }
+        let first = rest.chars().next().map_or(1, char::len_utf8);
+        let end = rest[first..].find('<').map(|i| i + first).unwrap_or(rest.len());
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(Node::Text(decode_entities(&rest[..end])));
+        }
+        rest = &rest[end..];
+    }
+    while stack.len() > 1 {
+        pop_into_parent(&mut stack);
+    }
+    stack.pop().unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Markdown output
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BlockKind {
+    Paragraph,
+    List,
+    /// One paragraph of a Word list, which Word writes as a styled <p>
+    WordListItem,
+}
+
+struct Block {
+    text: String,
+    kind: BlockKind,
+}
+
+/// Blocks separated by blank lines, except where that would split a list
+fn join(blocks: &[Block], in_list_item: bool) -> String {
+    let mut out = String::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            let previous = blocks[i - 1].kind;
+            let tight = (previous == BlockKind::WordListItem && block.kind == BlockKind::WordListItem)
+                || (in_list_item && block.kind == BlockKind::List);
+            out.push_str(if tight { "\n" } else { "\n\n" });
+        }
+        out.push_str(&block.text);
+    }
+    out
+}
+
+/// Formatting already applied by an enclosing element
+#[derive(Debug, Clone, Copy, Default)]
+struct Inline {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+    link: bool,
+}
+
+/// Appends text with whitespace collapsed, escaping markdown syntax unless `raw`
+fn push_text(out: &mut String, text: &str, raw: bool) {
+    let mut pending_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() || c == '\u{a0}' {
+            pending_space = true;
+            continue;
+        }
+        if pending_space && !out.ends_with([' ', '\n']) {
+            out.push(' ');
+        }
+        pending_space = false;
+        if !raw {
+            let escape = match c {
+                '\\' | '*' | '`' | '[' | ']' => true,
+                // snake_case words are safe; only word-initial underscores can open emphasis
+                '_' => !out.ends_with(|p: char| p.is_alphanumeric()),
+                _ => false,
+            };
+            if escape {
+                out.push('\\');
+            }
+        }
+        out.push(c);
+    }
+    if pending_space && !out.ends_with([' ', '\n']) {
+        out.push(' ');
+    }
+}
+
+/// Appends converted inline markup, not doubling a space at the seam
+fn append(out: &mut String, text: &str) {
+    match text.strip_prefix(' ') {
+        Some(rest) if out.is_empty() || out.ends_with([' ', '\n']) => out.push_str(rest),
+        _ => out.push_str(text),
+    }
+}
+
+/// `marker` around `text`, with surrounding whitespace kept outside so the
+/// emphasis still opens and closes
+fn wrap(text: &str, marker: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let lead = if text.starts_with(char::is_whitespace) { " " } else { "" };
+    let trail = if text.ends_with(char::is_whitespace) { " " } else { "" };
+    format!("{}{}{}{}{}", lead, marker, trimmed, marker, trail)
+}
+
+fn code_span(text: &str) -> String {
+    let text = text.trim();
+    if text.is_empty() {
+        return String::new();
+    }
+    let longest = longest_run(text, '`');
+    let fence = "`".repeat(longest + 1);
+    let pad = if text.starts_with('`') || text.ends_with('`') { " " } else { "" };
+    format!("{}{}{}{}{}", fence, pad, text, pad, fence)
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    for ch in text.chars() {
+        run = if ch == c { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    longest
+}
+
+/// A link destination, in angle brackets when it has spaces or parentheses
+fn destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+/// Escapes what would turn the start of a line into a heading, quote or list
+fn escape_line_start(line: &str) -> String {
+    if line.starts_with(['#', '>', '-', '+', '=']) {
+        return format!("\\{}", line);
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && line[digits..].starts_with(['.', ')']) {
+        return format!("{}\\{}", &line[..digits], &line[digits..]);
+    }
+    line.to_string()
+}
+
+/// Inline output to paragraph text; `<br>`s arrive as newlines and become
+/// backslash hard breaks, which survive trailing-whitespace trimming
+fn finish_paragraph(raw: &str) -> String {
+    raw.split('\n')
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(escape_line_start)
+        .collect::<Vec<_>>()
+        .join("\\\n")
+}
+
+/// Plain text of an element, keeping whitespace; for <pre>
+fn text_content(node: &Node, out: &mut String) {
+    match node {
+        Node::Text(text) => out.push_str(text),
+        Node::Element(element) if element.name == "br" => out.push('\n'),
+        Node::Element(element) if is_hidden(element) => {}
+        Node::Element(element) => {
+            for child in &element.children {
+                text_content(child, out);
+            }
+        }
+    }
+}
+
+fn contains_block(element: &Element) -> bool {
+    element.children.iter().any(|child| match child {
+        Node::Element(e) => is_block(&e.name) || contains_block(e),
+        Node::Text(_) => false,
+    })
+}
+
+/// Language of a code block from `class="language-rust"` and similar
+fn code_language(element: &Element) -> Option<String> {
+    let from_class = |e: &Element| {
+        e.attr("class")?.split_whitespace().find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+                .or_else(|| class.strip_prefix("highlight-source-"))
+                .map(str::to_string)
+        })
+    };
+    from_class(element).or_else(|| {
+        element.children.iter().find_map(|child| match child {
+            Node::Element(e) if e.name == "code" => from_class(e),
+            _ => None,
+        })
+    })
+}
+
+/// Nesting level of a Word list paragraph, from `mso-list: l0 level2 lfo1`
+fn word_list_level(element: &Element) -> Option<usize> {
+    let list = element.style("mso-list")?;
+    list.split_whitespace()
+        .find_map(|part| part.strip_prefix("level"))
+        .and_then(|level| level.parse().ok())
+}
+
+/// The marker Word wrote for a list paragraph, e.g. "1." or "·"
+fn word_list_marker(node: &Node) -> Option<String> {
+    let Node::Element(element) = node else {
+        return None;
+    };
+    if element.style("mso-list").as_deref() == Some("ignore") {
+        // text_content skips the marker itself, so start from its children
+        let mut text = String::new();
+        for child in &element.children {
+            text_content(child, &mut text);
+        }
+        return Some(text.trim_matches(|c: char| c.is_whitespace() || c == '\u{a0}').to_string());
+    }
+    element.children.iter().find_map(word_list_marker)
+}
+
+struct Converter {
+    base: Option<Url>,
+}
+
+impl Converter {
+    fn url(&self, raw: &str) -> String {
+        let raw = raw.trim();
+        match &self.base {
+            Some(base) => base.join(raw).map(|u| u.to_string()).unwrap_or_else(|_| raw.to_string()),
+            None => raw.to_string(),
+        }
+    }
+
+    /// Markdown blocks for `children`; runs of inline content become paragraphs
+    fn blocks(&self, children: &[Node], out: &mut Vec<Block>) {
+        let mut inline = String::new();
+        for child in children {
+            match child {
+                // Google Docs wraps whole documents in a <b>, so an inline
+                // element holding blocks is treated as a block container
+                Node::Element(element) if is_block(&element.name) || contains_block(element) => {
+                    self.flush(&mut inline, out);
+                    self.block(element, out);
+                }
+                _ => self.inline(child, Inline::default(), &mut inline),
+            }
+        }
+        self.flush(&mut inline, out);
+    }
+
+    fn flush(&self, inline: &mut String, out: &mut Vec<Block>) {
+        let text = finish_paragraph(inline);
+        if !text.is_empty() {
+            out.push(Block {
+                text,
+                kind: BlockKind::Paragraph,
+            });
+        }
+        inline.clear();
+    }
+
+    fn inline_children(&self, element: &Element, state: Inline) -> String {
+        let mut out = String::new();
+        for child in &element.children {
+            self.inline(child, state, &mut out);
+        }
+        out
+    }
+
+    fn inline(&self, node: &Node, state: Inline, out: &mut String) {
+        let element = match node {
+            Node::Text(text) => return push_text(out, text, state.code),
+            Node::Element(element) => element,
+        };
+        if is_hidden(element) {
+            return;
+        }
+        match element.name.as_str() {
+            "br" => out.push(if state.code { ' ' } else { '\n' }),
+            "img" => {
+                let Some(src) = element.attr("src").filter(|s| !s.trim().is_empty()) else {
+                    return;
+                };
+                let mut alt = String::new();
+                push_text(&mut alt, element.attr("alt").unwrap_or(""), false);
+                append(out, &format!("![{}]({})", alt.trim(), destination(&self.url(src))));
+            }
+            "input" if element.attr("type").is_some_and(|t| t.eq_ignore_ascii_case("checkbox")) => {
+                append(out, if element.attr("checked").is_some() { "[x] " } else { "[ ] " });
+            }
+            "a" if !state.link && !state.code => {
+                let content = self.inline_children(element, Inline { link: true, ..state });
+                let raw_href = element.attr("href").map(str::trim).unwrap_or("");
+                let href = element
+                    .attr("href")
+                    .map(|href| self.url(href))
+                    .filter(|href| !href.is_empty() && !href.to_ascii_lowercase().starts_with("javascript:"));
+                let Some(href) = href else {
+                    return append(out, &content);
+                };
+                let text = content.trim();
+                if text.is_empty() {
+                    // Named anchors and icon-only links
+                    return;
+                }
+                // Bare URLs and addresses become autolinks
+                if text == raw_href || text == href || Some(text) == href.strip_prefix("mailto:") {
+                    return append(out, &format!("<{}>", text));
+                }
+                let title = element
+                    .attr("title")
+                    .filter(|t| !t.is_empty())
+                    .map(|t| format!(" \"{}\"", t.replace('"', "\\\"")))
+                    .unwrap_or_default();
+                let lead = if content.starts_with(' ') { " " } else { "" };
+                let trail = if content.ends_with(' ') { " " } else { "" };
+                append(out, &format!("{}[{}]({}{}){}", lead, text, destination(&href), title, trail));
+            }
+            "code" | "kbd" | "samp" | "tt" if !state.code => {
+                let content = self.inline_children(element, Inline { code: true, ..state });
+                append(out, &code_span(&content));
+            }
+            _ => {
+                let weight = element.style("font-weight");
+                let bold = match weight.as_deref() {
+                    Some("normal" | "400" | "lighter") => false,
+                    Some("bold" | "bolder" | "600" | "700" | "800" | "900") => true,
+                    _ => matches!(element.name.as_str(), "b" | "strong"),
+                };
+                let italic = match element.style("font-style").as_deref() {
+                    Some("normal") => false,
+                    Some("italic" | "oblique") => true,
+                    _ => matches!(element.name.as_str(), "i" | "em" | "cite" | "dfn" | "var"),
+                };
+                let strike = matches!(element.name.as_str(), "del" | "s" | "strike")
+                    || element
+                        .style("text-decoration")
+                        .or_else(|| element.style("text-decoration-line"))
+                        .is_some_and(|d| d.contains("line-through"));
+
+                let add_bold = bold && !state.bold && !state.code;
+                let add_italic = italic && !state.italic && !state.code;
+                let add_strike = strike && !state.strike && !state.code;
+                let inner = Inline {
+                    bold: state.bold || bold,
+                    italic: state.italic || italic,
+                    strike: state.strike || strike,
+                    ..state
+                };
+                let mut text = self.inline_children(element, inner);
+                if add_strike {
+                    text = wrap(&text, "~~");
+                }
+                if add_italic {
+                    text = wrap(&text, "*");
+                }
+                if add_bold {
+                    text = wrap(&text, "**");
+                }
+                append(out, &text);
+            }
+        }
+    }
+
+    fn block(&self, element: &Element, out: &mut Vec<Block>) {
+        if is_hidden(element) {
+            return;
+        }
+        match element.name.as_str() {
+            name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+                // Headings are already bold; don't add ** around their runs
+                let state = Inline {
+                    bold: true,
+                    ..Inline::default()
+                };
+                let text = finish_paragraph(&self.inline_children(element, state)).replace("\\\n", " ");
+                if !text.is_empty() {
+                    let level = name[1..].parse().unwrap_or(1);
+                    out.push(Block {
+                        text: format!("{} {}", "#".repeat(level), text),
+                        kind: BlockKind::Paragraph,
+                    });
+                }
+            }
+            "p" if word_list_level(element).is_some() => {
+                let level = word_list_level(element).unwrap_or(1).max(1);
+                let marker = element.children.iter().find_map(word_list_marker).unwrap_or_default();
+                let ordered = marker.starts_with(|c: char| c.is_alphanumeric());
+                let text = finish_paragraph(&self.inline_children(element, Inline::default()));
+                out.push(Block {
+                    text: format!(
+                        "{}{} {}",
+                        "    ".repeat(level - 1),
+                        if ordered { "1." } else { "-" },
+                        text
+                    ),
+                    kind: BlockKind::WordListItem,
+                });
+            }
+            "hr" => out.push(Block {
+                text: "---".to_string(),
+                kind: BlockKind::Paragraph,
+            }),
+            "pre" => {
+                let mut text = String::new();
+                for child in &element.children {
+                    text_content(child, &mut text);
+                }
+                let text = text.strip_prefix('\n').unwrap_or(&text).trim_end();
+                if text.is_empty() {
+                    return;
+                }
+                let fence = "`".repeat(longest_run(text, '`').max(2) + 1);
+                out.push(Block {
+                    text: format!(
+                        "{}{}\n{}\n{}",
+                        fence,
+                        code_language(element).unwrap_or_default(),
+                        text,
+                        fence
+                    ),
+                    kind: BlockKind::Paragraph,
+                });
+            }
+            "blockquote" => {
+                let mut inner = Vec::new();
+                self.blocks(&element.children, &mut inner);
+                let text = join(&inner, false);
+                if !text.is_empty() {
+                    let quoted: Vec<String> = text
+                        .lines()
+                        .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                        .collect();
+                    out.push(Block {
+                        text: quoted.join("\n"),
+                        kind: BlockKind::Paragraph,
+                    });
+                }
+            }
+            "ul" | "ol" => self.list(element, out),
+            "table" => self.table(element, out),
+            _ => self.blocks(&element.children, out),
+        }
+    }
+
+    fn list(&self, element: &Element, out: &mut Vec<Block>) {
+        let ordered = element.name == "ol";
+        let start: u64 = element.attr("start").and_then(|s| s.trim().parse().ok()).unwrap_or(1);
+
+        let mut items: Vec<Vec<Block>> = Vec::new();
+        for child in &element.children {
+            let Node::Element(item) = child else {
+                continue;
+            };
+            if is_hidden(item) {
+                continue;
+            }
+            if item.name == "li" {
+                let mut blocks = Vec::new();
+                self.blocks(&item.children, &mut blocks);
+                items.push(blocks);
+            } else {
+                // A nested list written directly inside the list belongs to the previous item
+                if items.is_empty() {
+                    items.push(Vec::new());
+                }
+                if let Some(last) = items.last_mut() {
+                    self.block(item, last);
+                }
+            }
+        }
+        if items.is_empty() {
+            return;
+        }
+
+        let loose = items
+            .iter()
+            .any(|blocks| blocks.iter().filter(|b| b.kind != BlockKind::List).count() > 1);
+        let rendered: Vec<String> = items
+            .iter()
+            .enumerate()
+            .map(|(i, blocks)| {
+                let marker = if ordered { format!("{}.", start + i as u64) } else { "-".to_string() };
+                let indent = " ".repeat(marker.len() + 1);
+                let text = join(blocks, true);
+                let mut lines = text.lines();
+                let mut item = match lines.next() {
+                    Some(first) => format!("{} {}", marker, first),
+                    None => marker,
+                };
+                for line in lines {
+                    item.push('\n');
+                    if !line.is_empty() {
+                        item.push_str(&indent);
+                        item.push_str(line);
+                    }
+                }
+                item
+            })
+            .collect();
+        out.push(Block {
+            text: rendered.join(if loose { "\n\n" } else { "\n" }),
+            kind: BlockKind::List,
+        });
+    }
+
+    fn cell_text(&self, cell: &Element) -> String {
+        let mut blocks = Vec::new();
+        self.blocks(&cell.children, &mut blocks);
+        join(&blocks, false)
+            .replace("\\\n", " ")
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace('|', "\\|")
+    }
+
+    fn collect_rows<'a>(&self, element: &'a Element, rows: &mut Vec<Vec<&'a Element>>) {
+        for child in &element.children {
+            let Node::Element(child) = child else {
+                continue;
+            };
+            match child.name.as_str() {
+                "tr" => rows.push(
+                    child
+                        .children
+                        .iter()
+                        .filter_map(|cell| match cell {
+                            Node::Element(cell) if cell.name == "td" || cell.name == "th" => Some(cell),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                "thead" | "tbody" | "tfoot" => self.collect_rows(child, rows),
+                _ => {}
+            }
+        }
+    }
+
+    fn table(&self, element: &Element, out: &mut Vec<Block>) {
+        let mut rows = Vec::new();
+        self.collect_rows(element, &mut rows);
+        rows.retain(|row| !row.is_empty());
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if width == 0 {
+            return;
+        }
+
+        let alignments: Vec<&str> = (0..width)
+            .map(|i| {
+                let align = rows[0].get(i).and_then(|cell| {
+                    cell.attr("align")
+                        .map(str::to_ascii_lowercase)
+                        .or_else(|| cell.style("text-align"))
+                });
+                match align.as_deref() {
+                    Some("center") => ":---:",
+                    Some("right") => "---:",
+                    _ => "---",
+                }
+            })
+            .collect();
+
+        let mut lines = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let mut cells: Vec<String> = row.iter().map(|cell| self.cell_text(cell)).collect();
+            cells.resize(width, String::new());
+            lines.push(format!("| {} |", cells.join(" | ")));
+            // The first row is the header, whether or not it used <th>
+            if i == 0 {
+                lines.push(format!("| {} |", alignments.join(" | ")));
+            }
+        }
+        out.push(Block {
+            text: lines.join("\n"),
+            kind: BlockKind::Paragraph,
+        });
+    }
+}
+
+/// The copied part of clipboard HTML, and the page it came from if known.
+/// Windows prefixes the markup with a header ("Version:0.9", "SourceURL:...")
+/// and browsers mark the selection with StartFragment/EndFragment comments.
+fn clipboard_fragment(html: &str) -> (&str, Option<&str>) {
+    let has_header = html.starts_with("Version:");
+    let source_url = if has_header {
+        html.lines()
+            .take_while(|line| !line.trim_start().starts_with('<'))
+            .find_map(|line| line.strip_prefix("SourceURL:"))
+            .map(str::trim)
+    } else {
+        None
+    };
+    const START: &str = "<!--StartFragment-->";
+    const END: &str = "<!--EndFragment-->";
+    let fragment = match (html.find(START), html.find(END)) {
+        (Some(start), Some(end)) if start < end => &html[start + START.len()..end],
+        _ if has_header => html.find('<').map(|start| &html[start..]).unwrap_or(""),
+        _ => html,
+    };
+    (fragment, source_url)
+}
+
+/// Markdown for an HTML fragment. Relative links resolve against `base_url`,
+/// falling back to the source page recorded in the clipboard data.
+pub fn html_to_markdown(html: &str, base_url: Option<&str>) -> String {
+    let (fragment, source_url) = clipboard_fragment(html);
+    let converter = Converter {
+        base: base_url.or(source_url).and_then(|url| Url::parse(url).ok()),
+    };
+    let root = parse(fragment);
+    let mut blocks = Vec::new();
+    converter.blocks(&root.children, &mut blocks);
+    join(&blocks, false)
+}
+
+/// Converts rich text from the clipboard to markdown before it is inserted
+#[tauri::command]
+pub async fn convert_html_to_markdown(html: String, base_url: Option<String>) -> Result<String, String> {
+    Ok(html_to_markdown(&html, base_url.as_deref()))
+}
@@ -0,0 +1,481 @@
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use base64::{engine::general_purpose, Engine as _};
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use regex::Regex;
+use serde::Serialize;
+use tauri::State;
+
+use crate::fs_guard::FsGuardState;
+use crate::images::{info_from_bytes, le16, le24, le32, ImageFormat, ImageInfo, Thumbnail};
+
+// Elements that can run code or pull in other documents; dropped with their content
+const BLOCKED_ELEMENTS: &[&str] = &[
+    "script", "foreignobject", "iframe", "embed", "object", "audio", "video", "handler", "listener",
+];
+const MAX_SVG_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct SvgPreview {
+    /// The sanitized document, safe to inline in the webview
+    pub content: String,
+    /// What was stripped, e.g. "<script>" or "onload attribute"
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FrameInfo {
+    /// Position of the frame on the canvas; frames are stored, not composited
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    pub duration_ms: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnimationInfo {
+    pub format: ImageFormat,
+    pub animated: bool,
+    pub width: u32,
+    pub height: u32,
+    /// 0 loops forever; None plays once
+    pub loop_count: Option<u32>,
+    pub frames: Vec<FrameInfo>,
+}
+
+fn note(removed: &mut Vec<String>, what: String) {
+    if !removed.contains(&what) {
+        removed.push(what);
+    }
+}
+
+/// Only same-document fragments and inline raster data may be referenced;
+/// anything else could run script or leak that the file was opened
+fn is_safe_reference(value: &str) -> bool {
+    let value: String = value.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+    value.is_empty() || value.starts_with('#') || (value.starts_with("data:image/") && !value.starts_with("data:image/svg"))
+}
+
+fn clean_css(css: &str, removed: &mut Vec<String>) -> String {
+    static IMPORT: OnceLock<Regex> = OnceLock::new();
+    static URL: OnceLock<Regex> = OnceLock::new();
+    let import = IMPORT.get_or_init(|| Regex::new(r"(?i)@import[^;]*;?").unwrap());
+    let url = URL.get_or_init(|| Regex::new(r#"(?i)url\(\s*(?:"([^"]*)"|'([^']*)'|([^)]*))\s*\)"#).unwrap());
+
+    if import.is_match(css) {
+        note(removed, "@import rule".to_string());
+    }
+    let css = import.replace_all(css, "");
+    url.replace_all(&css, |caps: &regex::Captures| {
+        let target = caps.get(1).or(caps.get(2)).or(caps.get(3)).map_or("", |m| m.as_str());
+        if is_safe_reference(target) {
+            caps[0].to_string()
+        } else {
+            note(removed, "external url()".to_string());
+            "none".to_string()
+        }
+    })
+    .to_string()
+}
+
+fn local_name(start: &BytesStart) -> String {
+    String::from_utf8_lossy(start.local_name().as_ref()).to_lowercase()
+}
+
+/// Whether an `<animate>`/`<set>` would rewrite a link or event handler
+fn animates_unsafe_attribute(start: &BytesStart) -> bool {
+    start.attributes().flatten().any(|attr| {
+        attr.key.local_name().as_ref().eq_ignore_ascii_case(b"attributename")
+            && attr.unescape_value().is_ok_and(|v| {
+                let v = v.trim().to_lowercase();
+                v == "href" || v == "xlink:href" || v.starts_with("on")
+            })
+    })
+}
+
+fn is_blocked(start: &BytesStart) -> bool {
+    let name = local_name(start);
+    BLOCKED_ELEMENTS.contains(&name.as_str()) || (matches!(name.as_str(), "set" | "animate") && animates_unsafe_attribute(start))
+}
+
+/// Copies the element with event handlers, script URLs and external
+/// references removed
+fn clean_element(start: &BytesStart, removed: &mut Vec<String>) -> BytesStart<'static> {
+    let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+    let mut clean = BytesStart::new(name);
+    for attr in start.attributes().with_checks(false) {
+        let Ok(attr) = attr else {
+            note(removed, "malformed attribute".to_string());
+            continue;
+        };
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+        let local = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_lowercase();
+        // Undefined entities can't be checked, so they don't get through either
+        let Ok(value) = attr.unescape_value() else {
+            note(removed, format!("{} attribute", key));
+            continue;
+        };
+        let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+
+        if local.starts_with("on") {
+            note(removed, format!("{} attribute", local));
+        } else if local == "href" && !is_safe_reference(&value) {
+            note(removed, "external href".to_string());
+        } else if compact.contains("javascript:") || compact.contains("vbscript:") {
+            note(removed, format!("script URL in {}", key));
+        } else if local == "style" {
+            let css = clean_css(&value, removed);
+            clean.push_attribute((key.as_str(), css.as_str()));
+        } else {
+            clean.push_attribute(attr);
+        }
+    }
+    clean.into_owned()
+}
+
+/// Strips scripts, event handlers and external references from an SVG so it
+/// can be shown inline without executing anything
+pub fn sanitize_svg(text: &str) -> Result<SvgPreview, String> {
+    let mut reader = Reader::from_str(text);
+    let mut writer = Writer::new(Vec::new());
+    let mut removed = Vec::new();
+    // Depth inside an element that is being dropped with its content
+    let mut skipping = 0usize;
+    let mut in_style = false;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("Failed to parse SVG at byte {}: {}", reader.buffer_position(), e))?;
+        if skipping > 0 {
+            match event {
+                Event::Start(_) => skipping += 1,
+                Event::End(_) => skipping -= 1,
+                Event::Eof => break,
+                _ => {}
+            }
+            continue;
+        }
+
+        let out = match event {
+            Event::Start(start) if is_blocked(&start) => {
+                note(&mut removed, format!("<{}>", local_name(&start)));
+                skipping = 1;
+                continue;
+            }
+            Event::Empty(start) if is_blocked(&start) => {
+                note(&mut removed, format!("<{}>", local_name(&start)));
+                continue;
+            }
+            Event::Start(start) => {
+                in_style = local_name(&start) == "style";
+                Event::Start(clean_element(&start, &mut removed))
+            }
+            Event::Empty(start) => Event::Empty(clean_element(&start, &mut removed)),
+            Event::End(end) => {
+                in_style = false;
+                Event::End(end)
+            }
+            Event::Text(text) if in_style => {
+                let css = text.unescape().map_err(|e| format!("Failed to parse SVG style: {}", e))?;
+                Event::Text(BytesText::new(&clean_css(&css, &mut removed)).into_owned())
+            }
+            Event::CData(data) if in_style => {
+                let css = String::from_utf8_lossy(&data).to_string();
+                Event::Text(BytesText::new(&clean_css(&css, &mut removed)).into_owned())
+            }
+            // Doctypes can declare entities and stylesheet PIs load external CSS
+            Event::DocType(_) => {
+                note(&mut removed, "DOCTYPE".to_string());
+                continue;
+            }
+            Event::PI(_) => {
+                note(&mut removed, "processing instruction".to_string());
+                continue;
+            }
+            Event::Eof => break,
+            other => other,
+        };
+        writer
+            .write_event(out)
+            .map_err(|e| format!("Failed to write SVG: {}", e))?;
+    }
+
+    let content = String::from_utf8(writer.into_inner()).map_err(|e| format!("Failed to write SVG: {}", e))?;
+    Ok(SvgPreview { content, removed })
+}
+
+struct GifFrame {
+    /// Byte ranges of the graphic control extension and the image block
+    control: Option<(usize, usize)>,
+    image: (usize, usize),
+    info: FrameInfo,
+}
+
+/// End of a chain of GIF data sub-blocks starting at `at`
+fn sub_blocks_end(b: &[u8], mut at: usize) -> Option<usize> {
+    loop {
+        let len = *b.get(at)? as usize;
+        at += 1 + len;
+        if len == 0 {
+            return Some(at);
+        }
+    }
+}
+
+fn color_table_len(packed: u8) -> usize {
+    if packed & 0x80 != 0 {
+        3 << ((packed & 0x07) + 1)
+    } else {
+        0
+    }
+}
+
+/// Walks a GIF's blocks without decoding any pixels. Returns the length of
+/// the header (with global colour table), the loop count and the frames.
+fn parse_gif(b: &[u8]) -> Option<(usize, Option<u32>, Vec<GifFrame>)> {
+    let header_end = 13 + color_table_len(*b.get(10)?);
+    let mut at = header_end;
+    let mut loop_count = None;
+    let mut control = None;
+    let mut frames = Vec::new();
+
+    // A truncated file still yields the frames read so far
+    while let Some(&tag) = b.get(at) {
+        match tag {
+            0x21 => {
+                let Some(end) = sub_blocks_end(b, at + 2) else { break };
+                match b.get(at + 1) {
+                    Some(0xF9) => control = Some((at, end)),
+                    Some(0xFF) if b.get(at + 3..at + 14) == Some(b"NETSCAPE2.0") => loop_count = le16(b, at + 16),
+                    _ => {}
+                }
+                at = end;
+            }
+            0x2C => {
+                let (Some(left), Some(top), Some(width), Some(height), Some(&packed)) = (
+                    le16(b, at + 1),
+                    le16(b, at + 3),
+                    le16(b, at + 5),
+                    le16(b, at + 7),
+                    b.get(at + 9),
+                ) else {
+                    break;
+                };
+                // Colour table, then the LZW minimum code size byte
+                let data = at + 10 + color_table_len(packed) + 1;
+                let Some(end) = sub_blocks_end(b, data) else { break };
+                let duration_ms = control.and_then(|(start, _)| le16(b, start + 4)).unwrap_or(0) * 10;
+                frames.push(GifFrame {
+                    control: control.take(),
+                    image: (at, end),
+                    info: FrameInfo {
+                        left,
+                        top,
+                        width,
+                        height,
+                        duration_ms,
+                    },
+                });
+                at = end;
+            }
+            _ => break,
+        }
+    }
+    Some((header_end, loop_count, frames))
+}
+
+/// Rebuilds one frame as a still GIF with the original canvas and palette
+fn gif_frame(b: &[u8], header_end: usize, frame: &GifFrame) -> Vec<u8> {
+    let mut out = Vec::with_capacity(header_end + frame.image.1 - frame.image.0 + 16);
+    out.extend_from_slice(b"GIF89a");
+    out.extend_from_slice(&b[6..header_end]);
+    if let Some((start, end)) = frame.control {
+        let mut control = b[start..end].to_vec();
+        // Disposal only matters when another frame follows
+        control[3] &= !0x1C;
+        out.extend_from_slice(&control);
+    }
+    out.extend_from_slice(&b[frame.image.0..frame.image.1]);
+    out.push(0x3B);
+    out
+}
+
+struct WebpFrame {
+    /// Byte range of the frame's ALPH/VP8/VP8L chunks
+    data: (usize, usize),
+    has_alpha: bool,
+    info: FrameInfo,
+}
+
+/// Lists the frames of an animated WebP; None for still images
+fn parse_animated_webp(b: &[u8]) -> Option<(Option<u32>, Vec<WebpFrame>)> {
+    let mut at = 12;
+    let mut loop_count = None;
+    let mut frames = Vec::new();
+    while let (Some(fourcc), Some(size)) = (b.get(at..at + 4), le32(b, at + 4)) {
+        let data = at + 8;
+        let end = data + size as usize;
+        match fourcc {
+            b"VP8X" if b.get(data).is_some_and(|flags| flags & 0x02 == 0) => return None,
+            b"ANIM" => loop_count = le16(b, data + 4),
+            b"ANMF" => {
+                let frame = (|| {
+                    Some(WebpFrame {
+                        data: (data + 16, end.min(b.len())),
+                        has_alpha: b.get(data + 16..data + 20) == Some(b"ALPH"),
+                        info: FrameInfo {
+                            left: le24(b, data)? * 2,
+                            top: le24(b, data + 3)? * 2,
+                            width: le24(b, data + 6)? + 1,
+                            height: le24(b, data + 9)? + 1,
+                            duration_ms: le24(b, data + 12)?,
+                        },
+                    })
+                })();
+                let Some(frame) = frame else { break };
+                frames.push(frame);
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        at = end + (size as usize & 1);
+    }
+    if frames.is_empty() {
+        return None;
+    }
+    Some((loop_count, frames))
+}
+
+/// Wraps one animation frame's bitstream in its own RIFF container
+fn webp_frame(b: &[u8], frame: &WebpFrame) -> Vec<u8> {
+    let mut body = Vec::new();
+    if frame.has_alpha {
+        // ALPH chunks are only valid in the extended format
+        body.extend_from_slice(b"VP8X");
+        body.extend_from_slice(&10u32.to_le_bytes());
+        body.extend_from_slice(&[0x10, 0, 0, 0]);
+        body.extend_from_slice(&(frame.info.width - 1).to_le_bytes()[..3]);
+        body.extend_from_slice(&(frame.info.height - 1).to_le_bytes()[..3]);
+    }
+    body.extend_from_slice(&b[frame.data.0..frame.data.1]);
+
+    let mut out = Vec::with_capacity(body.len() + 12);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    out.extend_from_slice(b"WEBP");
+    out.extend_from_slice(&body);
+    out
+}
+
+fn read_image(path: &Path) -> Result<(Vec<u8>, ImageInfo), String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read image file: {}", e))?;
+    let info = info_from_bytes(&bytes)?;
+    Ok((bytes, info))
+}
+
+/// Returns the SVG with scripts, event handlers and external references
+/// removed, for inline previews
+#[tauri::command]
+pub async fn get_svg_preview(guard: State<'_, FsGuardState>, path: String) -> Result<SvgPreview, String> {
+    let path = guard.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let size = fs::metadata(&path).map_err(|e| format!("Failed to read metadata: {}", e))?.len();
+        if size > MAX_SVG_BYTES {
+            return Err("SVG is too large to preview".to_string());
+        }
+        let text = fs::read_to_string(&path).map_err(|e| format!("Failed to read SVG: {}", e))?;
+        sanitize_svg(&text)
+    })
+    .await
+    .map_err(|e| format!("Image task failed: {}", e))?
+}
+
+/// Lists the frames of an animated GIF or WebP; other images report a
+/// single frame covering the whole picture
+#[tauri::command]
+pub async fn get_image_frames(guard: State<'_, FsGuardState>, path: String) -> Result<AnimationInfo, String> {
+    let path = guard.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let (bytes, info) = read_image(&path)?;
+        let (loop_count, frames) = match info.format {
+            ImageFormat::Gif => {
+                let (_, loop_count, frames) = parse_gif(&bytes).ok_or_else(|| "Corrupt GIF".to_string())?;
+                (loop_count, frames.into_iter().map(|f| f.info).collect())
+            }
+            ImageFormat::Webp => match parse_animated_webp(&bytes) {
+                Some((loop_count, frames)) => (loop_count, frames.into_iter().map(|f| f.info).collect()),
+                None => (None, Vec::new()),
+            },
+            _ => (None, Vec::new()),
+        };
+        let mut frames: Vec<FrameInfo> = frames;
+        if frames.is_empty() {
+            frames.push(FrameInfo {
+                left: 0,
+                top: 0,
+                width: info.width,
+                height: info.height,
+                duration_ms: 0,
+            });
+        }
+        Ok(AnimationInfo {
+            format: info.format,
+            animated: frames.len() > 1,
+            width: info.width,
+            height: info.height,
+            loop_count: loop_count.filter(|_| frames.len() > 1),
+            frames,
+        })
+    })
+    .await
+    .map_err(|e| format!("Image task failed: {}", e))?
+}
+
+/// Extracts frame `index` of an animated GIF or WebP as a still image in the
+/// same format. Frames are returned as stored, so later frames may only
+/// cover part of the canvas (see `FrameInfo::left`/`top`).
+#[tauri::command]
+pub async fn extract_image_frame(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    index: usize,
+) -> Result<Thumbnail, String> {
+    let path = guard.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let (bytes, info) = read_image(&path)?;
+        let out_of_range = || format!("Frame {} does not exist", index);
+        let (data, width, height) = match info.format {
+            ImageFormat::Gif => {
+                let (header_end, _, frames) = parse_gif(&bytes).ok_or_else(|| "Corrupt GIF".to_string())?;
+                let frame = frames.get(index).ok_or_else(out_of_range)?;
+                (gif_frame(&bytes, header_end, frame), info.width, info.height)
+            }
+            ImageFormat::Webp => match parse_animated_webp(&bytes) {
+                Some((_, frames)) => {
+                    let frame = frames.get(index).ok_or_else(out_of_range)?;
+                    (webp_frame(&bytes, frame), frame.info.width, frame.info.height)
+                }
+                None if index == 0 => (bytes, info.width, info.height),
+                None => return Err(out_of_range()),
+            },
+            // SVGs go through get_svg_preview so they are never handed over raw
+            ImageFormat::Svg => return Err("Use get_svg_preview for SVG files".to_string()),
+            _ if index == 0 => (bytes, info.width, info.height),
+            _ => return Err(out_of_range()),
+        };
+        Ok(Thumbnail {
+            data: general_purpose::STANDARD.encode(data),
+            mime: info.format.mime(),
+            width,
+            height,
+            source: "frame",
+        })
+    })
+    .await
+    .map_err(|e| format!("Image task failed: {}", e))?
+}
@@ -0,0 +1,475 @@
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use base64::{engine::general_purpose, Engine as _};
+use regex::Regex;
+use serde::Serialize;
+use tauri::State;
+
+use crate::fs_guard::FsGuardState;
+
+// Enough to cover every header we parse, including a JPEG's EXIF block
+const PROBE_BYTES: u64 = 256 * 1024;
+// Files up to this size are sent as-is when they can't be downscaled here
+const ORIGINAL_LIMIT: u64 = 2 * 1024 * 1024;
+// Refuse to decode anything larger than this into memory
+const MAX_PIXELS: u64 = 100_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Bmp,
+    Ico,
+    Svg,
+}
+
+impl ImageFormat {
+    pub fn mime(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Bmp => "image/bmp",
+            ImageFormat::Ico => "image/x-icon",
+            ImageFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageInfo {
+    pub format: ImageFormat,
+    pub mime: &'static str,
+    /// Pixel dimensions; 0 when an SVG declares no size
+    pub width: u32,
+    pub height: u32,
+    /// File size in bytes
+    pub size: u64,
+    /// EXIF orientation (1-8) of JPEG photos, so previews can be rotated
+    pub orientation: Option<u16>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Thumbnail {
+    /// Base64 image data
+    pub data: String,
+    pub mime: &'static str,
+    pub width: u32,
+    pub height: u32,
+    /// "decoded" when scaled here, "embedded" for a JPEG's EXIF preview,
+    /// "original" when the file itself is small enough to send
+    pub source: &'static str,
+}
+
+struct Header {
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    orientation: Option<u16>,
+    /// Byte range of the EXIF thumbnail within the file
+    exif_thumbnail: Option<(usize, usize)>,
+}
+
+fn be16(b: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(b.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+pub(crate) fn le16(b: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(b.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+pub(crate) fn le24(b: &[u8], at: usize) -> Option<u32> {
+    let s = b.get(at..at + 3)?;
+    Some(s[0] as u32 | (s[1] as u32) << 8 | (s[2] as u32) << 16)
+}
+
+fn be32(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+pub(crate) fn le32(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn parse_png(b: &[u8]) -> Option<Header> {
+    if b.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some(Header {
+        format: ImageFormat::Png,
+        width: be32(b, 16)?,
+        height: be32(b, 20)?,
+        orientation: None,
+        exif_thumbnail: None,
+    })
+}
+
+/// Reads the orientation and thumbnail location from a TIFF-structured EXIF
+/// block starting at `tiff` within the file
+fn parse_exif(b: &[u8], tiff: usize) -> (Option<u16>, Option<(usize, usize)>) {
+    let Some(data) = b.get(tiff..) else {
+        return (None, None);
+    };
+    let little = match data.get(0..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return (None, None),
+    };
+    let u16_at = |at: usize| if little { le16(data, at) } else { be16(data, at) };
+    let u32_at = |at: usize| if little { le32(data, at) } else { be32(data, at) };
+    // Each IFD entry: tag(2) type(2) count(4) value(4)
+    let entries = |ifd: usize| {
+        let count = u16_at(ifd).unwrap_or(0) as usize;
+        (0..count).filter_map(move |i| {
+            let at = ifd + 2 + i * 12;
+            Some((u16_at(at)?, at + 8))
+        })
+    };
+
+    let mut orientation = None;
+    let mut thumbnail = None;
+    let Some(ifd0) = u32_at(4).map(|o| o as usize) else {
+        return (None, None);
+    };
+    for (tag, value) in entries(ifd0) {
+        if tag == 0x0112 {
+            orientation = u16_at(value).map(|o| o as u16).filter(|o| (1..=8).contains(o));
+        }
+    }
+    let count = u16_at(ifd0).unwrap_or(0) as usize;
+    if let Some(ifd1) = u32_at(ifd0 + 2 + count * 12).filter(|o| *o != 0) {
+        let (mut offset, mut length) = (None, None);
+        for (tag, value) in entries(ifd1 as usize) {
+            match tag {
+                0x0201 => offset = u32_at(value),
+                0x0202 => length = u32_at(value),
+                _ => {}
+            }
+        }
+        if let (Some(offset), Some(length)) = (offset, length) {
+            let start = tiff + offset as usize;
+            thumbnail = Some((start, start + length as usize));
+        }
+    }
+    (orientation, thumbnail)
+}
+
+fn parse_jpeg(b: &[u8]) -> Option<Header> {
+    let mut orientation = None;
+    let mut exif_thumbnail = None;
+    let mut at = 2;
+    loop {
+        // Skip fill bytes before the marker
+        while *b.get(at)? == 0xFF && *b.get(at + 1)? == 0xFF {
+            at += 1;
+        }
+        if *b.get(at)? != 0xFF {
+            return None;
+        }
+        let marker = *b.get(at + 1)?;
+        let length = be16(b, at + 2)? as usize;
+        match marker {
+            0xE1 if b.get(at + 4..at + 10) == Some(b"Exif\0\0") => {
+                (orientation, exif_thumbnail) = parse_exif(b, at + 10);
+            }
+            // Start of frame, except DHT (C4), JPG (C8) and DAC (CC)
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                return Some(Header {
+                    format: ImageFormat::Jpeg,
+                    width: be16(b, at + 7)?,
+                    height: be16(b, at + 5)?,
+                    orientation,
+                    exif_thumbnail,
+                });
+            }
+            _ => {}
+        }
+        at += 2 + length;
+    }
+}
+
+fn parse_webp(b: &[u8]) -> Option<Header> {
+    let chunk = b.get(12..16)?;
+    let (width, height) = match chunk {
+        b"VP8 " if b.get(23..26)? == [0x9D, 0x01, 0x2A] => (le16(b, 26)? & 0x3FFF, le16(b, 28)? & 0x3FFF),
+        b"VP8L" if *b.get(20)? == 0x2F => {
+            let bits = le32(b, 21)?;
+            ((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1)
+        }
+        b"VP8X" => (le24(b, 24)? + 1, le24(b, 27)? + 1),
+        _ => return None,
+    };
+    Some(Header {
+        format: ImageFormat::Webp,
+        width,
+        height,
+        orientation: None,
+        exif_thumbnail: None,
+    })
+}
+
+fn parse_ico(b: &[u8]) -> Option<Header> {
+    let count = le16(b, 4)? as usize;
+    // Report the largest of the contained icons; a stored 0 means 256
+    let (width, height) = (0..count)
+        .filter_map(|i| {
+            let entry = b.get(6 + i * 16..8 + i * 16)?;
+            let side = |v: u8| if v == 0 { 256 } else { v as u32 };
+            Some((side(entry[0]), side(entry[1])))
+        })
+        .max_by_key(|(w, h)| w * h)?;
+    Some(Header {
+        format: ImageFormat::Ico,
+        width,
+        height,
+        orientation: None,
+        exif_thumbnail: None,
+    })
+}
+
+fn svg_size(text: &str) -> (u32, u32) {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    let tag = TAG.get_or_init(|| Regex::new(r"(?s)<svg\b[^>]*>").unwrap());
+    let attr = ATTR.get_or_init(|| Regex::new(r#"\b(width|height|viewBox)\s*=\s*["']([^"']*)["']"#).unwrap());
+
+    let Some(root) = tag.find(text) else {
+        return (0, 0);
+    };
+    let (mut width, mut height, mut view_box) = (None, None, None);
+    for caps in attr.captures_iter(root.as_str()) {
+        let value = caps[2].trim().to_string();
+        match &caps[1] {
+            "width" => width = Some(value),
+            "height" => height = Some(value),
+            _ => view_box = Some(value),
+        }
+    }
+    // Percentages and other relative units say nothing about the intrinsic size
+    let absolute = |v: Option<String>| {
+        v.and_then(|v| v.trim_end_matches("px").parse::<f64>().ok())
+            .map(|v| v.round() as u32)
+    };
+    let from_view_box: Vec<f64> = view_box
+        .map(|v| v.split([' ', ',']).filter_map(|n| n.parse().ok()).collect())
+        .unwrap_or_default();
+    let (vb_width, vb_height) = match from_view_box[..] {
+        [_, _, w, h] => (w.round() as u32, h.round() as u32),
+        _ => (0, 0),
+    };
+    (absolute(width).unwrap_or(vb_width), absolute(height).unwrap_or(vb_height))
+}
+
+fn parse_header(b: &[u8]) -> Option<Header> {
+    let simple = |format, width, height| {
+        Some(Header {
+            format,
+            width,
+            height,
+            orientation: None,
+            exif_thumbnail: None,
+        })
+    };
+    if b.starts_with(b"\x89PNG\r\n\x1a\n") {
+        parse_png(b)
+    } else if b.starts_with(&[0xFF, 0xD8]) {
+        parse_jpeg(b)
+    } else if b.starts_with(b"GIF87a") || b.starts_with(b"GIF89a") {
+        simple(ImageFormat::Gif, le16(b, 6)?, le16(b, 8)?)
+    } else if b.starts_with(b"RIFF") && b.get(8..12) == Some(b"WEBP") {
+        parse_webp(b)
+    } else if b.starts_with(b"BM") {
+        simple(ImageFormat::Bmp, le32(b, 18)?, (le32(b, 22)? as i32).unsigned_abs())
+    } else if b.starts_with(&[0, 0, 1, 0]) {
+        parse_ico(b)
+    } else {
+        let text = String::from_utf8_lossy(b);
+        if !text.contains("<svg") {
+            return None;
+        }
+        let (width, height) = svg_size(&text);
+        simple(ImageFormat::Svg, width, height)
+    }
+}
+
+fn read_prefix(path: &Path) -> Result<(Vec<u8>, u64), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
+    let size = file.metadata().map_err(|e| format!("Failed to read metadata: {}", e))?.len();
+    let mut prefix = Vec::new();
+    file.take(PROBE_BYTES)
+        .read_to_end(&mut prefix)
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+    Ok((prefix, size))
+}
+
+fn probe(path: &Path) -> Result<(Header, u64, Vec<u8>), String> {
+    let (prefix, size) = read_prefix(path)?;
+    let header = parse_header(&prefix).ok_or_else(|| "Unsupported or corrupt image".to_string())?;
+    Ok((header, size, prefix))
+}
+
+fn to_info(header: &Header, size: u64) -> ImageInfo {
+    ImageInfo {
+        format: header.format,
+        mime: header.format.mime(),
+        width: header.width,
+        height: header.height,
+        size,
+        orientation: header.orientation,
+    }
+}
+
+/// Identifies an image already in memory
+pub fn info_from_bytes(bytes: &[u8]) -> Result<ImageInfo, String> {
+    let header = parse_header(bytes).ok_or_else(|| "Unsupported or corrupt image".to_string())?;
+    Ok(to_info(&header, bytes.len() as u64))
+}
+
+/// Fits `width` x `height` inside a `max_dim` square, keeping the aspect ratio
+fn fit(width: u32, height: u32, max_dim: u32) -> (u32, u32) {
+    let scale = max_dim as f64 / width.max(height) as f64;
+    (
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    )
+}
+
+/// Decodes a PNG and averages it down to fit `max_dim`
+fn png_thumbnail(path: &Path, max_dim: u32) -> Result<(Vec<u8>, u32, u32), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
+    let limits = png::Limits {
+        bytes: (MAX_PIXELS * 4) as usize,
+    };
+    let mut decoder = png::Decoder::new_with_limits(BufReader::new(file), limits);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| format!("Failed to decode PNG: {}", e))?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let frame = reader
+        .next_frame(&mut pixels)
+        .map_err(|e| format!("Failed to decode PNG: {}", e))?;
+    let channels = frame.color_type.samples();
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    let (out_width, out_height) = fit(frame.width, frame.height, max_dim);
+
+    // Box filter: every output pixel averages the source pixels it covers
+    let mut out = Vec::with_capacity(out_width as usize * out_height as usize * 4);
+    for oy in 0..out_height as usize {
+        let y0 = oy * height / out_height as usize;
+        let y1 = ((oy + 1) * height / out_height as usize).max(y0 + 1);
+        for ox in 0..out_width as usize {
+            let x0 = ox * width / out_width as usize;
+            let x1 = ((ox + 1) * width / out_width as usize).max(x0 + 1);
+            let mut sum = [0u64; 4];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let px = &pixels[(y * width + x) * channels..][..channels];
+                    let rgba = match channels {
+                        1 => [px[0], px[0], px[0], 255],
+                        2 => [px[0], px[0], px[0], px[1]],
+                        3 => [px[0], px[1], px[2], 255],
+                        _ => [px[0], px[1], px[2], px[3]],
+                    };
+                    for (s, v) in sum.iter_mut().zip(rgba) {
+                        *s += v as u64;
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u64;
+            out.extend(sum.iter().map(|s| (s / count) as u8));
+        }
+    }
+
+    let mut encoded = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut encoded, out_width, out_height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| format!("Failed to encode PNG: {}", e))?;
+        writer
+            .write_image_data(&out)
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    }
+    Ok((encoded, out_width, out_height))
+}
+
+/// Reads an image's format and dimensions from its header without decoding it
+#[tauri::command]
+pub async fn get_image_info(guard: State<'_, FsGuardState>, path: String) -> Result<ImageInfo, String> {
+    let path = guard.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let (header, size, _) = probe(&path)?;
+        Ok(to_info(&header, size))
+    })
+    .await
+    .map_err(|e| format!("Image task failed: {}", e))?
+}
+
+/// Returns a preview no larger than `max_dim` pixels on either side where
+/// possible. PNGs are downscaled here and JPEG photos use their embedded
+/// EXIF preview; other formats are only returned when the file is small.
+#[tauri::command]
+pub async fn get_image_thumbnail(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    max_dim: u32,
+) -> Result<Thumbnail, String> {
+    let path = guard.check(&path)?;
+    let max_dim = max_dim.max(1);
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = path.as_path();
+        let (header, size, prefix) = probe(path)?;
+        let fits = header.width <= max_dim && header.height <= max_dim;
+
+        if header.format == ImageFormat::Png && !fits {
+            if header.width as u64 * header.height as u64 > MAX_PIXELS {
+                return Err("Image is too large to preview".to_string());
+            }
+            let (bytes, width, height) = png_thumbnail(path, max_dim)?;
+            return Ok(Thumbnail {
+                data: general_purpose::STANDARD.encode(bytes),
+                mime: ImageFormat::Png.mime(),
+                width,
+                height,
+                source: "decoded",
+            });
+        }
+
+        if !fits || size > ORIGINAL_LIMIT {
+            if let Some((start, end)) = header.exif_thumbnail {
+                if let Some(embedded) = prefix.get(start..end).filter(|b| b.starts_with(&[0xFF, 0xD8])) {
+                    let (width, height) = parse_jpeg(embedded)
+                        .map(|h| (h.width, h.height))
+                        .unwrap_or_else(|| fit(header.width, header.height, 160));
+                    return Ok(Thumbnail {
+                        data: general_purpose::STANDARD.encode(embedded),
+                        mime: ImageFormat::Jpeg.mime(),
+                        width,
+                        height,
+                        source: "embedded",
+                    });
+                }
+            }
+        }
+
+        if size > ORIGINAL_LIMIT {
+            return Err(format!("Image is too large to preview ({})", header.format.mime()));
+        }
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read image file: {}", e))?;
+        Ok(Thumbnail {
+            data: general_purpose::STANDARD.encode(bytes),
+            mime: header.format.mime(),
+            width: header.width,
+            height: header.height,
+            source: "original",
+        })
+    })
+    .await
+    .map_err(|e| format!("Image task failed: {}", e))?
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::menu::{MenuItem, MenuItemBuilder};
+use tauri::{AppHandle, Emitter, Manager, State, Wry};
+
+use crate::atomic_write::write_atomic;
+
+// User overrides only: {"save": "CmdOrCtrl+S", "toggle-terminal": null}
+const KEYMAP_FILE: &str = "keybindings.json";
+
+/// Menu commands that can be rebound: (menu id, label, default accelerator)
+const DEFAULT_KEYBINDINGS: &[(&str, &str, &str)] = &[
+    ("open-folder", "Open Folder...", "CmdOrCtrl+O"),
+    ("open-file", "Open File...", "CmdOrCtrl+Shift+O"),
+    ("settings", "Settings...", "CmdOrCtrl+,"),
+    ("save", "Save", "CmdOrCtrl+S"),
+    ("save-all", "Save All", "CmdOrCtrl+Alt+S"),
+    ("toggle-terminal", "Toggle Terminal", "CmdOrCtrl+`"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Keybinding {
+    pub command: String,
+    pub label: String,
+    /// None when the user unbound the command
+    pub accelerator: Option<String>,
+    pub default_accelerator: String,
+    pub is_custom: bool,
+}
+
+/// The rebindable menu items, so their accelerators can be changed at runtime
+#[derive(Default)]
+pub struct KeybindingState {
+    items: Mutex<HashMap<String, MenuItem<Wry>>>,
+}
+
+fn keymap_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|d| d.join(KEYMAP_FILE))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+/// User overrides; a `None` value means the command is unbound
+fn load_keymap(app: &AppHandle) -> HashMap<String, Option<String>> {
+    let Ok(path) = keymap_path(app) else {
+        return HashMap::new();
+    };
+    let Ok(text) = fs::read_to_string(&path) else {
+        return HashMap::new();
+    };
+    match serde_json::from_str(&text) {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            tracing::warn!("Ignoring invalid {}: {}", path.display(), e);
+            HashMap::new()
+        }
+    }
+}
+
+fn save_keymap(app: &AppHandle, keymap: &HashMap<String, Option<String>>) -> Result<(), String> {
+    let path = keymap_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let json = serde_json::to_vec_pretty(keymap).map_err(|e| format!("Failed to serialize keybindings: {}", e))?;
+    write_atomic(&path, &json).map_err(|e| format!("Failed to save keybindings: {}", e))
+}
+
+fn bindings(keymap: &HashMap<String, Option<String>>) -> Vec<Keybinding> {
+    DEFAULT_KEYBINDINGS
+        .iter()
+        .map(|(command, label, default)| {
+            let custom = keymap.get(*command);
+            Keybinding {
+                command: command.to_string(),
+                label: label.to_string(),
+                accelerator: match custom {
+                    Some(accelerator) => accelerator.clone(),
+                    None => Some(default.to_string()),
+                },
+                default_accelerator: default.to_string(),
+                is_custom: custom.is_some(),
+            }
+        })
+        .collect()
+}
+
+/// Builds the menu item for `command` with the user's accelerator and keeps
+/// it so `set_keybinding` can update it later
+pub fn menu_item(app: &AppHandle, command: &str) -> tauri::Result<MenuItem<Wry>> {
+    let binding = bindings(&load_keymap(app))
+        .into_iter()
+        .find(|b| b.command == command);
+    let label = binding.as_ref().map(|b| b.label.clone()).unwrap_or_else(|| command.to_string());
+    let mut builder = MenuItemBuilder::with_id(command, label);
+    if let Some(accelerator) = binding.and_then(|b| b.accelerator) {
+        builder = builder.accelerator(accelerator);
+    }
+    let item = builder.build(app)?;
+    if let Ok(mut items) = app.state::<KeybindingState>().items.lock() {
+        items.insert(command.to_string(), item.clone());
+    }
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn get_keybindings(app_handle: AppHandle) -> Result<Vec<Keybinding>, String> {
+    Ok(bindings(&load_keymap(&app_handle)))
+}
+
+/// Rebinds a menu command. `accelerator` uses the menu syntax
+/// ("CmdOrCtrl+Shift+S"); an empty string unbinds the command and `None`
+/// restores its default.
+#[tauri::command]
+pub async fn set_keybinding(
+    app_handle: AppHandle,
+    state: State<'_, KeybindingState>,
+    command: String,
+    accelerator: Option<String>,
+) -> Result<Vec<Keybinding>, String> {
+    if !DEFAULT_KEYBINDINGS.iter().any(|(id, _, _)| *id == command) {
+        return Err(format!("Unknown command: {}", command));
+    }
+
+    let mut keymap = load_keymap(&app_handle);
+    match accelerator.map(|a| a.trim().to_string()) {
+        None => {
+            keymap.remove(&command);
+        }
+        Some(a) if a.is_empty() => {
+            keymap.insert(command.clone(), None);
+        }
+        Some(a) => {
+            let taken = bindings(&keymap).into_iter().find(|b| {
+                b.command != command && b.accelerator.as_deref().is_some_and(|other| other.eq_ignore_ascii_case(&a))
+            });
+            if let Some(other) = taken {
+                return Err(format!("{} is already bound to {}", a, other.label));
+            }
+            keymap.insert(command.clone(), Some(a));
+        }
+    }
+
+    let updated = bindings(&keymap);
+    let accelerator = updated
+        .iter()
+        .find(|b| b.command == command)
+        .and_then(|b| b.accelerator.clone());
+    {
+        let items = state.items.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        if let Some(item) = items.get(&command) {
+            // Also validates the accelerator before anything is written
+            item.set_accelerator(accelerator.as_deref())
+                .map_err(|e| format!("Invalid shortcut: {}", e))?;
+        }
+    }
+    save_keymap(&app_handle, &keymap)?;
+    let _ = app_handle.emit("keybindings-changed", &updated);
+    Ok(updated)
+}
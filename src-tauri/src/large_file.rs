@@ -0,0 +1,120 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::fs_guard::FsGuardState;
+
+// Upper bound for a single chunk so one call can't pull a whole huge file into memory
+const MAX_CHUNK_LEN: u64 = 16 * 1024 * 1024;
+const COUNT_BUFFER_LEN: usize = 64 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct FileChunk {
+    pub content: String,
+    /// Byte offset where `content` starts (may be after the requested offset
+    /// if that fell inside a multi-byte character)
+    pub offset: u64,
+    /// Byte offset to request for the following chunk
+    pub next_offset: u64,
+    pub total_size: u64,
+    pub eof: bool,
+}
+
+/// Number of bytes at the start of `bytes` that are UTF-8 continuation bytes
+fn leading_continuation_bytes(bytes: &[u8]) -> usize {
+    bytes.iter().take(3).take_while(|b| (**b & 0b1100_0000) == 0b1000_0000).count()
+}
+
+/// Length of the longest prefix of `bytes` that doesn't end inside a character
+fn complete_prefix_len(bytes: &[u8]) -> usize {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        // error_len() == None means the input ended mid-character
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => bytes.len(),
+    }
+}
+
+pub fn read_range(path: &Path, offset: u64, length: u64) -> Result<FileChunk, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let total_size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read metadata: {}", e))?
+        .len();
+
+    let offset = offset.min(total_size);
+    // At least one full character (4 bytes) so callers always make progress
+    let length = length.clamp(4, MAX_CHUNK_LEN).min(total_size - offset);
+
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek: {}", e))?;
+    let mut buf = Vec::with_capacity(length as usize);
+    file.by_ref()
+        .take(length)
+        .read_to_end(&mut buf)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    // Realign to character boundaries on both ends
+    let skip = if offset > 0 { leading_continuation_bytes(&buf) } else { 0 };
+    let reached_end = offset + buf.len() as u64 >= total_size;
+    let end = if reached_end { buf.len() } else { skip + complete_prefix_len(&buf[skip..]) };
+
+    let content = String::from_utf8_lossy(&buf[skip..end]).to_string();
+    let next_offset = offset + end as u64;
+
+    Ok(FileChunk {
+        content,
+        offset: offset + skip as u64,
+        next_offset,
+        total_size,
+        eof: next_offset >= total_size,
+    })
+}
+
+pub fn count_lines(path: &Path) -> Result<u64, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut buf = vec![0u8; COUNT_BUFFER_LEN];
+    let mut lines = 0u64;
+    let mut last_byte = None;
+
+    loop {
+        let n = file.read(&mut buf).map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        lines += buf[..n].iter().filter(|b| **b == b'\n').count() as u64;
+        last_byte = Some(buf[n - 1]);
+    }
+
+    // A final line without a trailing newline still counts
+    match last_byte {
+        Some(b'\n') | None => Ok(lines),
+        Some(_) => Ok(lines + 1),
+    }
+}
+
+/// Reads up to `length` bytes starting at `offset`, trimmed to whole UTF-8
+/// characters. Continue with `next_offset` until `eof`.
+#[tauri::command]
+pub async fn read_file_range(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    offset: u64,
+    length: u64,
+) -> Result<FileChunk, String> {
+    let path = guard.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || read_range(&path, offset, length))
+        .await
+        .map_err(|e| format!("Read task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_file_line_count(guard: State<'_, FsGuardState>, path: String) -> Result<u64, String> {
+    let path = guard.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || count_lines(&path))
+        .await
+        .map_err(|e| format!("Line count task failed: {}", e))?
+}
@@ -1,185 +1,680 @@
 use std::fs;
+use std::io;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
 use tauri::{Manager, Emitter, State};
 
+mod error;
+use error::AppError;
+
 mod pty;
-use pty::PtySession;
+use pty::{PtySession, TerminalOptions, TerminalStatus};
 
 mod lsp;
 
-#[derive(Debug, Serialize, Deserialize)]
+mod dap;
+
+mod search;
+
+mod replace;
+
+mod git;
+
+mod git_sync;
+
+mod diff;
+
+mod clipboard;
+
+mod html_to_markdown;
+
+mod url_metadata;
+
+mod atomic_write;
+
+mod file_ops;
+
+mod encoding;
+
+mod large_file;
+
+mod file_info;
+
+mod workspace;
+
+mod file_version;
+use file_version::{FileVersion, SaveError};
+
+mod tasks;
+
+mod scaffold;
+
+mod run_configs;
+
+mod recents;
+
+mod archive;
+
+mod file_index;
+
+mod symbol_index;
+
+mod highlight;
+
+mod markdown;
+
+mod export;
+
+mod site;
+
+mod pandoc;
+
+mod assets;
+
+mod front_matter;
+
+mod outline;
+
+mod diagrams;
+
+mod math;
+
+mod links;
+
+mod tags;
+mod debounced_watch;
+
+mod link_check;
+
+mod link_rename;
+
+mod backups;
+
+mod history;
+
+mod remote;
+
+mod settings;
+
+mod themes;
+
+mod templates;
+
+mod daily_notes;
+
+mod plugins;
+mod wasm_plugin;
+
+mod spellcheck;
+
+mod workspace_settings;
+
+mod commands;
+
+mod command_policy;
+
+mod shell_env;
+
+mod processes;
+
+mod logging;
+
+mod updater;
+
+mod single_instance;
+
+mod deep_link;
+
+mod formatter;
+
+mod diagnostics;
+
+mod dir_stats;
+
+mod dir_stream;
+
+mod file_sort;
+
+mod glob_files;
+
+mod fs_guard;
+
+mod problem_matcher;
+
+mod pickers;
+
+mod keybindings;
+
+mod drop_import;
+
+mod system_open;
+
+mod images;
+
+mod image_preview;
+
+mod hex_view;
+
+mod line_endings;
+
+mod editorconfig;
+
+mod save_transforms;
+
+mod text_index;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct FileEntry {
     name: String,
     path: String,
+    /// For symlinks these describe the target; both are false for broken links
     is_directory: bool,
     is_file: bool,
+    is_symlink: bool,
+    /// The link's target as stored in the link (may be relative)
+    symlink_target: Option<String>,
+    /// True for links pointing at one of their own ancestors, which must not be expanded recursively
+    is_circular: bool,
+    /// Matched by .gitignore, .ignore or the repository's exclude rules
+    is_ignored: bool,
+    /// In bytes; 0 for directories
+    size: u64,
+    /// Milliseconds since the Unix epoch
+    modified_ms: Option<u64>,
+    readonly: bool,
+    /// Without the dot; None for directories and names without one
+    extension: Option<String>,
+}
+
+/// Extension of a file name as shown in the explorer's type column
+fn extension_of(name: &str, is_directory: bool) -> Option<String> {
+    if is_directory {
+        return None;
+    }
+    std::path::Path::new(name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+}
+
+/// Names of the entries of `dir` that gitignore rules exclude. Rules come from
+/// every .gitignore up to the repository root plus the global and repo excludes.
+fn ignored_names(dir: &std::path::Path) -> std::collections::HashSet<std::ffi::OsString> {
+    let kept: std::collections::HashSet<_> = ignore::WalkBuilder::new(dir)
+        .max_depth(Some(1))
+        // Only ignore rules count; hidden files are handled by `show_hidden`
+        .hidden(false)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.depth() == 1)
+        .map(|entry| entry.file_name().to_os_string())
+        .collect();
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name())
+                .filter(|name| !kept.contains(name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// True if `path` is a symlinked directory that resolves to one of its own ancestors
+fn is_circular_link(path: &std::path::Path) -> bool {
+    let target = match fs::canonicalize(path) {
+        Ok(t) => t,
+        Err(_) => return false,
+    };
+    match path.parent().map(fs::canonicalize) {
+        Some(Ok(parent)) => parent.starts_with(&target),
+        _ => false,
+    }
 }
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+/// The explorer entry for `entry`, or None if it is filtered out or its
+/// name isn't valid UTF-8
+fn file_entry(
+    entry: &fs::DirEntry,
+    ignored: &std::collections::HashSet<std::ffi::OsString>,
+    show_hidden: bool,
+    hide_ignored: bool,
+) -> Option<FileEntry> {
+    let path = entry.path();
+    let file_type = entry.file_type().ok()?;
+    let name = entry.file_name().into_string().ok()?;
+
+    // Skip hidden files if show_hidden is false
+    if !show_hidden && name.starts_with('.') {
+        return None;
+    }
+
+    let is_ignored = ignored.contains(&entry.file_name());
+    if hide_ignored && is_ignored {
+        return None;
+    }
+
+    let is_symlink = file_type.is_symlink();
+    // Follows links; a broken one is neither a file nor a directory
+    let metadata = fs::metadata(&path).ok();
+    let (is_directory, is_file) = match &metadata {
+        Some(metadata) => (metadata.is_dir(), metadata.is_file()),
+        None if is_symlink => (false, false),
+        None => (file_type.is_dir(), file_type.is_file()),
+    };
+    let symlink_target = if is_symlink {
+        fs::read_link(&path).ok().map(|t| t.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    Some(FileEntry {
+        extension: extension_of(&name, is_directory),
+        name,
+        is_circular: is_symlink && is_directory && is_circular_link(&path),
+        path: path.to_string_lossy().to_string(),
+        is_directory,
+        is_file,
+        is_symlink,
+        symlink_target,
+        is_ignored,
+        size: metadata.as_ref().filter(|m| m.is_file()).map_or(0, |m| m.len()),
+        modified_ms: metadata
+            .as_ref()
+            .and_then(|m| file_info::system_time_ms(m.modified())),
+        readonly: metadata.as_ref().is_some_and(|m| m.permissions().readonly()),
+    })
+}
+
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+/// Entries of `path`; `sort` defaults to folders first, then names
+/// compared ignoring case
 #[tauri::command]
-async fn read_directory(path: String, show_hidden: Option<bool>) -> Result<Vec<FileEntry>, String> {
-    let dir_path = PathBuf::from(&path);
+async fn read_directory(
+    remote_state: State<'_, remote::RemoteState>,
+    guard: State<'_, fs_guard::FsGuardState>,
+    path: String,
+    show_hidden: Option<bool>,
+    hide_ignored: Option<bool>,
+    sort: Option<file_sort::SortOptions>,
+) -> Result<Vec<FileEntry>, AppError> {
     let show_hidden = show_hidden.unwrap_or(true); // Default to true
+    let sort = sort.unwrap_or_default();
+
+    if remote::is_remote(&path) {
+        let mut entries = remote::read_directory(&remote_state, &path, show_hidden).await?;
+        file_sort::sort_entries(&mut entries, &sort);
+        return Ok(entries);
+    }
     
+    let dir_path = guard.check(&path)?;
     if !dir_path.exists() {
-        return Err("Directory does not exist".to_string());
+        return Err(AppError::not_found("Directory does not exist"));
     }
     
     if !dir_path.is_dir() {
-        return Err("Path is not a directory".to_string());
+        return Err(AppError::invalid_input("Path is not a directory"));
     }
     
-    let mut entries = Vec::new();
+    let ignored = ignored_names(&dir_path);
+    let hide_ignored = hide_ignored.unwrap_or(false);
+    let mut entries: Vec<FileEntry> = fs::read_dir(&dir_path)
+        .map_err(|e| AppError::io("Failed to read directory", e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| file_entry(&entry, &ignored, show_hidden, hide_ignored))
+        .collect();
     
-    match fs::read_dir(&dir_path) {
-        Ok(dir_entries) => {
-            for entry in dir_entries {
-                match entry {
-                    Ok(entry) => {
-                        let path = entry.path();
-                        let metadata = match entry.metadata() {
-                            Ok(m) => m,
-                            Err(_) => continue,
-                        };
-                        
-                        let name = match entry.file_name().into_string() {
-                            Ok(n) => n,
-                            Err(_) => continue,
-                        };
-                        
-                        // Skip hidden files if show_hidden is false
-                        if !show_hidden && name.starts_with('.') {
-                            continue;
-                        }
-                        
-                        entries.push(FileEntry {
-                            name,
-                            path: path.to_string_lossy().to_string(),
-                            is_directory: metadata.is_dir(),
-                            is_file: metadata.is_file(),
-                        });
-                    }
-                    Err(_) => continue,
-                }
-            }
-        }
-        Err(e) => return Err(format!("Failed to read directory: {}", e)),
-    }
-    
-    // Sort: directories first, then files, both alphabetically
-    entries.sort_by(|a, b| {
-        match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        }
-    });
+    file_sort::sort_entries(&mut entries, &sort);
     
     Ok(entries)
 }
 
 #[tauri::command]
-async fn path_exists(path: String) -> Result<bool, String> {
-    let path_buf = PathBuf::from(&path);
-    Ok(path_buf.exists())
+async fn path_exists(guard: State<'_, fs_guard::FsGuardState>, path: String) -> Result<bool, AppError> {
+    Ok(guard.check_entry(&path)?.exists())
 }
 
 #[tauri::command]
-async fn read_file_content(path: String) -> Result<String, String> {
-    match fs::read_to_string(&path) {
+async fn read_file_content(
+    remote_state: State<'_, remote::RemoteState>,
+    guard: State<'_, fs_guard::FsGuardState>,
+    path: String,
+) -> Result<String, AppError> {
+    if remote::is_remote(&path) {
+        let bytes = remote::read_file(&remote_state, &path).await?;
+        return String::from_utf8(bytes)
+            .map_err(|e| AppError::io("Failed to read file", io::Error::new(io::ErrorKind::InvalidData, e)));
+    }
+    let path = guard.check(&path)?;
+    match fs::read_to_string(path) {
         Ok(content) => Ok(content),
-        Err(e) => Err(format!("Failed to read file: {}", e)),
+        Err(e) => Err(AppError::io("Failed to read file", e)),
     }
 }
 
 #[tauri::command]
-async fn read_image_file(path: String) -> Result<String, String> {
+async fn read_image_file(guard: State<'_, fs_guard::FsGuardState>, path: String) -> Result<String, AppError> {
     use base64::{Engine as _, engine::general_purpose};
     
-    match fs::read(&path) {
+    let path = guard.check(&path)?;
+    match fs::read(path) {
         Ok(bytes) => {
             let base64_string = general_purpose::STANDARD.encode(&bytes);
             Ok(base64_string)
         }
-        Err(e) => Err(format!("Failed to read image file: {}", e)),
+        Err(e) => Err(AppError::io("Failed to read image file", e)),
+    }
+}
+
+#[tauri::command]
+async fn create_file(
+    guard: State<'_, fs_guard::FsGuardState>,
+    path: String,
+    overwrite: Option<bool>,
+) -> Result<(), AppError> {
+    create_file_with_content(guard, path, String::new(), overwrite).await
+}
+
+/// Creates `path` with `content`. Fails if something already exists there
+/// unless `overwrite` is set, in which case the file is replaced atomically.
+#[tauri::command]
+async fn create_file_with_content(
+    guard: State<'_, fs_guard::FsGuardState>,
+    path: String,
+    content: String,
+    overwrite: Option<bool>,
+) -> Result<(), AppError> {
+    use std::io::Write;
+
+    let target = guard.check(&path)?;
+    if overwrite.unwrap_or(false) {
+        return atomic_write::write_atomic(&target, content.as_bytes())
+            .map_err(|e| AppError::io("Failed to create file", e));
+    }
+    // create_new checks and creates in one step, so a concurrent file is never clobbered
+    let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(&target) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Err(AppError::AlreadyExists {
+                message: format!("A file or folder named {} already exists", path),
+                os_error: e.raw_os_error(),
+            });
+        }
+        Err(e) => return Err(AppError::io("Failed to create file", e)),
+    };
+    file.write_all(content.as_bytes())
+        .map_err(|e| AppError::io("Failed to write file", e))
+}
+
+/// First free name of `untitled.ext`, `untitled-2.ext`, ... in `dir`,
+/// returned as a full path
+#[tauri::command]
+async fn suggest_untitled_name(
+    guard: State<'_, fs_guard::FsGuardState>,
+    dir: String,
+    ext: String,
+) -> Result<String, AppError> {
+    let dir_path = guard.check(&dir)?;
+    if !dir_path.is_dir() {
+        return Err(AppError::invalid_input("Path is not a directory"));
     }
+    let ext = ext.trim_start_matches('.');
+    let name = |n: usize| {
+        let stem = if n == 1 { "untitled".to_string() } else { format!("untitled-{}", n) };
+        if ext.is_empty() { stem } else { format!("{}.{}", stem, ext) }
+    };
+    let path = (1..)
+        .map(|n| dir_path.join(name(n)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded range always yields a free name");
+    Ok(path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
-async fn create_file(path: String) -> Result<(), String> {
-    match fs::File::create(&path) {
+async fn create_directory(guard: State<'_, fs_guard::FsGuardState>, path: String) -> Result<(), AppError> {
+    let path = guard.check_entry(&path)?;
+    match fs::create_dir(path) {
         Ok(_) => Ok(()),
-        Err(e) => Err(format!("Failed to create file: {}", e)),
+        Err(e) => Err(AppError::io("Failed to create directory", e)),
     }
 }
 
+/// Creates a symbolic link at `link_path` pointing to `target`
 #[tauri::command]
-async fn create_directory(path: String) -> Result<(), String> {
-    match fs::create_dir(&path) {
+async fn create_symlink(
+    guard: State<'_, fs_guard::FsGuardState>,
+    target: String,
+    link_path: String,
+) -> Result<(), AppError> {
+    // Reading through the link is checked against where it leads
+    let link_path = guard.check_entry(&link_path)?;
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(&target, &link_path);
+    
+    #[cfg(windows)]
+    let result = {
+        // Windows needs to know the kind of link; resolve relative targets against the link's directory
+        let resolved = link_path
+            .parent()
+            .map(|dir| dir.join(&target))
+            .unwrap_or_else(|| PathBuf::from(&target));
+        if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(&target, &link_path)
+        } else {
+            std::os::windows::fs::symlink_file(&target, &link_path)
+        }
+    };
+    
+    match result {
         Ok(_) => Ok(()),
-        Err(e) => Err(format!("Failed to create directory: {}", e)),
+        Err(e) => Err(AppError::io("Failed to create symlink", e)),
     }
 }
 
 #[tauri::command]
-async fn delete_path(path: String) -> Result<(), String> {
-    let path_buf = PathBuf::from(&path);
+async fn delete_path(
+    guard: State<'_, fs_guard::FsGuardState>,
+    path: String,
+    permanent: Option<bool>,
+) -> Result<(), AppError> {
+    let path_buf = guard.check_entry(&path)?;
+    
+    // symlink_metadata so broken links can still be deleted
+    if fs::symlink_metadata(&path_buf).is_err() {
+        return Err(AppError::not_found("Path does not exist"));
+    }
     
-    if !path_buf.exists() {
-        return Err("Path does not exist".to_string());
+    // Move to the system trash unless permanent deletion is requested
+    if !permanent.unwrap_or(false) {
+        return match trash::delete(&path_buf) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(AppError::trash("Failed to move to trash", e)),
+        };
     }
     
     if path_buf.is_dir() {
         match fs::remove_dir_all(&path) {
             Ok(_) => Ok(()),
-            Err(e) => Err(format!("Failed to delete directory: {}", e)),
+            Err(e) => Err(AppError::io("Failed to delete directory", e)),
         }
     } else {
         match fs::remove_file(&path) {
             Ok(_) => Ok(()),
-            Err(e) => Err(format!("Failed to delete file: {}", e)),
+            Err(e) => Err(AppError::io("Failed to delete file", e)),
         }
     }
 }
 
+/// Restores the most recently trashed item that originally lived at `path`
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
 #[tauri::command]
-async fn rename_path(old_path: String, new_path: String) -> Result<(), String> {
-    match fs::rename(&old_path, &new_path) {
+async fn restore_from_trash(guard: State<'_, fs_guard::FsGuardState>, path: String) -> Result<(), AppError> {
+    let original = guard.check_entry(&path)?;
+    let items = trash::os_limited::list().map_err(|e| AppError::trash("Failed to list trash", e))?;
+    
+    let item = items
+        .into_iter()
+        .filter(|item| item.original_path() == original)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| AppError::not_found("Item not found in trash"))?;
+    
+    if original.exists() {
+        return Err(AppError::AlreadyExists {
+            message: "A file already exists at the original location".to_string(),
+            os_error: None,
+        });
+    }
+    
+    match trash::os_limited::restore_all([item]) {
         Ok(_) => Ok(()),
-        Err(e) => Err(format!("Failed to rename: {}", e)),
+        Err(e) => Err(AppError::trash("Failed to restore from trash", e)),
     }
 }
 
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+#[tauri::command]
+async fn restore_from_trash(_path: String) -> Result<(), AppError> {
+    Err(AppError::Unsupported {
+        message: "Restoring from trash is not supported on this platform".to_string(),
+    })
+}
+
 #[tauri::command]
-async fn save_file(path: String, content: String) -> Result<(), String> {
-    match fs::write(&path, content) {
+async fn rename_path(
+    guard: State<'_, fs_guard::FsGuardState>,
+    old_path: String,
+    new_path: String,
+) -> Result<(), AppError> {
+    let old_path = guard.check_entry(&old_path)?;
+    let new_path = guard.check_entry(&new_path)?;
+    match fs::rename(old_path, new_path) {
         Ok(_) => Ok(()),
-        Err(e) => Err(format!("Failed to save file: {}", e)),
+        Err(e) => Err(AppError::io("Failed to rename", e)),
     }
 }
 
+/// Saves `content`; returns the new on-disk version for the next save.
+/// When `expected` is given and the file changed on disk since, nothing is
+/// written and a `SaveError::Conflict` is returned instead. `line_ending`
+/// converts the content first, or keeps the file's current style with "preserve".
+/// `transforms` trims whitespace, fixes the final newline and reindents (see
+/// `save_transforms`).
 #[tauri::command]
-async fn execute_command(command: String, working_dir: Option<String>) -> Result<String, String> {
-    use std::process::Command;
+#[allow(clippy::too_many_arguments)]
+async fn save_file(
+    app_handle: tauri::AppHandle,
+    link_index: State<'_, links::LinkIndexState>,
+    remote_state: State<'_, remote::RemoteState>,
+    guard: State<'_, fs_guard::FsGuardState>,
+    path: String,
+    content: String,
+    atomic: Option<bool>,
+    encoding: Option<String>,
+    bom: Option<bool>,
+    expected: Option<FileVersion>,
+    line_ending: Option<line_endings::LineEndingPolicy>,
+    transforms: Option<save_transforms::SaveTransforms>,
+) -> Result<FileVersion, SaveError> {
+    // Canonical path of a local file; remote ones go through their connection
+    let local = if remote::is_remote(&path) { None } else { Some(guard.check(&path)?) };
+    let content = match transforms {
+        Some(transforms) => {
+            let target = local.clone().unwrap_or_else(|| PathBuf::from(&path));
+            save_transforms::transform(&target, local.is_some(), &content, &transforms)
+        }
+        None => content,
+    };
+    let content = match line_ending {
+        Some(policy) => {
+            let existing = match &local {
+                Some(local) => fs::read(local).ok(),
+                None => remote::read_file(&remote_state, &path).await.ok(),
+            };
+            match line_endings::resolve_policy(policy, existing.as_deref()) {
+                Some(target) => line_endings::convert(&content, target).into_owned(),
+                None => content,
+            }
+        }
+        None => content,
+    };
+
+    // Encode back into the file's original (or requested) encoding; UTF-8 by default
+    let bytes = match encoding {
+        Some(label) => encoding::encode(&content, encoding::encoding_for_label(&label)?, bom.unwrap_or(false))?,
+        None if bom.unwrap_or(false) => encoding::encode(&content, encoding_rs::UTF_8, true)?,
+        None => content.into_bytes(),
+    };
+
+    let Some(path) = local else {
+        return remote::save_file(&remote_state, &path, &bytes, expected.as_ref()).await;
+    };
+    if let Some(expected) = &expected {
+        file_version::check_unchanged(&path, expected)?;
+    }
     
-    // Parse command into parts (simple split by whitespace)
-    let parts: Vec<&str> = command.split_whitespace().collect();
-    if parts.is_empty() {
-        return Err("Empty command".to_string());
+    // Atomic (temp file + rename) by default; `atomic: false` writes in place
+    let result = if atomic.unwrap_or(true) {
+        atomic_write::write_atomic(&path, &bytes)
+    } else {
+        fs::write(&path, &bytes)
+    };
+    
+    match result {
+        Ok(_) => {
+            link_index.refresh(&path);
+            diagnostics::on_save(&app_handle, &path);
+            // History is best effort; a failed snapshot must not fail the save
+            if let Err(e) = history::record(&app_handle, &path, &bytes) {
+                tracing::warn!("Failed to record file history: {}", e);
+            }
+            Ok(file_version::version_of(&path, &bytes))
+        }
+        Err(e) => Err(AppError::io("Failed to save file", e).into()),
     }
+}
+
+/// Runs a command to completion and returns its stdout, or its stderr if it
+/// failed. See `commands::resolve_command` for how `command`, `args` and
+/// `use_shell` are interpreted, and `command_policy` for which programs may run.
+#[tauri::command]
+async fn execute_command(
+    app_handle: tauri::AppHandle,
+    policy_state: State<'_, command_policy::CommandPolicyState>,
+    guard: State<'_, fs_guard::FsGuardState>,
+    command: String,
+    working_dir: Option<String>,
+    args: Option<Vec<String>>,
+    use_shell: Option<bool>,
+) -> Result<String, AppError> {
+    use std::process::Command;
     
-    let program = parts[0];
-    let args = &parts[1..];
+    let working_dir = working_dir.map(|dir| guard.check(&dir)).transpose()?;
+    let use_shell = use_shell.unwrap_or(false);
+    let (program, args) = commands::resolve_command(&command, args, use_shell)?;
+    command_policy::authorize(
+        &app_handle,
+        &policy_state,
+        "execute_command",
+        &program,
+        &args,
+        working_dir.as_deref(),
+        use_shell,
+    )
+    .await?;
     
-    let mut cmd = Command::new(program);
-    cmd.args(args);
+    let mut cmd = Command::new(&program);
+    cmd.args(&args).envs(shell_env::environment());
     
     // Set working directory if provided
     if let Some(dir) = working_dir {
@@ -192,10 +687,10 @@ async fn execute_command(command: String, working_dir: Option<String>) -> Result
             if output.status.success() {
                 Ok(String::from_utf8_lossy(&output.stdout).to_string())
             } else {
-                Err(String::from_utf8_lossy(&output.stderr).to_string())
+                Err(String::from_utf8_lossy(&output.stderr).to_string().into())
             }
         },
-        Err(e) => Err(format!("Failed to execute command: {}", e)),
+        Err(e) => Err(AppError::io("Failed to execute command", e)),
     }
 }
 
@@ -210,7 +705,18 @@ async fn start_pty_session(
     state: State<'_, PtyState>,
     terminal_id: String,
     working_dir: Option<String>,
-) -> Result<(), String> {
+    rows: Option<u16>,
+    cols: Option<u16>,
+    options: Option<TerminalOptions>,
+) -> Result<(), AppError> {
+    // Remote folders are opened over their SSH connection instead
+    let working_dir = match working_dir {
+        Some(dir) if !remote::is_remote(&dir) => {
+            let dir = app_handle.state::<fs_guard::FsGuardState>().check(&dir)?;
+            Some(dir.to_string_lossy().to_string())
+        }
+        other => other,
+    };
     let mut sessions = state.sessions.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
     
     // Kill old session if it exists for this terminal
@@ -219,7 +725,14 @@ async fn start_pty_session(
     }
     
     // Create new session with terminal-specific event channel
-    let session = PtySession::new(app_handle, terminal_id.clone(), working_dir)?;
+    let session = PtySession::new(
+        app_handle,
+        terminal_id.clone(),
+        working_dir,
+        rows,
+        cols,
+        options.unwrap_or_default(),
+    )?;
     sessions.insert(terminal_id, session);
     Ok(())
 }
@@ -229,13 +742,59 @@ async fn write_to_pty(
     state: State<'_, PtyState>,
     terminal_id: String,
     data: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let sessions = state.sessions.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
     if let Some(session) = sessions.get(&terminal_id) {
         session.write(&data)?;
         Ok(())
     } else {
-        Err(format!("No active PTY session for terminal {}", terminal_id))
+        Err(AppError::not_found(format!("No active PTY session for terminal {}", terminal_id)))
+    }
+}
+
+#[tauri::command]
+async fn resize_pty(
+    state: State<'_, PtyState>,
+    terminal_id: String,
+    rows: u16,
+    cols: u16,
+) -> Result<(), AppError> {
+    if rows == 0 || cols == 0 {
+        return Err(AppError::invalid_input("Terminal size must be non-zero"));
+    }
+    let sessions = state.sessions.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(session) = sessions.get(&terminal_id) {
+        session.resize(rows, cols)
+    } else {
+        Err(AppError::not_found(format!("No active PTY session for terminal {}", terminal_id)))
+    }
+}
+
+/// Returns the buffered output of a terminal so a reloaded view can restore its history
+#[tauri::command]
+async fn get_terminal_scrollback(
+    state: State<'_, PtyState>,
+    terminal_id: String,
+) -> Result<String, AppError> {
+    let sessions = state.sessions.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(session) = sessions.get(&terminal_id) {
+        session.scrollback()
+    } else {
+        Err(AppError::not_found(format!("No active PTY session for terminal {}", terminal_id)))
+    }
+}
+
+/// Whether the terminal's shell is still running, and how it exited if not
+#[tauri::command]
+async fn get_terminal_status(
+    state: State<'_, PtyState>,
+    terminal_id: String,
+) -> Result<TerminalStatus, AppError> {
+    let sessions = state.sessions.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(session) = sessions.get(&terminal_id) {
+        session.status()
+    } else {
+        Err(AppError::not_found(format!("No active PTY session for terminal {}", terminal_id)))
     }
 }
 
@@ -243,7 +802,7 @@ async fn write_to_pty(
 async fn stop_pty_session(
     state: State<'_, PtyState>,
     terminal_id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let mut sessions = state.sessions.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
     
     // Kill and remove the session
@@ -258,35 +817,71 @@ async fn stop_pty_session(
 pub fn run() {
     #[allow(unused_imports)]
     use tauri::menu::{PredefinedMenuItem};
-    use tauri::menu::{Menu, MenuItemBuilder, SubmenuBuilder};
+    use tauri::menu::{Menu, SubmenuBuilder};
     
     tauri::Builder::default()
+        // Before the other plugins, so a second launch exits early
+        .plugin(single_instance::init())
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::new().build())
+        .plugin(tauri_plugin_updater::Builder::new().build())
         .manage(PtyState {
             sessions: Arc::new(Mutex::new(std::collections::HashMap::new())),
         })
         .manage(lsp::LspState::default())
+        .manage(search::SearchState::default())
+        .manage(dap::DapState::default())
+        .manage(tasks::TaskState::default())
+        .manage(run_configs::RunConfigState::default())
+        .manage(recents::RecentMenu::default())
+        .manage(file_index::FileIndexState::default())
+        .manage(symbol_index::SymbolIndexState::default())
+        .manage(links::LinkIndexState::default())
+        .manage(tags::TagIndexState::default())
+        .manage(remote::RemoteState::default())
+        .manage(workspace_settings::WorkspaceSettingsState::default())
+        .manage(commands::CommandState::default())
+        .manage(command_policy::CommandPolicyState::default())
+        .manage(diagnostics::DiagnosticsState::default())
+        .manage(dir_stats::DirStatsState::default())
+        .manage(dir_stream::DirStreamState::default())
+        .manage(fs_guard::FsGuardState::default())
+        .manage(updater::UpdaterState::default())
+        .manage(deep_link::DeepLinkState::default())
+        .manage(single_instance::LaunchState::default())
+        .manage(keybindings::KeybindingState::default())
+        .manage(text_index::TextIndexState::default())
+        .manage(git_sync::GitSyncState::default())
+        .manage(themes::ThemeState::default())
+        .manage(plugins::PluginState::default())
+        .manage(spellcheck::SpellcheckState::default())
+        .manage(diagrams::DiagramState::default())
+        .manage(url_metadata::UrlMetadataState::default())
         .setup(|app| {
-            // Create menu items
-            let open_folder = MenuItemBuilder::with_id("open-folder", "Open Folder...")
-                .accelerator("CmdOrCtrl+O")
-                .build(app)?;
+            app.manage(logging::init(app.handle()));
+            deep_link::init(app.handle());
+            single_instance::init_launch_paths(app.handle());
+
+            // Ask the login shell for its environment now rather than on the first spawn
+            std::thread::spawn(shell_env::environment);
+
+            // Create menu items; accelerators come from the user's keymap
+            let open_folder = keybindings::menu_item(app.handle(), "open-folder")?;
+            
+            let open_file = keybindings::menu_item(app.handle(), "open-file")?;
             
-            let open_file = MenuItemBuilder::with_id("open-file", "Open File...")
-                .accelerator("CmdOrCtrl+Shift+O")
-                .build(app)?;
+            let settings_item = keybindings::menu_item(app.handle(), "settings")?;
             
-            let settings_item = MenuItemBuilder::with_id("settings", "Settings...")
-                .accelerator("CmdOrCtrl+,")
-                .build(app)?;
+            let open_recent = recents::build_menu(app.handle())?;
             
             // Build File submenu
             #[allow(unused_mut)]
             let mut file_menu_builder = SubmenuBuilder::new(app, "File")
                 .item(&open_folder)
-                .item(&open_file);
+                .item(&open_file)
+                .item(&open_recent);
             
             // On Windows and Linux, add Settings and Exit in File menu
             #[cfg(not(target_os = "macos"))]
@@ -303,13 +898,9 @@ pub fn run() {
             let file_menu = file_menu_builder.build()?;
             
             // Create Save menu items
-            let save_item = MenuItemBuilder::with_id("save", "Save")
-                .accelerator("CmdOrCtrl+S")
-                .build(app)?;
+            let save_item = keybindings::menu_item(app.handle(), "save")?;
             
-            let save_all_item = MenuItemBuilder::with_id("save-all", "Save All")
-                .accelerator("CmdOrCtrl+Alt+S")
-                .build(app)?;
+            let save_all_item = keybindings::menu_item(app.handle(), "save-all")?;
             
             // Create Edit menu with standard editing commands
             let edit_menu = SubmenuBuilder::new(app, "Edit")
@@ -327,15 +918,16 @@ pub fn run() {
                 .build()?;
             
             // Create Terminal menu item
-            let toggle_terminal_item = MenuItemBuilder::with_id("toggle-terminal", "Toggle Terminal")
-                .accelerator("CmdOrCtrl+`")
-                .build(app)?;
+            let toggle_terminal_item = keybindings::menu_item(app.handle(), "toggle-terminal")?;
             
             // Create View menu
             let view_menu = SubmenuBuilder::new(app, "View")
                 .item(&toggle_terminal_item)
                 .build()?;
             
+            // Filled with the menu items running plugins contribute
+            let plugins_menu = plugins::build_menu(app.handle())?;
+            
             // Create main menu
             let menu = Menu::new(app)?;
             
@@ -360,11 +952,20 @@ pub fn run() {
             // Add View menu
             menu.append(&view_menu)?;
             
+            // Add Plugins menu
+            menu.append(&plugins_menu)?;
+            
             app.set_menu(menu)?;
             
             // Handle menu events
             app.on_menu_event(move |app, event| {
                 let event_id = event.id().as_ref();
+                if recents::handle_menu_event(app, event_id) {
+                    return;
+                }
+                if plugins::handle_menu_event(app, event_id) {
+                    return;
+                }
                 if let Some(window) = app.get_webview_window("main") {
                     match event_id {
                         "open-folder" => {
@@ -392,26 +993,221 @@ pub fn run() {
             
             Ok(())
         })
+        .on_window_event(drop_import::handle_window_event)
         .invoke_handler(tauri::generate_handler![
             greet,
             read_directory,
             path_exists,
+            file_info::stat_path,
             read_file_content,
+            encoding::read_file_with_encoding,
+            file_version::read_file_with_metadata,
+            large_file::read_file_range,
+            large_file::get_file_line_count,
+            hex_view::read_file_hex,
+            hex_view::write_file_hex_patch,
+            line_endings::convert_line_endings,
+            save_transforms::apply_save_transforms,
+            text_index::open_text_index,
+            text_index::query_index,
+            text_index::rebuild_index,
+            text_index::close_text_index,
             read_image_file,
+            images::get_image_info,
+            images::get_image_thumbnail,
+            image_preview::get_svg_preview,
+            image_preview::get_image_frames,
+            image_preview::extract_image_frame,
             create_file,
+            create_file_with_content,
+            suggest_untitled_name,
             create_directory,
+            create_symlink,
             delete_path,
+            restore_from_trash,
             rename_path,
+            archive::list_archive_entries,
+            archive::read_archive_file,
+            archive::extract_archive,
+            file_ops::copy_path,
+            file_ops::move_path,
+            file_ops::batch_fs_operation,
             save_file,
             execute_command,
             start_pty_session,
             write_to_pty,
+            resize_pty,
+            get_terminal_scrollback,
+            get_terminal_status,
+            pty::list_available_shells,
             stop_pty_session,
+            workspace::save_workspace_state,
+            workspace::load_workspace_state,
             lsp::start_lsp_server,
             lsp::stop_lsp_server,
+            lsp::restart_lsp_server,
+            lsp::get_lsp_logs,
+            lsp::get_lsp_status,
+            lsp::get_lsp_settings,
+            lsp::set_lsp_settings,
             lsp::detect_project_type,
             lsp::check_lsp_available,
+            lsp::list_lsp_servers,
+            lsp::register_lsp_server,
+            lsp::unregister_lsp_server,
+            lsp::installer::install_lsp_server,
+            lsp::installer::uninstall_lsp_server,
+            recents::add_recent,
+            recents::open_recent,
+            recents::get_recents,
+            recents::clear_recents,
+            tasks::list_tasks,
+            tasks::run_task,
+            tasks::cancel_task,
+            scaffold::scaffold_project,
+            run_configs::list_run_configs,
+            run_configs::save_run_config,
+            run_configs::delete_run_config,
+            run_configs::run_config,
+            run_configs::get_run_status,
+            dap::list_dap_adapters,
+            dap::start_dap_session,
+            dap::stop_dap_session,
+            file_index::build_file_index,
+            file_index::query_file_index,
+            symbol_index::build_symbol_index,
+            symbol_index::query_workspace_symbols,
+            highlight::highlight_range,
+            markdown::render_markdown,
+            export::export_markdown,
+            site::export_workspace,
+            pandoc::get_pandoc_info,
+            pandoc::convert_document,
+            assets::save_pasted_image,
+            front_matter::parse_front_matter,
+            front_matter::update_front_matter,
+            outline::get_markdown_outline,
+            outline::generate_toc,
+            diagrams::render_diagram,
+            diagrams::clear_diagram_cache,
+            links::build_link_index,
+            links::update_link_index,
+            links::get_backlinks,
+            links::get_outgoing_links,
+            links::get_link_graph,
+            tags::build_tag_index,
+            tags::get_all_tags,
+            tags::get_files_with_tag,
+            link_check::check_links,
+            link_rename::rename_with_link_update,
+            backups::store_unsaved_buffer,
+            backups::discard_unsaved_buffer,
+            backups::list_recoverable_buffers,
+            backups::read_recoverable_buffer,
+            history::list_file_history,
+            history::read_file_history_entry,
+            history::restore_file_history_entry,
+            remote::connect_remote,
+            remote::disconnect_remote,
+            settings::get_settings,
+            settings::get_setting,
+            settings::set_setting,
+            settings::set_settings,
+            themes::list_themes,
+            themes::load_theme,
+            templates::list_templates,
+            templates::get_templates_dir,
+            templates::create_from_template,
+            daily_notes::open_daily_note,
+            plugins::list_plugins,
+            plugins::start_plugin,
+            plugins::stop_plugin,
+            plugins::execute_plugin_command,
+            spellcheck::list_dictionaries,
+            spellcheck::check_text,
+            spellcheck::add_word,
+            spellcheck::remove_word,
+            workspace_settings::load_workspace_settings,
+            workspace_settings::save_workspace_settings,
+            workspace_settings::unwatch_workspace_settings,
+            commands::spawn_command,
+            commands::kill_command,
+            command_policy::get_command_policy,
+            command_policy::set_command_policy,
+            command_policy::confirm_command,
+            command_policy::get_command_audit_log,
+            shell_env::get_shell_environment,
+            processes::list_child_processes,
+            processes::kill_process_tree,
+            formatter::format_document,
+            diagnostics::set_linter_config,
+            diagnostics::run_linters,
+            dir_stats::compute_directory_stats,
+            dir_stats::cancel_directory_stats,
+            dir_stream::read_directory_stream,
+            dir_stream::cancel_directory_stream,
+            glob_files::glob_files,
+            fs_guard::remove_workspace_root,
+            fs_guard::get_workspace_roots,
+            logging::set_log_level,
+            logging::get_log_level,
+            logging::get_recent_logs,
+            updater::check_for_updates,
+            updater::download_update,
+            updater::install_update_and_restart,
+            deep_link::take_pending_deep_links,
+            single_instance::take_launch_paths,
+            problem_matcher::match_problems,
+            pickers::pick_folder,
+            pickers::pick_files,
+            pickers::pick_save_path,
+            keybindings::get_keybindings,
+            keybindings::set_keybinding,
+            drop_import::import_files_into_workspace,
+            system_open::open_in_default_app,
+            system_open::reveal_in_file_manager,
+            search::search_in_project,
+            search::cancel_search,
+            replace::replace_in_files,
+            replace::undo_last_replace,
+            git::git_status,
+            git::git_stage,
+            git::git_unstage,
+            git::git_commit,
+            git::git_diff_file,
+            git::git_current_branch,
+            git::git_line_diff,
+            git::git_list_branches,
+            git::git_create_branch,
+            git::git_checkout,
+            git::git_delete_branch,
+            git::git_stash_save,
+            git::git_stash_list,
+            git::git_stash_apply,
+            git::git_stash_pop,
+            git::git_stash_drop,
+            git_sync::git_fetch,
+            git_sync::git_pull,
+            git_sync::git_push,
+            git_sync::git_provide_credentials,
+            diff::compute_diff,
+            clipboard::clipboard_copy_files,
+            clipboard::clipboard_read_files,
+            clipboard::clipboard_paste_files,
+            html_to_markdown::convert_html_to_markdown,
+            url_metadata::fetch_url_metadata,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Give language servers a chance to exit cleanly instead of orphaning them
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(lsp::shutdown_all(&app.state::<lsp::LspState>()));
+                plugins::stop_all(app);
+            }
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = &event {
+                single_instance::open_urls(app, urls);
+            }
+        });
 }
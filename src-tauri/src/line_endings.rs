@@ -0,0 +1,159 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::atomic_write::write_atomic;
+use crate::fs_guard::FsGuardState;
+
+// Larger files are left alone by the bulk conversion
+const MAX_CONVERT_SIZE: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+/// How `save_file` treats the line endings of the content it is given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndingPolicy {
+    /// Use whatever the file on disk uses now
+    Preserve,
+    Lf,
+    Crlf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineEndingStats {
+    pub lf: usize,
+    pub crlf: usize,
+}
+
+impl LineEndingStats {
+    /// The prevailing style; None when the text has no line breaks
+    pub fn dominant(self) -> Option<LineEnding> {
+        match (self.lf, self.crlf) {
+            (0, 0) => None,
+            (lf, crlf) if crlf > lf => Some(LineEnding::Crlf),
+            _ => Some(LineEnding::Lf),
+        }
+    }
+
+    pub fn is_mixed(self) -> bool {
+        self.lf > 0 && self.crlf > 0
+    }
+}
+
+pub fn count(text: &str) -> LineEndingStats {
+    let crlf = text.matches("\r\n").count();
+    LineEndingStats {
+        lf: text.matches('\n').count() - crlf,
+        crlf,
+    }
+}
+
+/// Rewrites every line break in `text` as `target`
+pub fn convert(text: &str, target: LineEnding) -> Cow<'_, str> {
+    let stats = count(text);
+    match target {
+        LineEnding::Lf if stats.crlf == 0 => Cow::Borrowed(text),
+        LineEnding::Crlf if stats.lf == 0 => Cow::Borrowed(text),
+        LineEnding::Lf => Cow::Owned(text.replace("\r\n", "\n")),
+        LineEnding::Crlf => Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n")),
+    }
+}
+
+/// The line ending `content` should be saved with under `policy`, given the
+/// file's current bytes (if it exists)
+pub fn resolve_policy(policy: LineEndingPolicy, existing: Option<&[u8]>) -> Option<LineEnding> {
+    match policy {
+        LineEndingPolicy::Lf => Some(LineEnding::Lf),
+        LineEndingPolicy::Crlf => Some(LineEnding::Crlf),
+        LineEndingPolicy::Preserve => {
+            let existing = crate::encoding::decode(existing?, None);
+            count(&existing.content).dominant()
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ConvertSummary {
+    pub files_scanned: usize,
+    /// Files whose line endings were (or, on a dry run, would be) rewritten
+    pub changed: Vec<String>,
+    /// Binary, non-UTF-8 or oversized files that were left untouched
+    pub skipped: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+fn convert_file(path: &Path, target: LineEnding, dry_run: bool, summary: &mut ConvertSummary) {
+    let display = path.to_string_lossy().to_string();
+    summary.files_scanned += 1;
+    if fs::metadata(path).is_ok_and(|m| m.len() > MAX_CONVERT_SIZE) {
+        summary.skipped.push(display);
+        return;
+    }
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            summary.errors.push(format!("{}: {}", display, e));
+            return;
+        }
+    };
+    // Only plain UTF-8 text is rewritten; other encodings go through the editor
+    let text = match std::str::from_utf8(&bytes) {
+        Ok(text) if !text.contains('\0') => text,
+        _ => {
+            summary.skipped.push(display);
+            return;
+        }
+    };
+    let Cow::Owned(converted) = convert(text, target) else {
+        return;
+    };
+    if !dry_run {
+        if let Err(e) = write_atomic(path, converted.as_bytes()) {
+            summary.errors.push(format!("{}: {}", display, e));
+            return;
+        }
+    }
+    summary.changed.push(display);
+}
+
+/// Converts a file, or every text file below a folder (honouring
+/// .gitignore), to `target` line endings. `dry_run` only reports what would change.
+#[tauri::command]
+pub async fn convert_line_endings(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    target: LineEnding,
+    dry_run: Option<bool>,
+) -> Result<ConvertSummary, String> {
+    let root = guard.check(&path)?;
+    if !root.exists() {
+        return Err("Path does not exist".to_string());
+    }
+    let dry_run = dry_run.unwrap_or(false);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut summary = ConvertSummary::default();
+        if root.is_file() {
+            convert_file(&root, target, dry_run, &mut summary);
+            return summary;
+        }
+        for entry in WalkBuilder::new(&root).build().flatten() {
+            if entry.file_type().is_some_and(|t| t.is_file()) {
+                convert_file(entry.path(), target, dry_run, &mut summary);
+            }
+        }
+        summary
+    })
+    .await
+    .map_err(|e| format!("Convert task failed: {}", e))
+}
@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tokio::sync::Semaphore;
+
+use crate::fs_guard::FsGuardState;
+use crate::links::{self, LinkIndex, LinkIndexState, LinkKind};
+use crate::markdown::slugify;
+
+const USER_AGENT: &str = concat!("tmd-editor/", env!("CARGO_PKG_VERSION"));
+const DEFAULT_CONCURRENCY: usize = 8;
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CheckLinksOptions {
+    /// Also request http(s) URLs; off by default since it hits the network
+    pub check_external: bool,
+    /// Maximum number of URLs requested at once
+    pub concurrency: Option<usize>,
+    /// Per-request timeout in seconds
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkDiagnostic {
+    pub path: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 0-based column in characters
+    pub column: usize,
+    pub target: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+fn anchor_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"<a\s[^>]*(?:id|name)="([^"]+)""#).unwrap())
+}
+
+/// Fragment ids a document defines: heading slugs (deduplicated like the
+/// renderer does) and explicit `<a id="...">` anchors
+fn anchors_in(content: &str) -> HashSet<String> {
+    let mut anchors = HashSet::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            let slug = slugify(trimmed[hashes..].trim().trim_end_matches('#'));
+            let count = counts.entry(slug.clone()).or_insert(0);
+            anchors.insert(if *count == 0 { slug.clone() } else { format!("{}-{}", slug, count) });
+            *count += 1;
+        }
+        for caps in anchor_regex().captures_iter(line) {
+            anchors.insert(caps[1].to_string());
+        }
+    }
+    anchors
+}
+
+fn markdown_files(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+    WalkBuilder::new(path)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()) && links::is_markdown(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Checks local links and anchors; returns diagnostics plus the external URLs
+/// seen, with every place each one is used
+#[allow(clippy::type_complexity)]
+fn check_local(
+    files: &[PathBuf],
+    index: &LinkIndex,
+) -> (Vec<LinkDiagnostic>, HashMap<String, Vec<(String, usize, usize)>>) {
+    let mut diagnostics = Vec::new();
+    let mut external: HashMap<String, Vec<(String, usize, usize)>> = HashMap::new();
+    let mut anchor_cache: HashMap<PathBuf, HashSet<String>> = HashMap::new();
+
+    for file in files {
+        let Ok(content) = fs::read_to_string(file) else {
+            continue;
+        };
+        for link in links::scan_links(file, &content, true) {
+            let mut report = |severity, message: String| {
+                diagnostics.push(LinkDiagnostic {
+                    path: link.source.clone(),
+                    line: link.line,
+                    column: link.column,
+                    target: link.target.clone(),
+                    severity,
+                    message,
+                });
+            };
+
+            if links::is_external(&link.target) {
+                if link.target.starts_with("http://") || link.target.starts_with("https://") {
+                    external
+                        .entry(link.target.clone())
+                        .or_default()
+                        .push((link.source.clone(), link.line, link.column));
+                }
+                continue;
+            }
+
+            let Some(target) = index.resolve(&link) else {
+                let what = if link.kind == LinkKind::Wiki { "page" } else { "file" };
+                report(Severity::Error, format!("No {} named {}", what, link.target));
+                continue;
+            };
+
+            let Some(fragment) = &link.fragment else {
+                continue;
+            };
+            if !links::is_markdown(&target) {
+                continue;
+            }
+            let anchors = anchor_cache.entry(target.clone()).or_insert_with(|| {
+                fs::read_to_string(&target).map(|c| anchors_in(&c)).unwrap_or_default()
+            });
+            // Wiki links name the heading text, markdown links its slug
+            let wanted = if link.kind == LinkKind::Wiki { slugify(fragment) } else { fragment.clone() };
+            if !anchors.contains(&wanted) && !anchors.contains(&slugify(&wanted)) {
+                report(Severity::Warning, format!("No heading #{} in {}", fragment, target.display()));
+            }
+        }
+    }
+    (diagnostics, external)
+}
+
+/// Result of requesting `url`: None if it is reachable
+async fn check_url(client: &reqwest::Client, url: &str) -> Option<(Severity, String)> {
+    let response = match client.head(url).send().await {
+        // Plenty of servers reject HEAD; retry those with GET before reporting
+        Ok(r) if matches!(r.status().as_u16(), 403 | 405 | 501) => client.get(url).send().await,
+        other => other,
+    };
+    match response {
+        Ok(r) if r.status().is_success() || r.status().is_redirection() => None,
+        Ok(r) => Some((Severity::Error, format!("HTTP {}", r.status()))),
+        Err(e) if e.is_timeout() => Some((Severity::Warning, "Request timed out".to_string())),
+        Err(e) => Some((Severity::Warning, format!("Request failed: {}", e))),
+    }
+}
+
+async fn check_external(
+    urls: HashMap<String, Vec<(String, usize, usize)>>,
+    options: &CheckLinksOptions,
+) -> Result<Vec<LinkDiagnostic>, String> {
+    let client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(options.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let limit = Arc::new(Semaphore::new(options.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1)));
+
+    let checks = urls.into_iter().map(|(url, uses)| {
+        let client = client.clone();
+        let limit = limit.clone();
+        async move {
+            let _permit = limit.acquire_owned().await.ok()?;
+            let (severity, message) = check_url(&client, &url).await?;
+            Some(
+                uses.into_iter()
+                    .map(|(path, line, column)| LinkDiagnostic {
+                        path,
+                        line,
+                        column,
+                        target: url.clone(),
+                        severity,
+                        message: message.clone(),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+    });
+    Ok(futures_util::future::join_all(checks)
+        .await
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect())
+}
+
+/// Validates the links of one markdown document or of every document in a
+/// folder: relative files, wiki pages, `#anchors` and, if enabled, http(s) URLs
+#[tauri::command]
+pub async fn check_links(
+    state: State<'_, LinkIndexState>,
+    guard: State<'_, FsGuardState>,
+    path: String,
+    options: Option<CheckLinksOptions>,
+) -> Result<Vec<LinkDiagnostic>, String> {
+    let target = guard.check(&path)?;
+    let options = options.unwrap_or_default();
+    if !target.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+
+    // Wiki links resolve against the whole workspace when the file is part of it
+    let root = match state.root() {
+        Some(root) if target.starts_with(&root) => root,
+        _ if target.is_dir() => target.clone(),
+        _ => target.parent().map(Path::to_path_buf).unwrap_or_else(|| target.clone()),
+    };
+
+    let (mut diagnostics, external) = tauri::async_runtime::spawn_blocking(move || {
+        let index = links::build(&root);
+        check_local(&markdown_files(&target), &index)
+    })
+    .await
+    .map_err(|e| format!("Link check failed: {}", e))?;
+
+    if options.check_external && !external.is_empty() {
+        diagnostics.extend(check_external(external, &options).await?);
+    }
+
+    diagnostics.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)).then(a.column.cmp(&b.column)));
+    Ok(diagnostics)
+}
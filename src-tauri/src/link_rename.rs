@@ -0,0 +1,278 @@
+//! Renaming or moving a file without breaking links to it: markdown links
+//! and wiki links elsewhere in the workspace are rewritten, and so are the
+//! moved document's own relative links.
+
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::atomic_write;
+use crate::fs_guard::FsGuardState;
+use crate::links::{self, LinkIndex, LinkIndexState, LinkKind};
+
+#[derive(Debug, Serialize)]
+pub struct UpdatedFile {
+    /// Where the file is now; the renamed document's new path if it linked to itself
+    pub path: String,
+    /// Number of links rewritten
+    pub links: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailedFile {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameReport {
+    pub old_path: String,
+    pub new_path: String,
+    pub updated: Vec<UpdatedFile>,
+    /// Files whose links could not be rewritten; the rename itself went through
+    pub failed: Vec<FailedFile>,
+}
+
+/// One replacement in a document: 0-based line, byte range within it, new text
+struct Edit {
+    line: usize,
+    range: Range<usize>,
+    text: String,
+}
+
+/// Path of `to` as seen from the folder `from_dir`, both absolute
+fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut out = PathBuf::new();
+    for _ in common..from.len() {
+        out.push("..");
+    }
+    for component in &to[common..] {
+        out.push(component);
+    }
+    out
+}
+
+fn to_slashes(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// New destination for a markdown link to `target`, written the way `raw`
+/// was: filesystem-absolute, site-absolute (`/docs/page.md`) or relative,
+/// keeping a `./` prefix and the `#fragment`
+fn markdown_destination(raw: &str, bracketed: bool, source_dir: &Path, root: &Path, target: &Path) -> String {
+    let (path_part, fragment) = match raw.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (raw, None),
+    };
+    let decoded = links::percent_decode(path_part);
+    let written = Path::new(decoded.trim());
+
+    let mut destination = if written.is_absolute() && written.exists() {
+        to_slashes(target)
+    } else if written.is_absolute() {
+        format!("/{}", to_slashes(target.strip_prefix(root).unwrap_or(target)))
+    } else {
+        let relative = to_slashes(&relative_path(source_dir, target));
+        if path_part.starts_with("./") && !relative.starts_with("../") {
+            format!("./{}", relative)
+        } else {
+            relative
+        }
+    };
+    // Inside <...> spaces are fine; otherwise they would end the destination
+    if !bracketed {
+        destination = destination.replace(' ', "%20").replace('(', "%28").replace(')', "%29");
+    }
+    if let Some(fragment) = fragment {
+        destination.push('#');
+        destination.push_str(fragment);
+    }
+    destination
+}
+
+/// New page name for a wiki link to `target`: just the file name when that
+/// is how it was written and still unambiguous, else the path from the root
+fn wiki_destination(written: &str, index: &LinkIndex, root: &Path, old: &Path, target: &Path) -> String {
+    let written = written.trim();
+    let stem = target
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let qualified = written.contains(['/', '\\']);
+    let ambiguous = index.documents().filter(|path| *path != old).any(|path| {
+        path.file_stem()
+            .is_some_and(|s| s.to_string_lossy().to_lowercase() == stem.to_lowercase())
+    });
+
+    let mut page = match target.strip_prefix(root) {
+        Ok(relative) if qualified || ambiguous => to_slashes(&relative.with_extension("")),
+        _ => stem,
+    };
+    let lower = written.to_lowercase();
+    if lower.ends_with(".md") || lower.ends_with(".markdown") {
+        if let Some(extension) = target.extension() {
+            page.push('.');
+            page.push_str(&extension.to_string_lossy());
+        }
+    }
+    page
+}
+
+/// `content` with `edits` applied; their ranges must not overlap
+fn apply_edits(content: &str, edits: Vec<Edit>) -> String {
+    let mut line_starts = Vec::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        line_starts.push(offset);
+        offset += line.len();
+    }
+
+    let mut edits: Vec<(Range<usize>, String)> = edits
+        .into_iter()
+        .filter_map(|edit| {
+            let start = *line_starts.get(edit.line)?;
+            Some((start + edit.range.start..start + edit.range.end, edit.text))
+        })
+        .collect();
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut out = content.to_string();
+    for (range, text) in edits {
+        out.replace_range(range, &text);
+    }
+    out
+}
+
+/// Link rewrites needed for `old` to become `new`: each affected document
+/// with its updated content and the number of links changed
+fn plan(index: &LinkIndex, root: &Path, old: &Path, new: &Path) -> Vec<(PathBuf, String, usize)> {
+    let mut documents: Vec<PathBuf> = index.documents().map(Path::to_path_buf).collect();
+    documents.sort();
+    let new_dir = new.parent().unwrap_or(root);
+
+    let mut changes = Vec::new();
+    for document in documents {
+        let Ok(content) = fs::read_to_string(&document) else {
+            continue;
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        let moved = document == old;
+        let source_dir = if moved {
+            new_dir
+        } else {
+            document.parent().unwrap_or(root)
+        };
+
+        let mut edits = Vec::new();
+        for (link, span) in links::scan_link_spans(&document, &content, false) {
+            if link.target.is_empty() {
+                continue;
+            }
+            let Some(target) = index.resolve(&link) else {
+                continue;
+            };
+            // The moved document's own relative links change with its folder
+            let target = match (target == old, moved) {
+                (true, _) => new.to_path_buf(),
+                (false, true) if link.kind == LinkKind::Markdown => target,
+                (false, _) => continue,
+            };
+
+            let line = lines[link.line - 1];
+            let written = &line[span.clone()];
+            let text = match link.kind {
+                LinkKind::Markdown => {
+                    let bracketed = line[..span.start].ends_with('<');
+                    markdown_destination(written, bracketed, source_dir, root, &target)
+                }
+                LinkKind::Wiki => wiki_destination(written, index, root, old, &target),
+            };
+            if text != written {
+                edits.push(Edit {
+                    line: link.line - 1,
+                    range: span,
+                    text,
+                });
+            }
+        }
+
+        if !edits.is_empty() {
+            let count = edits.len();
+            changes.push((document, apply_edits(&content, edits), count));
+        }
+    }
+    changes
+}
+
+/// Renames or moves `old_path` to `new_path` and rewrites every markdown and
+/// wiki link in the workspace that pointed to it. The rename happens first;
+/// documents that can't be updated afterwards are listed in `failed`.
+#[tauri::command]
+pub async fn rename_with_link_update(
+    state: State<'_, LinkIndexState>,
+    guard: State<'_, FsGuardState>,
+    old_path: String,
+    new_path: String,
+) -> Result<RenameReport, String> {
+    let old = guard.check_entry(&old_path)?;
+    let new = guard.check_entry(&new_path)?;
+    if !old.is_file() {
+        return Err(format!("File not found: {}", old_path));
+    }
+    if new.exists() {
+        return Err(format!("File already exists: {}", new_path));
+    }
+
+    // Same workspace as the link index when the file is part of it
+    let root = match state.root() {
+        Some(root) if old.starts_with(&root) => root,
+        _ => old.parent().map(Path::to_path_buf).unwrap_or_else(|| old.clone()),
+    };
+
+    let (old_clone, new_clone) = (old.clone(), new.clone());
+    let report = tauri::async_runtime::spawn_blocking(move || {
+        let (old, new) = (old_clone, new_clone);
+        let index = links::build(&root);
+        let changes = plan(&index, &root, &old, &new);
+
+        if let Some(parent) = new.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        fs::rename(&old, &new).map_err(|e| format!("Failed to rename: {}", e))?;
+
+        let mut updated = Vec::new();
+        let mut failed = Vec::new();
+        for (document, content, links) in changes {
+            let path = if document == old { new.clone() } else { document };
+            let path_str = path.to_string_lossy().to_string();
+            match atomic_write::write_atomic(&path, content.as_bytes()) {
+                Ok(()) => updated.push(UpdatedFile { path: path_str, links }),
+                Err(e) => failed.push(FailedFile {
+                    path: path_str,
+                    error: format!("Failed to write file: {}", e),
+                }),
+            }
+        }
+        Ok::<_, String>(RenameReport {
+            old_path: old.to_string_lossy().to_string(),
+            new_path: new.to_string_lossy().to_string(),
+            updated,
+            failed,
+        })
+    })
+    .await
+    .map_err(|e| format!("Rename task failed: {}", e))??;
+
+    state.refresh(&old);
+    state.refresh(&new);
+    for file in &report.updated {
+        state.refresh(Path::new(&file.path));
+    }
+    Ok(report)
+}
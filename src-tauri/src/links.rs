@@ -0,0 +1,450 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::Serialize;
+use tauri::State;
+
+use crate::fs_guard::FsGuardState;
+
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    /// `[[Page]]`, `[[Page#Heading|alias]]`
+    Wiki,
+    /// `[text](path.md)` and `![alt](image.png)`
+    Markdown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Link {
+    pub kind: LinkKind,
+    /// Target as written, without the `#fragment`
+    pub target: String,
+    pub fragment: Option<String>,
+    /// Absolute path of the linked file, if it exists in the workspace
+    pub resolved: Option<String>,
+    /// Document containing the link
+    pub source: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 0-based column in characters
+    pub column: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphNode {
+    pub path: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    /// Number of links from `source` to `target`
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Raw links of every markdown document under a workspace root
+pub struct LinkIndex {
+    root: PathBuf,
+    /// Outgoing links per document, targets unresolved
+    documents: HashMap<PathBuf, Vec<Link>>,
+}
+
+#[derive(Default)]
+pub struct LinkIndexState {
+    index: Mutex<Option<LinkIndex>>,
+}
+
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .map(|e| MARKDOWN_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn wiki_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\[\[([^\[\]|#]+)(?:#([^\[\]|]*))?(?:\|[^\[\]]*)?\]\]").unwrap())
+}
+
+fn markdown_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"!?\[[^\]]*\]\(\s*(?:<([^>]+)>|([^)\s]+))(?:\s+"[^"]*")?\s*\)"#).unwrap())
+}
+
+/// Blanks out inline code spans so links inside them are ignored; keeps byte offsets
+pub(crate) fn mask_code_spans(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_code = false;
+    for c in line.chars() {
+        if c == '`' {
+            in_code = !in_code;
+            out.push(c);
+        } else if in_code {
+            out.push_str(&" ".repeat(c.len_utf8()));
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub fn is_external(target: &str) -> bool {
+    target.contains("://") || target.starts_with("mailto:") || target.starts_with("data:")
+}
+
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Local links in one document, skipping fenced code blocks and code spans.
+/// `resolved` is filled in by the index.
+pub fn extract_links(source: &Path, content: &str) -> Vec<Link> {
+    scan_links(source, content, false)
+}
+
+/// Like `extract_links`, optionally also returning external URLs (kept whole in `target`)
+pub fn scan_links(source: &Path, content: &str, include_external: bool) -> Vec<Link> {
+    scan_link_spans(source, content, include_external)
+        .into_iter()
+        .map(|(link, _)| link)
+        .collect()
+}
+
+/// Links with the byte range of their target as written within the line:
+/// the page name of a wiki link, or the destination (fragment included,
+/// without angle brackets) of a markdown link
+pub(crate) fn scan_link_spans(source: &Path, content: &str, include_external: bool) -> Vec<(Link, Range<usize>)> {
+    let source_str = source.to_string_lossy().to_string();
+    let mut links = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+
+        let masked = mask_code_spans(line);
+        let mut push = |kind, target: &str, fragment: Option<&str>, start: usize, span: Range<usize>| {
+            links.push((
+                Link {
+                    kind,
+                    target: target.trim().to_string(),
+                    fragment: fragment.map(|f| f.trim().to_string()).filter(|f| !f.is_empty()),
+                    resolved: None,
+                    source: source_str.clone(),
+                    line: i + 1,
+                    column: line[..start].chars().count(),
+                },
+                span,
+            ));
+        };
+
+        for caps in wiki_regex().captures_iter(&masked) {
+            let whole = caps.get(0).unwrap();
+            let page = caps.get(1).unwrap();
+            push(
+                LinkKind::Wiki,
+                page.as_str(),
+                caps.get(2).map(|m| m.as_str()),
+                whole.start(),
+                page.range(),
+            );
+        }
+        for caps in markdown_link_regex().captures_iter(&masked) {
+            let whole = caps.get(0).unwrap();
+            let Some(destination) = caps.get(1).or_else(|| caps.get(2)) else {
+                continue;
+            };
+            let raw = destination.as_str();
+            if is_external(raw) {
+                if include_external {
+                    push(LinkKind::Markdown, raw, None, whole.start(), destination.range());
+                }
+                continue;
+            }
+            let (target, fragment) = match raw.split_once('#') {
+                Some((t, f)) => (t, Some(f)),
+                None => (raw, None),
+            };
+            push(
+                LinkKind::Markdown,
+                &percent_decode(target),
+                fragment,
+                whole.start(),
+                destination.range(),
+            );
+        }
+    }
+    links
+}
+
+/// Resolves `.` and `..` without touching the filesystem
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+impl LinkIndex {
+    /// Document a link points to, if it is part of the index or exists on disk
+    pub fn resolve(&self, link: &Link) -> Option<PathBuf> {
+        let source = Path::new(&link.source);
+        let source_dir = source.parent().unwrap_or(&self.root);
+
+        if link.target.is_empty() {
+            // `[text](#heading)` points into the same document
+            return Some(source.to_path_buf());
+        }
+
+        match link.kind {
+            LinkKind::Markdown => {
+                let target = Path::new(&link.target);
+                let full = if target.is_absolute() && target.exists() {
+                    target.to_path_buf()
+                } else if target.is_absolute() {
+                    // Site-style absolute links are relative to the workspace root
+                    self.root.join(target.strip_prefix("/").unwrap_or(target))
+                } else {
+                    source_dir.join(target)
+                };
+                let full = normalize(&full);
+                (self.documents.contains_key(&full) || full.exists()).then_some(full)
+            }
+            LinkKind::Wiki => {
+                let wanted = link.target.replace('\\', "/").to_lowercase();
+                let wanted = wanted
+                    .strip_suffix(".md")
+                    .or_else(|| wanted.strip_suffix(".markdown"))
+                    .unwrap_or(&wanted)
+                    .to_string();
+                // Prefer a match in the linking document's folder, then the shortest path
+                let mut candidates: Vec<&PathBuf> = self
+                    .documents
+                    .keys()
+                    .filter(|path| {
+                        let rel = path.strip_prefix(&self.root).unwrap_or(path).with_extension("");
+                        let rel = rel.to_string_lossy().replace('\\', "/").to_lowercase();
+                        rel == wanted || rel.ends_with(&format!("/{}", wanted))
+                    })
+                    .collect();
+                candidates.sort_by_key(|path| (path.parent() != Some(source_dir), path.as_os_str().len()));
+                candidates.first().map(|p| (*p).clone())
+            }
+        }
+    }
+
+    /// Paths of the indexed documents
+    pub(crate) fn documents(&self) -> impl Iterator<Item = &Path> {
+        self.documents.keys().map(PathBuf::as_path)
+    }
+
+    fn resolved(&self, link: &Link) -> Link {
+        let mut link = link.clone();
+        link.resolved = self.resolve(&link).map(|p| p.to_string_lossy().to_string());
+        link
+    }
+
+    pub fn outgoing(&self, path: &Path) -> Vec<Link> {
+        self.documents
+            .get(path)
+            .map(|links| links.iter().map(|l| self.resolved(l)).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn backlinks(&self, path: &Path) -> Vec<Link> {
+        let mut links: Vec<Link> = self
+            .documents
+            .iter()
+            .filter(|(source, _)| source.as_path() != path)
+            .flat_map(|(_, links)| links.iter())
+            .filter(|link| self.resolve(link).as_deref() == Some(path))
+            .cloned()
+            .map(|mut link| {
+                link.resolved = Some(path.to_string_lossy().to_string());
+                link
+            })
+            .collect();
+        links.sort_by(|a, b| a.source.cmp(&b.source).then(a.line.cmp(&b.line)));
+        links
+    }
+
+    pub fn graph(&self) -> LinkGraph {
+        let mut edges: HashMap<(PathBuf, PathBuf), usize> = HashMap::new();
+        let mut nodes: HashSet<PathBuf> = self.documents.keys().cloned().collect();
+        for (source, links) in &self.documents {
+            for link in links {
+                if let Some(target) = self.resolve(link).filter(|t| t != source) {
+                    nodes.insert(target.clone());
+                    *edges.entry((source.clone(), target)).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut nodes: Vec<GraphNode> = nodes
+            .into_iter()
+            .map(|path| GraphNode {
+                name: path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+                path: path.to_string_lossy().to_string(),
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut edges: Vec<GraphEdge> = edges
+            .into_iter()
+            .map(|((source, target), count)| GraphEdge {
+                source: source.to_string_lossy().to_string(),
+                target: target.to_string_lossy().to_string(),
+                count,
+            })
+            .collect();
+        edges.sort_by(|a, b| a.source.cmp(&b.source).then(a.target.cmp(&b.target)));
+        LinkGraph { nodes, edges }
+    }
+
+    /// Re-reads one document, dropping it if it no longer exists
+    pub fn refresh(&mut self, path: &Path) {
+        if !path.starts_with(&self.root) || !is_markdown(path) {
+            return;
+        }
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                self.documents.insert(path.to_path_buf(), extract_links(path, &content));
+            }
+            Err(_) => {
+                self.documents.remove(path);
+            }
+        }
+    }
+}
+
+pub fn build(root: &Path) -> LinkIndex {
+    let walker = WalkBuilder::new(root)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let documents = walker
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()) && is_markdown(entry.path()))
+        .filter_map(|entry| {
+            let content = fs::read_to_string(entry.path()).ok()?;
+            Some((entry.path().to_path_buf(), extract_links(entry.path(), &content)))
+        })
+        .collect();
+
+    LinkIndex {
+        root: root.to_path_buf(),
+        documents,
+    }
+}
+
+impl LinkIndexState {
+    /// Keeps the index current after a document is saved, renamed or deleted
+    pub fn refresh(&self, path: &Path) {
+        if let Ok(mut index) = self.index.lock() {
+            if let Some(index) = index.as_mut() {
+                index.refresh(path);
+            }
+        }
+    }
+
+    /// Workspace root of the current index, if one has been built
+    pub fn root(&self) -> Option<PathBuf> {
+        self.index.lock().ok()?.as_ref().map(|index| index.root.clone())
+    }
+
+    fn with_index<T>(&self, f: impl FnOnce(&LinkIndex) -> T) -> Result<T, String> {
+        let index = self.index.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        let index = index.as_ref().ok_or_else(|| "Link index has not been built".to_string())?;
+        Ok(f(index))
+    }
+}
+
+/// Indexes the links of every markdown document under `root`; returns the document count
+#[tauri::command]
+pub async fn build_link_index(
+    state: State<'_, LinkIndexState>,
+    guard: State<'_, FsGuardState>,
+    root: String,
+) -> Result<usize, String> {
+    let root = guard.check(&root)?;
+    let index = tauri::async_runtime::spawn_blocking(move || build(&root))
+        .await
+        .map_err(|e| format!("Indexing task failed: {}", e))?;
+
+    let count = index.documents.len();
+    *state.index.lock().map_err(|e| format!("Failed to lock state: {}", e))? = Some(index);
+    Ok(count)
+}
+
+/// Re-indexes documents that changed on disk (created, edited, renamed or deleted)
+#[tauri::command]
+pub async fn update_link_index(
+    state: State<'_, LinkIndexState>,
+    guard: State<'_, FsGuardState>,
+    paths: Vec<String>,
+) -> Result<(), String> {
+    for path in paths {
+        state.refresh(&guard.check_entry(&path)?);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_backlinks(state: State<'_, LinkIndexState>, path: String) -> Result<Vec<Link>, String> {
+    state.with_index(|index| index.backlinks(Path::new(&path)))
+}
+
+#[tauri::command]
+pub async fn get_outgoing_links(state: State<'_, LinkIndexState>, path: String) -> Result<Vec<Link>, String> {
+    state.with_index(|index| index.outgoing(Path::new(&path)))
+}
+
+#[tauri::command]
+pub async fn get_link_graph(state: State<'_, LinkIndexState>) -> Result<LinkGraph, String> {
+    state.with_index(|index| index.graph())
+}
@@ -0,0 +1,175 @@
+//! Logging through `tracing`. Events go to stderr, to a daily rotated file
+//! in `app_log_dir` (a week of files is kept), and to an in-memory buffer
+//! behind `get_recent_logs` for the log panel and bug reports. The level
+//! applies to the editor's own modules; dependencies only log warnings and
+//! errors. It is stored in `logging.json` and can be changed at runtime.
+
+use std::collections::VecDeque;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_store::StoreExt;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
+
+const LOG_STORE: &str = "logging.json";
+const LEVEL_KEY: &str = "level";
+const LOG_FILE_PREFIX: &str = "tmd-editor";
+const KEPT_LOG_FILES: usize = 7;
+// Lines kept for `get_recent_logs`
+const RECENT_LINES: usize = 5000;
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+
+/// Keeps the newest formatted lines; each event is written as one line
+#[derive(Clone, Default)]
+struct RecentLines(Arc<Mutex<VecDeque<String>>>);
+
+impl io::Write for RecentLines {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Ok(mut lines) = self.0.lock() {
+            for line in String::from_utf8_lossy(buf).lines().filter(|l| !l.is_empty()) {
+                if lines.len() >= RECENT_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(line.to_string());
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for RecentLines {
+    type Writer = RecentLines;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+pub struct LogState {
+    recent: RecentLines,
+    filter: reload::Handle<Targets, Registry>,
+    level: Mutex<LevelFilter>,
+    log_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecentLogs {
+    /// Oldest first
+    pub lines: Vec<String>,
+    /// Where the rotated log files are, for attaching the full history
+    pub log_dir: Option<String>,
+}
+
+fn targets(level: LevelFilter) -> Targets {
+    Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), level)
+        .with_default(level.min(LevelFilter::WARN))
+}
+
+fn stored_level(app: &AppHandle) -> LevelFilter {
+    app.store(LOG_STORE)
+        .ok()
+        .and_then(|store| store.get(LEVEL_KEY))
+        .and_then(|value| value.as_str().and_then(|s| LevelFilter::from_str(s).ok()))
+        .unwrap_or(DEFAULT_LEVEL)
+}
+
+/// Installs the global subscriber; called once from `setup`
+pub fn init(app: &AppHandle) -> LogState {
+    let level = stored_level(app);
+    let (filter, handle) = reload::Layer::new(targets(level));
+    let recent = RecentLines::default();
+
+    let log_dir = app.path().app_log_dir().ok();
+    let file = log_dir.as_ref().map(|dir| {
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix("log")
+            .max_log_files(KEPT_LOG_FILES)
+            .build(dir)
+            .map_err(|e| format!("Failed to open log file in {}: {}", dir.display(), e))
+    });
+    // Reported once the logger is installed, so the error reaches stderr and the recent lines
+    let (file, file_error) = match file {
+        Some(Ok(file)) => (Some(file), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(io::stderr))
+        .with(file.map(|file| fmt::layer().with_ansi(false).with_writer(file)))
+        .with(fmt::layer().with_ansi(false).with_writer(recent.clone()))
+        .try_init();
+    if let Err(e) = result {
+        // Only fails when a logger is already installed, which then gets this
+        tracing::error!("Failed to install logger: {}", e);
+    }
+    if let Some(e) = file_error {
+        tracing::error!("{}", e);
+    }
+
+    LogState {
+        recent,
+        filter: handle,
+        level: Mutex::new(level),
+        log_dir,
+    }
+}
+
+/// Sets the level of the editor's own logs: "error", "warn", "info",
+/// "debug", "trace" or "off". Kept across restarts.
+#[tauri::command]
+pub async fn set_log_level(app_handle: AppHandle, state: State<'_, LogState>, level: String) -> Result<(), String> {
+    let filter = LevelFilter::from_str(level.trim()).map_err(|_| format!("Unknown log level: {}", level))?;
+    state
+        .filter
+        .reload(targets(filter))
+        .map_err(|e| format!("Failed to change log level: {}", e))?;
+    if let Ok(mut current) = state.level.lock() {
+        *current = filter;
+    }
+
+    let store = app_handle
+        .store(LOG_STORE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+    store.set(LEVEL_KEY, filter.to_string().to_lowercase());
+    store.save().map_err(|e| format!("Failed to save store: {}", e))?;
+    tracing::info!("Log level set to {}", filter);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_log_level(state: State<'_, LogState>) -> Result<String, String> {
+    let level = state.level.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    Ok(level.to_string().to_lowercase())
+}
+
+/// The last `lines` log lines of this session (default: all kept, up to 5000)
+#[tauri::command]
+pub async fn get_recent_logs(state: State<'_, LogState>, lines: Option<usize>) -> Result<RecentLogs, String> {
+    let recent = state
+        .recent
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to lock logs: {}", e))?;
+    let skip = lines.map_or(0, |lines| recent.len().saturating_sub(lines));
+    Ok(RecentLogs {
+        lines: recent.iter().skip(skip).cloned().collect(),
+        log_dir: state.log_dir.as_ref().map(|d| d.to_string_lossy().to_string()),
+    })
+}
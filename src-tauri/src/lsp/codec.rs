@@ -0,0 +1,150 @@
+//! Framing of the language server's stdout: `Content-Length` headers
+//! followed by a JSON body. Decoding works on whole buffered reads instead
+//! of fetching the header a byte at a time.
+
+use std::io;
+
+use tokio_util::bytes::{Buf, BytesMut};
+use tokio_util::codec::Decoder;
+
+const HEADER_END: &[u8] = b"\r\n\r\n";
+// A header this long without an end is not a header
+const MAX_HEADER_LEN: usize = 8 * 1024;
+// Far above the largest completion lists seen in practice; a bigger
+// Content-Length is a broken server, not a message to buffer
+const MAX_MESSAGE_LEN: usize = 64 * 1024 * 1024;
+
+#[derive(Default)]
+pub(super) struct LspCodec {
+    /// Body length of the message whose header was already consumed
+    content_length: Option<usize>,
+}
+
+fn content_length(header: &[u8]) -> Option<usize> {
+    String::from_utf8_lossy(header).split("\r\n").find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("content-length") {
+            return None;
+        }
+        value.trim().parse().ok()
+    })
+}
+
+impl Decoder for LspCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<String>> {
+        loop {
+            let length = match self.content_length {
+                Some(length) => length,
+                None => {
+                    let Some(end) = src.windows(HEADER_END.len()).position(|w| w == HEADER_END) else {
+                        if src.len() > MAX_HEADER_LEN {
+                            return Err(io::Error::new(io::ErrorKind::InvalidData, "LSP header too long"));
+                        }
+                        return Ok(None);
+                    };
+                    let length = content_length(&src[..end]);
+                    src.advance(end + HEADER_END.len());
+                    match length {
+                        Some(length) if length > MAX_MESSAGE_LEN => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!(
+                                    "LSP message of {} bytes exceeds the {} byte limit",
+                                    length, MAX_MESSAGE_LEN
+                                ),
+                            ));
+                        }
+                        Some(length) if length > 0 => length,
+                        _ => {
+                            tracing::warn!("Missing Content-Length");
+                            continue;
+                        }
+                    }
+                }
+            };
+            if src.len() < length {
+                self.content_length = Some(length);
+                src.reserve(length - src.len());
+                return Ok(None);
+            }
+            self.content_length = None;
+            let body = src.split_to(length);
+            match String::from_utf8(body.to_vec()) {
+                Ok(text) => return Ok(Some(text)),
+                Err(e) => tracing::warn!("UTF-8 error: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    /// Feeds `input` in pieces split at `splits` and collects every decoded message
+    fn decode_split(input: &[u8], splits: &[usize]) -> Vec<String> {
+        let mut codec = LspCodec::default();
+        let mut src = BytesMut::new();
+        let mut messages = Vec::new();
+        let mut start = 0;
+        for &end in splits.iter().chain(std::iter::once(&input.len())) {
+            src.extend_from_slice(&input[start..end]);
+            start = end;
+            while let Some(message) = codec.decode(&mut src).unwrap() {
+                messages.push(message);
+            }
+        }
+        messages
+    }
+
+    #[test]
+    fn decodes_whole_messages() {
+        let input = frame(r#"{"id":1}"#) + &frame(r#"{"id":2}"#);
+        assert_eq!(decode_split(input.as_bytes(), &[]), [r#"{"id":1}"#, r#"{"id":2}"#]);
+    }
+
+    #[test]
+    fn decodes_split_header() {
+        let input = frame(r#"{"id":1}"#);
+        for split in 1..input.find('{').unwrap() {
+            assert_eq!(
+                decode_split(input.as_bytes(), &[split]),
+                [r#"{"id":1}"#],
+                "split at {}",
+                split
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_split_body() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":[]}"#;
+        let input = frame(body);
+        let start = input.find('{').unwrap();
+        for split in start..input.len() {
+            assert_eq!(decode_split(input.as_bytes(), &[split]), [body], "split at {}", split);
+        }
+        let every_byte: Vec<usize> = (1..input.len()).collect();
+        assert_eq!(decode_split(input.as_bytes(), &every_byte), [body]);
+    }
+
+    #[test]
+    fn skips_header_without_length() {
+        let input = "X-Other: 1\r\n\r\n".to_string() + &frame("{}");
+        assert_eq!(decode_split(input.as_bytes(), &[]), ["{}"]);
+    }
+
+    #[test]
+    fn rejects_oversized_message() {
+        let mut src = BytesMut::from(format!("Content-Length: {}\r\n\r\n", MAX_MESSAGE_LEN + 1).as_bytes());
+        let error = LspCodec::default().decode(&mut src).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
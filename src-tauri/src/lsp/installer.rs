@@ -0,0 +1,400 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+
+use super::registry::{self, find_executable};
+use crate::error::AppError;
+
+// Servers are installed under app_data_dir/lsp-servers/{language_id}
+const INSTALL_DIR: &str = "lsp-servers";
+const INSTALL_MANIFEST: &str = "install.json";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+const USER_AGENT: &str = concat!("tmd-editor/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Clone, Copy)]
+enum AssetFormat {
+    /// A single gzip-compressed executable
+    Gz,
+    /// A zip archive extracted as a whole (clangd needs its bundled headers)
+    Zip,
+}
+
+/// Picks the release asset for this platform
+struct AssetSpec {
+    prefix: String,
+    suffix: &'static str,
+    format: AssetFormat,
+}
+
+enum InstallMethod {
+    /// Latest GitHub release, verified against the asset's published SHA-256 digest
+    GithubRelease { repo: &'static str, asset: AssetSpec },
+    /// `go install`, verified by the Go checksum database
+    GoInstall { package: &'static str },
+    /// `npm install` into a private prefix, verified by npm's lockfile integrity
+    Npm { packages: &'static [&'static str] },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledServer {
+    pub language_id: String,
+    pub version: String,
+    pub binary: String,
+    /// SHA-256 of the downloaded asset, for direct downloads
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallProgress {
+    pub language_id: String,
+    /// "resolving", "downloading", "verifying", "extracting", "installing" or "done"
+    pub stage: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+    size: u64,
+    /// "sha256:<hex>"; only present for assets uploaded since GitHub started recording digests
+    digest: Option<String>,
+}
+
+fn rust_target_triple() -> Option<&'static str> {
+    let triple = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => "x86_64-unknown-linux-gnu",
+        ("linux", "aarch64") => "aarch64-unknown-linux-gnu",
+        ("macos", "x86_64") => "x86_64-apple-darwin",
+        ("macos", "aarch64") => "aarch64-apple-darwin",
+        ("windows", "x86_64") => "x86_64-pc-windows-msvc",
+        ("windows", "aarch64") => "aarch64-pc-windows-msvc",
+        _ => return None,
+    };
+    Some(triple)
+}
+
+fn install_method(language_id: &str) -> Option<InstallMethod> {
+    match language_id {
+        "rust" => {
+            let triple = rust_target_triple()?;
+            let (suffix, format) = if cfg!(windows) { (".zip", AssetFormat::Zip) } else { (".gz", AssetFormat::Gz) };
+            Some(InstallMethod::GithubRelease {
+                repo: "rust-lang/rust-analyzer",
+                asset: AssetSpec {
+                    prefix: format!("rust-analyzer-{}", triple),
+                    suffix,
+                    format,
+                },
+            })
+        }
+        "cpp" => {
+            let platform = match (std::env::consts::OS, std::env::consts::ARCH) {
+                ("linux", "x86_64") => "linux",
+                ("macos", _) => "mac",
+                ("windows", _) => "windows",
+                _ => return None,
+            };
+            Some(InstallMethod::GithubRelease {
+                repo: "clangd/clangd",
+                asset: AssetSpec {
+                    prefix: format!("clangd-{}-", platform),
+                    suffix: ".zip",
+                    format: AssetFormat::Zip,
+                },
+            })
+        }
+        "go" => Some(InstallMethod::GoInstall {
+            package: "golang.org/x/tools/gopls@latest",
+        }),
+        "typescript" => Some(InstallMethod::Npm {
+            packages: &["typescript-language-server", "typescript"],
+        }),
+        "python" => Some(InstallMethod::Npm { packages: &["pyright"] }),
+        _ => None,
+    }
+}
+
+fn install_root(app: &AppHandle, language_id: &str) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|d| d.join(INSTALL_DIR).join(language_id))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+fn executable_name(command: &str) -> String {
+    format!("{}{}", command, std::env::consts::EXE_SUFFIX)
+}
+
+/// Path of a server previously installed by `install_lsp_server`, if it is still there
+pub fn installed_binary(app: &AppHandle, language_id: &str) -> Option<PathBuf> {
+    let manifest = fs::read(install_root(app, language_id).ok()?.join(INSTALL_MANIFEST)).ok()?;
+    let installed: InstalledServer = serde_json::from_slice(&manifest).ok()?;
+    let binary = PathBuf::from(installed.binary);
+    binary.is_file().then_some(binary)
+}
+
+fn emit_progress(app: &AppHandle, language_id: &str, stage: &str, downloaded: u64, total: Option<u64>) {
+    let progress = InstallProgress {
+        language_id: language_id.to_string(),
+        stage: stage.to_string(),
+        downloaded,
+        total,
+    };
+    let _ = app.emit(&format!("lsp-install-progress-{}", language_id), progress);
+}
+
+/// Looks for `name` up to a few levels below `dir`
+fn find_file(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+    let entries = fs::read_dir(dir).ok()?;
+    let mut subdirs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirs.push(path);
+        } else if entry.file_name() == name {
+            return Some(path);
+        }
+    }
+    if depth == 0 {
+        return None;
+    }
+    subdirs.iter().find_map(|d| find_file(d, name, depth - 1))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Failed to mark server executable: {}", e))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+async fn install_from_github(
+    app: &AppHandle,
+    language_id: &str,
+    command: &str,
+    dir: &Path,
+    repo: &str,
+    spec: &AssetSpec,
+) -> Result<InstalledServer, String> {
+    let client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    emit_progress(app, language_id, "resolving", 0, None);
+    let release: GithubRelease = client
+        .get(format!("https://api.github.com/repos/{}/releases/latest", repo))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to query {} releases: {}", repo, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid release metadata: {}", e))?;
+
+    let asset = release
+        .assets
+        .iter()
+        .find(|a| a.name.starts_with(&spec.prefix) && a.name.ends_with(spec.suffix))
+        .ok_or_else(|| format!("No {} release asset for this platform", repo))?;
+    let expected = asset
+        .digest
+        .as_deref()
+        .and_then(|d| d.strip_prefix("sha256:"))
+        .ok_or_else(|| format!("{} has no published checksum; refusing to install it", asset.name))?
+        .to_lowercase();
+
+    // Stream to disk, hashing as we go
+    let download_path = dir.join(format!(".{}.download", asset.name));
+    let response = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download {}: {}", asset.name, e))?;
+    let total = response.content_length().or(Some(asset.size));
+    let mut file = tokio::fs::File::create(&download_path)
+        .await
+        .map_err(|e| format!("Failed to create download file: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut downloaded = 0u64;
+    let mut last_emit = Instant::now();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+        hasher.update(&chunk);
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+        downloaded += chunk.len() as u64;
+        if last_emit.elapsed() >= PROGRESS_INTERVAL {
+            emit_progress(app, language_id, "downloading", downloaded, total);
+            last_emit = Instant::now();
+        }
+    }
+    file.flush().await.map_err(|e| format!("Failed to write download: {}", e))?;
+    drop(file);
+
+    emit_progress(app, language_id, "verifying", downloaded, total);
+    let actual: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    if actual != expected {
+        let _ = fs::remove_file(&download_path);
+        return Err(format!(
+            "Checksum mismatch for {} (expected {}, got {})",
+            asset.name, expected, actual
+        ));
+    }
+
+    emit_progress(app, language_id, "extracting", downloaded, total);
+    let binary_name = executable_name(command);
+    let bin_dir = dir.join("bin");
+    let download = download_path.clone();
+    let format = spec.format;
+    let binary = tauri::async_runtime::spawn_blocking(move || -> Result<PathBuf, String> {
+        if bin_dir.exists() {
+            fs::remove_dir_all(&bin_dir).map_err(|e| format!("Failed to remove old install: {}", e))?;
+        }
+        fs::create_dir_all(&bin_dir).map_err(|e| format!("Failed to create install directory: {}", e))?;
+        let binary = match format {
+            AssetFormat::Gz => {
+                let target = bin_dir.join(&binary_name);
+                let input = fs::File::open(&download).map_err(|e| format!("Failed to open download: {}", e))?;
+                let mut output = fs::File::create(&target).map_err(|e| format!("Failed to create binary: {}", e))?;
+                std::io::copy(&mut flate2::read::GzDecoder::new(input), &mut output)
+                    .map_err(|e| format!("Failed to decompress {}: {}", binary_name, e))?;
+                target
+            }
+            AssetFormat::Zip => {
+                // archive::extract picks the format from the extension
+                let zip_path = download.with_extension("zip");
+                fs::rename(&download, &zip_path).map_err(|e| format!("Failed to prepare archive: {}", e))?;
+                let result = crate::archive::extract(&zip_path, &bin_dir);
+                let _ = fs::remove_file(&zip_path);
+                result?;
+                find_file(&bin_dir, &binary_name, 3)
+                    .ok_or_else(|| format!("{} not found in the downloaded archive", binary_name))?
+            }
+        };
+        let _ = fs::remove_file(&download);
+        make_executable(&binary)?;
+        Ok(binary)
+    })
+    .await
+    .map_err(|e| format!("Install task failed: {}", e))??;
+
+    Ok(InstalledServer {
+        language_id: language_id.to_string(),
+        version: release.tag_name,
+        binary: binary.to_string_lossy().to_string(),
+        sha256: Some(actual),
+    })
+}
+
+async fn run_installer(program: &str, args: &[&str], envs: &[(&str, &Path)]) -> Result<String, String> {
+    let executable = find_executable(program).ok_or_else(|| format!("{} is required but was not found in PATH", program))?;
+    let mut cmd = tokio::process::Command::new(executable);
+    cmd.args(args);
+    for (key, value) in envs {
+        cmd.env(key, value);
+    }
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Downloads (or builds via the language toolchain) the server for `language`
+/// into the app data directory. Progress is reported on
+/// `lsp-install-progress-{language}`; the installed binary is then preferred
+/// over anything in PATH.
+#[tauri::command]
+pub async fn install_lsp_server(app_handle: AppHandle, language: String) -> Result<InstalledServer, AppError> {
+    let config = registry::find_server(&app_handle, &language)
+        .ok_or_else(|| AppError::not_found(format!("Unknown language: {}", language)))?;
+    let method = install_method(&language).ok_or_else(|| AppError::Unsupported {
+        message: format!("No installer available for {} on this platform", language),
+    })?;
+    let dir = install_root(&app_handle, &language)?;
+    fs::create_dir_all(&dir).map_err(|e| AppError::io("Failed to create install directory", e))?;
+
+    let installed = match method {
+        InstallMethod::GithubRelease { repo, asset } => {
+            install_from_github(&app_handle, &language, &config.command, &dir, repo, &asset).await?
+        }
+        InstallMethod::GoInstall { package } => {
+            emit_progress(&app_handle, &language, "installing", 0, None);
+            let bin_dir = dir.join("bin");
+            run_installer("go", &["install", package], &[("GOBIN", &bin_dir)]).await?;
+            InstalledServer {
+                language_id: language.clone(),
+                version: package.rsplit('@').next().unwrap_or("latest").to_string(),
+                binary: bin_dir.join(executable_name(&config.command)).to_string_lossy().to_string(),
+                sha256: None,
+            }
+        }
+        InstallMethod::Npm { packages } => {
+            emit_progress(&app_handle, &language, "installing", 0, None);
+            let prefix = dir.to_string_lossy().to_string();
+            let mut args = vec!["install", "--prefix", prefix.as_str(), "--no-fund", "--no-audit"];
+            args.extend_from_slice(packages);
+            run_installer("npm", &args, &[]).await?;
+            let shim = if cfg!(windows) { format!("{}.cmd", config.command) } else { config.command.clone() };
+            InstalledServer {
+                language_id: language.clone(),
+                version: "latest".to_string(),
+                binary: dir.join("node_modules").join(".bin").join(shim).to_string_lossy().to_string(),
+                sha256: None,
+            }
+        }
+    };
+
+    if !Path::new(&installed.binary).is_file() {
+        return Err(AppError::not_found(format!(
+            "Installation finished but {} was not found",
+            installed.binary
+        )));
+    }
+    let manifest = serde_json::to_vec_pretty(&installed).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    crate::atomic_write::write_atomic(&dir.join(INSTALL_MANIFEST), &manifest)
+        .map_err(|e| AppError::io("Failed to write install manifest", e))?;
+
+    emit_progress(&app_handle, &language, "done", 0, None);
+    tracing::info!("Installed {} server: {}", language, installed.binary);
+    Ok(installed)
+}
+
+#[tauri::command]
+pub async fn uninstall_lsp_server(app_handle: AppHandle, language: String) -> Result<(), AppError> {
+    let dir = install_root(&app_handle, &language)?;
+    if !dir.exists() {
+        return Err(AppError::not_found(format!("No installed server for language: {}", language)));
+    }
+    fs::remove_dir_all(&dir).map_err(|e| AppError::io("Failed to remove installed server", e))
+}
@@ -1,162 +1,867 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{oneshot, Mutex};
 
 use futures_util::{SinkExt, StreamExt};
-use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpListener;
-use tokio::process::{Child, Command};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
 use tokio_tungstenite::tungstenite::Message;
+use tokio_util::codec::FramedRead;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub enum LspLanguage {
-    Rust,
-    Go,
+use crate::command_policy::{self, CommandPolicyState};
+use crate::error::AppError;
+use crate::fs_guard::FsGuardState;
+
+mod codec;
+pub mod installer;
+pub mod registry;
+mod router;
+pub mod settings;
+use registry::LspServerConfig;
+
+/// Looks up the server for `language`, pointing built-in ones at a copy
+/// installed by `install_lsp_server` when there is one
+fn resolve_server(app_handle: &tauri::AppHandle, language: &str) -> Option<LspServerConfig> {
+    let mut config = registry::find_server(app_handle, language)?;
+    let is_builtin = registry::builtin_servers().contains(&config);
+    if is_builtin {
+        if let Some(binary) = installer::installed_binary(app_handle, language) {
+            config.command = binary.to_string_lossy().to_string();
+        }
+    }
+    Some(config)
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct StartLspResult {
     pub lsp_id: String,
     pub port: u16,
+    /// Must be sent as the `token` query parameter when connecting, e.g.
+    /// `ws://127.0.0.1:{port}/?token={token}`
+    pub token: String,
+    /// True if a server already running for the same language and root was returned
+    pub reused: bool,
+}
+
+/// A line the server wrote to stderr, sent as `lsp-log`
+#[derive(Debug, Clone, Serialize)]
+pub struct LspLogLine {
+    pub lsp_id: String,
+    pub line: String,
 }
 
-struct LspProcess {
-    #[allow(dead_code)]
-    child: Child,
+#[derive(Debug, Clone, Serialize)]
+pub struct LspLogs {
+    /// Oldest first
+    pub lines: Vec<String>,
+    /// Where the full stderr of this language's servers is kept
+    pub log_file: Option<String>,
 }
 
-struct LspServer {
-    #[allow(dead_code)]
-    language: LspLanguage,
-    #[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LspHealth {
+    /// Waiting for the answer to `initialize`
+    Starting,
+    /// Work-done progress (e.g. rust-analyzer indexing) is running
+    Indexing,
+    Ready,
+    /// Exited unexpectedly and not (yet) respawned
+    Crashed,
+}
+
+/// Answer of `get_lsp_status`, also sent periodically as `lsp-status`
+#[derive(Debug, Clone, Serialize)]
+pub struct LspStatus {
+    pub lsp_id: String,
+    pub language_id: String,
+    pub state: LspHealth,
+    pub pid: Option<u32>,
+    /// Time since the current process was started
+    pub uptime_ms: u64,
+    pub memory_bytes: Option<u64>,
+    /// Last message in either direction, in ms since the Unix epoch
+    pub last_activity_ms: Option<u64>,
+    pub clients: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LspCrashed {
+    pub lsp_id: String,
+    pub language_id: String,
+    pub exit_code: Option<i32>,
+    /// Last lines the server wrote to stderr, oldest first
+    pub stderr_tail: Vec<String>,
+    /// Whether the server will be respawned automatically
+    pub restarting: bool,
+    pub attempt: u32,
+}
+
+// How long a server gets to answer `shutdown` and then to exit after `exit`
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+// How long a respawned server gets to answer the replayed `initialize`
+const REINITIALIZE_TIMEOUT: Duration = Duration::from_secs(30);
+const STDERR_TAIL_LINES: usize = 50;
+// Stderr lines kept in memory for `get_lsp_logs`
+const LOG_BUFFER_LINES: usize = 2000;
+// A log file past this size is moved to `.old` when a server starts
+const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// A server that ran this long before crashing gets a fresh set of restart attempts
+const STABLE_RUN: Duration = Duration::from_secs(60);
+// How long a server with no connected clients is kept around for the next one
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+// How often the state is checked for `lsp-status`; changes are sent right away
+const STATUS_POLL: Duration = Duration::from_secs(1);
+// Unchanged status (uptime, memory) is sent this often
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// State that outlives the server process: clients stay connected to the
+/// same WebSocket while a crashed process is replaced behind it
+struct Shared {
+    config: LspServerConfig,
+    /// Secret a WebSocket client has to present; other local processes
+    /// must not be able to drive the server
+    token: String,
     root_path: PathBuf,
+    auto_restart: bool,
+    stdin: Mutex<Option<ChildStdin>>,
+    /// Connected clients and the requests each of them has in flight
+    router: router::Router,
+    /// Requests sent by the bridge itself (shutdown, replayed initialize), keyed by id
+    internal_requests: Mutex<HashMap<String, oneshot::Sender<String>>>,
+    /// The client's `initialize` params, replayed after a respawn
+    initialize_params: Mutex<Option<serde_json::Value>>,
+    /// User settings for the language; updated by `set_lsp_settings`
+    settings: StdMutex<settings::LspSettings>,
+    /// Recent stderr lines across restarts, oldest first
+    stderr_log: StdMutex<VecDeque<String>>,
+    /// `{app_log_dir}/lsp/{language}.log`, shared by that language's servers
+    log_file: Option<PathBuf>,
+    stopping: AtomicBool,
+    health: StdMutex<Health>,
+    /// Bumped on every connect and disconnect, so an idle timer can tell
+    /// whether anything happened while it was waiting
+    client_generation: AtomicU64,
+}
+
+/// What `get_lsp_status` reports about the current process
+struct Health {
+    pid: Option<u32>,
+    started: Instant,
+    last_activity: Option<SystemTime>,
+    crashed: bool,
+    /// A respawned process is being sent the replayed `initialize`
+    reinitializing: bool,
+    /// Tokens of `$/progress` that have begun but not ended
+    progress: HashSet<String>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            pid: None,
+            started: Instant::now(),
+            last_activity: None,
+            crashed: false,
+            reinitializing: false,
+            progress: HashSet::new(),
+        }
+    }
+}
+
+struct LspServer {
+    shared: Arc<Shared>,
     port: u16,
-    _ws_task: tokio::task::JoinHandle<()>,
-    _stdout_task: tokio::task::JoinHandle<()>,
+    /// `start_lsp_server` calls not yet matched by `stop_lsp_server`
+    refs: usize,
+    kill_tx: Option<oneshot::Sender<()>>,
+    /// Owns the child; finishes once the server is gone for good
+    supervisor: tokio::task::JoinHandle<()>,
+    ws_task: tokio::task::JoinHandle<()>,
+    status_task: tokio::task::JoinHandle<()>,
 }
 
-impl LspServer {
-    async fn spawn(language: LspLanguage, root_path: PathBuf) -> io::Result<Self> {
-        eprintln!("[LSP] Starting {:?} server for: {}", language, root_path.display());
-        
-        // 1) Spawn the language server process
-        let mut cmd = match language {
-            LspLanguage::Rust => Command::new("rust-analyzer"),
-            LspLanguage::Go => {
-                let mut c = Command::new("gopls");
-                c.arg("serve");
-                c
+impl Drop for LspServer {
+    fn drop(&mut self) {
+        // Aborting the supervisor drops the child, which is killed on drop
+        self.supervisor.abort();
+        self.ws_task.abort();
+        self.status_task.abort();
+    }
+}
+
+fn touch(shared: &Shared) {
+    if let Ok(mut health) = shared.health.lock() {
+        health.last_activity = Some(SystemTime::now());
+    }
+}
+
+/// Follows work-done progress so the status can show the server as busy
+fn track_progress(shared: &Shared, text: &str) {
+    if !text.contains("\"$/progress\"") {
+        return;
+    }
+    let Ok(message) = serde_json::from_str::<serde_json::Value>(text) else {
+        return;
+    };
+    let Some(params) = message.get("params") else {
+        return;
+    };
+    let token = params.get("token").map(|t| t.to_string()).unwrap_or_default();
+    let kind = params.pointer("/value/kind").and_then(|k| k.as_str());
+    if let Ok(mut health) = shared.health.lock() {
+        match kind {
+            Some("begin") => {
+                health.progress.insert(token);
+            }
+            Some("end") => {
+                health.progress.remove(&token);
             }
+            _ => {}
+        }
+    }
+}
+
+fn status(lsp_id: &str, shared: &Shared, memory_bytes: Option<u64>) -> LspStatus {
+    let health = shared.health.lock().unwrap_or_else(|e| e.into_inner());
+    let state = if health.crashed {
+        LspHealth::Crashed
+    } else if health.reinitializing || !shared.router.is_initialized().unwrap_or(false) {
+        LspHealth::Starting
+    } else if !health.progress.is_empty() {
+        LspHealth::Indexing
+    } else {
+        LspHealth::Ready
+    };
+    LspStatus {
+        lsp_id: lsp_id.to_string(),
+        language_id: shared.config.language_id.clone(),
+        state,
+        pid: health.pid,
+        uptime_ms: if health.pid.is_some() {
+            health.started.elapsed().as_millis() as u64
+        } else {
+            0
+        },
+        memory_bytes,
+        last_activity_ms: health
+            .last_activity
+            .and_then(|t| crate::file_info::system_time_ms(Ok(t))),
+        clients: shared.router.client_count().unwrap_or(0),
+    }
+}
+
+/// Sends `lsp-status` whenever the state changes, and every
+/// `STATUS_INTERVAL` otherwise
+async fn report_status(app_handle: AppHandle, lsp_id: String, shared: Arc<Shared>) {
+    let mut last: Option<(LspHealth, Instant)> = None;
+    loop {
+        tokio::time::sleep(STATUS_POLL).await;
+        let state = status(&lsp_id, &shared, None).state;
+        let due = match last {
+            Some((previous, sent)) => previous != state || sent.elapsed() >= STATUS_INTERVAL,
+            None => true,
+        };
+        if !due {
+            continue;
+        }
+        let pid = shared.health.lock().ok().and_then(|h| h.pid);
+        let memory = match pid {
+            Some(pid) => crate::processes::memory_usage(pid).await,
+            None => None,
         };
-        
-        cmd.current_dir(&root_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::inherit());
-
-        let mut child = cmd.spawn()?;
-        let stdin = child.stdin.take().ok_or_else(|| io::Error::new(io::ErrorKind::Other, "No stdin"))?;
-        let stdout = child.stdout.take().ok_or_else(|| io::Error::new(io::ErrorKind::Other, "No stdout"))?;
-
-        let _proc = Arc::new(Mutex::new(LspProcess { child }));
-        
-        // Separate stdin and stdout - NO SHARED MUTEX!
-        let stdin = Arc::new(Mutex::new(stdin));
-        let stdout = Arc::new(Mutex::new(stdout));
-        
-        let clients: Arc<Mutex<Vec<tokio::sync::mpsc::UnboundedSender<String>>>> = Arc::new(Mutex::new(Vec::new()));
+        let _ = app_handle.emit("lsp-status", status(&lsp_id, &shared, memory));
+        last = Some((state, Instant::now()));
+    }
+}
+
+async fn write_message(shared: &Shared, text: &str) -> io::Result<()> {
+    let mut stdin = shared.stdin.lock().await;
+    let stdin = stdin
+        .as_mut()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "Language server is not running"))?;
+    stdin
+        .write_all(format!("Content-Length: {}\r\n\r\n", text.len()).as_bytes())
+        .await?;
+    stdin.write_all(text.as_bytes()).await?;
+    stdin.flush().await?;
+    touch(shared);
+    Ok(())
+}
+
+/// Sends a request on behalf of the bridge; its response is not forwarded to clients
+async fn send_internal_request(
+    shared: &Shared,
+    method: &str,
+    params: serde_json::Value,
+) -> io::Result<oneshot::Receiver<String>> {
+    let id = format!("tmd-{}", Uuid::new_v4());
+    let (tx, rx) = oneshot::channel();
+    shared.internal_requests.lock().await.insert(id.clone(), tx);
+    let request = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+    write_message(shared, &request.to_string()).await?;
+    Ok(rx)
+}
+
+async fn send_notification(shared: &Shared, method: &str, params: serde_json::Value) -> io::Result<()> {
+    let notification = serde_json::json!({ "jsonrpc": "2.0", "method": method, "params": params });
+    write_message(shared, &notification.to_string()).await
+}
+
+/// Merges the user's `initializationOptions` into a client's `initialize`
+/// request and remembers its params so it can be replayed; other messages
+/// are returned unchanged
+async fn prepare_initialize(shared: &Shared, text: String) -> String {
+    if !text.contains("\"initialize\"") {
+        return text;
+    }
+    let mut value = match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(value) if value.get("method").and_then(|m| m.as_str()) == Some("initialize") => value,
+        _ => return text,
+    };
+    let options = shared
+        .settings
+        .lock()
+        .ok()
+        .and_then(|s| s.initialization_options.clone());
+    let text = match (options, value.get_mut("params")) {
+        (Some(options), Some(serde_json::Value::Object(params))) => {
+            let current = params.entry("initializationOptions").or_insert(serde_json::Value::Null);
+            if current.is_null() {
+                *current = serde_json::json!({});
+            }
+            settings::merge(current, &options);
+            value.to_string()
+        }
+        _ => text,
+    };
+    *shared.initialize_params.lock().await = value.get("params").cloned();
+    text
+}
+
+/// Answers a `workspace/configuration` request from the server out of the
+/// user's settings; true if it did. Without settings the clients answer.
+async fn answer_configuration(shared: &Shared, text: &str) -> bool {
+    if !text.contains("\"workspace/configuration\"") {
+        return false;
+    }
+    let Some(configured) = shared.settings.lock().ok().and_then(|s| s.settings.clone()) else {
+        return false;
+    };
+    let Ok(request) = serde_json::from_str::<serde_json::Value>(text) else {
+        return false;
+    };
+    let (Some("workspace/configuration"), Some(id)) =
+        (request.get("method").and_then(|m| m.as_str()), request.get("id"))
+    else {
+        return false;
+    };
+    let items = request
+        .pointer("/params/items")
+        .and_then(|i| i.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let result: Vec<serde_json::Value> = items
+        .iter()
+        .map(|item| settings::section(&configured, item.get("section").and_then(|s| s.as_str())))
+        .collect();
+    let response = serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result });
+    if let Err(e) = write_message(shared, &response.to_string()).await {
+        tracing::warn!("Failed to answer workspace/configuration: {}", e);
+    }
+    true
+}
+
+/// Hands `text` to a pending internal request if it answers one; true if it did
+async fn route_internal_response(shared: &Shared, text: &str) -> bool {
+    let mut pending = shared.internal_requests.lock().await;
+    if pending.is_empty() {
+        return false;
+    }
+    let value = match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    if value.get("method").is_some() {
+        return false;
+    }
+    let id = value.get("id").and_then(|i| i.as_str()).unwrap_or_default();
+    match pending.remove(id) {
+        Some(sender) => {
+            let _ = sender.send(text.to_string());
+            true
+        }
+        None => false,
+    }
+}
+
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_BACKOFF)
+}
+
+/// Log file for `language`'s servers; an oversized one is set aside first
+fn prepare_log_file(app_handle: &AppHandle, language: &str) -> Option<PathBuf> {
+    let dir = app_handle.path().app_log_dir().ok()?.join("lsp");
+    std::fs::create_dir_all(&dir).ok()?;
+    let path = dir.join(format!("{}.log", language));
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_FILE_BYTES) {
+        let _ = std::fs::rename(&path, path.with_extension("log.old"));
+    }
+    Some(path)
+}
+
+async fn open_log_file(path: &Path) -> Option<tokio::fs::File> {
+    tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| tracing::warn!("Failed to open {}: {}", path.display(), e))
+        .ok()
+}
+
+/// Servers of the same language share a file, so lines carry the PID; the
+/// file is dropped after a failed write
+async fn append_log(file: &mut Option<tokio::fs::File>, pid: u32, line: &str) {
+    if let Some(f) = file.as_mut() {
+        let stamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+        if f.write_all(format!("{} [{}] {}\n", stamp, pid, line).as_bytes()).await.is_err() {
+            *file = None;
+        }
+    }
+}
+
+/// Spawns the server process, wires its stdin into `shared` and starts the
+/// stdout/stderr readers. Returns the child and the stdout reader task.
+fn spawn_process(
+    app_handle: &AppHandle,
+    lsp_id: &str,
+    shared: &Arc<Shared>,
+) -> io::Result<(Child, tokio::task::JoinHandle<()>)> {
+    let config = &shared.config;
+    let mut cmd = Command::new(&config.command);
+    cmd.args(registry::expand_args(config, &shared.root_path))
+        .envs(crate::shell_env::environment());
+
+    cmd.current_dir(&shared.root_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Last resort if the server is dropped without a graceful shutdown
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn()?;
+    if let Ok(mut health) = shared.health.lock() {
+        *health = Health {
+            pid: child.id(),
+            ..Health::default()
+        };
+    }
+    let stdin = child.stdin.take().ok_or_else(|| io::Error::other("No stdin"))?;
+    let stdout = child.stdout.take().ok_or_else(|| io::Error::other("No stdout"))?;
+    let stderr = child.stderr.take().ok_or_else(|| io::Error::other("No stderr"))?;
+
+    // Swap in the new stdin; connected clients write through `shared`
+    if let Ok(mut slot) = shared.stdin.try_lock() {
+        *slot = Some(stdin);
+    } else {
+        let shared = shared.clone();
+        tokio::spawn(async move {
+            *shared.stdin.lock().await = Some(stdin);
+        });
+    }
+
+    // Keep stderr in memory and in the log file, and stream it as `lsp-log`
+    let shared_for_stderr = shared.clone();
+    let app_for_stderr = app_handle.clone();
+    let lsp_id_for_stderr = lsp_id.to_string();
+    let pid = child.id().unwrap_or_default();
+    tokio::spawn(async move {
+        let shared = shared_for_stderr;
+        let mut file = match &shared.log_file {
+            Some(path) => open_log_file(path).await,
+            None => None,
+        };
+        let started = format!("--- {} started in {} ---", shared.config.command, shared.root_path.display());
+        append_log(&mut file, pid, &started).await;
+
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::debug!(language = %shared.config.language_id, "{}", line);
+            append_log(&mut file, pid, &line).await;
+            if let Ok(mut log) = shared.stderr_log.lock() {
+                log.push_back(line.clone());
+                while log.len() > LOG_BUFFER_LINES {
+                    log.pop_front();
+                }
+            }
+            let _ = app_for_stderr.emit(
+                "lsp-log",
+                LspLogLine {
+                    lsp_id: lsp_id_for_stderr.clone(),
+                    line,
+                },
+            );
+        }
+    });
+
+    // Read from LSP stdout and broadcast to all clients
+    let shared_for_stdout = shared.clone();
+    let stdout_task = tokio::spawn(async move {
+        let mut messages = FramedRead::new(stdout, codec::LspCodec::default());
+        loop {
+            let text = match messages.next().await {
+                Some(Ok(text)) => text,
+                Some(Err(e)) => {
+                    tracing::warn!("Read error: {}", e);
+                    return;
+                }
+                None => return,
+            };
+
+            tracing::debug!("← Received from LSP: {} bytes", text.len());
+
+            touch(&shared_for_stdout);
+            track_progress(&shared_for_stdout, &text);
+
+            // Answers to the bridge's own requests are not meant for the clients
+            if route_internal_response(&shared_for_stdout, &text).await {
+                continue;
+            }
+
+            if answer_configuration(&shared_for_stdout, &text).await {
+                continue;
+            }
+
+            // Responses go to the client that asked, everything else to all of them
+            if let Err(e) = shared_for_stdout.router.route_server(&text) {
+                tracing::warn!("Dropped a message from the server: {}", e);
+            }
+        }
+    });
+
+    Ok((child, stdout_task))
+}
+
+/// Replays the client's `initialize` handshake against a respawned server
+async fn reinitialize(shared: &Shared) -> io::Result<()> {
+    let params = shared.initialize_params.lock().await.clone();
+    let params = match params {
+        Some(p) => p,
+        // The client never initialized the old process either
+        None => return Ok(()),
+    };
+    let response = send_internal_request(shared, "initialize", params).await?;
+    tokio::time::timeout(REINITIALIZE_TIMEOUT, response)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "initialize timed out"))?
+        .map_err(|_| io::Error::other("initialize was not answered"))?;
+    send_notification(shared, "initialized", serde_json::json!({})).await
+}
+
+/// Waits for the server process to exit. Unexpected exits are reported as
+/// `lsp-crashed` and, if enabled, the process is respawned with exponential
+/// backoff; `lsp-restarted` tells the frontend to reopen its documents.
+async fn supervise(
+    app_handle: AppHandle,
+    lsp_id: String,
+    shared: Arc<Shared>,
+    mut child: Child,
+    mut kill_rx: oneshot::Receiver<()>,
+) {
+    let mut attempt = 0u32;
+    let mut started = Instant::now();
+    loop {
+        let status = tokio::select! {
+            status = child.wait() => status.ok(),
+            _ = &mut kill_rx => {
+                // kill() also waits for the process, so nothing is left behind
+                let _ = child.kill().await;
+                return;
+            }
+        };
+        *shared.stdin.lock().await = None;
+        shared.internal_requests.lock().await.clear();
+        if let Err(e) = shared.router.server_exited() {
+            tracing::warn!("Failed to fail pending requests: {}", e);
+        }
+        if shared.stopping.load(Ordering::SeqCst) {
+            tracing::info!("{} exited", lsp_id);
+            return;
+        }
+
+        if started.elapsed() >= STABLE_RUN {
+            attempt = 0;
+        }
+        attempt += 1;
+        let restarting = shared.auto_restart && attempt <= MAX_RESTART_ATTEMPTS;
+        if let Ok(mut health) = shared.health.lock() {
+            health.crashed = true;
+            health.pid = None;
+        }
+        let stderr_tail = shared
+            .stderr_log
+            .lock()
+            .map(|log| log.iter().skip(log.len().saturating_sub(STDERR_TAIL_LINES)).cloned().collect())
+            .unwrap_or_default();
+        tracing::error!("{} crashed with {:?}", lsp_id, status);
+        let _ = app_handle.emit(
+            "lsp-crashed",
+            LspCrashed {
+                lsp_id: lsp_id.clone(),
+                language_id: shared.config.language_id.clone(),
+                exit_code: status.and_then(|s| s.code()),
+                stderr_tail,
+                restarting,
+                attempt,
+            },
+        );
+        if !restarting {
+            return;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(backoff(attempt)) => {}
+            _ = &mut kill_rx => return,
+        }
+        if shared.stopping.load(Ordering::SeqCst) {
+            return;
+        }
+
+        match spawn_process(&app_handle, &lsp_id, &shared) {
+            Ok((new_child, _stdout_task)) => {
+                child = new_child;
+                started = Instant::now();
+                if let Ok(mut health) = shared.health.lock() {
+                    health.reinitializing = true;
+                }
+                if let Err(e) = reinitialize(&shared).await {
+                    tracing::warn!("Failed to reinitialize {}: {}", lsp_id, e);
+                }
+                if let Ok(mut health) = shared.health.lock() {
+                    health.reinitializing = false;
+                }
+                tracing::info!("Restarted {} (attempt {})", lsp_id, attempt);
+                let _ = app_handle.emit("lsp-restarted", lsp_id.clone());
+            }
+            Err(e) => {
+                tracing::warn!("Failed to respawn {}: {}", lsp_id, e);
+                let _ = app_handle.emit(
+                    "lsp-crashed",
+                    LspCrashed {
+                        lsp_id: lsp_id.clone(),
+                        language_id: shared.config.language_id.clone(),
+                        exit_code: None,
+                        stderr_tail: vec![e.to_string()],
+                        restarting: false,
+                        attempt,
+                    },
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Compares in time independent of where the strings differ
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Lets the handshake through only with the server's token in the query
+/// (`?token=`) or an `Authorization: Bearer` header. The error type is
+/// fixed by tungstenite's handshake callback.
+#[allow(clippy::result_large_err)]
+pub(crate) fn authorize(token: &str, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+    let from_query = request
+        .uri()
+        .query()
+        .into_iter()
+        .flat_map(|q| q.split('&'))
+        .find_map(|pair| pair.strip_prefix("token="));
+    let from_header = request
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if from_query
+        .or(from_header)
+        .is_some_and(|given| tokens_match(given, token))
+    {
+        return Ok(response);
+    }
+    let mut rejection = ErrorResponse::new(Some("Invalid or missing token".to_string()));
+    *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+    Err(rejection)
+}
+
+/// Shuts the server down if no client has connected within `IDLE_TIMEOUT`
+/// of the last one leaving (`generation` is the count at that moment)
+async fn stop_when_idle(app_handle: AppHandle, lsp_id: String, shared: Arc<Shared>, generation: u64) {
+    tokio::time::sleep(IDLE_TIMEOUT).await;
+    if shared.client_generation.load(Ordering::SeqCst) != generation || shared.router.client_count() != Ok(0) {
+        return;
+    }
+    let state = app_handle.state::<LspState>();
+    let server = {
+        let mut servers = state.servers.lock().await;
+        // The id may belong to a newer server after a restart
+        match servers.get(&lsp_id) {
+            Some(server) if Arc::ptr_eq(&server.shared, &shared) => servers.remove(&lsp_id),
+            _ => None,
+        }
+    };
+    if let Some(server) = server {
+        tracing::info!("Stopping idle server {}", lsp_id);
+        server.shutdown().await;
+        let _ = app_handle.emit("lsp-stopped", lsp_id);
+    }
+}
+
+impl LspServer {
+    async fn spawn(
+        app_handle: AppHandle,
+        lsp_id: String,
+        config: &LspServerConfig,
+        root_path: PathBuf,
+        auto_restart: bool,
+    ) -> io::Result<Self> {
+        tracing::info!("Starting {} server for: {}", config.language_id, root_path.display());
+
+        let shared = Arc::new(Shared {
+            config: config.clone(),
+            token: Uuid::new_v4().simple().to_string(),
+            root_path,
+            auto_restart,
+            stdin: Mutex::new(None),
+            router: router::Router::default(),
+            internal_requests: Mutex::new(HashMap::new()),
+            initialize_params: Mutex::new(None),
+            settings: StdMutex::new(settings::load(&app_handle, &config.language_id)),
+            stderr_log: StdMutex::new(VecDeque::new()),
+            log_file: prepare_log_file(&app_handle, &config.language_id),
+            stopping: AtomicBool::new(false),
+            health: StdMutex::new(Health::default()),
+            client_generation: AtomicU64::new(0),
+        });
+
+        // 1) Spawn the language server process
+        let (child, _stdout_task) = spawn_process(&app_handle, &lsp_id, &shared)?;
 
         // 2) Start WebSocket server on random port
         let listener = TcpListener::bind("127.0.0.1:0").await?;
         let port = listener.local_addr()?.port();
-        
-        eprintln!("[LSP] WebSocket server bound to port {}", port);
 
-        let clients_clone = clients.clone();
+        tracing::debug!("WebSocket server bound to port {}", port);
 
         // Use oneshot to ensure WebSocket server is ready
-        let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+        let (ready_tx, ready_rx) = oneshot::channel();
         let port_for_log = port;
-        
+        let shared_for_ws = shared.clone();
+        let (app_for_ws, id_for_ws) = (app_handle.clone(), lsp_id.clone());
+
         // WebSocket acceptor task
         let ws_task = tokio::spawn(async move {
             // Signal ready immediately after task starts
             let _ = ready_tx.send(());
-            eprintln!("[LSP] WebSocket acceptor ready on port {}", port_for_log);
-            
+            tracing::debug!("WebSocket acceptor ready on port {}", port_for_log);
+
             while let Ok((stream, _addr)) = listener.accept().await {
-                eprintln!("[LSP] Client connecting...");
-                
-                let ws_stream = match tokio_tungstenite::accept_async(stream).await {
+                tracing::debug!("Client connecting...");
+
+                let token = shared_for_ws.token.clone();
+                #[allow(clippy::result_large_err)]
+                let handshake = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response| {
+                    authorize(&token, request, response)
+                });
+                let ws_stream = match handshake.await {
                     Ok(s) => {
-                        eprintln!("[LSP] WebSocket handshake successful");
+                        tracing::debug!("WebSocket handshake successful");
                         s
                     },
                     Err(e) => {
-                        eprintln!("[LSP] WebSocket handshake failed: {}", e);
+                        tracing::warn!("WebSocket handshake failed: {}", e);
                         continue;
                     }
                 };
 
                 let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
-                {
-                    let mut list = clients_clone.lock().await;
-                    list.push(tx);
-                }
+                let client_id = match shared_for_ws.router.add_client(tx) {
+                    Ok(id) => id,
+                    Err(e) => {
+                        tracing::warn!("Failed to add client: {}", e);
+                        continue;
+                    }
+                };
+                shared_for_ws.client_generation.fetch_add(1, Ordering::SeqCst);
+                tracing::debug!(
+                    "Client {} connected ({} total)",
+                    client_id,
+                    shared_for_ws.router.client_count().unwrap_or(0)
+                );
 
                 let (mut sink, mut stream) = ws_stream.split();
-                let stdin_for_ws = stdin.clone();
+                let shared_for_client = shared_for_ws.clone();
+                let (app_for_client, id_for_client) = (app_for_ws.clone(), id_for_ws.clone());
 
                 // Client -> LSP
                 let writer_task = tokio::spawn(async move {
                     while let Some(Ok(msg)) = stream.next().await {
                         if let Message::Text(text) = msg {
-                            eprintln!("[LSP] → Received from WebSocket: {} bytes", text.len());
-                            eprintln!("[LSP] Message preview: {}", &text[..text.len().min(200)]);
-                            
-                            // Prepare the full message before locking
-                            let content_len = text.as_bytes().len();
-                            let header = format!("Content-Length: {}\r\n\r\n", content_len);
-                            let mut full_message = Vec::new();
-                            full_message.extend_from_slice(header.as_bytes());
-                            full_message.extend_from_slice(text.as_bytes());
-                            
-                            eprintln!("[LSP] Prepared message: header={}, body={}, total={}", 
-                                header.len(), text.len(), full_message.len());
-                            
-                            // Now lock stdin ONLY (not stdout!)
-                            let mut stdin_guard = stdin_for_ws.lock().await;
-                            eprintln!("[LSP] Got stdin lock, writing...");
-                            
-                            if let Err(e) = stdin_guard.write_all(&full_message).await {
-                                eprintln!("[LSP] Write error: {}", e);
-                                break;
-                            }
-                            eprintln!("[LSP] Write complete, flushing...");
-                            
-                            if let Err(e) = stdin_guard.flush().await {
-                                eprintln!("[LSP] Flush error: {}", e);
-                                break;
+                            tracing::debug!("→ Received from WebSocket: {} bytes", text.len());
+                            let text = match shared_for_client.router.route_client(client_id, &text) {
+                                Ok(Some(text)) => text,
+                                Ok(None) => continue,
+                                Err(e) => {
+                                    tracing::warn!("Dropped a message from client {}: {}", client_id, e);
+                                    continue;
+                                }
+                            };
+                            let text = prepare_initialize(&shared_for_client, text).await;
+
+                            // A write fails while a crashed server is being respawned;
+                            // keep the client connected and let the restart event resync it
+                            if let Err(e) = write_message(&shared_for_client, &text).await {
+                                tracing::warn!("Write error: {}", e);
                             }
-                            // Release lock immediately
-                            drop(stdin_guard);
-                            
-                            eprintln!("[LSP] ✓ Sent to LSP successfully (total {} bytes)", full_message.len());
                         }
                     }
-                    eprintln!("[LSP] Writer task ended");
+                    // Requests the client was still waiting for are of no use to anyone
+                    let cancels = shared_for_client.router.remove_client(client_id).unwrap_or_else(|e| {
+                        tracing::warn!("Failed to remove client {}: {}", client_id, e);
+                        Vec::new()
+                    });
+                    for cancel in cancels {
+                        let _ = write_message(&shared_for_client, &cancel).await;
+                    }
+                    tracing::debug!("Client {} disconnected", client_id);
+                    let generation = shared_for_client.client_generation.fetch_add(1, Ordering::SeqCst) + 1;
+                    if shared_for_client.router.client_count() == Ok(0) {
+                        tokio::spawn(stop_when_idle(app_for_client, id_for_client, shared_for_client, generation));
+                    }
                 });
 
                 // LSP -> Client
                 let forward_task = tokio::spawn(async move {
                     while let Some(msg) = rx.recv().await {
                         if let Err(e) = sink.send(Message::Text(msg)).await {
-                            eprintln!("[LSP] Forward error: {}", e);
+                            tracing::warn!("Forward error: {}", e);
                             break;
                         }
                     }
@@ -166,92 +871,47 @@ impl LspServer {
             }
         });
 
-        // Read from LSP stdout and broadcast to all clients
-        let stdout_for_reader = stdout.clone();
-        let clients_for_stdout = clients.clone();
-        let stdout_task = tokio::spawn(async move {
-            let mut buf = Vec::new();
-            loop {
-                // Read LSP header
-                let mut header = Vec::new();
-                {
-                    let mut stdout_guard = stdout_for_reader.lock().await;
-                    let mut last4 = [0u8; 4];
-                    loop {
-                        let mut byte = [0u8; 1];
-                        if let Err(e) = stdout_guard.read_exact(&mut byte).await {
-                            eprintln!("[LSP] Read error (header): {}", e);
-                            return;
-                        }
-                        header.push(byte[0]);
-                        last4[0] = last4[1];
-                        last4[1] = last4[2];
-                        last4[2] = last4[3];
-                        last4[3] = byte[0];
-                        if last4 == [b'\r', b'\n', b'\r', b'\n'] {
-                            break;
-                        }
-                    }
-                }
-
-                // Parse Content-Length
-                let header_str = String::from_utf8_lossy(&header);
-                let mut content_length: usize = 0;
-                for line in header_str.split("\r\n") {
-                    if let Some(rest) = line.to_ascii_lowercase().strip_prefix("content-length:") {
-                        if let Ok(n) = rest.trim().parse::<usize>() {
-                            content_length = n;
-                        }
-                    }
-                }
-                
-                if content_length == 0 {
-                    eprintln!("[LSP] Missing Content-Length");
-                    continue;
-                }
-
-                // Read body
-                buf.clear();
-                buf.resize(content_length, 0);
-                {
-                    let mut stdout_guard = stdout_for_reader.lock().await;
-                    if let Err(e) = stdout_guard.read_exact(&mut buf).await {
-                        eprintln!("[LSP] Read error (body): {}", e);
-                        return;
-                    }
-                }
-                
-                let text = match String::from_utf8(buf.clone()) {
-                    Ok(s) => s,
-                    Err(e) => {
-                        eprintln!("[LSP] UTF-8 error: {}", e);
-                        continue;
-                    }
-                };
-
-                eprintln!("[LSP] ← Received from LSP: {} bytes", text.len());
-
-                // Broadcast to all clients
-                let list = clients_for_stdout.lock().await;
-                eprintln!("[LSP] Broadcasting to {} client(s)", list.len());
-                for sender in list.iter() {
-                    let _ = sender.send(text.clone());
-                }
-            }
-        });
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let status_task = tokio::spawn(report_status(app_handle.clone(), lsp_id.clone(), shared.clone()));
+        let supervisor = tokio::spawn(supervise(app_handle, lsp_id, shared.clone(), child, kill_rx));
 
         // Wait for WebSocket server to be ready
-        ready_rx.await.map_err(|_| io::Error::new(io::ErrorKind::Other, "WebSocket task failed"))?;
-        eprintln!("[LSP] Server fully initialized on port {}", port);
+        ready_rx.await.map_err(|_| io::Error::other("WebSocket task failed"))?;
+        tracing::info!("Server fully initialized on port {}", port);
 
         Ok(Self {
-            language,
-            root_path,
+            shared,
             port,
-            _ws_task: ws_task,
-            _stdout_task: stdout_task,
+            refs: 1,
+            kill_tx: Some(kill_tx),
+            supervisor,
+            ws_task,
+            status_task,
         })
     }
+
+    /// Sends `shutdown` and `exit`, then waits for the process to go away,
+    /// killing it if it doesn't within `SHUTDOWN_TIMEOUT`. Always reaps the child.
+    async fn shutdown(mut self) {
+        let language = self.shared.config.language_id.clone();
+        self.shared.stopping.store(true, Ordering::SeqCst);
+
+        if let Ok(response) = send_internal_request(&self.shared, "shutdown", serde_json::Value::Null).await {
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, response).await.is_err() {
+                tracing::warn!("{} did not answer shutdown in time", language);
+            }
+            let _ = send_notification(&self.shared, "exit", serde_json::Value::Null).await;
+        }
+
+        // The supervisor returns as soon as the process has exited
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut self.supervisor).await.is_err() {
+            tracing::warn!("Killing unresponsive {} server", language);
+            if let Some(kill) = self.kill_tx.take() {
+                let _ = kill.send(());
+            }
+            let _ = (&mut self.supervisor).await;
+        }
+    }
 }
 
 #[derive(Default)]
@@ -259,48 +919,245 @@ pub struct LspState {
     servers: Mutex<HashMap<String, LspServer>>,
 }
 
+/// Gracefully stops every running server, e.g. when the app exits
+pub async fn shutdown_all(state: &LspState) {
+    let servers: Vec<LspServer> = state.servers.lock().await.drain().map(|(_, s)| s).collect();
+    futures_util::future::join_all(servers.into_iter().map(LspServer::shutdown)).await;
+}
+
+/// Takes another reference to the server already running for `config`'s
+/// language in `root`, if there is one. One whose supervisor gave up is
+/// dropped so a fresh server gets started instead.
+fn reuse(servers: &mut HashMap<String, LspServer>, config: &LspServerConfig, root: &Path) -> Option<StartLspResult> {
+    let matches = |s: &LspServer| s.shared.config.language_id == config.language_id && s.shared.root_path == root;
+    servers.retain(|id, s| {
+        let dead = matches(s) && s.supervisor.is_finished();
+        if dead {
+            tracing::info!("Dropping {}, which is no longer running", id);
+        }
+        !dead
+    });
+    let (id, server) = servers.iter_mut().find(|(_, s)| matches(s))?;
+    server.refs += 1;
+    tracing::debug!("Reusing {} (port {}, {} references)", id, server.port, server.refs);
+    Some(StartLspResult {
+        lsp_id: id.clone(),
+        port: server.port,
+        token: server.shared.token.clone(),
+        reused: true,
+    })
+}
+
+/// Custom servers run whatever command was registered, so they go through
+/// `command_policy` like any other program; built-in ones don't
+async fn authorize_custom(
+    app_handle: &AppHandle,
+    source: &str,
+    config: &LspServerConfig,
+    args: &[String],
+    root: Option<&Path>,
+) -> Result<(), AppError> {
+    // A custom server for a language takes precedence over the built-in one
+    if !registry::custom_servers(app_handle).iter().any(|c| c.language_id == config.language_id) {
+        return Ok(());
+    }
+    let policy = app_handle.state::<CommandPolicyState>();
+    command_policy::authorize(app_handle, &policy, source, &config.command, args, root, false).await
+}
+
+/// Starts a language server for `language` in `root_path`, or returns the
+/// one already running for the same language and root; each call must be
+/// matched by a `stop_lsp_server`
 #[tauri::command]
 pub async fn start_lsp_server(
+    app_handle: tauri::AppHandle,
     state: tauri::State<'_, LspState>,
+    guard: tauri::State<'_, FsGuardState>,
     language: String,
     root_path: String,
-) -> Result<StartLspResult, String> {
-    let lang = match language.as_str() {
-        "rust" => LspLanguage::Rust,
-        "go" => LspLanguage::Go,
-        _ => return Err(format!("Unsupported language: {}", language)),
-    };
+    auto_restart: Option<bool>,
+) -> Result<StartLspResult, AppError> {
+    // Canonical, so servers are shared per language and root however the root is spelled
+    let root = guard.check(&root_path)?;
+    let config = resolve_server(&app_handle, &language).ok_or_else(|| AppError::Unsupported {
+        message: format!("Unsupported language: {}", language),
+    })?;
+    if let Some(reused) = reuse(&mut *state.servers.lock().await, &config, &root) {
+        return Ok(reused);
+    }
+    // Asking the user can take a while, so it happens without the lock
+    let args = registry::expand_args(&config, &root);
+    authorize_custom(&app_handle, "start_lsp_server", &config, &args, Some(&root)).await?;
+
+    // Held while spawning so two calls for the same root can't both start one
+    let mut servers = state.servers.lock().await;
+    if let Some(reused) = reuse(&mut servers, &config, &root) {
+        return Ok(reused);
+    }
 
     let id = Uuid::new_v4().to_string();
-    let server = LspServer::spawn(lang, PathBuf::from(&root_path))
+    let server = LspServer::spawn(app_handle, id.clone(), &config, root, auto_restart.unwrap_or(true))
         .await
-        .map_err(|e| format!("Failed to start LSP: {}", e))?;
+        .map_err(|e| AppError::io("Failed to start LSP", e))?;
 
     let port = server.port;
-    {
-        let mut map = state.servers.lock().await;
-        map.insert(id.clone(), server);
-    }
+    let token = server.shared.token.clone();
+    servers.insert(id.clone(), server);
 
-    eprintln!("[LSP] Started with ID: {}, port: {}", id, port);
-    Ok(StartLspResult { lsp_id: id, port })
+    tracing::info!("Started with ID: {}, port: {}", id, port);
+    Ok(StartLspResult {
+        lsp_id: id,
+        port,
+        token,
+        reused: false,
+    })
 }
 
+/// Releases one reference to the server; it is shut down once the last
+/// one is released
 #[tauri::command]
 pub async fn stop_lsp_server(
     state: tauri::State<'_, LspState>,
     lsp_id: String,
-) -> Result<(), String> {
-    let mut map = state.servers.lock().await;
-    if let Some(server) = map.remove(&lsp_id) {
-        eprintln!("[LSP] Stopped server: {}", lsp_id);
-        drop(server);
-        Ok(())
-    } else {
-        Err(format!("No LSP server with id: {}", lsp_id))
+) -> Result<(), AppError> {
+    let server = {
+        let mut servers = state.servers.lock().await;
+        match servers.get_mut(&lsp_id) {
+            Some(server) if server.refs > 1 => {
+                server.refs -= 1;
+                return Ok(());
+            }
+            _ => servers.remove(&lsp_id),
+        }
+    };
+    match server {
+        Some(server) => {
+            server.shutdown().await;
+            tracing::info!("Stopped server: {}", lsp_id);
+            Ok(())
+        }
+        None => Err(AppError::not_found(format!("No LSP server with id: {}", lsp_id))),
     }
 }
 
+/// State, uptime, memory use and last activity of a running server
+#[tauri::command]
+pub async fn get_lsp_status(state: tauri::State<'_, LspState>, lsp_id: String) -> Result<LspStatus, AppError> {
+    let shared = state
+        .servers
+        .lock()
+        .await
+        .get(&lsp_id)
+        .map(|server| server.shared.clone())
+        .ok_or_else(|| AppError::not_found(format!("No LSP server with id: {}", lsp_id)))?;
+    let pid = shared.health.lock().ok().and_then(|h| h.pid);
+    let memory = match pid {
+        Some(pid) => crate::processes::memory_usage(pid).await,
+        None => None,
+    };
+    Ok(status(&lsp_id, &shared, memory))
+}
+
+#[tauri::command]
+pub async fn get_lsp_settings(
+    app_handle: tauri::AppHandle,
+    language: String,
+) -> Result<settings::LspSettings, AppError> {
+    Ok(settings::load(&app_handle, &language))
+}
+
+/// Stores the settings for `language`. Running servers of that language are
+/// sent `workspace/didChangeConfiguration`; initialization options only
+/// take effect when a server is next started.
+#[tauri::command]
+pub async fn set_lsp_settings(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, LspState>,
+    language: String,
+    settings: settings::LspSettings,
+) -> Result<(), AppError> {
+    settings::save(&app_handle, &language, &settings)?;
+    let running: Vec<Arc<Shared>> = state
+        .servers
+        .lock()
+        .await
+        .values()
+        .filter(|s| s.shared.config.language_id == language)
+        .map(|s| s.shared.clone())
+        .collect();
+    for shared in running {
+        if let Ok(mut current) = shared.settings.lock() {
+            *current = settings.clone();
+        }
+        let params = serde_json::json!({ "settings": settings.settings.clone().unwrap_or(serde_json::Value::Null) });
+        if let Err(e) = send_notification(&shared, "workspace/didChangeConfiguration", params).await {
+            tracing::warn!("Failed to send configuration to {}: {}", language, e);
+        }
+    }
+    Ok(())
+}
+
+/// The last `tail` lines (default: all kept) the server wrote to stderr,
+/// and the file holding the rest
+#[tauri::command]
+pub async fn get_lsp_logs(
+    state: tauri::State<'_, LspState>,
+    lsp_id: String,
+    tail: Option<usize>,
+) -> Result<LspLogs, AppError> {
+    let servers = state.servers.lock().await;
+    let server = servers
+        .get(&lsp_id)
+        .ok_or_else(|| AppError::not_found(format!("No LSP server with id: {}", lsp_id)))?;
+    let shared = &server.shared;
+    let log = shared
+        .stderr_log
+        .lock()
+        .map_err(|e| format!("Failed to lock log: {}", e))?;
+    let skip = tail.map_or(0, |tail| log.len().saturating_sub(tail));
+    Ok(LspLogs {
+        lines: log.iter().skip(skip).cloned().collect(),
+        log_file: shared.log_file.as_ref().map(|p| p.to_string_lossy().to_string()),
+    })
+}
+
+/// Gracefully stops the server and starts a fresh one for the same language
+/// and root. The id is kept; clients must reconnect to the returned port.
+#[tauri::command]
+pub async fn restart_lsp_server(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, LspState>,
+    lsp_id: String,
+) -> Result<StartLspResult, AppError> {
+    let server = state
+        .servers
+        .lock()
+        .await
+        .remove(&lsp_id)
+        .ok_or_else(|| AppError::not_found(format!("No LSP server with id: {}", lsp_id)))?;
+    let config = server.shared.config.clone();
+    let root_path = server.shared.root_path.clone();
+    let auto_restart = server.shared.auto_restart;
+    let refs = server.refs;
+    server.shutdown().await;
+
+    let mut server = LspServer::spawn(app_handle, lsp_id.clone(), &config, root_path, auto_restart)
+        .await
+        .map_err(|e| AppError::io("Failed to restart LSP", e))?;
+    server.refs = refs;
+    let port = server.port;
+    let token = server.shared.token.clone();
+    state.servers.lock().await.insert(lsp_id.clone(), server);
+
+    tracing::info!("Restarted {} on port {}", lsp_id, port);
+    Ok(StartLspResult {
+        lsp_id,
+        port,
+        token,
+        reused: false,
+    })
+}
+
 #[derive(Debug, Serialize)]
 pub struct ProjectInfo {
     pub project_type: String,
@@ -308,52 +1165,82 @@ pub struct ProjectInfo {
 }
 
 #[tauri::command]
-pub async fn detect_project_type(path: String) -> Result<ProjectInfo, String> {
-    let p = PathBuf::from(&path);
+pub async fn detect_project_type(
+    app_handle: tauri::AppHandle,
+    guard: tauri::State<'_, FsGuardState>,
+    path: String,
+) -> Result<ProjectInfo, AppError> {
+    let p = guard.check(&path)?;
     if !p.exists() {
-        return Err("Path does not exist".to_string());
+        return Err(AppError::not_found("Path does not exist"));
     }
     
-    // Walk up to find Cargo.toml or go.mod
-    let mut cur = p.as_path();
-    loop {
-        if let Some(parent) = cur.parent() {
-            let cargo_toml = parent.join("Cargo.toml");
-            if cargo_toml.exists() {
-                return Ok(ProjectInfo {
-                    project_type: "rust".to_string(),
-                    root_path: parent.to_string_lossy().to_string(),
-                });
-            }
-            
-            let go_mod = parent.join("go.mod");
-            if go_mod.exists() {
-                return Ok(ProjectInfo {
-                    project_type: "go".to_string(),
-                    root_path: parent.to_string_lossy().to_string(),
-                });
-            }
-            
-            cur = parent;
-        } else {
-            break;
-        }
+    // Walk up to find the nearest root marker (Cargo.toml, go.mod, package.json, ...)
+    match registry::detect_project(&p, &registry::all_servers(&app_handle)) {
+        Some((config, root)) => Ok(ProjectInfo {
+            project_type: config.language_id,
+            root_path: root.to_string_lossy().to_string(),
+        }),
+        None => Err(AppError::not_found("unknown")),
     }
-    
-    Err("unknown".to_string())
 }
 
 #[tauri::command]
-pub async fn check_lsp_available(language: String) -> Result<bool, String> {
+pub async fn list_lsp_servers(app_handle: tauri::AppHandle) -> Result<Vec<LspServerConfig>, AppError> {
+    Ok(registry::all_servers(&app_handle))
+}
+
+/// Adds or replaces a user-defined language server. A custom server with the
+/// same `language_id` as a built-in one takes precedence over it.
+#[tauri::command]
+pub async fn register_lsp_server(app_handle: tauri::AppHandle, config: LspServerConfig) -> Result<(), AppError> {
+    let config = registry::validate(config).map_err(AppError::invalid_input)?;
+    let policy = app_handle.state::<CommandPolicyState>();
+    command_policy::authorize(&app_handle, &policy, "register_lsp_server", &config.command, &config.args, None, false)
+        .await?;
+    let mut servers = registry::custom_servers(&app_handle);
+    servers.retain(|c| c.language_id != config.language_id);
+    tracing::info!("Registering custom server {} ({})", config.language_id, config.command);
+    servers.push(config);
+    registry::save_custom_servers(&app_handle, &servers).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn unregister_lsp_server(app_handle: tauri::AppHandle, language_id: String) -> Result<(), AppError> {
+    let mut servers = registry::custom_servers(&app_handle);
+    let before = servers.len();
+    servers.retain(|c| c.language_id != language_id);
+    if servers.len() == before {
+        return Err(AppError::not_found(format!("No custom LSP server for language: {}", language_id)));
+    }
+    registry::save_custom_servers(&app_handle, &servers).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn check_lsp_available(app_handle: tauri::AppHandle, language: String) -> Result<bool, AppError> {
     use std::process::Command;
     
-    let (cmd_name, args) = match language.as_str() {
-        "rust" => ("rust-analyzer", vec!["--version"]),
-        "go" => ("gopls", vec!["version"]),
-        _ => return Err(format!("Unknown language: {}", language)),
+    let config = resolve_server(&app_handle, &language)
+        .ok_or_else(|| AppError::not_found(format!("Unknown language: {}", language)))?;
+    let cmd_name = config.command.as_str();
+    
+    // Servers without a version flag are only checked for presence in PATH
+    let args = match &config.version_args {
+        Some(args) => {
+            authorize_custom(&app_handle, "check_lsp_available", &config, args, None).await?;
+            args
+        }
+        None => {
+            let found = registry::find_executable(cmd_name);
+            match &found {
+                Some(p) => tracing::debug!("{} available: {}", cmd_name, p.display()),
+                None => tracing::warn!("{} not found in PATH", cmd_name),
+            }
+            return Ok(found.is_some());
+        }
     };
     
-    match Command::new(cmd_name).args(&args).output() {
+    match Command::new(cmd_name).args(args).envs(crate::shell_env::environment()).output() {
         Ok(output) => {
             let stderr = String::from_utf8_lossy(&output.stderr);
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -365,20 +1252,22 @@ pub async fn check_lsp_available(language: String) -> Result<bool, String> {
             let success = output.status.success() && !has_error;
             
             if !success {
-                eprintln!("[LSP] {} check failed:", cmd_name);
-                eprintln!("  Exit code: {:?}", output.status.code());
-                eprintln!("  Stderr: {}", stderr);
-                eprintln!("  Stdout: {}", stdout);
+                tracing::warn!(
+                    exit_code = ?output.status.code(),
+                    %stderr,
+                    %stdout,
+                    "{} check failed",
+                    cmd_name
+                );
             } else {
-                eprintln!("[LSP] {} available: {}", cmd_name, stdout.trim());
+                tracing::debug!("{} available: {}", cmd_name, stdout.trim());
             }
             
             Ok(success)
         }
         Err(e) => {
-            eprintln!("[LSP] {} not found in PATH: {}", cmd_name, e);
+            tracing::warn!("{} not found in PATH: {}", cmd_name, e);
             Ok(false)
         }
     }
 }
-
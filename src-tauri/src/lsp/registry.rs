@@ -0,0 +1,240 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::file_info::sha256_hex;
+
+// User-registered servers live in their own store file, separate from the UI settings
+const CUSTOM_SERVERS_STORE: &str = "lsp-servers.json";
+const CUSTOM_SERVERS_KEY: &str = "servers";
+
+/// How to launch a language server and which projects it applies to.
+///
+/// `args` may contain the placeholders `${root}` (project root) and
+/// `${dataDir}` (a per-project scratch directory, e.g. for jdtls `-data`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LspServerConfig {
+    pub language_id: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extensions without the leading dot
+    #[serde(default)]
+    pub file_extensions: Vec<String>,
+    /// Files whose presence marks a project root
+    #[serde(default)]
+    pub root_markers: Vec<String>,
+    /// Arguments used to probe availability; `None` only checks PATH
+    #[serde(default)]
+    pub version_args: Option<Vec<String>>,
+}
+
+fn config(
+    language_id: &str,
+    command: &str,
+    args: &[&str],
+    file_extensions: &[&str],
+    root_markers: &[&str],
+    version_args: Option<&[&str]>,
+) -> LspServerConfig {
+    let to_vec = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    LspServerConfig {
+        language_id: language_id.to_string(),
+        command: command.to_string(),
+        args: to_vec(args),
+        file_extensions: to_vec(file_extensions),
+        root_markers: to_vec(root_markers),
+        version_args: version_args.map(to_vec),
+    }
+}
+
+pub fn builtin_servers() -> Vec<LspServerConfig> {
+    vec![
+        config("rust", "rust-analyzer", &[], &["rs"], &["Cargo.toml"], Some(&["--version"])),
+        config("go", "gopls", &["serve"], &["go"], &["go.mod"], Some(&["version"])),
+        config(
+            "typescript",
+            "typescript-language-server",
+            &["--stdio"],
+            &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+            &["tsconfig.json", "jsconfig.json", "package.json"],
+            Some(&["--version"]),
+        ),
+        config(
+            "python",
+            "pyright-langserver",
+            &["--stdio"],
+            &["py", "pyi"],
+            &["pyproject.toml", "pyrightconfig.json", "setup.py", "setup.cfg", "requirements.txt"],
+            None,
+        ),
+        config(
+            "cpp",
+            "clangd",
+            &["--background-index"],
+            &["c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+            &["compile_commands.json", "compile_flags.txt", ".clangd", "CMakeLists.txt"],
+            Some(&["--version"]),
+        ),
+        config(
+            "java",
+            "jdtls",
+            &["-data", "${dataDir}"],
+            &["java"],
+            &["pom.xml", "build.gradle", "build.gradle.kts", "settings.gradle", ".project"],
+            None,
+        ),
+    ]
+}
+
+/// Servers registered by the user via `register_lsp_server`
+pub fn custom_servers(app: &AppHandle) -> Vec<LspServerConfig> {
+    let store = match app.store(CUSTOM_SERVERS_STORE) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Failed to open {}: {}", CUSTOM_SERVERS_STORE, e);
+            return Vec::new();
+        }
+    };
+    store
+        .get(CUSTOM_SERVERS_KEY)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_custom_servers(app: &AppHandle, servers: &[LspServerConfig]) -> Result<(), String> {
+    let store = app
+        .store(CUSTOM_SERVERS_STORE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+    let value = serde_json::to_value(servers).map_err(|e| format!("Failed to serialize servers: {}", e))?;
+    store.set(CUSTOM_SERVERS_KEY, value);
+    store.save().map_err(|e| format!("Failed to save store: {}", e))
+}
+
+/// Custom servers first, then every built-in that isn't overridden by one
+pub fn all_servers(app: &AppHandle) -> Vec<LspServerConfig> {
+    let mut servers = custom_servers(app);
+    for builtin in builtin_servers() {
+        if !servers.iter().any(|c| c.language_id == builtin.language_id) {
+            servers.push(builtin);
+        }
+    }
+    servers
+}
+
+pub fn find_server(app: &AppHandle, language_id: &str) -> Option<LspServerConfig> {
+    all_servers(app).into_iter().find(|c| c.language_id == language_id)
+}
+
+/// Checks required fields and normalizes extensions (`.TS` -> `ts`)
+pub fn validate(mut config: LspServerConfig) -> Result<LspServerConfig, String> {
+    config.language_id = config.language_id.trim().to_string();
+    config.command = config.command.trim().to_string();
+    if config.language_id.is_empty() {
+        return Err("language_id must not be empty".to_string());
+    }
+    if config.command.is_empty() {
+        return Err("command must not be empty".to_string());
+    }
+    if config.language_id.contains(':') {
+        return Err("language_id must not contain ':'".to_string());
+    }
+
+    config.file_extensions = config
+        .file_extensions
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect();
+    config.root_markers.retain(|m| !m.trim().is_empty());
+    Ok(config)
+}
+
+/// Per-project scratch directory, stable across restarts and Rust versions
+fn data_dir(language_id: &str, root: &Path) -> PathBuf {
+    let hash = sha256_hex(root.as_os_str().as_encoded_bytes());
+    std::env::temp_dir()
+        .join("tmd-editor-lsp")
+        .join(format!("{}-{}", language_id, &hash[..16]))
+}
+
+/// Substitutes the `${...}` placeholders in the configured arguments
+pub fn expand_args(config: &LspServerConfig, root: &Path) -> Vec<String> {
+    let root_str = root.to_string_lossy();
+    let data_dir = data_dir(&config.language_id, root);
+    config
+        .args
+        .iter()
+        .map(|arg| {
+            arg.replace("${root}", &root_str)
+                .replace("${dataDir}", &data_dir.to_string_lossy())
+        })
+        .collect()
+}
+
+/// Searches PATH for an executable, honouring PATHEXT on Windows
+pub fn find_executable(command: &str) -> Option<PathBuf> {
+    let candidate = Path::new(command);
+    if candidate.components().count() > 1 {
+        return candidate.is_file().then(|| candidate.to_path_buf());
+    }
+
+    let extensions: Vec<String> = if cfg!(target_os = "windows") {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .map(|e| e.to_string())
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    // The login shell's PATH, which has e.g. ~/.cargo/bin when the app was started from Finder
+    let path = crate::shell_env::path()?;
+    std::env::split_paths(path).find_map(|dir| {
+        extensions.iter().find_map(|ext| {
+            let full = dir.join(format!("{}{}", command, ext));
+            full.is_file().then_some(full)
+        })
+    })
+}
+
+/// Finds the nearest ancestor of `path` containing a root marker of one of `servers`.
+///
+/// Candidates are narrowed first to servers for which the file itself is a
+/// root marker (e.g. `.../go.mod`), then to servers handling its extension,
+/// so a Go file inside a mixed Rust/Go tree still resolves to Go.
+pub fn detect_project(path: &Path, servers: &[LspServerConfig]) -> Option<(LspServerConfig, PathBuf)> {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string());
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+
+    let by_marker: Vec<&LspServerConfig> = servers
+        .iter()
+        .filter(|c| file_name.as_ref().is_some_and(|n| c.root_markers.contains(n)))
+        .collect();
+    let by_extension: Vec<&LspServerConfig> = servers
+        .iter()
+        .filter(|c| extension.as_ref().is_some_and(|e| c.file_extensions.contains(e)))
+        .collect();
+    let candidates: Vec<&LspServerConfig> = if !by_marker.is_empty() {
+        by_marker
+    } else if !by_extension.is_empty() {
+        by_extension
+    } else {
+        servers.iter().collect()
+    };
+
+    let mut cur = path;
+    while let Some(parent) = cur.parent() {
+        for config in &candidates {
+            if config.root_markers.iter().any(|m| parent.join(m).exists()) {
+                return Some(((*config).clone(), parent.to_path_buf()));
+            }
+        }
+        cur = parent;
+    }
+    None
+}
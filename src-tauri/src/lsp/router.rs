@@ -0,0 +1,252 @@
+//! Lets several editor views share one language server. Request IDs from
+//! each client are rewritten to IDs unique on the server and responses go
+//! back only to the client that asked, with its own ID restored;
+//! notifications and the server's own requests go to everyone.
+//!
+//! The handshake is shared too: the first client's `initialize` reaches the
+//! server and later clients get its cached result, while `shutdown` and
+//! `exit` are only passed on when the last client sends them.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedSender;
+
+// JSON-RPC code for a request the server can no longer answer
+const REQUEST_FAILED: i64 = -32803;
+
+struct PendingRequest {
+    client: u64,
+    /// The ID the client used, restored in the response
+    id: Value,
+    method: String,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_client: u64,
+    next_id: u64,
+    clients: HashMap<u64, UnboundedSender<String>>,
+    /// Client requests awaiting a response, by the ID sent to the server
+    pending: HashMap<u64, PendingRequest>,
+    /// Requests from the server that no client has answered yet
+    server_requests: HashSet<String>,
+    initialize_sent: bool,
+    initialize_result: Option<Value>,
+    /// Clients that sent `initialize` while the first one was still pending
+    waiting_for_initialize: Vec<(u64, Value)>,
+    initialized_sent: bool,
+}
+
+#[derive(Default)]
+pub(super) struct Router {
+    inner: Mutex<Inner>,
+}
+
+fn id_key(id: &Value) -> String {
+    id.to_string()
+}
+
+fn response(id: &Value, result: &Value) -> String {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string()
+}
+
+fn cancel_request(server_id: u64) -> String {
+    json!({ "jsonrpc": "2.0", "method": "$/cancelRequest", "params": { "id": server_id } }).to_string()
+}
+
+fn error_response(id: &Value, message: &str) -> String {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": REQUEST_FAILED, "message": message } }).to_string()
+}
+
+impl Inner {
+    fn send(&mut self, client: u64, text: String) {
+        let gone = self.clients.get(&client).is_some_and(|tx| tx.send(text).is_err());
+        if gone {
+            self.clients.remove(&client);
+        }
+    }
+
+    fn broadcast(&mut self, text: &str) {
+        self.clients.retain(|_, tx| tx.send(text.to_string()).is_ok());
+    }
+}
+
+impl Router {
+    fn lock(&self) -> Result<MutexGuard<'_, Inner>, String> {
+        self.inner.lock().map_err(|e| format!("Failed to lock state: {}", e))
+    }
+
+    pub(super) fn add_client(&self, sender: UnboundedSender<String>) -> Result<u64, String> {
+        let mut inner = self.lock()?;
+        inner.next_client += 1;
+        let id = inner.next_client;
+        inner.clients.insert(id, sender);
+        Ok(id)
+    }
+
+    /// Forgets a disconnected client; returns `$/cancelRequest` notifications
+    /// for its requests that are still running on the server
+    pub(super) fn remove_client(&self, client: u64) -> Result<Vec<String>, String> {
+        let mut inner = self.lock()?;
+        inner.clients.remove(&client);
+        inner.waiting_for_initialize.retain(|(c, _)| *c != client);
+        let abandoned: Vec<u64> = inner
+            .pending
+            .iter()
+            .filter(|(_, request)| request.client == client)
+            .map(|(id, _)| *id)
+            .collect();
+        Ok(abandoned
+            .into_iter()
+            .map(|id| {
+                inner.pending.remove(&id);
+                cancel_request(id)
+            })
+            .collect())
+    }
+
+    pub(super) fn client_count(&self) -> Result<usize, String> {
+        Ok(self.lock()?.clients.len())
+    }
+
+    /// Whether the server has answered the clients' `initialize`
+    pub(super) fn is_initialized(&self) -> Result<bool, String> {
+        Ok(self.lock()?.initialize_result.is_some())
+    }
+
+    /// Handles a message from `client`: returns the text to write to the
+    /// server, or None if it was answered here or must not reach the server
+    pub(super) fn route_client(&self, client: u64, text: &str) -> Result<Option<String>, String> {
+        let Ok(mut message) = serde_json::from_str::<Value>(text) else {
+            return Ok(Some(text.to_string()));
+        };
+        let Some(object) = message.as_object_mut() else {
+            // Batches are passed through untouched
+            return Ok(Some(text.to_string()));
+        };
+        let mut inner = self.lock()?;
+        let method = object.get("method").and_then(Value::as_str).map(str::to_string);
+        let id = object.get("id").cloned();
+
+        let routed = match (method, id) {
+            // A request from the client
+            (Some(method), Some(id)) => {
+                match method.as_str() {
+                    "initialize" if inner.initialize_sent => {
+                        match inner.initialize_result.clone() {
+                            Some(result) => inner.send(client, response(&id, &result)),
+                            None => inner.waiting_for_initialize.push((client, id)),
+                        }
+                        return Ok(None);
+                    }
+                    "initialize" => inner.initialize_sent = true,
+                    // Other views still need the server
+                    "shutdown" if inner.clients.len() > 1 => {
+                        inner.send(client, response(&id, &Value::Null));
+                        return Ok(None);
+                    }
+                    _ => {}
+                }
+                inner.next_id += 1;
+                let server_id = inner.next_id;
+                inner.pending.insert(server_id, PendingRequest { client, id, method });
+                object.insert("id".to_string(), json!(server_id));
+                Some(message.to_string())
+            }
+            // A notification from the client
+            (Some(method), None) => match method.as_str() {
+                "initialized" if inner.initialized_sent => None,
+                "initialized" => {
+                    inner.initialized_sent = true;
+                    Some(text.to_string())
+                }
+                "exit" if inner.clients.len() > 1 => None,
+                "$/cancelRequest" => object
+                    .get("params")
+                    .and_then(|p| p.get("id"))
+                    .and_then(|wanted| {
+                        inner
+                            .pending
+                            .iter()
+                            .find(|(_, request)| request.client == client && request.id == *wanted)
+                    })
+                    .map(|(id, _)| cancel_request(*id)),
+                _ => Some(text.to_string()),
+            },
+            // A response to a request from the server; the first answer wins
+            (None, Some(id)) => inner.server_requests.remove(&id_key(&id)).then(|| text.to_string()),
+            (None, None) => Some(text.to_string()),
+        };
+        Ok(routed)
+    }
+
+    /// Delivers a message from the server: responses to the client that sent
+    /// the request, everything else to all clients
+    pub(super) fn route_server(&self, text: &str) -> Result<(), String> {
+        let mut inner = self.lock()?;
+        let Ok(mut message) = serde_json::from_str::<Value>(text) else {
+            inner.broadcast(text);
+            return Ok(());
+        };
+        let method = message.get("method").is_some();
+        let id = message.get("id").cloned();
+
+        match (method, id) {
+            (false, Some(server_id)) => {
+                let Some(request) = server_id.as_u64().and_then(|id| inner.pending.remove(&id)) else {
+                    // The client went away or the request was never routed
+                    return Ok(());
+                };
+                if request.method == "initialize" {
+                    let result = message.get("result").cloned();
+                    let waiting = std::mem::take(&mut inner.waiting_for_initialize);
+                    if let Some(result) = &result {
+                        for (client, id) in waiting {
+                            inner.send(client, response(&id, result));
+                        }
+                    } else {
+                        // The clients that waited fail too; the next one to
+                        // send `initialize` tries again
+                        inner.initialize_sent = false;
+                        let reason = message
+                            .pointer("/error/message")
+                            .and_then(Value::as_str)
+                            .unwrap_or("Language server failed to initialize");
+                        for (client, id) in waiting {
+                            inner.send(client, error_response(&id, reason));
+                        }
+                    }
+                    inner.initialize_result = result;
+                }
+                message["id"] = request.id;
+                inner.send(request.client, message.to_string());
+            }
+            (true, Some(id)) => {
+                inner.server_requests.insert(id_key(&id));
+                inner.broadcast(text);
+            }
+            _ => inner.broadcast(text),
+        }
+        Ok(())
+    }
+
+    /// The server process went away: requests it never answered fail, and
+    /// its requests to the clients are void
+    pub(super) fn server_exited(&self) -> Result<(), String> {
+        let mut inner = self.lock()?;
+        let pending: Vec<PendingRequest> = inner.pending.drain().map(|(_, request)| request).collect();
+        for request in pending {
+            inner.send(request.client, error_response(&request.id, "Language server exited"));
+        }
+        inner.server_requests.clear();
+        if inner.initialize_result.is_none() {
+            inner.initialize_sent = false;
+            for (client, id) in std::mem::take(&mut inner.waiting_for_initialize) {
+                inner.send(client, error_response(&id, "Language server exited"));
+            }
+        }
+        Ok(())
+    }
+}
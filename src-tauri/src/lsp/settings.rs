@@ -0,0 +1,85 @@
+//! User settings for language servers, per language: `initializationOptions`
+//! merged into the client's `initialize`, and the workspace configuration
+//! the bridge answers `workspace/configuration` with (e.g. rust-analyzer's
+//! cargo features or gopls build flags).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+// Kept apart from the custom server list in lsp-servers.json
+const SETTINGS_STORE: &str = "lsp-settings.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LspSettings {
+    /// Merged over the `initializationOptions` the client sends; applies
+    /// from the next start of the server
+    #[serde(default)]
+    pub initialization_options: Option<Value>,
+    /// Workspace configuration by section, e.g. `{"rust-analyzer": {...}}`.
+    /// When unset, `workspace/configuration` is left to the clients.
+    #[serde(default)]
+    pub settings: Option<Value>,
+}
+
+pub fn load(app: &AppHandle, language_id: &str) -> LspSettings {
+    let store = match app.store(SETTINGS_STORE) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Failed to open {}: {}", SETTINGS_STORE, e);
+            return LspSettings::default();
+        }
+    };
+    store
+        .get(language_id)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(app: &AppHandle, language_id: &str, settings: &LspSettings) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+    if *settings == LspSettings::default() {
+        store.delete(language_id);
+    } else {
+        let value = serde_json::to_value(settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        store.set(language_id, value);
+    }
+    store.save().map_err(|e| format!("Failed to save store: {}", e))
+}
+
+/// Merges `overlay` into `base`: objects key by key, anything else replaced
+pub fn merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// The value for a `workspace/configuration` item: the whole settings for no
+/// section, otherwise the section as a flat key (`"rust-analyzer.cargo"`) or
+/// as a dotted path into nested objects; null when not set
+pub fn section(settings: &Value, section: Option<&str>) -> Value {
+    let Some(section) = section.filter(|s| !s.is_empty()) else {
+        return settings.clone();
+    };
+    if let Some(value) = settings.get(section) {
+        return value.clone();
+    }
+    section
+        .split('.')
+        .try_fold(settings, |value, key| value.get(key))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
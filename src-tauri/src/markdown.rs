@@ -0,0 +1,1413 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+
+use crate::diagrams::{self, DiagramState, DiagramTheme};
+use crate::highlight;
+use crate::math;
+use crate::settings;
+
+/// Which extensions to CommonMark are enabled; everything defaults to on
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RenderOptions {
+    pub tables: bool,
+    pub task_lists: bool,
+    pub footnotes: bool,
+    pub strikethrough: bool,
+    /// Turn bare `https://` and `www.` URLs into links
+    pub autolinks: bool,
+    pub highlight_code: bool,
+    /// Give headings GitHub-style slug ids so `#fragment` links work
+    pub heading_ids: bool,
+    /// Replace mermaid and PlantUML fences with rendered SVG (see diagrams.rs)
+    pub diagrams: bool,
+    /// Render `$...$` and `$$...$$` TeX math as MathML; off by default since
+    /// the preview typesets math client-side, on for export
+    pub math: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            task_lists: true,
+            footnotes: true,
+            strikethrough: true,
+            autolinks: true,
+            highlight_code: true,
+            heading_ids: true,
+            diagrams: true,
+            math: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug)]
+struct ListItem {
+    task: Option<bool>,
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug)]
+enum Block {
+    Heading(u8, String),
+    Paragraph(String),
+    Code { lang: String, text: String },
+    /// A `$$` display math block
+    Math(String),
+    Quote(Vec<Block>),
+    List {
+        ordered: bool,
+        start: u64,
+        loose: bool,
+        items: Vec<ListItem>,
+    },
+    Table {
+        aligns: Vec<Align>,
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    Rule,
+}
+
+/// Link reference definitions and footnotes collected while parsing blocks
+#[derive(Default)]
+struct Definitions {
+    links: HashMap<String, (String, Option<String>)>,
+    footnotes: HashMap<String, Vec<Block>>,
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// GitHub-style anchor for a heading: lowercase, punctuation dropped, spaces to dashes
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn normalize_label(label: &str) -> String {
+    label.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+// ---------------------------------------------------------------------------
+// Block structure
+// ---------------------------------------------------------------------------
+
+pub(crate) fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// Drops up to `n` leading spaces
+fn strip_indent(line: &str, n: usize) -> &str {
+    &line[indent_of(line).min(n)..]
+}
+
+pub(crate) struct Fence {
+    ch: char,
+    len: usize,
+    indent: usize,
+    info: String,
+}
+
+pub(crate) fn fence_start(line: &str) -> Option<Fence> {
+    let indent = indent_of(line);
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let ch = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = rest.chars().take_while(|c| *c == ch).count();
+    if len < 3 {
+        return None;
+    }
+    let info = rest[len..].trim();
+    if ch == '`' && info.contains('`') {
+        return None;
+    }
+    Some(Fence {
+        ch,
+        len,
+        indent,
+        info: info.to_string(),
+    })
+}
+
+pub(crate) fn is_fence_end(line: &str, fence: &Fence) -> bool {
+    let indent = indent_of(line);
+    let rest = line[indent..].trim_end();
+    indent <= 3 && rest.len() >= fence.len && rest.chars().all(|c| c == fence.ch)
+}
+
+pub(crate) fn atx_heading(line: &str) -> Option<(u8, String)> {
+    if indent_of(line) > 3 {
+        return None;
+    }
+    let rest = line.trim_start();
+    let level = rest.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let text = &rest[level..];
+    if !text.is_empty() && !text.starts_with(' ') && !text.starts_with('\t') {
+        return None;
+    }
+    // Optional closing sequence of #s
+    let text = text.trim();
+    let trimmed = text.trim_end_matches('#');
+    let text = if trimmed.is_empty() || trimmed.ends_with(' ') {
+        trimmed.trim_end()
+    } else {
+        text
+    };
+    Some((level as u8, text.to_string()))
+}
+
+fn is_rule(line: &str) -> bool {
+    if indent_of(line) > 3 {
+        return false;
+    }
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && matches!(chars[0], '-' | '*' | '_') && chars.iter().all(|c| *c == chars[0])
+}
+
+pub(crate) fn setext_level(line: &str) -> Option<u8> {
+    if indent_of(line) > 3 {
+        return None;
+    }
+    let t = line.trim();
+    if !t.is_empty() && t.chars().all(|c| c == '=') {
+        Some(1)
+    } else if !t.is_empty() && t.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+fn is_quote(line: &str) -> bool {
+    indent_of(line) <= 3 && line.trim_start().starts_with('>')
+}
+
+fn strip_quote(line: &str) -> &str {
+    let rest = line.trim_start();
+    let rest = &rest[1..];
+    rest.strip_prefix(' ').unwrap_or(rest)
+}
+
+#[derive(Debug, Clone)]
+struct ListMarker {
+    ordered: bool,
+    /// Bullet character, or the '.'/')' after an ordered number
+    delimiter: char,
+    start: u64,
+    /// Column where the item's content starts
+    content_indent: usize,
+    rest: String,
+}
+
+fn list_marker(line: &str) -> Option<ListMarker> {
+    let indent = indent_of(line);
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let (ordered, delimiter, start, marker_len) = match rest.chars().next()? {
+        c @ ('-' | '*' | '+') => (false, c, 0, 1),
+        c if c.is_ascii_digit() => {
+            let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            if digits > 9 {
+                return None;
+            }
+            let delimiter = rest[digits..].chars().next().filter(|c| *c == '.' || *c == ')')?;
+            (true, delimiter, rest[..digits].parse().ok()?, digits + 1)
+        }
+        _ => return None,
+    };
+
+    let after = &rest[marker_len..];
+    if after.is_empty() {
+        return Some(ListMarker {
+            ordered,
+            delimiter,
+            start,
+            content_indent: indent + marker_len + 1,
+            rest: String::new(),
+        });
+    }
+    if !after.starts_with(' ') {
+        return None;
+    }
+    let spaces = indent_of(after);
+    // More than four spaces means indented code inside the item
+    let padding = if spaces > 4 || after.trim().is_empty() { 1 } else { spaces };
+    Some(ListMarker {
+        ordered,
+        delimiter,
+        start,
+        content_indent: indent + marker_len + padding,
+        rest: after[padding.min(after.len())..].to_string(),
+    })
+}
+
+fn split_table_row(line: &str) -> Vec<String> {
+    let t = line.trim();
+    let t = t.strip_prefix('|').unwrap_or(t);
+    let t = t.strip_suffix('|').filter(|s| !s.ends_with('\\')).unwrap_or(t);
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = t.chars().peekable();
+    let mut in_code = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '`' => {
+                in_code = !in_code;
+                cell.push(c);
+            }
+            '|' if !in_code => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn table_delimiter(line: &str) -> Option<Vec<Align>> {
+    if !line.contains('-') || indent_of(line) > 3 {
+        return None;
+    }
+    let cells = split_table_row(line);
+    if cells.is_empty() {
+        return None;
+    }
+    cells
+        .iter()
+        .map(|cell| {
+            let left = cell.starts_with(':');
+            let right = cell.ends_with(':');
+            let dashes = cell.trim_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (left, right) {
+                (true, true) => Align::Center,
+                (true, false) => Align::Left,
+                (false, true) => Align::Right,
+                (false, false) => Align::None,
+            })
+        })
+        .collect()
+}
+
+/// `[label]: destination "title"`, the whole definition on one line
+fn link_definition(line: &str) -> Option<(String, String, Option<String>)> {
+    if indent_of(line) > 3 {
+        return None;
+    }
+    let rest = line.trim().strip_prefix('[')?;
+    let close = rest.find("]:")?;
+    let label = &rest[..close];
+    if label.trim().is_empty() || label.starts_with('^') {
+        return None;
+    }
+    let rest = rest[close + 2..].trim();
+    let (dest, rest) = if let Some(r) = rest.strip_prefix('<') {
+        let end = r.find('>')?;
+        (&r[..end], r[end + 1..].trim())
+    } else {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        (&rest[..end], rest[end..].trim())
+    };
+    if dest.is_empty() {
+        return None;
+    }
+    let title = if rest.is_empty() {
+        None
+    } else {
+        let quote = rest.chars().next()?;
+        let closing = match quote {
+            '"' => '"',
+            '\'' => '\'',
+            '(' => ')',
+            _ => return None,
+        };
+        Some(rest.strip_prefix(quote)?.strip_suffix(closing)?.to_string())
+    };
+    Some((normalize_label(label), dest.to_string(), title))
+}
+
+fn footnote_definition(line: &str) -> Option<(String, String)> {
+    let rest = line.trim_start().strip_prefix("[^")?;
+    let close = rest.find("]:")?;
+    let label = &rest[..close];
+    if label.is_empty() || label.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((normalize_label(label), rest[close + 2..].trim().to_string()))
+}
+
+/// Whether `line` starts a block that ends a paragraph without a blank line
+pub(crate) fn interrupts_paragraph(line: &str) -> bool {
+    if fence_start(line).is_some() || atx_heading(line).is_some() || is_rule(line) || is_quote(line) {
+        return true;
+    }
+    // Only non-empty bullets and lists starting at 1 may interrupt a paragraph
+    list_marker(line).is_some_and(|m| !m.rest.trim().is_empty() && (!m.ordered || m.start == 1))
+}
+
+fn parse_blocks(lines: &[String], defs: &mut Definitions, options: &RenderOptions) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = &lines[i];
+
+        if is_blank(line) {
+            i += 1;
+            continue;
+        }
+
+        if let Some(fence) = fence_start(line) {
+            let mut text = String::new();
+            i += 1;
+            while i < lines.len() && !is_fence_end(&lines[i], &fence) {
+                text.push_str(strip_indent(&lines[i], fence.indent));
+                text.push('\n');
+                i += 1;
+            }
+            i += 1;
+            let lang = fence.info.split_whitespace().next().unwrap_or("").to_string();
+            blocks.push(Block::Code { lang, text });
+            continue;
+        }
+
+        if options.math && indent_of(line) < 4 && line.trim_start().starts_with("$$") {
+            let first = &line.trim()[2..];
+            // Single-line $$...$$
+            if let Some(tex) = first.strip_suffix("$$") {
+                blocks.push(Block::Math(tex.trim().to_string()));
+                i += 1;
+                continue;
+            }
+            let mut tex = vec![first];
+            i += 1;
+            while i < lines.len() {
+                let current = lines[i].trim();
+                i += 1;
+                if let Some(last) = current.strip_suffix("$$") {
+                    tex.push(last);
+                    break;
+                }
+                tex.push(current);
+            }
+            blocks.push(Block::Math(tex.join("\n").trim().to_string()));
+            continue;
+        }
+
+        if indent_of(line) >= 4 {
+            let mut code: Vec<&str> = Vec::new();
+            while i < lines.len() && (indent_of(&lines[i]) >= 4 || is_blank(&lines[i])) {
+                code.push(strip_indent(&lines[i], 4));
+                i += 1;
+            }
+            while code.last().is_some_and(|l| is_blank(l)) {
+                code.pop();
+            }
+            let mut text = code.join("\n");
+            text.push('\n');
+            blocks.push(Block::Code { lang: String::new(), text });
+            continue;
+        }
+
+        if let Some((level, text)) = atx_heading(line) {
+            blocks.push(Block::Heading(level, text));
+            i += 1;
+            continue;
+        }
+
+        if is_rule(line) {
+            blocks.push(Block::Rule);
+            i += 1;
+            continue;
+        }
+
+        if is_quote(line) {
+            let mut inner = Vec::new();
+            while i < lines.len() && !is_blank(&lines[i]) {
+                if is_quote(&lines[i]) {
+                    inner.push(strip_quote(&lines[i]).to_string());
+                } else if inner.last().is_some_and(|l: &String| !is_blank(l)) && !interrupts_paragraph(&lines[i]) {
+                    // Lazy continuation of a quoted paragraph
+                    inner.push(lines[i].clone());
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+            blocks.push(Block::Quote(parse_blocks(&inner, defs, options)));
+            continue;
+        }
+
+        if options.footnotes {
+            if let Some((label, first)) = footnote_definition(line) {
+                let mut body = vec![first];
+                i += 1;
+                while i < lines.len() && (is_blank(&lines[i]) || indent_of(&lines[i]) >= 4) {
+                    body.push(strip_indent(&lines[i], 4).to_string());
+                    i += 1;
+                }
+                let parsed = parse_blocks(&body, defs, options);
+                defs.footnotes.entry(label).or_insert(parsed);
+                continue;
+            }
+        }
+
+        if let Some(marker) = list_marker(line) {
+            let (block, next) = parse_list(lines, i, marker, defs, options);
+            blocks.push(block);
+            i = next;
+            continue;
+        }
+
+        if let Some((label, dest, title)) = link_definition(line) {
+            defs.links.entry(label).or_insert((dest, title));
+            i += 1;
+            continue;
+        }
+
+        if options.tables && line.contains('|') {
+            if let Some(aligns) = lines.get(i + 1).and_then(|l| table_delimiter(l)) {
+                let header = split_table_row(line);
+                if header.len() == aligns.len() {
+                    i += 2;
+                    let mut rows = Vec::new();
+                    while i < lines.len() && !is_blank(&lines[i]) && !interrupts_paragraph(&lines[i]) {
+                        let mut row = split_table_row(&lines[i]);
+                        row.resize(aligns.len(), String::new());
+                        rows.push(row);
+                        i += 1;
+                    }
+                    blocks.push(Block::Table { aligns, header, rows });
+                    continue;
+                }
+            }
+        }
+
+        // Paragraph, possibly turned into a setext heading by its underline
+        let mut text = vec![line.trim_start()];
+        i += 1;
+        let mut heading = None;
+        while i < lines.len() && !is_blank(&lines[i]) {
+            if let Some(level) = setext_level(&lines[i]) {
+                heading = Some(level);
+                i += 1;
+                break;
+            }
+            if interrupts_paragraph(&lines[i]) {
+                break;
+            }
+            text.push(lines[i].trim_start());
+            i += 1;
+        }
+        let text = text.join("\n");
+        match heading {
+            Some(level) => blocks.push(Block::Heading(level, text.trim().to_string())),
+            None => blocks.push(Block::Paragraph(text.trim_end().to_string())),
+        }
+    }
+
+    blocks
+}
+
+/// Parses consecutive items of the same list type starting at `lines[start]`
+fn parse_list(
+    lines: &[String],
+    start: usize,
+    first: ListMarker,
+    defs: &mut Definitions,
+    options: &RenderOptions,
+) -> (Block, usize) {
+    let mut items = Vec::new();
+    let mut loose = false;
+    let mut i = start;
+    let mut marker = first.clone();
+
+    loop {
+        let mut item_lines = vec![marker.rest.clone()];
+        i += 1;
+        while i < lines.len() {
+            let line = &lines[i];
+            if is_blank(line) {
+                item_lines.push(String::new());
+            } else if indent_of(line) >= marker.content_indent {
+                item_lines.push(line[marker.content_indent..].to_string());
+            } else if item_lines.last().is_some_and(|l| !is_blank(l))
+                && list_marker(line).is_none()
+                && !interrupts_paragraph(line)
+            {
+                // Lazy continuation of the item's last paragraph
+                item_lines.push(line.trim_start().to_string());
+            } else {
+                break;
+            }
+            i += 1;
+        }
+
+        let mut trailing_blank = false;
+        while item_lines.last().is_some_and(|l| is_blank(l)) {
+            item_lines.pop();
+            trailing_blank = true;
+        }
+        // A blank line between two blocks of the same item makes the list loose
+        if item_lines.iter().skip(1).any(|l| is_blank(l)) {
+            loose = true;
+        }
+
+        let mut task = None;
+        if options.task_lists {
+            let first_line = &item_lines[0];
+            for (prefix, checked) in [("[ ] ", false), ("[x] ", true), ("[X] ", true)] {
+                if let Some(rest) = first_line.strip_prefix(prefix) {
+                    task = Some(checked);
+                    item_lines[0] = rest.to_string();
+                    break;
+                }
+            }
+        }
+
+        items.push(ListItem {
+            task,
+            blocks: parse_blocks(&item_lines, defs, options),
+        });
+
+        match lines.get(i).and_then(|l| list_marker(l)) {
+            Some(next)
+                if next.ordered == first.ordered
+                    && next.delimiter == first.delimiter
+                    && !is_rule(&lines[i]) =>
+            {
+                loose |= trailing_blank;
+                marker = next;
+            }
+            _ => break,
+        }
+    }
+
+    (
+        Block::List {
+            ordered: first.ordered,
+            start: first.start,
+            loose,
+            items,
+        },
+        i,
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Inline content
+// ---------------------------------------------------------------------------
+
+enum Piece {
+    Html(String),
+    Delimiter {
+        ch: char,
+        count: usize,
+        original: usize,
+        can_open: bool,
+        can_close: bool,
+        /// Closing tags emitted before the remaining delimiter characters
+        before: String,
+        /// Opening tags emitted after the remaining delimiter characters
+        after: String,
+    },
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || (!c.is_alphanumeric() && !c.is_whitespace() && !c.is_ascii())
+}
+
+/// Keeps only URLs that can't run script when clicked
+fn safe_url(url: &str) -> String {
+    let lower = url.trim().to_ascii_lowercase();
+    let blocked = ["javascript:", "vbscript:", "file:"];
+    if blocked.iter().any(|s| lower.starts_with(s)) || (lower.starts_with("data:") && !lower.starts_with("data:image/")) {
+        return "#".to_string();
+    }
+    escape_html(url.trim())
+}
+
+/// Keeps valid entity references and escapes everything else
+fn push_text_char(out: &mut String, chars: &[char], i: usize) -> usize {
+    let c = chars[i];
+    if c == '&' {
+        let rest: String = chars[i..chars.len().min(i + 34)].iter().collect();
+        if let Some(end) = rest.find(';') {
+            let body = &rest[1..end];
+            let valid = if let Some(num) = body.strip_prefix('#') {
+                let (digits, radix) = match num.strip_prefix(['x', 'X']) {
+                    Some(hex) => (hex, 16),
+                    None => (num, 10),
+                };
+                !digits.is_empty() && u32::from_str_radix(digits, radix).is_ok()
+            } else {
+                !body.is_empty() && body.chars().all(|c| c.is_ascii_alphanumeric())
+            };
+            if valid {
+                out.push_str(&rest[..=end]);
+                return end + 1;
+            }
+        }
+    }
+    out.push_str(&escape_html(&c.to_string()));
+    1
+}
+
+struct Renderer<'a> {
+    options: &'a RenderOptions,
+    defs: &'a Definitions,
+    /// Footnote labels in the order they were first referenced
+    footnote_order: Vec<String>,
+    heading_ids: HashMap<String, usize>,
+}
+
+impl Renderer<'_> {
+    fn footnote_number(&mut self, label: &str) -> usize {
+        match self.footnote_order.iter().position(|l| l == label) {
+            Some(i) => i + 1,
+            None => {
+                self.footnote_order.push(label.to_string());
+                self.footnote_order.len()
+            }
+        }
+    }
+
+    fn heading_id(&mut self, text: &str) -> String {
+        let slug = slugify(text);
+        let count = self.heading_ids.entry(slug.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            slug
+        } else {
+            format!("{}-{}", slug, *count - 1)
+        }
+    }
+
+    fn inline(&mut self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut pieces: Vec<Piece> = Vec::new();
+        let mut buf = String::new();
+        let mut i = 0;
+
+        macro_rules! flush {
+            () => {
+                if !buf.is_empty() {
+                    pieces.push(Piece::Html(std::mem::take(&mut buf)));
+                }
+            };
+        }
+
+        while i < chars.len() {
+            let c = chars[i];
+            match c {
+                '\\' if i + 1 < chars.len() && chars[i + 1] == '\n' => {
+                    buf.push_str("<br />\n");
+                    i += 2;
+                }
+                '\\' if i + 1 < chars.len() && chars[i + 1].is_ascii_punctuation() => {
+                    buf.push_str(&escape_html(&chars[i + 1].to_string()));
+                    i += 2;
+                }
+                ' ' => {
+                    let spaces = chars[i..].iter().take_while(|c| **c == ' ').count();
+                    if chars.get(i + spaces) == Some(&'\n') {
+                        buf.push_str(if spaces >= 2 { "<br />\n" } else { "\n" });
+                        i += spaces + 1;
+                    } else {
+                        buf.push_str(&" ".repeat(spaces));
+                        i += spaces;
+                    }
+                }
+                '`' => {
+                    let run = chars[i..].iter().take_while(|c| **c == '`').count();
+                    match find_code_span_end(&chars, i + run, run) {
+                        Some(end) => {
+                            let code: String = chars[i + run..end].iter().collect();
+                            let code = code.replace('\n', " ");
+                            let code = if code.len() > 2 && code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty() {
+                                &code[1..code.len() - 1]
+                            } else {
+                                &code[..]
+                            };
+                            buf.push_str(&format!("<code>{}</code>", escape_html(code)));
+                            i = end + run;
+                        }
+                        None => {
+                            buf.push_str(&"`".repeat(run));
+                            i += run;
+                        }
+                    }
+                }
+                '$' if self.options.math => match find_math_end(&chars, i) {
+                    Some((end, delimiter)) => {
+                        let tex: String = chars[i + delimiter..end].iter().collect();
+                        buf.push_str(&math::to_mathml(tex.trim(), delimiter == 2));
+                        i = end + delimiter;
+                    }
+                    None => {
+                        buf.push('$');
+                        i += 1;
+                    }
+                },
+                '<' => match autolink(&chars, i) {
+                    Some((html, len)) => {
+                        buf.push_str(&html);
+                        i += len;
+                    }
+                    None => {
+                        buf.push_str("&lt;");
+                        i += 1;
+                    }
+                },
+                '!' if chars.get(i + 1) == Some(&'[') => match self.link(&chars, i + 1, true) {
+                    Some((html, len)) => {
+                        buf.push_str(&html);
+                        i += 1 + len;
+                    }
+                    None => {
+                        buf.push('!');
+                        i += 1;
+                    }
+                },
+                '[' => match self.link(&chars, i, false) {
+                    Some((html, len)) => {
+                        buf.push_str(&html);
+                        i += len;
+                    }
+                    None => {
+                        buf.push('[');
+                        i += 1;
+                    }
+                },
+                '*' | '_' | '~' if c != '~' || self.options.strikethrough => {
+                    let run = chars[i..].iter().take_while(|x| **x == c).count();
+                    let prev = if i == 0 { ' ' } else { chars[i - 1] };
+                    let next = chars.get(i + run).copied().unwrap_or(' ');
+                    let left = !next.is_whitespace()
+                        && (!is_punctuation(next) || prev.is_whitespace() || is_punctuation(prev));
+                    let right = !prev.is_whitespace()
+                        && (!is_punctuation(prev) || next.is_whitespace() || is_punctuation(next));
+                    let (can_open, can_close) = if c == '_' {
+                        (left && (!right || is_punctuation(prev)), right && (!left || is_punctuation(next)))
+                    } else {
+                        (left, right)
+                    };
+                    flush!();
+                    pieces.push(Piece::Delimiter {
+                        ch: c,
+                        count: run,
+                        original: run,
+                        can_open,
+                        can_close,
+                        before: String::new(),
+                        after: String::new(),
+                    });
+                    i += run;
+                }
+                'h' | 'w' if self.options.autolinks && (i == 0 || !chars[i - 1].is_alphanumeric()) => {
+                    match bare_url(&chars, i) {
+                        Some(len) => {
+                            let url: String = chars[i..i + len].iter().collect();
+                            let href = if url.starts_with("www.") { format!("http://{}", url) } else { url.clone() };
+                            buf.push_str(&format!("<a href=\"{}\">{}</a>", safe_url(&href), escape_html(&url)));
+                            i += len;
+                        }
+                        None => {
+                            buf.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                _ => i += push_text_char(&mut buf, &chars, i),
+            }
+        }
+        flush!();
+
+        process_emphasis(&mut pieces);
+
+        let mut out = String::new();
+        for piece in pieces {
+            match piece {
+                Piece::Html(html) => out.push_str(&html),
+                Piece::Delimiter { ch, count, before, after, .. } => {
+                    out.push_str(&before);
+                    out.push_str(&ch.to_string().repeat(count));
+                    out.push_str(&after);
+                }
+            }
+        }
+        out
+    }
+
+    /// Parses `[text](dest "title")`, `[text][ref]`, `[ref]` or `[^note]` at `chars[start]`.
+    /// Returns the HTML and the number of characters consumed.
+    fn link(&mut self, chars: &[char], start: usize, image: bool) -> Option<(String, usize)> {
+        let close = find_bracket_end(chars, start)?;
+        let inner: String = chars[start + 1..close].iter().collect();
+
+        if !image && self.options.footnotes {
+            if let Some(label) = inner.strip_prefix('^') {
+                let label = normalize_label(label);
+                if self.defs.footnotes.contains_key(&label) {
+                    let n = self.footnote_number(&label);
+                    let id = escape_html(&slugify(&label));
+                    return Some((
+                        format!(
+                            "<sup class=\"footnote-ref\"><a href=\"#fn-{id}\" id=\"fnref-{id}\">{n}</a></sup>"
+                        ),
+                        close - start + 1,
+                    ));
+                }
+            }
+        }
+
+        let mut end = close + 1;
+        let target = if chars.get(end) == Some(&'(') {
+            let (dest, title, len) = inline_destination(chars, end)?;
+            end += len;
+            Some((dest, title))
+        } else if chars.get(end) == Some(&'[') {
+            let ref_close = find_bracket_end(chars, end)?;
+            let label: String = chars[end + 1..ref_close].iter().collect();
+            let label = if label.trim().is_empty() { inner.clone() } else { label };
+            end = ref_close + 1;
+            self.defs.links.get(&normalize_label(&label)).cloned()
+        } else {
+            self.defs.links.get(&normalize_label(&inner)).cloned()
+        };
+        let (dest, title) = target?;
+
+        let title_attr = title
+            .map(|t| format!(" title=\"{}\"", escape_html(&t)))
+            .unwrap_or_default();
+        let html = if image {
+            format!(
+                "<img src=\"{}\" alt=\"{}\"{} />",
+                safe_url(&dest),
+                escape_html(&plain_text(&inner)),
+                title_attr
+            )
+        } else {
+            format!("<a href=\"{}\"{}>{}</a>", safe_url(&dest), title_attr, self.inline(&inner))
+        };
+        Some((html, end - start))
+    }
+}
+
+/// End of the math span opened at `start`, and whether it is `$` or `$$`.
+/// Like pandoc, `$` must hug its content and a closing `$` can't be followed
+/// by a digit, so prices such as "$5 and $10" stay text.
+fn find_math_end(chars: &[char], start: usize) -> Option<(usize, usize)> {
+    if chars.get(start + 1) == Some(&'$') {
+        let from = start + 2;
+        let end = (from..chars.len().saturating_sub(1)).find(|&j| chars[j] == '$' && chars[j + 1] == '$')?;
+        return (end > from).then_some((end, 2));
+    }
+    let first = *chars.get(start + 1)?;
+    if first.is_whitespace() {
+        return None;
+    }
+    let mut j = start + 1;
+    while j < chars.len() {
+        match chars[j] {
+            '\\' => j += 2,
+            '$' if j > start + 1 && !chars[j - 1].is_whitespace() => {
+                return (!chars.get(j + 1).is_some_and(|c| c.is_ascii_digit())).then_some((j, 1));
+            }
+            _ => j += 1,
+        }
+    }
+    None
+}
+
+fn find_code_span_end(chars: &[char], from: usize, run: usize) -> Option<usize> {
+    let mut i = from;
+    while i < chars.len() {
+        if chars[i] == '`' {
+            let len = chars[i..].iter().take_while(|c| **c == '`').count();
+            if len == run {
+                return Some(i);
+            }
+            i += len;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Index of the `]` matching the `[` at `start`, skipping escapes and code spans
+fn find_bracket_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '`' => {
+                let run = chars[i..].iter().take_while(|c| **c == '`').count();
+                if let Some(end) = find_code_span_end(chars, i + run, run) {
+                    i = end + run - 1;
+                }
+            }
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Parses `(dest "title")` at `chars[start]`; returns dest, title and length
+fn inline_destination(chars: &[char], start: usize) -> Option<(String, Option<String>, usize)> {
+    let mut i = start + 1;
+    while i < chars.len() && chars[i].is_whitespace() {
+        i += 1;
+    }
+    let mut dest = String::new();
+    if chars.get(i) == Some(&'<') {
+        i += 1;
+        while i < chars.len() && chars[i] != '>' {
+            dest.push(chars[i]);
+            i += 1;
+        }
+        i += 1;
+    } else {
+        let mut depth = 0;
+        while i < chars.len() && !chars[i].is_whitespace() {
+            match chars[i] {
+                '(' => depth += 1,
+                ')' if depth == 0 => break,
+                ')' => depth -= 1,
+                '\\' if i + 1 < chars.len() => {
+                    i += 1;
+                }
+                _ => {}
+            }
+            dest.push(chars[i]);
+            i += 1;
+        }
+    }
+    while i < chars.len() && chars[i].is_whitespace() {
+        i += 1;
+    }
+    let mut title = None;
+    if let Some(&quote) = chars.get(i).filter(|c| matches!(c, '"' | '\'' | '(')) {
+        let closing = if quote == '(' { ')' } else { quote };
+        let mut t = String::new();
+        i += 1;
+        while i < chars.len() && chars[i] != closing {
+            t.push(chars[i]);
+            i += 1;
+        }
+        i += 1;
+        title = Some(t);
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+    }
+    if chars.get(i) != Some(&')') {
+        return None;
+    }
+    Some((dest, title, i + 1 - start))
+}
+
+/// `<https://...>` or `<user@example.com>` at `chars[start]`
+fn autolink(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let end = chars[start..].iter().position(|c| *c == '>')? + start;
+    let inner: String = chars[start + 1..end].iter().collect();
+    if inner.is_empty() || inner.contains(char::is_whitespace) || inner.contains('<') {
+        return None;
+    }
+    let href = if let Some(colon) = inner.find(':') {
+        let scheme = &inner[..colon];
+        if scheme.len() < 2 || !scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c)) {
+            return None;
+        }
+        inner.clone()
+    } else if inner.contains('@') && !inner.starts_with('@') && !inner.ends_with('@') {
+        format!("mailto:{}", inner)
+    } else {
+        return None;
+    };
+    Some((
+        format!("<a href=\"{}\">{}</a>", safe_url(&href), escape_html(&inner)),
+        end - start + 1,
+    ))
+}
+
+/// Length of a bare `https://`, `http://` or `www.` URL at `chars[start]`
+fn bare_url(chars: &[char], start: usize) -> Option<usize> {
+    let rest: String = chars[start..chars.len().min(start + 8)].iter().collect();
+    if !(rest.starts_with("https://") || rest.starts_with("http://") || rest.starts_with("www.")) {
+        return None;
+    }
+    let mut end = start;
+    while end < chars.len() && !chars[end].is_whitespace() && chars[end] != '<' {
+        end += 1;
+    }
+    // Trailing punctuation belongs to the sentence, as does an unbalanced ')'
+    loop {
+        let last = chars[end - 1];
+        if "?!.,:*_~'\"".contains(last) {
+            end -= 1;
+        } else if last == ')' {
+            let open = chars[start..end].iter().filter(|c| **c == '(').count();
+            let close = chars[start..end].iter().filter(|c| **c == ')').count();
+            if close > open {
+                end -= 1;
+            } else {
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+    let len = end - start;
+    (len > 8).then_some(len)
+}
+
+/// Text with markup characters removed, for `alt` attributes
+pub(crate) fn plain_text(markdown: &str) -> String {
+    markdown.chars().filter(|c| !matches!(c, '*' | '_' | '`' | '[' | ']' | '~')).collect()
+}
+
+/// Pairs emphasis delimiters following the CommonMark delimiter-run rules
+fn process_emphasis(pieces: &mut [Piece]) {
+    for closer in 0..pieces.len() {
+        loop {
+            let (ch, c_count, c_orig, c_open) = match &pieces[closer] {
+                Piece::Delimiter {
+                    ch,
+                    count,
+                    original,
+                    can_open,
+                    can_close: true,
+                    ..
+                } if *count > 0 => (*ch, *count, *original, *can_open),
+                _ => break,
+            };
+
+            let opener = (0..closer).rev().find(|&j| match &pieces[j] {
+                Piece::Delimiter {
+                    ch: oc,
+                    count,
+                    original,
+                    can_open: true,
+                    can_close,
+                    ..
+                } if *oc == ch && *count > 0 => {
+                    if ch == '~' {
+                        return *count == c_count;
+                    }
+                    // "Rule of 3" for runs that can both open and close
+                    !((*can_close || c_open) && (original + c_orig) % 3 == 0 && (original % 3 != 0 || c_orig % 3 != 0))
+                }
+                _ => false,
+            });
+            let Some(opener) = opener else {
+                break;
+            };
+
+            let o_count = match &pieces[opener] {
+                Piece::Delimiter { count, .. } => *count,
+                _ => unreachable!(),
+            };
+            let used = if ch == '~' { c_count } else if o_count >= 2 && c_count >= 2 { 2 } else { 1 };
+            let tag = match (ch, used) {
+                ('~', _) => "del",
+                (_, 2) => "strong",
+                _ => "em",
+            };
+
+            if let Piece::Delimiter { count, after, .. } = &mut pieces[opener] {
+                *count -= used;
+                after.insert_str(0, &format!("<{}>", tag));
+            }
+            if let Piece::Delimiter { count, before, .. } = &mut pieces[closer] {
+                *count -= used;
+                before.push_str(&format!("</{}>", tag));
+            }
+            // Delimiters between the pair can no longer match anything
+            for piece in &mut pieces[opener + 1..closer] {
+                if let Piece::Delimiter { can_open, can_close, .. } = piece {
+                    *can_open = false;
+                    *can_close = false;
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// HTML output
+// ---------------------------------------------------------------------------
+
+/// Maps a fence info string to a `highlight` language id
+fn fence_language(lang: &str) -> &str {
+    match lang.to_ascii_lowercase().as_str() {
+        "rs" => "rust",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "py" => "python",
+        "golang" => "go",
+        "c++" | "cc" | "cxx" | "hpp" => "cpp",
+        "h" => "c",
+        "cs" | "c#" => "csharp",
+        "sh" | "bash" | "zsh" | "console" => "shell",
+        _ => lang,
+    }
+}
+
+fn render_code(lang: &str, text: &str, options: &RenderOptions) -> String {
+    let class = if lang.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"language-{}\"", escape_html(lang))
+    };
+    let language = fence_language(lang).to_ascii_lowercase();
+    if !options.highlight_code || !highlight::is_supported(&language) {
+        return format!("<pre><code{}>{}</code></pre>\n", class, escape_html(text));
+    }
+
+    let result = highlight::highlight(text, &language, 0, usize::MAX);
+    let mut spans = result.spans.iter().peekable();
+    let mut out = String::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut col = 0;
+        while let Some(span) = spans.next_if(|s| s.line == line_no) {
+            out.push_str(&escape_html(&chars[col..span.start].iter().collect::<String>()));
+            out.push_str(&format!(
+                "<span class=\"tok-{}\">{}</span>",
+                span.kind.name(),
+                escape_html(&chars[span.start..span.end].iter().collect::<String>())
+            ));
+            col = span.end;
+        }
+        out.push_str(&escape_html(&chars[col..].iter().collect::<String>()));
+        out.push('\n');
+    }
+    format!("<pre><code{}>{}</code></pre>\n", class, out)
+}
+
+fn align_attr(align: Align) -> &'static str {
+    match align {
+        Align::None => "",
+        Align::Left => " style=\"text-align: left\"",
+        Align::Center => " style=\"text-align: center\"",
+        Align::Right => " style=\"text-align: right\"",
+    }
+}
+
+impl Renderer<'_> {
+    fn blocks(&mut self, blocks: &[Block], tight: bool, out: &mut String) {
+        for block in blocks {
+            match block {
+                Block::Heading(level, text) => {
+                    let id = if self.options.heading_ids {
+                        format!(" id=\"{}\"", escape_html(&self.heading_id(text)))
+                    } else {
+                        String::new()
+                    };
+                    let content = self.inline(text);
+                    out.push_str(&format!("<h{level}{id}>{content}</h{level}>\n"));
+                }
+                Block::Paragraph(text) if tight => {
+                    let content = self.inline(text);
+                    out.push_str(&content);
+                    out.push('\n');
+                }
+                Block::Paragraph(text) => {
+                    let content = self.inline(text);
+                    out.push_str(&format!("<p>{}</p>\n", content));
+                }
+                Block::Code { lang, text } => out.push_str(&render_code(lang, text, self.options)),
+                Block::Math(tex) => {
+                    out.push_str(&math::to_mathml(tex, true));
+                    out.push('\n');
+                }
+                Block::Quote(inner) => {
+                    out.push_str("<blockquote>\n");
+                    self.blocks(inner, false, out);
+                    out.push_str("</blockquote>\n");
+                }
+                Block::List { ordered, start, loose, items } => {
+                    let has_tasks = items.iter().any(|item| item.task.is_some());
+                    let class = if has_tasks { " class=\"contains-task-list\"" } else { "" };
+                    match (ordered, start) {
+                        (true, 1) => out.push_str(&format!("<ol{}>\n", class)),
+                        (true, n) => out.push_str(&format!("<ol start=\"{}\"{}>\n", n, class)),
+                        (false, _) => out.push_str(&format!("<ul{}>\n", class)),
+                    }
+                    for item in items {
+                        match item.task {
+                            Some(checked) => out.push_str(&format!(
+                                "<li class=\"task-list-item\"><input type=\"checkbox\" disabled{} /> ",
+                                if checked { " checked" } else { "" }
+                            )),
+                            None => out.push_str("<li>"),
+                        }
+                        if *loose {
+                            out.push('\n');
+                        }
+                        self.blocks(&item.blocks, !loose, out);
+                        if out.ends_with('\n') && !*loose {
+                            out.pop();
+                        }
+                        out.push_str("</li>\n");
+                    }
+                    out.push_str(if *ordered { "</ol>\n" } else { "</ul>\n" });
+                }
+                Block::Table { aligns, header, rows } => {
+                    out.push_str("<table>\n<thead>\n<tr>\n");
+                    for (cell, align) in header.iter().zip(aligns) {
+                        let content = self.inline(cell);
+                        out.push_str(&format!("<th{}>{}</th>\n", align_attr(*align), content));
+                    }
+                    out.push_str("</tr>\n</thead>\n");
+                    if !rows.is_empty() {
+                        out.push_str("<tbody>\n");
+                        for row in rows {
+                            out.push_str("<tr>\n");
+                            for (cell, align) in row.iter().zip(aligns) {
+                                let content = self.inline(cell);
+                                out.push_str(&format!("<td{}>{}</td>\n", align_attr(*align), content));
+                            }
+                            out.push_str("</tr>\n");
+                        }
+                        out.push_str("</tbody>\n");
+                    }
+                    out.push_str("</table>\n");
+                }
+                Block::Rule => out.push_str("<hr />\n"),
+            }
+        }
+    }
+
+    fn footnotes(&mut self, out: &mut String) {
+        if self.footnote_order.is_empty() {
+            return;
+        }
+        out.push_str("<section class=\"footnotes\">\n<ol>\n");
+        // Footnotes may reference further footnotes, which extends the order
+        let mut n = 0;
+        while n < self.footnote_order.len() {
+            let label = self.footnote_order[n].clone();
+            let id = escape_html(&slugify(&label));
+            out.push_str(&format!("<li id=\"fn-{}\">\n", id));
+            if let Some(blocks) = self.defs.footnotes.get(&label) {
+                self.blocks(blocks, false, out);
+            }
+            out.push_str(&format!(
+                "<a href=\"#fnref-{}\" class=\"footnote-backref\">\u{21a9}</a>\n</li>\n",
+                id
+            ));
+            n += 1;
+        }
+        out.push_str("</ol>\n</section>\n");
+    }
+}
+
+/// Renders markdown to HTML. Raw HTML in the source is escaped and link
+/// targets with script-capable schemes are dropped, so the output is safe to
+/// inject into the preview.
+pub fn render(content: &str, options: &RenderOptions) -> String {
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            // Expand leading tabs so indentation can be measured in spaces
+            let tabs = line.chars().take_while(|c| *c == '\t').count();
+            format!("{}{}", "    ".repeat(tabs), &line[tabs..])
+        })
+        .collect();
+
+    let mut defs = Definitions::default();
+    let blocks = parse_blocks(&lines, &mut defs, options);
+
+    let mut renderer = Renderer {
+        options,
+        defs: &defs,
+        footnote_order: Vec::new(),
+        heading_ids: HashMap::new(),
+    };
+    let mut out = String::new();
+    renderer.blocks(&blocks, false, &mut out);
+    renderer.footnotes(&mut out);
+    out
+}
+
+#[tauri::command]
+pub async fn render_markdown(
+    app_handle: AppHandle,
+    content: String,
+    options: Option<RenderOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let html = render(&content, &options);
+        if !options.diagrams {
+            return html;
+        }
+        // Diagrams follow the editor theme, like the rest of the preview
+        let theme = match settings::effective(&app_handle).get("theme").and_then(|t| t.as_str()) {
+            Some("dark") => DiagramTheme::Dark,
+            _ => DiagramTheme::Light,
+        };
+        let state = app_handle.state::<DiagramState>();
+        diagrams::render_in_html(&app_handle, &state, &html, theme)
+    })
+    .await
+    .map_err(|e| format!("Render task failed: {}", e))
+}
@@ -0,0 +1,763 @@
+//! Converts TeX math (the subset KaTeX users typically write) to MathML,
+//! which browsers and the PDF printer render without any JavaScript.
+
+use crate::markdown::escape_html;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Command(String),
+    Open,
+    Close,
+    Sup,
+    Sub,
+    /// Column separator in environments
+    Align,
+    /// `\\`, a row break in environments
+    Row,
+    Space,
+    Char(char),
+}
+
+fn tokenize(tex: &str) -> Vec<Token> {
+    let chars: Vec<char> = tex.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        tokens.push(match c {
+            '\\' => match chars.get(i) {
+                Some('\\') => {
+                    i += 1;
+                    Token::Row
+                }
+                Some(c) if c.is_ascii_alphabetic() => {
+                    let name: String = chars[i..].iter().take_while(|c| c.is_ascii_alphabetic()).collect();
+                    i += name.len();
+                    Token::Command(name)
+                }
+                Some(c) => {
+                    i += 1;
+                    Token::Command(c.to_string())
+                }
+                None => Token::Char('\\'),
+            },
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '^' => Token::Sup,
+            '_' => Token::Sub,
+            '&' => Token::Align,
+            '%' => {
+                // Comment to the end of the line
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            c if c.is_whitespace() => Token::Space,
+            c => Token::Char(c),
+        });
+    }
+    tokens
+}
+
+/// How an atom takes its sub- and superscripts
+#[derive(Clone, Copy, PartialEq)]
+enum Limits {
+    /// Always beside the atom
+    Side,
+    /// Under and over in display math, beside inline (\sum, \lim)
+    Display,
+    /// Always under and over (\underbrace, \overset)
+    Always,
+}
+
+struct Atom {
+    mathml: String,
+    limits: Limits,
+}
+
+impl Atom {
+    fn new(mathml: String) -> Self {
+        Atom { mathml, limits: Limits::Side }
+    }
+}
+
+fn mi(text: &str) -> String {
+    format!("<mi>{}</mi>", escape_html(text))
+}
+
+fn mo(text: &str) -> String {
+    format!("<mo>{}</mo>", escape_html(text))
+}
+
+fn mrow(items: Vec<String>) -> String {
+    if items.len() == 1 {
+        items.into_iter().next().unwrap_or_default()
+    } else {
+        format!("<mrow>{}</mrow>", items.concat())
+    }
+}
+
+fn fence(text: &str) -> String {
+    if text.is_empty() || text == "." {
+        String::new()
+    } else {
+        format!("<mo stretchy=\"true\" fence=\"true\">{}</mo>", escape_html(text))
+    }
+}
+
+/// Letters and letter-like symbols, shown as identifiers
+fn identifier(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" => "ϵ",
+        "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "varpi" => "ϖ",
+        "rho" => "ρ",
+        "varrho" => "ϱ",
+        "sigma" => "σ",
+        "varsigma" => "ς",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" => "ϕ",
+        "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "emptyset" | "varnothing" => "∅",
+        "imath" => "ı",
+        "jmath" => "ȷ",
+        _ => return None,
+    })
+}
+
+/// Operators, relations, arrows and punctuation
+fn operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "pm" => "±",
+        "mp" => "∓",
+        "times" => "×",
+        "div" => "÷",
+        "cdot" => "⋅",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "∙",
+        "oplus" => "⊕",
+        "ominus" => "⊖",
+        "otimes" => "⊗",
+        "odot" => "⊙",
+        "cap" => "∩",
+        "cup" => "∪",
+        "setminus" => "∖",
+        "wedge" | "land" => "∧",
+        "vee" | "lor" => "∨",
+        "neg" | "lnot" => "¬",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "ll" => "≪",
+        "gg" => "≫",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "supset" => "⊃",
+        "subseteq" => "⊆",
+        "supseteq" => "⊇",
+        "mid" => "∣",
+        "parallel" => "∥",
+        "perp" => "⊥",
+        "forall" => "∀",
+        "exists" => "∃",
+        "nexists" => "∄",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "longrightarrow" => "⟶",
+        "longleftarrow" => "⟵",
+        "Longrightarrow" => "⟹",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "vert" => "|",
+        "|" | "Vert" => "‖",
+        "lbrace" | "{" => "{",
+        "rbrace" | "}" => "}",
+        "prime" => "′",
+        "angle" => "∠",
+        "triangle" => "△",
+        "therefore" => "∴",
+        "because" => "∵",
+        "#" => "#",
+        "$" => "$",
+        "%" => "%",
+        "&" => "&",
+        "_" => "_",
+        _ => return None,
+    })
+}
+
+/// Operators that grow and take limits in display math
+fn large_operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "sum" => "∑",
+        "prod" => "∏",
+        "coprod" => "∐",
+        "int" => "∫",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        "bigcup" => "⋃",
+        "bigcap" => "⋂",
+        "bigoplus" => "⨁",
+        "bigotimes" => "⨂",
+        "bigvee" => "⋁",
+        "bigwedge" => "⋀",
+        _ => return None,
+    })
+}
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh", "coth", "log", "ln",
+    "lg", "exp", "deg", "dim", "hom", "ker", "arg", "det", "gcd", "Pr",
+];
+
+/// Functions whose subscripts go underneath in display math
+const LIMIT_FUNCTIONS: &[&str] = &["lim", "liminf", "limsup", "max", "min", "sup", "inf", "argmax", "argmin"];
+
+/// `\hat{x}` and friends: the accent character and whether it stretches
+fn accent(name: &str) -> Option<(&'static str, bool)> {
+    Some(match name {
+        "hat" => ("^", false),
+        "widehat" => ("^", true),
+        "bar" => ("¯", false),
+        "overline" => ("¯", true),
+        "vec" => ("→", false),
+        "overrightarrow" => ("→", true),
+        "tilde" => ("~", false),
+        "widetilde" => ("~", true),
+        "dot" => ("˙", false),
+        "ddot" => ("¨", false),
+        "check" => ("ˇ", false),
+        "breve" => ("˘", false),
+        "acute" => ("´", false),
+        "grave" => ("`", false),
+        _ => return None,
+    })
+}
+
+fn font_variant(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "mathbf" | "boldsymbol" | "bm" => "bold",
+        "mathit" => "italic",
+        "mathrm" => "normal",
+        "mathbb" => "double-struck",
+        "mathcal" => "script",
+        "mathfrak" => "fraktur",
+        "mathsf" => "sans-serif",
+        "mathtt" => "monospace",
+        _ => return None,
+    })
+}
+
+fn space(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "," | "thinspace" => "0.1667em",
+        ":" | ">" | "medspace" => "0.2222em",
+        ";" | "thickspace" => "0.2778em",
+        " " | "space" => "0.25em",
+        "quad" => "1em",
+        "qquad" => "2em",
+        "!" => "-0.1667em",
+        _ => return None,
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    display: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(&Token::Space) {
+            self.pos += 1;
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        self.skip_spaces();
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Atoms up to (not including) a closing brace, `&`, `\\`, `\right`,
+    /// `\end`, the end of input, or a token `stop` accepts
+    fn row(&mut self, stop: &dyn Fn(&Token) -> bool) -> Vec<String> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                None | Some(Token::Close | Token::Align | Token::Row) => break,
+                Some(Token::Command(name)) if name == "right" || name == "end" => break,
+                Some(token) if stop(token) => break,
+                _ => {}
+            }
+            if let Some(Token::Command(name)) = self.peek() {
+                // {a \over b} style fractions split the whole row
+                if name == "over" || name == "choose" {
+                    let binom = name == "choose";
+                    self.pos += 1;
+                    let numerator = mrow(std::mem::take(&mut items));
+                    let denominator = mrow(self.row(stop));
+                    let fraction = format!(
+                        "<mfrac{}>{}{}</mfrac>",
+                        if binom { " linethickness=\"0\"" } else { "" },
+                        numerator,
+                        denominator
+                    );
+                    items.push(if binom { mrow(vec![mo("("), fraction, mo(")")]) } else { fraction });
+                    break;
+                }
+            }
+            items.push(self.scripted());
+        }
+        items
+    }
+
+    /// The contents of `{...}`, or a single atom without scripts
+    fn argument(&mut self) -> String {
+        self.skip_spaces();
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            let items = self.row(&|_| false);
+            if self.peek() == Some(&Token::Close) {
+                self.pos += 1;
+            }
+            return mrow(items);
+        }
+        match self.peek() {
+            // x^12 is x¹2: a bare digit argument is one digit
+            Some(Token::Char(c)) if c.is_ascii_digit() => {
+                let digit = format!("<mn>{}</mn>", c);
+                self.pos += 1;
+                digit
+            }
+            Some(_) => self.atom().mathml,
+            None => String::new(),
+        }
+    }
+
+    /// Raw text of a `{...}` group, for \text and environment names
+    fn text_argument(&mut self) -> String {
+        self.skip_spaces();
+        if self.peek() != Some(&Token::Open) {
+            return match self.next() {
+                Some(Token::Char(c)) => c.to_string(),
+                _ => String::new(),
+            };
+        }
+        self.pos += 1;
+        let mut depth = 0;
+        let mut text = String::new();
+        while let Some(token) = self.tokens.get(self.pos).cloned() {
+            self.pos += 1;
+            match token {
+                Token::Open => depth += 1,
+                Token::Close if depth == 0 => break,
+                Token::Close => depth -= 1,
+                Token::Char(c) => text.push(c),
+                Token::Space => text.push(' '),
+                Token::Command(name) if name.len() == 1 => text.push_str(&name),
+                Token::Command(name) => text.push_str(&format!("\\{}", name)),
+                Token::Sup => text.push('^'),
+                Token::Sub => text.push('_'),
+                Token::Align => text.push('&'),
+                Token::Row => text.push('\n'),
+            }
+        }
+        text
+    }
+
+    /// `[...]` right after a command, e.g. the index of \sqrt[3]{x}
+    fn optional_argument(&mut self) -> Option<String> {
+        self.skip_spaces();
+        if self.peek() != Some(&Token::Char('[')) {
+            return None;
+        }
+        self.pos += 1;
+        let items = self.row(&|t| *t == Token::Char(']'));
+        if self.peek() == Some(&Token::Char(']')) {
+            self.pos += 1;
+        }
+        Some(mrow(items))
+    }
+
+    /// An atom followed by any sub- and superscripts and primes
+    fn scripted(&mut self) -> String {
+        let base = self.atom();
+        let mut sub = None;
+        let mut sup: Option<String> = None;
+        let mut primes = 0;
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some(Token::Sub) if sub.is_none() => {
+                    self.pos += 1;
+                    sub = Some(self.argument());
+                }
+                Some(Token::Sup) if sup.is_none() => {
+                    self.pos += 1;
+                    sup = Some(self.argument());
+                }
+                Some(Token::Char('\'')) => {
+                    self.pos += 1;
+                    primes += 1;
+                }
+                _ => break,
+            }
+        }
+        if primes > 0 {
+            let mark = mo(&"′".repeat(primes));
+            sup = Some(match sup {
+                Some(s) => mrow(vec![mark, s]),
+                None => mark,
+            });
+        }
+
+        let under = base.limits == Limits::Always || (base.limits == Limits::Display && self.display);
+        let (tag_both, tag_sub, tag_sup) = if under {
+            ("munderover", "munder", "mover")
+        } else {
+            ("msubsup", "msub", "msup")
+        };
+        match (sub, sup) {
+            (Some(b), Some(p)) => format!("<{0}>{1}{2}{3}</{0}>", tag_both, base.mathml, b, p),
+            (Some(b), None) => format!("<{0}>{1}{2}</{0}>", tag_sub, base.mathml, b),
+            (None, Some(p)) => format!("<{0}>{1}{2}</{0}>", tag_sup, base.mathml, p),
+            (None, None) => base.mathml,
+        }
+    }
+
+    fn atom(&mut self) -> Atom {
+        let Some(token) = self.next() else {
+            return Atom::new(String::new());
+        };
+        match token {
+            Token::Open => {
+                let items = self.row(&|_| false);
+                if self.peek() == Some(&Token::Close) {
+                    self.pos += 1;
+                }
+                Atom::new(mrow(items))
+            }
+            Token::Char(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(Token::Char(d)) = self.tokens.get(self.pos) {
+                    if !(d.is_ascii_digit() || (*d == '.' && number.chars().all(|c| c != '.'))) {
+                        break;
+                    }
+                    number.push(*d);
+                    self.pos += 1;
+                }
+                Atom::new(format!("<mn>{}</mn>", number))
+            }
+            Token::Char(c) if c.is_alphabetic() => Atom::new(mi(&c.to_string())),
+            Token::Char('\'') => Atom::new(mo("′")),
+            Token::Char('-') => Atom::new(mo("−")),
+            Token::Char(c) => Atom::new(mo(&c.to_string())),
+            Token::Command(name) => self.command(&name),
+            // Stray scripts and separators outside their context
+            Token::Sup => Atom::new(mo("^")),
+            Token::Sub => Atom::new(mo("_")),
+            Token::Align | Token::Row | Token::Close | Token::Space => Atom::new(String::new()),
+        }
+    }
+
+    fn command(&mut self, name: &str) -> Atom {
+        if let Some(symbol) = identifier(name) {
+            // Capital Greek letters are upright
+            let upright = symbol.chars().next().is_some_and(|c| c.is_uppercase());
+            return Atom::new(if upright {
+                format!("<mi mathvariant=\"normal\">{}</mi>", symbol)
+            } else {
+                mi(symbol)
+            });
+        }
+        if let Some(symbol) = operator(name) {
+            return Atom::new(mo(symbol));
+        }
+        if let Some(symbol) = large_operator(name) {
+            let integral = name.contains("int");
+            return Atom {
+                mathml: format!("<mo largeop=\"true\" movablelimits=\"true\">{}</mo>", symbol),
+                limits: if integral { Limits::Side } else { Limits::Display },
+            };
+        }
+        if FUNCTIONS.contains(&name) {
+            return Atom::new(mi(name));
+        }
+        if LIMIT_FUNCTIONS.contains(&name) {
+            let text = match name {
+                "argmax" => "arg max",
+                "argmin" => "arg min",
+                "liminf" => "lim inf",
+                "limsup" => "lim sup",
+                other => other,
+            };
+            return Atom {
+                mathml: format!("<mo movablelimits=\"true\">{}</mo>", text),
+                limits: Limits::Display,
+            };
+        }
+        if let Some(width) = space(name) {
+            return Atom::new(format!("<mspace width=\"{}\" />", width));
+        }
+        if let Some((mark, stretchy)) = accent(name) {
+            let base = self.argument();
+            return Atom::new(format!(
+                "<mover accent=\"true\">{}<mo stretchy=\"{}\">{}</mo></mover>",
+                base,
+                stretchy,
+                escape_html(mark)
+            ));
+        }
+        if let Some(variant) = font_variant(name) {
+            // Applies to every identifier inside, e.g. \mathbb{R}
+            let argument = self.argument();
+            return Atom::new(argument.replace("<mi>", &format!("<mi mathvariant=\"{}\">", variant)));
+        }
+
+        match name {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                Atom::new(format!("<mfrac>{}{}</mfrac>", numerator, denominator))
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let top = self.argument();
+                let bottom = self.argument();
+                Atom::new(mrow(vec![
+                    mo("("),
+                    format!("<mfrac linethickness=\"0\">{}{}</mfrac>", top, bottom),
+                    mo(")"),
+                ]))
+            }
+            "operatorname" => {
+                let text = self.text_argument();
+                Atom::new(mi(text.trim()))
+            }
+            "sqrt" => {
+                let index = self.optional_argument();
+                let radicand = self.argument();
+                Atom::new(match index {
+                    Some(index) => format!("<mroot>{}{}</mroot>", radicand, index),
+                    None => format!("<msqrt>{}</msqrt>", radicand),
+                })
+            }
+            "text" | "textrm" | "textnormal" | "mbox" | "textit" | "textbf" => {
+                let text = self.text_argument();
+                let variant = match name {
+                    "textit" => " mathvariant=\"italic\"",
+                    "textbf" => " mathvariant=\"bold\"",
+                    _ => "",
+                };
+                Atom::new(format!("<mtext{}>{}</mtext>", variant, escape_html(&text)))
+            }
+            "left" => {
+                let open = self.delimiter();
+                let inner = self.row(&|_| false);
+                let close = if self.peek() == Some(&Token::Command("right".to_string())) {
+                    self.pos += 1;
+                    self.delimiter()
+                } else {
+                    String::new()
+                };
+                let mut items = vec![fence(&open)];
+                items.extend(inner);
+                items.push(fence(&close));
+                Atom::new(format!("<mrow>{}</mrow>", items.concat()))
+            }
+            "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr" | "biggl" | "biggr" => {
+                let delimiter = self.delimiter();
+                Atom::new(format!("<mo stretchy=\"false\">{}</mo>", escape_html(&delimiter)))
+            }
+            "overset" | "stackrel" | "underset" => {
+                let script = self.argument();
+                let base = self.argument();
+                let tag = if name == "underset" { "munder" } else { "mover" };
+                Atom::new(format!("<{0}>{1}{2}</{0}>", tag, base, script))
+            }
+            "underbrace" | "overbrace" => {
+                let base = self.argument();
+                let (tag, brace) = if name == "underbrace" { ("munder", "⏟") } else { ("mover", "⏞") };
+                Atom {
+                    mathml: format!("<{0}>{1}<mo stretchy=\"true\">{2}</mo></{0}>", tag, base, brace),
+                    limits: Limits::Always,
+                }
+            }
+            "underline" => {
+                let base = self.argument();
+                Atom::new(format!("<munder>{}<mo stretchy=\"true\">_</mo></munder>", base))
+            }
+            "boxed" => {
+                let base = self.argument();
+                Atom::new(format!("<menclose notation=\"box\">{}</menclose>", base))
+            }
+            "displaystyle" | "textstyle" | "limits" | "nolimits" => Atom::new(String::new()),
+            "begin" => self.environment(),
+            _ => Atom::new(format!("<merror><mtext>\\{}</mtext></merror>", escape_html(name))),
+        }
+    }
+
+    /// The delimiter after \left, \right or \big: a character or a command like \langle
+    fn delimiter(&mut self) -> String {
+        match self.next() {
+            Some(Token::Char(c)) => c.to_string(),
+            Some(Token::Command(name)) => operator(&name).unwrap_or("").to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn environment(&mut self) -> Atom {
+        let name = self.text_argument();
+        let name = name.trim_end_matches('*');
+        if name == "array" {
+            // Column spec such as {c|c}
+            self.text_argument();
+        }
+
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut cells: Vec<String> = Vec::new();
+        loop {
+            cells.push(mrow(self.row(&|_| false)));
+            match self.next() {
+                Some(Token::Align) => {}
+                Some(Token::Row) => rows.push(std::mem::take(&mut cells)),
+                Some(Token::Command(end)) if end == "end" => {
+                    self.text_argument();
+                    break;
+                }
+                // A stray } or \right; keep going so the rest isn't lost
+                Some(_) => {}
+                None => break,
+            }
+        }
+        // A trailing \\ leaves an empty last row
+        if !(cells.len() == 1 && cells[0].is_empty()) {
+            rows.push(cells);
+        }
+
+        let column_align = match name {
+            "aligned" | "align" | "split" => " columnalign=\"right left\"",
+            "cases" | "dcases" => " columnalign=\"left left\"",
+            _ => "",
+        };
+        let table = format!(
+            "<mtable{}>{}</mtable>",
+            column_align,
+            rows.iter()
+                .map(|row| format!(
+                    "<mtr>{}</mtr>",
+                    row.iter().map(|cell| format!("<mtd>{}</mtd>", cell)).collect::<String>()
+                ))
+                .collect::<String>()
+        );
+        let (open, close) = match name {
+            "pmatrix" => ("(", ")"),
+            "bmatrix" => ("[", "]"),
+            "Bmatrix" => ("{", "}"),
+            "vmatrix" => ("|", "|"),
+            "Vmatrix" => ("‖", "‖"),
+            "cases" | "dcases" => ("{", ""),
+            _ => ("", ""),
+        };
+        Atom::new(if open.is_empty() && close.is_empty() {
+            table
+        } else {
+            format!("<mrow>{}{}{}</mrow>", fence(open), table, fence(close))
+        })
+    }
+}
+
+/// MathML for `tex`, with the source kept as an annotation so it can be
+/// copied back out of the document
+pub fn to_mathml(tex: &str, display: bool) -> String {
+    let mut parser = Parser {
+        tokens: tokenize(tex),
+        pos: 0,
+        display,
+    };
+    let mut items = Vec::new();
+    while parser.pos < parser.tokens.len() {
+        items.extend(parser.row(&|_| false));
+        // Unbalanced } or separators outside an environment
+        parser.pos += 1;
+    }
+    format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"{}><semantics><mrow>{}</mrow><annotation encoding=\"application/x-tex\">{}</annotation></semantics></math>",
+        if display { " display=\"block\"" } else { "" },
+        items.concat(),
+        escape_html(tex)
+    )
+}
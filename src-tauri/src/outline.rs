@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::front_matter;
+use crate::fs_guard::FsGuardState;
+use crate::markdown::{
+    atx_heading, fence_start, indent_of, interrupts_paragraph, is_fence_end, plain_text, setext_level, slugify,
+};
+
+/// Markers around a generated table of contents, as used by markdown-toc
+/// and most editors, so the block can be found and regenerated later
+const TOC_START: &str = "<!-- toc -->";
+const TOC_END: &str = "<!-- tocstop -->";
+
+/// A span of the document in UTF-16 code units, like CodeMirror positions
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TextRange {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineHeading {
+    pub level: u8,
+    /// Heading text with inline markup removed
+    pub text: String,
+    /// Anchor id, deduplicated the same way the renderer does
+    pub slug: String,
+    /// 0-based line of the heading (the text line for setext headings)
+    pub line: usize,
+    /// The heading itself, including a setext underline
+    pub range: TextRange,
+    /// The heading and everything up to the next heading of the same or a higher level
+    pub section: TextRange,
+    pub children: Vec<OutlineHeading>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Outline {
+    pub headings: Vec<OutlineHeading>,
+    /// Where the existing `<!-- toc -->` block is, markers included
+    pub toc: Option<TextRange>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TocOptions {
+    pub min_level: u8,
+    pub max_level: u8,
+    /// Numbered list instead of bullets
+    pub ordered: bool,
+    /// Wrap the list in `<!-- toc -->` markers so it can be updated later
+    pub markers: bool,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        Self {
+            min_level: 1,
+            max_level: 3,
+            ordered: false,
+            markers: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TocBlock {
+    pub markdown: String,
+    /// The existing TOC block this one should replace; None to insert it
+    pub replace: Option<TextRange>,
+}
+
+/// A heading before it is placed in the tree
+struct FlatHeading {
+    level: u8,
+    raw: String,
+    line: usize,
+    range: TextRange,
+}
+
+/// Text shown for a heading: link targets and markup characters dropped
+fn display_text(raw: &str) -> String {
+    let mut out = String::new();
+    let mut rest = raw;
+    // "[text](url)" and "![alt](src)" keep only their text
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        let Some(end) = rest[close..].find(')').map(|i| close + i) else {
+            break;
+        };
+        out.push_str(rest[..open].trim_end_matches('!'));
+        out.push_str(&rest[open + 1..close]);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    plain_text(&out).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Headings outside front matter and code blocks, in document order, plus
+/// the TOC block if there is one
+fn scan(content: &str) -> (Vec<FlatHeading>, Option<TextRange>, usize) {
+    let body_start = front_matter::split(content).map_or(0, |(_, _, start)| start);
+    let mut offset = utf16_len(&content[..body_start]);
+    let first_line = content[..body_start].matches('\n').count();
+
+    let mut headings = Vec::new();
+    let mut toc_start = None;
+    let mut toc = None;
+    let mut fence = None;
+    // Paragraph lines that a setext underline would turn into a heading
+    let mut paragraph: Option<(usize, usize, Vec<&str>)> = None;
+    let mut after_blank = true;
+
+    for (index, raw_line) in content[body_start..].split_inclusive('\n').enumerate() {
+        let line_no = first_line + index;
+        let start = offset;
+        offset += utf16_len(raw_line);
+        let line = raw_line.trim_end_matches(['\n', '\r']);
+
+        if let Some(open) = &fence {
+            if is_fence_end(line, open) {
+                fence = None;
+            }
+            continue;
+        }
+        match line.trim() {
+            TOC_START => toc_start = Some(start),
+            TOC_END => {
+                if let Some(from) = toc_start.take() {
+                    toc = Some(TextRange { from, to: offset });
+                }
+            }
+            _ => {}
+        }
+        if line.trim().is_empty() {
+            paragraph = None;
+            after_blank = true;
+            continue;
+        }
+        if let Some((text_line, text_start, lines)) = &paragraph {
+            if let Some(level) = setext_level(line) {
+                headings.push(FlatHeading {
+                    level,
+                    // Joined like the renderer does, so the slug matches its id
+                    raw: lines.join("\n").trim().to_string(),
+                    line: *text_line,
+                    range: TextRange {
+                        from: *text_start,
+                        to: offset,
+                    },
+                });
+                paragraph = None;
+                after_blank = false;
+                continue;
+            }
+        }
+        // Indented code only starts after a blank line, not inside a paragraph
+        if paragraph.is_none() && after_blank && indent_of(line) >= 4 {
+            continue;
+        }
+        after_blank = false;
+        if let Some(open) = fence_start(line) {
+            fence = Some(open);
+            paragraph = None;
+            continue;
+        }
+        if let Some((level, text)) = atx_heading(line) {
+            headings.push(FlatHeading {
+                level,
+                raw: text,
+                line: line_no,
+                range: TextRange {
+                    from: start,
+                    to: start + utf16_len(line),
+                },
+            });
+            paragraph = None;
+            continue;
+        }
+        if interrupts_paragraph(line) || line.contains('|') {
+            // Quotes, lists and tables can't be underlined into headings
+            paragraph = None;
+            continue;
+        }
+        match &mut paragraph {
+            Some((_, _, lines)) => lines.push(line.trim()),
+            None => paragraph = Some((line_no, start, vec![line.trim()])),
+        }
+    }
+    (headings, toc, offset)
+}
+
+/// Nests `flat` headings under the closest preceding heading of a lower level
+fn build_tree(flat: Vec<FlatHeading>, end: usize) -> Vec<OutlineHeading> {
+    let mut slug_counts: HashMap<String, usize> = HashMap::new();
+    let mut nodes: Vec<OutlineHeading> = Vec::with_capacity(flat.len());
+    for (i, heading) in flat.iter().enumerate() {
+        let slug = slugify(&heading.raw);
+        let count = slug_counts.entry(slug.clone()).or_insert(0);
+        let slug = if *count == 0 {
+            slug
+        } else {
+            format!("{}-{}", slug, count)
+        };
+        *count += 1;
+        let section_end = flat[i + 1..]
+            .iter()
+            .find(|next| next.level <= heading.level)
+            .map_or(end, |next| next.range.from);
+        nodes.push(OutlineHeading {
+            level: heading.level,
+            text: display_text(&heading.raw),
+            slug,
+            line: heading.line,
+            range: heading.range,
+            section: TextRange {
+                from: heading.range.from,
+                to: section_end,
+            },
+            children: Vec::new(),
+        });
+    }
+
+    // Stack of open headings; each is attached to its parent once closed
+    let mut roots = Vec::new();
+    let mut stack: Vec<OutlineHeading> = Vec::new();
+    let close = |stack: &mut Vec<OutlineHeading>, roots: &mut Vec<OutlineHeading>| {
+        let node = stack.pop().expect("stack is not empty");
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    };
+    for node in nodes {
+        while stack.last().is_some_and(|open| open.level >= node.level) {
+            close(&mut stack, &mut roots);
+        }
+        stack.push(node);
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+pub fn outline(content: &str) -> Outline {
+    let (flat, toc, end) = scan(content);
+    Outline {
+        headings: build_tree(flat, end),
+        toc,
+    }
+}
+
+fn write_toc(headings: &[OutlineHeading], options: &TocOptions, depth: usize, out: &mut String) {
+    let mut number = 0;
+    for heading in headings {
+        if heading.level > options.max_level {
+            continue;
+        }
+        // Levels above the range are skipped but their children still listed
+        if heading.level < options.min_level {
+            write_toc(&heading.children, options, depth, out);
+            continue;
+        }
+        number += 1;
+        let marker = if options.ordered {
+            format!("{}.", number)
+        } else {
+            "-".to_string()
+        };
+        let indent = " ".repeat(depth * if options.ordered { 3 } else { 2 });
+        let text = heading.text.replace('[', "\\[").replace(']', "\\]");
+        out.push_str(&format!("{}{} [{}](#{})\n", indent, marker, text, heading.slug));
+        write_toc(&heading.children, options, depth + 1, out);
+    }
+}
+
+fn read_input(guard: &FsGuardState, path: Option<String>, content: Option<String>) -> Result<String, String> {
+    match (content, path) {
+        (Some(content), _) => Ok(content),
+        (None, Some(path)) => {
+            let path = guard.check(&path)?;
+            fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))
+        }
+        (None, None) => Err("Either a path or content is required".to_string()),
+    }
+}
+
+/// Heading tree of a markdown document, read from `path` unless `content`
+/// (e.g. an unsaved buffer) is given
+#[tauri::command]
+pub async fn get_markdown_outline(
+    guard: State<'_, FsGuardState>,
+    path: Option<String>,
+    content: Option<String>,
+) -> Result<Outline, String> {
+    let content = read_input(&guard, path, content)?;
+    Ok(outline(&content))
+}
+
+/// A table of contents for the document and the existing TOC block it
+/// should replace, if any
+#[tauri::command]
+pub async fn generate_toc(
+    guard: State<'_, FsGuardState>,
+    path: Option<String>,
+    content: Option<String>,
+    options: Option<TocOptions>,
+) -> Result<TocBlock, String> {
+    let content = read_input(&guard, path, content)?;
+    let options = options.unwrap_or_default();
+    let outline = outline(&content);
+
+    let mut list = String::new();
+    write_toc(&outline.headings, &options, 0, &mut list);
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let markdown = if options.markers {
+        format!("{}\n\n{}\n{}\n", TOC_START, list, TOC_END)
+    } else {
+        list
+    };
+    Ok(TocBlock {
+        markdown: markdown.replace('\n', newline),
+        replace: outline.toc,
+    })
+}
@@ -0,0 +1,275 @@
+//! Import and export through pandoc: Word, OpenDocument and EPUB documents
+//! to and from markdown. Pandoc is found from an explicit path, a copy
+//! bundled with the app's resources, or PATH.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tokio::process::Command;
+
+use crate::export;
+use crate::fs_guard::FsGuardState;
+use crate::lsp::registry::find_executable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    Markdown,
+    Docx,
+    Odt,
+    Epub,
+    Html,
+}
+
+impl DocumentFormat {
+    /// Pandoc writer name
+    fn writer(self) -> &'static str {
+        match self {
+            // GitHub-flavoured so tables and task lists survive the import
+            DocumentFormat::Markdown => "gfm",
+            DocumentFormat::Docx => "docx",
+            DocumentFormat::Odt => "odt",
+            DocumentFormat::Epub => "epub",
+            DocumentFormat::Html => "html",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            DocumentFormat::Markdown => "md",
+            DocumentFormat::Docx => "docx",
+            DocumentFormat::Odt => "odt",
+            DocumentFormat::Epub => "epub",
+            DocumentFormat::Html => "html",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConvertOptions {
+    /// Defaults to the input path with the output format's extension
+    pub output_path: Option<String>,
+    /// Pandoc binary to use instead of the bundled one or the one on PATH
+    pub pandoc_path: Option<String>,
+    /// Folder images are extracted to when importing to markdown; defaults to
+    /// `<name>_media` next to the output
+    pub media_dir: Option<String>,
+    /// Styles template for docx/odt output (pandoc's --reference-doc)
+    pub reference_doc: Option<String>,
+    /// Document title, for formats that need one (EPUB)
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PandocInfo {
+    pub path: String,
+    pub version: String,
+    pub bundled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvertResult {
+    pub output: String,
+    /// Pandoc's warnings, one per line, e.g. unsupported elements that were dropped
+    pub warnings: Vec<String>,
+}
+
+/// Error returned by `convert_document`; `message` is always set so callers
+/// that only display errors keep working
+#[derive(Debug, Serialize)]
+pub struct PandocError {
+    pub message: String,
+    /// False when no pandoc binary was found, so the UI can offer to install one
+    pub pandoc_found: bool,
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+impl From<String> for PandocError {
+    fn from(message: String) -> Self {
+        PandocError {
+            message,
+            pandoc_found: true,
+            exit_code: None,
+            stderr: String::new(),
+        }
+    }
+}
+
+fn bundled_pandoc(app_handle: &AppHandle) -> Option<PathBuf> {
+    let name = if cfg!(target_os = "windows") {
+        "pandoc.exe"
+    } else {
+        "pandoc"
+    };
+    let path = app_handle.path().resource_dir().ok()?.join("pandoc").join(name);
+    path.is_file().then_some(path)
+}
+
+/// The pandoc binary to run and whether it is the bundled copy
+fn locate(app_handle: &AppHandle, explicit: Option<&str>) -> Option<(PathBuf, bool)> {
+    if let Some(path) = explicit {
+        return find_executable(path).map(|p| (p, false));
+    }
+    bundled_pandoc(app_handle)
+        .map(|p| (p, true))
+        .or_else(|| find_executable("pandoc").map(|p| (p, false)))
+}
+
+fn not_found() -> PandocError {
+    PandocError {
+        message: "pandoc was not found; install it or set its path".to_string(),
+        pandoc_found: false,
+        exit_code: None,
+        stderr: String::new(),
+    }
+}
+
+/// The pandoc that `convert_document` would use, with its version
+#[tauri::command]
+pub async fn get_pandoc_info(app_handle: AppHandle, pandoc_path: Option<String>) -> Result<PandocInfo, PandocError> {
+    let (path, bundled) = locate(&app_handle, pandoc_path.as_deref()).ok_or_else(not_found)?;
+    let output = Command::new(&path)
+        .arg("--version")
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", path.display(), e))?;
+    // First line is "pandoc 3.1.9"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("")
+        .to_string();
+    Ok(PandocInfo {
+        path: path.to_string_lossy().to_string(),
+        version,
+        bundled,
+    })
+}
+
+/// Sibling of `output` that pandoc writes to before it is moved into place,
+/// so a failed conversion never leaves a truncated file behind
+fn temp_output(output: &Path) -> PathBuf {
+    let name = output
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    output.with_file_name(format!(".{}.pandoc-tmp", name))
+}
+
+/// Converts `input` to `output_format` with pandoc. The input format comes
+/// from the file extension. Progress is emitted on `export-progress`;
+/// returns the path written and any warnings pandoc printed.
+#[tauri::command]
+pub async fn convert_document(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    input: String,
+    output_format: DocumentFormat,
+    options: Option<ConvertOptions>,
+) -> Result<ConvertResult, PandocError> {
+    let options = options.unwrap_or_default();
+    let (pandoc, _) = locate(&app_handle, options.pandoc_path.as_deref()).ok_or_else(not_found)?;
+
+    let source = guard.check(&input).map_err(String::from)?;
+    if !source.is_file() {
+        return Err(format!("File not found: {}", input).into());
+    }
+    let output = match &options.output_path {
+        Some(p) => PathBuf::from(p),
+        None => source.with_extension(output_format.extension()),
+    };
+    let output = guard.check_entry(&output.to_string_lossy()).map_err(String::from)?;
+    if output == source {
+        return Err("The output would overwrite the input".to_string().into());
+    }
+    let temp = temp_output(&output);
+
+    let mut cmd = Command::new(&pandoc);
+    cmd.arg(&source)
+        .args(["--to", output_format.writer(), "--standalone", "--output"])
+        .arg(&temp);
+    match output_format {
+        DocumentFormat::Markdown => {
+            let media = match &options.media_dir {
+                Some(dir) => PathBuf::from(dir),
+                None => {
+                    let stem = output
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    output.with_file_name(format!("{}_media", stem))
+                }
+            };
+            cmd.arg(format!("--extract-media={}", media.display()));
+            // Keep the source's line structure instead of re-wrapping paragraphs
+            cmd.arg("--wrap=preserve");
+        }
+        DocumentFormat::Html => {
+            // Same as export_markdown: the file must not need anything else to display
+            cmd.args(["--embed-resources", "--mathml"]);
+        }
+        _ => {}
+    }
+    if let Some(reference) = &options.reference_doc {
+        cmd.arg(format!("--reference-doc={}", reference));
+    }
+    if let Some(title) = &options.title {
+        cmd.args(["--metadata", &format!("title={}", title)]);
+    }
+    // Relative images and includes resolve against the document's folder
+    if let Some(dir) = source.parent().filter(|d| d.is_dir()) {
+        cmd.current_dir(dir);
+    }
+
+    export::emit_progress(&app_handle, &input, "converting", 0.0);
+    let result = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await;
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            return Err(format!("Failed to run {}: {}", pandoc.display(), e).into());
+        }
+    };
+
+    let stderr = String::from_utf8_lossy(&result.stderr).trim().to_string();
+    if !result.status.success() || !temp.is_file() {
+        let _ = fs::remove_file(&temp);
+        let summary = stderr.lines().next().unwrap_or("").to_string();
+        return Err(PandocError {
+            message: format!("pandoc failed: {}", summary),
+            pandoc_found: true,
+            exit_code: result.status.code(),
+            stderr,
+        });
+    }
+
+    export::emit_progress(&app_handle, &input, "writing", 0.9);
+    fs::rename(&temp, &output).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("Failed to write file: {}", e)
+    })?;
+
+    export::emit_progress(&app_handle, &input, "done", 1.0);
+    Ok(ConvertResult {
+        output: output.to_string_lossy().to_string(),
+        warnings: stderr
+            .lines()
+            .map(str::to_string)
+            .filter(|l| !l.trim().is_empty())
+            .collect(),
+    })
+}
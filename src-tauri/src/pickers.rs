@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, FileDialogBuilder, FilePath};
+use tauri_plugin_store::StoreExt;
+
+use crate::fs_guard;
+
+const DIALOG_STORE: &str = "dialogs.json";
+const LAST_DIRS_KEY: &str = "lastDirectories";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogFilter {
+    pub name: String,
+    /// Extensions without the dot, e.g. `["md", "markdown"]`
+    pub extensions: Vec<String>,
+}
+
+/// Key under which an operation's last directory is remembered; callers pass
+/// their own (e.g. "export-pdf") so unrelated dialogs don't share one
+fn operation_key(operation: Option<String>, fallback: &str) -> String {
+    operation.filter(|o| !o.is_empty()).unwrap_or_else(|| fallback.to_string())
+}
+
+fn last_directory(app: &AppHandle, operation: &str) -> Option<PathBuf> {
+    let store = app.store(DIALOG_STORE).ok()?;
+    let dir = store.get(LAST_DIRS_KEY)?.get(operation)?.as_str()?.to_string();
+    // The folder may have been deleted or unmounted since
+    Some(PathBuf::from(dir)).filter(|d| d.is_dir())
+}
+
+fn remember_directory(app: &AppHandle, operation: &str, dir: &Path) {
+    let store = match app.store(DIALOG_STORE) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Failed to open {}: {}", DIALOG_STORE, e);
+            return;
+        }
+    };
+    let mut dirs = store
+        .get(LAST_DIRS_KEY)
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default();
+    dirs.insert(operation.to_string(), dir.to_string_lossy().to_string().into());
+    store.set(LAST_DIRS_KEY, serde_json::Value::Object(dirs));
+    if let Err(e) = store.save() {
+        tracing::warn!("Failed to save {}: {}", DIALOG_STORE, e);
+    }
+}
+
+fn builder(
+    app: &AppHandle,
+    operation: &str,
+    start_dir: Option<String>,
+    filters: &[DialogFilter],
+) -> FileDialogBuilder<tauri::Wry> {
+    let mut dialog = app.dialog().file();
+    let start = start_dir
+        .map(PathBuf::from)
+        .filter(|d| d.is_dir())
+        .or_else(|| last_directory(app, operation));
+    if let Some(dir) = start {
+        dialog = dialog.set_directory(dir);
+    }
+    for filter in filters {
+        let extensions: Vec<&str> = filter.extensions.iter().map(|e| e.trim_start_matches('.')).collect();
+        dialog = dialog.add_filter(&filter.name, &extensions);
+    }
+    dialog
+}
+
+fn into_path(file: FilePath) -> Result<PathBuf, String> {
+    file.into_path().map_err(|e| format!("Unsupported path from dialog: {}", e))
+}
+
+/// Shows a folder picker; `None` if the user cancelled
+#[tauri::command]
+pub async fn pick_folder(
+    app_handle: AppHandle,
+    start_dir: Option<String>,
+    operation: Option<String>,
+) -> Result<Option<String>, String> {
+    let operation = operation_key(operation, "pick-folder");
+    let dialog = builder(&app_handle, &operation, start_dir, &[]);
+    // The blocking variants wait on the main thread, so keep them off the async runtime
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_pick_folder())
+        .await
+        .map_err(|e| format!("Dialog task failed: {}", e))?;
+
+    let Some(folder) = picked.map(into_path).transpose()? else {
+        return Ok(None);
+    };
+    fs_guard::grant_folder(&app_handle, &folder);
+    // Next time start beside the chosen folder rather than inside it
+    remember_directory(&app_handle, &operation, folder.parent().unwrap_or(&folder));
+    Ok(Some(folder.to_string_lossy().to_string()))
+}
+
+/// Shows an open-file picker; returns the chosen paths, empty if cancelled
+#[tauri::command]
+pub async fn pick_files(
+    app_handle: AppHandle,
+    filters: Option<Vec<DialogFilter>>,
+    multiple: Option<bool>,
+    start_dir: Option<String>,
+    operation: Option<String>,
+) -> Result<Vec<String>, String> {
+    let operation = operation_key(operation, "pick-files");
+    let dialog = builder(&app_handle, &operation, start_dir, &filters.unwrap_or_default());
+    let multiple = multiple.unwrap_or(true);
+    let picked = tauri::async_runtime::spawn_blocking(move || {
+        if multiple {
+            dialog.blocking_pick_files()
+        } else {
+            dialog.blocking_pick_file().map(|file| vec![file])
+        }
+    })
+    .await
+    .map_err(|e| format!("Dialog task failed: {}", e))?;
+
+    let files = picked
+        .unwrap_or_default()
+        .into_iter()
+        .map(into_path)
+        .collect::<Result<Vec<_>, _>>()?;
+    for file in &files {
+        fs_guard::grant_file(&app_handle, file);
+    }
+    if let Some(dir) = files.first().and_then(|f| f.parent()) {
+        remember_directory(&app_handle, &operation, dir);
+    }
+    Ok(files.into_iter().map(|f| f.to_string_lossy().to_string()).collect())
+}
+
+/// Shows a save dialog prefilled with `default_name`; `None` if cancelled
+#[tauri::command]
+pub async fn pick_save_path(
+    app_handle: AppHandle,
+    default_name: Option<String>,
+    filters: Option<Vec<DialogFilter>>,
+    start_dir: Option<String>,
+    operation: Option<String>,
+) -> Result<Option<String>, String> {
+    let operation = operation_key(operation, "pick-save-path");
+    let mut dialog = builder(&app_handle, &operation, start_dir, &filters.unwrap_or_default());
+    if let Some(name) = default_name.filter(|n| !n.is_empty()) {
+        dialog = dialog.set_file_name(name);
+    }
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file())
+        .await
+        .map_err(|e| format!("Dialog task failed: {}", e))?;
+
+    let Some(file) = picked.map(into_path).transpose()? else {
+        return Ok(None);
+    };
+    fs_guard::grant_file(&app_handle, &file);
+    if let Some(dir) = file.parent() {
+        remember_directory(&app_handle, &operation, dir);
+    }
+    Ok(Some(file.to_string_lossy().to_string()))
+}
@@ -0,0 +1,688 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::menu::{MenuItemBuilder, Submenu, SubmenuBuilder};
+use tauri::{AppHandle, Emitter, Manager, State, Wry};
+
+use crate::command_policy::{self, CommandPolicyState};
+use crate::fs_guard::FsGuardState;
+use crate::wasm_plugin::{self, WasmPlugin};
+
+const PLUGINS_DIR: &str = "plugins";
+const MANIFEST_FILE: &str = "plugin.json";
+pub const PLUGIN_MENU_PREFIX: &str = "plugin:";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// Plugins may read workspace files through the host, but not huge ones
+const MAX_READ_SIZE: u64 = 10 * 1024 * 1024;
+
+/// `plugin.json` at the root of a plugin folder
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Entry point, relative to the plugin folder
+    pub main: String,
+    /// "wasm", "node", "python" or "native"; guessed from `main` when omitted
+    #[serde(default)]
+    pub runtime: Option<String>,
+    /// "fs.read" to read files in the open workspace, "menus" to add menu items
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginCommand {
+    pub id: String,
+    pub title: String,
+    /// Whether the command is listed in the Plugins menu
+    pub in_menu: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub path: String,
+    pub permissions: Vec<String>,
+    pub running: bool,
+    pub commands: Vec<PluginCommand>,
+    /// Set when the manifest can't be read
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginMessage {
+    plugin_id: String,
+    /// "info", "warning" or "error"
+    level: String,
+    message: String,
+}
+
+type Reply = Result<Value, String>;
+
+/// What runs a plugin's entry point
+enum Runner {
+    /// A WASI module in the interpreter, with no access beyond its stdio
+    Wasm(WasmPlugin),
+    /// A Node, Python or native process with the user's own rights
+    Process(Child),
+}
+
+impl Runner {
+    fn kill(&mut self) {
+        match self {
+            Runner::Wasm(module) => module.kill(),
+            Runner::Process(child) => {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+}
+
+struct PluginProcess {
+    manifest: PluginManifest,
+    dir: PathBuf,
+    runner: Mutex<Runner>,
+    /// Taken when the plugin is stopped, which closes its stdin
+    stdin: Mutex<Option<Box<dyn Write + Send>>>,
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, mpsc::Sender<Reply>>>,
+    commands: Mutex<Vec<PluginCommand>>,
+}
+
+impl PluginProcess {
+    fn send(&self, message: &Value) -> Result<(), String> {
+        let mut line = serde_json::to_vec(message).map_err(|e| format!("Failed to encode message: {}", e))?;
+        line.push(b'\n');
+        let mut stdin = self.stdin.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        let stdin = stdin
+            .as_mut()
+            .ok_or_else(|| format!("Plugin {} is not running", self.manifest.id))?;
+        stdin
+            .write_all(&line)
+            .and_then(|_| stdin.flush())
+            .map_err(|e| format!("Plugin {} is not running: {}", self.manifest.id, e))
+    }
+
+    /// Sends a JSON-RPC request and blocks until the plugin answers
+    fn request(&self, method: &str, params: Value) -> Reply {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel();
+        self.pending
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?
+            .insert(id, tx);
+        let sent = self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        let reply = sent.and_then(|_| {
+            rx.recv_timeout(REQUEST_TIMEOUT)
+                .map_err(|_| format!("Plugin {} did not answer {}", self.manifest.id, method))?
+        });
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&id);
+        }
+        reply
+    }
+
+    fn has_permission(&self, permission: &str) -> bool {
+        self.manifest.permissions.iter().any(|p| p == permission)
+    }
+}
+
+/// Running plugins and the Plugins menu
+#[derive(Default)]
+pub struct PluginState {
+    running: Mutex<HashMap<String, Arc<PluginProcess>>>,
+    menu: Mutex<Option<Submenu<Wry>>>,
+}
+
+fn plugins_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join(PLUGINS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create plugins directory: {}", e))?;
+    Ok(dir)
+}
+
+fn read_manifest(dir: &Path) -> Result<PluginManifest, String> {
+    let text = fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("Failed to read {}: {}", MANIFEST_FILE, e))?;
+    let manifest: PluginManifest =
+        serde_json::from_str(&text).map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?;
+    let valid_id = !manifest.id.is_empty()
+        && manifest
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid_id {
+        return Err(format!("Invalid plugin id \"{}\"", manifest.id));
+    }
+    Ok(manifest)
+}
+
+/// A plugin folder with its manifest, or why it has none
+type Discovered = (PathBuf, Result<PluginManifest, String>);
+
+fn discover(app: &AppHandle) -> Result<Vec<Discovered>, String> {
+    let dir = plugins_dir(app)?;
+    let mut found: Vec<_> = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read plugins directory: {}", e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|path| {
+            let manifest = read_manifest(&path);
+            (path, manifest)
+        })
+        .collect();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(found)
+}
+
+fn find_manifest(app: &AppHandle, id: &str) -> Result<(PathBuf, PluginManifest), String> {
+    discover(app)?
+        .into_iter()
+        .find_map(|(dir, manifest)| manifest.ok().filter(|m| m.id == id).map(|m| (dir, m)))
+        .ok_or_else(|| format!("Plugin not found: {}", id))
+}
+
+/// How a plugin is started
+enum Launch {
+    Wasm(PathBuf),
+    Process(Command),
+}
+
+/// Resolves the plugin's entry point and how to run it
+fn plugin_launch(dir: &Path, manifest: &PluginManifest) -> Result<Launch, String> {
+    let main = dir.join(&manifest.main);
+    let main = fs::canonicalize(&main).map_err(|e| format!("Failed to find {}: {}", manifest.main, e))?;
+    if !main.starts_with(fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())) {
+        return Err("Plugin entry point must be inside the plugin folder".to_string());
+    }
+    let extension = main.extension().map(|e| e.to_string_lossy().to_lowercase());
+    let runtime = manifest.runtime.as_deref().unwrap_or(match extension.as_deref() {
+        Some("wasm") => "wasm",
+        Some("js" | "mjs" | "cjs") => "node",
+        Some("py") => "python",
+        _ => "native",
+    });
+
+    let mut command = match runtime {
+        "wasm" => return Ok(Launch::Wasm(main)),
+        "node" => {
+            let mut command = Command::new("node");
+            command.arg(&main);
+            command
+        }
+        "python" => {
+            let mut command = Command::new(if cfg!(windows) { "python" } else { "python3" });
+            command.arg(&main);
+            command
+        }
+        "native" => Command::new(&main),
+        other => return Err(format!("Unknown plugin runtime \"{}\"", other)),
+    };
+
+    // Keeps the editor's secrets out of the environment. This is no sandbox:
+    // the process can still do anything the user can, which is why it needs
+    // `command_policy` approval.
+    command.env_clear().current_dir(dir).env("TMD_PLUGIN_ID", &manifest.id);
+    for key in ["PATH", "SYSTEMROOT", "TEMP", "TMP", "LANG"] {
+        if let Some(value) = std::env::var_os(key) {
+            command.env(key, value);
+        }
+    }
+    Ok(Launch::Process(command))
+}
+
+/// Resolves `path` and checks it lies in an open workspace folder or the plugin's own folder
+fn readable_path(app: &AppHandle, plugin: &PluginProcess, path: &str) -> Result<PathBuf, String> {
+    let resolved = fs::canonicalize(plugin.dir.join(path)).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if resolved.starts_with(fs::canonicalize(&plugin.dir).unwrap_or_else(|_| plugin.dir.clone())) {
+        return Ok(resolved);
+    }
+    if !plugin.has_permission("fs.read") {
+        return Err("Plugin lacks the fs.read permission".to_string());
+    }
+    // The same folders and files the editor itself may open
+    app.state::<FsGuardState>()
+        .check(&resolved.to_string_lossy())
+        .map_err(|_| format!("{} is outside the open workspace", path))
+}
+
+/// Handles a request the plugin sent to the host
+fn handle_request(app: &AppHandle, plugin: &PluginProcess, method: &str, params: &Value) -> Reply {
+    let string = |key: &str| {
+        params
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| format!("Missing parameter \"{}\"", key))
+    };
+    match method {
+        "readFile" => {
+            let path = readable_path(app, plugin, &string("path")?)?;
+            if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_READ_SIZE) {
+                return Err("File is too large".to_string());
+            }
+            let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+            Ok(json!({ "content": content }))
+        }
+        "registerCommand" | "addMenuItem" => {
+            let in_menu = method == "addMenuItem";
+            if in_menu && !plugin.has_permission("menus") {
+                return Err("Plugin lacks the menus permission".to_string());
+            }
+            let id = string(if in_menu { "command" } else { "id" })?;
+            let title = string(if in_menu { "label" } else { "title" })
+                .or_else(|_| string("title"))
+                .unwrap_or_else(|_| id.clone());
+            let mut commands = plugin.commands.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+            match commands.iter_mut().find(|c| c.id == id) {
+                Some(existing) => {
+                    existing.title = title;
+                    existing.in_menu |= in_menu;
+                }
+                None => commands.push(PluginCommand { id, title, in_menu }),
+            }
+            drop(commands);
+            refresh(app);
+            Ok(Value::Null)
+        }
+        "showMessage" => {
+            let _ = app.emit(
+                "plugin-message",
+                PluginMessage {
+                    plugin_id: plugin.manifest.id.clone(),
+                    level: string("level").unwrap_or_else(|_| "info".to_string()),
+                    message: string("message")?,
+                },
+            );
+            Ok(Value::Null)
+        }
+        _ => Err(format!("Unknown method \"{}\"", method)),
+    }
+}
+
+/// Reads the plugin's stdout until it exits, routing replies and requests
+fn serve(app: AppHandle, plugin: Arc<PluginProcess>, stdout: impl std::io::Read) {
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            tracing::debug!(plugin = %plugin.manifest.id, "{}", line);
+            continue;
+        };
+        let method = message.get("method").and_then(|m| m.as_str());
+        let id = message.get("id").cloned();
+        match (method, id) {
+            (Some(method), id) => {
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                let reply = handle_request(&app, &plugin, method, &params);
+                // Notifications (no id) get no answer
+                if let Some(id) = id {
+                    let response = match reply {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32000, "message": e } }),
+                    };
+                    let _ = plugin.send(&response);
+                }
+            }
+            (None, Some(id)) => {
+                let reply = match message.get("error") {
+                    Some(error) => Err(error
+                        .get("message")
+                        .and_then(|m| m.as_str())
+                        .unwrap_or("Plugin error")
+                        .to_string()),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let sender = id
+                    .as_u64()
+                    .and_then(|id| plugin.pending.lock().ok().and_then(|mut p| p.remove(&id)));
+                if let Some(sender) = sender {
+                    let _ = sender.send(reply);
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    tracing::info!(plugin = %plugin.manifest.id, "Exited");
+    let state = app.state::<PluginState>();
+    if let Ok(mut running) = state.running.lock() {
+        if running.get(&plugin.manifest.id).is_some_and(|p| Arc::ptr_eq(p, &plugin)) {
+            running.remove(&plugin.manifest.id);
+        }
+    }
+    refresh(&app);
+}
+
+fn is_running(app: &AppHandle, id: &str) -> Result<bool, String> {
+    Ok(app
+        .state::<PluginState>()
+        .running
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .contains_key(id))
+}
+
+type Stdin = Box<dyn Write + Send>;
+type Output = Box<dyn Read + Send>;
+
+/// Starts `launch`, the approved entry point of the plugin in `dir`
+fn start(app: &AppHandle, dir: PathBuf, manifest: PluginManifest, launch: Launch) -> Result<(), String> {
+    let state = app.state::<PluginState>();
+    let id = manifest.id.clone();
+    let id = id.as_str();
+    if is_running(app, id)? {
+        return Ok(());
+    }
+
+    let (runner, stdin, stdout, stderr): (Runner, Stdin, Output, Option<Output>) = match launch {
+        Launch::Wasm(main) => {
+            let (module, stdio) = WasmPlugin::spawn(&main, id)?;
+            let wasm_plugin::Stdio { stdin, stdout, stderr } = stdio;
+            (Runner::Wasm(module), Box::new(stdin), Box::new(stdout), Some(Box::new(stderr)))
+        }
+        Launch::Process(mut command) => {
+            let mut child = command
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| format!("Failed to start plugin {}: {}", id, e))?;
+            let stdin = child.stdin.take().ok_or("Failed to open plugin stdin")?;
+            let stdout = child.stdout.take().ok_or("Failed to open plugin stdout")?;
+            let stderr = child.stderr.take().map(|stderr| Box::new(stderr) as Output);
+            (Runner::Process(child), Box::new(stdin), Box::new(stdout), stderr)
+        }
+    };
+    if let Some(stderr) = stderr {
+        let id = id.to_string();
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                tracing::debug!(plugin = %id, "{}", line);
+            }
+        });
+    }
+
+    let plugin = Arc::new(PluginProcess {
+        manifest,
+        dir,
+        runner: Mutex::new(runner),
+        stdin: Mutex::new(Some(stdin)),
+        next_id: AtomicU64::new(1),
+        pending: Mutex::new(HashMap::new()),
+        commands: Mutex::new(Vec::new()),
+    });
+    state
+        .running
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .insert(id.to_string(), plugin.clone());
+    let serving = plugin.clone();
+    let app_handle = app.clone();
+    thread::spawn(move || serve(app_handle, serving, stdout));
+
+    let activated = plugin.request(
+        "activate",
+        json!({ "pluginId": id, "hostVersion": app.package_info().version.to_string() }),
+    );
+    if let Err(e) = activated {
+        stop(app, id)?;
+        return Err(format!("Plugin {} failed to activate: {}", id, e));
+    }
+    refresh(app);
+    Ok(())
+}
+
+fn stop(app: &AppHandle, id: &str) -> Result<(), String> {
+    let state = app.state::<PluginState>();
+    let plugin = state
+        .running
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .remove(id);
+    if let Some(plugin) = plugin {
+        let _ = plugin.send(&json!({ "jsonrpc": "2.0", "method": "deactivate" }));
+        if let Ok(mut stdin) = plugin.stdin.lock() {
+            stdin.take();
+        }
+        if let Ok(mut runner) = plugin.runner.lock() {
+            runner.kill();
+        }
+    }
+    refresh(app);
+    Ok(())
+}
+
+/// Stops every plugin so none outlive the editor
+pub fn stop_all(app: &AppHandle) {
+    let ids: Vec<String> = match app.state::<PluginState>().running.lock() {
+        Ok(running) => running.keys().cloned().collect(),
+        Err(_) => return,
+    };
+    for id in ids {
+        let _ = stop(app, &id);
+    }
+}
+
+fn running_plugin(app: &AppHandle, id: &str) -> Result<Arc<PluginProcess>, String> {
+    app.state::<PluginState>()
+        .running
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .get(id)
+        .cloned()
+        .ok_or_else(|| format!("Plugin {} is not running", id))
+}
+
+fn execute(app: &AppHandle, plugin_id: &str, command: &str, args: Value) -> Reply {
+    let plugin = running_plugin(app, plugin_id)?;
+    let known = plugin
+        .commands
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .iter()
+        .any(|c| c.id == command);
+    if !known {
+        return Err(format!("Plugin {} has no command {}", plugin_id, command));
+    }
+    plugin.request("executeCommand", json!({ "command": command, "args": args }))
+}
+
+fn list(app: &AppHandle) -> Result<Vec<PluginInfo>, String> {
+    let running = app
+        .state::<PluginState>()
+        .running
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .clone();
+    Ok(discover(app)?
+        .into_iter()
+        .map(|(dir, manifest)| {
+            let path = dir.to_string_lossy().to_string();
+            match manifest {
+                Ok(manifest) => {
+                    let process = running.get(&manifest.id);
+                    PluginInfo {
+                        running: process.is_some(),
+                        commands: process
+                            .and_then(|p| p.commands.lock().ok().map(|c| c.clone()))
+                            .unwrap_or_default(),
+                        id: manifest.id,
+                        name: manifest.name,
+                        version: manifest.version,
+                        description: manifest.description,
+                        path,
+                        permissions: manifest.permissions,
+                        error: None,
+                    }
+                }
+                Err(e) => PluginInfo {
+                    id: dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                    name: String::new(),
+                    version: String::new(),
+                    description: None,
+                    path,
+                    permissions: Vec::new(),
+                    running: false,
+                    commands: Vec::new(),
+                    error: Some(e),
+                },
+            }
+        })
+        .collect())
+}
+
+/// Creates the Plugins submenu and remembers it so it can be refreshed later
+pub fn build_menu(app: &AppHandle) -> tauri::Result<Submenu<Wry>> {
+    let submenu = SubmenuBuilder::new(app, "Plugins").build()?;
+    fill_menu(app, &submenu)?;
+    if let Ok(mut menu) = app.state::<PluginState>().menu.lock() {
+        *menu = Some(submenu.clone());
+    }
+    Ok(submenu)
+}
+
+fn fill_menu(app: &AppHandle, submenu: &Submenu<Wry>) -> tauri::Result<()> {
+    for item in submenu.items()? {
+        submenu.remove(&item)?;
+    }
+    let plugins: Vec<Arc<PluginProcess>> = match app.state::<PluginState>().running.lock() {
+        Ok(running) => running.values().cloned().collect(),
+        Err(_) => Vec::new(),
+    };
+    let mut empty = true;
+    for plugin in plugins {
+        let commands = plugin.commands.lock().map(|c| c.clone()).unwrap_or_default();
+        for command in commands.iter().filter(|c| c.in_menu) {
+            let id = format!("{}{}:{}", PLUGIN_MENU_PREFIX, plugin.manifest.id, command.id);
+            submenu.append(&MenuItemBuilder::with_id(id, &command.title).build(app)?)?;
+            empty = false;
+        }
+    }
+    if empty {
+        let placeholder = MenuItemBuilder::new("No Plugin Commands").enabled(false).build(app)?;
+        submenu.append(&placeholder)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the Plugins menu and tells the frontend what changed
+fn refresh(app: &AppHandle) {
+    let submenu = match app.state::<PluginState>().menu.lock() {
+        Ok(menu) => menu.clone(),
+        Err(_) => None,
+    };
+    if let Some(submenu) = submenu {
+        if let Err(e) = fill_menu(app, &submenu) {
+            tracing::warn!("Failed to update Plugins menu: {}", e);
+        }
+    }
+    if let Ok(plugins) = list(app) {
+        let _ = app.emit("plugins-changed", plugins);
+    }
+}
+
+/// Runs the plugin command behind a Plugins menu item; returns false for other items
+pub fn handle_menu_event(app: &AppHandle, id: &str) -> bool {
+    let Some((plugin_id, command)) = id.strip_prefix(PLUGIN_MENU_PREFIX).and_then(|rest| rest.split_once(':')) else {
+        return false;
+    };
+    let (app, plugin_id, command) = (app.clone(), plugin_id.to_string(), command.to_string());
+    thread::spawn(move || {
+        if let Err(e) = execute(&app, &plugin_id, &command, Value::Null) {
+            let _ = app.emit(
+                "plugin-message",
+                PluginMessage {
+                    plugin_id,
+                    level: "error".to_string(),
+                    message: e,
+                },
+            );
+        }
+    });
+    true
+}
+
+/// Plugins installed in the app data plugins folder, each a folder with a
+/// `plugin.json` manifest
+#[tauri::command]
+pub async fn list_plugins(app_handle: AppHandle) -> Result<Vec<PluginInfo>, String> {
+    list(&app_handle)
+}
+
+/// Starts a plugin and waits for it to activate. Plugins talk
+/// newline-delimited JSON-RPC on stdio.
+///
+/// Only WASI plugins are sandboxed and reach nothing but the host API. Node,
+/// Python and native plugins are ordinary programs, so `command_policy`
+/// decides whether they may run.
+#[tauri::command]
+pub async fn start_plugin(
+    app_handle: AppHandle,
+    policy_state: State<'_, CommandPolicyState>,
+    id: String,
+) -> Result<(), String> {
+    if is_running(&app_handle, &id)? {
+        return Ok(());
+    }
+    let (dir, manifest) = find_manifest(&app_handle, &id)?;
+    let launch = plugin_launch(&dir, &manifest)?;
+    if let Launch::Process(command) = &launch {
+        let program = command.get_program().to_string_lossy().to_string();
+        let args: Vec<String> = command.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        command_policy::authorize(&app_handle, &policy_state, "start_plugin", &program, &args, Some(&dir), false)
+            .await?;
+    }
+
+    tauri::async_runtime::spawn_blocking(move || start(&app_handle, dir, manifest, launch))
+        .await
+        .map_err(|e| format!("Plugin task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn stop_plugin(app_handle: AppHandle, id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || stop(&app_handle, &id))
+        .await
+        .map_err(|e| format!("Plugin task failed: {}", e))?
+}
+
+/// Runs a command a plugin registered and returns its result
+#[tauri::command]
+pub async fn execute_plugin_command(
+    app_handle: AppHandle,
+    plugin_id: String,
+    command: String,
+    args: Option<Value>,
+) -> Result<Value, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        execute(&app_handle, &plugin_id, &command, args.unwrap_or(Value::Null))
+    })
+    .await
+    .map_err(|e| format!("Plugin task failed: {}", e))?
+}
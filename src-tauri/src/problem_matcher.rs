@@ -0,0 +1,235 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemMatcherKind {
+    /// rustc and cargo: `error[E0308]: message` followed by ` --> file:line:col`
+    Rustc,
+    /// go build / go vet: `./file.go:line:col: message`
+    Go,
+    /// tsc: `file.ts(line,col): error TS1234: message` or the `--pretty` form
+    Tsc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl ProblemSeverity {
+    fn parse(text: &str) -> ProblemSeverity {
+        match text {
+            "error" => ProblemSeverity::Error,
+            "warning" => ProblemSeverity::Warning,
+            _ => ProblemSeverity::Info,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    /// Absolute when the output's path could be resolved against the working directory
+    pub path: String,
+    /// 1-based line and column
+    pub line: usize,
+    pub column: usize,
+    pub severity: ProblemSeverity,
+    pub message: String,
+    pub code: Option<String>,
+    pub matcher: ProblemMatcherKind,
+}
+
+fn ansi_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]|\x1b\][^\x07]*(?:\x07|\x1b\\)").unwrap())
+}
+
+fn rustc_header() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(error|warning)(?:\[(\w+)\])?: (.+)$").unwrap())
+}
+
+fn rustc_location() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\s*--> (.+?):(\d+):(\d+)$").unwrap())
+}
+
+fn go_line() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(\S+\.go):(\d+)(?::(\d+))?: (.+)$").unwrap())
+}
+
+fn tsc_line() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^(\S.*?\.[cm]?[jt]sx?)(?:\((\d+),(\d+)\): | ?:(\d+):(\d+) - )(error|warning|info) (TS\d+): (.+)$")
+            .unwrap()
+    })
+}
+
+/// Strips colour and other terminal escape sequences
+pub fn strip_ansi(text: &str) -> String {
+    ansi_regex().replace_all(text, "").to_string()
+}
+
+/// Matchers that fit a command, from the program name
+pub fn default_matchers(command: &str) -> Vec<ProblemMatcherKind> {
+    let program = Path::new(command.split_whitespace().next().unwrap_or(""))
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match program.as_str() {
+        "cargo" | "rustc" => vec![ProblemMatcherKind::Rustc],
+        "go" => vec![ProblemMatcherKind::Go],
+        "tsc" | "npm" | "pnpm" | "yarn" | "npx" => vec![ProblemMatcherKind::Tsc],
+        _ => Vec::new(),
+    }
+}
+
+/// Feeds output line by line and collects problems. rustc diagnostics span
+/// several lines, so the matcher keeps the pending header between calls.
+pub struct ProblemMatcher {
+    kinds: Vec<ProblemMatcherKind>,
+    cwd: PathBuf,
+    partial: String,
+    pending_rustc: Option<(ProblemSeverity, Option<String>, String)>,
+}
+
+impl ProblemMatcher {
+    pub fn new(kinds: Vec<ProblemMatcherKind>, cwd: PathBuf) -> Self {
+        Self {
+            kinds,
+            cwd,
+            partial: String::new(),
+            pending_rustc: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    fn resolve(&self, file: &str) -> String {
+        let path = Path::new(file.trim_start_matches("./"));
+        if path.is_absolute() {
+            return path.to_string_lossy().to_string();
+        }
+        // Cargo prints paths relative to the workspace root, which may be above cwd
+        self.cwd
+            .ancestors()
+            .map(|dir| dir.join(path))
+            .find(|p| p.exists())
+            .unwrap_or_else(|| self.cwd.join(path))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn match_line(&mut self, line: &str) -> Option<Problem> {
+        for kind in self.kinds.clone() {
+            match kind {
+                ProblemMatcherKind::Rustc => {
+                    if let Some(caps) = rustc_header().captures(line) {
+                        // "warning: 3 warnings emitted" and friends have no location and are
+                        // simply replaced by the next header
+                        self.pending_rustc = Some((
+                            ProblemSeverity::parse(&caps[1]),
+                            caps.get(2).map(|m| m.as_str().to_string()),
+                            caps[3].to_string(),
+                        ));
+                        return None;
+                    }
+                    if let Some(caps) = rustc_location().captures(line) {
+                        if let Some((severity, code, message)) = self.pending_rustc.take() {
+                            return Some(Problem {
+                                path: self.resolve(&caps[1]),
+                                line: caps[2].parse().unwrap_or(1),
+                                column: caps[3].parse().unwrap_or(1),
+                                severity,
+                                message,
+                                code,
+                                matcher: kind,
+                            });
+                        }
+                    }
+                }
+                ProblemMatcherKind::Go => {
+                    if let Some(caps) = go_line().captures(line) {
+                        return Some(Problem {
+                            path: self.resolve(&caps[1]),
+                            line: caps[2].parse().unwrap_or(1),
+                            column: caps.get(3).and_then(|m| m.as_str().parse().ok()).unwrap_or(1),
+                            severity: ProblemSeverity::Error,
+                            message: caps[4].to_string(),
+                            code: None,
+                            matcher: kind,
+                        });
+                    }
+                }
+                ProblemMatcherKind::Tsc => {
+                    if let Some(caps) = tsc_line().captures(line) {
+                        let number = |a: usize, b: usize| {
+                            caps.get(a)
+                                .or(caps.get(b))
+                                .and_then(|m| m.as_str().parse().ok())
+                                .unwrap_or(1)
+                        };
+                        return Some(Problem {
+                            path: self.resolve(caps[1].trim()),
+                            line: number(2, 4),
+                            column: number(3, 5),
+                            severity: ProblemSeverity::parse(&caps[6]),
+                            message: caps[8].to_string(),
+                            code: Some(caps[7].to_string()),
+                            matcher: kind,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Consumes a chunk of output; an incomplete last line is kept for the next chunk
+    pub fn feed(&mut self, chunk: &str) -> Vec<Problem> {
+        self.partial.push_str(chunk);
+        let Some(end) = self.partial.rfind('\n') else {
+            return Vec::new();
+        };
+        let complete: String = self.partial.drain(..=end).collect();
+        complete
+            .lines()
+            .filter_map(|line| self.match_line(strip_ansi(line).trim_end_matches('\r')))
+            .collect()
+    }
+
+    /// Matches whatever is left once the output has ended
+    pub fn finish(&mut self) -> Vec<Problem> {
+        let rest = std::mem::take(&mut self.partial);
+        let problems = self.feed(&format!("{}\n", rest));
+        self.pending_rustc = None;
+        problems
+    }
+}
+
+/// Parses a block of compiler output, e.g. text selected in a terminal
+#[tauri::command]
+pub async fn match_problems(
+    output: String,
+    cwd: String,
+    matchers: Option<Vec<ProblemMatcherKind>>,
+) -> Result<Vec<Problem>, String> {
+    let kinds = matchers.unwrap_or_else(|| {
+        vec![ProblemMatcherKind::Rustc, ProblemMatcherKind::Go, ProblemMatcherKind::Tsc]
+    });
+    let mut matcher = ProblemMatcher::new(kinds, PathBuf::from(cwd));
+    let mut problems = matcher.feed(&output);
+    problems.extend(matcher.finish());
+    Ok(problems)
+}
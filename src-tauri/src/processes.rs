@@ -0,0 +1,234 @@
+//! Processes started by the editor (terminal shells, language servers,
+//! tasks, commands) and everything they started in turn, with a way to end
+//! a whole subtree such as a stuck build. The process table comes from `ps`
+//! on Unix and CIM (through PowerShell) on Windows.
+
+use std::collections::HashMap;
+use std::process::Stdio;
+#[cfg(unix)]
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::process::Command;
+
+/// How long a tree gets to exit after SIGTERM before it is killed
+#[cfg(unix)]
+const TERM_GRACE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChildProcess {
+    pub pid: u32,
+    pub parent_pid: u32,
+    /// 1 for the editor's direct children
+    pub depth: usize,
+    pub command: String,
+    /// Not available on Windows
+    pub cpu_percent: Option<f32>,
+    pub memory_bytes: Option<u64>,
+}
+
+/// One row of the system process table
+struct ProcessRow {
+    pid: u32,
+    parent_pid: u32,
+    command: String,
+    cpu_percent: Option<f32>,
+    memory_bytes: Option<u64>,
+}
+
+#[cfg(unix)]
+async fn process_table() -> Result<Vec<ProcessRow>, String> {
+    let child = Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid=,pcpu=,rss=,args="])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run ps: {}", e))?;
+    // The listing shouldn't include ps itself
+    let own_pid = child.id();
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to run ps: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("ps failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok().filter(|pid| Some(*pid) != own_pid)?;
+            let parent_pid = fields.next()?.parse().ok()?;
+            let cpu_percent = fields.next()?.parse().ok();
+            // Resident set size in KiB
+            let memory_bytes = fields.next()?.parse::<u64>().ok().map(|kib| kib * 1024);
+            Some(ProcessRow {
+                pid,
+                parent_pid,
+                command: fields.collect::<Vec<_>>().join(" "),
+                cpu_percent,
+                memory_bytes,
+            })
+        })
+        .collect())
+}
+
+#[cfg(windows)]
+async fn process_table() -> Result<Vec<ProcessRow>, String> {
+    let script = "Get-CimInstance Win32_Process | ForEach-Object { \"$($_.ProcessId)`t$($_.ParentProcessId)`t$($_.WorkingSetSize)`t$($_.Name)`t$($_.CommandLine)\" }";
+    let child = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+    // The listing shouldn't include PowerShell itself
+    let own_pid = child.id();
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Listing processes failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim_end_matches('\r').splitn(5, '\t');
+            let pid = fields.next()?.parse().ok().filter(|pid| Some(*pid) != own_pid)?;
+            let parent_pid = fields.next()?.parse().ok()?;
+            let memory_bytes = fields.next()?.parse().ok();
+            let name = fields.next().unwrap_or_default();
+            // CommandLine is empty for processes of other users
+            let command = fields.next().filter(|c| !c.trim().is_empty()).unwrap_or(name);
+            Some(ProcessRow {
+                pid,
+                parent_pid,
+                command: command.trim().to_string(),
+                cpu_percent: None,
+                memory_bytes,
+            })
+        })
+        .collect())
+}
+
+/// Descendants of `root` in depth-first order, children sorted by PID
+fn descendants(table: Vec<ProcessRow>, root: u32) -> Vec<ChildProcess> {
+    fn walk(pid: u32, depth: usize, children: &mut HashMap<u32, Vec<ProcessRow>>, out: &mut Vec<ChildProcess>) {
+        // Removed as visited, so a PID reused as its own ancestor can't loop
+        let Some(list) = children.remove(&pid) else {
+            return;
+        };
+        for row in list {
+            let child = row.pid;
+            out.push(ChildProcess {
+                pid: row.pid,
+                parent_pid: row.parent_pid,
+                depth: depth + 1,
+                command: row.command,
+                cpu_percent: row.cpu_percent,
+                memory_bytes: row.memory_bytes,
+            });
+            walk(child, depth + 1, children, out);
+        }
+    }
+
+    let mut children: HashMap<u32, Vec<ProcessRow>> = HashMap::new();
+    for row in table {
+        // PID 0 is its own parent on some systems
+        if row.pid != row.parent_pid {
+            children.entry(row.parent_pid).or_default().push(row);
+        }
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|row| row.pid);
+    }
+    let mut out = Vec::new();
+    walk(root, 0, &mut children, &mut out);
+    out
+}
+
+/// Resident memory of `pid`, e.g. for a language server's status
+pub(crate) async fn memory_usage(pid: u32) -> Option<u64> {
+    let table = process_table().await.ok()?;
+    table.into_iter().find(|row| row.pid == pid)?.memory_bytes
+}
+
+#[cfg(unix)]
+async fn signal(pids: &[u32], signal: &str) {
+    let _ = Command::new("kill")
+        .arg(format!("-{}", signal))
+        .args(pids.iter().map(u32::to_string))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+}
+
+/// Every process the editor started, directly or indirectly, as a tree in
+/// depth-first order (`depth` gives the nesting)
+#[tauri::command]
+pub async fn list_child_processes() -> Result<Vec<ChildProcess>, String> {
+    Ok(descendants(process_table().await?, std::process::id()))
+}
+
+/// Ends `pid` and all of its descendants; returns the PIDs that were
+/// signalled. Only processes started by the editor can be ended.
+#[tauri::command]
+pub async fn kill_process_tree(pid: u32) -> Result<Vec<u32>, String> {
+    let tree = descendants(process_table().await?, std::process::id());
+    let Some(index) = tree.iter().position(|p| p.pid == pid) else {
+        return Err(format!("Process {} was not started by the editor", pid));
+    };
+    // The subtree is the entries after it that are nested deeper
+    let depth = tree[index].depth;
+    let pids: Vec<u32> = std::iter::once(pid)
+        .chain(tree[index + 1..].iter().take_while(|p| p.depth > depth).map(|p| p.pid))
+        .collect();
+
+    #[cfg(unix)]
+    {
+        // Deepest first, so parents don't respawn or reap what was just ended
+        let order: Vec<u32> = pids.iter().rev().copied().collect();
+        signal(&order, "TERM").await;
+        let deadline = tokio::time::Instant::now() + TERM_GRACE;
+        while tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let table = process_table().await?;
+            if !table.iter().any(|row| pids.contains(&row.pid)) {
+                return Ok(pids);
+            }
+        }
+        let table = process_table().await?;
+        let remaining: Vec<u32> = order
+            .into_iter()
+            .filter(|p| table.iter().any(|r| r.pid == *p))
+            .collect();
+        if !remaining.is_empty() {
+            signal(&remaining, "KILL").await;
+        }
+    }
+    #[cfg(windows)]
+    {
+        let output = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run taskkill: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "taskkill failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    Ok(pids)
+}
@@ -1,52 +1,216 @@
-use portable_pty::{native_pty_system, CommandBuilder, PtySize, Child};
+use portable_pty::{native_pty_system, CommandBuilder, PtySize, Child, ExitStatus, MasterPty};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tauri::{AppHandle, Emitter};
 
+use crate::error::AppError;
+use crate::lsp::registry::find_executable;
+use crate::remote;
+use crate::shell_env;
+
+pub const DEFAULT_SCROLLBACK_LINES: usize = 10_000;
+// A "line" that never sees a newline (progress bars, full-screen apps) is cut here
+const MAX_PARTIAL_LINE_LEN: usize = 64 * 1024;
+
+/// How to launch the shell and size its scrollback, usually taken from the user's settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TerminalOptions {
+    /// Shell executable; defaults to `$SHELL` (or powershell on Windows)
+    pub shell: Option<String>,
+    /// Extra arguments passed after the login flag
+    pub args: Vec<String>,
+    /// Start a login shell; defaults to true except on Windows
+    pub login: Option<bool>,
+    pub env: HashMap<String, String>,
+    /// Lines kept for `get_terminal_scrollback`; defaults to `DEFAULT_SCROLLBACK_LINES`
+    pub scrollback_lines: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShellInfo {
+    pub name: String,
+    pub path: String,
+}
+
+/// How a terminal's shell exited, emitted on `terminal-exit-{id}`
+#[derive(Debug, Clone, Serialize)]
+pub struct TerminalExit {
+    /// None if the status could not be collected
+    pub exit_code: Option<u32>,
+    /// Name of the signal that terminated the process (Unix only)
+    pub signal: Option<String>,
+    pub success: bool,
+}
+
+impl TerminalExit {
+    fn from_status(status: Option<&ExitStatus>) -> Self {
+        // portable-pty only exposes the signal through Display ("Terminated by ...")
+        let signal = status.and_then(|s| {
+            s.to_string()
+                .strip_prefix("Terminated by ")
+                .map(|sig| sig.to_string())
+        });
+        Self {
+            exit_code: status.map(|s| s.exit_code()),
+            signal,
+            success: status.is_some_and(|s| s.success()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TerminalStatus {
+    pub running: bool,
+    /// Set once the shell has exited
+    pub exit: Option<TerminalExit>,
+}
+
+fn default_shell() -> String {
+    if cfg!(target_os = "windows") {
+        "powershell.exe".to_string()
+    } else {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+    }
+}
+
+/// The flag that makes `shell` a login shell, if it has one
+fn login_flag(shell: &str) -> Option<&'static str> {
+    let name = Path::new(shell)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match name.as_str() {
+        "cmd" | "powershell" => None,
+        // PowerShell only supports -Login on Unix
+        "pwsh" if cfg!(target_os = "windows") => None,
+        "pwsh" => Some("-Login"),
+        _ if cfg!(target_os = "windows") => None,
+        _ => Some("-l"),
+    }
+}
+
+/// The most recent output of a terminal, bounded by line count
+struct Scrollback {
+    lines: VecDeque<String>,
+    current: String,
+    max_lines: usize,
+}
+
+impl Scrollback {
+    fn new(max_lines: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            current: String::new(),
+            max_lines,
+        }
+    }
+
+    fn push(&mut self, output: &str) {
+        for segment in output.split_inclusive('\n') {
+            self.current.push_str(segment);
+            if segment.ends_with('\n') || self.current.len() >= MAX_PARTIAL_LINE_LEN {
+                self.lines.push_back(std::mem::take(&mut self.current));
+                while self.lines.len() > self.max_lines {
+                    self.lines.pop_front();
+                }
+            }
+        }
+    }
+
+    fn contents(&self) -> String {
+        let mut out = String::with_capacity(self.lines.iter().map(|l| l.len()).sum::<usize>() + self.current.len());
+        for line in &self.lines {
+            out.push_str(line);
+        }
+        out.push_str(&self.current);
+        out
+    }
+}
+
 pub struct PtySession {
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
     child: Arc<Mutex<Box<dyn Child + Send>>>,
+    // Kept alive so the PTY can be resized after spawning
+    master: Arc<Mutex<Box<dyn MasterPty + Send>>>,
+    scrollback: Arc<Mutex<Scrollback>>,
+    exit: Arc<Mutex<Option<TerminalExit>>>,
 }
 
 impl PtySession {
-    pub fn new(app_handle: AppHandle, terminal_id: String, working_dir: Option<String>) -> Result<Self, String> {
+    pub fn new(
+        app_handle: AppHandle,
+        terminal_id: String,
+        working_dir: Option<String>,
+        rows: Option<u16>,
+        cols: Option<u16>,
+        options: TerminalOptions,
+    ) -> Result<Self, AppError> {
         let pty_system = native_pty_system();
         
-        // Create a new PTY with default size
+        // Create a new PTY with the requested size, falling back to 24x80
         let pair = pty_system
             .openpty(PtySize {
-                rows: 24,
-                cols: 80,
+                rows: rows.unwrap_or(24),
+                cols: cols.unwrap_or(80),
                 pixel_width: 0,
                 pixel_height: 0,
             })
             .map_err(|e| format!("Failed to create PTY: {}", e))?;
 
-        // Get the default shell based on OS
-        let shell = if cfg!(target_os = "windows") {
-            "powershell.exe".to_string()
-        } else {
-            std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
-        };
+        let mut cmd = match working_dir.as_deref().filter(|dir| remote::is_remote(dir)) {
+            // Remote workspaces get the remote user's login shell over `ssh -t`
+            Some(dir) => {
+                let remote = remote::parse(dir)?;
+                let mut cmd = CommandBuilder::new("ssh");
+                cmd.arg("-t");
+                cmd.arg(&remote.destination);
+                cmd.arg(format!("cd {} && exec \"$SHELL\" -l", remote::shell_quote(&remote.path)));
+                cmd
+            }
+            None => {
+                let shell = options
+                    .shell
+                    .filter(|s| !s.trim().is_empty())
+                    .unwrap_or_else(default_shell);
 
-        let mut cmd = CommandBuilder::new(&shell);
-        
-        // Add login shell flag to load .zprofile, .zshrc, etc.
-        if !cfg!(target_os = "windows") {
-            cmd.arg("-l");  // Login shell flag
-        }
-        
-        // Set working directory if provided
-        if let Some(dir) = working_dir {
-            cmd.cwd(dir);
+                let mut cmd = CommandBuilder::new(&shell);
+                for (key, value) in shell_env::environment() {
+                    cmd.env(key, value);
+                }
+
+                // Login shells load .zprofile, .bash_profile, etc.
+                if options.login.unwrap_or(!cfg!(target_os = "windows")) {
+                    if let Some(flag) = login_flag(&shell) {
+                        cmd.arg(flag);
+                    }
+                }
+                cmd.args(&options.args);
+
+                // Set working directory if provided
+                if let Some(dir) = working_dir {
+                    cmd.cwd(dir);
+                }
+                cmd
+            }
+        };
+        for (key, value) in &options.env {
+            cmd.env(key, value);
         }
 
         // Spawn the shell in the PTY
+        // A missing shell or working directory surfaces as an io::Error inside
         let child = pair
             .slave
             .spawn_command(cmd)
-            .map_err(|e| format!("Failed to spawn shell: {}", e))?;
+            .map_err(|e| match e.downcast::<std::io::Error>() {
+                Ok(e) => AppError::io("Failed to spawn shell", e),
+                Err(e) => AppError::from(format!("Failed to spawn shell: {}", e)),
+            })?;
 
         let child: Arc<Mutex<Box<dyn Child + Send>>> = Arc::new(Mutex::new(child));
 
@@ -55,6 +219,14 @@ impl PtySession {
         let writer = pair.master.take_writer().map_err(|e| format!("Failed to get writer: {}", e))?;
 
         let writer = Arc::new(Mutex::new(writer));
+        let master = Arc::new(Mutex::new(pair.master));
+        let scrollback = Arc::new(Mutex::new(Scrollback::new(
+            options.scrollback_lines.unwrap_or(DEFAULT_SCROLLBACK_LINES),
+        )));
+        let scrollback_for_reader = scrollback.clone();
+        let exit = Arc::new(Mutex::new(None));
+        let exit_for_reader = exit.clone();
+        let child_for_reader = child.clone();
 
         // Start thread to read from PTY and emit to frontend
         // This will also detect when the shell exits (EOF)
@@ -63,42 +235,110 @@ impl PtySession {
             
             loop {
                 match reader.read(&mut buffer) {
-                    Ok(0) => {
-                        // EOF - shell has exited
-                        let _ = app_handle.emit(&format!("terminal-exit-{}", terminal_id), ());
-                        break;
-                    }
+                    // EOF - shell has exited
+                    Ok(0) => break,
                     Ok(n) => {
                         // Convert bytes to string (UTF-8 lossy conversion for safety)
                         let output = String::from_utf8_lossy(&buffer[..n]).to_string();
+                        if let Ok(mut scrollback) = scrollback_for_reader.lock() {
+                            scrollback.push(&output);
+                        }
                         let _ = app_handle.emit(&format!("terminal-output-{}", terminal_id), output);
                     }
-                    Err(_) => {
-                        // Error reading - shell has probably exited
-                        let _ = app_handle.emit(&format!("terminal-exit-{}", terminal_id), ());
-                        break;
-                    }
+                    // Error reading - shell has probably exited
+                    Err(_) => break,
                 }
             }
+
+            // Reap the shell so its exit code can be reported
+            let status = child_for_reader
+                .lock()
+                .ok()
+                .and_then(|mut child| child.wait().ok());
+            let status = TerminalExit::from_status(status.as_ref());
+            if let Ok(mut exit) = exit_for_reader.lock() {
+                *exit = Some(status.clone());
+            }
+            let _ = app_handle.emit(&format!("terminal-exit-{}", terminal_id), status);
         });
 
-        Ok(Self { writer, child })
+        Ok(Self { writer, child, master, scrollback, exit })
     }
 
-    pub fn write(&self, data: &str) -> Result<(), String> {
+    pub fn write(&self, data: &str) -> Result<(), AppError> {
         let mut writer = self.writer.lock().map_err(|e| format!("Failed to lock writer: {}", e))?;
         writer
             .write_all(data.as_bytes())
-            .map_err(|e| format!("Failed to write to PTY: {}", e))?;
+            .map_err(|e| AppError::io("Failed to write to PTY", e))?;
         writer
             .flush()
-            .map_err(|e| format!("Failed to flush PTY: {}", e))?;
+            .map_err(|e| AppError::io("Failed to flush PTY", e))?;
         Ok(())
     }
 
-    pub fn kill(&self) -> Result<(), String> {
+    pub fn resize(&self, rows: u16, cols: u16) -> Result<(), AppError> {
+        let master = self.master.lock().map_err(|e| format!("Failed to lock master: {}", e))?;
+        master
+            .resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| format!("Failed to resize PTY: {}", e))?;
+        Ok(())
+    }
+
+    /// Everything still held in the scrollback buffer, oldest output first
+    pub fn scrollback(&self) -> Result<String, AppError> {
+        let scrollback = self.scrollback.lock().map_err(|e| format!("Failed to lock scrollback: {}", e))?;
+        Ok(scrollback.contents())
+    }
+
+    pub fn status(&self) -> Result<TerminalStatus, AppError> {
+        let exit = self.exit.lock().map_err(|e| format!("Failed to lock exit status: {}", e))?;
+        Ok(TerminalStatus {
+            running: exit.is_none(),
+            exit: exit.clone(),
+        })
+    }
+
+    pub fn kill(&self) -> Result<(), AppError> {
         let mut child = self.child.lock().map_err(|e| format!("Failed to lock child: {}", e))?;
-        child.kill().map_err(|e| format!("Failed to kill child process: {}", e))?;
+        child.kill().map_err(|e| AppError::io("Failed to kill child process", e))?;
         Ok(())
     }
 }
+
+/// Shells found on this machine, the default one first
+#[tauri::command]
+pub async fn list_available_shells() -> Result<Vec<ShellInfo>, AppError> {
+    let candidates: &[&str] = if cfg!(target_os = "windows") {
+        &["pwsh", "powershell", "cmd", "bash"]
+    } else {
+        &["bash", "zsh", "fish", "pwsh", "sh"]
+    };
+
+    let mut shells: Vec<ShellInfo> = Vec::new();
+    let mut add = |path: &Path| {
+        let path_str = path.to_string_lossy().to_string();
+        if shells.iter().any(|s| s.path == path_str) {
+            return;
+        }
+        let name = path
+            .file_stem()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path_str.clone());
+        shells.push(ShellInfo { name, path: path_str });
+    };
+
+    if let Some(path) = find_executable(&default_shell()) {
+        add(&path);
+    }
+    for name in candidates {
+        if let Some(path) = find_executable(name) {
+            add(&path);
+        }
+    }
+    Ok(shells)
+}
@@ -0,0 +1,198 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::menu::{MenuItemBuilder, PredefinedMenuItem, Submenu, SubmenuBuilder};
+use tauri::{AppHandle, Emitter, Manager, State, Wry};
+use tauri_plugin_store::StoreExt;
+
+use crate::file_info::system_time_ms;
+use crate::fs_guard::{self, FsGuardState};
+
+// Same store the frontend used before recents moved to the backend
+const RECENTS_STORE: &str = "recent-files.json";
+const RECENTS_KEY: &str = "items";
+const MAX_RECENT_ITEMS: usize = 10;
+
+pub const RECENT_ITEM_PREFIX: &str = "open-recent-";
+pub const CLEAR_RECENTS_ID: &str = "clear-recents";
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind {
+    File,
+    Folder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentItem {
+    pub path: String,
+    pub name: String,
+    pub is_directory: bool,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+/// The File > Open Recent submenu, rebuilt whenever the list changes
+#[derive(Default)]
+pub struct RecentMenu(Mutex<Option<Submenu<Wry>>>);
+
+/// Most recent first
+pub fn load(app: &AppHandle) -> Vec<RecentItem> {
+    let store = match app.store(RECENTS_STORE) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Failed to open {}: {}", RECENTS_STORE, e);
+            return Vec::new();
+        }
+    };
+    let mut items: Vec<RecentItem> = store
+        .get(RECENTS_KEY)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    items.sort_by_key(|i| std::cmp::Reverse(i.timestamp));
+    items.truncate(MAX_RECENT_ITEMS);
+    items
+}
+
+fn save(app: &AppHandle, items: &[RecentItem]) -> Result<(), String> {
+    let store = app
+        .store(RECENTS_STORE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+    let value = serde_json::to_value(items).map_err(|e| format!("Failed to serialize recents: {}", e))?;
+    store.set(RECENTS_KEY, value);
+    store.save().map_err(|e| format!("Failed to save store: {}", e))?;
+
+    if let Err(e) = refresh_menu(app, items) {
+        tracing::warn!("Failed to update Open Recent menu: {}", e);
+    }
+    let _ = app.emit("recents-changed", items);
+    Ok(())
+}
+
+/// Creates the Open Recent submenu and remembers it so it can be refreshed later
+pub fn build_menu(app: &AppHandle) -> tauri::Result<Submenu<Wry>> {
+    let submenu = SubmenuBuilder::new(app, "Open Recent").build()?;
+    fill_menu(app, &submenu, &load(app))?;
+    if let Ok(mut menu) = app.state::<RecentMenu>().0.lock() {
+        *menu = Some(submenu.clone());
+    }
+    Ok(submenu)
+}
+
+fn fill_menu(app: &AppHandle, submenu: &Submenu<Wry>, items: &[RecentItem]) -> tauri::Result<()> {
+    for item in submenu.items()? {
+        submenu.remove(&item)?;
+    }
+
+    for (i, recent) in items.iter().enumerate() {
+        let entry = MenuItemBuilder::with_id(format!("{}{}", RECENT_ITEM_PREFIX, i), &recent.path).build(app)?;
+        submenu.append(&entry)?;
+    }
+    if !items.is_empty() {
+        submenu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    let clear = MenuItemBuilder::with_id(CLEAR_RECENTS_ID, "Clear Recently Opened")
+        .enabled(!items.is_empty())
+        .build(app)?;
+    submenu.append(&clear)
+}
+
+fn refresh_menu(app: &AppHandle, items: &[RecentItem]) -> tauri::Result<()> {
+    let submenu = match app.state::<RecentMenu>().0.lock() {
+        Ok(menu) => menu.clone(),
+        Err(_) => None,
+    };
+    match submenu {
+        Some(submenu) => fill_menu(app, &submenu, items),
+        None => Ok(()),
+    }
+}
+
+/// Opening a recent entry is the user choosing it again, like in a dialog.
+/// Entries only get into the list through `add_recent`, which requires
+/// access to them already.
+fn grant(app: &AppHandle, item: &RecentItem) {
+    let path = Path::new(&item.path);
+    if item.is_directory {
+        fs_guard::grant_folder(app, path);
+    } else {
+        fs_guard::grant_file(app, path);
+    }
+}
+
+/// Handles a click on an Open Recent entry; returns false for other menu IDs
+pub fn handle_menu_event(app: &AppHandle, id: &str) -> bool {
+    if id == CLEAR_RECENTS_ID {
+        if let Err(e) = save(app, &[]) {
+            tracing::warn!("{}", e);
+        }
+        return true;
+    }
+
+    let index = match id.strip_prefix(RECENT_ITEM_PREFIX).and_then(|i| i.parse::<usize>().ok()) {
+        Some(i) => i,
+        None => return false,
+    };
+    if let Some(item) = load(app).into_iter().nth(index) {
+        grant(app, &item);
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.emit("menu-open-recent", item);
+        }
+    }
+    true
+}
+
+/// Only paths the app may already access can be added
+#[tauri::command]
+pub async fn add_recent(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    path: String,
+    kind: RecentKind,
+) -> Result<Vec<RecentItem>, String> {
+    guard.check(&path)?;
+    let name = Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    let item = RecentItem {
+        path: path.clone(),
+        name,
+        is_directory: kind == RecentKind::Folder,
+        timestamp: system_time_ms(Ok(std::time::SystemTime::now())).unwrap_or(0),
+    };
+
+    let mut items = load(&app_handle);
+    items.retain(|i| i.path != path);
+    items.insert(0, item);
+    items.truncate(MAX_RECENT_ITEMS);
+    save(&app_handle, &items)?;
+    Ok(items)
+}
+
+/// Grants access to the recent entry at `path` for a click in the sidebar's
+/// list; the native menu does the same in `handle_menu_event`
+#[tauri::command]
+pub async fn open_recent(app_handle: AppHandle, path: String) -> Result<RecentItem, String> {
+    let item = load(&app_handle)
+        .into_iter()
+        .find(|i| i.path == path)
+        .ok_or_else(|| format!("{} is not a recent item", path))?;
+    if !Path::new(&item.path).exists() {
+        return Err(format!("File or folder does not exist: {}", item.path));
+    }
+    grant(&app_handle, &item);
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn get_recents(app_handle: AppHandle) -> Result<Vec<RecentItem>, String> {
+    Ok(load(&app_handle))
+}
+
+#[tauri::command]
+pub async fn clear_recents(app_handle: AppHandle) -> Result<(), String> {
+    save(&app_handle, &[])
+}
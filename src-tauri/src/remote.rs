@@ -0,0 +1,504 @@
+//! Workspaces on other machines, addressed as `ssh://[user@]host[:port]/path`.
+//! Files go over SFTP on a connection shared per host, made with `russh`;
+//! terminals run the system `ssh` client in a PTY (see `pty.rs`).
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use russh::client::{self, Handle};
+use russh::keys::agent::client::AgentClient;
+use russh::keys::{self, HashAlg, PrivateKeyWithHashAlg, PublicKeyOrCertificate};
+use russh::Disconnect;
+use russh_sftp::client::error::Error as SftpError;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::{OpenFlags, StatusCode};
+use tauri::State;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+use crate::file_info::{sha256_hex, system_time_ms};
+use crate::file_version::{FileVersion, SaveError};
+use crate::FileEntry;
+
+const SCHEME: &str = "ssh://";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+// Keys tried when neither the agent nor ~/.ssh/config offers one
+const DEFAULT_KEYS: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// A path on a remote host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemotePath {
+    /// `ssh://[user@]host[:port]`, the key connections are shared under
+    pub destination: String,
+    /// Absolute path on the remote host
+    pub path: String,
+}
+
+impl RemotePath {
+    pub fn url(&self) -> String {
+        format!("{}{}", self.destination, self.path)
+    }
+
+    fn join(&self, name: &str) -> RemotePath {
+        RemotePath {
+            destination: self.destination.clone(),
+            path: format!("{}/{}", self.path.trim_end_matches('/'), name),
+        }
+    }
+}
+
+pub fn is_remote(path: &str) -> bool {
+    path.starts_with(SCHEME)
+}
+
+pub fn parse(path: &str) -> Result<RemotePath, String> {
+    let rest = path
+        .strip_prefix(SCHEME)
+        .ok_or_else(|| format!("Not an ssh:// path: {}", path))?;
+    let (authority, remote) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() || authority.ends_with('@') || authority.ends_with(':') {
+        return Err(format!("Missing host in {}", path));
+    }
+    Ok(RemotePath {
+        destination: format!("{}{}", SCHEME, authority),
+        path: remote.to_string(),
+    })
+}
+
+/// Quotes `s` for a POSIX shell on the remote side
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// User, host and port given in a destination; `[::1]` style hosts lose
+/// their brackets
+fn split_destination(destination: &str) -> Result<(Option<&str>, &str, Option<u16>), String> {
+    let authority = destination.strip_prefix(SCHEME).unwrap_or(destination);
+    let (user, host_port) = match authority.rsplit_once('@') {
+        Some((user, rest)) => (Some(user), rest),
+        None => (None, authority),
+    };
+    let (host, port) = match host_port.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, rest) = bracketed
+                .split_once(']')
+                .ok_or_else(|| format!("Invalid host in {}", destination))?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        },
+    };
+    let port = port
+        .map(|p| p.parse::<u16>().map_err(|_| format!("Invalid port in {}", destination)))
+        .transpose()?;
+    if host.is_empty() {
+        return Err(format!("Missing host in {}", destination));
+    }
+    Ok((user, host, port))
+}
+
+/// Accepts only host keys already in known_hosts, like `ssh` with
+/// `StrictHostKeyChecking yes`
+struct KnownHosts {
+    host: String,
+    port: u16,
+    file: PathBuf,
+}
+
+impl client::Handler for KnownHosts {
+    type Error = russh::Error;
+
+    async fn check_server_key(&mut self, server_key: &PublicKeyOrCertificate) -> Result<bool, Self::Error> {
+        let PublicKeyOrCertificate::PublicKey { key, .. } = server_key else {
+            tracing::warn!("{} offered a host certificate, which is not supported", self.host);
+            return Ok(false);
+        };
+        match keys::check_known_hosts_path(&self.host, self.port, key, &self.file) {
+            Ok(true) => Ok(true),
+            Ok(false) => {
+                tracing::warn!("{}:{} is not in {}", self.host, self.port, self.file.display());
+                Ok(false)
+            }
+            Err(e) => {
+                tracing::warn!("Host key of {}:{} rejected: {}", self.host, self.port, e);
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// An authenticated SSH connection with its SFTP channel
+struct Connection {
+    handle: Handle<KnownHosts>,
+    sftp: SftpSession,
+}
+
+impl Connection {
+    /// Connects and authenticates like `ssh` would: ~/.ssh/config supplies the
+    /// host name, port, user, proxy command and identity files; the agent's
+    /// keys are tried first, then unencrypted key files.
+    async fn open(destination: &str) -> Result<Self, String> {
+        let (user, host, port) = split_destination(destination)?;
+        let mut config = russh_config::parse_home(host).unwrap_or_else(|_| russh_config::Config::default(host));
+        if let Some(user) = user {
+            config.user = Some(user.to_string());
+        }
+        if let Some(port) = port {
+            config.host_config.port = Some(port);
+        }
+        let home = std::env::home_dir().ok_or("Failed to find the home directory")?;
+        let verifier = KnownHosts {
+            host: config.host().to_string(),
+            port: config.port(),
+            file: config
+                .host_config
+                .user_known_hosts_file
+                .clone()
+                .unwrap_or_else(|| home.join(".ssh").join("known_hosts")),
+        };
+
+        let stream = config
+            .stream()
+            .await
+            .map_err(|e| format!("Failed to connect to {}: {}", destination, e))?;
+        let settings = client::Config {
+            // Notices a dead connection so it gets replaced
+            keepalive_interval: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let mut handle = client::connect_stream(Arc::new(settings), stream, verifier)
+            .await
+            .map_err(|e| match e {
+                russh::Error::UnknownKey => format!(
+                    "The host key of {} is unknown or has changed; connect once with ssh to check it",
+                    destination
+                ),
+                e => format!("Failed to connect to {}: {}", destination, e),
+            })?;
+
+        let user = config.user();
+        let mut identity_files = config.host_config.identity_file.clone().unwrap_or_default();
+        identity_files.extend(DEFAULT_KEYS.iter().map(|name| home.join(".ssh").join(name)));
+        if !authenticate(&mut handle, &user, &identity_files).await? {
+            return Err(format!("Authentication as {} failed on {}", user, destination));
+        }
+
+        let channel = handle
+            .channel_open_session()
+            .await
+            .map_err(|e| format!("Failed to open a channel on {}: {}", destination, e))?;
+        channel
+            .request_subsystem(true, "sftp")
+            .await
+            .map_err(|e| format!("Failed to start SFTP on {}: {}", destination, e))?;
+        let sftp = SftpSession::new(channel.into_stream())
+            .await
+            .map_err(|e| format!("Failed to start SFTP on {}: {}", destination, e))?;
+        Ok(Connection { handle, sftp })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.handle.is_closed()
+    }
+
+    async fn close(&self) {
+        let _ = self.sftp.close().await;
+        let _ = self.handle.disconnect(Disconnect::ByApplication, "", "en").await;
+    }
+}
+
+/// Tries the agent's keys, then each readable, unencrypted key file
+async fn authenticate(handle: &mut Handle<KnownHosts>, user: &str, identity_files: &[PathBuf]) -> Result<bool, String> {
+    #[cfg(unix)]
+    if let Ok(agent) = AgentClient::connect_env().await {
+        if authenticate_with_agent(handle, user, agent).await {
+            return Ok(true);
+        }
+    }
+    #[cfg(windows)]
+    if let Ok(agent) = AgentClient::connect_named_pipe(r"\\.\pipe\openssh-ssh-agent").await {
+        if authenticate_with_agent(handle, user, agent).await {
+            return Ok(true);
+        }
+    }
+
+    let rsa_hash = handle.best_supported_rsa_hash().await.ok().flatten().flatten();
+    for file in identity_files {
+        let Ok(key) = keys::load_secret_key(file, None) else {
+            continue;
+        };
+        let key = PrivateKeyWithHashAlg::new(Arc::new(key), rsa_hash);
+        match handle.authenticate_publickey(user, key).await {
+            Ok(result) if result.success() => return Ok(true),
+            Ok(_) => {}
+            Err(e) => return Err(format!("Authentication failed: {}", e)),
+        }
+    }
+    Ok(false)
+}
+
+async fn authenticate_with_agent<S>(handle: &mut Handle<KnownHosts>, user: &str, mut agent: AgentClient<S>) -> bool
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let Ok(identities) = agent.request_identities().await else {
+        return false;
+    };
+    let rsa_hash: Option<HashAlg> = handle.best_supported_rsa_hash().await.ok().flatten().flatten();
+    for identity in identities {
+        let key = identity.public_key().into_owned();
+        let result = handle.authenticate_publickey_with(user, key, rsa_hash, &mut agent).await;
+        if result.is_ok_and(|r| r.success()) {
+            return true;
+        }
+    }
+    false
+}
+
+/// SFTP status replies (no such file, permission denied, ...) leave the
+/// connection usable; anything else means it broke
+fn is_transport_error(error: &SftpError) -> bool {
+    !matches!(error, SftpError::Status(_))
+}
+
+/// Open connections, one per destination
+#[derive(Default)]
+pub struct RemoteState {
+    connections: Mutex<HashMap<String, Arc<Connection>>>,
+}
+
+impl RemoteState {
+    fn cached(&self, destination: &str) -> Result<Option<Arc<Connection>>, String> {
+        let connections = self.connections.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        Ok(connections.get(destination).filter(|c| !c.is_closed()).cloned())
+    }
+
+    /// The connection for `destination`, connecting on first use. Hosts must
+    /// already be in known_hosts.
+    async fn connect(&self, destination: &str) -> Result<Arc<Connection>, String> {
+        if let Some(connection) = self.cached(destination)? {
+            return Ok(connection);
+        }
+        // The handshake can take seconds, so other hosts don't wait on the lock
+        let connection = tokio::time::timeout(CONNECT_TIMEOUT, Connection::open(destination))
+            .await
+            .map_err(|_| format!("Timed out connecting to {}", destination))??;
+        let connection = Arc::new(connection);
+
+        let mut connections = self.connections.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        match connections.get(destination) {
+            // Another request connected meanwhile; keep that one
+            Some(existing) if !existing.is_closed() => Ok(existing.clone()),
+            _ => {
+                connections.insert(destination.to_string(), connection.clone());
+                Ok(connection)
+            }
+        }
+    }
+
+    /// Forgets `connection` unless it was already replaced
+    fn evict(&self, destination: &str, connection: &Arc<Connection>) {
+        if let Ok(mut connections) = self.connections.lock() {
+            if connections.get(destination).is_some_and(|c| Arc::ptr_eq(c, connection)) {
+                connections.remove(destination);
+            }
+        }
+    }
+
+    /// Runs `op` on the connection for `destination`. When the connection
+    /// turns out to be broken it's replaced and `op` runs once more.
+    async fn with_sftp<T, F, Fut>(&self, destination: &str, context: &str, op: F) -> Result<T, String>
+    where
+        F: Fn(Arc<Connection>) -> Fut,
+        Fut: Future<Output = Result<T, SftpError>>,
+    {
+        let connection = self.connect(destination).await?;
+        match op(connection.clone()).await {
+            Err(e) if is_transport_error(&e) || connection.is_closed() => {
+                tracing::info!("Reconnecting to {} after: {}", destination, e);
+                self.evict(destination, &connection);
+                let connection = self.connect(destination).await?;
+                op(connection).await.map_err(|e| format!("{}: {}", context, e))
+            }
+            result => result.map_err(|e| format!("{}: {}", context, e)),
+        }
+    }
+
+    async fn disconnect(&self, destination: &str) -> Result<(), String> {
+        let connection = self
+            .connections
+            .lock()
+            .map_err(|e| format!("Failed to lock state: {}", e))?
+            .remove(destination);
+        // Requests still in flight keep their own handle; the connection
+        // closes when the last one finishes
+        if let Some(connection) = connection.and_then(|c| Arc::try_unwrap(c).ok()) {
+            connection.close().await;
+        }
+        Ok(())
+    }
+}
+
+pub async fn read_directory(state: &RemoteState, path: &str, show_hidden: bool) -> Result<Vec<FileEntry>, String> {
+    let dir = parse(path)?;
+    let mut entries = state
+        .with_sftp(&dir.destination, "Failed to read directory", |connection| {
+            let dir = dir.clone();
+            async move {
+                let sftp = &connection.sftp;
+                let mut entries = Vec::new();
+                for entry in sftp.read_dir(dir.path.as_str()).await? {
+                    let name = entry.file_name();
+                    if name == "." || name == ".." || (!show_hidden && name.starts_with('.')) {
+                        continue;
+                    }
+                    let child = dir.join(&name);
+                    let is_symlink = entry.file_type().is_symlink();
+                    let (metadata, symlink_target) = if is_symlink {
+                        // Follow the link; a broken one is neither a file nor a directory
+                        let target = sftp.metadata(child.path.as_str()).await.ok();
+                        let link = sftp.read_link(child.path.as_str()).await.ok();
+                        (target, link)
+                    } else {
+                        (Some(entry.metadata()), None)
+                    };
+                    let target_type = metadata.as_ref().map(|m| m.file_type());
+                    let (is_directory, is_file) = (
+                        target_type.is_some_and(|t| t.is_dir()),
+                        target_type.is_some_and(|t| t.is_file()),
+                    );
+                    entries.push(FileEntry {
+                        extension: crate::extension_of(&name, is_directory),
+                        path: child.url(),
+                        name,
+                        is_directory,
+                        is_file,
+                        is_symlink,
+                        symlink_target,
+                        // Cycles are not detected remotely; the tree only expands on demand
+                        is_circular: false,
+                        is_ignored: false,
+                        size: metadata.as_ref().filter(|_| is_file).map(|m| m.len()).unwrap_or(0),
+                        modified_ms: metadata.as_ref().and_then(|m| system_time_ms(m.modified())),
+                        readonly: metadata.as_ref().is_some_and(|m| m.permissions().is_readonly()),
+                    });
+                }
+                Ok(entries)
+            }
+        })
+        .await?;
+
+    entries.sort_by(|a, b| match (a.is_directory, b.is_directory) {
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+    });
+    Ok(entries)
+}
+
+pub async fn read_file(state: &RemoteState, path: &str) -> Result<Vec<u8>, String> {
+    let file = parse(path)?;
+    state
+        .with_sftp(&file.destination, "Failed to read file", |connection| {
+            let path = file.path.clone();
+            async move { connection.sftp.read(path).await }
+        })
+        .await
+}
+
+/// Writes `bytes` to a remote file. With `expected`, the current contents are
+/// compared first, like `file_version::check_unchanged` does for local files.
+pub async fn save_file(
+    state: &RemoteState,
+    path: &str,
+    bytes: &[u8],
+    expected: Option<&FileVersion>,
+) -> Result<FileVersion, SaveError> {
+    let file = parse(path)?;
+
+    if let Some(hash) = expected.and_then(|v| v.hash.as_ref()) {
+        let current = state
+            .with_sftp(&file.destination, "Failed to read file", |connection| {
+                let path = file.path.clone();
+                async move {
+                    match connection.sftp.read(path).await {
+                        Ok(current) => Ok(Some(sha256_hex(&current))),
+                        Err(SftpError::Status(status)) if status.status_code == StatusCode::NoSuchFile => Ok(None),
+                        Err(e) => Err(e),
+                    }
+                }
+            })
+            .await?;
+        if current.as_ref() != Some(hash) {
+            let message = match current {
+                Some(_) => format!("{} was modified on the remote host since it was opened", path),
+                None => format!("{} was deleted on the remote host since it was opened", path),
+            };
+            return Err(SaveError::Conflict {
+                message,
+                path: path.to_string(),
+                current: current.map(|hash| FileVersion {
+                    modified_ms: None,
+                    hash: Some(hash),
+                }),
+            });
+        }
+    }
+
+    let modified_ms = state
+        .with_sftp(&file.destination, "Failed to save file", |connection| {
+            let path = file.path.clone();
+            async move {
+                let sftp = &connection.sftp;
+                let flags = OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE;
+                let mut remote = sftp.open_with_flags(path.as_str(), flags).await?;
+                remote.write_all(bytes).await.map_err(|e| SftpError::IO(e.to_string()))?;
+                remote.shutdown().await.map_err(|e| SftpError::IO(e.to_string()))?;
+                let metadata = sftp.metadata(path).await.ok();
+                Ok(metadata.and_then(|m| system_time_ms(m.modified())))
+            }
+        })
+        .await?;
+    Ok(FileVersion {
+        modified_ms,
+        hash: Some(sha256_hex(bytes)),
+    })
+}
+
+/// Opens (or reuses) the connection for an `ssh://` path so the first
+/// directory listing doesn't pay for the handshake
+#[tauri::command]
+pub async fn connect_remote(state: State<'_, RemoteState>, path: String) -> Result<String, String> {
+    let remote = parse(&path)?;
+    state.connect(&remote.destination).await?;
+    Ok(remote.destination)
+}
+
+#[tauri::command]
+pub async fn disconnect_remote(state: State<'_, RemoteState>, path: String) -> Result<(), String> {
+    state.disconnect(&parse(&path)?.destination).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destinations_split_into_user_host_and_port() {
+        assert_eq!(split_destination("ssh://example.com").unwrap(), (None, "example.com", None));
+        assert_eq!(
+            split_destination("ssh://me@example.com:2222").unwrap(),
+            (Some("me"), "example.com", Some(2222))
+        );
+        assert_eq!(split_destination("ssh://[::1]:22").unwrap(), (None, "::1", Some(22)));
+        assert!(split_destination("ssh://example.com:ssh").is_err());
+        assert!(split_destination("ssh://me@").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::atomic_write::write_atomic;
+use crate::encoding;
+use crate::file_info::{sha256_hex, system_time_ms};
+use crate::fs_guard::FsGuardState;
+use crate::search::{build_search_regex, SearchOptions};
+
+const BACKUP_DIR: &str = "replace-backup";
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// A match the user accepted in the search results, as reported by `search_in_project`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AcceptedMatch {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChangeReport {
+    pub path: String,
+    pub replacements: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplaceReport {
+    pub files: Vec<FileChangeReport>,
+    pub total_replacements: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupEntry {
+    path: String,
+    backup_file: String,
+    /// Hash of the content written by the replace, used to detect later edits
+    new_hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    created_ms: u64,
+    entries: Vec<BackupEntry>,
+}
+
+/// Replaces the matches of `re` whose (1-based line, 0-based char column)
+/// is in `accepted`, walking lines exactly like `search::search_content`.
+/// Returns the new content and the number of replacements made.
+pub fn apply_replacements(
+    content: &str,
+    re: &Regex,
+    replacement: &str,
+    expand_captures: bool,
+    accepted: &HashSet<(usize, usize)>,
+) -> (String, usize) {
+    let mut output = String::with_capacity(content.len());
+    let mut count = 0;
+
+    for (idx, segment) in content.split_inclusive('\n').enumerate() {
+        let line = segment.trim_end_matches('\n').trim_end_matches('\r');
+        let terminator = &segment[line.len()..];
+        let mut last = 0;
+
+        for caps in re.captures_iter(line) {
+            let m = caps.get(0).expect("group 0 always matches");
+            if m.start() == m.end() {
+                continue;
+            }
+            let column = line[..m.start()].chars().count();
+            if !accepted.contains(&(idx + 1, column)) {
+                continue;
+            }
+
+            output.push_str(&line[last..m.start()]);
+            if expand_captures {
+                caps.expand(replacement, &mut output);
+            } else {
+                output.push_str(replacement);
+            }
+            last = m.end();
+            count += 1;
+        }
+
+        output.push_str(&line[last..]);
+        output.push_str(terminator);
+    }
+
+    (output, count)
+}
+
+fn backup_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|d| d.join(BACKUP_DIR))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+fn write_backup(dir: &Path, changes: &[(PathBuf, Vec<u8>, Vec<u8>)]) -> Result<(), String> {
+    // Only the most recent operation can be undone, so start from a clean directory
+    if dir.exists() {
+        fs::remove_dir_all(dir).map_err(|e| format!("Failed to clear old backup: {}", e))?;
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let mut entries = Vec::new();
+    for (i, (path, original, new_content)) in changes.iter().enumerate() {
+        let backup_file = format!("{}.bak", i);
+        fs::write(dir.join(&backup_file), original).map_err(|e| format!("Failed to write backup: {}", e))?;
+        entries.push(BackupEntry {
+            path: path.to_string_lossy().to_string(),
+            backup_file,
+            new_hash: sha256_hex(new_content),
+        });
+    }
+
+    let manifest = BackupManifest {
+        created_ms: system_time_ms(Ok(std::time::SystemTime::now())).unwrap_or(0),
+        entries,
+    };
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    write_atomic(&dir.join(MANIFEST_FILE), &json).map_err(|e| format!("Failed to write manifest: {}", e))
+}
+
+/// Applies the accepted matches across all files as a single operation.
+///
+/// Every file is rewritten in memory first, in the encoding it was read in;
+/// if any accepted match is no longer present, or a file can't be decoded
+/// or re-encoded cleanly, the whole operation is aborted. Files written before a failed write
+/// are rolled back, and the originals are kept so `undo_last_replace` can
+/// revert the entire operation later.
+#[tauri::command]
+pub async fn replace_in_files(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    query: String,
+    replacement: String,
+    options: Option<SearchOptions>,
+    matches: Vec<AcceptedMatch>,
+) -> Result<ReplaceReport, String> {
+    let options = options.unwrap_or_default();
+    let re = build_search_regex(&query, &options)?;
+
+    // Keyed by the path as the search reported it, with its canonical form
+    let mut by_file: HashMap<String, (PathBuf, HashSet<(usize, usize)>)> = HashMap::new();
+    for m in matches {
+        let canonical = guard.check(&m.path)?;
+        by_file
+            .entry(m.path)
+            .or_insert_with(|| (canonical, HashSet::new()))
+            .1
+            .insert((m.line, m.column));
+    }
+
+    // Phase 1: compute every new file content without touching the disk
+    let mut changes: Vec<(PathBuf, Vec<u8>, Vec<u8>)> = Vec::new();
+    let mut reports = Vec::new();
+    let mut paths: Vec<&String> = by_file.keys().collect();
+    paths.sort();
+    for path in paths {
+        let (canonical, accepted) = &by_file[path];
+        let original = fs::read(canonical).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let text = encoding::decode(&original, None);
+        // Writing back undecodable bytes would replace them with U+FFFD
+        if text.had_errors {
+            return Err(format!("{} is not valid {}; nothing was replaced", path, text.encoding));
+        }
+        let (new_content, count) = apply_replacements(&text.content, &re, &replacement, options.regex, accepted);
+        if count != accepted.len() {
+            return Err(format!("{} changed since the search was run; please search again", path));
+        }
+        let new_content = encoding::encode(
+            &new_content,
+            encoding::encoding_for_label(&text.encoding)?,
+            text.has_bom,
+        )
+        .map_err(|e| format!("{}: {}", path, e))?;
+        reports.push(FileChangeReport {
+            path: path.clone(),
+            replacements: count,
+        });
+        changes.push((canonical.clone(), original, new_content));
+    }
+
+    // Phase 2: snapshot the originals, then write everything
+    write_backup(&backup_dir(&app_handle)?, &changes)?;
+
+    for (i, (path, _, new_content)) in changes.iter().enumerate() {
+        if let Err(e) = write_atomic(path, new_content) {
+            for (written_path, original, _) in &changes[..i] {
+                let _ = write_atomic(written_path, original);
+            }
+            return Err(format!("Failed to write {}: {} (changes rolled back)", path.display(), e));
+        }
+    }
+
+    let total_replacements = reports.iter().map(|r| r.replacements).sum();
+    Ok(ReplaceReport {
+        files: reports,
+        total_replacements,
+    })
+}
+
+/// Restores every file touched by the last `replace_in_files`. Files edited
+/// since then are left alone (and reported as an error) unless `force` is set.
+#[tauri::command]
+pub async fn undo_last_replace(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    force: Option<bool>,
+) -> Result<ReplaceReport, String> {
+    let dir = backup_dir(&app_handle)?;
+    let manifest_bytes = fs::read(dir.join(MANIFEST_FILE)).map_err(|_| "Nothing to undo".to_string())?;
+    let manifest: BackupManifest =
+        serde_json::from_slice(&manifest_bytes).map_err(|e| format!("Corrupt replace backup: {}", e))?;
+    // The workspace may have been closed since the replace
+    let targets = manifest
+        .entries
+        .iter()
+        .map(|entry| guard.check(&entry.path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if !force.unwrap_or(false) {
+        let modified: Vec<&str> = manifest
+            .entries
+            .iter()
+            .zip(&targets)
+            .filter(|(entry, target)| {
+                fs::read(target)
+                    .map(|current| sha256_hex(&current) != entry.new_hash)
+                    .unwrap_or(true)
+            })
+            .map(|(entry, _)| entry.path.as_str())
+            .collect();
+        if !modified.is_empty() {
+            return Err(format!("Files changed since the replace: {}", modified.join(", ")));
+        }
+    }
+
+    let mut files = Vec::new();
+    for (entry, target) in manifest.entries.iter().zip(&targets) {
+        let original = fs::read(dir.join(&entry.backup_file)).map_err(|e| format!("Failed to read backup: {}", e))?;
+        write_atomic(target, &original).map_err(|e| format!("Failed to restore {}: {}", entry.path, e))?;
+        files.push(FileChangeReport {
+            path: entry.path.clone(),
+            replacements: 0,
+        });
+    }
+
+    fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove backup: {}", e))?;
+    Ok(ReplaceReport {
+        files,
+        total_replacements: 0,
+    })
+}
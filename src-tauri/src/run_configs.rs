@@ -0,0 +1,196 @@
+//! Named run configurations per workspace (program, arguments, environment,
+//! working directory), stored in `.tmd/run.json` and started in a PTY
+//! through the task runner. The backend of the Run toolbar.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+use crate::atomic_write::write_atomic;
+use crate::fs_guard::FsGuardState;
+use crate::tasks::{self, TaskDefinition, TaskKind, TaskState};
+
+// Relative to the workspace root, next to tasks.json
+const RUN_FILE: &str = ".tmd/run.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunConfig {
+    /// Assigned by `save_run_config` when empty
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// Executable, run directly (no shell)
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Working directory, relative to the workspace root
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunFile {
+    #[serde(default)]
+    configurations: Vec<RunConfig>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunStatus {
+    pub config_id: String,
+    pub running: bool,
+    /// Task the configuration runs as; its output and exit arrive on the
+    /// `task-output-{id}` and `task-exit-{id}` events
+    pub task_id: Option<String>,
+}
+
+/// Latest task started for each (workspace root, configuration ID)
+#[derive(Default)]
+pub struct RunConfigState {
+    runs: Mutex<HashMap<(PathBuf, String), String>>,
+}
+
+fn load(root: &Path) -> Result<RunFile, String> {
+    let path = root.join(RUN_FILE);
+    if !path.exists() {
+        return Ok(RunFile::default());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", RUN_FILE, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", RUN_FILE, e))
+}
+
+fn store(root: &Path, file: &RunFile) -> Result<(), String> {
+    let path = root.join(RUN_FILE);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let mut json =
+        serde_json::to_string_pretty(file).map_err(|e| format!("Failed to serialize {}: {}", RUN_FILE, e))?;
+    json.push('\n');
+    write_atomic(&path, json.as_bytes()).map_err(|e| format!("Failed to write {}: {}", RUN_FILE, e))
+}
+
+impl RunConfigState {
+    fn status(&self, tasks: &TaskState, root: &Path, config_id: &str) -> Result<RunStatus, String> {
+        let runs = self.runs.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        let task_id = runs.get(&(root.to_path_buf(), config_id.to_string())).cloned();
+        Ok(RunStatus {
+            config_id: config_id.to_string(),
+            running: task_id.as_deref().is_some_and(|id| tasks.is_running(id)),
+            task_id,
+        })
+    }
+}
+
+/// Run configurations of the workspace, in file order
+#[tauri::command]
+pub async fn list_run_configs(guard: State<'_, FsGuardState>, root: String) -> Result<Vec<RunConfig>, String> {
+    let root = guard.check(&root)?;
+    Ok(load(&root)?.configurations)
+}
+
+/// Adds `config`, or replaces the one with the same ID; returns it with its ID
+#[tauri::command]
+pub async fn save_run_config(
+    guard: State<'_, FsGuardState>,
+    root: String,
+    config: RunConfig,
+) -> Result<RunConfig, String> {
+    let root = guard.check(&root)?;
+    let mut config = config;
+    config.name = config.name.trim().to_string();
+    config.program = config.program.trim().to_string();
+    if config.name.is_empty() {
+        return Err("Run configuration needs a name".to_string());
+    }
+    if config.program.is_empty() {
+        return Err("Run configuration needs a program".to_string());
+    }
+    if config.id.is_empty() {
+        config.id = Uuid::new_v4().to_string();
+    }
+
+    let mut file = load(&root)?;
+    match file.configurations.iter_mut().find(|c| c.id == config.id) {
+        Some(existing) => *existing = config.clone(),
+        None => file.configurations.push(config.clone()),
+    }
+    store(&root, &file)?;
+    Ok(config)
+}
+
+#[tauri::command]
+pub async fn delete_run_config(guard: State<'_, FsGuardState>, root: String, id: String) -> Result<(), String> {
+    let root = guard.check(&root)?;
+    let mut file = load(&root)?;
+    let before = file.configurations.len();
+    file.configurations.retain(|c| c.id != id);
+    if file.configurations.len() == before {
+        return Err(format!("No run configuration with id: {}", id));
+    }
+    store(&root, &file)
+}
+
+/// Starts configuration `id` in its own PTY, as task `task_id` (see
+/// `run_task`). Only one run per configuration at a time; stop it with
+/// `cancel_task`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_config(
+    app_handle: AppHandle,
+    state: State<'_, RunConfigState>,
+    tasks: State<'_, TaskState>,
+    task_id: String,
+    root: String,
+    id: String,
+    rows: Option<u16>,
+    cols: Option<u16>,
+) -> Result<RunStatus, String> {
+    let root = app_handle.state::<FsGuardState>().check(&root)?;
+    let config = load(&root)?
+        .configurations
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| format!("No run configuration with id: {}", id))?;
+    if state.status(&tasks, &root, &id)?.running {
+        return Err(format!("{} is already running", config.name));
+    }
+
+    let task = TaskDefinition {
+        label: config.name,
+        kind: TaskKind::Process,
+        command: config.program,
+        args: config.args,
+        cwd: config.cwd,
+        env: config.env,
+        group: Some("run".to_string()),
+        source: "run.json".to_string(),
+        problem_matchers: None,
+    };
+    tasks::authorize_task(&app_handle, "run_config", &task, &root).await?;
+    let task_id = tasks::spawn_task(app_handle, &tasks, task_id, &root, task, rows, cols)?;
+    state
+        .runs
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .insert((root.clone(), id.clone()), task_id);
+    state.status(&tasks, &root, &id)
+}
+
+/// Whether configuration `id` is running, and the task ID of its latest run
+#[tauri::command]
+pub async fn get_run_status(
+    state: State<'_, RunConfigState>,
+    tasks: State<'_, TaskState>,
+    root: String,
+    id: String,
+) -> Result<RunStatus, String> {
+    state.status(&tasks, Path::new(&root), &id)
+}
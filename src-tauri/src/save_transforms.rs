@@ -0,0 +1,185 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tauri::State;
+
+use crate::editorconfig;
+use crate::fs_guard::FsGuardState;
+use crate::line_endings::{self, LineEnding};
+use crate::workspace_settings;
+
+const DEFAULT_INDENT_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+/// Requested by the caller of `save_file`; anything left unset falls back to
+/// the workspace settings, then to `.editorconfig`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SaveTransforms {
+    pub trim_trailing_whitespace: Option<bool>,
+    /// true adds a missing final newline, false removes trailing ones
+    pub insert_final_newline: Option<bool>,
+    /// Rewrites leading indentation only
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<usize>,
+    /// Whether to consult `.editorconfig` files (default true)
+    pub editorconfig: Option<bool>,
+    /// Whether to consult the workspace's `.tmd/settings.json` (default true)
+    pub workspace_settings: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Resolved {
+    trim_trailing_whitespace: bool,
+    insert_final_newline: Option<bool>,
+    /// Style, indent size and tab width
+    indent: Option<(IndentStyle, usize, usize)>,
+    end_of_line: Option<LineEnding>,
+}
+
+fn resolve(path: &Path, options: &SaveTransforms, local: bool) -> Resolved {
+    let mut trim = None;
+    let mut final_newline = None;
+    let mut style = None;
+    let mut size = None;
+    let mut tab_width = None;
+    let mut end_of_line = None;
+
+    if local && options.editorconfig.unwrap_or(true) {
+        let config = editorconfig::resolve(path);
+        trim = config.get_bool("trim_trailing_whitespace");
+        final_newline = config.get_bool("insert_final_newline");
+        style = match config.get("indent_style") {
+            Some("tab") => Some(IndentStyle::Tab),
+            Some("space") => Some(IndentStyle::Space),
+            _ => None,
+        };
+        size = config.get_usize("indent_size");
+        tab_width = config.get_usize("tab_width");
+        end_of_line = match config.get("end_of_line") {
+            Some("lf") => Some(LineEnding::Lf),
+            Some("crlf") => Some(LineEnding::Crlf),
+            _ => None,
+        };
+    }
+
+    if local && options.workspace_settings.unwrap_or(true) {
+        let settings = workspace_settings::overrides_for_file(path);
+        trim = settings.get("trimTrailingWhitespace").and_then(|v| v.as_bool()).or(trim);
+        final_newline = settings.get("insertFinalNewline").and_then(|v| v.as_bool()).or(final_newline);
+        style = match settings.get("indentStyle").and_then(|v| v.as_str()) {
+            Some("tab") => Some(IndentStyle::Tab),
+            Some("space") => Some(IndentStyle::Space),
+            _ => style,
+        };
+        size = settings.get("indentSize").and_then(|v| v.as_u64()).map(|n| n as usize).or(size);
+    }
+
+    let style = options.indent_style.or(style);
+    let size = options.indent_size.filter(|n| *n > 0).or(size);
+    Resolved {
+        trim_trailing_whitespace: options.trim_trailing_whitespace.or(trim).unwrap_or(false),
+        insert_final_newline: options.insert_final_newline.or(final_newline),
+        indent: style.map(|style| {
+            let size = size.unwrap_or(DEFAULT_INDENT_SIZE);
+            (style, size, tab_width.unwrap_or(size))
+        }),
+        end_of_line,
+    }
+}
+
+fn reindent(indent: &str, style: IndentStyle, size: usize, tab_width: usize) -> String {
+    let columns = indent
+        .chars()
+        .fold(0, |col, c| if c == '\t' { (col / tab_width + 1) * tab_width } else { col + 1 });
+    match style {
+        IndentStyle::Space => " ".repeat(columns),
+        // Any remainder that isn't a whole level stays as spaces (alignment)
+        IndentStyle::Tab => format!("{}{}", "\t".repeat(columns / size), " ".repeat(columns % size)),
+    }
+}
+
+fn apply(path: &Path, content: &str, resolved: &Resolved) -> String {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let markdown = matches!(extension.as_str(), "md" | "markdown" | "mdx");
+    // Make recipes must start with a tab
+    let makefile = name == "makefile" || name == "gnumakefile" || extension == "mk";
+    let indent = resolved
+        .indent
+        .filter(|(style, _, _)| !(makefile && *style == IndentStyle::Space));
+
+    let mut out = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let (body, eol) = match line.strip_suffix("\r\n") {
+            Some(body) => (body, "\r\n"),
+            None => match line.strip_suffix('\n') {
+                Some(body) => (body, "\n"),
+                None => (line, ""),
+            },
+        };
+        let mut body = body.to_string();
+        if let Some((style, size, tab_width)) = indent {
+            let rest = body.trim_start_matches([' ', '\t']);
+            let leading = &body[..body.len() - rest.len()];
+            if !rest.is_empty() {
+                body = format!("{}{}", reindent(leading, style, size, tab_width), rest);
+            }
+        }
+        if resolved.trim_trailing_whitespace {
+            let trimmed = body.trim_end_matches([' ', '\t']);
+            // Two or more trailing spaces are a hard line break in Markdown
+            let hard_break = markdown && !trimmed.is_empty() && body[trimmed.len()..].starts_with("  ");
+            body = if hard_break { format!("{}  ", trimmed) } else { trimmed.to_string() };
+        }
+        out.push_str(&body);
+        out.push_str(eol);
+    }
+
+    match resolved.insert_final_newline {
+        Some(true) if !out.is_empty() && !out.ends_with('\n') => {
+            let eol = match line_endings::count(&out).dominant() {
+                Some(LineEnding::Crlf) => "\r\n",
+                _ => "\n",
+            };
+            out.push_str(eol);
+        }
+        Some(false) => {
+            let kept = out.trim_end_matches(['\r', '\n']).len();
+            out.truncate(kept);
+        }
+        _ => {}
+    }
+    match resolved.end_of_line {
+        Some(target) => line_endings::convert(&out, target).into_owned(),
+        None => out,
+    }
+}
+
+/// Applies the transforms configured for `path` to `content`. Settings and
+/// `.editorconfig` files are only consulted for `local` paths.
+pub fn transform(path: &Path, local: bool, content: &str, options: &SaveTransforms) -> String {
+    apply(path, content, &resolve(path, options, local))
+}
+
+/// What `save_file` would write for `content` with the same `transforms`,
+/// so the editor can update its buffer to match
+#[tauri::command]
+pub async fn apply_save_transforms(
+    guard: State<'_, FsGuardState>,
+    path: String,
+    content: String,
+    transforms: Option<SaveTransforms>,
+) -> Result<String, String> {
+    let local = !crate::remote::is_remote(&path);
+    let path = if local { guard.check(&path)? } else { PathBuf::from(path) };
+    tauri::async_runtime::spawn_blocking(move || transform(&path, local, &content, &transforms.unwrap_or_default()))
+        .await
+        .map_err(|e| format!("Transform task failed: {}", e))
+}
@@ -0,0 +1,220 @@
+//! New projects for File > New Project: runs the ecosystem's own tool
+//! (`cargo init`, `go mod init`, `npm init`) where there is one, and writes
+//! a small built-in template for whatever it doesn't create.
+
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+use tokio::process::Command;
+
+use crate::commands;
+use crate::fs_guard::FsGuardState;
+use crate::lsp::registry::find_executable;
+use crate::shell_env;
+
+/// Output of the tool and the files written, as text chunks
+const OUTPUT_EVENT: &str = "scaffold-output";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProjectKind {
+    Rust,
+    RustLib,
+    Go,
+    Node,
+    /// A markdown notes folder; no tool involved
+    Notes,
+}
+
+impl ProjectKind {
+    /// Program and arguments that create the project in `dir`, run from `dir`
+    fn tool(self, name: &str) -> Option<(&'static str, Vec<String>)> {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+        match self {
+            ProjectKind::Rust => Some(("cargo", args(&["init", "--bin", "--name", name]))),
+            ProjectKind::RustLib => Some(("cargo", args(&["init", "--lib", "--name", name]))),
+            ProjectKind::Go => Some(("go", args(&["mod", "init", name]))),
+            ProjectKind::Node => Some(("npm", args(&["init", "-y"]))),
+            ProjectKind::Notes => None,
+        }
+    }
+
+    /// Files written when missing, as (relative path, content with `{{name}}`)
+    fn template(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            ProjectKind::Rust => &[
+                (
+                    "Cargo.toml",
+                    "[package]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+                ),
+                ("src/main.rs", "fn main() {\n    println!(\"Hello, world!\");\n}\n"),
+                (".gitignore", "/target\n"),
+            ],
+            ProjectKind::RustLib => &[
+                (
+                    "Cargo.toml",
+                    "[package]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+                ),
+                (
+                    "src/lib.rs",
+                    "pub fn add(left: u64, right: u64) -> u64 {\n    left + right\n}\n",
+                ),
+                (".gitignore", "/target\n"),
+            ],
+            ProjectKind::Go => &[
+                ("go.mod", "module {{name}}\n\ngo 1.21\n"),
+                (
+                    "main.go",
+                    "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(\"Hello, world!\")\n}\n",
+                ),
+            ],
+            ProjectKind::Node => &[
+                (
+                    "package.json",
+                    "{\n  \"name\": \"{{name}}\",\n  \"version\": \"1.0.0\",\n  \"main\": \"index.js\",\n  \"scripts\": {\n    \"start\": \"node index.js\"\n  }\n}\n",
+                ),
+                ("index.js", "console.log(\"Hello, world!\");\n"),
+                (".gitignore", "node_modules/\n"),
+            ],
+            ProjectKind::Notes => &[("README.md", "# {{name}}\n")],
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaffoldResult {
+    pub project_dir: String,
+    /// Command line that was run, if the ecosystem's tool was found
+    pub command: Option<String>,
+    /// Files added from the built-in template, relative to the project
+    pub files: Vec<String>,
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Project name is empty".to_string());
+    }
+    if name.starts_with('.') || name.contains(['/', '\\']) || name.chars().any(char::is_control) {
+        return Err(format!("Invalid project name: {}", name));
+    }
+    Ok(())
+}
+
+/// Runs `program` in `dir`, streaming its output; fails on a non-zero exit
+async fn run_tool(app: &AppHandle, program: &Path, args: &[String], dir: &Path) -> Result<(), String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(shell_env::environment())
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program.display(), e))?;
+
+    let readers: Vec<_> = [
+        child
+            .stdout
+            .take()
+            .map(|out| tauri::async_runtime::spawn(commands::forward(app.clone(), OUTPUT_EVENT.to_string(), out))),
+        child
+            .stderr
+            .take()
+            .map(|err| tauri::async_runtime::spawn(commands::forward(app.clone(), OUTPUT_EVENT.to_string(), err))),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", program.display(), e))?;
+    for reader in readers {
+        let _ = reader.await;
+    }
+
+    if status.success() {
+        Ok(())
+    } else {
+        let name = program
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Err(match status.code() {
+            Some(code) => format!("{} exited with code {}", name, code),
+            None => format!("{} was terminated", name),
+        })
+    }
+}
+
+/// Creates project `name` of `kind` in a new folder under `target_dir`.
+///
+/// The ecosystem's tool is used when it is installed, with its output
+/// streamed on `scaffold-output`; files it doesn't create (or everything,
+/// when the tool is missing) come from a built-in template.
+#[tauri::command]
+pub async fn scaffold_project(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    kind: ProjectKind,
+    target_dir: String,
+    name: String,
+) -> Result<ScaffoldResult, String> {
+    validate_name(&name)?;
+    let parent = guard.check(&target_dir)?;
+    let name = name.trim();
+    if !parent.is_dir() {
+        return Err(format!("Not a directory: {}", target_dir));
+    }
+    let project_dir = parent.join(name);
+    let occupied = fs::read_dir(&project_dir).is_ok_and(|mut entries| entries.next().is_some());
+    if occupied || project_dir.is_file() {
+        return Err(format!("{} already exists", project_dir.display()));
+    }
+    fs::create_dir_all(&project_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let mut command = None;
+    if let Some((tool, args)) = kind.tool(name) {
+        match find_executable(tool) {
+            Some(program) => {
+                let line = format!("{} {}", tool, args.join(" "));
+                let _ = app_handle.emit(OUTPUT_EVENT, format!("$ {}\n", line));
+                run_tool(&app_handle, &program, &args, &project_dir).await?;
+                command = Some(line);
+            }
+            None => {
+                let _ = app_handle.emit(
+                    OUTPUT_EVENT,
+                    format!("{} was not found; using the built-in template\n", tool),
+                );
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    for (relative, content) in kind.template() {
+        let path = project_dir.join(relative);
+        if path.exists() {
+            continue;
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        fs::write(&path, content.replace("{{name}}", name))
+            .map_err(|e| format!("Failed to write {}: {}", relative, e))?;
+        let _ = app_handle.emit(OUTPUT_EVENT, format!("Created {}\n", relative));
+        files.push(relative.to_string());
+    }
+
+    Ok(ScaffoldResult {
+        project_dir: project_dir.to_string_lossy().to_string(),
+        command,
+        files,
+    })
+}
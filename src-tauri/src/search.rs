@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::encoding;
+use crate::file_info::is_probably_binary;
+use crate::fs_guard::FsGuardState;
+
+// Matches are sent to the frontend in batches of this size
+const BATCH_SIZE: usize = 100;
+// Preview lines longer than this are truncated
+const MAX_PREVIEW_CHARS: usize = 250;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub include_hidden: bool,
+    pub max_results: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            include_hidden: false,
+            max_results: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchMatch {
+    pub path: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 0-based column in characters
+    pub column: usize,
+    /// Length of the match in characters
+    pub match_length: usize,
+    pub preview: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchSummary {
+    pub total_matches: usize,
+    pub files_searched: usize,
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+#[derive(Default)]
+pub struct SearchState {
+    searches: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+pub fn build_search_regex(query: &str, options: &SearchOptions) -> Result<Regex, String> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let pattern = if options.whole_word {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+fn make_preview(line: &str) -> String {
+    let line = line.trim_end_matches('\r');
+    if line.chars().count() > MAX_PREVIEW_CHARS {
+        let truncated: String = line.chars().take(MAX_PREVIEW_CHARS).collect();
+        format!("{}…", truncated)
+    } else {
+        line.to_string()
+    }
+}
+
+/// Finds all matches of `re` in `content`, calling `on_match` for each one.
+/// Stops early and returns false if `on_match` returns false.
+pub fn search_content<F>(path: &str, content: &str, re: &Regex, mut on_match: F) -> bool
+where
+    F: FnMut(SearchMatch) -> bool,
+{
+    for (idx, line) in content.lines().enumerate() {
+        for m in re.find_iter(line) {
+            if m.start() == m.end() {
+                continue;
+            }
+            let column = line[..m.start()].chars().count();
+            let match_length = m.as_str().chars().count();
+            let keep_going = on_match(SearchMatch {
+                path: path.to_string(),
+                line: idx + 1,
+                column,
+                match_length,
+                preview: make_preview(line),
+            });
+            if !keep_going {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn run_search(
+    app_handle: AppHandle,
+    search_id: String,
+    root: PathBuf,
+    re: Regex,
+    options: SearchOptions,
+    cancelled: Arc<AtomicBool>,
+) -> SearchSummary {
+    let results_event = format!("search-results-{}", search_id);
+    let mut batch: Vec<SearchMatch> = Vec::with_capacity(BATCH_SIZE);
+    let mut total_matches = 0;
+    let mut files_searched = 0;
+    let mut truncated = false;
+
+    let walker = WalkBuilder::new(&root)
+        .hidden(!options.include_hidden)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    for entry in walker {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+
+        let bytes = match fs::read(entry.path()) {
+            Ok(b) => b,
+            Err(_) => continue,
+        };
+        if is_probably_binary(&bytes) {
+            continue;
+        }
+        files_searched += 1;
+
+        // Same detection as opening the file, so GBK or UTF-16 files match too
+        let content = encoding::decode(&bytes, None).content;
+        let path = entry.path().to_string_lossy().to_string();
+        let completed = search_content(&path, &content, &re, |m| {
+            batch.push(m);
+            total_matches += 1;
+            if batch.len() >= BATCH_SIZE {
+                let _ = app_handle.emit(&results_event, std::mem::take(&mut batch));
+            }
+            total_matches < options.max_results
+        });
+
+        if !completed {
+            truncated = true;
+            break;
+        }
+    }
+
+    if !batch.is_empty() {
+        let _ = app_handle.emit(&results_event, batch);
+    }
+
+    SearchSummary {
+        total_matches,
+        files_searched,
+        truncated,
+        cancelled: cancelled.load(Ordering::Relaxed),
+    }
+}
+
+/// Starts a background search. Matches are emitted in batches on
+/// `search-results-{search_id}` and a `SearchSummary` is emitted on
+/// `search-done-{search_id}` once the walk finishes or is cancelled.
+#[tauri::command]
+pub async fn search_in_project(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SearchState>,
+    guard: State<'_, FsGuardState>,
+    search_id: String,
+    root_path: String,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<(), String> {
+    let root = guard.check(&root_path)?;
+    let options = options.unwrap_or_default();
+    if !root.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+    if query.is_empty() {
+        return Err("Empty search query".to_string());
+    }
+    let re = build_search_regex(&query, &options)?;
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut searches = state.searches.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        // A new search with the same id supersedes the old one
+        if let Some(old) = searches.insert(search_id.clone(), cancelled.clone()) {
+            old.store(true, Ordering::Relaxed);
+        }
+    }
+
+    thread::spawn(move || {
+        let done_event = format!("search-done-{}", search_id);
+        let summary = run_search(app_handle.clone(), search_id.clone(), root, re, options, cancelled.clone());
+
+        // Forget the search unless it has already been superseded
+        if let Ok(mut searches) = app_handle.state::<SearchState>().searches.lock() {
+            if searches.get(&search_id).is_some_and(|flag| Arc::ptr_eq(flag, &cancelled)) {
+                searches.remove(&search_id);
+            }
+        }
+
+        let _ = app_handle.emit(&done_event, summary);
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_search(
+    state: tauri::State<'_, SearchState>,
+    search_id: String,
+) -> Result<(), String> {
+    let mut searches = state.searches.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(flag) = searches.remove(&search_id) {
+        flag.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
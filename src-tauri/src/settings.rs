@@ -0,0 +1,265 @@
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+
+// Same store the frontend used before settings moved to the backend
+const SETTINGS_STORE: &str = "settings.json";
+const VERSION_KEY: &str = "settingsVersion";
+const SETTINGS_VERSION: u64 = 1;
+
+pub(crate) enum SettingKind {
+    Bool,
+    Number { min: f64, max: f64 },
+    OneOf(&'static [&'static str]),
+    Text,
+}
+
+/// Every known setting, global or per workspace
+pub(crate) const SCHEMA: &[(&str, SettingKind)] = &[
+    ("theme", SettingKind::OneOf(&["light", "dark"])),
+    ("showHiddenFiles", SettingKind::Bool),
+    ("autoSave", SettingKind::OneOf(&["off", "afterDelay"])),
+    ("autoSaveDelay", SettingKind::Number { min: 0.0, max: 60_000.0 }),
+    ("markdownDefaultMode", SettingKind::OneOf(&["rich", "source", "split"])),
+    ("enableRustLsp", SettingKind::Bool),
+    ("enableGoLsp", SettingKind::Bool),
+    // Save transforms, applied by save_file (see save_transforms.rs)
+    ("trimTrailingWhitespace", SettingKind::Bool),
+    ("insertFinalNewline", SettingKind::Bool),
+    ("indentStyle", SettingKind::OneOf(&["tab", "space"])),
+    ("indentSize", SettingKind::Number { min: 1.0, max: 16.0 }),
+    // Daily notes (see daily_notes.rs): a strftime path under the workspace and a template id
+    ("dailyNotePath", SettingKind::Text),
+    ("dailyNoteTemplate", SettingKind::Text),
+    // Release channel checked by updater.rs
+    ("updateChannel", SettingKind::OneOf(&["stable", "beta"])),
+];
+
+/// Values used when the store has none; settings without a default here
+/// (e.g. the save transforms) are unset unless configured
+fn defaults() -> Map<String, Value> {
+    let Value::Object(map) = json!({
+        "theme": "light",
+        "showHiddenFiles": true,
+        "autoSave": "afterDelay",
+        "autoSaveDelay": 100,
+        "markdownDefaultMode": "source",
+        "enableRustLsp": false,
+        "enableGoLsp": false,
+        "dailyNotePath": "journal/%Y-%m-%d.md",
+        "dailyNoteTemplate": "journal",
+        "updateChannel": "stable",
+    }) else {
+        unreachable!()
+    };
+    map
+}
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades settings written at version n to version n + 1
+const MIGRATIONS: &[Migration] = &[drop_invalid_values];
+
+/// Version 0 is what the frontend wrote through the store without any
+/// validation; values that don't fit the schema fall back to their defaults
+fn drop_invalid_values(settings: &mut Map<String, Value>) {
+    settings.retain(|key, value| {
+        let keep = !matches!(
+            validate_value(key, value),
+            Some(SettingsIssue { severity: IssueSeverity::Error, .. })
+        );
+        if !keep {
+            tracing::warn!("Dropping invalid value for {}: {}", key, value);
+        }
+        keep
+    });
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SettingsIssue {
+    pub key: String,
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SettingsSnapshot {
+    /// Stored values over the defaults
+    pub settings: Map<String, Value>,
+    /// Stored values that were ignored because they don't fit the schema
+    pub issues: Vec<SettingsIssue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SettingsChanged {
+    /// The new effective value of each changed key
+    changes: Map<String, Value>,
+    settings: Map<String, Value>,
+}
+
+pub(crate) fn validate_value(key: &str, value: &Value) -> Option<SettingsIssue> {
+    let error = |message: String| SettingsIssue {
+        key: key.to_string(),
+        severity: IssueSeverity::Error,
+        message,
+    };
+    let Some((_, kind)) = SCHEMA.iter().find(|(name, _)| *name == key) else {
+        // Kept as-is so files written by newer versions still round-trip
+        return Some(SettingsIssue {
+            key: key.to_string(),
+            severity: IssueSeverity::Warning,
+            message: format!("Unknown setting \"{}\"", key),
+        });
+    };
+    match kind {
+        SettingKind::Bool if !value.is_boolean() => Some(error("Expected true or false".to_string())),
+        SettingKind::Number { min, max } => match value.as_f64() {
+            Some(n) if n >= *min && n <= *max => None,
+            Some(_) => Some(error(format!("Expected a number between {} and {}", min, max))),
+            None => Some(error("Expected a number".to_string())),
+        },
+        SettingKind::OneOf(allowed) if !value.as_str().is_some_and(|s| allowed.contains(&s)) => {
+            Some(error(format!("Expected one of: {}", allowed.join(", "))))
+        }
+        SettingKind::Text if !value.is_string() => Some(error("Expected a string".to_string())),
+        _ => None,
+    }
+}
+
+/// Reads the stored settings, upgrading them in place if they were written
+/// by an older version
+fn stored(app: &AppHandle) -> Result<Map<String, Value>, String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+    let mut settings: Map<String, Value> = store.entries().into_iter().collect();
+    let version = settings.remove(VERSION_KEY).and_then(|v| v.as_u64()).unwrap_or(0);
+    if version >= SETTINGS_VERSION {
+        return Ok(settings);
+    }
+
+    for migrate in MIGRATIONS.iter().skip(version as usize) {
+        migrate(&mut settings);
+    }
+    for key in store.keys() {
+        if !settings.contains_key(&key) {
+            store.delete(&key);
+        }
+    }
+    for (key, value) in &settings {
+        store.set(key.clone(), value.clone());
+    }
+    store.set(VERSION_KEY, SETTINGS_VERSION);
+    store.save().map_err(|e| format!("Failed to save store: {}", e))?;
+    tracing::info!("Migrated settings from version {} to {}", version, SETTINGS_VERSION);
+    Ok(settings)
+}
+
+fn snapshot(stored: &Map<String, Value>) -> SettingsSnapshot {
+    let mut settings = defaults();
+    let mut issues = Vec::new();
+    for (key, value) in stored {
+        match validate_value(key, value) {
+            Some(issue) if issue.severity == IssueSeverity::Error => issues.push(issue),
+            _ => {
+                settings.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    SettingsSnapshot { settings, issues }
+}
+
+/// The effective global settings: valid stored values over the defaults
+pub fn effective(app: &AppHandle) -> Map<String, Value> {
+    match stored(app) {
+        Ok(stored) => snapshot(&stored).settings,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            defaults()
+        }
+    }
+}
+
+/// Validates and stores `values`; null resets a key to its default. Nothing
+/// is written if any value is invalid.
+fn update(app: &AppHandle, values: Map<String, Value>) -> Result<Map<String, Value>, String> {
+    let errors: Vec<String> = values
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .filter_map(|(key, value)| match validate_value(key, value) {
+            Some(issue) => Some(format!("{}: {}", key, issue.message)),
+            None => None,
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(format!("Invalid settings: {}", errors.join("; ")));
+    }
+
+    // Runs any pending migration before new values are mixed in
+    stored(app)?;
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+    for (key, value) in &values {
+        if value.is_null() {
+            store.delete(key);
+        } else {
+            store.set(key.clone(), value.clone());
+        }
+    }
+    store.save().map_err(|e| format!("Failed to save store: {}", e))?;
+
+    let settings = snapshot(&stored(app)?).settings;
+    let changes = values
+        .keys()
+        .map(|key| (key.clone(), settings.get(key).cloned().unwrap_or(Value::Null)))
+        .collect();
+    let _ = app.emit(
+        "settings-changed",
+        SettingsChanged {
+            changes,
+            settings: settings.clone(),
+        },
+    );
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_settings(app_handle: AppHandle) -> Result<SettingsSnapshot, String> {
+    Ok(snapshot(&stored(&app_handle)?))
+}
+
+/// The effective value of one setting; null if it is unset and has no default
+#[tauri::command]
+pub async fn get_setting(app_handle: AppHandle, key: String) -> Result<Value, String> {
+    if !SCHEMA.iter().any(|(name, _)| *name == key) {
+        return Err(format!("Unknown setting \"{}\"", key));
+    }
+    Ok(snapshot(&stored(&app_handle)?)
+        .settings
+        .remove(&key)
+        .unwrap_or(Value::Null))
+}
+
+/// Stores one setting (null resets it) and emits `settings-changed`.
+/// Returns the new effective settings.
+#[tauri::command]
+pub async fn set_setting(app_handle: AppHandle, key: String, value: Value) -> Result<Map<String, Value>, String> {
+    let mut values = Map::new();
+    values.insert(key, value);
+    update(&app_handle, values)
+}
+
+/// Stores several settings at once; either all are written or none
+#[tauri::command]
+pub async fn set_settings(app_handle: AppHandle, values: Map<String, Value>) -> Result<Map<String, Value>, String> {
+    update(&app_handle, values)
+}
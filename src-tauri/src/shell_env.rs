@@ -0,0 +1,108 @@
+//! The user's login-shell environment. Apps started from Finder or a desktop
+//! launcher inherit a minimal environment without the PATH additions made
+//! in `.zprofile`, `.bash_profile` and friends, so tools like rust-analyzer
+//! in `~/.cargo/bin` wouldn't be found. The shell is asked once; spawned
+//! processes get the result applied.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+#[cfg(unix)]
+use std::{
+    io::Read,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+/// Brackets `env` output so anything rc files print is ignored
+#[cfg(unix)]
+const MARKER: &str = "__TMD_SHELL_ENV__";
+/// A shell that hangs (e.g. waiting on a prompt) must not block spawning
+#[cfg(unix)]
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Describe the shell process itself, not the programs it will start
+const SKIPPED: &[&str] = &["PWD", "OLDPWD", "SHLVL", "_"];
+
+/// Runs the user's shell as an interactive login shell and reads its `env`
+#[cfg(unix)]
+fn read_login_environment() -> Result<HashMap<String, String>, String> {
+    let shell = std::env::var("SHELL")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string());
+    let script = format!("printf '%s' {0}; env -0; printf '%s' {0}", MARKER);
+    let mut child = Command::new(&shell)
+        .args(["-i", "-l", "-c", &script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", shell, e))?;
+    let mut stdout = child.stdout.take().ok_or_else(|| "No stdout".to_string())?;
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        let _ = tx.send(output);
+    });
+    let output = match rx.recv_timeout(TIMEOUT) {
+        Ok(output) => output,
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("{} did not finish within {:?}", shell, TIMEOUT));
+        }
+    };
+    let _ = child.wait();
+
+    let output = String::from_utf8_lossy(&output);
+    let mut parts = output.split(MARKER);
+    let env = parts
+        .nth(1)
+        .filter(|_| parts.next().is_some())
+        .ok_or_else(|| format!("Unexpected output from {}", shell))?;
+    Ok(env
+        .split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .filter(|(key, _)| !key.is_empty() && !SKIPPED.contains(key))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+/// The app's own environment is what a Windows shell would report anyway
+#[cfg(not(unix))]
+fn read_login_environment() -> Result<HashMap<String, String>, String> {
+    Ok(HashMap::new())
+}
+
+/// The app's environment with the login shell's values on top; resolved on
+/// first use and cached for the life of the app
+pub fn environment() -> &'static HashMap<String, String> {
+    static ENV: OnceLock<HashMap<String, String>> = OnceLock::new();
+    ENV.get_or_init(|| {
+        let mut env: HashMap<String, String> = std::env::vars()
+            .filter(|(key, _)| !SKIPPED.contains(&key.as_str()))
+            .collect();
+        match read_login_environment() {
+            Ok(shell) => env.extend(shell),
+            Err(e) => tracing::warn!("Using the app environment: {}", e),
+        }
+        env
+    })
+}
+
+/// PATH of the login shell, for looking up executables
+pub fn path() -> Option<&'static str> {
+    environment().get("PATH").map(String::as_str)
+}
+
+/// The environment spawned processes get: the app's own with the login
+/// shell's values on top
+#[tauri::command]
+pub async fn get_shell_environment() -> Result<HashMap<String, String>, String> {
+    tauri::async_runtime::spawn_blocking(|| environment().clone())
+        .await
+        .map_err(|e| format!("Failed to read shell environment: {}", e))
+}
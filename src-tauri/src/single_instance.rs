@@ -0,0 +1,153 @@
+//! Keeps one running instance. A second launch, e.g. opening a file from
+//! Explorer, exits right away and hands its arguments to this one: the files
+//! and folders among them are emitted as `open-paths` and the window is
+//! brought to the front. `tmd://` links among them reach `deep_link`
+//! through the deep-link plugin. Paths that arrive before the frontend
+//! listens, starting with the ones the app was launched with, are kept for
+//! `take_launch_paths` instead.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Emitter, Manager, State, Wry};
+
+use crate::fs_guard;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OpenPaths {
+    pub files: Vec<String>,
+    pub folders: Vec<String>,
+}
+
+impl OpenPaths {
+    fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.folders.is_empty()
+    }
+
+    fn extend(&mut self, other: OpenPaths) {
+        self.files.extend(other.files);
+        self.folders.extend(other.folders);
+    }
+}
+
+#[derive(Default)]
+struct Pending {
+    paths: OpenPaths,
+    /// Set once the frontend took the launch paths; later ones are emitted
+    taken: bool,
+}
+
+#[derive(Default)]
+pub struct LaunchState {
+    pending: Mutex<Pending>,
+}
+
+/// Brings the main window to the front, e.g. after a link was opened
+pub(crate) fn focus_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+/// Existing files and folders among `args` (without the program name),
+/// resolved against the launch's working directory. Flags and URLs are
+/// skipped.
+fn collect(app: &AppHandle, args: impl IntoIterator<Item = String>, cwd: &Path) -> OpenPaths {
+    let mut paths = OpenPaths::default();
+    for arg in args {
+        if arg.starts_with('-') || arg.contains("://") {
+            continue;
+        }
+        add(app, &mut paths, cwd.join(arg));
+    }
+    paths
+}
+
+/// The user chose to open `path`, so it is permitted like one picked in a dialog
+fn add(app: &AppHandle, paths: &mut OpenPaths, path: PathBuf) {
+    let path = match path.canonicalize() {
+        Ok(path) => path,
+        Err(e) => {
+            tracing::warn!("Ignoring {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let display = path.to_string_lossy().to_string();
+    if path.is_dir() {
+        fs_guard::grant_folder(app, &path);
+        paths.folders.push(display);
+    } else {
+        fs_guard::grant_file(app, &path);
+        paths.files.push(display);
+    }
+}
+
+fn forward(app: &AppHandle, paths: OpenPaths) {
+    if !paths.is_empty() {
+        tracing::info!("Opening {:?}", paths);
+        let state = app.state::<LaunchState>();
+        let mut pending = match state.pending.lock() {
+            Ok(pending) => pending,
+            Err(e) => {
+                tracing::error!("Failed to lock launch paths: {}", e);
+                return;
+            }
+        };
+        if pending.taken {
+            let _ = app.emit("open-paths", paths);
+        } else {
+            pending.paths.extend(paths);
+        }
+    }
+    focus_main_window(app);
+}
+
+/// Must be the first plugin registered, so a second instance exits before
+/// anything else starts
+pub fn init() -> TauriPlugin<Wry> {
+    tauri_plugin_single_instance::init(|app, args, cwd| {
+        tracing::info!("Second launch with {:?}", args);
+        let paths = collect(app, args.into_iter().skip(1), Path::new(&cwd));
+        forward(app, paths);
+    })
+}
+
+/// Keeps the paths this instance was launched with; called from `setup`
+pub fn init_launch_paths(app: &AppHandle) {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let paths = collect(app, std::env::args().skip(1), &cwd);
+    if let Ok(mut pending) = app.state::<LaunchState>().pending.lock() {
+        pending.paths.extend(paths);
+    }
+}
+
+/// Files opened from Finder, which sends them to the running app
+/// (`RunEvent::Opened`) instead of starting a second instance. Also how
+/// macOS delivers the files the app was launched with.
+#[cfg(target_os = "macos")]
+pub fn open_urls(app: &AppHandle, urls: &[tauri::Url]) {
+    let mut paths = OpenPaths::default();
+    for url in urls.iter().filter(|url| url.scheme() == "file") {
+        if let Ok(path) = url.to_file_path() {
+            add(app, &mut paths, path);
+        }
+    }
+    forward(app, paths);
+}
+
+/// The paths the app was launched with; call once the `open-paths` listener
+/// is in place. Paths opened after this are only emitted.
+#[tauri::command]
+pub async fn take_launch_paths(state: State<'_, LaunchState>) -> Result<OpenPaths, String> {
+    let mut pending = state
+        .pending
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    pending.taken = true;
+    Ok(std::mem::take(&mut pending.paths))
+}
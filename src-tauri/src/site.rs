@@ -0,0 +1,379 @@
+//! Exports a whole markdown workspace as a static site: one HTML page per
+//! document with links between documents rewritten to the generated pages,
+//! referenced assets copied alongside, and an index page listing everything.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::atomic_write;
+use crate::diagrams::{self, DiagramState, DiagramTheme};
+use crate::export::{self, ExportOptions, ExportTheme};
+use crate::front_matter;
+use crate::links;
+use crate::markdown::{self, escape_html, RenderOptions};
+use crate::outline;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SiteExportOptions {
+    /// Workspace folder to export
+    pub root: String,
+    /// Folder the site is written to; created if missing
+    pub output_dir: String,
+    pub theme: ExportTheme,
+    /// Title of the index page; defaults to the workspace folder name
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteExportResult {
+    pub output_dir: String,
+    pub pages: usize,
+    pub assets: usize,
+    /// The generated index page, or None when the workspace has its own index.md
+    pub index: Option<String>,
+    /// Local link targets that were missing or outside the workspace, left as written
+    pub unresolved: Vec<String>,
+}
+
+const SITE_STYLE: &str = r#"
+.site-nav { font-size: 0.9em; margin-bottom: 2em; }
+.site-index ul { list-style: none; padding-left: 1.2em; }
+.site-index > ul { padding-left: 0; }
+"#;
+
+struct Page {
+    /// Path relative to the workspace root
+    rel: PathBuf,
+    title: String,
+}
+
+/// Relative paths of the markdown documents under `root`, skipping the
+/// output folder when it lives inside the workspace
+fn markdown_documents(root: &Path, output_dir: &Path) -> Vec<PathBuf> {
+    let output_dir = output_dir.to_path_buf();
+    let mut documents: Vec<PathBuf> = WalkBuilder::new(root)
+        .filter_entry(move |entry| entry.file_name() != ".git" && entry.path() != output_dir)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()) && links::is_markdown(entry.path()))
+        .filter_map(|entry| entry.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .collect();
+    documents.sort();
+    documents
+}
+
+/// Resolves `.` and `..` lexically; None if the path climbs above its start
+fn normalize_relative(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    Some(out)
+}
+
+/// URL of `target` as seen from a page in `from_dir`, both relative to the site root
+fn relative_url(from_dir: &Path, target: &Path) -> String {
+    let from: Vec<_> = from_dir.components().collect();
+    let to: Vec<_> = target.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        to[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().replace(' ', "%20")),
+    );
+    parts.join("/")
+}
+
+fn html_path(rel: &Path) -> PathBuf {
+    rel.with_extension("html")
+}
+
+fn page_title(rel: &Path, content: &str) -> String {
+    let from_front_matter = front_matter::parse(content)
+        .ok()
+        .and_then(|fm| fm.data.get("title").and_then(|t| t.as_str()).map(str::to_string));
+    from_front_matter
+        .or_else(|| {
+            outline::outline(content)
+                .headings
+                .into_iter()
+                .find(|h| h.level == 1)
+                .map(|h| h.text)
+        })
+        .unwrap_or_else(|| {
+            rel.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+}
+
+/// Offset just past the opening quote of the next `href` or `src` attribute
+fn next_url_attribute(html: &str) -> Option<usize> {
+    [" href=\"", " src=\""]
+        .iter()
+        .filter_map(|marker| html.find(marker).map(|pos| pos + marker.len()))
+        .min()
+}
+
+struct Rewriter<'a> {
+    root: &'a Path,
+    /// Folder of the page being rewritten, relative to the root
+    page_dir: &'a Path,
+    documents: &'a BTreeSet<PathBuf>,
+    assets: &'a mut BTreeSet<PathBuf>,
+    unresolved: &'a mut Vec<String>,
+}
+
+impl Rewriter<'_> {
+    /// New value for a local `href`/`src`, or None to leave it untouched
+    fn rewrite(&mut self, value: &str) -> Option<String> {
+        if value.is_empty() || value.starts_with('#') || links::is_external(value) {
+            return None;
+        }
+        let split = value.find(['?', '#']).unwrap_or(value.len());
+        let (path, suffix) = value.split_at(split);
+        let decoded = links::percent_decode(path);
+        let joined = match decoded.strip_prefix('/') {
+            // Site-style absolute links are relative to the workspace root
+            Some(absolute) => PathBuf::from(absolute),
+            None => self.page_dir.join(&decoded),
+        };
+
+        let Some(target) = normalize_relative(&joined).filter(|t| !t.as_os_str().is_empty()) else {
+            self.unresolved.push(value.to_string());
+            return None;
+        };
+        if self.documents.contains(&target) {
+            return Some(format!(
+                "{}{}",
+                relative_url(self.page_dir, &html_path(&target)),
+                suffix
+            ));
+        }
+        if self.root.join(&target).is_file() {
+            let url = relative_url(self.page_dir, &target);
+            self.assets.insert(target);
+            return Some(format!("{}{}", url, suffix));
+        }
+        self.unresolved.push(value.to_string());
+        None
+    }
+
+    /// Rewrites every `href="..."` and `src="..."` in rendered HTML
+    fn rewrite_html(&mut self, html: &str) -> String {
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(start) = next_url_attribute(rest) {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find('"').unwrap_or(rest.len());
+            // The renderer escaped the attribute; undo that to get the path back
+            let value = rest[..end].replace("&amp;", "&").replace("&quot;", "\"");
+            match self.rewrite(&value) {
+                Some(url) => out.push_str(&escape_html(&url)),
+                None => out.push_str(&rest[..end]),
+            }
+            rest = &rest[end..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Folder tree of pages for the index
+#[derive(Default)]
+struct IndexTree {
+    pages: Vec<(String, String)>,
+    folders: BTreeMap<String, IndexTree>,
+}
+
+impl IndexTree {
+    fn insert(&mut self, page: &Page) {
+        let mut node = self;
+        if let Some(parent) = page.rel.parent() {
+            for part in parent.components() {
+                node = node
+                    .folders
+                    .entry(part.as_os_str().to_string_lossy().to_string())
+                    .or_default();
+            }
+        }
+        node.pages
+            .push((relative_url(Path::new(""), &html_path(&page.rel)), page.title.clone()));
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push_str("<ul>\n");
+        for (url, title) in &self.pages {
+            out.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                escape_html(url),
+                escape_html(title)
+            ));
+        }
+        for (name, folder) in &self.folders {
+            out.push_str(&format!("<li><strong>{}/</strong>\n", escape_html(name)));
+            folder.write(out);
+            out.push_str("</li>\n");
+        }
+        out.push_str("</ul>\n");
+    }
+}
+
+fn export_site(app_handle: &AppHandle, options: &SiteExportOptions) -> Result<SiteExportResult, String> {
+    let root = PathBuf::from(&options.root);
+    if !root.is_dir() {
+        return Err(format!("Not a folder: {}", options.root));
+    }
+    if options.output_dir.is_empty() {
+        return Err("An output folder is required".to_string());
+    }
+    let output_dir = PathBuf::from(&options.output_dir);
+    fs::create_dir_all(&output_dir).map_err(|e| format!("Failed to create output folder: {}", e))?;
+    let output_dir = output_dir.canonicalize().unwrap_or(output_dir);
+    let root = root.canonicalize().unwrap_or(root);
+
+    let progress_key = &options.root;
+    export::emit_progress(app_handle, progress_key, "scanning", 0.0);
+    let documents: BTreeSet<PathBuf> = markdown_documents(&root, &output_dir).into_iter().collect();
+    let generate_index = !documents.contains(Path::new("index.md"));
+    let site_title = options.title.clone().unwrap_or_else(|| {
+        root.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Index".to_string())
+    });
+
+    let document_options = ExportOptions {
+        theme: options.theme,
+        ..ExportOptions::default()
+    };
+    let render_options = RenderOptions {
+        math: true,
+        ..RenderOptions::default()
+    };
+    let diagram_theme = match options.theme {
+        ExportTheme::Light => DiagramTheme::Light,
+        ExportTheme::Dark => DiagramTheme::Dark,
+    };
+    let diagram_state = app_handle.state::<DiagramState>();
+
+    let mut pages = Vec::new();
+    let mut assets = BTreeSet::new();
+    let mut unresolved = Vec::new();
+    let total = documents.len().max(1);
+    for (done, rel) in documents.iter().enumerate() {
+        let Ok(content) = fs::read_to_string(root.join(rel)) else {
+            continue;
+        };
+        let title = page_title(rel, &content);
+        let body_text = front_matter::split(&content)
+            .map(|(_, _, start)| &content[start..])
+            .unwrap_or(&content);
+        let body = markdown::render(body_text, &render_options);
+        let body = diagrams::render_in_html(app_handle, &diagram_state, &body, diagram_theme);
+
+        let page_dir = rel.parent().unwrap_or(Path::new(""));
+        let mut rewriter = Rewriter {
+            root: &root,
+            page_dir,
+            documents: &documents,
+            assets: &mut assets,
+            unresolved: &mut unresolved,
+        };
+        let mut body = rewriter.rewrite_html(&body);
+        if generate_index {
+            let home = relative_url(page_dir, Path::new("index.html"));
+            body = format!(
+                "<nav class=\"site-nav\"><a href=\"{}\">{}</a></nav>\n{}",
+                escape_html(&home),
+                escape_html(&site_title),
+                body
+            );
+        }
+
+        let html = export::html_document(&title, &body, &document_options, SITE_STYLE);
+        let target = output_dir.join(html_path(rel));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        atomic_write::write_atomic(&target, html.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        pages.push(Page {
+            rel: rel.clone(),
+            title,
+        });
+        export::emit_progress(
+            app_handle,
+            progress_key,
+            "pages",
+            0.8 * (done + 1) as f32 / total as f32,
+        );
+    }
+
+    export::emit_progress(app_handle, progress_key, "assets", 0.8);
+    let mut copied = 0;
+    for asset in &assets {
+        let target = output_dir.join(asset);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::copy(root.join(asset), &target).map_err(|e| format!("Failed to copy {}: {}", asset.display(), e))?;
+        copied += 1;
+    }
+
+    let index = if generate_index {
+        export::emit_progress(app_handle, progress_key, "index", 0.95);
+        let mut tree = IndexTree::default();
+        for page in &pages {
+            tree.insert(page);
+        }
+        let mut body = format!("<h1>{}</h1>\n<nav class=\"site-index\">\n", escape_html(&site_title));
+        tree.write(&mut body);
+        body.push_str("</nav>\n");
+        let html = export::html_document(&site_title, &body, &document_options, SITE_STYLE);
+        let target = output_dir.join("index.html");
+        atomic_write::write_atomic(&target, html.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        Some(target.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    unresolved.sort();
+    unresolved.dedup();
+    export::emit_progress(app_handle, progress_key, "done", 1.0);
+    Ok(SiteExportResult {
+        output_dir: output_dir.to_string_lossy().to_string(),
+        pages: pages.len(),
+        assets: copied,
+        index,
+        unresolved,
+    })
+}
+
+/// Converts every markdown document in a workspace to HTML, keeping the folder
+/// layout so relative links still work. Progress is emitted on
+/// `export-progress` with the workspace root as the path.
+#[tauri::command]
+pub async fn export_workspace(app_handle: AppHandle, options: SiteExportOptions) -> Result<SiteExportResult, String> {
+    tauri::async_runtime::spawn_blocking(move || export_site(&app_handle, &options))
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))?
+}
@@ -0,0 +1,790 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use encoding_rs::Encoding;
+use regex::Regex;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::atomic_write::write_atomic;
+
+const SPELLCHECK_DIR: &str = "spellcheck";
+const USER_WORDS_FILE: &str = "user-words.txt";
+const MAX_SUGGESTIONS: usize = 5;
+// Two-edit suggestions are only tried for short words; the candidate count grows quadratically
+const MAX_EDIT2_LEN: usize = 12;
+
+/// Where hunspell/myspell dictionaries are usually installed
+fn dictionary_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(data) = app.path().app_data_dir() {
+        dirs.push(data.join(SPELLCHECK_DIR));
+    }
+    if cfg!(target_os = "macos") {
+        if let Ok(home) = app.path().home_dir() {
+            dirs.push(home.join("Library/Spelling"));
+        }
+        dirs.push(PathBuf::from("/Library/Spelling"));
+    }
+    if cfg!(target_os = "linux") {
+        for dir in ["/usr/share/hunspell", "/usr/share/myspell", "/usr/share/myspell/dicts"] {
+            dirs.push(PathBuf::from(dir));
+        }
+    }
+    dirs
+}
+
+type Flag = u32;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FlagMode {
+    /// One character per flag (also used for FLAG UTF-8)
+    Char,
+    /// Two characters per flag
+    Long,
+    /// Comma-separated numbers
+    Num,
+}
+
+fn parse_flags(text: &str, mode: FlagMode) -> Vec<Flag> {
+    match mode {
+        FlagMode::Char => text.chars().map(|c| c as Flag).collect(),
+        FlagMode::Long => {
+            let chars: Vec<char> = text.chars().collect();
+            chars
+                .chunks(2)
+                .map(|pair| ((pair[0] as Flag) << 16) | pair.get(1).map_or(0, |c| *c as Flag))
+                .collect()
+        }
+        FlagMode::Num => text.split(',').filter_map(|n| n.trim().parse().ok()).collect(),
+    }
+}
+
+#[derive(Debug)]
+enum CondPart {
+    Any,
+    Char(char),
+    Set { negated: bool, chars: Vec<char> },
+}
+
+impl CondPart {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CondPart::Any => true,
+            CondPart::Char(expected) => *expected == c,
+            CondPart::Set { negated, chars } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+fn parse_condition(text: &str) -> Vec<CondPart> {
+    if text == "." {
+        return Vec::new();
+    }
+    let mut parts = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => parts.push(CondPart::Any),
+            '[' => {
+                let mut set: Vec<char> = chars.by_ref().take_while(|c| *c != ']').collect();
+                let negated = set.first() == Some(&'^');
+                if negated {
+                    set.remove(0);
+                }
+                parts.push(CondPart::Set { negated, chars: set });
+            }
+            c => parts.push(CondPart::Char(c)),
+        }
+    }
+    parts
+}
+
+struct AffixRule {
+    strip: String,
+    add: String,
+    /// Flags of further suffixes that may follow this one
+    continuation: Vec<Flag>,
+    condition: Vec<CondPart>,
+}
+
+struct AffixClass {
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+impl AffixRule {
+    fn apply_suffix(&self, word: &str) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < self.condition.len() {
+            return None;
+        }
+        let tail = &chars[chars.len() - self.condition.len()..];
+        if !self.condition.iter().zip(tail).all(|(part, c)| part.matches(*c)) {
+            return None;
+        }
+        let stem = word.strip_suffix(self.strip.as_str())?;
+        Some(format!("{}{}", stem, self.add))
+    }
+
+    fn apply_prefix(&self, word: &str) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < self.condition.len() {
+            return None;
+        }
+        if !self.condition.iter().zip(&chars).all(|(part, c)| part.matches(*c)) {
+            return None;
+        }
+        let stem = word.strip_prefix(self.strip.as_str())?;
+        Some(format!("{}{}", self.add, stem))
+    }
+}
+
+/// A hunspell dictionary expanded to the full list of accepted word forms.
+/// Compounding rules are not supported.
+struct Dictionary {
+    words: HashSet<String>,
+    forbidden: HashSet<String>,
+    /// Characters to try when generating suggestions, most likely first
+    try_chars: Vec<char>,
+    /// Common misspellings (from, to)
+    replacements: Vec<(String, String)>,
+}
+
+struct AffixFile {
+    encoding: &'static Encoding,
+    mode: FlagMode,
+    aliases: Vec<Vec<Flag>>,
+    prefixes: HashMap<Flag, AffixClass>,
+    suffixes: HashMap<Flag, AffixClass>,
+    need_affix: Option<Flag>,
+    forbidden: Option<Flag>,
+    only_in_compound: Option<Flag>,
+    try_chars: Vec<char>,
+    replacements: Vec<(String, String)>,
+}
+
+fn decode_file(bytes: &[u8], encoding: &'static Encoding) -> String {
+    encoding.decode_with_bom_removal(bytes).0.into_owned()
+}
+
+fn parse_aff(bytes: &[u8]) -> AffixFile {
+    // SET decides how the rest of the file (and the .dic) is decoded
+    let encoding = String::from_utf8_lossy(bytes)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("SET ").map(|s| s.trim().to_string()))
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let text = decode_file(bytes, encoding);
+
+    let mut aff = AffixFile {
+        encoding,
+        mode: FlagMode::Char,
+        aliases: Vec::new(),
+        prefixes: HashMap::new(),
+        suffixes: HashMap::new(),
+        need_affix: None,
+        forbidden: None,
+        only_in_compound: None,
+        try_chars: Vec::new(),
+        replacements: Vec::new(),
+    };
+    // Flags are parsed after FLAG is known, so keep the raw lines until the end
+    let mut affix_lines: Vec<Vec<&str>> = Vec::new();
+    let mut special: Vec<(&str, &str)> = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["FLAG", mode, ..] => {
+                aff.mode = match *mode {
+                    "long" => FlagMode::Long,
+                    "num" => FlagMode::Num,
+                    _ => FlagMode::Char,
+                }
+            }
+            ["TRY", chars, ..] => aff.try_chars = chars.chars().collect(),
+            ["REP", from, to, ..] => aff
+                .replacements
+                .push((from.replace('_', " "), to.replace('_', " "))),
+            ["AF", flags, ..] if !flags.chars().all(|c| c.is_ascii_digit()) => affix_lines.push(fields),
+            ["PFX" | "SFX", ..] if fields.len() >= 4 => affix_lines.push(fields),
+            [name @ ("NEEDAFFIX" | "PSEUDOROOT" | "FORBIDDENWORD" | "ONLYINCOMPOUND"), flag, ..] => {
+                special.push((name, flag))
+            }
+            _ => {}
+        }
+    }
+
+    let single = |flag: &str, mode| parse_flags(flag, mode).first().copied();
+    for (name, flag) in special {
+        match name {
+            "NEEDAFFIX" | "PSEUDOROOT" => aff.need_affix = single(flag, aff.mode),
+            "FORBIDDENWORD" => aff.forbidden = single(flag, aff.mode),
+            _ => aff.only_in_compound = single(flag, aff.mode),
+        }
+    }
+
+    for fields in affix_lines {
+        if fields[0] == "AF" {
+            aff.aliases.push(parse_flags(fields[1], aff.mode));
+            continue;
+        }
+        let Some(flag) = single(fields[1], aff.mode) else {
+            continue;
+        };
+        let classes = if fields[0] == "PFX" { &mut aff.prefixes } else { &mut aff.suffixes };
+        // The header line is "SFX flag Y|N count"; rule lines have strip/add/condition
+        if fields.len() == 4 && matches!(fields[2], "Y" | "N") && fields[3].chars().all(|c| c.is_ascii_digit()) {
+            classes.entry(flag).or_insert(AffixClass {
+                cross_product: fields[2] == "Y",
+                rules: Vec::new(),
+            });
+            continue;
+        }
+        let Some(class) = classes.get_mut(&flag) else {
+            continue;
+        };
+        let zero = |s: &str| if s == "0" { String::new() } else { s.to_string() };
+        let (add, continuation) = match fields[3].split_once('/') {
+            Some((add, flags)) => (zero(add), parse_flags(flags, aff.mode)),
+            None => (zero(fields[3]), Vec::new()),
+        };
+        class.rules.push(AffixRule {
+            strip: zero(fields[2]),
+            add,
+            continuation,
+            condition: parse_condition(fields.get(4).copied().unwrap_or(".")),
+        });
+    }
+    aff
+}
+
+impl AffixFile {
+    fn word_flags(&self, text: &str) -> Vec<Flag> {
+        if !self.aliases.is_empty() {
+            if let Ok(index) = text.parse::<usize>() {
+                return self.aliases.get(index.wrapping_sub(1)).cloned().unwrap_or_default();
+            }
+        }
+        parse_flags(text, self.mode)
+    }
+
+    /// Every form of `stem` its flags allow
+    fn expand(&self, stem: &str, flags: &[Flag], out: &mut Vec<String>) {
+        if !self.need_affix.is_some_and(|f| flags.contains(&f)) {
+            out.push(stem.to_string());
+        }
+        let prefixes: Vec<&AffixClass> = flags.iter().filter_map(|f| self.prefixes.get(f)).collect();
+        for prefix in &prefixes {
+            out.extend(prefix.rules.iter().filter_map(|r| r.apply_prefix(stem)));
+        }
+
+        for flag in flags {
+            let Some(class) = self.suffixes.get(flag) else {
+                continue;
+            };
+            for rule in &class.rules {
+                let Some(form) = rule.apply_suffix(stem) else {
+                    continue;
+                };
+                // One level of twofold suffixes, e.g. "-ize" then "-s"
+                for next in rule.continuation.iter().filter_map(|f| self.suffixes.get(f)) {
+                    out.extend(next.rules.iter().filter_map(|r| r.apply_suffix(&form)));
+                }
+                if class.cross_product {
+                    for prefix in prefixes.iter().filter(|p| p.cross_product) {
+                        out.extend(prefix.rules.iter().filter_map(|r| r.apply_prefix(&form)));
+                    }
+                }
+                out.push(form);
+            }
+        }
+    }
+}
+
+fn load_dictionary(aff_path: &Path, dic_path: &Path) -> Result<Dictionary, String> {
+    let aff_bytes = fs::read(aff_path).map_err(|e| format!("Failed to read {}: {}", aff_path.display(), e))?;
+    let dic_bytes = fs::read(dic_path).map_err(|e| format!("Failed to read {}: {}", dic_path.display(), e))?;
+    let aff = parse_aff(&aff_bytes);
+    let dic = decode_file(&dic_bytes, aff.encoding);
+
+    let mut words = HashSet::new();
+    let mut forbidden = HashSet::new();
+    let mut forms = Vec::new();
+    // The first line is the approximate word count
+    for line in dic.lines().skip(1) {
+        let entry = line.split(['\t', ' ']).next().unwrap_or("").trim();
+        if entry.is_empty() || entry.starts_with('#') {
+            continue;
+        }
+        // A slash inside a word is escaped as "\/"
+        let (stem, flags) = match entry.replace("\\/", "\u{0}").split_once('/') {
+            Some((stem, flags)) => (stem.replace('\u{0}', "/"), aff.word_flags(flags)),
+            None => (entry.replace("\\/", "/"), Vec::new()),
+        };
+        if aff.only_in_compound.is_some_and(|f| flags.contains(&f)) {
+            continue;
+        }
+        if aff.forbidden.is_some_and(|f| flags.contains(&f)) {
+            forbidden.insert(stem);
+            continue;
+        }
+        forms.clear();
+        aff.expand(&stem, &flags, &mut forms);
+        words.extend(forms.drain(..));
+    }
+
+    let mut try_chars = aff.try_chars;
+    if try_chars.is_empty() {
+        let mut letters: Vec<char> = words.iter().flat_map(|w| w.chars()).filter(|c| c.is_lowercase()).collect();
+        letters.sort_unstable();
+        letters.dedup();
+        try_chars = letters;
+    }
+    Ok(Dictionary {
+        words,
+        forbidden,
+        try_chars,
+        replacements: aff.replacements,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Casing {
+    Lower,
+    Capitalized,
+    Upper,
+    Mixed,
+}
+
+fn casing(word: &str) -> Casing {
+    let mut chars = word.chars().filter(|c| c.is_alphabetic());
+    let Some(first) = chars.next() else {
+        return Casing::Lower;
+    };
+    let rest: Vec<char> = chars.collect();
+    let rest_lower = rest.iter().all(|c| !c.is_uppercase());
+    let rest_upper = rest.iter().all(|c| !c.is_lowercase());
+    match (first.is_uppercase(), rest_lower, rest_upper) {
+        (false, true, _) => Casing::Lower,
+        (true, true, _) => Casing::Capitalized,
+        (true, _, true) => Casing::Upper,
+        _ => Casing::Mixed,
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn recase(word: &str, like: Casing) -> String {
+    match like {
+        Casing::Capitalized => capitalize(word),
+        Casing::Upper => word.to_uppercase(),
+        _ => word.to_string(),
+    }
+}
+
+impl Dictionary {
+    fn knows(&self, word: &str) -> bool {
+        if self.forbidden.contains(word) {
+            return false;
+        }
+        if self.words.contains(word) {
+            return true;
+        }
+        // "Hello" at the start of a sentence or "HELLO" may be written for "hello"/"Paris"
+        match casing(word) {
+            Casing::Capitalized => self.words.contains(&word.to_lowercase()),
+            Casing::Upper => {
+                let lower = word.to_lowercase();
+                self.words.contains(&lower) || self.words.contains(&capitalize(&lower))
+            }
+            _ => false,
+        }
+    }
+
+    fn edits(&self, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut out = Vec::new();
+        for i in 0..chars.len() {
+            let mut deleted = chars.clone();
+            deleted.remove(i);
+            out.push(deleted.into_iter().collect());
+        }
+        for i in 0..chars.len().saturating_sub(1) {
+            let mut swapped = chars.clone();
+            swapped.swap(i, i + 1);
+            out.push(swapped.into_iter().collect());
+        }
+        for &c in &self.try_chars {
+            for i in 0..chars.len() {
+                if chars[i] != c {
+                    let mut replaced = chars.clone();
+                    replaced[i] = c;
+                    out.push(replaced.into_iter().collect());
+                }
+            }
+            for i in 0..=chars.len() {
+                let mut inserted = chars.clone();
+                inserted.insert(i, c);
+                out.push(inserted.into_iter().collect());
+            }
+        }
+        out
+    }
+
+    fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let case = casing(word);
+        let lower = word.to_lowercase();
+        let mut found: Vec<String> = Vec::new();
+        let push = |candidate: String, found: &mut Vec<String>| {
+            if found.len() < limit && !found.contains(&candidate) && candidate != word {
+                found.push(candidate);
+            }
+        };
+
+        // Proper nouns written in lowercase: "paris" -> "Paris"
+        if self.words.contains(&capitalize(&lower)) {
+            push(capitalize(&lower), &mut found);
+        }
+        for (from, to) in &self.replacements {
+            for (i, _) in lower.match_indices(from.as_str()) {
+                let candidate = format!("{}{}{}", &lower[..i], to, &lower[i + from.len()..]);
+                if candidate.split(' ').all(|part| self.knows(part)) {
+                    push(recase(&candidate, case), &mut found);
+                }
+            }
+        }
+        let edits = self.edits(&lower);
+        for candidate in edits.iter().filter(|c| self.knows(c)) {
+            push(recase(candidate, case), &mut found);
+        }
+        for candidate in edits.iter().filter(|c| !self.knows(c)) {
+            if self.words.contains(&capitalize(candidate)) {
+                push(capitalize(candidate), &mut found);
+            }
+        }
+        // Missing space: "thisis" -> "this is"
+        for (i, _) in lower.char_indices().skip(1) {
+            let (left, right) = lower.split_at(i);
+            if left.chars().count() > 1 && right.chars().count() > 1 && self.knows(left) && self.knows(right) {
+                push(recase(&format!("{} {}", left, right), case), &mut found);
+            }
+        }
+        if found.is_empty() && lower.chars().count() <= MAX_EDIT2_LEN {
+            for first in &edits {
+                for candidate in self.edits(first).iter().filter(|c| self.words.contains(*c)) {
+                    push(recase(candidate, case), &mut found);
+                }
+                if found.len() >= limit {
+                    break;
+                }
+            }
+        }
+        found
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DictionaryInfo {
+    /// e.g. "en_US"
+    pub language: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpellingIssue {
+    /// Offsets into the content in UTF-16 code units, like JS string indices
+    pub start: usize,
+    pub end: usize,
+    /// 1-based
+    pub line: usize,
+    pub word: String,
+    pub suggestions: Vec<String>,
+}
+
+/// Loaded dictionaries by language, and the user's own words
+#[derive(Default)]
+pub struct SpellcheckState {
+    dictionaries: Mutex<HashMap<String, Arc<Dictionary>>>,
+    user_words: Mutex<Option<HashSet<String>>>,
+}
+
+fn find_dictionaries(app: &AppHandle) -> Vec<DictionaryInfo> {
+    let mut found: Vec<DictionaryInfo> = Vec::new();
+    for dir in dictionary_dirs(app) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            let is_dic = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("dic"));
+            if !is_dic || !path.with_extension("aff").is_file() {
+                continue;
+            }
+            let language = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            // Earlier folders (the app's own first) win
+            if !found.iter().any(|d| d.language == language) {
+                found.push(DictionaryInfo {
+                    language,
+                    path: path.to_string_lossy().to_string(),
+                });
+            }
+        }
+    }
+    found.sort_by(|a, b| a.language.cmp(&b.language));
+    found
+}
+
+/// Picks the dictionary for `language` ("en-US", "en_US" or just "en")
+fn resolve_language<'a>(available: &'a [DictionaryInfo], language: &str) -> Option<&'a DictionaryInfo> {
+    let wanted = language.replace('-', "_");
+    available
+        .iter()
+        .find(|d| d.language.eq_ignore_ascii_case(&wanted))
+        .or_else(|| {
+            let base = wanted.split('_').next().unwrap_or(&wanted).to_lowercase();
+            available
+                .iter()
+                .find(|d| d.language.to_lowercase().split('_').next() == Some(base.as_str()))
+        })
+}
+
+fn dictionary(app: &AppHandle, state: &SpellcheckState, language: &str) -> Result<Arc<Dictionary>, String> {
+    let available = find_dictionaries(app);
+    let info = resolve_language(&available, language).ok_or_else(|| {
+        format!(
+            "No dictionary for {}; add {0}.dic and {0}.aff to the spellcheck folder in app data",
+            language
+        )
+    })?;
+    let mut loaded = state
+        .dictionaries
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(dictionary) = loaded.get(&info.language) {
+        return Ok(dictionary.clone());
+    }
+    let dic = PathBuf::from(&info.path);
+    let dictionary = Arc::new(load_dictionary(&dic.with_extension("aff"), &dic)?);
+    tracing::info!("Loaded {} ({} forms)", info.language, dictionary.words.len());
+    loaded.insert(info.language.clone(), dictionary.clone());
+    Ok(dictionary)
+}
+
+fn user_words_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(SPELLCHECK_DIR).join(USER_WORDS_FILE))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+fn with_user_words<T>(
+    app: &AppHandle,
+    state: &SpellcheckState,
+    f: impl FnOnce(&mut HashSet<String>) -> T,
+) -> Result<T, String> {
+    let mut words = state.user_words.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    let words = match &mut *words {
+        Some(words) => words,
+        None => {
+            let loaded = fs::read_to_string(user_words_path(app)?)
+                .map(|text| text.lines().map(str::trim).filter(|w| !w.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default();
+            words.insert(loaded)
+        }
+    };
+    Ok(f(words))
+}
+
+fn save_user_words(app: &AppHandle, words: &HashSet<String>) -> Result<(), String> {
+    let path = user_words_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create spellcheck directory: {}", e))?;
+    }
+    let mut sorted: Vec<&String> = words.iter().collect();
+    sorted.sort();
+    let text: String = sorted.iter().map(|w| format!("{}\n", w)).collect();
+    write_atomic(&path, text.as_bytes()).map_err(|e| format!("Failed to save user dictionary: {}", e))
+}
+
+/// Byte ranges of Markdown that isn't prose: front matter, code, URLs, HTML
+fn markdown_exclusions(content: &str) -> Vec<(usize, usize)> {
+    let inline = Regex::new(
+        r"(?m)`+[^`\n]*`+|\]\([^)\n]*\)|<[^>\n]+>|https?://\S+|www\.\S+|[\w.+-]+@[\w-]+\.[\w.]+|^\s*\[[^\]\n]+\]:.*$",
+    )
+    .expect("valid regex");
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+
+    let mut offset = 0;
+    let mut fence: Option<(usize, String)> = None;
+    let mut in_front_matter = content.starts_with("---\n") || content.starts_with("---\r\n");
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim();
+        let start = offset;
+        offset += line.len();
+        if in_front_matter {
+            ranges.push((start, offset));
+            if index > 0 && (trimmed == "---" || trimmed == "...") {
+                in_front_matter = false;
+            }
+            continue;
+        }
+        let marker: String = trimmed.chars().take_while(|c| *c == '`' || *c == '~').collect();
+        match &fence {
+            Some((_, open)) => {
+                ranges.push((start, offset));
+                if trimmed.starts_with(open.as_str()) && trimmed.chars().all(|c| c == open.chars().next().unwrap_or('`')) {
+                    fence = None;
+                }
+                continue;
+            }
+            None if marker.len() >= 3 && marker.chars().all(|c| c == marker.chars().next().unwrap_or('`')) => {
+                fence = Some((start, marker));
+                ranges.push((start, offset));
+                continue;
+            }
+            None => {}
+        }
+        for m in inline.find_iter(line) {
+            ranges.push((start + m.start(), start + m.end()));
+        }
+    }
+    ranges.sort_unstable();
+    ranges
+}
+
+/// Whether a token looks like code or a name rather than a word to check
+fn skip_token(word: &str) -> bool {
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    letters < 2
+        || word.chars().any(|c| c.is_ascii_digit() || c == '_')
+        // CJK text has no spelling dictionary and no spaces between words
+        || word.chars().any(|c| matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF))
+        // camelCase and ALLCAPS acronyms
+        || matches!(casing(word), Casing::Mixed | Casing::Upper)
+}
+
+fn check(
+    content: &str,
+    markdown: bool,
+    dictionary: &Dictionary,
+    user_words: &HashSet<String>,
+) -> Vec<SpellingIssue> {
+    let exclusions = if markdown { markdown_exclusions(content) } else { Vec::new() };
+    let mut next_exclusion = 0;
+    let mut issues = Vec::new();
+    let mut suggestion_cache: HashMap<String, Vec<String>> = HashMap::new();
+
+    // (byte start, utf16 start, line) of the word being collected
+    let mut word_start: Option<(usize, usize, usize)> = None;
+    let mut utf16 = 0;
+    let mut line = 1;
+    let mut finish = |start: (usize, usize, usize), end: usize, issues: &mut Vec<SpellingIssue>| {
+        let raw = &content[start.0..end];
+        let trimmed = raw.trim_matches(['\'', '\u{2019}']);
+        let lead = raw.len() - raw.trim_start_matches(['\'', '\u{2019}']).len();
+        if trimmed.is_empty() || skip_token(trimmed) {
+            return;
+        }
+        let word = trimmed.replace('\u{2019}', "'");
+        if dictionary.knows(&word)
+            || user_words.contains(&word)
+            || user_words.contains(&word.to_lowercase())
+            || word.strip_suffix("'s").is_some_and(|stem| dictionary.knows(stem))
+        {
+            return;
+        }
+        let start16 = start.1 + raw[..lead].encode_utf16().count();
+        let suggestions = suggestion_cache
+            .entry(word.clone())
+            .or_insert_with(|| dictionary.suggest(&word, MAX_SUGGESTIONS))
+            .clone();
+        issues.push(SpellingIssue {
+            start: start16,
+            end: start16 + trimmed.encode_utf16().count(),
+            line: start.2,
+            word: trimmed.to_string(),
+            suggestions,
+        });
+    };
+
+    for (i, c) in content.char_indices() {
+        while next_exclusion < exclusions.len() && exclusions[next_exclusion].1 <= i {
+            next_exclusion += 1;
+        }
+        let excluded = exclusions.get(next_exclusion).is_some_and(|(s, e)| *s <= i && i < *e);
+        let in_word = !excluded && (c.is_alphanumeric() || c == '_' || c == '\'' || c == '\u{2019}');
+        match (in_word, word_start) {
+            (true, None) => word_start = Some((i, utf16, line)),
+            (false, Some(start)) => {
+                finish(start, i, &mut issues);
+                word_start = None;
+            }
+            _ => {}
+        }
+        if c == '\n' {
+            line += 1;
+        }
+        utf16 += c.len_utf16();
+    }
+    if let Some(start) = word_start {
+        finish(start, content.len(), &mut issues);
+    }
+    issues
+}
+
+/// Installed hunspell dictionaries: the app's spellcheck folder, then the system's
+#[tauri::command]
+pub async fn list_dictionaries(app_handle: AppHandle) -> Result<Vec<DictionaryInfo>, String> {
+    Ok(find_dictionaries(&app_handle))
+}
+
+/// Misspelled words in `content` with suggestions. Markdown code, URLs,
+/// HTML and front matter are skipped unless `markdown` is false.
+#[tauri::command]
+pub async fn check_text(
+    app_handle: AppHandle,
+    content: String,
+    language: String,
+    markdown: Option<bool>,
+) -> Result<Vec<SpellingIssue>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app_handle.state::<SpellcheckState>();
+        let dictionary = dictionary(&app_handle, &state, &language)?;
+        let user_words = with_user_words(&app_handle, &state, |words| words.clone())?;
+        Ok(check(&content, markdown.unwrap_or(true), &dictionary, &user_words))
+    })
+    .await
+    .map_err(|e| format!("Spellcheck task failed: {}", e))?
+}
+
+/// Adds a word to the user dictionary, shared by all languages
+#[tauri::command]
+pub async fn add_word(app_handle: AppHandle, state: State<'_, SpellcheckState>, word: String) -> Result<(), String> {
+    let word = word.trim().to_string();
+    if word.is_empty() || word.contains(char::is_whitespace) {
+        return Err("A dictionary entry must be a single word".to_string());
+    }
+    with_user_words(&app_handle, &state, |words| {
+        words.insert(word);
+        save_user_words(&app_handle, words)
+    })?
+}
+
+#[tauri::command]
+pub async fn remove_word(app_handle: AppHandle, state: State<'_, SpellcheckState>, word: String) -> Result<(), String> {
+    with_user_words(&app_handle, &state, |words| {
+        if words.remove(word.trim()) {
+            save_user_words(&app_handle, words)
+        } else {
+            Ok(())
+        }
+    })?
+}
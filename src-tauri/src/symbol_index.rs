@@ -0,0 +1,288 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use ignore::WalkBuilder;
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher, Utf32Str};
+use regex::Regex;
+use serde::Serialize;
+use tauri::State;
+
+use crate::file_info::is_probably_binary;
+use crate::fs_guard::FsGuardState;
+
+const DEFAULT_QUERY_LIMIT: usize = 100;
+// Larger files are almost always generated or minified
+const MAX_INDEXED_FILE_LEN: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+    Struct,
+    Enum,
+    Interface,
+    Trait,
+    Type,
+    Module,
+    Constant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub path: String,
+    pub relative_path: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 0-based column in characters of the symbol name
+    pub column: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SymbolMatch {
+    #[serde(flatten)]
+    pub symbol: Symbol,
+    pub score: u32,
+    /// Char positions in `name` that matched, for highlighting
+    pub indices: Vec<u32>,
+}
+
+/// Declarations found under a workspace root, for languages with or without an LSP
+pub struct SymbolIndex {
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Default)]
+pub struct SymbolIndexState {
+    index: Mutex<Option<Arc<SymbolIndex>>>,
+}
+
+/// ctags-style declaration patterns; the `name` group is the symbol
+struct LanguageRules {
+    extensions: &'static [&'static str],
+    rules: Vec<(Regex, SymbolKind)>,
+}
+
+fn rules(patterns: &[(&str, SymbolKind)]) -> Vec<(Regex, SymbolKind)> {
+    patterns
+        .iter()
+        .map(|(pattern, kind)| (Regex::new(pattern).expect("invalid symbol pattern"), *kind))
+        .collect()
+}
+
+fn languages() -> &'static [LanguageRules] {
+    static LANGUAGES: OnceLock<Vec<LanguageRules>> = OnceLock::new();
+    LANGUAGES.get_or_init(|| {
+        use SymbolKind::*;
+        const RUST_VIS: &str = r"^\s*(?:pub(?:\([^)]*\))?\s+)?";
+        vec![
+            LanguageRules {
+                extensions: &["rs"],
+                rules: rules(&[
+                    (&format!(r"{}(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?(?:extern\s+\S+\s+)?fn\s+(?P<name>\w+)", RUST_VIS), Function),
+                    (&format!(r"{}struct\s+(?P<name>\w+)", RUST_VIS), Struct),
+                    (&format!(r"{}enum\s+(?P<name>\w+)", RUST_VIS), Enum),
+                    (&format!(r"{}(?:unsafe\s+)?trait\s+(?P<name>\w+)", RUST_VIS), Trait),
+                    (&format!(r"{}type\s+(?P<name>\w+)", RUST_VIS), Type),
+                    (&format!(r"{}mod\s+(?P<name>\w+)", RUST_VIS), Module),
+                    (&format!(r"{}(?:const|static)\s+(?:mut\s+)?(?P<name>[A-Z_][A-Z0-9_]*)\s*:", RUST_VIS), Constant),
+                    (r"^\s*macro_rules!\s*(?P<name>\w+)", Function),
+                ]),
+            },
+            LanguageRules {
+                extensions: &["go"],
+                rules: rules(&[
+                    (r"^func\s+\([^)]*\)\s*(?P<name>\w+)", Method),
+                    (r"^func\s+(?P<name>\w+)", Function),
+                    (r"^type\s+(?P<name>\w+)\s+struct\b", Struct),
+                    (r"^type\s+(?P<name>\w+)\s+interface\b", Interface),
+                    (r"^type\s+(?P<name>\w+)", Type),
+                ]),
+            },
+            LanguageRules {
+                extensions: &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+                rules: rules(&[
+                    (r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*(?P<name>[\w$]+)", Function),
+                    (r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?class\s+(?P<name>[\w$]+)", Class),
+                    (r"^\s*(?:export\s+)?interface\s+(?P<name>[\w$]+)", Interface),
+                    (r"^\s*(?:export\s+)?type\s+(?P<name>[\w$]+)\s*(?:<[^=]*>)?\s*=", Type),
+                    (r"^\s*(?:export\s+)?(?:const\s+)?enum\s+(?P<name>[\w$]+)", Enum),
+                    (r"^\s*(?:export\s+)?(?:const|let|var)\s+(?P<name>[\w$]+)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:\([^)]*\)|[\w$]+)\s*(?::[^=]+)?=>", Function),
+                    (r"^\s*(?:export\s+)?(?:declare\s+)?(?:namespace|module)\s+(?P<name>[\w$.]+)", Module),
+                ]),
+            },
+            LanguageRules {
+                extensions: &["py", "pyi"],
+                rules: rules(&[
+                    (r"^\s+(?:async\s+)?def\s+(?P<name>\w+)", Method),
+                    (r"^(?:async\s+)?def\s+(?P<name>\w+)", Function),
+                    (r"^\s*class\s+(?P<name>\w+)", Class),
+                    (r"^(?P<name>[A-Z_][A-Z0-9_]*)\s*(?::[^=]+)?=", Constant),
+                ]),
+            },
+            LanguageRules {
+                extensions: &["c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+                rules: rules(&[
+                    (r"^\s*(?:typedef\s+)?struct\s+(?P<name>\w+)\s*(?:\{|$)", Struct),
+                    (r"^\s*(?:template\s*<[^>]*>\s*)?class\s+(?P<name>\w+)\s*(?:final\s*)?(?:[:{]|$)", Class),
+                    (r"^\s*(?:typedef\s+)?enum\s+(?:class\s+)?(?P<name>\w+)", Enum),
+                    (r"^\s*namespace\s+(?P<name>\w+)", Module),
+                    (r"^\s*#\s*define\s+(?P<name>\w+)", Constant),
+                    (r"^(?:[\w:<>*&]+\s+)+\**(?P<name>[A-Za-z_][\w:~]*)\s*\([^;]*$", Function),
+                ]),
+            },
+            LanguageRules {
+                extensions: &["java", "kt"],
+                rules: rules(&[
+                    (r"^\s*(?:(?:public|private|protected|internal|abstract|final|static|sealed|data|open)\s+)*class\s+(?P<name>\w+)", Class),
+                    (r"^\s*(?:(?:public|private|protected|internal|sealed)\s+)*interface\s+(?P<name>\w+)", Interface),
+                    (r"^\s*(?:(?:public|private|protected|internal)\s+)*enum\s+(?:class\s+)?(?P<name>\w+)", Enum),
+                    (r"^\s*(?:(?:public|private|protected|internal|override|suspend|inline)\s+)*fun\s+(?:<[^>]*>\s*)?(?:[\w.]+\.)?(?P<name>\w+)", Function),
+                    (r"^\s*(?:(?:public|private|protected|abstract|final|static|synchronized|native)\s+)+(?:<[^>]*>\s*)?[\w<>\[\],.? ]+\s+(?P<name>\w+)\s*\(", Method),
+                ]),
+            },
+        ]
+    })
+}
+
+fn language_for(path: &Path) -> Option<&'static LanguageRules> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    languages().iter().find(|lang| lang.extensions.contains(&ext.as_str()))
+}
+
+/// Extracts the declarations in one file; the first matching rule wins per line
+pub fn symbols_in(path: &Path, relative_path: &str, content: &str) -> Vec<Symbol> {
+    let Some(language) = language_for(path) else {
+        return Vec::new();
+    };
+
+    let mut symbols = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        for (regex, kind) in &language.rules {
+            if let Some(name) = regex.captures(line).and_then(|c| c.name("name")) {
+                symbols.push(Symbol {
+                    name: name.as_str().to_string(),
+                    kind: *kind,
+                    path: path.to_string_lossy().to_string(),
+                    relative_path: relative_path.to_string(),
+                    line: i + 1,
+                    column: line[..name.start()].chars().count(),
+                });
+                break;
+            }
+        }
+    }
+    symbols
+}
+
+pub fn build(root: &Path) -> SymbolIndex {
+    let walker = WalkBuilder::new(root)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut symbols = Vec::new();
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !entry.file_type().is_some_and(|t| t.is_file()) || language_for(path).is_none() {
+            continue;
+        }
+        if entry.metadata().map(|m| m.len() > MAX_INDEXED_FILE_LEN).unwrap_or(true) {
+            continue;
+        }
+        let Ok(bytes) = fs::read(path) else {
+            continue;
+        };
+        if is_probably_binary(&bytes) {
+            continue;
+        }
+        let relative_path = path
+            .strip_prefix(root)
+            .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| path.to_string_lossy().to_string());
+        symbols.extend(symbols_in(path, &relative_path, &String::from_utf8_lossy(&bytes)));
+    }
+
+    SymbolIndex { symbols }
+}
+
+/// Best fuzzy score on the symbol name first, shorter names winning ties
+pub fn query(index: &SymbolIndex, query: &str, limit: usize) -> Vec<SymbolMatch> {
+    let mut matcher = Matcher::new(Config::DEFAULT);
+    let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+    let mut buf = Vec::new();
+
+    let mut scored: Vec<(u32, &Symbol)> = index
+        .symbols
+        .iter()
+        .filter_map(|symbol| {
+            pattern
+                .score(Utf32Str::new(&symbol.name, &mut buf), &mut matcher)
+                .map(|score| (score, symbol))
+        })
+        .collect();
+
+    let key = |(score, symbol): &(u32, &Symbol)| (Reverse(*score), symbol.name.len());
+    if scored.len() > limit && limit > 0 {
+        scored.select_nth_unstable_by_key(limit - 1, key);
+    }
+    scored.truncate(limit);
+    scored.sort_by_key(key);
+
+    scored
+        .into_iter()
+        .map(|(score, symbol)| {
+            let mut indices = Vec::new();
+            pattern.indices(Utf32Str::new(&symbol.name, &mut buf), &mut matcher, &mut indices);
+            indices.sort_unstable();
+            indices.dedup();
+            SymbolMatch {
+                symbol: symbol.clone(),
+                score,
+                indices,
+            }
+        })
+        .collect()
+}
+
+/// Scans `root` (respecting .gitignore) for declarations and keeps them in
+/// memory for `query_workspace_symbols`. Returns the number of symbols found.
+#[tauri::command]
+pub async fn build_symbol_index(
+    state: State<'_, SymbolIndexState>,
+    guard: State<'_, FsGuardState>,
+    root: String,
+) -> Result<usize, String> {
+    let root = guard.check(&root)?;
+    let index = tauri::async_runtime::spawn_blocking(move || build(&root))
+        .await
+        .map_err(|e| format!("Indexing task failed: {}", e))?;
+
+    let count = index.symbols.len();
+    *state.index.lock().map_err(|e| format!("Failed to lock state: {}", e))? = Some(Arc::new(index));
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn query_workspace_symbols(
+    state: State<'_, SymbolIndexState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SymbolMatch>, String> {
+    let index = state
+        .index
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .clone()
+        .ok_or_else(|| "Symbol index has not been built".to_string())?;
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+
+    tauri::async_runtime::spawn_blocking(move || self::query(&index, &query, limit))
+        .await
+        .map_err(|e| format!("Query task failed: {}", e))
+}
@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, State};
+
+use crate::command_policy::{self, CommandPolicyState};
+use crate::fs_guard::FsGuardState;
+
+fn existing_path(path: PathBuf) -> Result<PathBuf, String> {
+    if std::fs::symlink_metadata(&path).is_err() {
+        return Err("Path does not exist".to_string());
+    }
+    Ok(path)
+}
+
+/// Whether opening `path` would run it rather than show it in an app
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(windows)]
+fn is_executable(path: &Path) -> bool {
+    const EXTENSIONS: &[&str] = &[
+        "exe", "com", "bat", "cmd", "ps1", "msi", "lnk", "scr", "vbs", "js", "jse", "wsf", "hta",
+    ];
+    path.extension()
+        .map(|ext| EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+        .unwrap_or(false)
+}
+
+// Desktops without the freedesktop D-Bus services (bare window managers,
+// some containers) still usually have xdg-open
+#[cfg(target_os = "linux")]
+fn xdg_open(path: &Path) -> Result<(), String> {
+    let status = std::process::Command::new("xdg-open")
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Failed to run xdg-open: {}", e))?;
+    if !status.success() {
+        return Err(format!("xdg-open exited with {}", status));
+    }
+    Ok(())
+}
+
+fn open_path(path: &Path) -> Result<(), String> {
+    let result = tauri_plugin_opener::open_path(path, None::<&str>);
+    #[cfg(target_os = "linux")]
+    if let Err(e) = result {
+        tracing::warn!("Falling back to xdg-open: {}", e);
+        return xdg_open(path);
+    }
+    result.map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+fn reveal_path(path: &Path) -> Result<(), String> {
+    let result = tauri_plugin_opener::reveal_item_in_dir(path);
+    #[cfg(target_os = "linux")]
+    if let Err(e) = result {
+        // xdg-open can't select an item, so at least show the containing folder
+        tracing::warn!("Falling back to xdg-open: {}", e);
+        return xdg_open(path.parent().unwrap_or(path));
+    }
+    result.map_err(|e| format!("Failed to reveal {}: {}", path.display(), e))
+}
+
+/// Opens a file or folder with the application the OS associates with it.
+/// Executables would be run, so they go through `command_policy` first.
+#[tauri::command]
+pub async fn open_in_default_app(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    policy_state: State<'_, CommandPolicyState>,
+    path: String,
+) -> Result<(), String> {
+    let path = existing_path(guard.check(&path)?)?;
+    if is_executable(&path) {
+        command_policy::authorize(
+            &app_handle,
+            &policy_state,
+            "open_in_default_app",
+            &path.to_string_lossy(),
+            &[],
+            path.parent(),
+            false,
+        )
+        .await?;
+    }
+    tauri::async_runtime::spawn_blocking(move || open_path(&path))
+        .await
+        .map_err(|e| format!("Open task failed: {}", e))?
+}
+
+/// Shows the item selected in Finder, Explorer or the desktop's file manager
+#[tauri::command]
+pub async fn reveal_in_file_manager(guard: State<'_, FsGuardState>, path: String) -> Result<(), String> {
+    let path = existing_path(guard.check_entry(&path)?)?;
+    tauri::async_runtime::spawn_blocking(move || reveal_path(&path))
+        .await
+        .map_err(|e| format!("Reveal task failed: {}", e))?
+}
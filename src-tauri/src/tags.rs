@@ -0,0 +1,299 @@
+//! Tags of the markdown documents in a workspace, from front matter
+//! (`tags: [a, b]`) and inline `#tags`, kept current by a file watcher.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::debounced_watch::{self, WatchHandler};
+use crate::front_matter;
+use crate::fs_guard::FsGuardState;
+use crate::links::{is_markdown, mask_code_spans};
+
+// Watcher events are batched until the tree has been quiet this long
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    /// Number of documents with the tag
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagIndexUpdate {
+    pub root: String,
+    /// Documents whose tags were re-read
+    pub paths: Vec<String>,
+}
+
+/// Tags per markdown document under a workspace root
+struct TagIndex {
+    root: PathBuf,
+    documents: HashMap<PathBuf, BTreeSet<String>>,
+}
+
+#[derive(Default)]
+pub struct TagIndexState {
+    index: Arc<Mutex<Option<TagIndex>>>,
+    /// Stops the watcher of the current index
+    stop: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+fn inline_tag_regex() -> &'static Regex {
+    // `#tag` at the start of a line or after whitespace, so `# Heading`,
+    // `page#anchor` and `&#123;` don't count; `/` nests (`#project/alpha`)
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:^|\s)#([\p{L}\p{N}_][\p{L}\p{N}_/-]*)").unwrap())
+}
+
+/// Canonical form of a tag: without `#`, trailing separators or case.
+/// Purely numeric tags are issue references (`#42`), not tags.
+fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').trim_end_matches(['/', '-']);
+    if tag.is_empty() || tag.chars().all(|c| c.is_ascii_digit()) || tag.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(tag.to_lowercase())
+}
+
+/// Tags listed under `tags` (or `tag`) in front matter, as a list or a
+/// comma- or space-separated string
+fn front_matter_tags(content: &str, tags: &mut BTreeSet<String>) -> usize {
+    let Ok(front_matter) = front_matter::parse(content) else {
+        return 0;
+    };
+    for key in ["tags", "tag"] {
+        match front_matter.data.get(key) {
+            Some(Value::Array(items)) => {
+                tags.extend(items.iter().filter_map(Value::as_str).filter_map(normalize));
+            }
+            Some(Value::String(text)) => {
+                tags.extend(text.split([',', ' ']).filter_map(normalize));
+            }
+            _ => {}
+        }
+    }
+    front_matter.body_start_line
+}
+
+/// Every tag of one document, skipping code blocks and code spans
+pub fn extract_tags(content: &str) -> BTreeSet<String> {
+    let mut tags = BTreeSet::new();
+    let body_start = front_matter_tags(content, &mut tags);
+
+    let mut fence: Option<&str> = None;
+    for line in content.lines().skip(body_start) {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+        let masked = mask_code_spans(line);
+        tags.extend(
+            inline_tag_regex()
+                .captures_iter(&masked)
+                .filter_map(|cap| normalize(&cap[1])),
+        );
+    }
+    tags
+}
+
+/// Whether `tag` is `query` or nested below it (`project/alpha` under `project`)
+fn tag_matches(tag: &str, query: &str) -> bool {
+    tag.strip_prefix(query).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+fn is_hidden(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root)
+        .map(|rel| rel.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')))
+        .unwrap_or(true)
+}
+
+impl TagIndex {
+    fn build(root: &Path) -> Self {
+        let walker = WalkBuilder::new(root)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build();
+        let documents = walker
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()) && is_markdown(entry.path()))
+            .filter_map(|entry| {
+                let content = fs::read_to_string(entry.path()).ok()?;
+                Some((entry.path().to_path_buf(), extract_tags(&content)))
+            })
+            .collect();
+        TagIndex {
+            root: root.to_path_buf(),
+            documents,
+        }
+    }
+
+    /// Re-reads one document or folder after a change on disk, dropping
+    /// whatever no longer exists
+    fn refresh(&mut self, path: &Path) {
+        if !path.starts_with(&self.root) {
+            return;
+        }
+        // A folder moved in arrives as a single event
+        if path.is_dir() {
+            for entry in WalkBuilder::new(path).build().flatten() {
+                if entry.file_type().is_some_and(|t| t.is_file()) && is_markdown(entry.path()) {
+                    self.refresh(entry.path());
+                }
+            }
+            return;
+        }
+        if !path.exists() {
+            // A removed folder takes everything below it along
+            self.documents.retain(|doc, _| !doc.starts_with(path));
+            return;
+        }
+        if !is_markdown(path) {
+            return;
+        }
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                self.documents.insert(path.to_path_buf(), extract_tags(&content));
+            }
+            Err(_) => {
+                self.documents.remove(path);
+            }
+        }
+    }
+
+    fn all_tags(&self) -> Vec<TagCount> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for tag in self.documents.values().flatten() {
+            *counts.entry(tag).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .map(|(tag, count)| TagCount {
+                tag: tag.to_string(),
+                count,
+            })
+            .collect()
+    }
+
+    fn files_with_tag(&self, tag: &str) -> Vec<String> {
+        let Some(query) = normalize(tag) else {
+            return Vec::new();
+        };
+        let mut files: Vec<String> = self
+            .documents
+            .iter()
+            .filter(|(_, tags)| tags.iter().any(|t| tag_matches(t, &query)))
+            .map(|(path, _)| path.to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+/// Refreshes the documents behind each batch of changed paths
+struct TagWatcher {
+    app: AppHandle,
+    index: Arc<Mutex<Option<TagIndex>>>,
+    root: PathBuf,
+}
+
+impl WatchHandler for TagWatcher {
+    fn wants(&self, path: &Path) -> bool {
+        // Non-markdown paths may be folders that were moved or removed
+        !is_hidden(&self.root, path)
+    }
+
+    fn flush(&mut self, paths: Vec<PathBuf>) -> bool {
+        let Ok(mut index) = self.index.lock() else {
+            return false;
+        };
+        // The index was replaced by one for another root
+        let Some(index) = index.as_mut().filter(|index| index.root == self.root) else {
+            return false;
+        };
+        for path in &paths {
+            index.refresh(path);
+        }
+        let _ = self.app.emit(
+            "tag-index-updated",
+            TagIndexUpdate {
+                root: self.root.to_string_lossy().to_string(),
+                paths: paths.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+            },
+        );
+        true
+    }
+}
+
+impl TagIndexState {
+    fn with_index<T>(&self, f: impl FnOnce(&TagIndex) -> T) -> Result<T, String> {
+        let index = self.index.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        let index = index.as_ref().ok_or_else(|| "Tag index has not been built".to_string())?;
+        Ok(f(index))
+    }
+}
+
+/// Reads the tags of every markdown document under `root` and keeps them
+/// current as files change, announcing each batch on `tag-index-updated`.
+/// Replaces the index of any previous root. Returns the number of distinct tags.
+#[tauri::command]
+pub async fn build_tag_index(
+    app_handle: AppHandle,
+    state: State<'_, TagIndexState>,
+    guard: State<'_, FsGuardState>,
+    root: String,
+) -> Result<usize, String> {
+    let root = guard.check(&root)?;
+    if !root.is_dir() {
+        return Err(format!("Workspace root is not a directory: {}", root.display()));
+    }
+    let build_root = root.clone();
+    let index = tauri::async_runtime::spawn_blocking(move || TagIndex::build(&build_root))
+        .await
+        .map_err(|e| format!("Indexing task failed: {}", e))?;
+    let count = index.all_tags().len();
+
+    let mut stop = state.stop.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(previous) = stop.take() {
+        previous.store(true, Ordering::Relaxed);
+    }
+    *state.index.lock().map_err(|e| format!("Failed to lock state: {}", e))? = Some(index);
+    let flag = Arc::new(AtomicBool::new(false));
+    let watcher = TagWatcher {
+        app: app_handle,
+        index: state.index.clone(),
+        root: root.clone(),
+    };
+    debounced_watch::watch(&root, WATCH_DEBOUNCE, flag.clone(), watcher)?;
+    *stop = Some(flag);
+    Ok(count)
+}
+
+/// Every tag in the workspace with the number of documents using it, by name
+#[tauri::command]
+pub async fn get_all_tags(state: State<'_, TagIndexState>) -> Result<Vec<TagCount>, String> {
+    state.with_index(|index| index.all_tags())
+}
+
+/// Documents tagged `tag` or a tag nested below it, case-insensitively
+#[tauri::command]
+pub async fn get_files_with_tag(state: State<'_, TagIndexState>, tag: String) -> Result<Vec<String>, String> {
+    state.with_index(|index| index.files_with_tag(&tag))
+}
@@ -0,0 +1,398 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, PtySize};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::command_policy::{self, CommandPolicyState};
+use crate::fs_guard::FsGuardState;
+use crate::problem_matcher::{self, ProblemMatcher, ProblemMatcherKind};
+use crate::shell_env;
+
+// User-defined tasks, relative to the project root
+const TASKS_FILE: &str = ".tmd/tasks.json";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    /// `command` is a command line run by the platform shell
+    #[default]
+    Shell,
+    /// `command` is an executable run directly with `args`
+    Process,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskDefinition {
+    pub label: String,
+    #[serde(rename = "type", default)]
+    pub kind: TaskKind,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Working directory, relative to the project root
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// "build", "test", "run" or anything else the UI wants to group by
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Where the task came from ("tasks.json", "cargo", "npm", "go"); filled in by `list_tasks`
+    #[serde(default)]
+    pub source: String,
+    /// Parsers for compiler errors in the output; picked from `command` when unset
+    #[serde(default)]
+    pub problem_matchers: Option<Vec<ProblemMatcherKind>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TasksFile {
+    #[serde(default)]
+    tasks: Vec<TaskDefinition>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskExit {
+    pub task_id: String,
+    pub exit_code: Option<u32>,
+    pub success: bool,
+    pub cancelled: bool,
+}
+
+struct RunningTask {
+    killer: Box<dyn ChildKiller + Send + Sync>,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+pub struct TaskState {
+    tasks: Mutex<HashMap<String, RunningTask>>,
+}
+
+fn process_task(label: &str, group: &str, source: &str, command: &str, args: &[&str]) -> TaskDefinition {
+    TaskDefinition {
+        label: label.to_string(),
+        kind: TaskKind::Process,
+        command: command.to_string(),
+        args: args.iter().map(|a| a.to_string()).collect(),
+        cwd: None,
+        env: HashMap::new(),
+        group: Some(group.to_string()),
+        source: source.to_string(),
+        problem_matchers: None,
+    }
+}
+
+fn load_tasks_file(root: &Path) -> Result<Vec<TaskDefinition>, String> {
+    let path = root.join(TASKS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", TASKS_FILE, e))?;
+    let file: TasksFile = serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", TASKS_FILE, e))?;
+    Ok(file
+        .tasks
+        .into_iter()
+        .map(|mut task| {
+            task.source = "tasks.json".to_string();
+            task
+        })
+        .collect())
+}
+
+fn npm_client(root: &Path) -> &'static str {
+    if root.join("pnpm-lock.yaml").exists() {
+        "pnpm"
+    } else if root.join("yarn.lock").exists() {
+        "yarn"
+    } else {
+        "npm"
+    }
+}
+
+/// Tasks inferred from the build files found in `root`
+pub fn detect_tasks(root: &Path) -> Vec<TaskDefinition> {
+    let mut tasks = Vec::new();
+
+    if root.join("Cargo.toml").exists() {
+        tasks.push(process_task("cargo build", "build", "cargo", "cargo", &["build"]));
+        tasks.push(process_task("cargo run", "run", "cargo", "cargo", &["run"]));
+        tasks.push(process_task("cargo test", "test", "cargo", "cargo", &["test"]));
+    }
+
+    if let Ok(content) = fs::read_to_string(root.join("package.json")) {
+        let client = npm_client(root);
+        let scripts = serde_json::from_str::<serde_json::Value>(&content)
+            .ok()
+            .and_then(|v| v.get("scripts").and_then(|s| s.as_object()).cloned())
+            .unwrap_or_default();
+        for name in scripts.keys() {
+            let group = match name.as_str() {
+                "build" => "build",
+                "test" => "test",
+                _ => "run",
+            };
+            let label = format!("{} run {}", client, name);
+            tasks.push(process_task(&label, group, "npm", client, &["run", name]));
+        }
+    }
+
+    if root.join("go.mod").exists() {
+        tasks.push(process_task("go build", "build", "go", "go", &["build", "./..."]));
+        tasks.push(process_task("go test", "test", "go", "go", &["test", "./..."]));
+    }
+
+    tasks
+}
+
+fn task_cwd(task: &TaskDefinition, root: &Path) -> PathBuf {
+    match &task.cwd {
+        Some(dir) => root.join(dir),
+        None => root.to_path_buf(),
+    }
+}
+
+/// The program and arguments `task` runs; shell tasks run their line
+/// through the platform shell
+fn task_program(task: &TaskDefinition) -> (String, Vec<String>) {
+    match task.kind {
+        TaskKind::Process => (task.command.clone(), task.args.clone()),
+        TaskKind::Shell => {
+            let line = std::iter::once(task.command.as_str())
+                .chain(task.args.iter().map(|a| a.as_str()))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if cfg!(target_os = "windows") {
+                ("cmd.exe".to_string(), vec!["/C".to_string(), line])
+            } else {
+                ("/bin/sh".to_string(), vec!["-c".to_string(), line])
+            }
+        }
+    }
+}
+
+fn build_command(task: &TaskDefinition, root: &Path) -> CommandBuilder {
+    let (program, args) = task_program(task);
+    let mut cmd = CommandBuilder::new(program);
+    cmd.args(args);
+    cmd.cwd(task_cwd(task, root));
+    for (key, value) in shell_env::environment().iter().chain(&task.env) {
+        cmd.env(key, value);
+    }
+    cmd
+}
+
+/// Tasks from `.tmd/tasks.json` followed by the auto-detected ones
+#[tauri::command]
+pub async fn list_tasks(guard: State<'_, FsGuardState>, root_path: String) -> Result<Vec<TaskDefinition>, String> {
+    let root = guard.check(&root_path)?;
+    let mut tasks = load_tasks_file(&root)?;
+    for detected in detect_tasks(&root) {
+        if !tasks.iter().any(|t| t.label == detected.label) {
+            tasks.push(detected);
+        }
+    }
+    Ok(tasks)
+}
+
+/// Starts `task` in its own PTY under `task_id`, chosen by the caller so it
+/// can listen before any output arrives. Returns the task ID.
+///
+/// Output is streamed on `task-output-{task_id}`, compiler errors found in it
+/// on `task-problems-{task_id}`, and a `TaskExit` is emitted on
+/// `task-exit-{task_id}` once the process has finished.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_task(
+    app_handle: AppHandle,
+    state: State<'_, TaskState>,
+    guard: State<'_, FsGuardState>,
+    task_id: String,
+    root_path: String,
+    task: TaskDefinition,
+    rows: Option<u16>,
+    cols: Option<u16>,
+) -> Result<String, String> {
+    let root = guard.check(&root_path)?;
+    authorize_task(&app_handle, "run_task", &task, &root).await?;
+    spawn_task(app_handle, &state, task_id, &root, task, rows, cols)
+}
+
+/// Puts `task` through `command_policy` like any other command; `source`
+/// names the caller in the audit log
+pub(crate) async fn authorize_task(
+    app_handle: &AppHandle,
+    source: &str,
+    task: &TaskDefinition,
+    root: &Path,
+) -> Result<(), String> {
+    let (program, args) = task_program(task);
+    let cwd = task_cwd(task, root);
+    command_policy::authorize(
+        app_handle,
+        &app_handle.state::<CommandPolicyState>(),
+        source,
+        &program,
+        &args,
+        Some(&cwd),
+        task.kind == TaskKind::Shell,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Task ids end up in event names, which only allow ASCII letters, digits,
+/// `-`, `/`, `:` and `_`
+fn check_task_id(task_id: &str) -> Result<(), String> {
+    let valid = !task_id.is_empty()
+        && task_id.len() <= 128
+        && task_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ':' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid task id: {:?}", task_id))
+    }
+}
+
+/// `run_task` for callers inside the backend, e.g. run configurations. The
+/// task must pass `authorize_task` first.
+pub(crate) fn spawn_task(
+    app_handle: AppHandle,
+    state: &TaskState,
+    task_id: String,
+    root: &Path,
+    task: TaskDefinition,
+    rows: Option<u16>,
+    cols: Option<u16>,
+) -> Result<String, String> {
+    check_task_id(&task_id)?;
+    if state.is_running(&task_id) {
+        return Err(format!("A task with id {} is already running", task_id));
+    }
+    let pair = native_pty_system()
+        .openpty(PtySize {
+            rows: rows.unwrap_or(24),
+            cols: cols.unwrap_or(80),
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| format!("Failed to create PTY: {}", e))?;
+
+    let cmd = build_command(&task, root);
+    let mut child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| format!("Failed to start task {}: {}", task.label, e))?;
+    drop(pair.slave);
+
+    let mut reader = match pair.master.try_clone_reader() {
+        Ok(reader) => reader,
+        Err(e) => {
+            let _ = child.kill();
+            return Err(format!("Failed to clone reader: {}", e));
+        }
+    };
+
+    // The check above is only a shortcut; another start with the same id
+    // may have won the race while this one was spawning
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let registered = match state.tasks.lock() {
+        Ok(mut tasks) => match tasks.entry(task_id.clone()) {
+            Entry::Occupied(_) => Err(format!("A task with id {} is already running", task_id)),
+            Entry::Vacant(slot) => {
+                slot.insert(RunningTask {
+                    killer: child.clone_killer(),
+                    cancelled: cancelled.clone(),
+                });
+                Ok(())
+            }
+        },
+        Err(e) => Err(format!("Failed to lock state: {}", e)),
+    };
+    if let Err(e) = registered {
+        let _ = child.kill();
+        return Err(e);
+    }
+
+    let matchers = task
+        .problem_matchers
+        .clone()
+        .unwrap_or_else(|| problem_matcher::default_matchers(&task.command));
+    let mut matcher = ProblemMatcher::new(matchers, task_cwd(&task, root));
+
+    let id = task_id.clone();
+    let master = pair.master;
+    thread::spawn(move || {
+        let problems_event = format!("task-problems-{}", id);
+        let mut buffer = [0u8; 4096];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let output = String::from_utf8_lossy(&buffer[..n]).to_string();
+                    if !matcher.is_empty() {
+                        let problems = matcher.feed(&output);
+                        if !problems.is_empty() {
+                            let _ = app_handle.emit(&problems_event, problems);
+                        }
+                    }
+                    let _ = app_handle.emit(&format!("task-output-{}", id), output);
+                }
+            }
+        }
+        let problems = matcher.finish();
+        if !problems.is_empty() {
+            let _ = app_handle.emit(&problems_event, problems);
+        }
+
+        let status = child.wait().ok();
+        drop(master);
+        // Only ever remove this run's own entry
+        if let Ok(mut tasks) = app_handle.state::<TaskState>().tasks.lock() {
+            if tasks.get(&id).is_some_and(|task| Arc::ptr_eq(&task.cancelled, &cancelled)) {
+                tasks.remove(&id);
+            }
+        }
+
+        let exit = TaskExit {
+            task_id: id.clone(),
+            exit_code: status.as_ref().map(|s| s.exit_code()),
+            success: status.as_ref().is_some_and(|s| s.success()),
+            cancelled: cancelled.load(Ordering::SeqCst),
+        };
+        let _ = app_handle.emit(&format!("task-exit-{}", id), exit);
+    });
+
+    Ok(task_id)
+}
+
+impl TaskState {
+    /// Whether the task with this ID is still running
+    pub(crate) fn is_running(&self, task_id: &str) -> bool {
+        self.tasks.lock().is_ok_and(|tasks| tasks.contains_key(task_id))
+    }
+}
+
+#[tauri::command]
+pub async fn cancel_task(state: State<'_, TaskState>, task_id: String) -> Result<(), String> {
+    let mut tasks = state.tasks.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    match tasks.get_mut(&task_id) {
+        Some(task) => {
+            task.cancelled.store(true, Ordering::SeqCst);
+            task.killer
+                .kill()
+                .map_err(|e| format!("Failed to cancel task: {}", e))
+        }
+        None => Err(format!("No running task with id: {}", task_id)),
+    }
+}
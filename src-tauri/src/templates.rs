@@ -0,0 +1,238 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Local};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::atomic_write;
+use crate::fs_guard::FsGuardState;
+
+const TEMPLATES_DIR: &str = "templates";
+const BUNDLED: &[(&str, &str)] = &[
+    ("note", include_str!("../templates/note.md")),
+    ("meeting", include_str!("../templates/meeting.md")),
+    ("journal", include_str!("../templates/journal.md")),
+];
+const MAX_TEMPLATE_SIZE: u64 = 1024 * 1024;
+
+/// Placeholders filled in without being passed as variables
+const BUILTIN_VARIABLES: &[&str] = &[
+    "date", "time", "datetime", "year", "month", "day", "weekday", "title", "author", "filename",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateSource {
+    Bundled,
+    User,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateSummary {
+    /// File name without extension; user templates shadow bundled ones with the same id
+    pub id: String,
+    pub name: String,
+    pub source: TemplateSource,
+    pub path: Option<String>,
+    /// Placeholders that aren't built in, for the UI to ask for
+    pub variables: Vec<String>,
+}
+
+fn placeholder_regex() -> &'static Regex {
+    // {{name}} or {{name:format}}, e.g. {{date:%d.%m.%Y}}
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z_][\w-]*)\s*(?::([^}]*))?\}\}").unwrap())
+}
+
+pub(crate) fn templates_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join(TEMPLATES_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create templates directory: {}", e))?;
+    Ok(dir)
+}
+
+fn template_id(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// "meeting-notes" -> "Meeting notes"
+fn display_name(id: &str) -> String {
+    let spaced = id.replace(['-', '_'], " ");
+    let mut chars = spaced.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn user_template_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && !template_id(p).starts_with('.'))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+fn custom_variables(text: &str) -> Vec<String> {
+    let names: BTreeSet<String> = placeholder_regex()
+        .captures_iter(text)
+        .map(|cap| cap[1].to_string())
+        .filter(|name| !BUILTIN_VARIABLES.contains(&name.as_str()))
+        .collect();
+    names.into_iter().collect()
+}
+
+fn read_user_template(path: &Path) -> Result<String, String> {
+    let size = fs::metadata(path).map_err(|e| format!("Failed to read template: {}", e))?.len();
+    if size > MAX_TEMPLATE_SIZE {
+        return Err("Template is too large".to_string());
+    }
+    fs::read_to_string(path).map_err(|e| format!("Failed to read template: {}", e))
+}
+
+/// Text of a template, user templates first
+pub(crate) fn template_text(app: &AppHandle, id: &str) -> Result<String, String> {
+    let dir = templates_dir(app)?;
+    if let Some(path) = user_template_files(&dir).into_iter().find(|p| template_id(p) == id) {
+        return read_user_template(&path);
+    }
+    BUNDLED
+        .iter()
+        .find(|(bundled, _)| *bundled == id)
+        .map(|(_, text)| text.to_string())
+        .ok_or_else(|| format!("Unknown template: {}", id))
+}
+
+/// The user's name for `{{author}}`: git's user.name, else the login name
+fn default_author() -> Option<String> {
+    git2::Config::open_default()
+        .and_then(|config| config.get_string("user.name"))
+        .ok()
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// Replaces `{{placeholders}}`, with dates and times taken from `now`.
+/// `variables` win over the built-in ones; unknown placeholders are left in
+/// place so nothing is silently lost.
+pub(crate) fn render(
+    template: &str,
+    target: &Path,
+    variables: &HashMap<String, String>,
+    now: DateTime<Local>,
+) -> String {
+    let stem = target.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let author = OnceLock::new();
+
+    placeholder_regex()
+        .replace_all(template, |cap: &Captures| {
+            let name = &cap[1];
+            if let Some(value) = variables.get(name) {
+                return value.clone();
+            }
+            let format = cap.get(2).map(|m| m.as_str().trim()).filter(|f| !f.is_empty());
+            let date_format = match name {
+                "date" => Some(format.unwrap_or("%Y-%m-%d")),
+                "time" => Some(format.unwrap_or("%H:%M")),
+                "datetime" => Some(format.unwrap_or("%Y-%m-%dT%H:%M:%S%:z")),
+                "year" => Some("%Y"),
+                "month" => Some("%m"),
+                "day" => Some("%d"),
+                "weekday" => Some("%A"),
+                _ => None,
+            };
+            if let Some(date_format) = date_format {
+                // Writing instead of to_string(): an invalid format is an error, not a panic
+                let mut out = String::new();
+                return match write!(out, "{}", now.format(date_format)) {
+                    Ok(()) => out,
+                    Err(_) => cap[0].to_string(),
+                };
+            }
+            match name {
+                "title" => stem.clone(),
+                "filename" => target.file_name().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+                "author" => author.get_or_init(default_author).clone().unwrap_or_default(),
+                _ => cap[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+#[tauri::command]
+pub async fn list_templates(app_handle: AppHandle) -> Result<Vec<TemplateSummary>, String> {
+    let dir = templates_dir(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let user: Vec<TemplateSummary> = user_template_files(&dir)
+            .iter()
+            .map(|path| {
+                let id = template_id(path);
+                TemplateSummary {
+                    name: display_name(&id),
+                    variables: read_user_template(path).map(|t| custom_variables(&t)).unwrap_or_default(),
+                    id,
+                    source: TemplateSource::User,
+                    path: Some(path.to_string_lossy().to_string()),
+                }
+            })
+            .collect();
+        let bundled: Vec<TemplateSummary> = BUNDLED
+            .iter()
+            .filter(|(id, _)| !user.iter().any(|t| t.id == *id))
+            .map(|(id, text)| TemplateSummary {
+                id: id.to_string(),
+                name: display_name(id),
+                source: TemplateSource::Bundled,
+                path: None,
+                variables: custom_variables(text),
+            })
+            .collect();
+        bundled.into_iter().chain(user).collect()
+    })
+    .await
+    .map_err(|e| format!("Failed to list templates: {}", e))
+}
+
+/// Folder user templates are read from, so the UI can open it
+#[tauri::command]
+pub async fn get_templates_dir(app_handle: AppHandle) -> Result<String, String> {
+    templates_dir(&app_handle).map(|dir| dir.to_string_lossy().to_string())
+}
+
+/// Creates `target_path` from a template, filling in `{{date}}`, `{{title}}`
+/// (the file name), `{{author}}` and any `variables`. Fails if the file exists.
+#[tauri::command]
+pub async fn create_from_template(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    template_id: String,
+    target_path: String,
+    variables: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let target = guard.check_entry(&target_path)?;
+    let template = template_text(&app_handle, &template_id)?;
+    if target.exists() {
+        return Err(format!("File already exists: {}", target_path));
+    }
+    let content = render(&template, &target, &variables.unwrap_or_default(), Local::now());
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    atomic_write::write_atomic(&target, content.as_bytes()).map_err(|e| format!("Failed to write file: {}", e))?;
+    Ok(target_path)
+}
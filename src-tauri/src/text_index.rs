@@ -0,0 +1,589 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::atomic_write::write_atomic;
+use crate::debounced_watch::{self, WatchHandler};
+use crate::file_info::{sha256_hex, system_time_ms};
+use crate::fs_guard::FsGuardState;
+
+const INDEX_DIR: &str = "text_index";
+// Bumped whenever the stored layout or the tokenizer changes
+const INDEX_VERSION: u32 = 1;
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+const MAX_TOKEN_CHARS: usize = 64;
+// How many index terms the last, prefix-matched query word may expand to
+const MAX_PREFIX_TERMS: usize = 200;
+const MAX_SNIPPETS: usize = 3;
+const DEFAULT_QUERY_LIMIT: usize = 50;
+// Watcher events are batched until the tree has been quiet this long
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+// Incremental updates are written back to disk at most this often
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Doc {
+    /// '/'-separated path relative to the root
+    path: String,
+    modified_ms: u64,
+    size: u64,
+    /// Distinct terms, so the postings can be removed when the file changes
+    terms: Vec<String>,
+}
+
+/// Inverted index of the text files under a workspace root
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TextIndex {
+    version: u32,
+    root: PathBuf,
+    docs: Vec<Option<Doc>>,
+    /// Term to (doc id, occurrences in that doc)
+    postings: BTreeMap<String, Vec<(u32, u32)>>,
+    /// Ids of removed docs, reused before the list grows
+    free: Vec<u32>,
+    #[serde(skip)]
+    by_path: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexStats {
+    pub root: String,
+    pub files: usize,
+    pub terms: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexSnippet {
+    /// 1-based
+    pub line: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexHit {
+    pub path: String,
+    pub relative_path: String,
+    pub score: f64,
+    /// First few lines containing a query word
+    pub snippets: Vec<IndexSnippet>,
+}
+
+struct OpenIndex {
+    index: Arc<Mutex<TextIndex>>,
+    stop: Arc<AtomicBool>,
+}
+
+/// Open indexes keyed by workspace root, each kept current by a watcher
+#[derive(Default)]
+pub struct TextIndexState {
+    indexes: Mutex<HashMap<PathBuf, OpenIndex>>,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+/// Lowercased words of two or more characters. Identifiers are also indexed
+/// by their `_`-separated parts, and CJK runs, which have no spaces, as
+/// overlapping character pairs.
+fn tokenize(text: &str) -> Vec<String> {
+    fn flush_word(word: &mut String, tokens: &mut Vec<String>) {
+        let len = word.chars().count();
+        if (2..=MAX_TOKEN_CHARS).contains(&len) {
+            if word.contains('_') {
+                tokens.extend(word.split('_').filter(|p| p.chars().count() >= 2).map(str::to_string));
+            }
+            tokens.push(std::mem::take(word));
+        }
+        word.clear();
+    }
+    fn flush_cjk(run: &mut Vec<char>, tokens: &mut Vec<String>) {
+        match run.len() {
+            0 => {}
+            1 => tokens.push(run[0].to_string()),
+            _ => tokens.extend(run.windows(2).map(|pair| pair.iter().collect::<String>())),
+        }
+        run.clear();
+    }
+
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut run = Vec::new();
+    for c in text.chars() {
+        if is_cjk(c) {
+            flush_word(&mut word, &mut tokens);
+            run.push(c);
+        } else if c.is_alphanumeric() || c == '_' {
+            flush_cjk(&mut run, &mut tokens);
+            word.extend(c.to_lowercase());
+        } else {
+            flush_word(&mut word, &mut tokens);
+            flush_cjk(&mut run, &mut tokens);
+        }
+    }
+    flush_word(&mut word, &mut tokens);
+    flush_cjk(&mut run, &mut tokens);
+    tokens
+}
+
+/// Contents of `path` if it is a reasonably small UTF-8 text file
+fn read_text(path: &Path) -> Option<(String, u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if bytes[..bytes.len().min(8192)].contains(&0) {
+        return None;
+    }
+    let text = String::from_utf8(bytes).ok()?;
+    Some((text, system_time_ms(metadata.modified()).unwrap_or(0), metadata.len()))
+}
+
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(root).ok().map(|rel| rel.to_string_lossy().replace('\\', "/"))
+}
+
+impl TextIndex {
+    fn new(root: &Path) -> Self {
+        TextIndex {
+            version: INDEX_VERSION,
+            root: root.to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    fn stats(&self) -> IndexStats {
+        IndexStats {
+            root: self.root.to_string_lossy().to_string(),
+            files: self.by_path.len(),
+            terms: self.postings.len(),
+        }
+    }
+
+    fn remove(&mut self, rel: &str) {
+        let Some(id) = self.by_path.remove(rel) else {
+            return;
+        };
+        if let Some(doc) = self.docs[id as usize].take() {
+            for term in doc.terms {
+                if let Some(list) = self.postings.get_mut(&term) {
+                    list.retain(|(doc_id, _)| *doc_id != id);
+                    if list.is_empty() {
+                        self.postings.remove(&term);
+                    }
+                }
+            }
+        }
+        self.free.push(id);
+    }
+
+    fn add(&mut self, rel: String, modified_ms: u64, size: u64, text: &str) {
+        self.remove(&rel);
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for token in tokenize(text) {
+            *counts.entry(token).or_default() += 1;
+        }
+
+        let id = match self.free.pop() {
+            Some(id) => id,
+            None => {
+                self.docs.push(None);
+                (self.docs.len() - 1) as u32
+            }
+        };
+        for (term, count) in &counts {
+            self.postings.entry(term.clone()).or_default().push((id, *count));
+        }
+        self.docs[id as usize] = Some(Doc {
+            path: rel.clone(),
+            modified_ms,
+            size,
+            terms: counts.into_keys().collect(),
+        });
+        self.by_path.insert(rel, id);
+    }
+
+    /// Re-reads `path` after a change on disk, or drops it if it's gone
+    fn update_path(&mut self, path: &Path) {
+        let Some(rel) = relative(&self.root, path) else {
+            return;
+        };
+        // A folder moved in arrives as a single event
+        if path.is_dir() {
+            for entry in WalkBuilder::new(path).build().flatten() {
+                if entry.file_type().is_some_and(|t| t.is_file()) {
+                    self.update_path(entry.path());
+                }
+            }
+            return;
+        }
+        match read_text(path) {
+            Some((text, modified_ms, size)) => self.add(rel, modified_ms, size, &text),
+            None => {
+                self.remove(&rel);
+                // A removed folder takes everything below it along
+                let prefix = format!("{}/", rel);
+                let below: Vec<String> = self.by_path.keys().filter(|p| p.starts_with(&prefix)).cloned().collect();
+                for rel in below {
+                    self.remove(&rel);
+                }
+            }
+        }
+    }
+
+    /// Brings the index in line with the tree, re-reading only files whose
+    /// size or modification time changed
+    fn reconcile(&mut self) {
+        let mut seen = HashSet::new();
+        let walker = WalkBuilder::new(&self.root)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build();
+        for entry in walker.flatten() {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let Some(rel) = relative(&self.root, entry.path()) else {
+                continue;
+            };
+            let unchanged = self.by_path.get(&rel).and_then(|id| self.docs[*id as usize].as_ref()).is_some_and(|doc| {
+                entry.metadata().is_ok_and(|m| {
+                    m.len() == doc.size && system_time_ms(m.modified()).unwrap_or(0) == doc.modified_ms
+                })
+            });
+            if !unchanged {
+                match read_text(entry.path()) {
+                    Some((text, modified_ms, size)) => self.add(rel.clone(), modified_ms, size, &text),
+                    None => self.remove(&rel),
+                }
+            }
+            seen.insert(rel);
+        }
+        let gone: Vec<String> = self.by_path.keys().filter(|p| !seen.contains(*p)).cloned().collect();
+        for rel in gone {
+            self.remove(&rel);
+        }
+    }
+
+    /// Docs containing every query word (the last one as a prefix), best first
+    fn query(&self, query: &str, limit: usize) -> Vec<(String, f64)> {
+        let mut words = tokenize(query);
+        words.dedup();
+        let Some(last) = words.pop() else {
+            return Vec::new();
+        };
+        let total = self.by_path.len().max(1) as f64;
+
+        let mut groups: Vec<Vec<&Vec<(u32, u32)>>> = words.iter().map(|w| self.postings.get(w).into_iter().collect()).collect();
+        groups.push(
+            self.postings
+                .range(last.clone()..)
+                .take_while(|(term, _)| term.starts_with(&last))
+                .take(MAX_PREFIX_TERMS)
+                .map(|(_, list)| list)
+                .collect(),
+        );
+
+        let mut scores: Option<HashMap<u32, f64>> = None;
+        for group in groups {
+            let mut group_scores: HashMap<u32, f64> = HashMap::new();
+            for list in group {
+                let idf = (1.0 + total / list.len() as f64).ln();
+                for (doc, count) in list {
+                    *group_scores.entry(*doc).or_default() += *count as f64 * idf;
+                }
+            }
+            scores = Some(match scores {
+                None => group_scores,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(doc, score)| group_scores.get(&doc).map(|s| (doc, score + s)))
+                    .collect(),
+            });
+        }
+
+        let mut ranked: Vec<(String, f64)> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(id, score)| Some((self.docs.get(id as usize)?.as_ref()?.path.clone(), score)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+fn index_file(app: &AppHandle, root: &Path) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join(INDEX_DIR);
+    let id = &sha256_hex(root.to_string_lossy().as_bytes())[..32];
+    Ok(dir.join(format!("{}.json.gz", id)))
+}
+
+fn load(app: &AppHandle, root: &Path) -> Option<TextIndex> {
+    let compressed = fs::read(index_file(app, root).ok()?).ok()?;
+    let mut json = Vec::new();
+    GzDecoder::new(compressed.as_slice()).read_to_end(&mut json).ok()?;
+    let mut index: TextIndex = serde_json::from_slice(&json).ok()?;
+    if index.version != INDEX_VERSION || index.root != root {
+        return None;
+    }
+    index.by_path = index
+        .docs
+        .iter()
+        .enumerate()
+        .filter_map(|(id, doc)| Some((doc.as_ref()?.path.clone(), id as u32)))
+        .collect();
+    Some(index)
+}
+
+fn save(app: &AppHandle, index: &TextIndex) -> Result<(), String> {
+    let file = index_file(app, &index.root)?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create index directory: {}", e))?;
+    }
+    let json = serde_json::to_vec(index).map_err(|e| format!("Failed to serialize index: {}", e))?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+    encoder
+        .write_all(&json)
+        .and_then(|_| encoder.finish())
+        .and_then(|compressed| write_atomic(&file, &compressed))
+        .map_err(|e| format!("Failed to save index: {}", e))
+}
+
+fn root_gitignore(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    builder.add(root.join(".gitignore"));
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+/// Whether a watcher event for `path` concerns the index; mirrors the
+/// walker's defaults (no hidden files, root `.gitignore` honoured)
+fn is_indexed_path(root: &Path, gitignore: &Gitignore, path: &Path) -> bool {
+    let Ok(rel) = path.strip_prefix(root) else {
+        return false;
+    };
+    if rel.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')) {
+        return false;
+    }
+    !gitignore.matched_path_or_any_parents(rel, path.is_dir()).is_ignore()
+}
+
+/// Applies batches of changed paths to the index and writes it back to
+/// disk now and then
+struct IndexWatcher {
+    app: AppHandle,
+    index: Arc<Mutex<TextIndex>>,
+    root: PathBuf,
+    gitignore: Gitignore,
+    dirty: bool,
+    last_save: Instant,
+}
+
+impl IndexWatcher {
+    fn save(&mut self) {
+        if let Ok(index) = self.index.lock() {
+            if let Err(e) = save(&self.app, &index) {
+                tracing::warn!("{}", e);
+            }
+        }
+        self.dirty = false;
+        self.last_save = Instant::now();
+    }
+}
+
+impl WatchHandler for IndexWatcher {
+    fn wants(&self, path: &Path) -> bool {
+        is_indexed_path(&self.root, &self.gitignore, path)
+    }
+
+    fn flush(&mut self, paths: Vec<PathBuf>) -> bool {
+        if let Ok(mut index) = self.index.lock() {
+            for path in &paths {
+                index.update_path(path);
+            }
+            let _ = self.app.emit("text-index-updated", index.stats());
+        }
+        self.dirty = true;
+        true
+    }
+
+    fn tick(&mut self) {
+        if self.dirty && self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.dirty {
+            self.save();
+        }
+    }
+}
+
+/// The open index for `root`, loading it from disk (or building it) and
+/// starting its watcher on first use
+async fn ensure_open(app: &AppHandle, state: &TextIndexState, root: &Path) -> Result<Arc<Mutex<TextIndex>>, String> {
+    {
+        let indexes = state.indexes.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+        if let Some(open) = indexes.get(root) {
+            return Ok(open.index.clone());
+        }
+    }
+    if !root.is_dir() {
+        return Err(format!("Workspace root is not a directory: {}", root.display()));
+    }
+
+    let (app_clone, root_clone) = (app.clone(), root.to_path_buf());
+    let index = tauri::async_runtime::spawn_blocking(move || {
+        let mut index = load(&app_clone, &root_clone).unwrap_or_else(|| TextIndex::new(&root_clone));
+        index.reconcile();
+        if let Err(e) = save(&app_clone, &index) {
+            tracing::warn!("{}", e);
+        }
+        index
+    })
+    .await
+    .map_err(|e| format!("Index task failed: {}", e))?;
+
+    let mut indexes = state.indexes.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    // Another call may have opened it while this one was building
+    if let Some(open) = indexes.get(root) {
+        return Ok(open.index.clone());
+    }
+    let index = Arc::new(Mutex::new(index));
+    let stop = Arc::new(AtomicBool::new(false));
+    let watcher = IndexWatcher {
+        app: app.clone(),
+        index: index.clone(),
+        root: root.to_path_buf(),
+        gitignore: root_gitignore(root),
+        dirty: false,
+        last_save: Instant::now(),
+    };
+    debounced_watch::watch(root, WATCH_DEBOUNCE, stop.clone(), watcher)?;
+    indexes.insert(
+        root.to_path_buf(),
+        OpenIndex {
+            index: index.clone(),
+            stop,
+        },
+    );
+    Ok(index)
+}
+
+fn snippets(path: &Path, words: &[String]) -> Vec<IndexSnippet> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let lower = line.to_lowercase();
+            words.iter().any(|w| lower.contains(w.as_str()))
+        })
+        .take(MAX_SNIPPETS)
+        .map(|(i, line)| IndexSnippet {
+            line: i + 1,
+            text: line.trim().chars().take(200).collect(),
+        })
+        .collect()
+}
+
+/// Loads the persisted full-text index of `root`, catches it up with changes
+/// made while the app was closed, and keeps it current from then on
+#[tauri::command]
+pub async fn open_text_index(
+    app_handle: AppHandle,
+    state: State<'_, TextIndexState>,
+    guard: State<'_, FsGuardState>,
+    root: String,
+) -> Result<IndexStats, String> {
+    let root = guard.check(&root)?;
+    let index = ensure_open(&app_handle, &state, &root).await?;
+    let stats = index.lock().map_err(|e| format!("Failed to lock index: {}", e))?.stats();
+    Ok(stats)
+}
+
+/// Files under `root` containing every word of `term`, the last word
+/// matching as a prefix
+#[tauri::command]
+pub async fn query_index(
+    app_handle: AppHandle,
+    state: State<'_, TextIndexState>,
+    guard: State<'_, FsGuardState>,
+    root: String,
+    term: String,
+    limit: Option<usize>,
+) -> Result<Vec<IndexHit>, String> {
+    let root = guard.check(&root)?;
+    let index = ensure_open(&app_handle, &state, &root).await?;
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let ranked = index.lock().map_err(|e| format!("Failed to lock index: {}", e))?.query(&term, limit);
+        let words: Vec<String> = term.split_whitespace().map(str::to_lowercase).collect();
+        Ok(ranked
+            .into_iter()
+            .map(|(relative_path, score)| {
+                let path = root.join(&relative_path);
+                IndexHit {
+                    snippets: snippets(&path, &words),
+                    path: path.to_string_lossy().to_string(),
+                    relative_path,
+                    score,
+                }
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("Index task failed: {}", e))?
+}
+
+/// Discards the index of `root` and reads every file again
+#[tauri::command]
+pub async fn rebuild_index(
+    app_handle: AppHandle,
+    state: State<'_, TextIndexState>,
+    guard: State<'_, FsGuardState>,
+    root: String,
+) -> Result<IndexStats, String> {
+    let root = guard.check(&root)?;
+    let index = ensure_open(&app_handle, &state, &root).await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut fresh = TextIndex::new(&root);
+        fresh.reconcile();
+        let mut index = index.lock().map_err(|e| format!("Failed to lock index: {}", e))?;
+        *index = fresh;
+        save(&app_handle, &index)?;
+        Ok(index.stats())
+    })
+    .await
+    .map_err(|e| format!("Index task failed: {}", e))?
+}
+
+/// Stops watching `root`; pending updates are written to disk
+#[tauri::command]
+pub async fn close_text_index(state: State<'_, TextIndexState>, root: String) -> Result<(), String> {
+    let mut indexes = state.indexes.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(open) = indexes.remove(Path::new(&root)) {
+        open.stop.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
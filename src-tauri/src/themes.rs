@@ -0,0 +1,542 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const THEMES_DIR: &str = "themes";
+const BUNDLED: &[(&str, &str)] = &[
+    ("light", include_str!("../themes/light.json")),
+    ("dark", include_str!("../themes/dark.json")),
+];
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_THEME_SIZE: u64 = 4 * 1024 * 1024;
+
+/// The UI colors a theme defines; mirrors `lightTheme.colors` in theme.ts.
+/// Missing ones are taken from the bundled theme of the same kind.
+const COLOR_KEYS: &[&str] = &[
+    "background",
+    "sidebar",
+    "sidebarBorder",
+    "editor",
+    "activityBar",
+    "activityBarForeground",
+    "statusBar",
+    "statusBarForeground",
+    "text",
+    "textSecondary",
+    "border",
+    "hover",
+    "selected",
+    "folderIcon",
+    "fileIcon",
+];
+
+/// VS Code workbench colors that correspond to ours, in order of preference
+const VSCODE_COLORS: &[(&str, &[&str])] = &[
+    ("background", &["editor.background"]),
+    ("editor", &["editor.background"]),
+    ("sidebar", &["sideBar.background"]),
+    ("sidebarBorder", &["sideBar.border", "panel.border"]),
+    ("activityBar", &["activityBar.background"]),
+    ("activityBarForeground", &["activityBar.foreground"]),
+    ("statusBar", &["statusBar.background"]),
+    ("statusBarForeground", &["statusBar.foreground"]),
+    ("text", &["editor.foreground", "foreground"]),
+    ("textSecondary", &["descriptionForeground", "editorLineNumber.foreground"]),
+    ("border", &["panel.border", "editorGroup.border"]),
+    ("hover", &["list.hoverBackground"]),
+    ("selected", &["list.activeSelectionBackground", "list.inactiveSelectionBackground"]),
+];
+
+/// TextMate global settings that correspond to ours
+const TEXTMATE_COLORS: &[(&str, &[&str])] = &[
+    ("background", &["background"]),
+    ("editor", &["background"]),
+    ("text", &["foreground"]),
+    ("textSecondary", &["gutterForeground", "foreground"]),
+    ("hover", &["lineHighlight"]),
+    ("selected", &["selection"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeKind {
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeSource {
+    Bundled,
+    User,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenColor {
+    pub name: Option<String>,
+    pub scope: Vec<String>,
+    pub foreground: Option<String>,
+    pub background: Option<String>,
+    /// e.g. "italic", "bold underline"
+    pub font_style: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Theme {
+    /// File name without extension; user themes shadow bundled ones with the same id
+    pub id: String,
+    pub name: String,
+    pub kind: ThemeKind,
+    pub source: ThemeSource,
+    pub path: Option<String>,
+    pub colors: Map<String, Value>,
+    pub token_colors: Vec<TokenColor>,
+    /// Problems that were worked around, e.g. invalid colors that were replaced
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThemeSummary {
+    pub id: String,
+    pub name: String,
+    pub kind: Option<ThemeKind>,
+    pub source: ThemeSource,
+    pub path: Option<String>,
+    /// Set when the file can't be loaded at all
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ThemeFileChanged {
+    id: String,
+    removed: bool,
+}
+
+/// Whether the themes folder is being watched
+#[derive(Default)]
+pub struct ThemeState {
+    watching: AtomicBool,
+}
+
+fn themes_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join(THEMES_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create themes directory: {}", e))?;
+    Ok(dir)
+}
+
+fn is_theme_file(path: &Path) -> bool {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+    matches!(extension.as_deref(), Some("json" | "tmtheme"))
+}
+
+fn theme_id(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn is_color(value: &str) -> bool {
+    let Some(hex) = value.strip_prefix('#') else {
+        return false;
+    };
+    matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Relative luminance of a `#rgb`/`#rrggbb` color, 0 (black) to 1 (white)
+fn luminance(color: &str) -> Option<f64> {
+    let hex = color.strip_prefix('#')?;
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok().map(|v| v as f64 / 255.0);
+    let (r, g, b) = match hex.len() {
+        3 | 4 => (
+            channel(&hex[0..1].repeat(2))?,
+            channel(&hex[1..2].repeat(2))?,
+            channel(&hex[2..3].repeat(2))?,
+        ),
+        6 | 8 => (channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?),
+        _ => return None,
+    };
+    Some(0.2126 * r + 0.7152 * g + 0.0722 * b)
+}
+
+/// Reads an Apple property list (the .tmTheme format) into JSON values
+fn parse_plist(text: &str) -> Result<Value, String> {
+    enum Frame {
+        Dict(Map<String, Value>, Option<String>),
+        Array(Vec<Value>),
+    }
+
+    fn emit(stack: &mut [Frame], root: &mut Option<Value>, value: Value) {
+        match stack.last_mut() {
+            Some(Frame::Dict(map, key)) => {
+                if let Some(key) = key.take() {
+                    map.insert(key, value);
+                }
+            }
+            Some(Frame::Array(items)) => items.push(value),
+            None => *root = Some(value),
+        }
+    }
+
+    let mut reader = Reader::from_str(text);
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut root = None;
+    let mut text_buf = String::new();
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("Invalid plist at byte {}: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(e) => match e.name().as_ref() {
+                b"dict" => stack.push(Frame::Dict(Map::new(), None)),
+                b"array" => stack.push(Frame::Array(Vec::new())),
+                _ => text_buf.clear(),
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"true" => emit(&mut stack, &mut root, Value::Bool(true)),
+                b"false" => emit(&mut stack, &mut root, Value::Bool(false)),
+                b"dict" => emit(&mut stack, &mut root, Value::Object(Map::new())),
+                b"array" => emit(&mut stack, &mut root, Value::Array(Vec::new())),
+                b"string" => emit(&mut stack, &mut root, Value::String(String::new())),
+                _ => {}
+            },
+            Event::Text(t) => {
+                let unescaped = t.unescape().map_err(|e| format!("Invalid plist text: {}", e))?;
+                text_buf.push_str(&unescaped);
+            }
+            Event::CData(t) => text_buf.push_str(&String::from_utf8_lossy(&t)),
+            Event::End(e) => {
+                let text = std::mem::take(&mut text_buf);
+                match e.name().as_ref() {
+                    b"dict" | b"array" => {
+                        let value = match stack.pop() {
+                            Some(Frame::Dict(map, _)) => Value::Object(map),
+                            Some(Frame::Array(items)) => Value::Array(items),
+                            None => return Err("Unbalanced plist".to_string()),
+                        };
+                        emit(&mut stack, &mut root, value);
+                    }
+                    b"key" => {
+                        if let Some(Frame::Dict(_, key)) = stack.last_mut() {
+                            *key = Some(text.trim().to_string());
+                        }
+                    }
+                    b"integer" => {
+                        let n: i64 = text.trim().parse().map_err(|_| format!("Invalid plist integer: {}", text))?;
+                        emit(&mut stack, &mut root, Value::from(n));
+                    }
+                    b"real" => {
+                        let n: f64 = text.trim().parse().map_err(|_| format!("Invalid plist real: {}", text))?;
+                        emit(&mut stack, &mut root, Value::from(n));
+                    }
+                    b"string" | b"date" | b"data" => emit(&mut stack, &mut root, Value::String(text)),
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    root.ok_or_else(|| "Empty plist".to_string())
+}
+
+/// Collects token rules from VS Code's `tokenColors` or a .tmTheme's `settings`
+fn token_colors(rules: &[Value], warnings: &mut Vec<String>) -> Vec<TokenColor> {
+    let color = |settings: &Map<String, Value>, key: &str, warnings: &mut Vec<String>| {
+        let value = settings.get(key)?.as_str()?;
+        if is_color(value) {
+            Some(value.to_string())
+        } else {
+            warnings.push(format!("Ignoring invalid token {} \"{}\"", key, value));
+            None
+        }
+    };
+    rules
+        .iter()
+        .filter_map(|rule| {
+            let rule = rule.as_object()?;
+            let scope: Vec<String> = match rule.get("scope")? {
+                Value::String(s) => s.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+                Value::Array(items) => items.iter().filter_map(|s| s.as_str().map(str::to_string)).collect(),
+                _ => return None,
+            };
+            let settings = rule.get("settings")?.as_object()?;
+            Some(TokenColor {
+                name: rule.get("name").and_then(|n| n.as_str()).map(str::to_string),
+                scope,
+                foreground: color(settings, "foreground", warnings),
+                background: color(settings, "background", warnings),
+                font_style: settings.get("fontStyle").and_then(|s| s.as_str()).map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+fn pick(source: &Map<String, Value>, mapping: &[(&str, &[&str])], colors: &mut Map<String, Value>) {
+    for (ours, theirs) in mapping {
+        if colors.contains_key(*ours) {
+            continue;
+        }
+        if let Some(value) = theirs.iter().find_map(|key| source.get(*key).and_then(|v| v.as_str())) {
+            colors.insert(ours.to_string(), Value::String(value.to_string()));
+        }
+    }
+}
+
+/// What a theme file declares, before validation and defaults
+struct ParsedTheme {
+    name: Option<String>,
+    kind: Option<ThemeKind>,
+    colors: Map<String, Value>,
+    token_colors: Vec<TokenColor>,
+}
+
+/// Reads any of the supported formats
+fn parse_theme(path: &Path, text: &str, warnings: &mut Vec<String>) -> Result<ParsedTheme, String> {
+    let is_textmate = path
+        .extension()
+        .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("tmtheme"));
+    if is_textmate {
+        let plist = parse_plist(text)?;
+        let name = plist.get("name").and_then(|n| n.as_str()).map(str::to_string);
+        let rules = plist.get("settings").and_then(|s| s.as_array()).cloned().unwrap_or_default();
+        // The rule without a scope holds the editor-wide colors
+        let globals = rules
+            .iter()
+            .find(|r| r.get("scope").is_none())
+            .and_then(|r| r.get("settings"))
+            .and_then(|s| s.as_object())
+            .cloned()
+            .unwrap_or_default();
+        let mut colors = Map::new();
+        pick(&globals, TEXTMATE_COLORS, &mut colors);
+        return Ok(ParsedTheme {
+            name,
+            kind: None,
+            colors,
+            token_colors: token_colors(&rules, warnings),
+        });
+    }
+
+    let json: Value = serde_json::from_str(text).map_err(|e| format!("Invalid theme JSON: {}", e))?;
+    let Value::Object(json) = json else {
+        return Err("A theme must be a JSON object".to_string());
+    };
+    let name = json.get("name").and_then(|n| n.as_str()).map(str::to_string);
+    let kind = match json.get("type").and_then(|t| t.as_str()) {
+        Some("light" | "hcLight") => Some(ThemeKind::Light),
+        Some("dark" | "hc" | "hcDark") => Some(ThemeKind::Dark),
+        Some(other) => {
+            warnings.push(format!("Unknown theme type \"{}\"", other));
+            None
+        }
+        None => None,
+    };
+
+    let source = json.get("colors").and_then(|c| c.as_object()).cloned().unwrap_or_default();
+    let mut colors = Map::new();
+    for (key, value) in &source {
+        if COLOR_KEYS.contains(&key.as_str()) {
+            colors.insert(key.clone(), value.clone());
+        }
+    }
+    // VS Code color themes use workbench keys such as "editor.background"
+    pick(&source, VSCODE_COLORS, &mut colors);
+
+    let rules = match json.get("tokenColors") {
+        Some(Value::Array(rules)) => token_colors(rules, warnings),
+        Some(Value::String(include)) => {
+            warnings.push(format!("tokenColors includes another file ({}), which is not supported", include));
+            Vec::new()
+        }
+        _ => Vec::new(),
+    };
+    Ok(ParsedTheme {
+        name,
+        kind,
+        colors,
+        token_colors: rules,
+    })
+}
+
+fn bundled_base(kind: ThemeKind) -> Map<String, Value> {
+    let id = match kind {
+        ThemeKind::Light => "light",
+        ThemeKind::Dark => "dark",
+    };
+    BUNDLED
+        .iter()
+        .find(|(bundled, _)| *bundled == id)
+        .and_then(|(_, text)| serde_json::from_str::<Value>(text).ok())
+        .and_then(|json| json.get("colors").and_then(|c| c.as_object()).cloned())
+        .unwrap_or_default()
+}
+
+fn build_theme(id: &str, source: ThemeSource, path: Option<&Path>, text: &str) -> Result<Theme, String> {
+    let mut warnings = Vec::new();
+    let ParsedTheme {
+        name,
+        kind,
+        mut colors,
+        token_colors,
+    } = parse_theme(path.unwrap_or(Path::new("theme.json")), text, &mut warnings)?;
+
+    colors.retain(|key, value| match value.as_str() {
+        Some(color) if is_color(color) => true,
+        _ => {
+            warnings.push(format!("Ignoring invalid color for {}: {}", key, value));
+            false
+        }
+    });
+    let kind = kind.unwrap_or_else(|| {
+        let background = colors.get("editor").or(colors.get("background")).and_then(|v| v.as_str());
+        match background.and_then(luminance) {
+            Some(l) if l > 0.5 => ThemeKind::Light,
+            Some(_) => ThemeKind::Dark,
+            None => ThemeKind::Light,
+        }
+    });
+    for (key, value) in bundled_base(kind) {
+        colors.entry(key).or_insert(value);
+    }
+
+    Ok(Theme {
+        id: id.to_string(),
+        name: name.unwrap_or_else(|| id.to_string()),
+        kind,
+        source,
+        path: path.map(|p| p.to_string_lossy().to_string()),
+        colors,
+        token_colors,
+        warnings,
+    })
+}
+
+fn read_user_theme(path: &Path) -> Result<Theme, String> {
+    if fs::metadata(path).is_ok_and(|m| m.len() > MAX_THEME_SIZE) {
+        return Err("Theme file is too large".to_string());
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    build_theme(&theme_id(path), ThemeSource::User, Some(path), &text)
+}
+
+fn user_theme_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_file() && is_theme_file(p)).collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Emits `theme-file-changed` whenever a file in the themes folder changes
+fn watch(app: AppHandle, dir: PathBuf) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| format!("Failed to create watcher: {}", e))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+
+    thread::spawn(move || {
+        // Keeps the watcher alive for as long as the thread runs
+        let _watcher = watcher;
+        loop {
+            match rx.recv_timeout(WATCH_POLL_INTERVAL) {
+                Ok(Ok(event)) => {
+                    for path in event.paths.iter().filter(|p| is_theme_file(p)) {
+                        let _ = app.emit(
+                            "theme-file-changed",
+                            ThemeFileChanged {
+                                id: theme_id(path),
+                                removed: !path.exists(),
+                            },
+                        );
+                    }
+                }
+                Ok(Err(e)) => tracing::warn!("Watch error: {}", e),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Bundled themes followed by the user's `.json` (our format or VS Code
+/// color themes) and `.tmTheme` files in the app data themes folder. Starts
+/// watching that folder; edits arrive as `theme-file-changed` events.
+#[tauri::command]
+pub async fn list_themes(app_handle: AppHandle, state: State<'_, ThemeState>) -> Result<Vec<ThemeSummary>, String> {
+    let dir = themes_dir(&app_handle)?;
+    if !state.watching.swap(true, Ordering::SeqCst) {
+        if let Err(e) = watch(app_handle.clone(), dir.clone()) {
+            state.watching.store(false, Ordering::SeqCst);
+            tracing::warn!("{}", e);
+        }
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let user: Vec<ThemeSummary> = user_theme_files(&dir)
+            .iter()
+            .map(|path| {
+                let (name, kind, error) = match read_user_theme(path) {
+                    Ok(theme) => (theme.name, Some(theme.kind), None),
+                    Err(e) => (theme_id(path), None, Some(e)),
+                };
+                ThemeSummary {
+                    id: theme_id(path),
+                    name,
+                    kind,
+                    source: ThemeSource::User,
+                    path: Some(path.to_string_lossy().to_string()),
+                    error,
+                }
+            })
+            .collect();
+        let bundled: Vec<ThemeSummary> = BUNDLED
+            .iter()
+            .filter(|(id, _)| !user.iter().any(|t| t.id == *id))
+            .filter_map(|(id, text)| build_theme(id, ThemeSource::Bundled, None, text).ok())
+            .map(|theme| ThemeSummary {
+                id: theme.id,
+                name: theme.name,
+                kind: Some(theme.kind),
+                source: ThemeSource::Bundled,
+                path: None,
+                error: None,
+            })
+            .collect();
+        bundled.into_iter().chain(user).collect()
+    })
+    .await
+    .map_err(|e| format!("Theme task failed: {}", e))
+}
+
+/// Loads a theme by id, resolving its colors against the bundled theme of
+/// the same kind so every UI color is set
+#[tauri::command]
+pub async fn load_theme(app_handle: AppHandle, name: String) -> Result<Theme, String> {
+    let dir = themes_dir(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(path) = user_theme_files(&dir).into_iter().find(|p| theme_id(p) == name) {
+            return read_user_theme(&path);
+        }
+        let (id, text) = BUNDLED
+            .iter()
+            .find(|(id, _)| *id == name)
+            .ok_or_else(|| format!("Theme not found: {}", name))?;
+        build_theme(id, ThemeSource::Bundled, None, text)
+    })
+    .await
+    .map_err(|e| format!("Theme task failed: {}", e))?
+}
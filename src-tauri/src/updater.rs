@@ -0,0 +1,182 @@
+//! In-app updates through tauri-plugin-updater. Each channel has its own
+//! manifest on the GitHub releases page, picked by the `updateChannel`
+//! setting. `check_for_updates` keeps the update it found for
+//! `download_update`, which keeps the downloaded bundle for
+//! `install_update_and_restart`.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::settings;
+
+const STABLE_ENDPOINT: &str = "https://github.com/niuhuan/tmd-editor/releases/latest/download/latest.json";
+// Prereleases publish their manifest under the moving `beta` tag
+const BETA_ENDPOINT: &str = "https://github.com/niuhuan/tmd-editor/releases/download/beta/latest.json";
+// Public half of the release signing key, provided when building releases
+const UPDATER_PUBKEY: Option<&str> = option_env!("TMD_UPDATER_PUBKEY");
+
+#[derive(Default)]
+pub struct UpdaterState {
+    /// Found by the last check, not downloaded yet
+    available: Mutex<Option<Update>>,
+    /// Downloaded and verified, waiting to be installed
+    downloaded: Mutex<Option<(Update, Vec<u8>)>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: String,
+    /// Release date from the manifest
+    pub date: Option<String>,
+    /// Release notes
+    pub notes: Option<String>,
+}
+
+/// Sent as `update-download-progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub done: bool,
+}
+
+fn channel(app: &AppHandle) -> String {
+    settings::effective(app)
+        .get("updateChannel")
+        .and_then(|v| v.as_str())
+        .unwrap_or("stable")
+        .to_string()
+}
+
+fn endpoint(channel: &str) -> &'static str {
+    match channel {
+        "beta" => BETA_ENDPOINT,
+        _ => STABLE_ENDPOINT,
+    }
+}
+
+/// Looks for a newer version on the configured channel; `None` if this is
+/// the latest one
+#[tauri::command]
+pub async fn check_for_updates(
+    app_handle: AppHandle,
+    state: State<'_, UpdaterState>,
+) -> Result<Option<UpdateInfo>, String> {
+    let channel = channel(&app_handle);
+    let url = Url::parse(endpoint(&channel)).map_err(|e| format!("Invalid update endpoint: {}", e))?;
+    let mut builder = app_handle
+        .updater_builder()
+        .endpoints(vec![url])
+        .map_err(|e| format!("Invalid update endpoint: {}", e))?;
+    if let Some(pubkey) = UPDATER_PUBKEY {
+        builder = builder.pubkey(pubkey);
+    }
+    let updater = builder
+        .build()
+        .map_err(|e| format!("Failed to set up updater: {}", e))?;
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        channel: channel.clone(),
+        date: update.date.map(|date| date.to_string()),
+        notes: update.body.clone(),
+    });
+    match &info {
+        Some(info) => tracing::info!("Update {} available on {}", info.version, channel),
+        None => tracing::info!("No update available on {}", channel),
+    }
+    *state
+        .available
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))? = update;
+    Ok(info)
+}
+
+/// Downloads and verifies the update found by `check_for_updates`.
+/// Progress is emitted on `update-download-progress`.
+#[tauri::command]
+pub async fn download_update(app_handle: AppHandle, state: State<'_, UpdaterState>) -> Result<(), String> {
+    if UPDATER_PUBKEY.is_none() {
+        return Err("This build can't verify updates; download the release manually".to_string());
+    }
+    let update = state
+        .available
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .take()
+        .ok_or("No update to download; check for updates first")?;
+
+    let mut downloaded = 0u64;
+    let progress_handle = app_handle.clone();
+    let finish_handle = app_handle.clone();
+    let result = update
+        .download(
+            move |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = progress_handle.emit(
+                    "update-download-progress",
+                    UpdateProgress {
+                        downloaded,
+                        total,
+                        done: false,
+                    },
+                );
+            },
+            move || {
+                let _ = finish_handle.emit(
+                    "update-download-progress",
+                    UpdateProgress {
+                        downloaded: 0,
+                        total: None,
+                        done: true,
+                    },
+                );
+            },
+        )
+        .await;
+
+    match result {
+        Ok(bytes) => {
+            tracing::info!("Downloaded update {} ({} bytes)", update.version, bytes.len());
+            *state
+                .downloaded
+                .lock()
+                .map_err(|e| format!("Failed to lock state: {}", e))? = Some((update, bytes));
+            Ok(())
+        }
+        Err(e) => {
+            // Keep it so the download can be retried without another check
+            if let Ok(mut available) = state.available.lock() {
+                available.get_or_insert(update);
+            }
+            Err(format!("Failed to download update: {}", e))
+        }
+    }
+}
+
+/// Installs the downloaded update and restarts into it. On Windows the
+/// installer takes over and the app exits before this returns.
+#[tauri::command]
+pub async fn install_update_and_restart(app_handle: AppHandle, state: State<'_, UpdaterState>) -> Result<(), String> {
+    let (update, bytes) = state
+        .downloaded
+        .lock()
+        .map_err(|e| format!("Failed to lock state: {}", e))?
+        .take()
+        .ok_or("No downloaded update to install")?;
+    update
+        .install(bytes)
+        .map_err(|e| format!("Failed to install update: {}", e))?;
+    tracing::info!("Installed update {}, restarting", update.version);
+    app_handle.restart()
+}
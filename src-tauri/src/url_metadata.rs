@@ -0,0 +1,273 @@
+//! Page titles and descriptions for pasted URLs, so a bare link can become
+//! `[Title](url)`. Requests are capped in time and size, spaced out per host,
+//! and cached for a while.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::State;
+use url::Url;
+
+use crate::html_to_markdown::decode_entities;
+
+const USER_AGENT: &str = concat!("tmd-editor/", env!("CARGO_PKG_VERSION"));
+const TIMEOUT: Duration = Duration::from_secs(8);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(4);
+/// Titles live in the <head>; nothing past this is read
+const MAX_BYTES: usize = 512 * 1024;
+/// Minimum time between two requests to the same host
+const HOST_INTERVAL: Duration = Duration::from_secs(1);
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+const CACHE_LIMIT: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlMetadata {
+    /// Where the request ended up after redirects
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub site_name: Option<String>,
+    pub content_type: Option<String>,
+    /// `[Title](url)` for the requested URL, or `<url>` when there is no title
+    pub markdown: String,
+}
+
+#[derive(Default)]
+pub struct UrlMetadataState {
+    client: OnceLock<reqwest::Client>,
+    /// When the next request to each host may start
+    next_slot: Mutex<HashMap<String, Instant>>,
+    cache: Mutex<HashMap<String, (Instant, UrlMetadata)>>,
+}
+
+impl UrlMetadataState {
+    fn client(&self) -> Result<&reqwest::Client, String> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::limited(5))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        Ok(self.client.get_or_init(|| client))
+    }
+
+    /// How long to wait before requesting `host`, reserving the slot after it
+    fn reserve(&self, host: &str) -> Duration {
+        let now = Instant::now();
+        let mut slots = self.next_slot.lock().unwrap();
+        slots.retain(|_, at| *at > now);
+        let start = slots.get(host).copied().unwrap_or(now).max(now);
+        slots.insert(host.to_string(), start + HOST_INTERVAL);
+        start - now
+    }
+
+    fn cached(&self, url: &str) -> Option<UrlMetadata> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(url)
+            .filter(|(at, _)| at.elapsed() < CACHE_TTL)
+            .map(|(_, metadata)| metadata.clone())
+    }
+
+    fn store(&self, url: &str, metadata: &UrlMetadata) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+        if cache.len() >= CACHE_LIMIT {
+            if let Some(oldest) = cache.iter().min_by_key(|(_, (at, _))| *at).map(|(k, _)| k.clone()) {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(url.to_string(), (Instant::now(), metadata.clone()));
+    }
+}
+
+fn title_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap())
+}
+
+fn meta_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<meta\s[^>]*>").unwrap())
+}
+
+fn attribute_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"([a-zA-Z:_-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap())
+}
+
+/// Entity-decoded text with whitespace collapsed; None if empty
+fn clean(text: &str) -> Option<String> {
+    let text = decode_entities(text).split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// `<meta>` tags as (name or property, content) pairs, names lowercased
+fn meta_tags(head: &str) -> Vec<(String, String)> {
+    meta_regex()
+        .find_iter(head)
+        .filter_map(|tag| {
+            let mut key = None;
+            let mut content = None;
+            for cap in attribute_regex().captures_iter(tag.as_str()) {
+                let value = cap
+                    .get(2)
+                    .or_else(|| cap.get(3))
+                    .or_else(|| cap.get(4))
+                    .map_or("", |m| m.as_str());
+                match cap[1].to_ascii_lowercase().as_str() {
+                    "name" | "property" | "http-equiv" => key = Some(value.to_ascii_lowercase()),
+                    "content" => content = Some(value.to_string()),
+                    "charset" => {
+                        key = Some("charset".to_string());
+                        content = Some(value.to_string());
+                    }
+                    _ => {}
+                }
+            }
+            Some((key?, content?))
+        })
+        .collect()
+}
+
+/// Charset from a Content-Type value such as `text/html; charset=ISO-8859-1`
+fn charset_of(content_type: &str) -> Option<&str> {
+    content_type
+        .split(';')
+        .find_map(|part| part.trim().strip_prefix("charset="))
+        .map(|c| c.trim_matches('"'))
+}
+
+fn decode(bytes: &[u8], header_charset: Option<&str>) -> String {
+    // The header wins; otherwise look for <meta charset> in what is plain ASCII anyway
+    let label = header_charset.map(str::to_string).or_else(|| {
+        let ascii = String::from_utf8_lossy(bytes);
+        meta_tags(&ascii)
+            .into_iter()
+            .find_map(|(key, value)| match key.as_str() {
+                "charset" => Some(value),
+                "content-type" => charset_of(&value).map(str::to_string),
+                _ => None,
+            })
+    });
+    let encoding = label
+        .and_then(|l| encoding_rs::Encoding::for_label(l.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+fn markdown_link(url: &str, title: Option<&str>) -> String {
+    match title {
+        Some(title) => {
+            let escaped = title.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]");
+            let target = if url.contains([' ', '(', ')']) {
+                format!("<{}>", url)
+            } else {
+                url.to_string()
+            };
+            format!("[{}]({})", escaped, target)
+        }
+        None => format!("<{}>", url),
+    }
+}
+
+async fn fetch(client: &reqwest::Client, url: &Url) -> Result<UrlMetadata, String> {
+    let mut response = client
+        .get(url.clone())
+        .header(
+            reqwest::header::ACCEPT,
+            "text/html,application/xhtml+xml;q=0.9,*/*;q=0.5",
+        )
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                "Request timed out".to_string()
+            } else {
+                format!("Request failed: {}", e)
+            }
+        })?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+
+    let final_url = response.url().to_string();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let is_html = content_type
+        .as_deref()
+        .is_none_or(|t| t.contains("text/html") || t.contains("application/xhtml"));
+    let mut metadata = UrlMetadata {
+        url: final_url,
+        title: None,
+        description: None,
+        site_name: None,
+        content_type: content_type.clone(),
+        markdown: String::new(),
+    };
+    if !is_html {
+        return Ok(metadata);
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("Request failed: {}", e))? {
+        let seen = body.len().saturating_sub(6);
+        body.extend_from_slice(&chunk);
+        // Everything needed is in the head
+        if body.len() >= MAX_BYTES || body[seen..].windows(7).any(|w| w.eq_ignore_ascii_case(b"</head>")) {
+            break;
+        }
+    }
+    body.truncate(MAX_BYTES);
+
+    let html = decode(&body, content_type.as_deref().and_then(charset_of));
+    let head = match html.to_ascii_lowercase().find("</head>") {
+        Some(end) => &html[..end],
+        None => &html[..],
+    };
+    let meta = meta_tags(head);
+    let meta_value = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| meta.iter().find(|(k, _)| k == key).and_then(|(_, v)| clean(v)))
+    };
+    metadata.title = meta_value(&["og:title", "twitter:title"])
+        .or_else(|| title_regex().captures(head).and_then(|cap| clean(&cap[1])));
+    metadata.description = meta_value(&["og:description", "description", "twitter:description"]);
+    metadata.site_name = meta_value(&["og:site_name", "application-name"]);
+    Ok(metadata)
+}
+
+/// Title and description of a web page, for turning a pasted URL into a
+/// markdown link. Only http(s) URLs are fetched.
+#[tauri::command]
+pub async fn fetch_url_metadata(state: State<'_, UrlMetadataState>, url: String) -> Result<UrlMetadata, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme: {}", parsed.scheme()));
+    }
+    if let Some(cached) = state.cached(parsed.as_str()) {
+        return Ok(cached);
+    }
+
+    let host = parsed.host_str().unwrap_or_default().to_string();
+    let wait = state.reserve(&host);
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+
+    let mut metadata = fetch(state.client()?, &parsed).await?;
+    metadata.markdown = markdown_link(url.trim(), metadata.title.as_deref());
+    state.store(parsed.as_str(), &metadata);
+    Ok(metadata)
+}
@@ -0,0 +1,120 @@
+//! Runs WASI plugins in the wasmi interpreter. A module gets stdin, stdout
+//! and stderr, its id in `TMD_PLUGIN_ID` and nothing else: no preopened
+//! folders, no sockets and none of the editor's environment. Files come
+//! through the host API (`readFile`), which applies the plugin's permissions.
+
+use std::fs;
+use std::io::{self, PipeReader, PipeWriter};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc, TypedResumableCall};
+use wasmi_wasi::wasi_common::pipe::{ReadPipe, WritePipe};
+use wasmi_wasi::{WasiCtx, WasiCtxBuilder};
+
+// Roughly the instructions run between checks whether the plugin was stopped
+const FUEL_SLICE: u64 = 10_000_000;
+const MAX_MEMORY: usize = 256 * 1024 * 1024;
+
+struct Host {
+    wasi: WasiCtx,
+    limits: StoreLimits,
+}
+
+/// The host's ends of a plugin's stdio
+pub struct Stdio {
+    pub stdin: PipeWriter,
+    pub stdout: PipeReader,
+    pub stderr: PipeReader,
+}
+
+/// A plugin module running `_start` on its own thread
+pub struct WasmPlugin {
+    stopped: Arc<AtomicBool>,
+}
+
+impl WasmPlugin {
+    /// Loads the module at `main` and starts it
+    pub fn spawn(main: &Path, id: &str) -> Result<(Self, Stdio), String> {
+        let bytes = fs::read(main).map_err(|e| format!("Failed to read {}: {}", main.display(), e))?;
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &bytes).map_err(|e| format!("Invalid WebAssembly module: {}", e))?;
+
+        let pipe = |what: &str| io::pipe().map_err(|e| format!("Failed to open plugin {}: {}", what, e));
+        let (stdin_reader, stdin) = pipe("stdin")?;
+        let (stdout, stdout_writer) = pipe("stdout")?;
+        let (stderr, stderr_writer) = pipe("stderr")?;
+        let name = main.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let wasi = WasiCtxBuilder::new()
+            .arg(&name)
+            .and_then(|builder| builder.env("TMD_PLUGIN_ID", id))
+            .map_err(|e| format!("Failed to set up WASI: {}", e))?
+            .stdin(Box::new(ReadPipe::new(stdin_reader)))
+            .stdout(Box::new(WritePipe::new(stdout_writer)))
+            .stderr(Box::new(WritePipe::new(stderr_writer)))
+            .build();
+
+        let host = Host {
+            wasi,
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build(),
+        };
+        let mut store = Store::new(&engine, host);
+        store.limiter(|host| &mut host.limits);
+        store.set_fuel(FUEL_SLICE).map_err(|e| e.to_string())?;
+        let mut linker = Linker::new(&engine);
+        wasmi_wasi::add_to_linker(&mut linker, |host: &mut Host| &mut host.wasi)
+            .map_err(|e| format!("Failed to set up WASI: {}", e))?;
+        let start = linker
+            .instantiate_and_start(&mut store, &module)
+            .and_then(|instance| instance.get_typed_func::<(), ()>(&store, "_start"))
+            .map_err(|e| format!("Failed to instantiate plugin: {}", e))?;
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let flag = stopped.clone();
+        let id = id.to_string();
+        thread::spawn(move || match run(&mut store, &start, &flag) {
+            Ok(()) => tracing::debug!(plugin = %id, "Module finished"),
+            Err(e) => tracing::warn!(plugin = %id, "Module stopped: {}", e),
+        });
+
+        Ok((WasmPlugin { stopped }, Stdio { stdin, stdout, stderr }))
+    }
+
+    /// Ends the module at its next fuel check. One waiting on stdin ends
+    /// once the host closes it.
+    pub fn kill(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Runs `_start` in fuel slices until it returns, exits or is stopped
+fn run(store: &mut Store<Host>, start: &TypedFunc<(), ()>, stopped: &AtomicBool) -> Result<(), String> {
+    let mut call = start.call_resumable(&mut *store, ()).map_err(|e| e.to_string())?;
+    loop {
+        call = match call {
+            TypedResumableCall::Finished(()) => return Ok(()),
+            // `proc_exit` arrives as a host error
+            TypedResumableCall::HostTrap(trap) => {
+                let error = trap.host_error();
+                return match error.i32_exit_status() {
+                    Some(0) => Ok(()),
+                    Some(status) => Err(format!("exited with status {}", status)),
+                    None => Err(error.to_string()),
+                };
+            }
+            TypedResumableCall::OutOfFuel(paused) => {
+                if stopped.load(Ordering::SeqCst) {
+                    return Err("stopped by the host".to_string());
+                }
+                store
+                    .set_fuel(FUEL_SLICE.max(paused.required_fuel()))
+                    .map_err(|e| e.to_string())?;
+                paused.resume(&mut *store).map_err(|e| e.to_string())?
+            }
+        };
+    }
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::atomic_write::write_atomic;
+use crate::file_info::{sha256_hex, system_time_ms};
+
+const WORKSPACES_DIR: &str = "workspaces";
+const STATE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TabState {
+    pub path: String,
+    /// 0-based cursor position
+    pub cursor_line: u32,
+    pub cursor_column: u32,
+    pub scroll_top: f64,
+    /// Markdown view mode ("rich", "source", "split")
+    pub view_mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalLayout {
+    pub visible: bool,
+    pub height: Option<f64>,
+    /// Working directory of each open terminal, in tab order
+    pub terminals: Vec<String>,
+    pub active_terminal: Option<usize>,
+}
+
+/// Everything needed to restore a window for one workspace root.
+/// Unknown or missing fields fall back to defaults so older files still load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceState {
+    pub version: u32,
+    pub root: String,
+    pub open_folders: Vec<String>,
+    pub open_tabs: Vec<TabState>,
+    pub active_tab: Option<String>,
+    pub expanded_paths: Vec<String>,
+    pub terminal: TerminalLayout,
+    pub sidebar_width: Option<f64>,
+    pub saved_ms: u64,
+}
+
+impl Default for WorkspaceState {
+    fn default() -> Self {
+        Self {
+            version: STATE_VERSION,
+            root: String::new(),
+            open_folders: Vec::new(),
+            open_tabs: Vec::new(),
+            active_tab: None,
+            expanded_paths: Vec::new(),
+            terminal: TerminalLayout::default(),
+            sidebar_width: None,
+            saved_ms: 0,
+        }
+    }
+}
+
+/// One state file per workspace, named by a hash of the canonical root path
+fn state_file(app_handle: &AppHandle, root: &str) -> Result<PathBuf, String> {
+    let canonical = Path::new(root)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(root));
+    let name = sha256_hex(canonical.to_string_lossy().as_bytes());
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|d| d.join(WORKSPACES_DIR).join(format!("{}.json", &name[..32])))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+#[tauri::command]
+pub async fn save_workspace_state(
+    app_handle: AppHandle,
+    root: String,
+    mut state: WorkspaceState,
+) -> Result<(), String> {
+    let path = state_file(&app_handle, &root)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create workspace state directory: {}", e))?;
+    }
+
+    state.version = STATE_VERSION;
+    state.root = root;
+    state.saved_ms = system_time_ms(Ok(SystemTime::now())).unwrap_or(0);
+
+    let json = serde_json::to_vec_pretty(&state).map_err(|e| format!("Failed to serialize workspace state: {}", e))?;
+    write_atomic(&path, &json).map_err(|e| format!("Failed to save workspace state: {}", e))
+}
+
+/// Returns the saved state for `root`, or `None` if the folder was never opened.
+/// Tabs whose files no longer exist are dropped.
+#[tauri::command]
+pub async fn load_workspace_state(app_handle: AppHandle, root: String) -> Result<Option<WorkspaceState>, String> {
+    let path = state_file(&app_handle, &root)?;
+    let bytes = match fs::read(&path) {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read workspace state: {}", e)),
+    };
+
+    let mut state: WorkspaceState = match serde_json::from_slice(&bytes) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Ignoring corrupt state file {}: {}", path.display(), e);
+            return Ok(None);
+        }
+    };
+
+    state.open_tabs.retain(|tab| Path::new(&tab.path).exists());
+    if state
+        .active_tab
+        .as_ref()
+        .is_some_and(|active| !state.open_tabs.iter().any(|t| &t.path == active))
+    {
+        state.active_tab = state.open_tabs.first().map(|t| t.path.clone());
+    }
+    state.expanded_paths.retain(|p| Path::new(p).is_dir());
+
+    Ok(Some(state))
+}
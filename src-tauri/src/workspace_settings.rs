@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::atomic_write::write_atomic;
+use crate::fs_guard::FsGuardState;
+use crate::settings::{self, validate_value, IssueSeverity, SettingsIssue};
+
+const SETTINGS_DIR: &str = ".tmd";
+const SETTINGS_FILE: &str = "settings.json";
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct WorkspaceSettings {
+    pub root: String,
+    pub path: String,
+    /// Whether `.tmd/settings.json` exists
+    pub exists: bool,
+    /// The workspace file as written
+    pub settings: Map<String, Value>,
+    /// Global settings with the valid workspace overrides applied
+    pub effective: Map<String, Value>,
+    pub issues: Vec<SettingsIssue>,
+}
+
+/// Watchers for the settings file of each open workspace, keyed by root
+#[derive(Default)]
+pub struct WorkspaceSettingsState {
+    watchers: Mutex<HashMap<PathBuf, Arc<AtomicBool>>>,
+}
+
+fn settings_path(root: &Path) -> PathBuf {
+    root.join(SETTINGS_DIR).join(SETTINGS_FILE)
+}
+
+fn validate(settings: &Map<String, Value>) -> Vec<SettingsIssue> {
+    settings
+        .iter()
+        .filter_map(|(key, value)| validate_value(key, value))
+        .collect()
+}
+
+fn read_json_object(path: &Path) -> Result<Option<Map<String, Value>>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    match serde_json::from_str(&text) {
+        Ok(Value::Object(map)) => Ok(Some(map)),
+        Ok(_) => Err(format!("{} must contain a JSON object", path.display())),
+        Err(e) => Err(format!("Failed to parse {}: {}", path.display(), e)),
+    }
+}
+
+/// The valid settings of the workspace `path` belongs to, found by looking
+/// for the nearest `.tmd/settings.json` above it
+pub fn overrides_for_file(path: &Path) -> Map<String, Value> {
+    let Some(settings) = path
+        .ancestors()
+        .skip(1)
+        .find_map(|dir| read_json_object(&settings_path(dir)).ok().flatten())
+    else {
+        return Map::new();
+    };
+    settings
+        .into_iter()
+        .filter(|(key, value)| validate_value(key, value).is_none())
+        .collect()
+}
+
+/// Overlays `overrides` on `base`; nested objects are merged key by key
+fn merge(base: &mut Map<String, Value>, overrides: &Map<String, Value>) {
+    for (key, value) in overrides {
+        match (base.get_mut(key), value) {
+            (Some(Value::Object(existing)), Value::Object(nested)) => merge(existing, nested),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn load(app: &AppHandle, root: &Path) -> WorkspaceSettings {
+    let path = settings_path(root);
+    let (exists, settings, mut issues) = match read_json_object(&path) {
+        Ok(Some(settings)) => {
+            let issues = validate(&settings);
+            (true, settings, issues)
+        }
+        Ok(None) => (false, Map::new(), Vec::new()),
+        // A broken file shouldn't take the workspace down; report it and use the globals
+        Err(message) => (
+            true,
+            Map::new(),
+            vec![SettingsIssue {
+                key: String::new(),
+                severity: IssueSeverity::Error,
+                message,
+            }],
+        ),
+    };
+
+    let valid: Map<String, Value> = settings
+        .iter()
+        .filter(|(key, _)| !issues.iter().any(|i| &i.key == *key && i.severity == IssueSeverity::Error))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let mut effective = settings::effective(app);
+    merge(&mut effective, &valid);
+    issues.sort_by(|a, b| a.key.cmp(&b.key));
+
+    WorkspaceSettings {
+        root: root.to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+        exists,
+        settings,
+        effective,
+        issues,
+    }
+}
+
+/// Global settings with the workspace's overrides applied
+pub(crate) fn effective_for_root(app: &AppHandle, root: &Path) -> Map<String, Value> {
+    load(app, root).effective
+}
+
+/// Emits `workspace-settings-changed` whenever the settings file of `root` is
+/// created, edited or removed, until `stop` is set
+fn watch(app: AppHandle, root: PathBuf, stop: Arc<AtomicBool>) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| format!("Failed to create watcher: {}", e))?;
+    // The root is watched too so a `.tmd` folder created later is picked up
+    watcher
+        .watch(&root, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+    let dir = root.join(SETTINGS_DIR);
+    let file = settings_path(&root);
+    thread::spawn(move || {
+        let mut watching_dir = watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok();
+        let mut last = load(&app, &root);
+        loop {
+            match rx.recv_timeout(WATCH_POLL_INTERVAL) {
+                Ok(Ok(event)) => {
+                    if !event.paths.iter().any(|p| p == &dir || p == &file) {
+                        continue;
+                    }
+                    if !watching_dir {
+                        watching_dir = watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok();
+                    }
+                    // One save produces several events; only report real changes
+                    let current = load(&app, &root);
+                    if current != last {
+                        let _ = app.emit("workspace-settings-changed", &current);
+                        last = current;
+                    }
+                }
+                Ok(Err(e)) => tracing::warn!("Watch error for {}: {}", root.display(), e),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Settings for the workspace at `root`, merged over the global settings.
+/// Also starts watching the file so later edits arrive as
+/// `workspace-settings-changed` events.
+#[tauri::command]
+pub async fn load_workspace_settings(
+    app_handle: AppHandle,
+    state: State<'_, WorkspaceSettingsState>,
+    guard: State<'_, FsGuardState>,
+    root: String,
+) -> Result<WorkspaceSettings, String> {
+    let root = guard.check(&root)?;
+    if !root.is_dir() {
+        return Err(format!("Workspace root is not a directory: {}", root.display()));
+    }
+
+    let mut watchers = state.watchers.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if !watchers.contains_key(&root) {
+        let stop = Arc::new(AtomicBool::new(false));
+        watch(app_handle.clone(), root.clone(), stop.clone())?;
+        watchers.insert(root.clone(), stop);
+    }
+    drop(watchers);
+
+    Ok(load(&app_handle, &root))
+}
+
+/// Validates and writes `.tmd/settings.json`; nothing is written if any
+/// setting has the wrong type or value
+#[tauri::command]
+pub async fn save_workspace_settings(
+    app_handle: AppHandle,
+    guard: State<'_, FsGuardState>,
+    root: String,
+    settings: Value,
+) -> Result<WorkspaceSettings, String> {
+    let root = guard.check(&root)?;
+    let Value::Object(settings) = settings else {
+        return Err("Workspace settings must be a JSON object".to_string());
+    };
+    let errors: Vec<String> = validate(&settings)
+        .into_iter()
+        .filter(|i| i.severity == IssueSeverity::Error)
+        .map(|i| format!("{}: {}", i.key, i.message))
+        .collect();
+    if !errors.is_empty() {
+        return Err(format!("Invalid workspace settings: {}", errors.join("; ")));
+    }
+
+    let path = settings_path(&root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let mut json =
+        serde_json::to_vec_pretty(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    json.push(b'\n');
+    write_atomic(&path, &json).map_err(|e| format!("Failed to save workspace settings: {}", e))?;
+
+    Ok(load(&app_handle, &root))
+}
+
+#[tauri::command]
+pub async fn unwatch_workspace_settings(state: State<'_, WorkspaceSettingsState>, root: String) -> Result<(), String> {
+    let mut watchers = state.watchers.lock().map_err(|e| format!("Failed to lock state: {}", e))?;
+    if let Some(stop) = watchers.remove(Path::new(&root)) {
+        stop.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
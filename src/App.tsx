@@ -9,7 +9,7 @@ import { StatusBar } from "./components/StatusBar";
 import { TerminalPanel } from "./components/TerminalPanel";
 import { LspManager } from "./components/LspManager";
 import { AppSettings } from "./components/Settings";
-import { OpenFile } from "./types";
+import { OpenFile, FileVersion, FileWithMetadata, SaveError } from "./types";
 import { usePersistedSettings } from "./hooks/useSettings";
 import { LspProvider } from "./contexts/LspContext";
 import "./App.css";
@@ -155,14 +155,26 @@ function App() {
     // Handle different file types
     try {
       let content = '';
+      let encoding: string | undefined;
+      let hasBom: boolean | undefined;
+      let diskVersion: FileVersion | undefined;
       
       if (fileType === 'image') {
         // Read image as base64
         content = await invoke<string>('read_image_file', { path });
       } else if (fileType !== 'unsupported') {
-        // Read all text-based files
+        // Read all text-based files, detecting their encoding
         try {
-          content = await invoke<string>('read_file_content', { path });
+          // Refuse to open binaries as text
+          const stat = await invoke<{ is_binary: boolean }>('stat_path', { path });
+          if (stat.is_binary) {
+            throw new Error('Binary file');
+          }
+          const decoded = await invoke<FileWithMetadata>('read_file_with_metadata', { path });
+          content = decoded.content;
+          encoding = decoded.encoding;
+          hasBom = decoded.has_bom;
+          diskVersion = decoded.version;
         } catch (error) {
           // If text reading fails, treat as unsupported
           console.error('Failed to read as text:', error);
@@ -190,6 +202,9 @@ function App() {
         isUnsupported: fileType === 'unsupported',
         isDirty: false,
         markdownViewMode: fileType === 'markdown' ? settings.markdownDefaultMode : undefined,
+        encoding,
+        hasBom,
+        diskVersion,
       };
 
       // If opening an unsupported file or image, close any existing unsupported file
@@ -273,19 +288,25 @@ function App() {
     });
   };
 
-  const saveFile = async (path: string, content?: string) => {
+  const saveFile = async (path: string, content?: string, force = false) => {
     const file = openFiles.find(f => f.path === path);
     if (!file) return;
 
     const contentToSave = content ?? file.content;
 
     try {
-      await invoke('save_file', { path, content: contentToSave });
+      const diskVersion = await invoke<FileVersion>('save_file', {
+        path,
+        content: contentToSave,
+        encoding: file.encoding,
+        bom: file.hasBom,
+        expected: force ? undefined : file.diskVersion,
+      });
       
       // Update file state: mark as not dirty, update original content
       setOpenFiles(prev => prev.map(f => 
         f.path === path 
-          ? { ...f, content: contentToSave, originalContent: contentToSave, isDirty: false } 
+          ? { ...f, content: contentToSave, originalContent: contentToSave, isDirty: false, diskVersion } 
           : f
       ));
       
@@ -298,8 +319,15 @@ function App() {
       
       console.log(`File saved: ${path}`);
     } catch (error) {
+      const saveError = error as SaveError;
+      if (saveError?.kind === 'conflict') {
+        if (confirm(`${saveError.message}.\n\nOverwrite the file on disk with your version?`)) {
+          await saveFile(path, contentToSave, true);
+        }
+        return;
+      }
       console.error('Failed to save file:', error);
-      alert(`Failed to save file: ${error}`);
+      alert(`Failed to save file: ${saveError?.message ?? error}`);
     }
   };
 
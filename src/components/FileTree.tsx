@@ -5,7 +5,7 @@ import FolderOpenIcon from '@mui/icons-material/FolderOpen';
 import InsertDriveFileIcon from '@mui/icons-material/InsertDriveFile';
 import ChevronRightIcon from '@mui/icons-material/ChevronRight';
 import ExpandMoreIcon from '@mui/icons-material/ExpandMore';
-import { FileEntry } from '../types';
+import { FileEntry, errorMessage } from '../types';
 import { useTheme } from '../theme';
 import { ContextMenu, ContextMenuItem } from './ContextMenu';
 import './FileTree.css';
@@ -124,7 +124,7 @@ const FileTreeNode: React.FC<FileTreeNodeProps> = ({
   return (
     <div className="file-tree-node">
       <div
-        className={`file-tree-item ${mode} ${isSelected ? 'selected' : ''}`}
+        className={`file-tree-item ${mode} ${isSelected ? 'selected' : ''} ${entry.is_ignored ? 'ignored' : ''}`}
         style={{ paddingLeft: `${level * 16 + 8}px` }}
         onClick={handleClick}
         onContextMenu={(e) => {
@@ -132,6 +132,7 @@ const FileTreeNode: React.FC<FileTreeNodeProps> = ({
           // Don't stop propagation - let it bubble to parent
         }}
         data-path={entry.path}
+        title={entry.is_symlink ? `${entry.name} → ${entry.symlink_target ?? '?'}` : undefined}
       >
         <span className="expand-icon">
           {entry.is_directory ? (
@@ -364,12 +365,12 @@ export const FileTree: React.FC<FileTreeProps> = ({
       onRefreshNeeded?.();
     } catch (error) {
       console.error('Failed to rename:', error);
-      alert(`Failed to rename: ${error}`);
+      alert(`Failed to rename: ${errorMessage(error)}`);
     }
   };
 
   const handleDelete = async (paths: string[]) => {
-    const confirmed = confirm(`Are you sure you want to delete ${paths.length} item(s)?`);
+    const confirmed = confirm(`Move ${paths.length} item(s) to the trash?`);
     if (!confirmed) return;
 
     try {
@@ -380,7 +381,7 @@ export const FileTree: React.FC<FileTreeProps> = ({
       onRefreshNeeded?.();
     } catch (error) {
       console.error('Failed to delete:', error);
-      alert(`Failed to delete: ${error}`);
+      alert(`Failed to delete: ${errorMessage(error)}`);
     }
   };
 
@@ -394,7 +395,7 @@ export const FileTree: React.FC<FileTreeProps> = ({
       onRefreshNeeded?.();
     } catch (error) {
       console.error('Failed to create file:', error);
-      alert(`Failed to create file: ${error}`);
+      alert(`Failed to create file: ${errorMessage(error)}`);
     }
   };
 
@@ -408,7 +409,7 @@ export const FileTree: React.FC<FileTreeProps> = ({
       onRefreshNeeded?.();
     } catch (error) {
       console.error('Failed to create folder:', error);
-      alert(`Failed to create folder: ${error}`);
+      alert(`Failed to create folder: ${errorMessage(error)}`);
     }
   };
 
@@ -1,6 +1,6 @@
 import React, { useState, useEffect } from 'react';
-import { open } from '@tauri-apps/plugin-dialog';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import NoteAddIcon from '@mui/icons-material/NoteAdd';
 import CreateNewFolderIcon from '@mui/icons-material/CreateNewFolder';
 import RefreshIcon from '@mui/icons-material/Refresh';
@@ -8,9 +8,9 @@ import DeleteIcon from '@mui/icons-material/Delete';
 import FolderIcon from '@mui/icons-material/Folder';
 import DescriptionIcon from '@mui/icons-material/Description';
 import { FileTree } from './FileTree';
-import { FileEntry } from '../types';
+import { FileEntry, errorMessage } from '../types';
 import { useTheme } from '../theme';
-import { useRecentFiles } from '../hooks/useRecentFiles';
+import { useRecentFiles, RecentItem } from '../hooks/useRecentFiles';
 import './Sidebar.css';
 
 interface SidebarProps {
@@ -60,14 +60,22 @@ export const Sidebar: React.FC<SidebarProps> = ({ onFileClick, openFolderTrigger
     }
   }, [openFileTrigger, addRecentItem]);
 
+  // File > Open Recent in the native menu
+  useEffect(() => {
+    const unlisten = listen<RecentItem>('menu-open-recent', (event) => {
+      handleRecentItemClick(event.payload);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [addRecentItem, onFileClick, onWorkspaceChange]);
+
   const handleOpenFolder = async () => {
     try {
-      const selected = await open({
-        directory: true,
-        multiple: false,
-      });
+      // The backend picker also grants access to the chosen folder
+      const selected = await invoke<string | null>('pick_folder');
 
-      if (selected && typeof selected === 'string') {
+      if (selected) {
         setRootPath(selected);
         // Extract folder name from path
         const parts = selected.split(/[/\\]/);
@@ -94,12 +102,9 @@ export const Sidebar: React.FC<SidebarProps> = ({ onFileClick, openFolderTrigger
 
   const handleOpenFile = async () => {
     try {
-      const selected = await open({
-        directory: false,
-        multiple: false,
-      });
+      const [selected] = await invoke<string[]>('pick_files', { multiple: false });
 
-      if (selected && typeof selected === 'string') {
+      if (selected) {
         // Add to recent items
         addRecentItem(selected, false);
         // Open file
@@ -134,13 +139,8 @@ export const Sidebar: React.FC<SidebarProps> = ({ onFileClick, openFolderTrigger
 
   const handleRecentItemClick = async (item: { path: string; isDirectory: boolean }) => {
     try {
-      // Check if path exists
-      const exists = await invoke<boolean>('path_exists', { path: item.path });
-      
-      if (!exists) {
-        alert(`File or folder does not exist:\n${item.path}`);
-        return;
-      }
+      // Grants access again; fails if the item no longer exists
+      await invoke('open_recent', { path: item.path });
 
       if (item.isDirectory) {
         // Open folder
@@ -168,7 +168,7 @@ export const Sidebar: React.FC<SidebarProps> = ({ onFileClick, openFolderTrigger
       }
     } catch (error) {
       console.error('Failed to open recent item:', error);
-      alert(`Failed to open:\n${item.path}\n\nError: ${error}`);
+      alert(`Failed to open:\n${item.path}\n\nError: ${errorMessage(error)}`);
     }
   };
 
@@ -211,7 +211,7 @@ export const Sidebar: React.FC<SidebarProps> = ({ onFileClick, openFolderTrigger
       setRefreshKey(prev => prev + 1);
     } catch (error) {
       console.error('Failed to create item:', error);
-      alert(`Failed to create: ${error}`);
+      alert(`Failed to create: ${errorMessage(error)}`);
     }
   };
 
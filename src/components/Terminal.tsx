@@ -8,6 +8,12 @@ import { useTheme } from '../theme';
 import '@xterm/xterm/css/xterm.css';
 import './Terminal.css';
 
+interface TerminalExit {
+  exit_code: number | null;
+  signal: string | null;
+  success: boolean;
+}
+
 interface TerminalProps {
   workingDirectory: string | null;
   terminalId?: string;
@@ -126,7 +132,9 @@ export const Terminal: React.FC<TerminalProps> = ({ workingDirectory, terminalId
         // Start PTY session first
         await invoke('start_pty_session', { 
           terminalId,
-          workingDir: workingDirectory || undefined 
+          workingDir: workingDirectory || undefined,
+          rows: xterm.rows,
+          cols: xterm.cols,
         });
         
         // Session started successfully, now set up listeners
@@ -140,9 +148,16 @@ export const Terminal: React.FC<TerminalProps> = ({ workingDirectory, terminalId
 
         // Listen for session exit (specific to this terminal)
         // Only listen after session is confirmed started
-        const unlistenExit = await listen(`terminal-exit-${terminalId}`, () => {
+        const unlistenExit = await listen<TerminalExit>(`terminal-exit-${terminalId}`, (event) => {
           setIsSessionActive(false);
-          xterm.writeln('\r\n\x1b[90m[Process completed]\x1b[0m');
+          const { exit_code, signal, success } = event.payload;
+          let message = 'Process completed';
+          if (signal) {
+            message = `Process terminated by ${signal}`;
+          } else if (!success && exit_code !== null) {
+            message = `Process exited with code ${exit_code}`;
+          }
+          xterm.writeln(`\r\n\x1b[90m[${message}]\x1b[0m`);
         });
         unlistenExitRef.current = unlistenExit;
 
@@ -155,6 +170,13 @@ export const Terminal: React.FC<TerminalProps> = ({ workingDirectory, terminalId
           }
           // If session is inactive, ignore input (no restart)
         });
+
+        // Keep the PTY size in sync with xterm (fit() triggers onResize)
+        xterm.onResize(({ rows, cols }) => {
+          invoke('resize_pty', { terminalId, rows, cols }).catch((error) => {
+            console.error('Failed to resize PTY:', error);
+          });
+        });
       } catch (error) {
         console.error('Failed to start PTY session:', error);
         xterm.writeln('\x1b[31mError: Failed to start terminal session\x1b[0m');
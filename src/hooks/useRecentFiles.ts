@@ -1,17 +1,6 @@
 import { useState, useEffect, useCallback } from 'react';
-import { Store } from '@tauri-apps/plugin-store';
-
-const STORE_PATH = 'recent-files.json';
-const MAX_RECENT_ITEMS = 10;
-
-let store: Store | null = null;
-
-async function getStore() {
-  if (!store) {
-    store = await Store.load(STORE_PATH);
-  }
-  return store;
-}
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
 export interface RecentItem {
   path: string;
@@ -20,65 +9,35 @@ export interface RecentItem {
   timestamp: number;
 }
 
+// Recents live in the backend so the native File > Open Recent menu stays in sync
 export function useRecentFiles() {
   const [recentItems, setRecentItems] = useState<RecentItem[]>([]);
   const [isLoaded, setIsLoaded] = useState(false);
 
-  // Load recent items from store
+  // Load recent items and follow changes made elsewhere (e.g. the native menu)
   useEffect(() => {
-    async function loadRecentItems() {
-      try {
-        const storeInstance = await getStore();
-        const items = await storeInstance.get<RecentItem[]>('items');
-        if (items && Array.isArray(items)) {
-          // Sort by timestamp (most recent first) and limit to MAX_RECENT_ITEMS
-          const sorted = items
-            .sort((a, b) => b.timestamp - a.timestamp)
-            .slice(0, MAX_RECENT_ITEMS);
-          setRecentItems(sorted);
-        }
-        setIsLoaded(true);
-      } catch (error) {
-        console.error('Failed to load recent files:', error);
-        setIsLoaded(true);
-      }
-    }
-
-    loadRecentItems();
+    invoke<RecentItem[]>('get_recents')
+      .then(setRecentItems)
+      .catch((error) => console.error('Failed to load recent files:', error))
+      .finally(() => setIsLoaded(true));
+
+    const unlisten = listen<RecentItem[]>('recents-changed', (event) => {
+      setRecentItems(event.payload);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
   }, []);
 
   // Add a new item to recent files
   const addRecentItem = useCallback(async (path: string, isDirectory: boolean) => {
     try {
-      const parts = path.split(/[/\\]/);
-      const name = parts[parts.length - 1] || path;
-      
-      const newItem: RecentItem = {
+      const items = await invoke<RecentItem[]>('add_recent', {
         path,
-        name,
-        isDirectory,
-        timestamp: Date.now(),
-      };
-
-      setRecentItems(prev => {
-        // Remove if already exists
-        const filtered = prev.filter(item => item.path !== path);
-        // Add new item at the beginning
-        const updated = [newItem, ...filtered].slice(0, MAX_RECENT_ITEMS);
-        
-        // Save to store asynchronously
-        (async () => {
-          try {
-            const storeInstance = await getStore();
-            await storeInstance.set('items', updated);
-            await storeInstance.save();
-          } catch (error) {
-            console.error('Failed to save recent items:', error);
-          }
-        })();
-        
-        return updated;
+        kind: isDirectory ? 'folder' : 'file',
       });
+      setRecentItems(items);
     } catch (error) {
       console.error('Failed to add recent item:', error);
     }
@@ -87,10 +46,8 @@ export function useRecentFiles() {
   // Clear all recent items
   const clearRecentItems = useCallback(async () => {
     try {
+      await invoke('clear_recents');
       setRecentItems([]);
-      const storeInstance = await getStore();
-      await storeInstance.set('items', []);
-      await storeInstance.save();
     } catch (error) {
       console.error('Failed to clear recent items:', error);
     }
@@ -103,4 +60,3 @@ export function useRecentFiles() {
     clearRecentItems,
   };
 }
-
@@ -1,5 +1,6 @@
 import { useState, useEffect } from 'react';
-import { Store } from '@tauri-apps/plugin-store';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { ThemeMode } from '../theme';
 import { AppSettings, AutoSaveMode, MarkdownViewMode } from '../components/Settings';
 
@@ -13,6 +14,7 @@ interface StoredSettings {
   enableGoLsp: boolean;
 }
 
+// Shown until the backend answers; the backend's defaults are authoritative
 const DEFAULT_SETTINGS: StoredSettings = {
   theme: 'light',
   showHiddenFiles: true,
@@ -23,15 +25,12 @@ const DEFAULT_SETTINGS: StoredSettings = {
   enableGoLsp: false,     // Default off - user must enable
 };
 
-let store: Store | null = null;
-
-async function getStore() {
-  if (!store) {
-    store = await Store.load('settings.json');
-  }
-  return store;
+interface SettingsSnapshot {
+  settings: StoredSettings;
+  issues: { key: string; severity: 'error' | 'warning'; message: string }[];
 }
 
+// Settings live in the backend, which validates, migrates and fills defaults
 export function usePersistedSettings() {
   const [theme, setTheme] = useState<ThemeMode>(DEFAULT_SETTINGS.theme);
   const [appSettings, setAppSettings] = useState<AppSettings>({
@@ -44,50 +43,41 @@ export function usePersistedSettings() {
   });
   const [isLoaded, setIsLoaded] = useState(false);
 
-  // Load settings on mount
-  useEffect(() => {
-    loadSettings();
-  }, []);
+  const applySettings = (settings: StoredSettings) => {
+    setTheme(settings.theme);
+    setAppSettings({
+      showHiddenFiles: settings.showHiddenFiles,
+      autoSave: settings.autoSave,
+      autoSaveDelay: settings.autoSaveDelay,
+      markdownDefaultMode: settings.markdownDefaultMode,
+      enableRustLsp: settings.enableRustLsp,
+      enableGoLsp: settings.enableGoLsp,
+    });
+  };
 
-  const loadSettings = async () => {
-    try {
-      const storeInstance = await getStore();
-      
-      const savedTheme = await storeInstance.get<ThemeMode>('theme');
-      const savedShowHiddenFiles = await storeInstance.get<boolean>('showHiddenFiles');
-      const savedAutoSave = await storeInstance.get<AutoSaveMode>('autoSave');
-      const savedAutoSaveDelay = await storeInstance.get<number>('autoSaveDelay');
-      const savedMarkdownDefaultMode = await storeInstance.get<MarkdownViewMode>('markdownDefaultMode');
-      const savedEnableRustLsp = await storeInstance.get<boolean>('enableRustLsp');
-      const savedEnableGoLsp = await storeInstance.get<boolean>('enableGoLsp');
+  // Load settings on mount and follow changes made elsewhere (e.g. another window)
+  useEffect(() => {
+    invoke<SettingsSnapshot>('get_settings')
+      .then(({ settings, issues }) => {
+        issues.forEach((issue) => console.warn(`Ignoring setting ${issue.key}: ${issue.message}`));
+        applySettings(settings);
+      })
+      .catch((error) => console.error('Failed to load settings:', error))
+      .finally(() => setIsLoaded(true));
 
-      if (savedTheme) {
-        setTheme(savedTheme);
-      }
-      
-      setAppSettings(prev => ({
-        ...prev,
-        showHiddenFiles: savedShowHiddenFiles ?? prev.showHiddenFiles,
-        autoSave: savedAutoSave ?? prev.autoSave,
-        autoSaveDelay: savedAutoSaveDelay ?? prev.autoSaveDelay,
-        markdownDefaultMode: savedMarkdownDefaultMode ?? prev.markdownDefaultMode,
-        enableRustLsp: savedEnableRustLsp ?? prev.enableRustLsp,
-        enableGoLsp: savedEnableGoLsp ?? prev.enableGoLsp,
-      }));
+    const unlisten = listen<{ settings: StoredSettings }>('settings-changed', (event) => {
+      applySettings(event.payload.settings);
+    });
 
-      setIsLoaded(true);
-    } catch (error) {
-      console.error('Failed to load settings:', error);
-      setIsLoaded(true);
-    }
-  };
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
 
   const saveTheme = async (newTheme: ThemeMode) => {
     setTheme(newTheme);
     try {
-      const storeInstance = await getStore();
-      await storeInstance.set('theme', newTheme);
-      await storeInstance.save();
+      await invoke('set_setting', { key: 'theme', value: newTheme });
     } catch (error) {
       console.error('Failed to save theme:', error);
     }
@@ -96,14 +86,16 @@ export function usePersistedSettings() {
   const saveAppSettings = async (newSettings: AppSettings) => {
     setAppSettings(newSettings);
     try {
-      const storeInstance = await getStore();
-      await storeInstance.set('showHiddenFiles', newSettings.showHiddenFiles);
-      await storeInstance.set('autoSave', newSettings.autoSave);
-      await storeInstance.set('autoSaveDelay', newSettings.autoSaveDelay);
-      await storeInstance.set('markdownDefaultMode', newSettings.markdownDefaultMode);
-      await storeInstance.set('enableRustLsp', newSettings.enableRustLsp);
-      await storeInstance.set('enableGoLsp', newSettings.enableGoLsp);
-      await storeInstance.save();
+      await invoke('set_settings', {
+        values: {
+          showHiddenFiles: newSettings.showHiddenFiles,
+          autoSave: newSettings.autoSave,
+          autoSaveDelay: newSettings.autoSaveDelay,
+          markdownDefaultMode: newSettings.markdownDefaultMode,
+          enableRustLsp: newSettings.enableRustLsp,
+          enableGoLsp: newSettings.enableGoLsp,
+        },
+      });
     } catch (error) {
       console.error('Failed to save app settings:', error);
     }
@@ -117,4 +109,3 @@ export function usePersistedSettings() {
     setAppSettings: saveAppSettings,
   };
 }
-
@@ -1,7 +1,9 @@
 import { invoke } from '@tauri-apps/api/core';
 import { LSPClient, type Transport, languageServerExtensions } from '@codemirror/lsp-client';
 
-export type SupportedLanguage = 'rust' | 'go';
+export type SupportedLanguage = 'rust' | 'go' | 'typescript' | 'python' | 'cpp' | 'java';
+
+const SUPPORTED_LANGUAGES: SupportedLanguage[] = ['rust', 'go', 'typescript', 'python', 'cpp', 'java'];
 
 export interface StartLspResult {
   lsp_id: string;
@@ -31,7 +33,7 @@ export class LspManager {
       console.log('[LspManager] Detecting project for:', filePath);
       const info = await invoke<{ project_type: string; root_path: string }>('detect_project_type', { path: filePath });
       
-      if (info.project_type === 'rust' || info.project_type === 'go') {
+      if (SUPPORTED_LANGUAGES.includes(info.project_type as SupportedLanguage)) {
         console.log('[LspManager] Project detected:', info);
         return info as ProjectInfo;
       }
@@ -219,8 +221,13 @@ export class LspManager {
  * Determine language from file path
  */
 export function languageIdForPath(path: string): SupportedLanguage | null {
-  if (path.endsWith('.rs')) return 'rust';
-  if (path.endsWith('.go')) return 'go';
+  const ext = path.toLowerCase().split('.').pop() || '';
+  if (ext === 'rs') return 'rust';
+  if (ext === 'go') return 'go';
+  if (['ts', 'tsx', 'js', 'jsx', 'mjs', 'cjs'].includes(ext)) return 'typescript';
+  if (['py', 'pyi'].includes(ext)) return 'python';
+  if (['c', 'h', 'cc', 'cpp', 'cxx', 'hh', 'hpp', 'hxx'].includes(ext)) return 'cpp';
+  if (ext === 'java') return 'java';
   return null;
 }
 
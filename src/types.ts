@@ -3,6 +3,10 @@ export interface FileEntry {
   path: string;
   is_directory: boolean;
   is_file: boolean;
+  is_symlink?: boolean;
+  symlink_target?: string | null;  // Target as stored in the link
+  is_circular?: boolean;  // Symlink to one of its own ancestors
+  is_ignored?: boolean;  // Excluded by .gitignore rules
 }
 
 export type FileType = 
@@ -23,4 +27,52 @@ export interface OpenFile {
   isUnsupported?: boolean;
   isDirty?: boolean;  // Has unsaved changes
   markdownViewMode?: 'rich' | 'source' | 'split';  // Markdown view mode: rich (WYSIWYG), source (code only), split (side-by-side)
+  encoding?: string;  // Detected on-disk encoding (e.g. "UTF-8", "GBK"), used when saving
+  hasBom?: boolean;  // Whether the file started with a byte order mark
+  diskVersion?: FileVersion;  // On-disk version last read or written, used to detect external changes
 }
+
+export interface DecodedText {
+  content: string;
+  encoding: string;
+  has_bom: boolean;
+  had_errors: boolean;
+  line_ending: LineEnding | null;
+  mixed_line_endings: boolean;
+}
+
+export type LineEnding = 'lf' | 'crlf';
+
+export interface FileVersion {
+  modified_ms: number | null;
+  hash: string | null;
+}
+
+export interface FileWithMetadata extends DecodedText {
+  size: number;
+  version: FileVersion;
+}
+
+export type AppErrorCode =
+  | 'not_found' | 'permission_denied' | 'already_exists'
+  | 'invalid_input' | 'unsupported' | 'io' | 'failed';
+
+// Rejection value of commands returning the backend's AppError
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+  os_error?: number | null;
+}
+
+export type SaveError =
+  | { kind: 'conflict'; message: string; path: string; current: FileVersion | null }
+  | { kind: 'failed'; message: string }
+  | ({ kind: 'error' } & AppError);
+
+// Commands reject with an AppError object or, from older ones, a plain string
+export const errorMessage = (error: unknown): string => {
+  if (typeof error === 'object' && error !== null && 'message' in error) {
+    return String((error as { message: unknown }).message);
+  }
+  return String(error);
+};